@@ -0,0 +1,73 @@
+//! Pays a lightning invoice and records it in the payment log the way Keystache does.
+//! Payment simulation is turned on, so no federation or funds are needed. Everything
+//! is kept in a temporary directory.
+//!
+//! Run with `cargo run -p keystache-core --example pay_invoice`.
+
+use std::str::FromStr;
+
+use fedimint_core::{config::FederationId, Amount};
+use keystache_core::{
+    clock::Clock,
+    config::SettingsHandle,
+    db::Database,
+    fedimint::{PaymentDirection, PaymentSimulation, Wallet},
+    util::format_amount,
+};
+use lightning_invoice::Bolt11Invoice;
+use nostr_sdk::bitcoin::{bip32::Xpriv, Network};
+
+// An invoice for 250,000 sats from the BOLT 11 test vectors. It has long since
+// expired, which doesn't matter since the payment is only simulated.
+const EXAMPLE_INVOICE: &str = "lnbc2500u1pvjluezsp5zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zygspp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdq5xysxxatsyp3k7enxv4jsxqzpu9qrsgquk0rl77nj30yxdy8j9vdx85fkpmdla2087ne0xh8nhedh8w27kyke0lp53ut353s06fv3qfegext0eh0ymjpf39tuven09sam30g4vgpfna3rh";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let data_dir = tempfile::tempdir()?;
+    let db = Database::open_or_create(data_dir.path(), "example.db", "example password")?;
+
+    let settings = SettingsHandle::new(db.get_settings()?);
+
+    // A regtest wallet, so that it can never hold real funds.
+    let wallet = Wallet::new_with_data_dir(
+        Xpriv::new_master(Network::Regtest, &[0; 32])?,
+        Network::Regtest,
+        data_dir.path().join("fedimint_clients"),
+        settings.subscribe(),
+        Clock::default(),
+    );
+
+    // The developer setting is saved like any other, and handed to the wallet on unlock.
+    db.save_payment_simulation(PaymentSimulation::Succeed)?;
+    wallet.set_payment_simulation(db.get_payment_simulation()?);
+
+    let invoice = Bolt11Invoice::from_str(EXAMPLE_INVOICE)?;
+    let federation_id = FederationId::dummy();
+
+    let outcome = wallet.pay_invoice(invoice.clone(), federation_id).await?;
+    assert!(outcome.is_simulated);
+
+    db.save_payment(
+        &federation_id,
+        PaymentDirection::Outgoing,
+        Amount::from_msats(invoice.amount_milli_satoshis().unwrap_or_default()),
+        outcome.fee,
+        Some(&invoice),
+        outcome.preimage_or.as_deref(),
+        None,
+        outcome.gateway_id_or.as_ref(),
+        outcome.is_simulated,
+        None,
+    )?;
+
+    for payment in db.list_payments(10, 0)? {
+        println!(
+            "Paid {} with {} in fees (simulated: {})",
+            format_amount(payment.amount),
+            format_amount(payment.fee),
+            payment.is_simulated
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+//! Answers a NIP-46 `sign_event` request the way Keystache does: the request is described
+//! for the approval prompt, signed with a key from the database, and recorded against the
+//! app that sent it. Everything is kept in a temporary directory.
+//!
+//! Run with `cargo run -p keystache-core --example sign_event`.
+
+use std::sync::Arc;
+
+use futures::StreamExt;
+use keystache_core::{db::Database, policy, signing_worker::SigningWorker};
+use nostr_sdk::{
+    nips::nip46::Request,
+    secp256k1::{Keypair, SECP256K1},
+    EventBuilder, Keys, ToBech32,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let data_dir = tempfile::tempdir()?;
+    let db = Arc::new(Database::open_or_create(
+        data_dir.path(),
+        "example.db",
+        "example password",
+    )?);
+
+    // The user's identity, as if it had been created on the keypairs page.
+    let user_keys = Keys::generate();
+    db.save_keypair(&Keypair::from_secret_key(SECP256K1, user_keys.secret_key()))?;
+    let npub = user_keys.public_key().to_bech32()?;
+
+    // An app asks for a note to be signed.
+    let app_public_key = Keys::generate().public_key();
+    let requests = vec![Request::SignEvent(
+        EventBuilder::text_note("Hello from the sign_event example", [])
+            .to_unsigned_event(user_keys.public_key()),
+    )];
+
+    // Apps that haven't been approved before always prompt.
+    assert!(db.get_nip46_app(&app_public_key)?.is_none());
+    assert!(!policy::is_public_key_read(&requests));
+    println!("Prompt: {}", policy::describe_requests(&requests));
+
+    // Once approved, the events are signed with the key from the database.
+    let keypair = db.get_keypair(&npub)?;
+    let signing_keys = Keys::new(keypair.secret_key().into());
+
+    for request in &requests {
+        if let Request::SignEvent(unsigned_event) = request {
+            let event = unsigned_event.clone().sign(&signing_keys)?;
+            event.verify()?;
+
+            println!("Signed event {}", event.id);
+        }
+    }
+
+    // The bookkeeping happens in the background, and registers the app
+    // so that its next requests can be approved without prompting.
+    let signing_worker = SigningWorker::new(db.clone());
+    let mut progress_stream = std::pin::pin!(signing_worker.progress_stream());
+
+    signing_worker.submit(app_public_key, requests, true);
+
+    while let Some(progress) = progress_stream.next().await {
+        if !progress.is_in_progress() {
+            break;
+        }
+    }
+
+    assert!(db.get_nip46_app(&app_public_key)?.is_some());
+
+    for (kind, sign_count) in db.get_nip46_app_sign_counts_by_kind(&app_public_key)? {
+        println!(
+            "The app has signed {sign_count} {}",
+            policy::describe_event_kind(kind)
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,7 @@
+CREATE TABLE federation_balance_thresholds (
+    id INTEGER PRIMARY KEY NOT NULL,
+    federation_id TEXT NOT NULL UNIQUE,
+    low_balance_msats BIGINT,
+    high_balance_msats BIGINT,
+    create_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
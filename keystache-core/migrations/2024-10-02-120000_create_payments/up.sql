@@ -0,0 +1,9 @@
+CREATE TABLE payments (
+    id INTEGER PRIMARY KEY NOT NULL,
+    federation_id TEXT NOT NULL,
+    direction TEXT NOT NULL,
+    amount_msats BIGINT NOT NULL,
+    fee_msats BIGINT NOT NULL,
+    bolt11_invoice TEXT,
+    create_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
@@ -0,0 +1 @@
+DROP TABLE nwc_connections
@@ -0,0 +1,7 @@
+CREATE TABLE nwc_connections (
+    id INTEGER PRIMARY KEY NOT NULL,
+    service_nsec TEXT NOT NULL UNIQUE,
+    client_nsec TEXT NOT NULL UNIQUE,
+    relay_url TEXT NOT NULL,
+    create_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
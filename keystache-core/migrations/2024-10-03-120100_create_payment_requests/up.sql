@@ -0,0 +1,8 @@
+CREATE TABLE payment_requests (
+    id INTEGER PRIMARY KEY NOT NULL,
+    request_event_id TEXT NOT NULL UNIQUE,
+    requester_npub TEXT NOT NULL,
+    bolt11_invoice TEXT NOT NULL,
+    status TEXT NOT NULL,
+    create_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
@@ -0,0 +1,6 @@
+CREATE TABLE app_settings (
+    id INTEGER PRIMARY KEY NOT NULL,
+    setting_key TEXT NOT NULL UNIQUE,
+    setting_value TEXT NOT NULL,
+    create_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
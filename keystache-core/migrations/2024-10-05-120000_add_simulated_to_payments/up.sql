@@ -0,0 +1 @@
+ALTER TABLE payments ADD COLUMN simulated BOOLEAN NOT NULL DEFAULT FALSE
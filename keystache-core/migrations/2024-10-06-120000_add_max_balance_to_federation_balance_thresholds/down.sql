@@ -0,0 +1 @@
+ALTER TABLE federation_balance_thresholds DROP COLUMN max_balance_msats
@@ -0,0 +1 @@
+ALTER TABLE federation_balance_thresholds ADD COLUMN max_balance_msats BIGINT
@@ -0,0 +1 @@
+ALTER TABLE payments ADD COLUMN requester_npub TEXT
@@ -0,0 +1,10 @@
+CREATE TABLE delegations (
+    id INTEGER PRIMARY KEY NOT NULL,
+    delegator_npub TEXT NOT NULL,
+    delegatee_npub TEXT NOT NULL,
+    conditions TEXT NOT NULL,
+    signature TEXT NOT NULL,
+    valid_until DATETIME NOT NULL,
+    revoke_time DATETIME,
+    create_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
@@ -0,0 +1,11 @@
+CREATE TABLE zap_receipts (
+    id INTEGER PRIMARY KEY NOT NULL,
+    event_id TEXT NOT NULL UNIQUE,
+    recipient_npub TEXT NOT NULL,
+    sender_npub TEXT,
+    zapped_event_id TEXT,
+    amount_msats BIGINT NOT NULL,
+    bolt11_invoice TEXT NOT NULL,
+    comment TEXT NOT NULL,
+    create_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
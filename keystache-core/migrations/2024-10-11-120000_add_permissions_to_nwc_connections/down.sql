@@ -0,0 +1,5 @@
+ALTER TABLE nwc_connections DROP COLUMN revoke_time;
+ALTER TABLE nwc_connections DROP COLUMN budget_period;
+ALTER TABLE nwc_connections DROP COLUMN budget_msats;
+ALTER TABLE nwc_connections DROP COLUMN allowed_methods;
+ALTER TABLE nwc_connections DROP COLUMN name
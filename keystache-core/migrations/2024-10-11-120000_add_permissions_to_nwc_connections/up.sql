@@ -0,0 +1,5 @@
+ALTER TABLE nwc_connections ADD COLUMN name TEXT NOT NULL DEFAULT 'Unnamed app';
+ALTER TABLE nwc_connections ADD COLUMN allowed_methods TEXT NOT NULL DEFAULT 'pay_invoice,make_invoice,get_balance';
+ALTER TABLE nwc_connections ADD COLUMN budget_msats BIGINT;
+ALTER TABLE nwc_connections ADD COLUMN budget_period TEXT;
+ALTER TABLE nwc_connections ADD COLUMN revoke_time DATETIME
@@ -0,0 +1,5 @@
+CREATE TABLE pinned_gateways (
+    federation_id TEXT PRIMARY KEY NOT NULL,
+    gateway_id TEXT NOT NULL,
+    create_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
@@ -0,0 +1,5 @@
+CREATE TABLE nip46_apps (
+    npub TEXT PRIMARY KEY NOT NULL,
+    auto_approve_public_key_reads BOOLEAN DEFAULT TRUE NOT NULL,
+    create_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
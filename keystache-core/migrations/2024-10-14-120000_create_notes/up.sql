@@ -0,0 +1,5 @@
+CREATE TABLE notes (
+    subject TEXT PRIMARY KEY NOT NULL,
+    body TEXT NOT NULL,
+    update_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
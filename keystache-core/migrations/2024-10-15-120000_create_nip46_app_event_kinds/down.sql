@@ -0,0 +1 @@
+DROP TABLE nip46_app_event_kinds
@@ -0,0 +1,7 @@
+CREATE TABLE nip46_app_event_kinds (
+    npub TEXT NOT NULL,
+    kind INTEGER NOT NULL,
+    sign_count BIGINT NOT NULL,
+    last_sign_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (npub, kind)
+)
@@ -0,0 +1 @@
+DROP TABLE nip46_rejections
@@ -0,0 +1,7 @@
+CREATE TABLE nip46_rejections (
+    id INTEGER PRIMARY KEY NOT NULL,
+    npub TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    requests_description TEXT NOT NULL,
+    create_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
@@ -0,0 +1,5 @@
+CREATE TABLE zap_allowlist (
+    npub TEXT PRIMARY KEY NOT NULL,
+    petname TEXT,
+    create_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
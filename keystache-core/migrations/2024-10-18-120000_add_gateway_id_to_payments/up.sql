@@ -0,0 +1 @@
+ALTER TABLE payments ADD COLUMN gateway_id TEXT
@@ -0,0 +1 @@
+DROP TABLE nostr_outbox
@@ -0,0 +1,8 @@
+CREATE TABLE nostr_outbox (
+    id INTEGER PRIMARY KEY NOT NULL,
+    event_id TEXT NOT NULL UNIQUE,
+    event_json TEXT NOT NULL,
+    attempt_count INTEGER NOT NULL,
+    next_attempt_time DATETIME NOT NULL,
+    create_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
@@ -0,0 +1,2 @@
+ALTER TABLE payments DROP COLUMN batch_id;
+DROP TABLE payment_batches
\ No newline at end of file
@@ -0,0 +1,6 @@
+CREATE TABLE payment_batches (
+    id INTEGER PRIMARY KEY NOT NULL,
+    federation_id TEXT NOT NULL,
+    create_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+ALTER TABLE payments ADD COLUMN batch_id INTEGER REFERENCES payment_batches(id)
\ No newline at end of file
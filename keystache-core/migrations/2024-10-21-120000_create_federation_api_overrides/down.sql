@@ -0,0 +1 @@
+DROP TABLE federation_api_overrides
\ No newline at end of file
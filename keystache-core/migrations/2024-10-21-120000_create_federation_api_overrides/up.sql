@@ -0,0 +1,5 @@
+CREATE TABLE federation_api_overrides (
+    federation_id TEXT PRIMARY KEY NOT NULL,
+    api_secret TEXT,
+    create_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
\ No newline at end of file
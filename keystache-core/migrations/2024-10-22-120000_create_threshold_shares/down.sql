@@ -0,0 +1 @@
+DROP TABLE threshold_shares
\ No newline at end of file
@@ -0,0 +1,5 @@
+CREATE TABLE threshold_shares (
+    npub TEXT PRIMARY KEY NOT NULL,
+    share_json TEXT NOT NULL,
+    create_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
\ No newline at end of file
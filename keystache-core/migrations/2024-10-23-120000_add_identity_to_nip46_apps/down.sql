@@ -0,0 +1 @@
+ALTER TABLE nip46_apps DROP COLUMN identity_npub
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE nip46_apps ADD COLUMN identity_npub TEXT
\ No newline at end of file
@@ -0,0 +1,11 @@
+CREATE TABLE transactions (
+    operation_id TEXT PRIMARY KEY NOT NULL,
+    federation_id TEXT NOT NULL,
+    federation_name TEXT,
+    kind TEXT NOT NULL,
+    direction TEXT NOT NULL,
+    amount_msats BIGINT NOT NULL,
+    fee_msats BIGINT NOT NULL,
+    status TEXT NOT NULL,
+    create_time DATETIME NOT NULL
+)
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE nip46_app_kind_policies
\ No newline at end of file
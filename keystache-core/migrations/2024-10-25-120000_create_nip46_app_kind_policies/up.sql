@@ -0,0 +1,7 @@
+CREATE TABLE nip46_app_kind_policies (
+    npub TEXT NOT NULL,
+    kind INTEGER NOT NULL,
+    decision TEXT NOT NULL,
+    create_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (npub, kind)
+)
\ No newline at end of file
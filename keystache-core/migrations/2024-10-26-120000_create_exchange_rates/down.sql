@@ -0,0 +1,3 @@
+ALTER TABLE transactions DROP COLUMN fiat_btc_price;
+ALTER TABLE transactions DROP COLUMN fiat_currency;
+DROP TABLE exchange_rates
\ No newline at end of file
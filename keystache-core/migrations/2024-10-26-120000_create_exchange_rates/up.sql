@@ -0,0 +1,8 @@
+CREATE TABLE exchange_rates (
+    id INTEGER PRIMARY KEY NOT NULL,
+    currency TEXT NOT NULL,
+    btc_price DOUBLE NOT NULL,
+    fetch_time DATETIME NOT NULL
+);
+ALTER TABLE transactions ADD COLUMN fiat_currency TEXT;
+ALTER TABLE transactions ADD COLUMN fiat_btc_price DOUBLE
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE nip46_apps DROP COLUMN remember_conversations
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE nip46_apps ADD COLUMN remember_conversations BOOLEAN DEFAULT FALSE NOT NULL
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE nostr_relays DROP COLUMN auto_reconnect
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE nostr_relays ADD COLUMN auto_reconnect BOOLEAN DEFAULT TRUE NOT NULL
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE nostr_relays DROP COLUMN write;
+ALTER TABLE nostr_relays DROP COLUMN read
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE nostr_relays ADD COLUMN read BOOLEAN DEFAULT TRUE NOT NULL;
+ALTER TABLE nostr_relays ADD COLUMN write BOOLEAN DEFAULT TRUE NOT NULL
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE federation_metadata
\ No newline at end of file
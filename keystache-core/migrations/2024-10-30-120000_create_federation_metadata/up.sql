@@ -0,0 +1,8 @@
+CREATE TABLE federation_metadata (
+    federation_id TEXT PRIMARY KEY NOT NULL,
+    name TEXT,
+    module_kinds TEXT NOT NULL,
+    guardian_names TEXT NOT NULL,
+    icon_url TEXT,
+    create_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE nwc_handled_requests
\ No newline at end of file
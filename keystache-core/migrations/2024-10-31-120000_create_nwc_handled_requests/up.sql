@@ -0,0 +1,6 @@
+CREATE TABLE nwc_handled_requests (
+    request_event_id TEXT PRIMARY KEY NOT NULL,
+    requester_npub TEXT NOT NULL,
+    request_time DATETIME NOT NULL,
+    create_time DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
\ No newline at end of file
@@ -0,0 +1,279 @@
+use std::time::Duration;
+
+use nostr_sdk::{nips::nip44, Event, EventBuilder, Filter, Keys, Kind, PublicKey, Tag};
+use serde_json::{json, Value};
+
+use crate::{db::Database, nostr::NostrModule, policy::Nip46App};
+
+/// Identifies Keystache's backup among the NIP-78 app data events of a key.
+const APP_BACKUP_IDENTIFIER: &str = "keystache/connected-apps";
+
+/// Bumped whenever the backup's format changes, so that older
+/// versions of Keystache don't misread newer backups.
+const APP_BACKUP_VERSION: u64 = 1;
+
+/// How long relays are given to return a backup.
+const APP_BACKUP_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The registered NIP-46 apps and their approval settings, as backed up to relays.
+/// See [`publish_app_backup`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppBackup {
+    /// The global setting for approving `get_public_key` requests without prompting.
+    pub auto_approve_public_key_reads: bool,
+    pub apps: Vec<AppBackupEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppBackupEntry {
+    pub public_key: PublicKey,
+    pub auto_approve_public_key_reads: bool,
+    /// See [`Nip46App::identity_or`]. Backups made before identities could be chosen have none.
+    pub identity_or: Option<PublicKey>,
+    /// See [`Nip46App::remember_conversations`]. Off for backups made before it could be set.
+    pub remember_conversations: bool,
+}
+
+impl From<&Nip46App> for AppBackupEntry {
+    fn from(nip46_app: &Nip46App) -> Self {
+        Self {
+            public_key: nip46_app.public_key,
+            auto_approve_public_key_reads: nip46_app.auto_approve_public_key_reads,
+            identity_or: nip46_app.identity_or,
+            remember_conversations: nip46_app.remember_conversations,
+        }
+    }
+}
+
+impl AppBackup {
+    /// Reads the apps and settings to back up from the database.
+    pub fn load(db: &Database) -> anyhow::Result<Self> {
+        Ok(Self {
+            auto_approve_public_key_reads: db.get_auto_approve_public_key_reads()?,
+            apps: db
+                .list_nip46_apps()?
+                .iter()
+                .map(AppBackupEntry::from)
+                .collect(),
+        })
+    }
+
+    /// Registers the backed up apps and saves their settings. Apps that are already
+    /// registered keep their signing history, but take the backed up settings.
+    pub fn restore(&self, db: &Database) -> anyhow::Result<()> {
+        for entry in &self.apps {
+            db.register_nip46_app(&entry.public_key)?;
+            db.save_nip46_app_auto_approve_public_key_reads(
+                &entry.public_key,
+                entry.auto_approve_public_key_reads,
+            )?;
+            db.save_nip46_app_remember_conversations(
+                &entry.public_key,
+                entry.remember_conversations,
+            )?;
+
+            // A backup without an identity doesn't undo one chosen since.
+            if let Some(identity) = &entry.identity_or {
+                db.save_nip46_app_identity(&entry.public_key, Some(identity))?;
+            }
+        }
+
+        db.save_auto_approve_public_key_reads(self.auto_approve_public_key_reads)
+    }
+
+    fn to_json(&self) -> String {
+        json!({
+            "version": APP_BACKUP_VERSION,
+            "auto_approve_public_key_reads": self.auto_approve_public_key_reads,
+            "apps": self.apps.iter().map(|entry| json!({
+                "public_key": entry.public_key.to_hex(),
+                "auto_approve_public_key_reads": entry.auto_approve_public_key_reads,
+                "identity": entry.identity_or.map(|identity| identity.to_hex()),
+                "remember_conversations": entry.remember_conversations,
+            })).collect::<Vec<_>>(),
+        })
+        .to_string()
+    }
+
+    fn from_json(content: &str) -> anyhow::Result<Self> {
+        let value: Value = serde_json::from_str(content)?;
+
+        match value.get("version").and_then(Value::as_u64) {
+            Some(APP_BACKUP_VERSION) => {}
+            Some(version) if version > APP_BACKUP_VERSION => {
+                anyhow::bail!("The backup was made by a newer version of Keystache");
+            }
+            _ => anyhow::bail!("The backup is malformed"),
+        }
+
+        let read_bool = |value: &Value| {
+            value
+                .get("auto_approve_public_key_reads")
+                .and_then(Value::as_bool)
+                .ok_or_else(|| anyhow::anyhow!("The backup is malformed"))
+        };
+
+        Ok(Self {
+            auto_approve_public_key_reads: read_bool(&value)?,
+            apps: value
+                .get("apps")
+                .and_then(Value::as_array)
+                .ok_or_else(|| anyhow::anyhow!("The backup is malformed"))?
+                .iter()
+                .map(|app| {
+                    Ok(AppBackupEntry {
+                        public_key: PublicKey::from_hex(
+                            app.get("public_key")
+                                .and_then(Value::as_str)
+                                .unwrap_or_default(),
+                        )?,
+                        auto_approve_public_key_reads: read_bool(app)?,
+                        identity_or: app
+                            .get("identity")
+                            .and_then(Value::as_str)
+                            .map(PublicKey::from_hex)
+                            .transpose()?,
+                        remember_conversations: app
+                            .get("remember_conversations")
+                            .and_then(Value::as_bool)
+                            .unwrap_or(false),
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+
+    /// Encrypts the backup to `keys` with NIP-44 and signs it as a NIP-78 app data event.
+    /// Only `keys` can read it, but relays can see when it was last updated.
+    pub fn to_event(&self, keys: &Keys) -> anyhow::Result<Event> {
+        let content = nip44::encrypt(
+            keys.secret_key(),
+            &keys.public_key(),
+            self.to_json(),
+            nip44::Version::V2,
+        )?;
+
+        Ok(EventBuilder::new(
+            Kind::ApplicationSpecificData,
+            content,
+            [Tag::identifier(APP_BACKUP_IDENTIFIER)],
+        )
+        .to_event(keys)?)
+    }
+
+    /// Decrypts a backup made by [`Self::to_event`] with the same `keys`.
+    pub fn from_event(event: &Event, keys: &Keys) -> anyhow::Result<Self> {
+        let is_app_backup = event.tags.iter().any(|tag| match tag.as_slice() {
+            [name, identifier, ..] => name == "d" && identifier == APP_BACKUP_IDENTIFIER,
+            _ => false,
+        });
+
+        if event.pubkey != keys.public_key()
+            || event.kind != Kind::ApplicationSpecificData
+            || !is_app_backup
+        {
+            anyhow::bail!("Not a backup made by this key");
+        }
+
+        event.verify()?;
+
+        Self::from_json(&nip44::decrypt(
+            keys.secret_key(),
+            &keys.public_key(),
+            &event.content,
+        )?)
+    }
+}
+
+/// Backs up the registered apps and their settings to relays, encrypted to `keys`.
+/// Replaces any earlier backup by the same key. Restore it with [`fetch_app_backup`].
+pub async fn publish_app_backup(
+    nostr_module: &NostrModule,
+    db: &Database,
+    keys: &Keys,
+) -> anyhow::Result<()> {
+    nostr_module
+        .publish(AppBackup::load(db)?.to_event(keys)?)
+        .await?;
+
+    Ok(())
+}
+
+/// Fetches the newest backup made by `keys` from relays.
+pub async fn fetch_app_backup(
+    nostr_module: &NostrModule,
+    keys: &Keys,
+) -> anyhow::Result<AppBackup> {
+    let events = nostr_module
+        .fetch_events(
+            vec![Filter::new()
+                .author(keys.public_key())
+                .kind(Kind::ApplicationSpecificData)
+                .identifier(APP_BACKUP_IDENTIFIER)],
+            APP_BACKUP_FETCH_TIMEOUT,
+        )
+        .await?;
+
+    // Backups that fail to decrypt are skipped, since relays could send anything.
+    events
+        .iter()
+        .filter_map(|event| {
+            AppBackup::from_event(event, keys)
+                .ok()
+                .map(|backup| (event.created_at, backup))
+        })
+        .max_by_key(|(created_at, _)| *created_at)
+        .map(|(_, backup)| backup)
+        .ok_or_else(|| anyhow::anyhow!("No backup found"))
+}
+
+/// Fetches the newest backup made by `keys` and restores it. Returns how many apps it held.
+pub async fn restore_app_backup(
+    nostr_module: &NostrModule,
+    db: &Database,
+    keys: &Keys,
+) -> anyhow::Result<usize> {
+    let app_backup = fetch_app_backup(nostr_module, keys).await?;
+
+    app_backup.restore(db)?;
+
+    Ok(app_backup.apps.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_app_backup_event_round_trip() {
+        let keys = Keys::generate();
+
+        let backup = AppBackup {
+            auto_approve_public_key_reads: true,
+            apps: vec![
+                AppBackupEntry {
+                    public_key: Keys::generate().public_key(),
+                    auto_approve_public_key_reads: true,
+                    identity_or: Some(Keys::generate().public_key()),
+                    remember_conversations: true,
+                },
+                AppBackupEntry {
+                    public_key: Keys::generate().public_key(),
+                    auto_approve_public_key_reads: false,
+                    identity_or: None,
+                    remember_conversations: false,
+                },
+            ],
+        };
+
+        let event = backup.to_event(&keys).unwrap();
+
+        // The apps aren't readable by relays.
+        assert!(!event.content.contains(&backup.apps[0].public_key.to_hex()));
+
+        assert_eq!(AppBackup::from_event(&event, &keys).unwrap(), backup);
+
+        // Only the key that made the backup can read it.
+        assert!(AppBackup::from_event(&event, &Keys::generate()).is_err());
+    }
+}
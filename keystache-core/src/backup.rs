@@ -0,0 +1,169 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::NaiveDateTime;
+
+use crate::{db::Database, fedimint::Wallet};
+
+/// How often scheduled backups are made.
+pub const BACKUP_INTERVAL: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// How often to check whether a scheduled backup is due.
+pub const BACKUP_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+pub const DEFAULT_BACKUP_ROTATION_COUNT: usize = 7;
+
+// Every backup is written to its own folder whose name starts with this prefix,
+// followed by the backup's UTC timestamp. Sorting the folder names by name
+// therefore sorts the backups from oldest to newest.
+const BACKUP_FOLDER_PREFIX: &str = "keystache-backup-";
+const BACKUP_FOLDER_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+const DATABASE_BACKUP_FILE_NAME: &str = "keystache.sqlite";
+
+/// Where scheduled backups are written, and how many are kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupSettings {
+    /// Scheduled backups are disabled if this is `None`.
+    pub directory_or: Option<PathBuf>,
+    /// The number of most recent backups to keep. Older backups are deleted.
+    pub rotation_count: usize,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        Self {
+            directory_or: None,
+            rotation_count: DEFAULT_BACKUP_ROTATION_COUNT,
+        }
+    }
+}
+
+/// The outcome of the most recent backups.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BackupStatus {
+    pub last_success_time_or: Option<NaiveDateTime>,
+    /// The error from the most recent backup, if it failed.
+    pub last_error_or: Option<String>,
+}
+
+impl BackupStatus {
+    /// Whether a scheduled backup should be made at `now`.
+    pub fn is_backup_due(&self, now: NaiveDateTime) -> bool {
+        !self.last_success_time_or.is_some_and(|last_success_time| {
+            now.signed_duration_since(last_success_time)
+                .to_std()
+                .is_ok_and(|elapsed| elapsed < BACKUP_INTERVAL)
+        })
+    }
+}
+
+/// Writes a new backup to a timestamped folder inside `directory`, then deletes
+/// all but the most recent `rotation_count` backups. The backup contains the
+/// database, which is encrypted with the user's password, and an e-cash backup
+/// for each federation, which is encrypted with a key derived from the wallet's seed.
+/// The folder is named after `backup_time`.
+pub async fn run_backup(
+    db: Arc<Database>,
+    wallet: Arc<Wallet>,
+    directory: PathBuf,
+    rotation_count: usize,
+    backup_time: NaiveDateTime,
+) -> anyhow::Result<()> {
+    let encrypted_ecash_backups = wallet.create_encrypted_ecash_backups().await?;
+
+    let backup_folder = directory.join(format!(
+        "{BACKUP_FOLDER_PREFIX}{}",
+        backup_time.format(BACKUP_FOLDER_TIMESTAMP_FORMAT)
+    ));
+
+    // The file system and the database are blocking, so they're kept off the async worker
+    // threads. The database stays locked while it's copied, which can take a while.
+    tokio::task::spawn_blocking(move || {
+        std::fs::create_dir_all(&backup_folder)?;
+
+        db.export_encrypted_copy(&backup_folder.join(DATABASE_BACKUP_FILE_NAME))?;
+
+        for (federation_id, encrypted_backup) in encrypted_ecash_backups {
+            std::fs::write(
+                backup_folder.join(format!("ecash-{federation_id}.bin")),
+                encrypted_backup,
+            )?;
+        }
+
+        prune_old_backups(&directory, rotation_count)
+    })
+    .await?
+}
+
+/// Deletes all but the most recent `rotation_count` backup folders in `directory`.
+/// Anything else in `directory` is left untouched.
+fn prune_old_backups(directory: &Path, rotation_count: usize) -> anyhow::Result<()> {
+    let mut backup_folder_names: Vec<String> = std::fs::read_dir(directory)?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+
+            if !entry.file_type().ok()?.is_dir() {
+                return None;
+            }
+
+            entry
+                .file_name()
+                .into_string()
+                .ok()
+                .filter(|name| name.starts_with(BACKUP_FOLDER_PREFIX))
+        })
+        .collect();
+
+    backup_folder_names.sort();
+
+    let prune_count = backup_folder_names.len().saturating_sub(rotation_count);
+
+    for backup_folder_name in backup_folder_names.into_iter().take(prune_count) {
+        std::fs::remove_dir_all(directory.join(backup_folder_name))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prune_old_backups() {
+        let directory = tempfile::tempdir().unwrap();
+
+        for timestamp in ["20241001-000000", "20241002-000000", "20241003-000000"] {
+            std::fs::create_dir(
+                directory
+                    .path()
+                    .join(format!("{BACKUP_FOLDER_PREFIX}{timestamp}")),
+            )
+            .unwrap();
+        }
+
+        // Unrelated files and folders are never deleted.
+        std::fs::create_dir(directory.path().join("photos")).unwrap();
+
+        prune_old_backups(directory.path(), 2).unwrap();
+
+        let mut remaining_names: Vec<String> = std::fs::read_dir(directory.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        remaining_names.sort();
+
+        assert_eq!(
+            remaining_names,
+            vec![
+                format!("{BACKUP_FOLDER_PREFIX}20241002-000000"),
+                format!("{BACKUP_FOLDER_PREFIX}20241003-000000"),
+                "photos".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::BTreeMap;
+
+/// Every `BBQr` part starts with this, followed by the rest of its header.
+const HEADER_PREFIX: &str = "B$";
+
+/// The header is the prefix, the encoding, the file type, the part count and the part index.
+const HEADER_LEN: usize = 8;
+
+/// Encoding of the data in each part: RFC 4648 base32 without padding.
+/// Base32 only uses characters that fit QR codes' compact alphanumeric mode.
+const BASE32_ENCODING: char = '2';
+
+/// Encoding of the data in each part: uppercase hex.
+const HEX_ENCODING: char = 'H';
+
+/// File type of data that is UTF-8 text.
+const UNICODE_TEXT_FILE_TYPE: char = 'U';
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// The most parts that a part count or index of two base 36 digits can describe.
+const MAX_PART_COUNT: usize = 36 * 36 - 1;
+
+/// Splits `text` into the parts of a `BBQr` code, to be shown one after another as an animated
+/// QR code. Each part holds at most `max_data_len` characters of data after its header.
+/// See <https://bbqr.org> for the format.
+pub fn split(text: &str, max_data_len: usize) -> anyhow::Result<Vec<String>> {
+    let data = encode_base32(text.as_bytes());
+
+    // Base32 decodes in groups of 8 characters, so every part but the last has to hold whole groups.
+    let part_data_len = max_data_len - max_data_len % 8;
+    if part_data_len == 0 {
+        anyhow::bail!("Parts must hold at least 8 characters of data");
+    }
+
+    let part_count = data.len().div_ceil(part_data_len).max(1);
+    if part_count > MAX_PART_COUNT {
+        anyhow::bail!("Too much data for a BBQr code");
+    }
+
+    Ok((0..part_count)
+        .map(|index| {
+            let start = index * part_data_len;
+            let end = (start + part_data_len).min(data.len());
+
+            format!(
+                "{HEADER_PREFIX}{BASE32_ENCODING}{UNICODE_TEXT_FILE_TYPE}{}{}{}",
+                encode_base36(part_count),
+                encode_base36(index),
+                &data[start..end]
+            )
+        })
+        .collect())
+}
+
+/// Whether `text` looks like a part of a `BBQr` code, rather than something to be used as is.
+pub fn is_part(text: &str) -> bool {
+    text.trim().starts_with(HEADER_PREFIX)
+}
+
+/// Puts the parts of a `BBQr` code back together as they're scanned, in any order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Joiner {
+    // The encoding, file type and part count shared by every part of the code.
+    header_or: Option<(char, char, usize)>,
+    parts: BTreeMap<usize, String>,
+}
+
+impl Joiner {
+    /// Adds a scanned part. Scanning a part again does nothing, but a part of a different code
+    /// is refused, since codes can't be mixed. Returns the joined text once every part is in.
+    pub fn add_part(&mut self, part: &str) -> anyhow::Result<Option<String>> {
+        let part = part.trim();
+
+        let Some(header) = part
+            .get(..HEADER_LEN)
+            .filter(|header| header.is_ascii() && is_part(header))
+        else {
+            anyhow::bail!("Not a BBQr code");
+        };
+
+        let mut header_chars = header[HEADER_PREFIX.len()..].chars();
+        let (Some(encoding), Some(file_type)) = (header_chars.next(), header_chars.next()) else {
+            anyhow::bail!("Not a BBQr code");
+        };
+
+        let part_count = decode_base36(&header[4..6])?;
+        let index = decode_base36(&header[6..8])?;
+
+        if index >= part_count {
+            anyhow::bail!(
+                "Part {} is past the end of a {part_count} part code",
+                index + 1
+            );
+        }
+
+        if file_type != UNICODE_TEXT_FILE_TYPE {
+            anyhow::bail!("This BBQr code holds a file rather than text");
+        }
+
+        if encoding != BASE32_ENCODING && encoding != HEX_ENCODING {
+            anyhow::bail!("Compressed BBQr codes aren't supported");
+        }
+
+        match self.header_or {
+            Some(expected_header) if expected_header != (encoding, file_type, part_count) => {
+                anyhow::bail!("This part belongs to a different code");
+            }
+            _ => self.header_or = Some((encoding, file_type, part_count)),
+        }
+
+        self.parts.insert(index, part[HEADER_LEN..].to_string());
+
+        self.join()
+    }
+
+    /// How many of the code's parts have been scanned, and how many it has in total.
+    pub fn progress(&self) -> (usize, usize) {
+        (
+            self.parts.len(),
+            self.header_or
+                .map_or(0, |(_encoding, _file_type, part_count)| part_count),
+        )
+    }
+
+    fn join(&self) -> anyhow::Result<Option<String>> {
+        let Some((encoding, _file_type, part_count)) = self.header_or else {
+            return Ok(None);
+        };
+
+        if self.parts.len() < part_count {
+            return Ok(None);
+        }
+
+        let data: String = self.parts.values().map(String::as_str).collect();
+
+        let bytes = if encoding == HEX_ENCODING {
+            decode_hex(&data)?
+        } else {
+            decode_base32(&data)?
+        };
+
+        Ok(Some(String::from_utf8(bytes)?))
+    }
+}
+
+fn encode_base36(value: usize) -> String {
+    const DIGITS: &[u8; 36] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+    [value / 36, value % 36]
+        .into_iter()
+        .map(|digit| char::from(DIGITS[digit]))
+        .collect()
+}
+
+fn decode_base36(digits: &str) -> anyhow::Result<usize> {
+    Ok(usize::from_str_radix(digits, 36)?)
+}
+
+fn encode_base32(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(5) * 8);
+
+    let mut buffer: u16 = 0;
+    let mut buffered_bits = 0;
+
+    for byte in bytes {
+        buffer = (buffer << 8) | u16::from(*byte);
+        buffered_bits += 8;
+
+        while buffered_bits >= 5 {
+            buffered_bits -= 5;
+            encoded.push(char::from(
+                BASE32_ALPHABET[usize::from((buffer >> buffered_bits) & 0x1f)],
+            ));
+        }
+    }
+
+    if buffered_bits > 0 {
+        encoded.push(char::from(
+            BASE32_ALPHABET[usize::from((buffer << (5 - buffered_bits)) & 0x1f)],
+        ));
+    }
+
+    encoded
+}
+
+fn decode_base32(encoded: &str) -> anyhow::Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(encoded.len() * 5 / 8);
+
+    let mut buffer: u16 = 0;
+    let mut buffered_bits = 0;
+
+    for character in encoded.trim_end_matches('=').bytes() {
+        let Some(value) = BASE32_ALPHABET
+            .iter()
+            .position(|alphabet_character| *alphabet_character == character)
+        else {
+            anyhow::bail!("Invalid base32 character");
+        };
+
+        // Can't truncate, since the alphabet has 32 characters.
+        buffer = (buffer << 5) | value as u16;
+        buffered_bits += 5;
+
+        if buffered_bits >= 8 {
+            buffered_bits -= 8;
+            bytes.push((buffer >> buffered_bits) as u8);
+        }
+    }
+
+    Ok(bytes)
+}
+
+fn decode_hex(encoded: &str) -> anyhow::Result<Vec<u8>> {
+    // Checking for ASCII first means that slicing in pairs of bytes can't split a character.
+    if !encoded.is_ascii() || encoded.len() % 2 != 0 {
+        anyhow::bail!("Invalid hex data");
+    }
+
+    (0..encoded.len())
+        .step_by(2)
+        .map(|start| Ok(u8::from_str_radix(&encoded[start..start + 2], 16)?))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_and_join() {
+        let text = "lnbc1".to_string() + &"qpzry9x8gf2tvdw0s3jn54khce6mua7l".repeat(20);
+
+        let parts = split(&text, 100).unwrap();
+        assert_eq!(parts.len(), 11);
+        assert!(parts[0].starts_with("B$2U0B00"));
+        assert!(parts[10].starts_with("B$2U0B0A"));
+        assert!(parts.iter().all(|part| is_part(part)));
+
+        // Parts can be scanned in any order, and more than once.
+        let mut joiner = Joiner::default();
+        for part in parts.iter().rev().skip(1) {
+            assert_eq!(joiner.add_part(part).unwrap(), None);
+        }
+        assert_eq!(joiner.add_part(&parts[1]).unwrap(), None);
+        assert_eq!(joiner.progress(), (10, 11));
+        assert_eq!(joiner.add_part(&parts[10]).unwrap(), Some(text));
+
+        // A short text still makes a valid code of one part.
+        assert_eq!(
+            Joiner::default()
+                .add_part(&split("hi", 100).unwrap()[0])
+                .unwrap(),
+            Some("hi".to_string())
+        );
+    }
+
+    #[test]
+    fn test_join_rejects_mixed_codes() {
+        let mut joiner = Joiner::default();
+
+        assert_eq!(joiner.add_part("B$HU02006869").unwrap(), None);
+
+        // Parts of other codes, parts past the end and compressed codes are refused.
+        assert!(joiner.add_part("B$HU030121").is_err());
+        assert!(joiner.add_part("B$HU020221").is_err());
+        assert!(joiner.add_part("B$ZU020121").is_err());
+        assert!(joiner.add_part("lnbc1").is_err());
+        assert_eq!(joiner.progress(), (1, 2));
+
+        assert_eq!(
+            joiner.add_part("B$HU020121").unwrap(),
+            Some("hi!".to_string())
+        );
+    }
+
+    #[test]
+    fn test_base32() {
+        // Test vectors from RFC 4648, without padding.
+        for (bytes, encoded) in [
+            ("", ""),
+            ("f", "MY"),
+            ("fo", "MZXQ"),
+            ("foo", "MZXW6"),
+            ("foob", "MZXW6YQ"),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI"),
+        ] {
+            assert_eq!(encode_base32(bytes.as_bytes()), encoded);
+            assert_eq!(decode_base32(encoded).unwrap(), bytes.as_bytes());
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use secp256k1::rand::{rngs::StdRng, thread_rng, RngCore, SeedableRng};
+use tokio::sync::watch;
+
+/// Where the current time and randomness come from. Cloning the clock shares it.
+///
+/// The default clock reads the system clock and uses thread-local randomness. Tests
+/// use [`Self::manual`] instead, so that time only passes when [`Self::advance`]
+/// is called and random choices are the same on every run.
+#[derive(Debug, Clone, Default)]
+pub struct Clock {
+    manual_clock_or: Option<Arc<ManualClock>>,
+}
+
+#[derive(Debug)]
+struct ManualClock {
+    start_system_time: SystemTime,
+    start_instant: Instant,
+    elapsed_sender: watch::Sender<Duration>,
+    rng: Mutex<StdRng>,
+}
+
+impl Clock {
+    /// A clock that starts at `start_system_time` and stands still until it's
+    /// advanced. Its randomness is seeded with `seed`.
+    pub fn manual(start_system_time: SystemTime, seed: u64) -> Self {
+        Self {
+            manual_clock_or: Some(Arc::new(ManualClock {
+                start_system_time,
+                start_instant: Instant::now(),
+                elapsed_sender: watch::channel(Duration::ZERO).0,
+                rng: Mutex::new(StdRng::seed_from_u64(seed)),
+            })),
+        }
+    }
+
+    pub fn now(&self) -> SystemTime {
+        self.manual_clock_or
+            .as_ref()
+            .map_or_else(SystemTime::now, |manual_clock| {
+                manual_clock.start_system_time + *manual_clock.elapsed_sender.borrow()
+            })
+    }
+
+    pub fn now_utc(&self) -> NaiveDateTime {
+        DateTime::<Utc>::from(self.now()).naive_utc()
+    }
+
+    /// Seconds since the Unix epoch, or zero if the clock is set before it.
+    pub fn unix_timestamp_secs(&self) -> u64 {
+        self.now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default()
+    }
+
+    /// A monotonic instant, for measuring how long something took.
+    pub fn instant(&self) -> Instant {
+        self.manual_clock_or
+            .as_ref()
+            .map_or_else(Instant::now, |manual_clock| {
+                manual_clock.start_instant + *manual_clock.elapsed_sender.borrow()
+            })
+    }
+
+    /// Moves a manual clock forward, waking anything sleeping on it that's now due.
+    /// Does nothing to the system clock.
+    pub fn advance(&self, duration: Duration) {
+        if let Some(manual_clock) = &self.manual_clock_or {
+            manual_clock
+                .elapsed_sender
+                .send_modify(|elapsed| *elapsed += duration);
+        }
+    }
+
+    /// Waits for `duration` to pass on this clock.
+    pub async fn sleep(&self, duration: Duration) {
+        let Some(manual_clock) = &self.manual_clock_or else {
+            tokio::time::sleep(duration).await;
+            return;
+        };
+
+        let mut elapsed_receiver = manual_clock.elapsed_sender.subscribe();
+        let wake_elapsed = *elapsed_receiver.borrow() + duration;
+
+        // The sender lives as long as the clock, so this only fails once nothing can advance it.
+        let _ = elapsed_receiver
+            .wait_for(|elapsed| *elapsed >= wake_elapsed)
+            .await;
+    }
+
+    /// Runs `f` with this clock's source of randomness.
+    pub fn with_rng<T>(&self, f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+        match &self.manual_clock_or {
+            Some(manual_clock) => {
+                let mut rng = manual_clock
+                    .rng
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner);
+                f(&mut *rng)
+            }
+            None => f(&mut thread_rng()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use secp256k1::rand::Rng;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_manual_clock() {
+        let start_system_time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let clock = Clock::manual(start_system_time, 7);
+        let start_instant = clock.instant();
+
+        assert_eq!(clock.now(), start_system_time);
+        assert_eq!(clock.unix_timestamp_secs(), 1_700_000_000);
+
+        let sleep = clock.sleep(Duration::from_secs(60));
+        tokio::pin!(sleep);
+        assert!(futures::poll!(&mut sleep).is_pending());
+
+        clock.advance(Duration::from_secs(30));
+        assert!(futures::poll!(&mut sleep).is_pending());
+
+        clock.advance(Duration::from_secs(30));
+        assert!(futures::poll!(&mut sleep).is_ready());
+
+        assert_eq!(clock.now(), start_system_time + Duration::from_secs(60));
+        assert_eq!(clock.instant() - start_instant, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_manual_clock_randomness_is_repeatable() {
+        let random_numbers = |clock: &Clock| {
+            (0..4)
+                .map(|_| clock.with_rng(|rng| rng.gen::<u64>()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            random_numbers(&Clock::manual(UNIX_EPOCH, 7)),
+            random_numbers(&Clock::manual(UNIX_EPOCH, 7))
+        );
+        assert_ne!(
+            random_numbers(&Clock::manual(UNIX_EPOCH, 7)),
+            random_numbers(&Clock::manual(UNIX_EPOCH, 8))
+        );
+    }
+}
@@ -0,0 +1,544 @@
+use std::{fmt::Display, str::FromStr, sync::Arc, time::Duration};
+
+use tokio::sync::watch;
+
+use crate::exchange_rate::{FiatCurrency, PriceSource};
+
+const THEME_KEY: &str = "theme";
+const CLOCK_FORMAT_KEY: &str = "clock_format";
+const NIP55_SOCKET_PATH_KEY: &str = "nip55_socket_path";
+const NIP46_REQUEST_EXPIRY_KEY: &str = "nip46_request_expiry_secs";
+const READ_RELAY_COUNT_KEY: &str = "read_relay_count";
+const WALLET_VIEW_UPDATE_INTERVAL_KEY: &str = "wallet_view_update_interval_secs";
+const LOW_DATA_MODE_KEY: &str = "low_data_mode";
+const REFRESH_BALANCES_ON_DEMAND_KEY: &str = "refresh_balances_on_demand";
+const FIAT_CURRENCY_KEY: &str = "fiat_currency";
+const PRICE_SOURCE_KEY: &str = "price_source";
+/// The key of [`Settings::show_release_notes`], for frontends that offer it outside the settings.
+pub const SHOW_RELEASE_NOTES_KEY: &str = "show_release_notes";
+
+const DEFAULT_NIP55_SOCKET_PATH: &str = "/tmp/nip55-kind24133.sock";
+
+// The value of the fiat currency setting when amounts are only shown in bitcoin.
+const NO_FIAT_CURRENCY: &str = "none";
+
+// Most apps give up on a signer within a couple of minutes, so
+// prompts left longer than that are usually for nobody.
+const DEFAULT_NIP46_REQUEST_EXPIRY: Duration = Duration::from_secs(120);
+const MIN_NIP46_REQUEST_EXPIRY_SECS: u64 = 10;
+const MAX_NIP46_REQUEST_EXPIRY_SECS: u64 = 3600;
+
+// Reads are only sent to the fastest few relays, since the
+// slowest relay holds up the whole read.
+const DEFAULT_READ_RELAY_COUNT: usize = 3;
+const MAX_READ_RELAY_COUNT: u64 = 20;
+
+const DEFAULT_WALLET_VIEW_UPDATE_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_WALLET_VIEW_UPDATE_INTERVAL_SECS: u64 = 600;
+
+// Balances still update in low data mode, just less often.
+const LOW_DATA_MODE_POLL_INTERVAL_MULTIPLIER: u32 = 6;
+
+/// The color theme of the app.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AppTheme {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl AppTheme {
+    pub const ALL: [Self; 2] = [Self::Dark, Self::Light];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Dark => "dark",
+            Self::Light => "light",
+        }
+    }
+}
+
+impl Display for AppTheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Dark => write!(f, "Dark"),
+            Self::Light => write!(f, "Light"),
+        }
+    }
+}
+
+impl FromStr for AppTheme {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dark" => Ok(Self::Dark),
+            "light" => Ok(Self::Light),
+            _ => Err(anyhow::anyhow!("Unknown theme: {s}")),
+        }
+    }
+}
+
+/// Whether times are shown with a 12-hour clock (2:05 PM) or a 24-hour clock (14:05).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClockFormat {
+    TwelveHour,
+    #[default]
+    TwentyFourHour,
+}
+
+impl ClockFormat {
+    pub const ALL: [Self; 2] = [Self::TwentyFourHour, Self::TwelveHour];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::TwelveHour => "12h",
+            Self::TwentyFourHour => "24h",
+        }
+    }
+}
+
+impl Display for ClockFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TwelveHour => write!(f, "12-hour"),
+            Self::TwentyFourHour => write!(f, "24-hour"),
+        }
+    }
+}
+
+impl FromStr for ClockFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "12h" => Ok(Self::TwelveHour),
+            "24h" => Ok(Self::TwentyFourHour),
+            _ => Err(anyhow::anyhow!("Unknown clock format: {s}")),
+        }
+    }
+}
+
+/// Settings that subsystems read while running, rather than only on startup.
+/// Saved in the database with [`crate::db::Database::save_settings`], and
+/// shared with subsystems through a [`SettingsHandle`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    pub theme: AppTheme,
+    pub clock_format: ClockFormat,
+    /// Where the NIP-46 server listens for apps.
+    pub nip55_socket_path: String,
+    /// How long NIP-46 requests wait for the user before they're rejected as expired.
+    pub nip46_request_expiry: Duration,
+    /// How many of the fastest connected relays are read from.
+    pub read_relay_count: usize,
+    /// How often the wallet checks its federations for changes.
+    pub wallet_view_update_interval: Duration,
+    /// Whether to poll less often and put off fetches that aren't needed,
+    /// such as profile pictures, to save data on metered connections.
+    pub low_data_mode: bool,
+    /// Whether the wallet only checks its federations when asked to, rather
+    /// than in the background, so that its traffic doesn't reveal it's running.
+    pub refresh_balances_on_demand: bool,
+    /// Whether to show what's new on the first unlock after an update.
+    pub show_release_notes: bool,
+    /// The currency that amounts can also be entered and shown in. Prices are
+    /// only fetched when one is set, since fetching them reveals Keystache is running.
+    pub fiat_currency_or: Option<FiatCurrency>,
+    pub price_source: PriceSource,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: AppTheme::default(),
+            clock_format: ClockFormat::default(),
+            nip55_socket_path: DEFAULT_NIP55_SOCKET_PATH.to_string(),
+            nip46_request_expiry: DEFAULT_NIP46_REQUEST_EXPIRY,
+            read_relay_count: DEFAULT_READ_RELAY_COUNT,
+            wallet_view_update_interval: DEFAULT_WALLET_VIEW_UPDATE_INTERVAL,
+            low_data_mode: false,
+            refresh_balances_on_demand: false,
+            show_release_notes: true,
+            fiat_currency_or: None,
+            price_source: PriceSource::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// The key of every field, as used by [`Self::with_field`].
+    pub const KEYS: [&'static str; 11] = [
+        THEME_KEY,
+        CLOCK_FORMAT_KEY,
+        NIP55_SOCKET_PATH_KEY,
+        NIP46_REQUEST_EXPIRY_KEY,
+        READ_RELAY_COUNT_KEY,
+        WALLET_VIEW_UPDATE_INTERVAL_KEY,
+        LOW_DATA_MODE_KEY,
+        REFRESH_BALANCES_ON_DEMAND_KEY,
+        SHOW_RELEASE_NOTES_KEY,
+        FIAT_CURRENCY_KEY,
+        PRICE_SOURCE_KEY,
+    ];
+
+    /// How often the wallet actually checks its federations, which is less often in low data mode.
+    pub fn effective_wallet_view_update_interval(&self) -> Duration {
+        if self.low_data_mode {
+            self.wallet_view_update_interval * LOW_DATA_MODE_POLL_INTERVAL_MULTIPLIER
+        } else {
+            self.wallet_view_update_interval
+        }
+    }
+
+    /// Describes each field along with its current value, so that
+    /// frontends can show every setting without knowing about it.
+    pub fn fields(&self) -> Vec<SettingField> {
+        vec![
+            SettingField {
+                key: THEME_KEY,
+                label: "Theme",
+                description: "The color theme of the app.",
+                kind: SettingKind::Choice(
+                    AppTheme::ALL
+                        .iter()
+                        .map(|theme| SettingChoice {
+                            value: theme.as_str(),
+                            label: theme.to_string(),
+                        })
+                        .collect(),
+                ),
+                value: self.theme.as_str().to_string(),
+            },
+            SettingField {
+                key: CLOCK_FORMAT_KEY,
+                label: "Clock",
+                description: "How times of day are shown. Times are always shown in your local time zone.",
+                kind: SettingKind::Choice(
+                    ClockFormat::ALL
+                        .iter()
+                        .map(|clock_format| SettingChoice {
+                            value: clock_format.as_str(),
+                            label: clock_format.to_string(),
+                        })
+                        .collect(),
+                ),
+                value: self.clock_format.as_str().to_string(),
+            },
+            SettingField {
+                key: NIP55_SOCKET_PATH_KEY,
+                label: "NIP-46 Socket Path",
+                description: "Where apps connect to Keystache to request signatures. Apps have to be pointed at the new path when it's changed.",
+                kind: SettingKind::Text,
+                value: self.nip55_socket_path.clone(),
+            },
+            SettingField {
+                key: NIP46_REQUEST_EXPIRY_KEY,
+                label: "Signing Request Expiry (seconds)",
+                description: "How long signing requests wait for you before they're rejected automatically. Apps usually stop waiting for an answer long before a request expires.",
+                kind: SettingKind::Number {
+                    min: MIN_NIP46_REQUEST_EXPIRY_SECS,
+                    max: MAX_NIP46_REQUEST_EXPIRY_SECS,
+                },
+                value: self.nip46_request_expiry.as_secs().to_string(),
+            },
+            SettingField {
+                key: READ_RELAY_COUNT_KEY,
+                label: "Relays Read From",
+                description: "How many of the fastest connected relays are asked for profiles and other events. Reading from more relays finds more events but takes longer.",
+                kind: SettingKind::Number {
+                    min: 1,
+                    max: MAX_READ_RELAY_COUNT,
+                },
+                value: self.read_relay_count.to_string(),
+            },
+            SettingField {
+                key: WALLET_VIEW_UPDATE_INTERVAL_KEY,
+                label: "Wallet Refresh Interval (seconds)",
+                description: "How often federation balances are checked for changes.",
+                kind: SettingKind::Number {
+                    min: 1,
+                    max: MAX_WALLET_VIEW_UPDATE_INTERVAL_SECS,
+                },
+                value: self.wallet_view_update_interval.as_secs().to_string(),
+            },
+            SettingField {
+                key: LOW_DATA_MODE_KEY,
+                label: "Low Data Mode",
+                description: "Saves data on metered connections. Federation balances are checked less often, and profile pictures and clock checks wait until it's turned off.",
+                kind: SettingKind::Toggle,
+                value: self.low_data_mode.to_string(),
+            },
+            SettingField {
+                key: REFRESH_BALANCES_ON_DEMAND_KEY,
+                label: "Refresh Balances Only on Demand",
+                description: "Stops checking federations in the background, so that network observers can't tell Keystache is running from its regular checks. Balances still update after payments and when you refresh the wallet.",
+                kind: SettingKind::Toggle,
+                value: self.refresh_balances_on_demand.to_string(),
+            },
+            SettingField {
+                key: SHOW_RELEASE_NOTES_KEY,
+                label: "What's New After Updates",
+                description: "Shows the highlights of a new version the first time Keystache is unlocked after updating.",
+                kind: SettingKind::Toggle,
+                value: self.show_release_notes.to_string(),
+            },
+            SettingField {
+                key: FIAT_CURRENCY_KEY,
+                label: "Fiat Currency",
+                description: "Lets amounts be entered in this currency on the Send and Receive pages, and shows what transactions were worth. The price of bitcoin is fetched every few minutes while a currency is set.",
+                kind: SettingKind::Choice(
+                    std::iter::once(SettingChoice {
+                        value: NO_FIAT_CURRENCY,
+                        label: "None".to_string(),
+                    })
+                    .chain(FiatCurrency::ALL.iter().map(|currency| SettingChoice {
+                        value: currency.as_str(),
+                        label: currency.to_string(),
+                    }))
+                    .collect(),
+                ),
+                value: self
+                    .fiat_currency_or
+                    .map_or(NO_FIAT_CURRENCY, FiatCurrency::as_str)
+                    .to_string(),
+            },
+            SettingField {
+                key: PRICE_SOURCE_KEY,
+                label: "Price Source",
+                description: "Where the price of bitcoin is fetched from. It can tell when Keystache is running.",
+                kind: SettingKind::Choice(
+                    PriceSource::ALL
+                        .iter()
+                        .map(|price_source| SettingChoice {
+                            value: price_source.as_str(),
+                            label: price_source.to_string(),
+                        })
+                        .collect(),
+                ),
+                value: self.price_source.as_str().to_string(),
+            },
+        ]
+    }
+
+    /// Returns a copy of these settings with the field with the given key set to `value`.
+    /// Fails if the key is unknown or the value isn't valid for the field.
+    pub fn with_field(&self, key: &str, value: &str) -> anyhow::Result<Self> {
+        let mut settings = self.clone();
+
+        match key {
+            THEME_KEY => settings.theme = value.parse()?,
+            CLOCK_FORMAT_KEY => settings.clock_format = value.parse()?,
+            NIP55_SOCKET_PATH_KEY => {
+                let path = value.trim();
+                if path.is_empty() {
+                    anyhow::bail!("The socket path can't be empty");
+                }
+                settings.nip55_socket_path = path.to_string();
+            }
+            NIP46_REQUEST_EXPIRY_KEY => {
+                settings.nip46_request_expiry = Duration::from_secs(parse_number(
+                    value,
+                    MIN_NIP46_REQUEST_EXPIRY_SECS,
+                    MAX_NIP46_REQUEST_EXPIRY_SECS,
+                )?);
+            }
+            READ_RELAY_COUNT_KEY => {
+                settings.read_relay_count =
+                    usize::try_from(parse_number(value, 1, MAX_READ_RELAY_COUNT)?)?;
+            }
+            WALLET_VIEW_UPDATE_INTERVAL_KEY => {
+                settings.wallet_view_update_interval = Duration::from_secs(parse_number(
+                    value,
+                    1,
+                    MAX_WALLET_VIEW_UPDATE_INTERVAL_SECS,
+                )?);
+            }
+            LOW_DATA_MODE_KEY => settings.low_data_mode = value.trim().parse()?,
+            REFRESH_BALANCES_ON_DEMAND_KEY => {
+                settings.refresh_balances_on_demand = value.trim().parse()?;
+            }
+            SHOW_RELEASE_NOTES_KEY => settings.show_release_notes = value.trim().parse()?,
+            FIAT_CURRENCY_KEY => {
+                settings.fiat_currency_or = match value {
+                    NO_FIAT_CURRENCY => None,
+                    _ => Some(value.parse()?),
+                };
+            }
+            PRICE_SOURCE_KEY => settings.price_source = value.parse()?,
+            _ => anyhow::bail!("Unknown setting: {key}"),
+        }
+
+        Ok(settings)
+    }
+}
+
+fn parse_number(value: &str, min: u64, max: u64) -> anyhow::Result<u64> {
+    let number: u64 = value.trim().parse()?;
+
+    if !(min..=max).contains(&number) {
+        anyhow::bail!("Must be between {min} and {max}");
+    }
+
+    Ok(number)
+}
+
+/// A field of [`Settings`], described for frontends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingField {
+    pub key: &'static str,
+    pub label: &'static str,
+    pub description: &'static str,
+    pub kind: SettingKind,
+    /// The current value, as accepted by [`Settings::with_field`].
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SettingKind {
+    /// One of a fixed set of values.
+    Choice(Vec<SettingChoice>),
+    /// Free-form text.
+    Text,
+    /// A whole number in an inclusive range.
+    Number { min: u64, max: u64 },
+    /// On or off, as `true` or `false`.
+    Toggle,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingChoice {
+    pub value: &'static str,
+    pub label: String,
+}
+
+impl Display for SettingChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label)
+    }
+}
+
+/// Shares the current [`Settings`] with subsystems, and notifies them when they change.
+/// Cloning the handle shares the same settings.
+#[derive(Debug, Clone)]
+pub struct SettingsHandle {
+    sender: Arc<watch::Sender<Settings>>,
+}
+
+impl SettingsHandle {
+    pub fn new(settings: Settings) -> Self {
+        Self {
+            sender: Arc::new(watch::channel(settings).0),
+        }
+    }
+
+    pub fn get(&self) -> Settings {
+        self.sender.borrow().clone()
+    }
+
+    /// Subscribes to the settings. Subsystems read the current settings from the
+    /// receiver when they need them, and can wait on it to react to changes.
+    pub fn subscribe(&self) -> watch::Receiver<Settings> {
+        self.sender.subscribe()
+    }
+
+    /// Replaces the settings, notifying subscribers if anything changed.
+    /// Doesn't save them. See [`crate::db::Database::save_settings`].
+    pub fn set(&self, settings: Settings) {
+        self.sender.send_if_modified(|current| {
+            if *current == settings {
+                false
+            } else {
+                *current = settings;
+                true
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields_round_trip() {
+        let settings = Settings {
+            theme: AppTheme::Light,
+            clock_format: ClockFormat::TwelveHour,
+            nip55_socket_path: "/run/keystache.sock".to_string(),
+            nip46_request_expiry: Duration::from_secs(300),
+            read_relay_count: 5,
+            wallet_view_update_interval: Duration::from_secs(30),
+            low_data_mode: true,
+            refresh_balances_on_demand: true,
+            show_release_notes: false,
+            fiat_currency_or: Some(FiatCurrency::Eur),
+            price_source: PriceSource::Coinbase,
+        };
+
+        let mut round_tripped = Settings::default();
+        for field in settings.fields() {
+            round_tripped = round_tripped.with_field(field.key, &field.value).unwrap();
+        }
+
+        assert_eq!(round_tripped, settings);
+        assert_eq!(
+            settings
+                .fields()
+                .iter()
+                .map(|field| field.key)
+                .collect::<Vec<_>>(),
+            Settings::KEYS
+        );
+    }
+
+    #[test]
+    fn test_with_field_rejects_invalid_values() {
+        let settings = Settings::default();
+
+        assert!(settings.with_field(THEME_KEY, "purple").is_err());
+        assert!(settings.with_field(CLOCK_FORMAT_KEY, "36h").is_err());
+        assert!(settings.with_field(NIP55_SOCKET_PATH_KEY, "  ").is_err());
+        assert!(settings.with_field(NIP46_REQUEST_EXPIRY_KEY, "5").is_err());
+        assert!(settings.with_field(READ_RELAY_COUNT_KEY, "0").is_err());
+        assert!(settings.with_field(READ_RELAY_COUNT_KEY, "21").is_err());
+        assert!(settings.with_field(READ_RELAY_COUNT_KEY, "many").is_err());
+        assert!(settings
+            .with_field(WALLET_VIEW_UPDATE_INTERVAL_KEY, "601")
+            .is_err());
+        assert!(settings.with_field(LOW_DATA_MODE_KEY, "sometimes").is_err());
+        assert!(settings.with_field(FIAT_CURRENCY_KEY, "XYZ").is_err());
+        assert!(settings.with_field(PRICE_SOURCE_KEY, "oracle").is_err());
+        assert!(settings.with_field("unknown", "1").is_err());
+    }
+
+    #[test]
+    fn test_low_data_mode_slows_wallet_updates() {
+        let settings = Settings::default();
+        assert_eq!(
+            settings.effective_wallet_view_update_interval(),
+            DEFAULT_WALLET_VIEW_UPDATE_INTERVAL
+        );
+
+        let settings = settings.with_field(LOW_DATA_MODE_KEY, "true").unwrap();
+        assert_eq!(
+            settings.effective_wallet_view_update_interval(),
+            DEFAULT_WALLET_VIEW_UPDATE_INTERVAL * LOW_DATA_MODE_POLL_INTERVAL_MULTIPLIER
+        );
+    }
+
+    #[test]
+    fn test_settings_handle_notifies_on_change() {
+        let handle = SettingsHandle::new(Settings::default());
+        let mut receiver = handle.subscribe();
+
+        handle.set(Settings::default());
+        assert!(!receiver.has_changed().unwrap());
+
+        let settings = Settings::default().with_field(THEME_KEY, "light").unwrap();
+        handle.set(settings.clone());
+        assert!(receiver.has_changed().unwrap());
+        assert_eq!(*receiver.borrow_and_update(), settings);
+        assert_eq!(handle.get(), settings);
+    }
+}
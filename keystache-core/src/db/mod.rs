@@ -0,0 +1,3902 @@
+mod model;
+mod schema;
+
+pub use model::NostrKeypairSummary;
+
+use chrono::{DateTime, NaiveDateTime};
+use diesel::connection::SimpleConnection;
+use diesel::delete;
+use diesel::{insert_into, insert_or_ignore_into, prelude::*, update};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use fedimint_core::{config::FederationId, invite_code::InviteCode, Amount};
+use lightning_invoice::Bolt11Invoice;
+use model::{
+    FederationBalanceThresholds, NewAppSetting, NewDelegation, NewExchangeRate,
+    NewFederationApiOverrides, NewFederationBalanceThresholds, NewFederationMetadata, NewNip46App,
+    NewNip46AppEventKind, NewNip46AppKindPolicy, NewNip46Rejection, NewNostrKeypair,
+    NewNostrOutboxEvent, NewNostrRelay, NewNote, NewNwcConnection, NewNwcHandledRequest,
+    NewPayment, NewPaymentBatch, NewPaymentRequest, NewPinnedGateway, NewThresholdShare,
+    NewTransaction, NewZapAllowlistEntry, NewZapReceipt, NostrKeypair, NostrRelay, Payment,
+};
+use nip_55::KeyManager;
+use nostr_sdk::secp256k1::{schnorr::Signature, Keypair, SECP256K1};
+use nostr_sdk::{
+    bip39::Mnemonic, Event, EventId, JsonUtil, Kind, PublicKey, SecretKey, ToBech32, Url,
+};
+use schema::app_settings::dsl as app_settings_dsl;
+use schema::delegations::dsl as delegations_dsl;
+use schema::exchange_rates::dsl as exchange_rates_dsl;
+use schema::federation_api_overrides::dsl as federation_api_overrides_dsl;
+use schema::federation_balance_thresholds::dsl as federation_balance_thresholds_dsl;
+use schema::federation_metadata::dsl as federation_metadata_dsl;
+use schema::nip46_app_event_kinds::dsl as nip46_app_event_kinds_dsl;
+use schema::nip46_app_kind_policies::dsl as nip46_app_kind_policies_dsl;
+use schema::nip46_apps::dsl as nip46_apps_dsl;
+use schema::nip46_rejections::dsl as nip46_rejections_dsl;
+use schema::nostr_keys::dsl as nostr_keys_dsl;
+use schema::nostr_outbox::dsl as nostr_outbox_dsl;
+use schema::nostr_relays::dsl as nostr_relays_dsl;
+use schema::notes::dsl as notes_dsl;
+use schema::nwc_connections::dsl as nwc_connections_dsl;
+use schema::nwc_handled_requests::dsl as nwc_handled_requests_dsl;
+use schema::payment_batches::dsl as payment_batches_dsl;
+use schema::payment_requests::dsl as payment_requests_dsl;
+use schema::payments::dsl as payments_dsl;
+use schema::pinned_gateways::dsl as pinned_gateways_dsl;
+use schema::threshold_shares::dsl as threshold_shares_dsl;
+use schema::transactions::dsl as transactions_dsl;
+use schema::zap_allowlist::dsl as zap_allowlist_dsl;
+use schema::zap_receipts::dsl as zap_receipts_dsl;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::backup::{BackupSettings, BackupStatus};
+use crate::config::Settings;
+use crate::delegation::{Delegation, DelegationConditions};
+use crate::exchange_rate::{ExchangeRate, FiatCurrency};
+use crate::fedimint::{
+    BalanceThresholds, FederationApiOverrides, FederationMetadata, GatewayFeeSample,
+    GatewayFeeStats, GatewayId, PaymentDirection, PaymentRecord, PaymentSimulation,
+    TransactionRecord, DEFAULT_APP_PAYMENT_CAP_SATS,
+};
+use crate::follows::{FollowedKey, ZapAllowlistEntry};
+use crate::keychain;
+use crate::lnurl::ReceivingLightningAddress;
+use crate::nostr::OutboxEvent;
+use crate::notes::NoteSubject;
+use crate::nwc::{
+    NwcBudget, NwcConnection, NwcConnectionRecord, PayInvoiceRequest, PaymentRequest,
+    PaymentRequestStatus,
+};
+use crate::policy::{
+    describe_requests, KindPolicyDecision, Nip46App, Nip46KindPolicy, Nip46Rejection,
+    Nip46RejectionReason,
+};
+use crate::privacy::InvoicePrivacy;
+use crate::relay_list::RelayListEntry;
+use crate::spend_approval::SpendApprovalPolicy;
+use crate::threshold_key::ThresholdShare;
+use crate::zap::{ZapReceipt, ZapRecord};
+
+const DATABASE_NAME: &str = "keystache.sqlite";
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
+
+const INVOICE_PRIVACY_SETTING_KEY: &str = "invoice_privacy";
+const PAYMENT_SIMULATION_SETTING_KEY: &str = "payment_simulation";
+const APP_PAYMENT_CAP_SETTING_KEY: &str = "app_payment_cap_sats";
+const SPEND_APPROVAL_POLICY_SETTING_KEY: &str = "spend_approval_policy";
+const BACKUP_DIRECTORY_SETTING_KEY: &str = "backup_directory";
+const BACKUP_ROTATION_COUNT_SETTING_KEY: &str = "backup_rotation_count";
+const BACKUP_LAST_SUCCESS_TIME_SETTING_KEY: &str = "backup_last_success_time";
+const BACKUP_LAST_ERROR_SETTING_KEY: &str = "backup_last_error";
+const DEFAULT_FEDERATION_SETTING_KEY: &str = "default_federation";
+const BUSY_TIMEOUT_SETTING_KEY: &str = "busy_timeout_secs";
+const KEYCHAIN_UNLOCK_SETTING_KEY: &str = "keychain_unlock_enabled";
+const AUTO_APPROVE_PUBLIC_KEY_READS_SETTING_KEY: &str = "auto_approve_public_key_reads";
+const LAST_RUN_VERSION_SETTING_KEY: &str = "last_run_version";
+const ARCHIVED_FEDERATIONS_SETTING_KEY: &str = "archived_federation_invite_codes";
+const WALLET_MNEMONIC_SETTING_KEY: &str = "wallet_mnemonic";
+const RECOVERY_FEDERATIONS_SETTING_KEY: &str = "recovery_federation_invite_codes";
+const LIGHTNING_ADDRESS_SETTING_KEY: &str = "lightning_address";
+const LIGHTNING_ADDRESS_FEDERATION_SETTING_KEY: &str = "lightning_address_federation";
+const LIGHTNING_ADDRESS_CLIENT_PUBLIC_KEY_SETTING_KEY: &str = "lightning_address_client_public_key";
+
+// A transaction is only given an exchange rate fetched within this long of when it was made.
+const EXCHANGE_RATE_MAX_TIME_DIFFERENCE: chrono::Duration = chrono::Duration::hours(1);
+// Rates are kept long enough for transactions to be synced after a long time away.
+const EXCHANGE_RATE_RETENTION: chrono::Duration = chrono::Duration::days(90);
+
+/// How long SQLite waits for a lock before reporting that the database is busy.
+pub const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(15);
+
+// Calls made off the UI thread that still find the database busy after the busy timeout
+// are retried a few more times, waiting twice as long before each retry.
+// See `retry_while_busy`.
+const BUSY_RETRY_COUNT: u32 = 3;
+const BUSY_RETRY_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+// How many rows `iter_*` methods load at a time.
+const ITER_BATCH_SIZE: i64 = 100;
+
+/// Returned when the database stays busy even after retrying,
+/// such as during a burst of signing requests.
+#[derive(Debug)]
+pub struct DatabaseBusyError;
+
+impl std::fmt::Display for DatabaseBusyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The database is busy. Try again in a moment.")
+    }
+}
+
+impl std::error::Error for DatabaseBusyError {}
+
+/// Returned when an existing database can't be decrypted with the key it was opened with,
+/// which usually means the password is wrong.
+#[derive(Debug)]
+pub struct IncorrectPasswordError;
+
+impl std::fmt::Display for IncorrectPasswordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The database couldn't be decrypted. The password may be incorrect."
+        )
+    }
+}
+
+impl std::error::Error for IncorrectPasswordError {}
+
+/// Returned when the system keychain can't be read to check for a device key,
+/// such as when it's locked or the user denied access. See [`keychain::get_device_key`].
+#[derive(Debug)]
+pub struct DeviceKeyUnavailableError(anyhow::Error);
+
+impl std::fmt::Display for DeviceKeyUnavailableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The device key couldn't be read from the system keychain: {}",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for DeviceKeyUnavailableError {}
+
+/// Runs `call` until the database isn't busy, waiting twice as long before each retry.
+/// Returns the [`DatabaseBusyError`] if it's still busy after the last retry. The waiting
+/// doesn't block the thread, so this is for queries made off the UI thread.
+pub async fn retry_while_busy<T>(mut call: impl FnMut() -> anyhow::Result<T>) -> anyhow::Result<T> {
+    let mut backoff = BUSY_RETRY_INITIAL_BACKOFF;
+
+    for _ in 0..BUSY_RETRY_COUNT {
+        match call() {
+            Err(err) if err.is::<DatabaseBusyError>() => {
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+            result => return result,
+        }
+    }
+
+    call()
+}
+
+/// Whether SQLCipher couldn't read the database, which is how it reports the wrong key.
+fn is_not_a_database_error(err: &diesel::result::Error) -> bool {
+    matches!(
+        err,
+        diesel::result::Error::DatabaseError(_, info)
+            if info.message().contains("file is not a database")
+    )
+}
+
+fn is_busy_error(err: &diesel::result::Error) -> bool {
+    matches!(
+        err,
+        diesel::result::Error::DatabaseError(_, info)
+            if info.message().contains("database is locked")
+                || info.message().contains("database is busy")
+    )
+}
+
+pub(crate) fn normalize_password(password: &str) -> String {
+    password.replace('\'', "''")
+}
+
+/// Gets the encryption key for the database in the app's data directory from its password.
+/// While the database is bound to this device, the password is combined with the device key.
+/// Returns a [`DeviceKeyUnavailableError`] if the keychain can't be read, rather than
+/// falling back to the password alone, which would fail like a wrong password.
+fn get_app_data_dir_encryption_key(encryption_password: &str) -> anyhow::Result<String> {
+    let device_key_or = keychain::get_device_key().map_err(DeviceKeyUnavailableError)?;
+
+    Ok(match device_key_or {
+        Some(device_key) => bind_encryption_password(encryption_password, &device_key),
+        None => encryption_password.to_string(),
+    })
+}
+
+fn bind_encryption_password(encryption_password: &str, device_key: &str) -> String {
+    format!("{encryption_password}:{device_key}")
+}
+
+/// Copies everything in the database open on `connection` to a new database
+/// at `path`, encrypted with `encryption_password`.
+fn export_attached(
+    connection: &mut SqliteConnection,
+    path: &Path,
+    encryption_password: &str,
+) -> anyhow::Result<()> {
+    connection.batch_execute(&format!(
+        "ATTACH DATABASE '{}' AS export KEY '{}';",
+        path.to_string_lossy().replace('\'', "''"),
+        normalize_password(encryption_password)
+    ))?;
+
+    let result = connection.batch_execute("SELECT sqlcipher_export('export');");
+
+    // The copy is detached even if it failed, so that the next export can attach a new one.
+    connection.batch_execute("DETACH DATABASE export;")?;
+
+    Ok(result?)
+}
+
+/// Builds a `LIKE` pattern that matches any value containing `search_query`.
+/// Wildcards in `search_query` are escaped with `\`, so they match literally.
+fn to_like_pattern(search_query: &str) -> String {
+    let escaped_query = search_query
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+
+    format!("%{escaped_query}%")
+}
+
+/// Database handle for Keystache data.
+pub struct Database {
+    // TODO: Use an async `Mutex` and make functions async.
+    connection: Mutex<SqliteConnection>,
+    path: PathBuf,
+}
+
+impl Database {
+    // TODO: Test this.
+    pub fn exists() -> anyhow::Result<bool> {
+        let project_dirs = Self::get_project_dirs()?;
+        let db_path = project_dirs.data_dir().join(DATABASE_NAME);
+        Ok(db_path.is_file())
+    }
+
+    // TODO: Test this.
+    pub fn delete() -> anyhow::Result<()> {
+        let project_dirs = Self::get_project_dirs()?;
+        let db_path = project_dirs.data_dir().join(DATABASE_NAME);
+        std::fs::remove_file(db_path)?;
+        Ok(())
+    }
+
+    /// The folder that the database is kept in.
+    pub fn app_data_dir() -> anyhow::Result<PathBuf> {
+        Ok(Self::get_project_dirs()?.data_dir().to_path_buf())
+    }
+
+    /// Creates a new database handle in the app's data directory.
+    /// If an existing database is found, it will be opened.
+    /// If the database does not exist, it will be created.
+    ///
+    /// # Arguments
+    ///
+    /// * `encryption_password` - The encryption password for the database.
+    ///                           If there is no existing database, the encryption password will be used to create a new encrypted database.
+    ///                           If there is an existing database, the encryption password will be used to unlock the database and an error will be returned if the password is incorrect.
+    /// If the database is bound to this device, the device key is read from the system keychain.
+    /// See [`keychain::get_device_key`].
+    pub fn open_or_create_in_app_data_dir(encryption_password: &str) -> anyhow::Result<Self> {
+        let project_dirs = Self::get_project_dirs()?;
+
+        Self::open_or_create(
+            project_dirs.data_dir(),
+            DATABASE_NAME,
+            &get_app_data_dir_encryption_key(encryption_password)?,
+        )
+    }
+
+    /// Like [`Self::open_or_create_in_app_data_dir`], but for a database named `file_name`
+    /// in `folder`, such as a temporary directory for tests and examples. The password is
+    /// used as is, so device binding doesn't apply.
+    pub fn open_or_create(
+        folder: &Path,
+        file_name: &str,
+        encryption_password: &str,
+    ) -> anyhow::Result<Self> {
+        // TODO: See if this comment is still true and if the statement below is still needed.
+        // The call to `ConnectionManager::new()` below doesn't
+        // create the directory if it doesn't exist, so we
+        // need to do it ourselves.
+        if !folder.try_exists()? {
+            std::fs::create_dir_all(folder)?;
+        }
+
+        let path = folder.join(file_name);
+
+        let mut connection = SqliteConnection::establish(path.to_str().unwrap_or_default())?;
+
+        let password = normalize_password(encryption_password);
+        connection.batch_execute(&format!("PRAGMA key='{password}'"))?;
+        connection.batch_execute("PRAGMA foreign_keys = ON;")?;
+        connection.batch_execute(&format!(
+            "PRAGMA busy_timeout = {};",
+            DEFAULT_BUSY_TIMEOUT.as_millis()
+        ))?;
+
+        // Check if the database encryption password is correct by running a simple query.
+        connection
+            .batch_execute("SELECT name FROM sqlite_master WHERE type='table'")
+            .map_err(|err| {
+                if is_not_a_database_error(&err) {
+                    anyhow::Error::from(IncorrectPasswordError)
+                } else {
+                    err.into()
+                }
+            })?;
+
+        connection
+            .run_pending_migrations(MIGRATIONS)
+            .map_err(|_| anyhow::anyhow!("SQLite migration failed."))?;
+
+        let db = Self {
+            connection: Mutex::new(connection),
+            path,
+        };
+
+        // TODO: Log a warning if the busy timeout fails to load.
+        if let Ok(busy_timeout) = db.get_busy_timeout() {
+            db.apply_busy_timeout(busy_timeout)?;
+        }
+
+        Ok(db)
+    }
+
+    /// Like [`Self::restore_archive`], but restores to the app's data directory. The archive's
+    /// password becomes the database's password, and fails if a database already exists there.
+    pub fn restore_archive_in_app_data_dir(
+        archive_path: &Path,
+        archive_password: &str,
+    ) -> anyhow::Result<Self> {
+        if Self::exists()? {
+            return Err(anyhow::anyhow!(
+                "A database already exists. Delete it before restoring a backup."
+            ));
+        }
+
+        let project_dirs = Self::get_project_dirs()?;
+
+        Self::restore_archive(
+            archive_path,
+            archive_password,
+            project_dirs.data_dir(),
+            DATABASE_NAME,
+            &get_app_data_dir_encryption_key(archive_password)?,
+        )
+    }
+
+    /// Creates a new database in the app's data directory for a wallet restored from `mnemonic`.
+    /// The e-cash that the seed held in the federations of `federation_invite_codes` is
+    /// recovered once the database is unlocked. Fails if a database already exists there.
+    pub fn restore_seed_in_app_data_dir(
+        encryption_password: &str,
+        mnemonic: &Mnemonic,
+        federation_invite_codes: &[InviteCode],
+    ) -> anyhow::Result<Self> {
+        if Self::exists()? {
+            return Err(anyhow::anyhow!(
+                "A database already exists. Delete it before restoring a wallet."
+            ));
+        }
+
+        let db = Self::open_or_create_in_app_data_dir(encryption_password)?;
+
+        let result = db.save_wallet_mnemonic(mnemonic).and_then(|()| {
+            db.save_invite_codes_setting(RECOVERY_FEDERATIONS_SETTING_KEY, federation_invite_codes)
+        });
+
+        // Without its seed, the database would get a new one when it's unlocked.
+        if let Err(err) = result {
+            drop(db);
+            // TODO: Log a warning if the database fails to be deleted.
+            let _ = Self::delete();
+            return Err(err);
+        }
+
+        Ok(db)
+    }
+
+    /// Copies an archive written by [`Self::export_archive`] to a new database named
+    /// `file_name` in `folder`, encrypted with `encryption_password`, and opens it.
+    /// Archives from older versions of Keystache are migrated once they're opened.
+    pub fn restore_archive(
+        archive_path: &Path,
+        archive_password: &str,
+        folder: &Path,
+        file_name: &str,
+        encryption_password: &str,
+    ) -> anyhow::Result<Self> {
+        let path = folder.join(file_name);
+
+        if path.try_exists()? {
+            return Err(anyhow::anyhow!("{} already exists.", path.display()));
+        }
+
+        // The archive is opened first, so that a wrong password fails before anything is written.
+        let mut connection =
+            SqliteConnection::establish(archive_path.to_str().unwrap_or_default())?;
+        connection.batch_execute(&format!(
+            "PRAGMA key='{}'",
+            normalize_password(archive_password)
+        ))?;
+        connection
+            .batch_execute("SELECT name FROM sqlite_master WHERE type='table'")
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "The password may be incorrect, or the file isn't a Keystache backup."
+                )
+            })?;
+
+        if !folder.try_exists()? {
+            std::fs::create_dir_all(folder)?;
+        }
+
+        if let Err(err) = export_attached(&mut connection, &path, encryption_password) {
+            // TODO: Log a warning if the partial copy fails to be removed.
+            let _ = std::fs::remove_file(&path);
+
+            return Err(err);
+        }
+
+        drop(connection);
+
+        Self::open_or_create(folder, file_name, encryption_password)
+    }
+
+    /// Runs `query` on the connection. Every query goes through here, so that a database that
+    /// stays busy for the whole busy timeout is always reported as a [`DatabaseBusyError`].
+    /// Nothing sleeps here, since most queries run on the UI thread. Callers that can wait
+    /// retry the whole call with [`retry_while_busy`] instead.
+    fn run_query<T, E: Into<anyhow::Error>>(
+        &self,
+        query: impl FnOnce(&mut SqliteConnection) -> Result<T, E>,
+    ) -> anyhow::Result<T> {
+        query(
+            &mut self
+                .connection
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner),
+        )
+        .map_err(|err| {
+            let err = err.into();
+
+            if err
+                .downcast_ref::<diesel::result::Error>()
+                .is_some_and(is_busy_error)
+            {
+                DatabaseBusyError.into()
+            } else {
+                err
+            }
+        })
+    }
+
+    /// Saves how long to wait for a lock before the database is reported as busy,
+    /// and applies it to the open connection.
+    pub fn save_busy_timeout(&self, busy_timeout: Duration) -> anyhow::Result<()> {
+        self.save_setting(
+            BUSY_TIMEOUT_SETTING_KEY,
+            &busy_timeout.as_secs().to_string(),
+        )?;
+
+        self.apply_busy_timeout(busy_timeout)
+    }
+
+    /// Gets how long to wait for a lock before the database is reported as busy.
+    /// Returns the default timeout if none has been saved.
+    pub fn get_busy_timeout(&self) -> anyhow::Result<Duration> {
+        Ok(self
+            .get_setting(BUSY_TIMEOUT_SETTING_KEY)?
+            .map(|secs| secs.parse::<u64>())
+            .transpose()?
+            .map_or(DEFAULT_BUSY_TIMEOUT, Duration::from_secs))
+    }
+
+    fn apply_busy_timeout(&self, busy_timeout: Duration) -> anyhow::Result<()> {
+        self.run_query(|connection| {
+            connection.batch_execute(&format!(
+                "PRAGMA busy_timeout = {};",
+                busy_timeout.as_millis()
+            ))
+        })
+    }
+
+    /// Moves the contents of the write-ahead log into the database file and truncates the log,
+    /// then lets SQLite refresh any query planner statistics that are out of date.
+    /// The checkpoint does nothing unless the database is in WAL mode.
+    pub fn run_maintenance(&self) -> anyhow::Result<()> {
+        self.run_query(|connection| {
+            connection.batch_execute("PRAGMA wal_checkpoint(TRUNCATE); PRAGMA optimize;")
+        })
+    }
+
+    /// Rebuilds the database file so that space left behind by deleted rows is returned
+    /// to the operating system. This can take a while for large databases,
+    /// and nothing else can use the database while it runs.
+    pub fn vacuum(&self) -> anyhow::Result<()> {
+        self.run_query(|connection| connection.batch_execute("VACUUM;"))?;
+
+        self.run_maintenance()
+    }
+
+    /// Gets the size in bytes of the database on disk,
+    /// including any write-ahead log, shared memory or rollback journal files next to it.
+    pub fn get_size_on_disk(&self) -> anyhow::Result<u64> {
+        let mut size = std::fs::metadata(&self.path)?.len();
+
+        for suffix in ["-wal", "-shm", "-journal"] {
+            let mut file_name = self.path.clone().into_os_string();
+            file_name.push(suffix);
+
+            // These files only exist while the database is in a matching journal mode.
+            if let Ok(metadata) = std::fs::metadata(file_name) {
+                size += metadata.len();
+            }
+        }
+
+        Ok(size)
+    }
+
+    /// Changes the encryption password for the database.
+    pub fn change_password(
+        &self,
+        current_encryption_password: &str,
+        new_encryption_password: &str,
+    ) -> anyhow::Result<()> {
+        // Check that the current password is correct.
+        Self::open_or_create_in_app_data_dir(current_encryption_password)?;
+
+        // Change the password.
+        self.rekey(&get_app_data_dir_encryption_key(new_encryption_password)?)
+    }
+
+    fn rekey(&self, new_encryption_key: &str) -> anyhow::Result<()> {
+        let new_encryption_key = normalize_password(new_encryption_key);
+
+        self.run_query(|connection| {
+            connection.batch_execute(&format!("PRAGMA rekey='{new_encryption_key}'"))
+        })
+    }
+
+    /// Whether the database can only be opened with the device key saved in this device's
+    /// system keychain, as well as the password. See [`keychain::get_device_key`].
+    pub fn is_bound_to_device() -> bool {
+        // TODO: Log a warning if the keychain can't be read.
+        matches!(keychain::get_device_key(), Ok(Some(_)))
+    }
+
+    /// Binds the database to this device by creating a device key
+    /// and combining it with the password in the encryption key.
+    pub fn bind_to_device(&self, encryption_password: &str) -> anyhow::Result<()> {
+        if Self::is_bound_to_device() {
+            return Err(anyhow::anyhow!("Database is already bound to this device."));
+        }
+
+        // Check that the password is correct.
+        Self::open_or_create_in_app_data_dir(encryption_password)?;
+
+        let device_key = keychain::create_device_key()?;
+
+        if let Err(err) = self.rekey(&bind_encryption_password(encryption_password, &device_key)) {
+            // TODO: Log a warning if the device key fails to be removed.
+            let _ = keychain::delete_device_key();
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// Unbinds the database from this device, so that the password alone opens it again.
+    pub fn unbind_from_device(&self, encryption_password: &str) -> anyhow::Result<()> {
+        let Some(device_key) = keychain::get_device_key()? else {
+            return Err(anyhow::anyhow!("Database isn't bound to this device."));
+        };
+
+        // Check that the password is correct.
+        Self::open_or_create_in_app_data_dir(encryption_password)?;
+
+        self.rekey(encryption_password)?;
+
+        // If the device key stayed behind, it would be combined with the password on the next
+        // unlock and the database wouldn't open. So the database is bound again instead.
+        if let Err(err) = keychain::delete_device_key() {
+            self.rekey(&bind_encryption_password(encryption_password, &device_key))?;
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// Saves a keypair to the database.
+    pub fn save_keypair(&self, keypair: &Keypair) -> anyhow::Result<()> {
+        let public_key: PublicKey = keypair.x_only_public_key().0.into();
+        let secret_key: SecretKey = keypair.secret_key().into();
+
+        let new_keypair = NewNostrKeypair {
+            display_name: None,
+            npub: public_key.to_bech32()?,
+            nsec: secret_key.to_bech32()?,
+        };
+
+        self.run_query(|connection| {
+            insert_into(schema::nostr_keys::table)
+                .values(&new_keypair)
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Gets the keypair for an npub.
+    pub fn get_keypair(&self, public_key: &str) -> anyhow::Result<Keypair> {
+        let nsec: String = self.run_query(|connection| {
+            nostr_keys_dsl::nostr_keys
+                .select(nostr_keys_dsl::nsec)
+                .filter(nostr_keys_dsl::npub.eq(public_key))
+                .first(connection)
+        })?;
+
+        Ok(Keypair::from_secret_key(
+            SECP256K1,
+            &SecretKey::from_str(&nsec)?,
+        ))
+    }
+
+    /// Gets the keypair for a public key, or `None` if it isn't saved. It's looked up by the
+    /// unique npub column, so signing doesn't slow down as more keypairs are saved.
+    pub fn get_keypair_by_npub(&self, public_key: &PublicKey) -> anyhow::Result<Option<Keypair>> {
+        let npub = public_key.to_bech32()?;
+
+        let nsec_or: Option<String> = self.run_query(|connection| {
+            nostr_keys_dsl::nostr_keys
+                .select(nostr_keys_dsl::nsec)
+                .filter(nostr_keys_dsl::npub.eq(&npub))
+                .first(connection)
+                .optional()
+        })?;
+
+        let Some(nsec) = nsec_or else {
+            return Ok(None);
+        };
+
+        Ok(Some(Keypair::from_secret_key(
+            SECP256K1,
+            &SecretKey::from_str(&nsec)?,
+        )))
+    }
+
+    /// Sets the name that a keypair is shown by in Keystache. It's only stored locally, unlike the
+    /// name in the key's profile. The name is trimmed, and an empty name removes it.
+    pub fn set_keypair_display_name(
+        &self,
+        public_key: &str,
+        display_name: &str,
+    ) -> anyhow::Result<()> {
+        let display_name = display_name.trim();
+
+        let updated_count = self.run_query(|connection| {
+            update(nostr_keys_dsl::nostr_keys.filter(nostr_keys_dsl::npub.eq(public_key)))
+                .set(
+                    nostr_keys_dsl::display_name
+                        .eq((!display_name.is_empty()).then_some(display_name)),
+                )
+                .execute(connection)
+        })?;
+
+        if updated_count == 0 {
+            anyhow::bail!("No keypair found for {public_key}");
+        }
+
+        Ok(())
+    }
+
+    /// Lists the display names of the keypairs that have one, by npub.
+    pub fn list_keypair_display_names(&self) -> anyhow::Result<HashMap<String, String>> {
+        let display_names: Vec<(String, Option<String>)> = self.run_query(|connection| {
+            nostr_keys_dsl::nostr_keys
+                .select((nostr_keys_dsl::npub, nostr_keys_dsl::display_name))
+                .filter(nostr_keys_dsl::display_name.is_not_null())
+                .load(connection)
+        })?;
+
+        Ok(display_names
+            .into_iter()
+            .filter_map(|(npub, display_name_or)| Some((npub, display_name_or?)))
+            .collect())
+    }
+
+    /// Removes a keypair from the database.
+    pub fn remove_keypair(&self, public_key: &str) -> anyhow::Result<()> {
+        self.run_query(|connection| {
+            delete(nostr_keys_dsl::nostr_keys.filter(nostr_keys_dsl::npub.eq(public_key)))
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Removes multiple keypairs from the database at once.
+    pub fn remove_keypairs(&self, public_keys: &[String]) -> anyhow::Result<()> {
+        self.run_query(|connection| {
+            delete(nostr_keys_dsl::nostr_keys.filter(nostr_keys_dsl::npub.eq_any(public_keys)))
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Lists keypairs in the database. Ordered by id in ascending order.
+    /// Use limit and offset parameters for pagination.
+    pub fn list_keypairs(&self, limit: i64, offset: i64) -> anyhow::Result<Vec<NostrKeypair>> {
+        self.run_query(|connection| {
+            nostr_keys_dsl::nostr_keys
+                .order(nostr_keys_dsl::id)
+                .limit(limit)
+                .offset(offset)
+                .load(connection)
+        })
+    }
+
+    /// Lists public keys of keypairs in the database. Ordered by id in ascending order.
+    /// Only keys whose npub or display name contains `search_query` are included.
+    /// An empty search query matches all keys.
+    /// Use limit and offset parameters for pagination.
+    pub fn list_public_keys(
+        &self,
+        search_query: &str,
+        limit: i64,
+        offset: i64,
+    ) -> anyhow::Result<Vec<String>> {
+        Ok(self
+            .search_keypairs(search_query, limit, offset)?
+            .into_iter()
+            .map(|keypair| keypair.npub)
+            .collect())
+    }
+
+    /// Same as [`Self::list_public_keys`], but lists each keypair's
+    /// display name and when it was added along with its npub.
+    pub fn search_keypairs(
+        &self,
+        search_query: &str,
+        limit: i64,
+        offset: i64,
+    ) -> anyhow::Result<Vec<NostrKeypairSummary>> {
+        let pattern = to_like_pattern(search_query);
+
+        self.run_query(|connection| {
+            nostr_keys_dsl::nostr_keys
+                .select(NostrKeypairSummary::as_select())
+                .filter(
+                    nostr_keys_dsl::npub
+                        .like(&pattern)
+                        .escape('\\')
+                        .or(nostr_keys_dsl::display_name.like(&pattern).escape('\\')),
+                )
+                .order(nostr_keys_dsl::id)
+                .limit(limit)
+                .offset(offset)
+                .load(connection)
+        })
+    }
+
+    /// Iterates over every keypair, without their secret keys, ordered by id in ascending order.
+    /// Keypairs are loaded a batch at a time. See [`iter_by_id`].
+    pub fn iter_keypairs(&self) -> impl Iterator<Item = anyhow::Result<NostrKeypairSummary>> + '_ {
+        iter_by_id(
+            |after_id| {
+                self.run_query(|connection| {
+                    nostr_keys_dsl::nostr_keys
+                        .select(NostrKeypairSummary::as_select())
+                        .filter(nostr_keys_dsl::id.gt(after_id))
+                        .order(nostr_keys_dsl::id)
+                        .limit(ITER_BATCH_SIZE)
+                        .load(connection)
+                })
+            },
+            |keypair: &NostrKeypairSummary| keypair.id,
+        )
+    }
+
+    /// Counts the keypairs in the database whose npub or display name contains `search_query`.
+    /// An empty search query matches all keys.
+    pub fn count_keypairs(&self, search_query: &str) -> anyhow::Result<i64> {
+        let pattern = to_like_pattern(search_query);
+
+        self.run_query(|connection| {
+            nostr_keys_dsl::nostr_keys
+                .filter(
+                    nostr_keys_dsl::npub
+                        .like(&pattern)
+                        .escape('\\')
+                        .or(nostr_keys_dsl::display_name.like(&pattern).escape('\\')),
+                )
+                .count()
+                .get_result(connection)
+        })
+    }
+
+    /// Saves a NIP-26 delegation issued from one of the stored keys.
+    pub fn save_delegation(
+        &self,
+        delegator_public_key: &PublicKey,
+        delegatee_public_key: &PublicKey,
+        conditions: &DelegationConditions,
+        signature: &Signature,
+    ) -> anyhow::Result<()> {
+        let new_delegation = NewDelegation {
+            delegator_npub: delegator_public_key.to_bech32()?,
+            delegatee_npub: delegatee_public_key.to_bech32()?,
+            conditions: conditions.to_query_string(),
+            signature: signature.to_string(),
+            valid_until: conditions.valid_until,
+        };
+
+        self.run_query(|connection| {
+            insert_into(schema::delegations::table)
+                .values(&new_delegation)
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Lists issued delegations, including expired and revoked ones.
+    /// Ordered by id in ascending order.
+    /// Use limit and offset parameters for pagination.
+    pub fn list_delegations(&self, limit: i64, offset: i64) -> anyhow::Result<Vec<Delegation>> {
+        let delegations: Vec<model::Delegation> = self.run_query(|connection| {
+            delegations_dsl::delegations
+                .order(delegations_dsl::id)
+                .limit(limit)
+                .offset(offset)
+                .load(connection)
+        })?;
+
+        delegations.into_iter().map(TryInto::try_into).collect()
+    }
+
+    /// Marks a delegation as revoked at `revoke_time`.
+    pub fn revoke_delegation(&self, id: i32, revoke_time: NaiveDateTime) -> anyhow::Result<()> {
+        self.run_query(|connection| {
+            update(delegations_dsl::delegations.filter(delegations_dsl::id.eq(id)))
+                .set(delegations_dsl::revoke_time.eq(Some(revoke_time)))
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Registers an app that signs in over NIP-46, if it isn't registered already.
+    pub fn register_nip46_app(&self, public_key: &PublicKey) -> anyhow::Result<()> {
+        let new_app = NewNip46App {
+            npub: public_key.to_bech32()?,
+        };
+
+        self.run_query(|connection| {
+            insert_or_ignore_into(schema::nip46_apps::table)
+                .values(&new_app)
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Gets a registered NIP-46 app, or `None` if the app isn't registered.
+    pub fn get_nip46_app(&self, public_key: &PublicKey) -> anyhow::Result<Option<Nip46App>> {
+        let npub = public_key.to_bech32()?;
+
+        self.run_query(|connection| {
+            nip46_apps_dsl::nip46_apps
+                .select((
+                    nip46_apps_dsl::npub,
+                    nip46_apps_dsl::auto_approve_public_key_reads,
+                    nip46_apps_dsl::create_time,
+                    nip46_apps_dsl::identity_npub,
+                    nip46_apps_dsl::remember_conversations,
+                ))
+                .filter(nip46_apps_dsl::npub.eq(&npub))
+                .first::<(String, bool, NaiveDateTime, Option<String>, bool)>(connection)
+                .optional()
+        })?
+        .map(Nip46App::try_from)
+        .transpose()
+    }
+
+    /// Lists registered NIP-46 apps, oldest first.
+    pub fn list_nip46_apps(&self) -> anyhow::Result<Vec<Nip46App>> {
+        let apps: Vec<(String, bool, NaiveDateTime, Option<String>, bool)> =
+            self.run_query(|connection| {
+                nip46_apps_dsl::nip46_apps
+                    .select((
+                        nip46_apps_dsl::npub,
+                        nip46_apps_dsl::auto_approve_public_key_reads,
+                        nip46_apps_dsl::create_time,
+                        nip46_apps_dsl::identity_npub,
+                        nip46_apps_dsl::remember_conversations,
+                    ))
+                    .order(nip46_apps_dsl::create_time)
+                    .load(connection)
+            })?;
+
+        apps.into_iter().map(Nip46App::try_from).collect()
+    }
+
+    /// Saves whether `get_public_key` requests from a registered app are approved without prompting.
+    pub fn save_nip46_app_auto_approve_public_key_reads(
+        &self,
+        public_key: &PublicKey,
+        is_enabled: bool,
+    ) -> anyhow::Result<()> {
+        let npub = public_key.to_bech32()?;
+
+        self.run_query(|connection| {
+            update(nip46_apps_dsl::nip46_apps.filter(nip46_apps_dsl::npub.eq(&npub)))
+                .set(nip46_apps_dsl::auto_approve_public_key_reads.eq(is_enabled))
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Saves whether encryption requests from a registered app with peers that were already
+    /// approved are approved without prompting. See [`Nip46App::remember_conversations`].
+    pub fn save_nip46_app_remember_conversations(
+        &self,
+        public_key: &PublicKey,
+        is_enabled: bool,
+    ) -> anyhow::Result<()> {
+        let npub = public_key.to_bech32()?;
+
+        self.run_query(|connection| {
+            update(nip46_apps_dsl::nip46_apps.filter(nip46_apps_dsl::npub.eq(&npub)))
+                .set(nip46_apps_dsl::remember_conversations.eq(is_enabled))
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Saves which keypair an app signs as, or lets it sign as any keypair if `identity_or`
+    /// is `None`. The app is registered first if it isn't already, so that an identity can
+    /// be chosen while approving its first request. See [`Nip46App::identity_or`].
+    pub fn save_nip46_app_identity(
+        &self,
+        public_key: &PublicKey,
+        identity_or: Option<&PublicKey>,
+    ) -> anyhow::Result<()> {
+        let npub = public_key.to_bech32()?;
+        let identity_npub_or = identity_or.map(ToBech32::to_bech32).transpose()?;
+
+        self.run_query(|connection| {
+            insert_or_ignore_into(schema::nip46_apps::table)
+                .values(&NewNip46App { npub: npub.clone() })
+                .execute(connection)?;
+
+            update(nip46_apps_dsl::nip46_apps.filter(nip46_apps_dsl::npub.eq(&npub)))
+                .set(nip46_apps_dsl::identity_npub.eq(&identity_npub_or))
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Forgets a registered NIP-46 app, along with its settings, signing history, and rejections.
+    /// The app is registered again the next time one of its requests is approved.
+    pub fn remove_nip46_app(&self, public_key: &PublicKey) -> anyhow::Result<()> {
+        let npub = public_key.to_bech32()?;
+
+        self.run_query(|connection| {
+            delete(nip46_apps_dsl::nip46_apps.filter(nip46_apps_dsl::npub.eq(&npub)))
+                .execute(connection)?;
+            delete(
+                nip46_app_event_kinds_dsl::nip46_app_event_kinds
+                    .filter(nip46_app_event_kinds_dsl::npub.eq(&npub)),
+            )
+            .execute(connection)?;
+            delete(
+                nip46_rejections_dsl::nip46_rejections.filter(nip46_rejections_dsl::npub.eq(&npub)),
+            )
+            .execute(connection)?;
+            delete(
+                nip46_app_kind_policies_dsl::nip46_app_kind_policies
+                    .filter(nip46_app_kind_policies_dsl::npub.eq(&npub)),
+            )
+            .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Records that a batch of NIP-46 requests from an app wasn't signed, and why.
+    pub fn record_nip46_rejection(
+        &self,
+        public_key: &PublicKey,
+        reason: Nip46RejectionReason,
+        requests: &[nostr_sdk::nips::nip46::Request],
+    ) -> anyhow::Result<()> {
+        let new_nip46_rejection = NewNip46Rejection {
+            npub: public_key.to_bech32()?,
+            reason: reason.as_str().to_string(),
+            requests_description: describe_requests(requests),
+        };
+
+        self.run_query(|connection| -> QueryResult<_> {
+            insert_into(schema::nip46_rejections::table)
+                .values(&new_nip46_rejection)
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Lists the most recent NIP-46 rejections from every app, newest first.
+    pub fn list_nip46_rejections(&self, limit: i64) -> anyhow::Result<Vec<Nip46Rejection>> {
+        let rejections: Vec<(String, String, String, NaiveDateTime)> =
+            self.run_query(|connection| {
+                nip46_rejections_dsl::nip46_rejections
+                    .select((
+                        nip46_rejections_dsl::npub,
+                        nip46_rejections_dsl::reason,
+                        nip46_rejections_dsl::requests_description,
+                        nip46_rejections_dsl::create_time,
+                    ))
+                    .order(nip46_rejections_dsl::id.desc())
+                    .limit(limit)
+                    .load(connection)
+            })?;
+
+        rejections
+            .into_iter()
+            .map(Nip46Rejection::try_from)
+            .collect()
+    }
+
+    /// Records that an app signed events of `kinds` over NIP-46.
+    /// A kind that's listed more than once is counted once per listing.
+    pub fn record_nip46_app_signed_event_kinds(
+        &self,
+        public_key: &PublicKey,
+        kinds: &[Kind],
+    ) -> anyhow::Result<()> {
+        let npub = public_key.to_bech32()?;
+        let now = chrono::Utc::now().naive_utc();
+
+        // The kinds are counted together, so that retrying while the database
+        // is busy never counts some of them twice.
+        self.run_query(|connection| {
+            connection.transaction::<_, diesel::result::Error, _>(|connection| {
+                for kind in kinds {
+                    insert_into(schema::nip46_app_event_kinds::table)
+                        .values(&NewNip46AppEventKind {
+                            npub: npub.clone(),
+                            kind: i32::from(kind.as_u16()),
+                            sign_count: 1,
+                        })
+                        .on_conflict((
+                            nip46_app_event_kinds_dsl::npub,
+                            nip46_app_event_kinds_dsl::kind,
+                        ))
+                        .do_update()
+                        .set((
+                            nip46_app_event_kinds_dsl::sign_count
+                                .eq(nip46_app_event_kinds_dsl::sign_count + 1),
+                            nip46_app_event_kinds_dsl::last_sign_time.eq(now),
+                        ))
+                        .execute(connection)?;
+                }
+
+                Ok(())
+            })
+        })
+    }
+
+    /// Gets how many events of each kind an app has signed over NIP-46.
+    pub fn get_nip46_app_sign_counts_by_kind(
+        &self,
+        public_key: &PublicKey,
+    ) -> anyhow::Result<BTreeMap<Kind, u64>> {
+        let npub = public_key.to_bech32()?;
+
+        let rows: Vec<(i32, i64)> = self.run_query(|connection| {
+            nip46_app_event_kinds_dsl::nip46_app_event_kinds
+                .select((
+                    nip46_app_event_kinds_dsl::kind,
+                    nip46_app_event_kinds_dsl::sign_count,
+                ))
+                .filter(nip46_app_event_kinds_dsl::npub.eq(&npub))
+                .load(connection)
+        })?;
+
+        rows.into_iter()
+            .map(|(kind, sign_count)| {
+                Ok((Kind::from(u16::try_from(kind)?), u64::try_from(sign_count)?))
+            })
+            .collect()
+    }
+
+    /// Saves how an app's requests to sign events of `kind` are answered,
+    /// replacing any decision saved before. See [`crate::policy::apply_kind_policies`].
+    pub fn save_nip46_kind_policy(
+        &self,
+        public_key: &PublicKey,
+        kind: Kind,
+        decision: KindPolicyDecision,
+    ) -> anyhow::Result<()> {
+        let new_kind_policy = NewNip46AppKindPolicy {
+            npub: public_key.to_bech32()?,
+            kind: i32::from(kind.as_u16()),
+            decision: decision.as_str().to_string(),
+        };
+
+        self.run_query(|connection| {
+            insert_into(schema::nip46_app_kind_policies::table)
+                .values(&new_kind_policy)
+                .on_conflict((
+                    nip46_app_kind_policies_dsl::npub,
+                    nip46_app_kind_policies_dsl::kind,
+                ))
+                .do_update()
+                .set(nip46_app_kind_policies_dsl::decision.eq(decision.as_str()))
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Revokes an app's kind policy, so that its requests to sign events of `kind` prompt again.
+    pub fn remove_nip46_kind_policy(
+        &self,
+        public_key: &PublicKey,
+        kind: Kind,
+    ) -> anyhow::Result<()> {
+        let npub = public_key.to_bech32()?;
+
+        self.run_query(|connection| {
+            delete(
+                nip46_app_kind_policies_dsl::nip46_app_kind_policies
+                    .filter(nip46_app_kind_policies_dsl::npub.eq(&npub))
+                    .filter(nip46_app_kind_policies_dsl::kind.eq(i32::from(kind.as_u16()))),
+            )
+            .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Gets an app's kind policies, keyed by event kind.
+    pub fn get_nip46_kind_policies(
+        &self,
+        public_key: &PublicKey,
+    ) -> anyhow::Result<BTreeMap<Kind, KindPolicyDecision>> {
+        let npub = public_key.to_bech32()?;
+
+        let rows: Vec<(i32, String)> = self.run_query(|connection| {
+            nip46_app_kind_policies_dsl::nip46_app_kind_policies
+                .select((
+                    nip46_app_kind_policies_dsl::kind,
+                    nip46_app_kind_policies_dsl::decision,
+                ))
+                .filter(nip46_app_kind_policies_dsl::npub.eq(&npub))
+                .load(connection)
+        })?;
+
+        rows.into_iter()
+            .map(|(kind, decision)| Ok((Kind::from(u16::try_from(kind)?), decision.parse()?)))
+            .collect()
+    }
+
+    /// Lists every app's kind policies, ordered by app and then by kind.
+    pub fn list_nip46_kind_policies(&self) -> anyhow::Result<Vec<Nip46KindPolicy>> {
+        let rows: Vec<(String, i32, String, NaiveDateTime)> = self.run_query(|connection| {
+            nip46_app_kind_policies_dsl::nip46_app_kind_policies
+                .select((
+                    nip46_app_kind_policies_dsl::npub,
+                    nip46_app_kind_policies_dsl::kind,
+                    nip46_app_kind_policies_dsl::decision,
+                    nip46_app_kind_policies_dsl::create_time,
+                ))
+                .order((
+                    nip46_app_kind_policies_dsl::npub,
+                    nip46_app_kind_policies_dsl::kind,
+                ))
+                .load(connection)
+        })?;
+
+        rows.into_iter().map(TryInto::try_into).collect()
+    }
+
+    /// Saves a nostr relay to the database.
+    pub fn save_relay(&self, websocket_url: String) -> anyhow::Result<()> {
+        self.run_query(|connection| -> QueryResult<_> {
+            insert_into(schema::nostr_relays::table)
+                .values(&NewNostrRelay {
+                    websocket_url,
+                    read: true,
+                    write: true,
+                })
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Saves the relays from a NIP-65 relay list. Relays that are already saved keep
+    /// their other settings, but take on the list's read and write markers.
+    pub fn import_relays(&self, entries: &[RelayListEntry]) -> anyhow::Result<()> {
+        self.run_query(|connection| {
+            for entry in entries {
+                insert_into(schema::nostr_relays::table)
+                    .values(&NewNostrRelay {
+                        websocket_url: entry.websocket_url.clone(),
+                        read: entry.read,
+                        write: entry.write,
+                    })
+                    .on_conflict(nostr_relays_dsl::websocket_url)
+                    .do_update()
+                    .set((
+                        nostr_relays_dsl::read.eq(entry.read),
+                        nostr_relays_dsl::write.eq(entry.write),
+                    ))
+                    .execute(connection)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Removes a nostr relay from the database.
+    pub fn remove_relay(&self, websocket_url: &str) -> anyhow::Result<()> {
+        self.run_query(|connection| {
+            delete(
+                nostr_relays_dsl::nostr_relays
+                    .filter(nostr_relays_dsl::websocket_url.eq(websocket_url)),
+            )
+            .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Saves whether a nostr relay is reconnected to after its connection drops.
+    pub fn save_relay_auto_reconnect(
+        &self,
+        websocket_url: &str,
+        auto_reconnect: bool,
+    ) -> anyhow::Result<()> {
+        self.run_query(|connection| {
+            update(
+                nostr_relays_dsl::nostr_relays
+                    .filter(nostr_relays_dsl::websocket_url.eq(websocket_url)),
+            )
+            .set(nostr_relays_dsl::auto_reconnect.eq(auto_reconnect))
+            .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Saves whether the user reads from and writes to a nostr relay.
+    /// See [`Self::import_relays`].
+    pub fn save_relay_usage(
+        &self,
+        websocket_url: &str,
+        read: bool,
+        write: bool,
+    ) -> anyhow::Result<()> {
+        self.run_query(|connection| {
+            update(
+                nostr_relays_dsl::nostr_relays
+                    .filter(nostr_relays_dsl::websocket_url.eq(websocket_url)),
+            )
+            .set((
+                nostr_relays_dsl::read.eq(read),
+                nostr_relays_dsl::write.eq(write),
+            ))
+            .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Removes multiple nostr relays from the database at once.
+    pub fn remove_relays(&self, websocket_urls: &[String]) -> anyhow::Result<()> {
+        self.run_query(|connection| {
+            delete(
+                nostr_relays_dsl::nostr_relays
+                    .filter(nostr_relays_dsl::websocket_url.eq_any(websocket_urls)),
+            )
+            .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Lists relays in the database. Ordered by id in ascending order.
+    /// Use limit and offset parameters for pagination.
+    pub fn list_relays(&self, limit: i64, offset: i64) -> anyhow::Result<Vec<NostrRelay>> {
+        self.run_query(|connection| {
+            nostr_relays_dsl::nostr_relays
+                .order(nostr_relays_dsl::id)
+                .limit(limit)
+                .offset(offset)
+                .load(connection)
+        })
+    }
+
+    /// Iterates over every relay, ordered by id in ascending order.
+    /// Relays are loaded a batch at a time. See [`iter_by_id`].
+    pub fn iter_relays(&self) -> impl Iterator<Item = anyhow::Result<NostrRelay>> + '_ {
+        iter_by_id(
+            |after_id| {
+                self.run_query(|connection| {
+                    nostr_relays_dsl::nostr_relays
+                        .filter(nostr_relays_dsl::id.gt(after_id))
+                        .order(nostr_relays_dsl::id)
+                        .limit(ITER_BATCH_SIZE)
+                        .load(connection)
+                })
+            },
+            |relay: &NostrRelay| relay.id,
+        )
+    }
+
+    /// Lists relays whose websocket URL contains `search_query`. Ordered by id in ascending order.
+    /// An empty search query matches all relays.
+    /// Use limit and offset parameters for pagination.
+    pub fn search_relays(
+        &self,
+        search_query: &str,
+        limit: i64,
+        offset: i64,
+    ) -> anyhow::Result<Vec<NostrRelay>> {
+        let pattern = to_like_pattern(search_query);
+
+        self.run_query(|connection| {
+            nostr_relays_dsl::nostr_relays
+                .filter(nostr_relays_dsl::websocket_url.like(&pattern).escape('\\'))
+                .order(nostr_relays_dsl::id)
+                .limit(limit)
+                .offset(offset)
+                .load(connection)
+        })
+    }
+
+    /// Counts the relays in the database whose websocket URL contains `search_query`.
+    /// An empty search query matches all relays.
+    pub fn count_relays(&self, search_query: &str) -> anyhow::Result<i64> {
+        let pattern = to_like_pattern(search_query);
+
+        self.run_query(|connection| -> QueryResult<_> {
+            nostr_relays_dsl::nostr_relays
+                .filter(nostr_relays_dsl::websocket_url.like(&pattern).escape('\\'))
+                .count()
+                .get_result(connection)
+        })
+    }
+
+    /// Queues `event` to be published once it can be sent, first trying at `next_attempt_time`.
+    /// Keeps at most `capacity` events, dropping the oldest ones first. Queuing an event that's
+    /// already queued does nothing.
+    pub fn save_outbox_event(
+        &self,
+        event: &Event,
+        next_attempt_time: NaiveDateTime,
+        capacity: usize,
+    ) -> anyhow::Result<()> {
+        let new_outbox_event = NewNostrOutboxEvent {
+            event_id: event.id.to_hex(),
+            event_json: event.as_json(),
+            attempt_count: 0,
+            next_attempt_time,
+        };
+
+        let kept_offset = i64::try_from(capacity.saturating_sub(1))?;
+
+        self.run_query(|connection| {
+            insert_or_ignore_into(schema::nostr_outbox::table)
+                .values(&new_outbox_event)
+                .execute(connection)?;
+
+            let oldest_kept_id_or: Option<i32> = nostr_outbox_dsl::nostr_outbox
+                .select(nostr_outbox_dsl::id)
+                .order(nostr_outbox_dsl::id.desc())
+                .offset(kept_offset)
+                .first(connection)
+                .optional()?;
+
+            if let Some(oldest_kept_id) = oldest_kept_id_or {
+                delete(
+                    nostr_outbox_dsl::nostr_outbox.filter(nostr_outbox_dsl::id.lt(oldest_kept_id)),
+                )
+                .execute(connection)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Lists the events waiting to be published, oldest first.
+    pub fn list_outbox_events(&self) -> anyhow::Result<Vec<OutboxEvent>> {
+        let outbox_events: Vec<model::NostrOutboxEvent> = self.run_query(|connection| {
+            nostr_outbox_dsl::nostr_outbox
+                .order(nostr_outbox_dsl::id)
+                .load(connection)
+        })?;
+
+        outbox_events
+            .into_iter()
+            .map(|outbox_event| {
+                Ok(OutboxEvent {
+                    event: Event::from_json(&outbox_event.event_json)?,
+                    attempt_count: u32::try_from(outbox_event.attempt_count)?,
+                    next_attempt_time: outbox_event.next_attempt_time,
+                    create_time: outbox_event.create_time,
+                })
+            })
+            .collect()
+    }
+
+    /// Counts the events waiting to be published.
+    pub fn count_outbox_events(&self) -> anyhow::Result<i64> {
+        self.run_query(|connection| {
+            nostr_outbox_dsl::nostr_outbox
+                .count()
+                .get_result(connection)
+        })
+    }
+
+    /// Records a failed attempt to publish a queued event, to be tried again at `next_attempt_time`.
+    pub fn record_outbox_attempt(
+        &self,
+        event_id: &EventId,
+        next_attempt_time: NaiveDateTime,
+    ) -> anyhow::Result<()> {
+        self.run_query(|connection| {
+            update(
+                nostr_outbox_dsl::nostr_outbox
+                    .filter(nostr_outbox_dsl::event_id.eq(event_id.to_hex())),
+            )
+            .set((
+                nostr_outbox_dsl::attempt_count.eq(nostr_outbox_dsl::attempt_count + 1),
+                nostr_outbox_dsl::next_attempt_time.eq(next_attempt_time),
+            ))
+            .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Removes an event from the outbox, once it has been published or given up on.
+    pub fn remove_outbox_event(&self, event_id: &EventId) -> anyhow::Result<()> {
+        self.run_query(|connection| {
+            delete(
+                nostr_outbox_dsl::nostr_outbox
+                    .filter(nostr_outbox_dsl::event_id.eq(event_id.to_hex())),
+            )
+            .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Saves the balance alert thresholds and maximum balance for a federation,
+    /// replacing any thresholds previously saved for it.
+    pub fn save_federation_balance_thresholds(
+        &self,
+        federation_id: &FederationId,
+        thresholds: BalanceThresholds,
+    ) -> anyhow::Result<()> {
+        if !thresholds.is_valid() {
+            anyhow::bail!("The low balance alert can't be above the high balance alert");
+        }
+
+        let new_thresholds = NewFederationBalanceThresholds {
+            federation_id: federation_id.to_string(),
+            low_balance_msats: thresholds
+                .low_or
+                .map(|amount| i64::try_from(amount.msats))
+                .transpose()?,
+            high_balance_msats: thresholds
+                .high_or
+                .map(|amount| i64::try_from(amount.msats))
+                .transpose()?,
+            max_balance_msats: thresholds
+                .max_or
+                .map(|amount| i64::try_from(amount.msats))
+                .transpose()?,
+        };
+
+        self.run_query(|connection| {
+            insert_into(schema::federation_balance_thresholds::table)
+                .values(&new_thresholds)
+                .on_conflict(federation_balance_thresholds_dsl::federation_id)
+                .do_update()
+                .set(&new_thresholds)
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Gets the balance alert thresholds and maximum balance for a federation.
+    /// Returns empty thresholds if none have been saved.
+    pub fn get_federation_balance_thresholds(
+        &self,
+        federation_id: &FederationId,
+    ) -> anyhow::Result<BalanceThresholds> {
+        let thresholds_or: Option<FederationBalanceThresholds> = self.run_query(|connection| {
+            federation_balance_thresholds_dsl::federation_balance_thresholds
+                .filter(
+                    federation_balance_thresholds_dsl::federation_id.eq(federation_id.to_string()),
+                )
+                .first(connection)
+                .optional()
+        })?;
+
+        Ok(thresholds_or
+            .map(|thresholds| thresholds.to_balance_thresholds())
+            .unwrap_or_default())
+    }
+
+    /// Lists the balance alert thresholds of all federations that have any.
+    pub fn list_federation_balance_thresholds(
+        &self,
+    ) -> anyhow::Result<BTreeMap<FederationId, BalanceThresholds>> {
+        let thresholds: Vec<FederationBalanceThresholds> = self.run_query(|connection| {
+            federation_balance_thresholds_dsl::federation_balance_thresholds.load(connection)
+        })?;
+
+        Ok(thresholds
+            .into_iter()
+            .filter_map(|thresholds| {
+                let federation_id = thresholds.federation_id.parse().ok()?;
+                Some((federation_id, thresholds.to_balance_thresholds()))
+            })
+            .collect())
+    }
+
+    /// Saves the federation used by default for receiving, such as when
+    /// an app requests an invoice. `None` clears the default federation.
+    pub fn save_default_federation(
+        &self,
+        federation_id_or: Option<&FederationId>,
+    ) -> anyhow::Result<()> {
+        self.save_setting(
+            DEFAULT_FEDERATION_SETTING_KEY,
+            &federation_id_or
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Gets the federation used by default for receiving, if one is set.
+    pub fn get_default_federation(&self) -> anyhow::Result<Option<FederationId>> {
+        Ok(self
+            .get_setting(DEFAULT_FEDERATION_SETTING_KEY)?
+            .filter(|federation_id| !federation_id.is_empty())
+            .map(|federation_id| federation_id.parse())
+            .transpose()?)
+    }
+
+    /// Saves the lightning address that the user receives to, replacing any existing one.
+    pub fn save_receiving_lightning_address(
+        &self,
+        lightning_address: &ReceivingLightningAddress,
+    ) -> anyhow::Result<()> {
+        self.save_setting(
+            LIGHTNING_ADDRESS_FEDERATION_SETTING_KEY,
+            &lightning_address.federation_id.to_string(),
+        )?;
+        self.save_setting(
+            LIGHTNING_ADDRESS_CLIENT_PUBLIC_KEY_SETTING_KEY,
+            &lightning_address.client_public_key.to_hex(),
+        )?;
+
+        // Saved last, since the other settings are ignored without it.
+        self.save_setting(LIGHTNING_ADDRESS_SETTING_KEY, &lightning_address.address)
+    }
+
+    /// Gets the lightning address that the user receives to, if one is set.
+    pub fn get_receiving_lightning_address(
+        &self,
+    ) -> anyhow::Result<Option<ReceivingLightningAddress>> {
+        let Some(address) = self.get_setting(LIGHTNING_ADDRESS_SETTING_KEY)? else {
+            return Ok(None);
+        };
+
+        let federation_id = self
+            .get_setting(LIGHTNING_ADDRESS_FEDERATION_SETTING_KEY)?
+            .ok_or_else(|| anyhow::anyhow!("The lightning address has no federation."))?
+            .parse()?;
+
+        let client_public_key = PublicKey::from_hex(
+            &self
+                .get_setting(LIGHTNING_ADDRESS_CLIENT_PUBLIC_KEY_SETTING_KEY)?
+                .ok_or_else(|| anyhow::anyhow!("The lightning address has no connection."))?,
+        )?;
+
+        Ok(Some(ReceivingLightningAddress {
+            address,
+            federation_id,
+            client_public_key,
+        }))
+    }
+
+    /// Forgets the lightning address that the user receives to.
+    /// Its Wallet Connect connection has to be revoked separately.
+    pub fn remove_receiving_lightning_address(&self) -> anyhow::Result<()> {
+        self.remove_setting(LIGHTNING_ADDRESS_SETTING_KEY)?;
+        self.remove_setting(LIGHTNING_ADDRESS_FEDERATION_SETTING_KEY)?;
+        self.remove_setting(LIGHTNING_ADDRESS_CLIENT_PUBLIC_KEY_SETTING_KEY)
+    }
+
+    /// Saves the user's note about `subject`, replacing any existing note.
+    /// Saving an empty note deletes it.
+    pub fn save_note(&self, subject: NoteSubject, body: &str) -> anyhow::Result<()> {
+        if body.trim().is_empty() {
+            self.run_query(|connection| {
+                delete(notes_dsl::notes.filter(notes_dsl::subject.eq(subject.to_string())))
+                    .execute(connection)
+            })?;
+
+            return Ok(());
+        }
+
+        self.run_query(|connection| {
+            insert_into(schema::notes::table)
+                .values(&NewNote {
+                    subject: subject.to_string(),
+                    body: body.to_string(),
+                })
+                .on_conflict(notes_dsl::subject)
+                .do_update()
+                .set((
+                    notes_dsl::body.eq(body),
+                    notes_dsl::update_time.eq(chrono::Utc::now().naive_utc()),
+                ))
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Gets the user's note about `subject`. Empty if there's no note.
+    pub fn get_note(&self, subject: NoteSubject) -> anyhow::Result<String> {
+        Ok(self
+            .run_query(|connection| {
+                notes_dsl::notes
+                    .select(notes_dsl::body)
+                    .filter(notes_dsl::subject.eq(subject.to_string()))
+                    .first(connection)
+                    .optional()
+            })?
+            .unwrap_or_default())
+    }
+
+    /// Pins the gateway used for lightning payments with a federation,
+    /// or unpins it if `gateway_id_or` is `None`.
+    pub fn save_pinned_gateway(
+        &self,
+        federation_id: &FederationId,
+        gateway_id_or: Option<&GatewayId>,
+    ) -> anyhow::Result<()> {
+        self.run_query(|connection| match gateway_id_or {
+            Some(gateway_id) => insert_into(schema::pinned_gateways::table)
+                .values(&NewPinnedGateway {
+                    federation_id: federation_id.to_string(),
+                    gateway_id: gateway_id.to_string(),
+                })
+                .on_conflict(pinned_gateways_dsl::federation_id)
+                .do_update()
+                .set(pinned_gateways_dsl::gateway_id.eq(gateway_id.to_string()))
+                .execute(connection),
+            None => delete(
+                pinned_gateways_dsl::pinned_gateways
+                    .filter(pinned_gateways_dsl::federation_id.eq(federation_id.to_string())),
+            )
+            .execute(connection),
+        })?;
+
+        Ok(())
+    }
+
+    /// Lists the pinned gateway of every federation that has one.
+    pub fn list_pinned_gateways(&self) -> anyhow::Result<BTreeMap<FederationId, GatewayId>> {
+        let pinned_gateways: Vec<(String, String)> = self.run_query(|connection| {
+            pinned_gateways_dsl::pinned_gateways
+                .select((
+                    pinned_gateways_dsl::federation_id,
+                    pinned_gateways_dsl::gateway_id,
+                ))
+                .load(connection)
+        })?;
+
+        pinned_gateways
+            .into_iter()
+            .map(|(federation_id, gateway_id)| Ok((federation_id.parse()?, gateway_id.parse()?)))
+            .collect()
+    }
+
+    /// Saves how a federation's guardians are reached, replacing any earlier overrides.
+    /// Empty overrides are deleted, so that the federation's config is used again.
+    pub fn save_federation_api_overrides(
+        &self,
+        federation_id: &FederationId,
+        api_overrides: &FederationApiOverrides,
+    ) -> anyhow::Result<()> {
+        if api_overrides.is_empty() {
+            self.run_query(|connection| {
+                delete(
+                    federation_api_overrides_dsl::federation_api_overrides.filter(
+                        federation_api_overrides_dsl::federation_id.eq(federation_id.to_string()),
+                    ),
+                )
+                .execute(connection)
+            })?;
+
+            return Ok(());
+        }
+
+        let new_api_overrides = NewFederationApiOverrides {
+            federation_id: federation_id.to_string(),
+            api_secret: api_overrides.api_secret_or.clone(),
+        };
+
+        self.run_query(|connection| {
+            insert_into(schema::federation_api_overrides::table)
+                .values(&new_api_overrides)
+                .on_conflict(federation_api_overrides_dsl::federation_id)
+                .do_update()
+                .set(&new_api_overrides)
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Lists the API overrides of every federation that has any.
+    pub fn list_federation_api_overrides(
+        &self,
+    ) -> anyhow::Result<BTreeMap<FederationId, FederationApiOverrides>> {
+        let api_overrides: Vec<(String, Option<String>)> = self.run_query(|connection| {
+            federation_api_overrides_dsl::federation_api_overrides
+                .select((
+                    federation_api_overrides_dsl::federation_id,
+                    federation_api_overrides_dsl::api_secret,
+                ))
+                .load(connection)
+        })?;
+
+        api_overrides
+            .into_iter()
+            .map(|(federation_id, api_secret_or)| {
+                Ok((
+                    federation_id.parse()?,
+                    FederationApiOverrides { api_secret_or },
+                ))
+            })
+            .collect()
+    }
+
+    /// Caches a federation's metadata, replacing what was cached before,
+    /// so that the federation can be listed before its client connects.
+    pub fn save_federation_metadata(
+        &self,
+        federation_id: &FederationId,
+        metadata: &FederationMetadata,
+    ) -> anyhow::Result<()> {
+        let new_metadata = NewFederationMetadata {
+            federation_id: federation_id.to_string(),
+            name: metadata.name_or.clone(),
+            module_kinds: serde_json::to_string(&metadata.module_kinds)?,
+            guardian_names: serde_json::to_string(&metadata.guardian_names)?,
+            icon_url: metadata.icon_url_or.clone(),
+        };
+
+        self.run_query(|connection| {
+            insert_into(schema::federation_metadata::table)
+                .values(&new_metadata)
+                .on_conflict(federation_metadata_dsl::federation_id)
+                .do_update()
+                .set(&new_metadata)
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Lists the cached metadata of every federation that has any.
+    pub fn list_federation_metadata(
+        &self,
+    ) -> anyhow::Result<BTreeMap<FederationId, FederationMetadata>> {
+        let metadata: Vec<(String, Option<String>, String, String, Option<String>)> = self
+            .run_query(|connection| {
+                federation_metadata_dsl::federation_metadata
+                    .select((
+                        federation_metadata_dsl::federation_id,
+                        federation_metadata_dsl::name,
+                        federation_metadata_dsl::module_kinds,
+                        federation_metadata_dsl::guardian_names,
+                        federation_metadata_dsl::icon_url,
+                    ))
+                    .load(connection)
+            })?;
+
+        metadata
+            .into_iter()
+            .map(
+                |(federation_id, name_or, module_kinds, guardian_names, icon_url_or)| {
+                    Ok((
+                        federation_id.parse()?,
+                        FederationMetadata {
+                            name_or,
+                            module_kinds: serde_json::from_str(&module_kinds)?,
+                            guardian_names: serde_json::from_str(&guardian_names)?,
+                            icon_url_or,
+                        },
+                    ))
+                },
+            )
+            .collect()
+    }
+
+    /// Removes a federation's cached metadata, such as after leaving it.
+    pub fn remove_federation_metadata(&self, federation_id: &FederationId) -> anyhow::Result<()> {
+        self.run_query(|connection| {
+            delete(
+                federation_metadata_dsl::federation_metadata
+                    .filter(federation_metadata_dsl::federation_id.eq(federation_id.to_string())),
+            )
+            .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Saves a completed lightning payment to the payment log.
+    /// Simulated payments are flagged so they can be told apart from real ones.
+    /// The preimage of outgoing payments is kept as proof of payment.
+    /// Payments requested by an app over Wallet Connect are attributed to that app,
+    /// and outgoing payments to the gateway they went through. Payments made together
+    /// are grouped under a batch created with [`Self::create_payment_batch`].
+    // TODO: Group these parameters into a struct.
+    #[allow(clippy::too_many_arguments)]
+    pub fn save_payment(
+        &self,
+        federation_id: &FederationId,
+        direction: PaymentDirection,
+        amount: Amount,
+        fee: Amount,
+        bolt11_invoice_or: Option<&Bolt11Invoice>,
+        preimage_or: Option<&str>,
+        requester_public_key_or: Option<&PublicKey>,
+        gateway_id_or: Option<&GatewayId>,
+        is_simulated: bool,
+        batch_id_or: Option<i32>,
+    ) -> anyhow::Result<()> {
+        let new_payment = NewPayment {
+            federation_id: federation_id.to_string(),
+            direction: direction.as_str().to_string(),
+            amount_msats: i64::try_from(amount.msats)?,
+            fee_msats: i64::try_from(fee.msats)?,
+            bolt11_invoice: bolt11_invoice_or.map(ToString::to_string),
+            simulated: is_simulated,
+            preimage: preimage_or.map(ToString::to_string),
+            requester_npub: requester_public_key_or
+                .map(ToBech32::to_bech32)
+                .transpose()?,
+            gateway_id: gateway_id_or.map(ToString::to_string),
+            batch_id: batch_id_or,
+        };
+
+        self.run_query(|connection| {
+            insert_into(schema::payments::table)
+                .values(&new_payment)
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Creates a batch to group payments made together from a federation,
+    /// such as several invoices paid in one go. Returns the id of the batch.
+    pub fn create_payment_batch(&self, federation_id: &FederationId) -> anyhow::Result<i32> {
+        let new_payment_batch = NewPaymentBatch {
+            federation_id: federation_id.to_string(),
+        };
+
+        // The connection is held throughout, so the newest batch is the one just inserted.
+        self.run_query(|connection| {
+            insert_into(schema::payment_batches::table)
+                .values(&new_payment_batch)
+                .execute(connection)?;
+
+            payment_batches_dsl::payment_batches
+                .select(payment_batches_dsl::id)
+                .order(payment_batches_dsl::id.desc())
+                .first(connection)
+        })
+    }
+
+    /// Lists the fees paid through each gateway for outgoing payments from a federation,
+    /// oldest first. Simulated payments are left out, since no fees were actually paid.
+    pub fn list_gateway_fee_history(
+        &self,
+        federation_id: &FederationId,
+    ) -> anyhow::Result<BTreeMap<GatewayId, Vec<GatewayFeeSample>>> {
+        let mut fee_history: BTreeMap<GatewayId, Vec<GatewayFeeSample>> = BTreeMap::new();
+
+        for (gateway_id, sample) in self.load_gateway_fee_samples(Some(federation_id))? {
+            fee_history.entry(gateway_id).or_default().push(sample);
+        }
+
+        Ok(fee_history)
+    }
+
+    /// Gets the totals of the fees paid through each gateway, across all federations.
+    /// See [`crate::fedimint::Wallet::set_gateway_fee_stats`].
+    pub fn get_gateway_fee_stats(&self) -> anyhow::Result<BTreeMap<GatewayId, GatewayFeeStats>> {
+        let mut fee_stats: BTreeMap<GatewayId, GatewayFeeStats> = BTreeMap::new();
+
+        for (gateway_id, sample) in self.load_gateway_fee_samples(None)? {
+            fee_stats
+                .entry(gateway_id)
+                .or_default()
+                .record(sample.amount, sample.fee);
+        }
+
+        Ok(fee_stats)
+    }
+
+    fn load_gateway_fee_samples(
+        &self,
+        federation_id_or: Option<&FederationId>,
+    ) -> anyhow::Result<Vec<(GatewayId, GatewayFeeSample)>> {
+        let mut query = payments_dsl::payments
+            .select((
+                payments_dsl::gateway_id.assume_not_null(),
+                payments_dsl::amount_msats,
+                payments_dsl::fee_msats,
+                payments_dsl::create_time,
+            ))
+            .filter(payments_dsl::gateway_id.is_not_null())
+            .filter(payments_dsl::direction.eq(PaymentDirection::Outgoing.as_str()))
+            .filter(payments_dsl::simulated.eq(false))
+            .order(payments_dsl::id)
+            .into_boxed();
+
+        if let Some(federation_id) = federation_id_or {
+            query = query.filter(payments_dsl::federation_id.eq(federation_id.to_string()));
+        }
+
+        let rows: Vec<(String, i64, i64, NaiveDateTime)> =
+            self.run_query(|connection| query.load(connection))?;
+
+        rows.into_iter()
+            .map(|(gateway_id, amount_msats, fee_msats, create_time)| {
+                Ok((
+                    gateway_id.parse()?,
+                    GatewayFeeSample {
+                        amount: Amount::from_msats(u64::try_from(amount_msats)?),
+                        fee: Amount::from_msats(u64::try_from(fee_msats)?),
+                        create_time,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    /// Lists payments in the payment log. Ordered by id in ascending order.
+    /// Use limit and offset parameters for pagination.
+    pub fn list_payments(&self, limit: i64, offset: i64) -> anyhow::Result<Vec<PaymentRecord>> {
+        let payments: Vec<Payment> = self.run_query(|connection| {
+            payments_dsl::payments
+                .order(payments_dsl::id)
+                .limit(limit)
+                .offset(offset)
+                .load(connection)
+        })?;
+
+        payments.into_iter().map(TryInto::try_into).collect()
+    }
+
+    /// Gets a single payment from the payment log.
+    pub fn get_payment(&self, id: i32) -> anyhow::Result<PaymentRecord> {
+        let payment: Payment = self.run_query(|connection| {
+            payments_dsl::payments
+                .filter(payments_dsl::id.eq(id))
+                .first(connection)
+        })?;
+
+        payment.try_into()
+    }
+
+    /// Saves transactions read from federations' operation logs. Transactions that were
+    /// already saved are updated, since their status changes once they finish.
+    /// New transactions are given the saved exchange rate in `fiat_currency_or`
+    /// that was fetched closest to when they were made, if there is one.
+    pub fn save_transactions(
+        &self,
+        transactions: &[TransactionRecord],
+        fiat_currency_or: Option<FiatCurrency>,
+    ) -> anyhow::Result<()> {
+        self.run_query(|connection| -> anyhow::Result<()> {
+            for transaction in transactions {
+                let status = transaction.status.as_str();
+
+                let exchange_rate_or = match (transaction.exchange_rate_or, fiat_currency_or) {
+                    (Some(exchange_rate), _) => Some(exchange_rate),
+                    (None, Some(fiat_currency)) => get_closest_exchange_rate(
+                        connection,
+                        fiat_currency,
+                        transaction.create_time,
+                    )?,
+                    (None, None) => None,
+                };
+
+                insert_into(schema::transactions::table)
+                    .values(&NewTransaction {
+                        operation_id: transaction.operation_id.clone(),
+                        federation_id: transaction.federation_id.to_string(),
+                        federation_name: transaction.federation_name_or.clone(),
+                        kind: transaction.kind.as_str().to_string(),
+                        direction: transaction.direction.as_str().to_string(),
+                        amount_msats: i64::try_from(transaction.amount.msats)?,
+                        fee_msats: i64::try_from(transaction.fee.msats)?,
+                        status: status.to_string(),
+                        create_time: transaction.create_time,
+                        fiat_currency: exchange_rate_or
+                            .map(|exchange_rate| exchange_rate.currency.as_str().to_string()),
+                        fiat_btc_price: exchange_rate_or
+                            .map(|exchange_rate| exchange_rate.btc_price),
+                    })
+                    .on_conflict(transactions_dsl::operation_id)
+                    .do_update()
+                    .set((
+                        transactions_dsl::federation_name.eq(&transaction.federation_name_or),
+                        transactions_dsl::status.eq(status),
+                    ))
+                    .execute(connection)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Lists saved transactions, newest first.
+    /// Use limit and offset parameters for pagination.
+    pub fn list_transactions(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> anyhow::Result<Vec<TransactionRecord>> {
+        let transactions: Vec<model::Transaction> = self.run_query(|connection| {
+            transactions_dsl::transactions
+                .order((
+                    transactions_dsl::create_time.desc(),
+                    transactions_dsl::operation_id,
+                ))
+                .limit(limit)
+                .offset(offset)
+                .load(connection)
+        })?;
+
+        transactions.into_iter().map(TryInto::try_into).collect()
+    }
+
+    pub fn count_transactions(&self) -> anyhow::Result<i64> {
+        self.run_query(|connection| {
+            transactions_dsl::transactions
+                .count()
+                .get_result(connection)
+        })
+    }
+
+    /// Saves an exchange rate fetched at `fetch_time`, so that transactions
+    /// made around then can be given it once they're synced.
+    /// Rates fetched more than 90 days earlier are deleted.
+    pub fn save_exchange_rate(
+        &self,
+        exchange_rate: &ExchangeRate,
+        fetch_time: NaiveDateTime,
+    ) -> anyhow::Result<()> {
+        self.run_query(|connection| {
+            insert_into(schema::exchange_rates::table)
+                .values(&NewExchangeRate {
+                    currency: exchange_rate.currency.as_str().to_string(),
+                    btc_price: exchange_rate.btc_price,
+                    fetch_time,
+                })
+                .execute(connection)?;
+
+            delete(
+                exchange_rates_dsl::exchange_rates.filter(
+                    exchange_rates_dsl::fetch_time.lt(fetch_time - EXCHANGE_RATE_RETENTION),
+                ),
+            )
+            .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Saves a zap receipt, unless one with the same event id has already been saved.
+    /// Returns whether the receipt was newly saved.
+    pub fn save_zap_receipt(&self, receipt: &ZapReceipt) -> anyhow::Result<bool> {
+        let new_zap_receipt = NewZapReceipt {
+            event_id: receipt.event_id.to_hex(),
+            recipient_npub: receipt.recipient_public_key.to_bech32()?,
+            sender_npub: receipt
+                .sender_public_key_or
+                .as_ref()
+                .map(ToBech32::to_bech32)
+                .transpose()?,
+            zapped_event_id: receipt.zapped_event_id_or.map(|event_id| event_id.to_hex()),
+            amount_msats: i64::try_from(receipt.amount.msats)?,
+            bolt11_invoice: receipt.bolt11_invoice.to_string(),
+            comment: receipt.comment.clone(),
+        };
+
+        let inserted_rows = self.run_query(|connection| {
+            insert_or_ignore_into(schema::zap_receipts::table)
+                .values(&new_zap_receipt)
+                .execute(connection)
+        })?;
+
+        Ok(inserted_rows > 0)
+    }
+
+    /// Lists saved zap receipts, along with the incoming payment each one paid if there is one.
+    /// Ordered by id in ascending order.
+    /// Use limit and offset parameters for pagination.
+    pub fn list_zap_receipts(&self, limit: i64, offset: i64) -> anyhow::Result<Vec<ZapRecord>> {
+        self.run_query(|connection| -> anyhow::Result<_> {
+            let zap_receipts: Vec<model::ZapReceipt> = zap_receipts_dsl::zap_receipts
+                .order(zap_receipts_dsl::id)
+                .limit(limit)
+                .offset(offset)
+                .load(connection)?;
+
+            zap_receipts
+                .into_iter()
+                .map(|zap_receipt| {
+                    Ok(ZapRecord {
+                        id: zap_receipt.id,
+                        payment_id_or: get_incoming_payment_id(
+                            connection,
+                            &zap_receipt.bolt11_invoice,
+                        )?,
+                        create_time: zap_receipt.create_time,
+                        receipt: zap_receipt.try_into()?,
+                    })
+                })
+                .collect()
+        })
+    }
+
+    /// Adds followed keys to the zap allowlist. Keys that are already on it are left as they are.
+    pub fn add_to_zap_allowlist(&self, followed_keys: &[FollowedKey]) -> anyhow::Result<()> {
+        let new_entries = followed_keys
+            .iter()
+            .map(|followed_key| {
+                Ok(NewZapAllowlistEntry {
+                    npub: followed_key.public_key.to_bech32()?,
+                    petname: followed_key.petname_or.clone(),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        self.run_query(|connection| {
+            insert_or_ignore_into(schema::zap_allowlist::table)
+                .values(&new_entries)
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    pub fn remove_from_zap_allowlist(&self, public_key: &PublicKey) -> anyhow::Result<()> {
+        let npub = public_key.to_bech32()?;
+
+        self.run_query(|connection| {
+            delete(zap_allowlist_dsl::zap_allowlist.filter(zap_allowlist_dsl::npub.eq(&npub)))
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    pub fn is_on_zap_allowlist(&self, public_key: &PublicKey) -> anyhow::Result<bool> {
+        let npub = public_key.to_bech32()?;
+
+        Ok(self
+            .run_query(|connection| {
+                zap_allowlist_dsl::zap_allowlist
+                    .select(zap_allowlist_dsl::npub)
+                    .filter(zap_allowlist_dsl::npub.eq(&npub))
+                    .first::<String>(connection)
+                    .optional()
+            })?
+            .is_some())
+    }
+
+    /// Lists the keys on the zap allowlist, oldest first.
+    pub fn list_zap_allowlist(&self) -> anyhow::Result<Vec<ZapAllowlistEntry>> {
+        let entries: Vec<(String, Option<String>, NaiveDateTime)> =
+            self.run_query(|connection| {
+                zap_allowlist_dsl::zap_allowlist
+                    .select((
+                        zap_allowlist_dsl::npub,
+                        zap_allowlist_dsl::petname,
+                        zap_allowlist_dsl::create_time,
+                    ))
+                    .order((zap_allowlist_dsl::create_time, zap_allowlist_dsl::npub))
+                    .load(connection)
+            })?;
+
+        entries
+            .into_iter()
+            .map(ZapAllowlistEntry::try_from)
+            .collect()
+    }
+
+    /// Saves this device's share of a threshold key, replacing any earlier share of the same key.
+    pub fn save_threshold_share(&self, share: &ThresholdShare) -> anyhow::Result<()> {
+        let new_share = NewThresholdShare {
+            npub: share.public_key.to_bech32()?,
+            share_json: share.to_json()?,
+        };
+
+        self.run_query(|connection| {
+            insert_into(schema::threshold_shares::table)
+                .values(&new_share)
+                .on_conflict(threshold_shares_dsl::npub)
+                .do_update()
+                .set(&new_share)
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Lists this device's shares of threshold keys, oldest first.
+    pub fn list_threshold_shares(&self) -> anyhow::Result<Vec<ThresholdShare>> {
+        let share_jsons: Vec<String> = self.run_query(|connection| {
+            threshold_shares_dsl::threshold_shares
+                .select(threshold_shares_dsl::share_json)
+                .order((
+                    threshold_shares_dsl::create_time,
+                    threshold_shares_dsl::npub,
+                ))
+                .load(connection)
+        })?;
+
+        share_jsons
+            .iter()
+            .map(|share_json| ThresholdShare::from_json(share_json))
+            .collect()
+    }
+
+    pub fn remove_threshold_share(&self, public_key: &PublicKey) -> anyhow::Result<()> {
+        self.run_query(|connection| -> anyhow::Result<_> {
+            delete(
+                threshold_shares_dsl::threshold_shares
+                    .filter(threshold_shares_dsl::npub.eq(public_key.to_bech32()?)),
+            )
+            .execute(connection)?;
+
+            Ok(())
+        })
+    }
+
+    /// Gets the id of the incoming payment in the payment log that paid `invoice`, if any.
+    pub fn get_incoming_payment_id_for_invoice(
+        &self,
+        invoice: &Bolt11Invoice,
+    ) -> anyhow::Result<Option<i32>> {
+        self.run_query(|connection| get_incoming_payment_id(connection, &invoice.to_string()))
+    }
+
+    /// Saves a Nostr Wallet Connect connection.
+    pub fn save_nwc_connection(&self, nwc_connection: &NwcConnection) -> anyhow::Result<()> {
+        self.run_query(|connection| -> anyhow::Result<_> {
+            insert_into(schema::nwc_connections::table)
+                .values(&NewNwcConnection {
+                    service_nsec: nwc_connection.service_secret_key.to_bech32()?,
+                    client_nsec: nwc_connection.client_secret_key.to_bech32()?,
+                    relay_url: nwc_connection.relay_url.to_string(),
+                    name: nwc_connection.name.clone(),
+                    allowed_methods: nwc_connection
+                        .allowed_methods
+                        .iter()
+                        .map(|method| method.as_str())
+                        .collect::<Vec<_>>()
+                        .join(","),
+                    budget_msats: nwc_connection
+                        .budget_or
+                        .map(|budget| i64::try_from(budget.amount.msats))
+                        .transpose()?,
+                    budget_period: nwc_connection
+                        .budget_or
+                        .map(|budget| budget.period.as_str().to_string()),
+                })
+                .execute(connection)?;
+
+            Ok(())
+        })
+    }
+
+    /// Lists every Nostr Wallet Connect connection, including revoked ones.
+    /// Ordered by id in ascending order.
+    pub fn list_nwc_connections(&self) -> anyhow::Result<Vec<NwcConnectionRecord>> {
+        self.run_query(|connection| -> anyhow::Result<_> {
+            let nwc_connections: Vec<model::NwcConnection> = nwc_connections_dsl::nwc_connections
+                .order(nwc_connections_dsl::id)
+                .load(connection)?;
+
+            nwc_connections.into_iter().map(TryInto::try_into).collect()
+        })
+    }
+
+    /// Gets the Nostr Wallet Connect connection used by the app with `client_public_key`.
+    pub fn get_nwc_connection_by_client_public_key(
+        &self,
+        client_public_key: &PublicKey,
+    ) -> anyhow::Result<Option<NwcConnectionRecord>> {
+        // Only the client's secret key is saved, so the public keys are derived here.
+        Ok(self
+            .list_nwc_connections()?
+            .into_iter()
+            .find(|record| &record.connection.client_public_key() == client_public_key))
+    }
+
+    /// Revokes a Nostr Wallet Connect connection. Its app can't make any more requests.
+    pub fn revoke_nwc_connection(&self, id: i32, revoke_time: NaiveDateTime) -> anyhow::Result<()> {
+        self.run_query(|connection| -> anyhow::Result<_> {
+            update(nwc_connections_dsl::nwc_connections.filter(nwc_connections_dsl::id.eq(id)))
+                .set(nwc_connections_dsl::revoke_time.eq(revoke_time))
+                .execute(connection)?;
+
+            Ok(())
+        })
+    }
+
+    /// Gets how much the app with `client_public_key` has spent since `since_or`,
+    /// including fees. Simulated payments don't count, since they don't move any funds.
+    pub fn get_nwc_connection_spent(
+        &self,
+        client_public_key: &PublicKey,
+        since_or: Option<NaiveDateTime>,
+    ) -> anyhow::Result<Amount> {
+        let requester_npub = client_public_key.to_bech32()?;
+
+        let mut query = payments_dsl::payments
+            .select((payments_dsl::amount_msats, payments_dsl::fee_msats))
+            .filter(payments_dsl::requester_npub.eq(requester_npub))
+            .filter(payments_dsl::direction.eq(PaymentDirection::Outgoing.as_str()))
+            .filter(payments_dsl::simulated.eq(false))
+            .into_boxed();
+
+        if let Some(since) = since_or {
+            query = query.filter(payments_dsl::create_time.ge(since));
+        }
+
+        let amounts: Vec<(i64, i64)> = self.run_query(|connection| query.load(connection))?;
+
+        let mut spent_msats: u64 = 0;
+        for (amount_msats, fee_msats) in amounts {
+            spent_msats = spent_msats
+                .saturating_add(u64::try_from(amount_msats)?)
+                .saturating_add(u64::try_from(fee_msats)?);
+        }
+
+        Ok(Amount::from_msats(spent_msats))
+    }
+
+    /// Records that a NIP-47 request sent at `request_time` has been handled, so that
+    /// it isn't handled again when it's received again after a restart or resubscribe.
+    /// Returns `false` if the request was already handled.
+    pub fn save_nwc_handled_request(
+        &self,
+        request_event_id: &EventId,
+        requester_public_key: &PublicKey,
+        request_time: NaiveDateTime,
+    ) -> anyhow::Result<bool> {
+        self.run_query(|connection| -> anyhow::Result<_> {
+            let inserted_rows = insert_or_ignore_into(schema::nwc_handled_requests::table)
+                .values(&NewNwcHandledRequest {
+                    request_event_id: request_event_id.to_hex(),
+                    requester_npub: requester_public_key.to_bech32()?,
+                    request_time,
+                })
+                .execute(connection)?;
+
+            Ok(inserted_rows > 0)
+        })
+    }
+
+    /// Gets when the latest handled NIP-47 request from the app with
+    /// `requester_public_key` was sent. `None` if none have been handled.
+    pub fn get_last_nwc_request_time(
+        &self,
+        requester_public_key: &PublicKey,
+    ) -> anyhow::Result<Option<NaiveDateTime>> {
+        let requester_npub = requester_public_key.to_bech32()?;
+
+        self.run_query(|connection| {
+            nwc_handled_requests_dsl::nwc_handled_requests
+                .select(diesel::dsl::max(nwc_handled_requests_dsl::request_time))
+                .filter(nwc_handled_requests_dsl::requester_npub.eq(requester_npub))
+                .first(connection)
+        })
+    }
+
+    /// Saves an incoming `pay_invoice` request to the payment request inbox.
+    /// Returns `false` if the request was already saved.
+    pub fn save_payment_request(&self, request: &PayInvoiceRequest) -> anyhow::Result<bool> {
+        self.run_query(|connection| -> anyhow::Result<_> {
+            let inserted_rows = insert_or_ignore_into(schema::payment_requests::table)
+                .values(&NewPaymentRequest {
+                    request_event_id: request.request_event_id.to_hex(),
+                    requester_npub: request.requester_public_key.to_bech32()?,
+                    bolt11_invoice: request.invoice.to_string(),
+                    status: PaymentRequestStatus::Pending.as_str().to_string(),
+                })
+                .execute(connection)?;
+
+            Ok(inserted_rows > 0)
+        })
+    }
+
+    /// Lists payment requests that haven't been paid or rejected yet.
+    /// Ordered by id in ascending order.
+    /// Use limit and offset parameters for pagination.
+    pub fn list_pending_payment_requests(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> anyhow::Result<Vec<PaymentRequest>> {
+        let payment_requests: Vec<model::PaymentRequest> = self.run_query(|connection| {
+            payment_requests_dsl::payment_requests
+                .filter(payment_requests_dsl::status.eq(PaymentRequestStatus::Pending.as_str()))
+                .order(payment_requests_dsl::id)
+                .limit(limit)
+                .offset(offset)
+                .load(connection)
+        })?;
+
+        payment_requests
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    /// Marks a payment request as paid or rejected.
+    pub fn set_payment_request_status(
+        &self,
+        id: i32,
+        status: PaymentRequestStatus,
+    ) -> anyhow::Result<()> {
+        self.run_query(|connection| {
+            update(payment_requests_dsl::payment_requests.filter(payment_requests_dsl::id.eq(id)))
+                .set(payment_requests_dsl::status.eq(status.as_str()))
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    /// Saves how much invoice metadata to include in exports.
+    pub fn save_invoice_privacy(&self, invoice_privacy: InvoicePrivacy) -> anyhow::Result<()> {
+        self.save_setting(INVOICE_PRIVACY_SETTING_KEY, invoice_privacy.as_str())
+    }
+
+    /// Gets how much invoice metadata to include in exports.
+    /// Returns the default privacy level if none has been saved.
+    pub fn get_invoice_privacy(&self) -> anyhow::Result<InvoicePrivacy> {
+        self.get_setting(INVOICE_PRIVACY_SETTING_KEY)?
+            .map_or_else(|| Ok(InvoicePrivacy::default()), |value| value.parse())
+    }
+
+    /// Saves the developer setting for simulating lightning payments.
+    pub fn save_payment_simulation(
+        &self,
+        payment_simulation: PaymentSimulation,
+    ) -> anyhow::Result<()> {
+        self.save_setting(PAYMENT_SIMULATION_SETTING_KEY, payment_simulation.as_str())
+    }
+
+    /// Gets the developer setting for simulating lightning payments.
+    /// Defaults to [`PaymentSimulation::Disabled`] if it has never been set.
+    pub fn get_payment_simulation(&self) -> anyhow::Result<PaymentSimulation> {
+        self.get_setting(PAYMENT_SIMULATION_SETTING_KEY)?
+            .map_or_else(|| Ok(PaymentSimulation::default()), |value| value.parse())
+    }
+
+    /// Saves the largest invoice that apps can have paid without the user confirming
+    /// that it's over the cap. See [`crate::fedimint::Wallet::pay_app_invoice`].
+    pub fn save_app_payment_cap(&self, app_payment_cap: Amount) -> anyhow::Result<()> {
+        self.save_setting(
+            APP_PAYMENT_CAP_SETTING_KEY,
+            &app_payment_cap.sats_round_down().to_string(),
+        )
+    }
+
+    /// Gets the app payment cap. Defaults to [`DEFAULT_APP_PAYMENT_CAP_SATS`] if it has never been set.
+    pub fn get_app_payment_cap(&self) -> anyhow::Result<Amount> {
+        Ok(Amount::from_sats(
+            self.get_setting(APP_PAYMENT_CAP_SETTING_KEY)?
+                .map(|sats| sats.parse::<u64>())
+                .transpose()?
+                .unwrap_or(DEFAULT_APP_PAYMENT_CAP_SATS),
+        ))
+    }
+
+    /// Saves the two-person rule for large payments, or removes it if `spend_approval_policy_or`
+    /// is `None`. See [`crate::spend_approval::SpendApprovalPolicy`].
+    pub fn save_spend_approval_policy(
+        &self,
+        spend_approval_policy_or: Option<&SpendApprovalPolicy>,
+    ) -> anyhow::Result<()> {
+        match spend_approval_policy_or {
+            Some(spend_approval_policy) => self.save_setting(
+                SPEND_APPROVAL_POLICY_SETTING_KEY,
+                &spend_approval_policy.to_json(),
+            ),
+            None => self.remove_setting(SPEND_APPROVAL_POLICY_SETTING_KEY),
+        }
+    }
+
+    /// Gets the two-person rule for large payments, if one has been saved.
+    pub fn get_spend_approval_policy(&self) -> anyhow::Result<Option<SpendApprovalPolicy>> {
+        self.get_setting(SPEND_APPROVAL_POLICY_SETTING_KEY)?
+            .map(|json| SpendApprovalPolicy::from_json(&json))
+            .transpose()
+    }
+
+    /// Saves every field of `settings`.
+    pub fn save_settings(&self, settings: &Settings) -> anyhow::Result<()> {
+        for field in settings.fields() {
+            self.save_setting(field.key, &field.value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the saved settings. Fields that have never been saved keep their defaults.
+    pub fn get_settings(&self) -> anyhow::Result<Settings> {
+        let mut settings = Settings::default();
+
+        for key in Settings::KEYS {
+            if let Some(value) = self.get_setting(key)? {
+                settings = settings.with_field(key, &value)?;
+            }
+        }
+
+        Ok(settings)
+    }
+
+    /// Saves whether the password may be saved to the system keychain to unlock Keystache.
+    pub fn save_keychain_unlock_enabled(&self, is_enabled: bool) -> anyhow::Result<()> {
+        self.save_setting(KEYCHAIN_UNLOCK_SETTING_KEY, &is_enabled.to_string())
+    }
+
+    /// Gets whether the password may be saved to the system keychain to unlock Keystache.
+    /// Defaults to `true`, though the password is only saved if the user asks for it.
+    pub fn get_keychain_unlock_enabled(&self) -> anyhow::Result<bool> {
+        Ok(self
+            .get_setting(KEYCHAIN_UNLOCK_SETTING_KEY)?
+            .map(|value| value.parse())
+            .transpose()?
+            .unwrap_or(true))
+    }
+
+    /// Saves whether `get_public_key` requests may be approved without prompting.
+    /// Each app can also turn this off for itself.
+    pub fn save_auto_approve_public_key_reads(&self, is_enabled: bool) -> anyhow::Result<()> {
+        self.save_setting(
+            AUTO_APPROVE_PUBLIC_KEY_READS_SETTING_KEY,
+            &is_enabled.to_string(),
+        )
+    }
+
+    /// Gets whether `get_public_key` requests may be approved without prompting.
+    /// Defaults to `true`.
+    pub fn get_auto_approve_public_key_reads(&self) -> anyhow::Result<bool> {
+        Ok(self
+            .get_setting(AUTO_APPROVE_PUBLIC_KEY_READS_SETTING_KEY)?
+            .map(|value| value.parse())
+            .transpose()?
+            .unwrap_or(true))
+    }
+
+    /// Saves the version of Keystache that last opened the database.
+    /// See [`crate::release_notes::record_app_version`].
+    pub fn save_last_run_version(&self, version: &str) -> anyhow::Result<()> {
+        self.save_setting(LAST_RUN_VERSION_SETTING_KEY, version)
+    }
+
+    /// Gets the version of Keystache that last opened the database,
+    /// or `None` if no version has been saved yet.
+    pub fn get_last_run_version(&self) -> anyhow::Result<Option<String>> {
+        self.get_setting(LAST_RUN_VERSION_SETTING_KEY)
+    }
+
+    /// Saves the seed that the Bitcoin wallet's keys are derived from.
+    /// Refuses to replace a saved seed, since funds received with it would become unspendable.
+    pub fn save_wallet_mnemonic(&self, mnemonic: &Mnemonic) -> anyhow::Result<()> {
+        if self.get_wallet_mnemonic()?.is_some() {
+            return Err(anyhow::anyhow!("A wallet seed has already been saved."));
+        }
+
+        self.save_setting(WALLET_MNEMONIC_SETTING_KEY, &mnemonic.to_string())
+    }
+
+    /// Gets the seed that the Bitcoin wallet's keys are derived from,
+    /// or `None` if no seed has been saved yet.
+    pub fn get_wallet_mnemonic(&self) -> anyhow::Result<Option<Mnemonic>> {
+        self.get_setting(WALLET_MNEMONIC_SETTING_KEY)?
+            .map(|value| Ok(Mnemonic::from_str(&value)?))
+            .transpose()
+    }
+
+    /// Like [`Self::get_wallet_mnemonic`], but checks `encryption_password` first,
+    /// so that the seed is only shown to whoever knows the password.
+    pub fn reveal_wallet_mnemonic(
+        &self,
+        encryption_password: &str,
+    ) -> anyhow::Result<Option<Mnemonic>> {
+        // Check that the password is correct.
+        Self::open_or_create_in_app_data_dir(encryption_password)?;
+
+        self.get_wallet_mnemonic()
+    }
+
+    /// Copies the database file to `destination`. The copy
+    /// is encrypted with the same password as the database.
+    pub fn export_encrypted_copy(&self, destination: &Path) -> anyhow::Result<()> {
+        // Hold the connection so that nothing is written while copying.
+        self.run_query(|_connection| std::fs::copy(&self.path, destination))?;
+
+        Ok(())
+    }
+
+    /// Writes a copy of the whole database to `destination`, encrypted with `archive_password`
+    /// rather than the database's own password, so that it can be restored on another device
+    /// with [`Self::restore_archive_in_app_data_dir`] even if this one is bound to the device.
+    /// Joined federations are kept by the wallet rather than the database,
+    /// so their invite codes are saved in the archive to be rejoined.
+    pub fn export_archive(
+        &self,
+        destination: &Path,
+        archive_password: &str,
+        federation_invite_codes: &[InviteCode],
+    ) -> anyhow::Result<()> {
+        if destination.try_exists()? {
+            return Err(anyhow::anyhow!("{} already exists.", destination.display()));
+        }
+
+        if let Err(err) =
+            self.run_query(|connection| export_attached(connection, destination, archive_password))
+        {
+            // TODO: Log a warning if the partial archive fails to be removed.
+            let _ = std::fs::remove_file(destination);
+
+            return Err(err);
+        }
+
+        let archive = Self::open_or_create(
+            destination.parent().unwrap_or_else(|| Path::new("")),
+            &destination
+                .file_name()
+                .ok_or_else(|| anyhow::anyhow!("Choose a file to export to."))?
+                .to_string_lossy(),
+            archive_password,
+        )?;
+
+        archive.save_invite_codes_setting(ARCHIVED_FEDERATIONS_SETTING_KEY, federation_invite_codes)
+    }
+
+    /// Gets the invite codes of the federations that were joined when the archive this database
+    /// was restored from was exported. Empty unless the database was restored from an archive.
+    pub fn get_archived_federation_invite_codes(&self) -> anyhow::Result<Vec<InviteCode>> {
+        self.get_invite_codes_setting(ARCHIVED_FEDERATIONS_SETTING_KEY)
+    }
+
+    /// Forgets the archived invite codes, once their federations have been rejoined.
+    pub fn remove_archived_federation_invite_codes(&self) -> anyhow::Result<()> {
+        self.remove_setting(ARCHIVED_FEDERATIONS_SETTING_KEY)
+    }
+
+    /// Gets the invite codes of the federations to recover e-cash from, which were given
+    /// when the wallet was restored from its seed. See [`Self::restore_seed_in_app_data_dir`].
+    pub fn get_recovery_federation_invite_codes(&self) -> anyhow::Result<Vec<InviteCode>> {
+        self.get_invite_codes_setting(RECOVERY_FEDERATIONS_SETTING_KEY)
+    }
+
+    /// Forgets the recovery invite codes, once their federations have been recovered.
+    pub fn remove_recovery_federation_invite_codes(&self) -> anyhow::Result<()> {
+        self.remove_setting(RECOVERY_FEDERATIONS_SETTING_KEY)
+    }
+
+    /// Saves where scheduled backups are written, and how many are kept.
+    pub fn save_backup_settings(&self, backup_settings: &BackupSettings) -> anyhow::Result<()> {
+        // An empty directory means scheduled backups are disabled.
+        self.save_setting(
+            BACKUP_DIRECTORY_SETTING_KEY,
+            &backup_settings
+                .directory_or
+                .as_ref()
+                .map(|directory| directory.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        )?;
+
+        self.save_setting(
+            BACKUP_ROTATION_COUNT_SETTING_KEY,
+            &backup_settings.rotation_count.to_string(),
+        )
+    }
+
+    /// Gets where scheduled backups are written, and how many are kept.
+    pub fn get_backup_settings(&self) -> anyhow::Result<BackupSettings> {
+        let directory_or = self
+            .get_setting(BACKUP_DIRECTORY_SETTING_KEY)?
+            .filter(|directory| !directory.is_empty())
+            .map(PathBuf::from);
+
+        let rotation_count = self
+            .get_setting(BACKUP_ROTATION_COUNT_SETTING_KEY)?
+            .map(|rotation_count| rotation_count.parse())
+            .transpose()?
+            .unwrap_or(BackupSettings::default().rotation_count);
+
+        Ok(BackupSettings {
+            directory_or,
+            rotation_count,
+        })
+    }
+
+    /// Records the outcome of a backup. A failed backup keeps the last success time.
+    pub fn save_backup_result(&self, result: &Result<NaiveDateTime, String>) -> anyhow::Result<()> {
+        match result {
+            Ok(backup_time) => {
+                self.save_setting(
+                    BACKUP_LAST_SUCCESS_TIME_SETTING_KEY,
+                    &backup_time.and_utc().to_rfc3339(),
+                )?;
+                self.save_setting(BACKUP_LAST_ERROR_SETTING_KEY, "")
+            }
+            Err(err) => self.save_setting(BACKUP_LAST_ERROR_SETTING_KEY, err),
+        }
+    }
+
+    /// Gets the outcome of the most recent backups.
+    pub fn get_backup_status(&self) -> anyhow::Result<BackupStatus> {
+        let last_success_time_or = self
+            .get_setting(BACKUP_LAST_SUCCESS_TIME_SETTING_KEY)?
+            .map(|time| DateTime::parse_from_rfc3339(&time).map(|time| time.naive_utc()))
+            .transpose()?;
+
+        let last_error_or = self
+            .get_setting(BACKUP_LAST_ERROR_SETTING_KEY)?
+            .filter(|err| !err.is_empty());
+
+        Ok(BackupStatus {
+            last_success_time_or,
+            last_error_or,
+        })
+    }
+
+    fn save_setting(&self, key: &str, value: &str) -> anyhow::Result<()> {
+        self.run_query(|connection| {
+            insert_into(schema::app_settings::table)
+                .values(&NewAppSetting {
+                    setting_key: key.to_string(),
+                    setting_value: value.to_string(),
+                })
+                .on_conflict(app_settings_dsl::setting_key)
+                .do_update()
+                .set(app_settings_dsl::setting_value.eq(value))
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    fn remove_setting(&self, key: &str) -> anyhow::Result<()> {
+        self.run_query(|connection| {
+            delete(app_settings_dsl::app_settings.filter(app_settings_dsl::setting_key.eq(key)))
+                .execute(connection)
+        })?;
+
+        Ok(())
+    }
+
+    fn get_setting(&self, key: &str) -> anyhow::Result<Option<String>> {
+        self.run_query(|connection| {
+            app_settings_dsl::app_settings
+                .select(app_settings_dsl::setting_value)
+                .filter(app_settings_dsl::setting_key.eq(key))
+                .first(connection)
+                .optional()
+        })
+    }
+
+    fn save_invite_codes_setting(
+        &self,
+        key: &str,
+        invite_codes: &[InviteCode],
+    ) -> anyhow::Result<()> {
+        self.save_setting(
+            key,
+            &serde_json::to_string(
+                &invite_codes
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>(),
+            )?,
+        )
+    }
+
+    fn get_invite_codes_setting(&self, key: &str) -> anyhow::Result<Vec<InviteCode>> {
+        let Some(value) = self.get_setting(key)? else {
+            return Ok(Vec::new());
+        };
+
+        serde_json::from_str::<Vec<String>>(&value)?
+            .iter()
+            .map(|invite_code| Ok(InviteCode::from_str(invite_code)?))
+            .collect()
+    }
+
+    fn get_project_dirs() -> anyhow::Result<directories::ProjectDirs> {
+        directories::ProjectDirs::from("co", "nodetec", "keystache")
+            .ok_or_else(|| anyhow::anyhow!("Could not determine Keystache project directories."))
+    }
+}
+
+impl FederationBalanceThresholds {
+    fn to_balance_thresholds(&self) -> BalanceThresholds {
+        let to_amount = |msats: Option<i64>| {
+            msats
+                .and_then(|msats| u64::try_from(msats).ok())
+                .map(Amount::from_msats)
+        };
+
+        BalanceThresholds {
+            low_or: to_amount(self.low_balance_msats),
+            high_or: to_amount(self.high_balance_msats),
+            max_or: to_amount(self.max_balance_msats),
+        }
+    }
+}
+
+impl TryFrom<Payment> for PaymentRecord {
+    type Error = anyhow::Error;
+
+    fn try_from(payment: Payment) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: payment.id,
+            federation_id: payment.federation_id.parse()?,
+            direction: payment.direction.parse()?,
+            amount: Amount::from_msats(u64::try_from(payment.amount_msats)?),
+            fee: Amount::from_msats(u64::try_from(payment.fee_msats)?),
+            bolt11_invoice_or: payment
+                .bolt11_invoice
+                .map(|invoice| Bolt11Invoice::from_str(&invoice))
+                .transpose()?,
+            preimage_or: payment.preimage,
+            requester_public_key_or: payment
+                .requester_npub
+                .map(|npub| PublicKey::from_str(&npub))
+                .transpose()?,
+            gateway_id_or: payment
+                .gateway_id
+                .map(|gateway_id| gateway_id.parse())
+                .transpose()?,
+            is_simulated: payment.simulated,
+            batch_id_or: payment.batch_id,
+            create_time: payment.create_time,
+        })
+    }
+}
+
+impl TryFrom<model::Transaction> for TransactionRecord {
+    type Error = anyhow::Error;
+
+    fn try_from(transaction: model::Transaction) -> Result<Self, Self::Error> {
+        Ok(Self {
+            operation_id: transaction.operation_id,
+            federation_id: transaction.federation_id.parse()?,
+            federation_name_or: transaction.federation_name,
+            kind: transaction.kind.parse()?,
+            direction: transaction.direction.parse()?,
+            amount: Amount::from_msats(u64::try_from(transaction.amount_msats)?),
+            fee: Amount::from_msats(u64::try_from(transaction.fee_msats)?),
+            status: transaction.status.parse()?,
+            create_time: transaction.create_time,
+            exchange_rate_or: match (transaction.fiat_currency, transaction.fiat_btc_price) {
+                (Some(currency), Some(btc_price)) => Some(ExchangeRate {
+                    currency: currency.parse()?,
+                    btc_price,
+                }),
+                _ => None,
+            },
+        })
+    }
+}
+
+impl TryFrom<model::PaymentRequest> for PaymentRequest {
+    type Error = anyhow::Error;
+
+    fn try_from(payment_request: model::PaymentRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: payment_request.id,
+            request: PayInvoiceRequest {
+                request_event_id: EventId::from_hex(&payment_request.request_event_id)?,
+                requester_public_key: PublicKey::from_str(&payment_request.requester_npub)?,
+                invoice: Bolt11Invoice::from_str(&payment_request.bolt11_invoice)?,
+            },
+            create_time: payment_request.create_time,
+        })
+    }
+}
+
+impl TryFrom<model::NwcConnection> for NwcConnectionRecord {
+    type Error = anyhow::Error;
+
+    fn try_from(nwc_connection: model::NwcConnection) -> Result<Self, Self::Error> {
+        let budget_or = match (nwc_connection.budget_msats, nwc_connection.budget_period) {
+            (Some(budget_msats), Some(budget_period)) => Some(NwcBudget {
+                amount: Amount::from_msats(u64::try_from(budget_msats)?),
+                period: budget_period.parse()?,
+            }),
+            _ => None,
+        };
+
+        Ok(Self {
+            id: nwc_connection.id,
+            connection: NwcConnection {
+                service_secret_key: SecretKey::from_str(&nwc_connection.service_nsec)?,
+                client_secret_key: SecretKey::from_str(&nwc_connection.client_nsec)?,
+                relay_url: Url::parse(&nwc_connection.relay_url)?,
+                name: nwc_connection.name,
+                allowed_methods: nwc_connection
+                    .allowed_methods
+                    .split(',')
+                    .filter(|method| !method.is_empty())
+                    .map(str::parse)
+                    .collect::<anyhow::Result<_>>()?,
+                budget_or,
+            },
+            revoke_time_or: nwc_connection.revoke_time,
+            create_time: nwc_connection.create_time,
+        })
+    }
+}
+
+impl TryFrom<model::Delegation> for Delegation {
+    type Error = anyhow::Error;
+
+    fn try_from(delegation: model::Delegation) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: delegation.id,
+            delegator_public_key: PublicKey::from_str(&delegation.delegator_npub)?,
+            delegatee_public_key: PublicKey::from_str(&delegation.delegatee_npub)?,
+            conditions: delegation.conditions,
+            signature: Signature::from_str(&delegation.signature)?,
+            valid_until: delegation.valid_until,
+            revoke_time_or: delegation.revoke_time,
+            create_time: delegation.create_time,
+        })
+    }
+}
+
+impl TryFrom<model::ZapReceipt> for ZapReceipt {
+    type Error = anyhow::Error;
+
+    fn try_from(zap_receipt: model::ZapReceipt) -> Result<Self, Self::Error> {
+        Ok(Self {
+            event_id: EventId::from_hex(&zap_receipt.event_id)?,
+            recipient_public_key: PublicKey::from_str(&zap_receipt.recipient_npub)?,
+            sender_public_key_or: zap_receipt
+                .sender_npub
+                .as_deref()
+                .map(PublicKey::from_str)
+                .transpose()?,
+            zapped_event_id_or: zap_receipt
+                .zapped_event_id
+                .as_deref()
+                .map(EventId::from_hex)
+                .transpose()?,
+            amount: Amount::from_msats(u64::try_from(zap_receipt.amount_msats)?),
+            bolt11_invoice: Bolt11Invoice::from_str(&zap_receipt.bolt11_invoice)?,
+            comment: zap_receipt.comment,
+        })
+    }
+}
+
+/// Gets the saved exchange rate in `currency` that was fetched closest to `time`,
+/// as long as it was fetched within an hour of it.
+fn get_closest_exchange_rate(
+    connection: &mut SqliteConnection,
+    currency: FiatCurrency,
+    time: NaiveDateTime,
+) -> anyhow::Result<Option<ExchangeRate>> {
+    let latest_before_or: Option<model::ExchangeRate> = exchange_rates_dsl::exchange_rates
+        .filter(exchange_rates_dsl::currency.eq(currency.as_str()))
+        .filter(exchange_rates_dsl::fetch_time.le(time))
+        .filter(exchange_rates_dsl::fetch_time.ge(time - EXCHANGE_RATE_MAX_TIME_DIFFERENCE))
+        .order(exchange_rates_dsl::fetch_time.desc())
+        .first(connection)
+        .optional()?;
+
+    let earliest_after_or: Option<model::ExchangeRate> = exchange_rates_dsl::exchange_rates
+        .filter(exchange_rates_dsl::currency.eq(currency.as_str()))
+        .filter(exchange_rates_dsl::fetch_time.gt(time))
+        .filter(exchange_rates_dsl::fetch_time.le(time + EXCHANGE_RATE_MAX_TIME_DIFFERENCE))
+        .order(exchange_rates_dsl::fetch_time.asc())
+        .first(connection)
+        .optional()?;
+
+    let closest_or = match (latest_before_or, earliest_after_or) {
+        (Some(before), Some(after)) => {
+            if time - before.fetch_time <= after.fetch_time - time {
+                Some(before)
+            } else {
+                Some(after)
+            }
+        }
+        (before_or, after_or) => before_or.or(after_or),
+    };
+
+    Ok(closest_or.map(|exchange_rate| ExchangeRate {
+        currency,
+        btc_price: exchange_rate.btc_price,
+    }))
+}
+
+/// Iterates over the rows of a table in batches of [`ITER_BATCH_SIZE`], loading each batch with
+/// `load_batch` once the previous one runs out. Batches are keyed by the last id seen rather than
+/// an offset, so rows aren't skipped or repeated if others are added or removed in between, and
+/// later batches don't get slower to load. Stops after the first error.
+fn iter_by_id<T>(
+    mut load_batch: impl FnMut(i32) -> anyhow::Result<Vec<T>>,
+    id_of: impl Fn(&T) -> i32,
+) -> impl Iterator<Item = anyhow::Result<T>> {
+    let mut batch = VecDeque::new();
+    let mut after_id = i32::MIN;
+    let mut is_done = false;
+
+    std::iter::from_fn(move || {
+        if batch.is_empty() && !is_done {
+            match load_batch(after_id) {
+                Ok(rows) => {
+                    // A batch that isn't full was the last one.
+                    is_done = rows.len() < ITER_BATCH_SIZE.unsigned_abs() as usize;
+
+                    if let Some(last_row) = rows.last() {
+                        after_id = id_of(last_row);
+                    }
+
+                    batch.extend(rows);
+                }
+                Err(err) => {
+                    is_done = true;
+
+                    return Some(Err(err));
+                }
+            }
+        }
+
+        batch.pop_front().map(Ok)
+    })
+}
+
+fn get_incoming_payment_id(
+    connection: &mut SqliteConnection,
+    bolt11_invoice: &str,
+) -> anyhow::Result<Option<i32>> {
+    Ok(payments_dsl::payments
+        .filter(payments_dsl::bolt11_invoice.eq(bolt11_invoice))
+        .filter(payments_dsl::direction.eq(PaymentDirection::Incoming.as_str()))
+        .select(payments_dsl::id)
+        .first(connection)
+        .optional()?)
+}
+
+impl KeyManager for Database {
+    fn get_secret_key(&self, public_key: &PublicKey) -> Option<SecretKey> {
+        // TODO: Log a warning if the keypair fails to load.
+        self.get_keypair_by_npub(public_key)
+            .ok()
+            .flatten()
+            .map(|keypair| keypair.secret_key().into())
+    }
+}
+
+// TODO: Uncomment and fix tests.
+#[cfg(test)]
+mod round_trip_tests {
+    use std::collections::BTreeSet;
+
+    use fedimint_core::{util::SafeUrl, PeerId};
+    use nostr_sdk::{FromBech32, Keys};
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::fedimint::{TransactionKind, TransactionStatus};
+    use crate::nwc::{BudgetPeriod, NwcMethod};
+
+    const TEST_DB_KEY: &str = "test_db_key";
+
+    // Opening a database runs every migration, so each case is fairly slow.
+    const CASES: u32 = 16;
+
+    fn open_temp_db() -> (tempfile::TempDir, Database) {
+        let folder = tempfile::tempdir().unwrap();
+        let db = Database::open_or_create(folder.path(), "test.db", TEST_DB_KEY).unwrap();
+
+        // The folder is returned so that it isn't deleted while the database is open.
+        (folder, db)
+    }
+
+    fn secret_key_strategy() -> impl Strategy<Value = SecretKey> {
+        any::<[u8; 32]>().prop_filter_map("Not a valid secret key", |bytes| {
+            SecretKey::from_slice(&bytes).ok()
+        })
+    }
+
+    fn relay_url_strategy() -> impl Strategy<Value = String> {
+        "wss://[a-z]{1,20}\\.[a-z]{2,6}(/[a-z0-9]{1,10})?"
+    }
+
+    fn nwc_connection_strategy() -> impl Strategy<Value = NwcConnection> {
+        (
+            secret_key_strategy(),
+            secret_key_strategy(),
+            relay_url_strategy(),
+            "[a-zA-Z0-9 ]{1,30}",
+            proptest::sample::subsequence(NwcMethod::ALL.to_vec(), 1..=NwcMethod::ALL.len()),
+            proptest::option::of((
+                0..=i64::MAX.unsigned_abs(),
+                proptest::sample::select(BudgetPeriod::ALL.to_vec()),
+            )),
+        )
+            .prop_map(
+                |(service_secret_key, client_secret_key, relay_url, name, methods, budget)| {
+                    NwcConnection {
+                        service_secret_key,
+                        client_secret_key,
+                        relay_url: Url::parse(&relay_url).unwrap(),
+                        name,
+                        allowed_methods: methods.into_iter().collect::<BTreeSet<_>>(),
+                        budget_or: budget.map(|(msats, period)| NwcBudget {
+                            amount: Amount::from_msats(msats),
+                            period,
+                        }),
+                    }
+                },
+            )
+    }
+
+    // Test vectors from NIP-19. The keys are encoded when saved,
+    // so a change in encoding would make existing rows unreadable.
+    #[test]
+    fn keypair_bech32_test_vectors() {
+        let (_folder, db) = open_temp_db();
+
+        let secret_key =
+            SecretKey::from_hex("67dea2ed018072d675f5415ecfaed7d2597555e202d85b3d65ea4e58d2d92ffa")
+                .unwrap();
+        let keypair = Keypair::from_secret_key(SECP256K1, &secret_key);
+
+        db.save_keypair(&keypair).unwrap();
+
+        let saved_keypair = db.list_keypairs(1, 0).unwrap().remove(0);
+        assert_eq!(
+            saved_keypair.nsec,
+            "nsec1vl029mgpspedva04g90vltkh6fvh240zqtv9k0t9af8935ke9laqsnlfe5"
+        );
+        assert_eq!(db.get_keypair(&saved_keypair.npub).unwrap(), keypair);
+
+        let public_key =
+            PublicKey::from_hex("7e7e9c42a91bfef19fa929e5fda1b72e0ebc1a4c1141673e2794234d86addf4e")
+                .unwrap();
+        assert_eq!(
+            public_key.to_bech32().unwrap(),
+            "npub10elfcs4fr0l0r8af98jlmgdh9c8tcxjvz9qkw038js35mp4dma8qzvjptg"
+        );
+    }
+
+    #[test]
+    fn transactions_are_given_the_closest_exchange_rate() {
+        let (_folder, db) = open_temp_db();
+
+        let time = NaiveDateTime::default() + chrono::Duration::days(1);
+        let exchange_rate = |btc_price| ExchangeRate {
+            currency: FiatCurrency::Usd,
+            btc_price,
+        };
+
+        db.save_exchange_rate(
+            &exchange_rate(50_000.0),
+            time - chrono::Duration::minutes(20),
+        )
+        .unwrap();
+        db.save_exchange_rate(
+            &exchange_rate(60_000.0),
+            time + chrono::Duration::minutes(10),
+        )
+        .unwrap();
+        db.save_exchange_rate(
+            &ExchangeRate {
+                currency: FiatCurrency::Eur,
+                btc_price: 40_000.0,
+            },
+            time,
+        )
+        .unwrap();
+
+        let transaction = |operation_id: &str, create_time| TransactionRecord {
+            operation_id: operation_id.to_string(),
+            federation_id: FederationId::dummy(),
+            federation_name_or: None,
+            kind: TransactionKind::Lightning,
+            direction: PaymentDirection::Incoming,
+            amount: Amount::from_sats(1000),
+            fee: Amount::ZERO,
+            status: TransactionStatus::Succeeded,
+            create_time,
+            exchange_rate_or: None,
+        };
+
+        db.save_transactions(
+            &[
+                transaction("01", time),
+                // Too long before any rate was fetched.
+                transaction("00", time - chrono::Duration::hours(2)),
+            ],
+            Some(FiatCurrency::Usd),
+        )
+        .unwrap();
+
+        let transactions = db.list_transactions(10, 0).unwrap();
+        assert_eq!(
+            transactions[0].exchange_rate_or,
+            Some(exchange_rate(60_000.0))
+        );
+        assert_eq!(transactions[1].exchange_rate_or, None);
+
+        // Without a currency, new transactions aren't given a rate.
+        db.save_transactions(
+            &[transaction("02", time + chrono::Duration::minutes(30))],
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            db.list_transactions(1, 0).unwrap()[0].exchange_rate_or,
+            None
+        );
+    }
+
+    #[test]
+    fn test_iter_by_id() {
+        let (_folder, db) = open_temp_db();
+
+        // Enough relays and keypairs to need more than one batch.
+        let relay_count = ITER_BATCH_SIZE.unsigned_abs() as usize + 5;
+        for i in 0..relay_count {
+            db.save_relay(format!("wss://relay{i}.example.com"))
+                .unwrap();
+            db.save_keypair(&Keypair::new_global(
+                &mut nostr_sdk::secp256k1::rand::thread_rng(),
+            ))
+            .unwrap();
+        }
+        db.remove_relay("wss://relay0.example.com").unwrap();
+
+        let relays: Vec<NostrRelay> = db.iter_relays().collect::<anyhow::Result<_>>().unwrap();
+        assert_eq!(relays.len(), relay_count - 1);
+        assert_eq!(relays[0].websocket_url, "wss://relay1.example.com");
+        assert!(relays.windows(2).all(|pair| pair[0].id < pair[1].id));
+
+        let npubs: Vec<String> = db
+            .iter_keypairs()
+            .map(|keypair| keypair.unwrap().npub)
+            .collect();
+        assert_eq!(npubs, db.list_public_keys("", i64::MAX, 0).unwrap());
+    }
+
+    #[test]
+    fn federation_metadata_round_trip() {
+        let (_folder, db) = open_temp_db();
+
+        let federation_id = FederationId::dummy();
+        let mut metadata = FederationMetadata {
+            name_or: Some("Test Federation".to_string()),
+            module_kinds: vec!["ln".to_string(), "mint".to_string()],
+            guardian_names: vec!["Alice".to_string(), "Bob".to_string()],
+            icon_url_or: Some("https://fedimint.example.com/icon.png".to_string()),
+        };
+
+        db.save_federation_metadata(&federation_id, &metadata)
+            .unwrap();
+        assert_eq!(
+            db.list_federation_metadata().unwrap(),
+            BTreeMap::from([(federation_id, metadata.clone())])
+        );
+
+        // Newer metadata replaces the cached metadata, including fields that were removed.
+        metadata.name_or = None;
+        metadata.icon_url_or = None;
+        db.save_federation_metadata(&federation_id, &metadata)
+            .unwrap();
+        assert_eq!(
+            db.list_federation_metadata().unwrap(),
+            BTreeMap::from([(federation_id, metadata)])
+        );
+
+        db.remove_federation_metadata(&federation_id).unwrap();
+        assert!(db.list_federation_metadata().unwrap().is_empty());
+    }
+
+    #[test]
+    fn opening_with_the_wrong_password_is_reported() {
+        let (folder, db) = open_temp_db();
+        drop(db);
+
+        let err = Database::open_or_create(folder.path(), "test.db", "wrong password")
+            .err()
+            .unwrap();
+        assert!(err.is::<IncorrectPasswordError>());
+
+        Database::open_or_create(folder.path(), "test.db", TEST_DB_KEY).unwrap();
+    }
+
+    #[tokio::test]
+    async fn retry_while_busy_only_retries_busy_errors() {
+        let mut call_count = 0;
+        let result = retry_while_busy(|| {
+            call_count += 1;
+            if call_count < 3 {
+                Err(DatabaseBusyError.into())
+            } else {
+                Ok(call_count)
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+
+        let mut call_count = 0;
+        let result: anyhow::Result<()> = retry_while_busy(|| {
+            call_count += 1;
+            Err(anyhow::anyhow!("Not busy"))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(call_count, 1);
+
+        let result: anyhow::Result<()> = retry_while_busy(|| Err(DatabaseBusyError.into())).await;
+        assert!(result.unwrap_err().is::<DatabaseBusyError>());
+    }
+
+    #[test]
+    fn nwc_handled_requests_are_only_handled_once() {
+        let (_folder, db) = open_temp_db();
+
+        let requester_public_key = Keys::generate().public_key();
+        let first_request_time = DateTime::from_timestamp(1_700_000_000, 0)
+            .unwrap()
+            .naive_utc();
+        let second_request_time = DateTime::from_timestamp(1_700_000_060, 0)
+            .unwrap()
+            .naive_utc();
+
+        assert_eq!(
+            db.get_last_nwc_request_time(&requester_public_key).unwrap(),
+            None
+        );
+
+        let request_event_id = EventId::all_zeros();
+        assert!(db
+            .save_nwc_handled_request(&request_event_id, &requester_public_key, first_request_time)
+            .unwrap());
+        assert!(!db
+            .save_nwc_handled_request(&request_event_id, &requester_public_key, first_request_time)
+            .unwrap());
+
+        assert!(db
+            .save_nwc_handled_request(
+                &EventId::from_byte_array([1; 32]),
+                &requester_public_key,
+                second_request_time
+            )
+            .unwrap());
+        assert_eq!(
+            db.get_last_nwc_request_time(&requester_public_key).unwrap(),
+            Some(second_request_time)
+        );
+
+        // Requests from other apps don't move the cursor.
+        assert_eq!(
+            db.get_last_nwc_request_time(&Keys::generate().public_key())
+                .unwrap(),
+            None
+        );
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(CASES))]
+
+        #[test]
+        fn keypair_round_trip(secret_key in secret_key_strategy()) {
+            let (_folder, db) = open_temp_db();
+            let keypair = Keypair::from_secret_key(SECP256K1, &secret_key);
+            let npub = PublicKey::from(keypair.x_only_public_key().0).to_bech32().unwrap();
+
+            db.save_keypair(&keypair).unwrap();
+
+            let saved_keypairs = db.list_keypairs(10, 0).unwrap();
+            prop_assert_eq!(saved_keypairs.len(), 1);
+            prop_assert_eq!(&saved_keypairs[0].npub, &npub);
+            prop_assert_eq!(&saved_keypairs[0].nsec, &secret_key.to_bech32().unwrap());
+
+            // The saved encodings decode back to the same keys.
+            prop_assert_eq!(
+                PublicKey::from_bech32(&saved_keypairs[0].npub).unwrap().to_bech32().unwrap(),
+                npub.clone()
+            );
+            prop_assert_eq!(db.get_keypair(&npub).unwrap(), keypair);
+            prop_assert_eq!(
+                db.get_keypair_by_npub(&PublicKey::from_bech32(&npub).unwrap()).unwrap(),
+                Some(keypair)
+            );
+            prop_assert_eq!(
+                db.get_secret_key(&PublicKey::from_bech32(&npub).unwrap()),
+                Some(secret_key)
+            );
+            prop_assert_eq!(db.list_public_keys("", 10, 0).unwrap(), vec![npub.clone()]);
+
+            // npubs and nsecs are unique, so the same keypair can't be saved twice.
+            prop_assert!(db.save_keypair(&keypair).is_err());
+            prop_assert_eq!(db.count_keypairs("").unwrap(), 1);
+
+            // Display names are trimmed, can be searched for, and are removed when set to empty.
+            db.set_keypair_display_name(&npub, "  Work  ").unwrap();
+            prop_assert_eq!(
+                db.list_keypair_display_names().unwrap().get(&npub).map(String::as_str),
+                Some("Work")
+            );
+            prop_assert_eq!(db.count_keypairs("Work").unwrap(), 1);
+            prop_assert_eq!(
+                &db.search_keypairs("", 10, 0).unwrap()[0].display_name,
+                &Some("Work".to_string())
+            );
+            db.set_keypair_display_name(&npub, " ").unwrap();
+            prop_assert!(db.list_keypair_display_names().unwrap().is_empty());
+            prop_assert!(db.set_keypair_display_name("npub1unknown", "Work").is_err());
+
+            db.remove_keypair(&npub).unwrap();
+            prop_assert!(db.list_keypairs(10, 0).unwrap().is_empty());
+            prop_assert_eq!(
+                db.get_keypair_by_npub(&PublicKey::from_bech32(&npub).unwrap()).unwrap(),
+                None
+            );
+        }
+
+        #[test]
+        fn relay_round_trip(
+            websocket_urls in proptest::collection::btree_set(relay_url_strategy(), 1..5)
+        ) {
+            let (_folder, db) = open_temp_db();
+
+            for websocket_url in &websocket_urls {
+                db.save_relay(websocket_url.clone()).unwrap();
+            }
+
+            let saved_urls: BTreeSet<String> = db
+                .list_relays(10, 0)
+                .unwrap()
+                .into_iter()
+                .map(|relay| relay.websocket_url)
+                .collect();
+            prop_assert_eq!(&saved_urls, &websocket_urls);
+
+            // Relay URLs are unique.
+            let first_url = websocket_urls.first().unwrap();
+            prop_assert!(db.save_relay(first_url.clone()).is_err());
+
+            // Relays are reconnected to unless that's turned off.
+            db.save_relay_auto_reconnect(first_url, false).unwrap();
+            for relay in db.list_relays(10, 0).unwrap() {
+                prop_assert_eq!(relay.auto_reconnect, &relay.websocket_url != first_url);
+            }
+
+            // Importing a relay list updates saved relays rather than adding them again.
+            db.import_relays(&[RelayListEntry {
+                websocket_url: first_url.clone(),
+                read: true,
+                write: false,
+            }])
+            .unwrap();
+            let first_relay = db
+                .list_relays(10, 0)
+                .unwrap()
+                .into_iter()
+                .find(|relay| &relay.websocket_url == first_url)
+                .unwrap();
+            prop_assert!(first_relay.read && !first_relay.write && !first_relay.auto_reconnect);
+
+            db.save_relay_usage(first_url, false, true).unwrap();
+            let first_relay = db
+                .search_relays(first_url, 10, 0)
+                .unwrap()
+                .into_iter()
+                .find(|relay| &relay.websocket_url == first_url)
+                .unwrap();
+            prop_assert!(!first_relay.read && first_relay.write);
+            prop_assert_eq!(
+                db.count_relays("").unwrap(),
+                i64::try_from(websocket_urls.len()).unwrap()
+            );
+
+            db.remove_relay(first_url).unwrap();
+            prop_assert_eq!(
+                db.count_relays("").unwrap(),
+                i64::try_from(websocket_urls.len() - 1).unwrap()
+            );
+        }
+
+        #[test]
+        fn archive_round_trip(
+            websocket_urls in prop::collection::btree_set(relay_url_strategy(), 1..5),
+        ) {
+            let (folder, db) = open_temp_db();
+
+            for websocket_url in &websocket_urls {
+                db.save_relay(websocket_url.clone()).unwrap();
+            }
+
+            let archive_path = folder.path().join("archive.sqlite");
+            let invite_code = InviteCode::new(
+                SafeUrl::parse("wss://fedimint.example.com").unwrap(),
+                PeerId::from(0),
+                FederationId::dummy(),
+                None,
+            );
+            db.export_archive(&archive_path, "archive_password", &[invite_code.clone()])
+                .unwrap();
+
+            // Archives are never overwritten.
+            prop_assert!(db.export_archive(&archive_path, "archive_password", &[]).is_err());
+
+            prop_assert!(Database::restore_archive(
+                &archive_path,
+                "wrong_password",
+                folder.path(),
+                "wrong.db",
+                TEST_DB_KEY,
+            )
+            .is_err());
+            prop_assert!(!folder.path().join("wrong.db").exists());
+
+            let restored = Database::restore_archive(
+                &archive_path,
+                "archive_password",
+                folder.path(),
+                "restored.db",
+                TEST_DB_KEY,
+            )
+            .unwrap();
+
+            let restored_urls: BTreeSet<String> = restored
+                .list_relays(10, 0)
+                .unwrap()
+                .into_iter()
+                .map(|relay| relay.websocket_url)
+                .collect();
+            prop_assert_eq!(&restored_urls, &websocket_urls);
+            prop_assert_eq!(
+                restored.get_archived_federation_invite_codes().unwrap(),
+                vec![invite_code]
+            );
+
+            // The archive's invite codes only end up in restored databases.
+            prop_assert!(db.get_archived_federation_invite_codes().unwrap().is_empty());
+        }
+
+        #[test]
+        fn wallet_mnemonic_round_trip(entropy in any::<[u8; 16]>()) {
+            let (_folder, db) = open_temp_db();
+
+            prop_assert!(db.get_wallet_mnemonic().unwrap().is_none());
+
+            let mnemonic = Mnemonic::from_entropy(&entropy).unwrap();
+            db.save_wallet_mnemonic(&mnemonic).unwrap();
+            prop_assert_eq!(db.get_wallet_mnemonic().unwrap(), Some(mnemonic.clone()));
+
+            // A saved seed is never replaced.
+            prop_assert!(db.save_wallet_mnemonic(&mnemonic).is_err());
+        }
+
+        #[test]
+        fn receiving_lightning_address_round_trip(nwc_connection in nwc_connection_strategy()) {
+            let (_folder, db) = open_temp_db();
+
+            prop_assert!(db.get_receiving_lightning_address().unwrap().is_none());
+
+            let lightning_address = ReceivingLightningAddress {
+                address: "satoshi@example.com".to_string(),
+                federation_id: FederationId::dummy(),
+                client_public_key: nwc_connection.client_public_key(),
+            };
+            db.save_receiving_lightning_address(&lightning_address).unwrap();
+            prop_assert_eq!(
+                db.get_receiving_lightning_address().unwrap(),
+                Some(lightning_address)
+            );
+
+            db.remove_receiving_lightning_address().unwrap();
+            prop_assert!(db.get_receiving_lightning_address().unwrap().is_none());
+        }
+
+        #[test]
+        fn nwc_connection_round_trip(nwc_connection in nwc_connection_strategy()) {
+            let (_folder, db) = open_temp_db();
+
+            db.save_nwc_connection(&nwc_connection).unwrap();
+
+            let records = db.list_nwc_connections().unwrap();
+            prop_assert_eq!(records.len(), 1);
+
+            let saved_connection = &records[0].connection;
+            prop_assert_eq!(&saved_connection.service_secret_key, &nwc_connection.service_secret_key);
+            prop_assert_eq!(&saved_connection.client_secret_key, &nwc_connection.client_secret_key);
+            prop_assert_eq!(&saved_connection.relay_url, &nwc_connection.relay_url);
+            prop_assert_eq!(&saved_connection.name, &nwc_connection.name);
+            prop_assert_eq!(&saved_connection.allowed_methods, &nwc_connection.allowed_methods);
+            prop_assert_eq!(saved_connection.budget_or, nwc_connection.budget_or);
+            prop_assert!(!records[0].is_revoked());
+
+            // Connections are found by the public key that the app signs its requests with.
+            prop_assert_eq!(
+                db.get_nwc_connection_by_client_public_key(&nwc_connection.client_public_key())
+                    .unwrap()
+                    .map(|record| record.id),
+                Some(records[0].id)
+            );
+
+            // Connection secrets are unique.
+            prop_assert!(db.save_nwc_connection(&nwc_connection).is_err());
+        }
+    }
+}
+
+// #[cfg(test)]
+// mod tests {
+//     use nostr_sdk::secp256k1::{rand::thread_rng, Secp256k1};
+
+//     use super::*;
+//     use std::path::PathBuf;
+
+//     const CORRECT_DB_KEY: &str = "correct_db_key";
+//     const INCORRECT_DB_KEY: &str = "incorrect_db_key";
+
+//     fn get_temp_folder() -> PathBuf {
+//         tempfile::TempDir::new()
+//             .expect("Failed to create temporary directory")
+//             .path()
+//             .to_path_buf()
+//     }
+
+//     fn get_random_keypair() -> Keypair {
+//         let secp = Secp256k1::new();
+//         Keypair::new(&secp, &mut thread_rng())
+//     }
+
+//     #[test]
+//     fn open_db_where_folder_exists() {
+//         let folder = get_temp_folder();
+//         Database::open_or_create(&folder, "test.db", String::new()).unwrap();
+//     }
+
+//     #[test]
+//     fn open_db_where_folder_does_not_exist() {
+//         let folder = get_temp_folder();
+//         Database::open_or_create(
+//             &folder.join("non_existent_subfolder"),
+//             "test.db",
+//             String::new(),
+//         )
+//         .unwrap();
+//     }
+
+//     #[test]
+//     fn open_db_where_file_exists_at_folder_path() {
+//         let folder = get_temp_folder();
+
+//         std::fs::create_dir(&folder).unwrap();
+//         std::fs::File::create(&folder.join("foo")).unwrap();
+
+//         // Attempting to open a database where a file already exists at the folder path should cause an error.
+//         assert!(Database::open_or_create(&folder.join("foo"), "test.db", String::new()).is_err());
+//     }
+
+//     #[test]
+//     fn open_db_where_folder_exists_at_file_path() {
+//         let folder = get_temp_folder();
+
+//         std::fs::create_dir(&folder).unwrap();
+//         std::fs::create_dir(&folder.join("test.db")).unwrap();
+
+//         // Attempting to open a database where a folder already exists at the file path should cause an error.
+//         assert!(Database::open_or_create(&folder, "test.db", String::new()).is_err());
+//     }
+
+//     #[test]
+//     fn reopen_encrypted_db() {
+//         let folder = get_temp_folder();
+//         let db = Database::open_or_create(&folder, "test.db", CORRECT_DB_KEY).unwrap();
+//         let keypair = get_random_keypair();
+//         db.save_keypair(&keypair).unwrap();
+
+//         drop(db);
+
+//         let db = Database::open_or_create(&folder, "test.db", CORRECT_DB_KEY).unwrap();
+//         assert_eq!(db.get_first_keypair().unwrap().unwrap(), keypair);
+//     }
+
+//     #[test]
+//     fn reopen_encrypted_db_with_wrong_encryption_password_error() {
+//         let folder = get_temp_folder();
+//         let db = Database::open_or_create(&folder, "test.db", CORRECT_DB_KEY).unwrap();
+
+//         drop(db);
+
+//         let db = Database::open_or_create(&folder, "test.db", INCORRECT_DB_KEY);
+//         assert!(db.is_err());
+//     }
+
+//     #[test]
+//     fn save_and_remove_keypair() {
+//         let db = Database::open_or_create(&get_temp_folder(), "test.db", CORRECT_DB_KEY).unwrap();
+//         let keypair = get_random_keypair();
+
+//         // Save a keypair to the database.
+//         db.save_keypair(&keypair).unwrap();
+
+//         // Check that the keypair was saved.
+//         assert_eq!(db.list_keypairs(1, 0).unwrap(), vec![keypair]);
+//         assert_eq!(
+//             db.list_public_keys(1, 0).unwrap(),
+//             vec![keypair.x_only_public_key().0.into()]
+//         );
+
+//         // Remove the keypair from the database.
+//         db.remove_keypair(&keypair.x_only_public_key().0.into())
+//             .unwrap();
+
+//         // Check that the keypair was removed.
+//         assert!(db.list_keypairs(1, 0).unwrap().is_empty());
+//         assert!(db.list_public_keys(1, 0).unwrap().is_empty());
+//     }
+
+//     #[test]
+//     fn save_duplicate_keypair() {
+//         let db = Database::open_or_create(&get_temp_folder(), "test.db", CORRECT_DB_KEY).unwrap();
+//         let keypair = get_random_keypair();
+
+//         // Save a keypair to the database.
+//         db.save_keypair(&keypair).unwrap();
+
+//         // Saving the same keypair again should cause an error.
+//         assert!(db.save_keypair(&keypair).is_err());
+//     }
+
+//     #[test]
+//     fn remove_keypair_that_doesnt_exist() {
+//         let db = Database::open_or_create(&get_temp_folder(), "test.db", CORRECT_DB_KEY).unwrap();
+//         let keypair = get_random_keypair();
+
+//         // Removing a keypair that doesn't exist should not cause an error.
+//         assert!(db
+//             .remove_keypair(&keypair.x_only_public_key().0.into())
+//             .is_ok());
+//     }
+
+//     #[test]
+//     fn list_keypairs() {
+//         let db = Database::open_or_create(&get_temp_folder(), "test.db", CORRECT_DB_KEY).unwrap();
+
+//         // Returns an empty list since there are no keypairs in the database.
+//         assert!(db.list_keypairs(10, 0).unwrap().is_empty());
+
+//         // Using an offset with an empty database should return an empty list.
+//         assert!(db.list_keypairs(10, 1).unwrap().is_empty());
+
+//         let keypair_1 = get_random_keypair();
+//         let keypair_2 = get_random_keypair();
+//         let keypair_3 = get_random_keypair();
+
+//         // Add some keypairs to the database.
+//         db.save_keypair(&keypair_1).unwrap();
+//         db.save_keypair(&keypair_2).unwrap();
+//         db.save_keypair(&keypair_3).unwrap();
+
+//         // Returns the keypairs in the database.
+//         assert_eq!(
+//             db.list_keypairs(10, 0).unwrap(),
+//             vec![keypair_1, keypair_2, keypair_3]
+//         );
+
+//         // Responds to limit.
+//         assert_eq!(db.list_keypairs(2, 0).unwrap(), vec![keypair_1, keypair_2]);
+
+//         // Responds to limit and offset.
+//         assert_eq!(db.list_keypairs(2, 2).unwrap(), vec![keypair_3]);
+
+//         // Limit of 0 should return an empty list.
+//         assert!(db.list_keypairs(0, 0).unwrap().is_empty());
+//     }
+
+//     #[test]
+//     fn list_public_keys() {
+//         let db = Database::open_or_create(&get_temp_folder(), "test.db", CORRECT_DB_KEY).unwrap();
+
+//         // Returns an empty list since there are no keypairs in the database.
+//         assert!(db.list_public_keys(10, 0).unwrap().is_empty());
+
+//         // Using an offset with an empty database should return an empty list.
+//         assert!(db.list_public_keys(10, 1).unwrap().is_empty());
+
+//         let keypair_1 = get_random_keypair();
+//         let keypair_2 = get_random_keypair();
+//         let keypair_3 = get_random_keypair();
+
+//         let pubkey_1 = keypair_1.x_only_public_key().0.into();
+//         let pubkey_2 = keypair_2.x_only_public_key().0.into();
+//         let pubkey_3 = keypair_3.x_only_public_key().0.into();
+
+//         // Add some keypairs to the database.
+//         db.save_keypair(&keypair_1).unwrap();
+//         db.save_keypair(&keypair_2).unwrap();
+//         db.save_keypair(&keypair_3).unwrap();
+
+//         // Returns the pubkeys in the database.
+//         assert_eq!(
+//             db.list_public_keys(10, 0).unwrap(),
+//             vec![pubkey_1, pubkey_2, pubkey_3]
+//         );
+
+//         // Responds to limit.
+//         assert_eq!(db.list_public_keys(2, 0).unwrap(), vec![pubkey_1, pubkey_2]);
+
+//         // Responds to limit and offset.
+//         assert_eq!(db.list_public_keys(2, 2).unwrap(), vec![pubkey_3]);
+
+//         // Limit of 0 should return an empty list.
+//         assert!(db.list_public_keys(0, 0).unwrap().is_empty());
+//     }
+// }
@@ -0,0 +1,420 @@
+// Diesel requires that these structs contain all fields in the table, even if they are not used.
+#![allow(unused)]
+
+use super::schema;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::nostr_keys)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewNostrKeypair {
+    pub display_name: Option<String>,
+    pub npub: String,
+    pub nsec: String,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = schema::nostr_keys)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NostrKeypair {
+    pub id: i32,
+    pub display_name: Option<String>,
+    pub npub: String,
+    pub nsec: String,
+    pub create_time: NaiveDateTime,
+}
+
+/// A keypair without its secret key, for listing keypairs.
+#[derive(Queryable, Selectable, Debug, Clone, PartialEq, Eq)]
+#[diesel(table_name = schema::nostr_keys)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NostrKeypairSummary {
+    pub id: i32,
+    pub display_name: Option<String>,
+    pub npub: String,
+    pub create_time: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::nostr_outbox)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewNostrOutboxEvent {
+    pub event_id: String,
+    pub event_json: String,
+    pub attempt_count: i32,
+    pub next_attempt_time: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = schema::nostr_outbox)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NostrOutboxEvent {
+    pub id: i32,
+    pub event_id: String,
+    pub event_json: String,
+    pub attempt_count: i32,
+    pub next_attempt_time: NaiveDateTime,
+    pub create_time: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::nostr_relays)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewNostrRelay {
+    pub websocket_url: String,
+    pub read: bool,
+    pub write: bool,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = schema::nostr_relays)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NostrRelay {
+    pub id: i32,
+    pub websocket_url: String,
+    pub create_time: NaiveDateTime,
+    /// Whether the relay is reconnected to after its connection drops.
+    pub auto_reconnect: bool,
+    /// Whether the user reads from the relay, as marked in their NIP-65 relay list.
+    pub read: bool,
+    /// Whether the user writes to the relay, as marked in their NIP-65 relay list.
+    pub write: bool,
+}
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = schema::federation_api_overrides)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(treat_none_as_null = true)]
+pub struct NewFederationApiOverrides {
+    pub federation_id: String,
+    pub api_secret: Option<String>,
+}
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = schema::federation_metadata)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(treat_none_as_null = true)]
+pub struct NewFederationMetadata {
+    pub federation_id: String,
+    pub name: Option<String>,
+    /// JSON array of module kinds.
+    pub module_kinds: String,
+    /// JSON array of guardian names.
+    pub guardian_names: String,
+    pub icon_url: Option<String>,
+}
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = schema::federation_balance_thresholds)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(treat_none_as_null = true)]
+pub struct NewFederationBalanceThresholds {
+    pub federation_id: String,
+    pub low_balance_msats: Option<i64>,
+    pub high_balance_msats: Option<i64>,
+    pub max_balance_msats: Option<i64>,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = schema::federation_balance_thresholds)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct FederationBalanceThresholds {
+    pub id: i32,
+    pub federation_id: String,
+    pub low_balance_msats: Option<i64>,
+    pub high_balance_msats: Option<i64>,
+    pub create_time: NaiveDateTime,
+    pub max_balance_msats: Option<i64>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::payments)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewPayment {
+    pub federation_id: String,
+    pub direction: String,
+    pub amount_msats: i64,
+    pub fee_msats: i64,
+    pub bolt11_invoice: Option<String>,
+    pub simulated: bool,
+    pub preimage: Option<String>,
+    pub requester_npub: Option<String>,
+    pub gateway_id: Option<String>,
+    pub batch_id: Option<i32>,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = schema::payments)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Payment {
+    pub id: i32,
+    pub federation_id: String,
+    pub direction: String,
+    pub amount_msats: i64,
+    pub fee_msats: i64,
+    pub bolt11_invoice: Option<String>,
+    pub create_time: NaiveDateTime,
+    pub simulated: bool,
+    pub preimage: Option<String>,
+    pub requester_npub: Option<String>,
+    pub gateway_id: Option<String>,
+    pub batch_id: Option<i32>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::nwc_connections)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewNwcConnection {
+    pub service_nsec: String,
+    pub client_nsec: String,
+    pub relay_url: String,
+    pub name: String,
+    pub allowed_methods: String,
+    pub budget_msats: Option<i64>,
+    pub budget_period: Option<String>,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = schema::nwc_connections)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NwcConnection {
+    pub id: i32,
+    pub service_nsec: String,
+    pub client_nsec: String,
+    pub relay_url: String,
+    pub create_time: NaiveDateTime,
+    pub name: String,
+    pub allowed_methods: String,
+    pub budget_msats: Option<i64>,
+    pub budget_period: Option<String>,
+    pub revoke_time: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::payment_batches)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewPaymentBatch {
+    pub federation_id: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::nwc_handled_requests)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewNwcHandledRequest {
+    pub request_event_id: String,
+    pub requester_npub: String,
+    pub request_time: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::payment_requests)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewPaymentRequest {
+    pub request_event_id: String,
+    pub requester_npub: String,
+    pub bolt11_invoice: String,
+    pub status: String,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = schema::payment_requests)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct PaymentRequest {
+    pub id: i32,
+    pub request_event_id: String,
+    pub requester_npub: String,
+    pub bolt11_invoice: String,
+    pub status: String,
+    pub create_time: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::app_settings)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewAppSetting {
+    pub setting_key: String,
+    pub setting_value: String,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = schema::app_settings)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct AppSetting {
+    pub id: i32,
+    pub setting_key: String,
+    pub setting_value: String,
+    pub create_time: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::delegations)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewDelegation {
+    pub delegator_npub: String,
+    pub delegatee_npub: String,
+    pub conditions: String,
+    pub signature: String,
+    pub valid_until: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = schema::delegations)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Delegation {
+    pub id: i32,
+    pub delegator_npub: String,
+    pub delegatee_npub: String,
+    pub conditions: String,
+    pub signature: String,
+    pub valid_until: NaiveDateTime,
+    pub revoke_time: Option<NaiveDateTime>,
+    pub create_time: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::nip46_app_event_kinds)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewNip46AppEventKind {
+    pub npub: String,
+    pub kind: i32,
+    pub sign_count: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::nip46_app_kind_policies)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewNip46AppKindPolicy {
+    pub npub: String,
+    pub kind: i32,
+    pub decision: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::nip46_apps)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewNip46App {
+    pub npub: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::nip46_rejections)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewNip46Rejection {
+    pub npub: String,
+    pub reason: String,
+    pub requests_description: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::notes)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewNote {
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::pinned_gateways)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewPinnedGateway {
+    pub federation_id: String,
+    pub gateway_id: String,
+}
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = schema::threshold_shares)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewThresholdShare {
+    pub npub: String,
+    pub share_json: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::transactions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewTransaction {
+    pub operation_id: String,
+    pub federation_id: String,
+    pub federation_name: Option<String>,
+    pub kind: String,
+    pub direction: String,
+    pub amount_msats: i64,
+    pub fee_msats: i64,
+    pub status: String,
+    pub create_time: NaiveDateTime,
+    pub fiat_currency: Option<String>,
+    pub fiat_btc_price: Option<f64>,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = schema::transactions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Transaction {
+    pub operation_id: String,
+    pub federation_id: String,
+    pub federation_name: Option<String>,
+    pub kind: String,
+    pub direction: String,
+    pub amount_msats: i64,
+    pub fee_msats: i64,
+    pub status: String,
+    pub create_time: NaiveDateTime,
+    pub fiat_currency: Option<String>,
+    pub fiat_btc_price: Option<f64>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::exchange_rates)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewExchangeRate {
+    pub currency: String,
+    pub btc_price: f64,
+    pub fetch_time: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = schema::exchange_rates)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ExchangeRate {
+    pub id: i32,
+    pub currency: String,
+    pub btc_price: f64,
+    pub fetch_time: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::zap_allowlist)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewZapAllowlistEntry {
+    pub npub: String,
+    pub petname: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::zap_receipts)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewZapReceipt {
+    pub event_id: String,
+    pub recipient_npub: String,
+    pub sender_npub: Option<String>,
+    pub zapped_event_id: Option<String>,
+    pub amount_msats: i64,
+    pub bolt11_invoice: String,
+    pub comment: String,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = schema::zap_receipts)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ZapReceipt {
+    pub id: i32,
+    pub event_id: String,
+    pub recipient_npub: String,
+    pub sender_npub: Option<String>,
+    pub zapped_event_id: Option<String>,
+    pub amount_msats: i64,
+    pub bolt11_invoice: String,
+    pub comment: String,
+    pub create_time: NaiveDateTime,
+}
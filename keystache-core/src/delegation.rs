@@ -0,0 +1,152 @@
+use std::fmt::Write;
+
+use chrono::NaiveDateTime;
+use nostr_sdk::{
+    bitcoin::hashes::{sha256, Hash},
+    secp256k1::{schnorr::Signature, Keypair, Message, SECP256K1},
+    PublicKey,
+};
+use serde_json::json;
+
+/// What a delegatee may publish on the delegator's behalf, as described in NIP-26.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelegationConditions {
+    /// The event kinds the delegatee may publish. Empty if any kind is allowed.
+    pub kinds: Vec<u16>,
+    pub valid_from: NaiveDateTime,
+    pub valid_until: NaiveDateTime,
+}
+
+impl DelegationConditions {
+    /// Formats the conditions as a NIP-26 query string,
+    /// such as `kind=1&created_at>1700000000&created_at<1710000000`.
+    pub fn to_query_string(&self) -> String {
+        let mut query_string = String::new();
+
+        for kind in &self.kinds {
+            let _ = write!(query_string, "kind={kind}&");
+        }
+
+        let _ = write!(
+            query_string,
+            "created_at>{}&created_at<{}",
+            self.valid_from.and_utc().timestamp(),
+            self.valid_until.and_utc().timestamp()
+        );
+
+        query_string
+    }
+}
+
+/// A delegation issued from one of the user's keys, as saved in the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delegation {
+    pub id: i32,
+    pub delegator_public_key: PublicKey,
+    pub delegatee_public_key: PublicKey,
+    /// The NIP-26 query string that was signed.
+    pub conditions: String,
+    pub signature: Signature,
+    pub valid_until: NaiveDateTime,
+    /// When the delegation was revoked, if it has been.
+    pub revoke_time_or: Option<NaiveDateTime>,
+    pub create_time: NaiveDateTime,
+}
+
+impl Delegation {
+    /// Formats the delegation as the JSON of a NIP-26 `delegation` tag,
+    /// which the delegatee adds to the events it publishes.
+    pub fn to_tag_json(&self) -> String {
+        json!([
+            "delegation",
+            self.delegator_public_key.to_hex(),
+            self.conditions,
+            self.signature.to_string(),
+        ])
+        .to_string()
+    }
+
+    pub fn is_expired(&self, now: NaiveDateTime) -> bool {
+        self.valid_until <= now
+    }
+}
+
+/// Signs a NIP-26 delegation token, letting `delegatee` publish
+/// events matching `conditions` on behalf of `delegator`.
+pub fn sign_delegation(
+    delegator: &Keypair,
+    delegatee: &PublicKey,
+    conditions: &DelegationConditions,
+) -> Signature {
+    SECP256K1.sign_schnorr_no_aux_rand(
+        &to_delegation_token_message(delegatee, &conditions.to_query_string()),
+        delegator,
+    )
+}
+
+fn to_delegation_token_message(delegatee: &PublicKey, conditions: &str) -> Message {
+    let token = format!("nostr:delegation:{}:{conditions}", delegatee.to_hex());
+
+    Message::from_digest(sha256::Hash::hash(token.as_bytes()).to_byte_array())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use nostr_sdk::{secp256k1::rand::thread_rng, Keys};
+
+    use super::*;
+
+    #[test]
+    fn test_to_query_string() {
+        let conditions = DelegationConditions {
+            kinds: vec![1, 7],
+            valid_from: DateTime::from_timestamp(1_700_000_000, 0)
+                .unwrap()
+                .naive_utc(),
+            valid_until: DateTime::from_timestamp(1_710_000_000, 0)
+                .unwrap()
+                .naive_utc(),
+        };
+
+        assert_eq!(
+            conditions.to_query_string(),
+            "kind=1&kind=7&created_at>1700000000&created_at<1710000000"
+        );
+
+        let any_kind_conditions = DelegationConditions {
+            kinds: Vec::new(),
+            ..conditions
+        };
+
+        assert_eq!(
+            any_kind_conditions.to_query_string(),
+            "created_at>1700000000&created_at<1710000000"
+        );
+    }
+
+    #[test]
+    fn test_sign_delegation() {
+        let delegator = Keypair::new_global(&mut thread_rng());
+        let delegatee = Keys::generate().public_key();
+        let conditions = DelegationConditions {
+            kinds: vec![1],
+            valid_from: DateTime::from_timestamp(1_700_000_000, 0)
+                .unwrap()
+                .naive_utc(),
+            valid_until: DateTime::from_timestamp(1_710_000_000, 0)
+                .unwrap()
+                .naive_utc(),
+        };
+
+        let signature = sign_delegation(&delegator, &delegatee, &conditions);
+
+        assert!(SECP256K1
+            .verify_schnorr(
+                &signature,
+                &to_delegation_token_message(&delegatee, &conditions.to_query_string()),
+                &delegator.x_only_public_key().0,
+            )
+            .is_ok());
+    }
+}
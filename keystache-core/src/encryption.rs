@@ -0,0 +1,86 @@
+use std::collections::HashSet;
+
+use nostr_sdk::{nips::nip46::Request, PublicKey};
+
+/// The conversations that apps have been allowed to encrypt and decrypt this session, keyed
+/// by the app, the keypair it uses, and the peer it talks to. The requests themselves are
+/// still carried out by the NIP-46 signer once approved, this only decides whether to prompt.
+/// Conversations are kept in memory only, and are forgotten when Keystache is closed.
+#[derive(Clone, Default)]
+pub struct RememberedConversations {
+    conversations: HashSet<(PublicKey, PublicKey, PublicKey)>,
+}
+
+impl RememberedConversations {
+    /// Remembers that `app` may encrypt and decrypt messages between `identity` and `peer`.
+    pub fn remember(&mut self, app: PublicKey, identity: PublicKey, peer: PublicKey) {
+        self.conversations.insert((app, identity, peer));
+    }
+
+    /// Whether `app` has been allowed to encrypt and decrypt messages between `identity` and `peer`.
+    pub fn is_remembered(&self, app: &PublicKey, identity: &PublicKey, peer: &PublicKey) -> bool {
+        self.conversations.contains(&(*app, *identity, *peer))
+    }
+
+    /// Forgets every conversation of `app`, so that its next encryption request prompts again.
+    pub fn forget_app(&mut self, app: &PublicKey) {
+        self.conversations
+            .retain(|(conversation_app, _, _)| conversation_app != app);
+    }
+}
+
+/// The peer that a NIP-04 or NIP-44 encryption request encrypts to or decrypts from,
+/// or `None` for any other request.
+pub fn encryption_peer(request: &Request) -> Option<PublicKey> {
+    match request {
+        Request::Nip04Encrypt { public_key, .. }
+        | Request::Nip04Decrypt { public_key, .. }
+        | Request::Nip44Encrypt { public_key, .. }
+        | Request::Nip44Decrypt { public_key, .. } => Some(*public_key),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr_sdk::Keys;
+
+    use super::*;
+
+    #[test]
+    fn test_remembered_conversations() {
+        let app = Keys::generate().public_key();
+        let alice = Keys::generate().public_key();
+        let bob = Keys::generate().public_key();
+
+        let mut remembered_conversations = RememberedConversations::default();
+        assert!(!remembered_conversations.is_remembered(&app, &alice, &bob));
+
+        remembered_conversations.remember(app, alice, bob);
+        assert!(remembered_conversations.is_remembered(&app, &alice, &bob));
+
+        // Conversations are directed from the app's keypair to the peer.
+        assert!(!remembered_conversations.is_remembered(&app, &bob, &alice));
+
+        // Other apps don't share the conversation.
+        let other_app = Keys::generate().public_key();
+        assert!(!remembered_conversations.is_remembered(&other_app, &alice, &bob));
+
+        remembered_conversations.forget_app(&app);
+        assert!(!remembered_conversations.is_remembered(&app, &alice, &bob));
+    }
+
+    #[test]
+    fn test_encryption_peer() {
+        let bob = Keys::generate().public_key();
+
+        assert_eq!(
+            encryption_peer(&Request::Nip44Encrypt {
+                public_key: bob,
+                text: "Hello Bob".to_string(),
+            }),
+            Some(bob)
+        );
+        assert_eq!(encryption_peer(&Request::GetPublicKey), None);
+    }
+}
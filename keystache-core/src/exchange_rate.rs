@@ -0,0 +1,261 @@
+use std::{fmt::Display, str::FromStr};
+
+use fedimint_core::Amount;
+
+const MSATS_PER_BTC: f64 = 100_000_000_000.0;
+
+/// A fiat currency that amounts can be entered and shown in.
+/// Every currency here is offered by every [`PriceSource`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FiatCurrency {
+    Usd,
+    Eur,
+    Gbp,
+    Cad,
+    Chf,
+    Aud,
+    Jpy,
+}
+
+impl FiatCurrency {
+    pub const ALL: [Self; 7] = [
+        Self::Usd,
+        Self::Eur,
+        Self::Gbp,
+        Self::Cad,
+        Self::Chf,
+        Self::Aud,
+        Self::Jpy,
+    ];
+
+    /// The currency's ISO 4217 code.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Usd => "USD",
+            Self::Eur => "EUR",
+            Self::Gbp => "GBP",
+            Self::Cad => "CAD",
+            Self::Chf => "CHF",
+            Self::Aud => "AUD",
+            Self::Jpy => "JPY",
+        }
+    }
+
+    const fn decimal_places(self) -> usize {
+        match self {
+            Self::Jpy => 0,
+            _ => 2,
+        }
+    }
+}
+
+impl Display for FiatCurrency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for FiatCurrency {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|currency| currency.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| anyhow::anyhow!("Unknown currency: {s}"))
+    }
+}
+
+/// Where bitcoin prices are fetched from. Whichever source is used
+/// can see when Keystache is running and which currency it asks for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PriceSource {
+    #[default]
+    Mempool,
+    Coinbase,
+}
+
+impl PriceSource {
+    pub const ALL: [Self; 2] = [Self::Mempool, Self::Coinbase];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Mempool => "mempool",
+            Self::Coinbase => "coinbase",
+        }
+    }
+
+    /// The URL that the price of bitcoin in `currency` is fetched from.
+    pub fn price_url(self, currency: FiatCurrency) -> String {
+        match self {
+            Self::Mempool => "https://mempool.space/api/v1/prices".to_string(),
+            Self::Coinbase => format!("https://api.coinbase.com/v2/prices/BTC-{currency}/spot"),
+        }
+    }
+
+    /// Reads the price of bitcoin in `currency` from the JSON response to [`Self::price_url`].
+    pub fn read_exchange_rate(
+        self,
+        json: &str,
+        currency: FiatCurrency,
+    ) -> anyhow::Result<ExchangeRate> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+
+        let btc_price_or = match self {
+            Self::Mempool => value[currency.as_str()].as_f64(),
+            // Coinbase sends prices as strings, so that they aren't rounded.
+            Self::Coinbase => value["data"]["amount"]
+                .as_str()
+                .and_then(|amount| amount.parse().ok()),
+        };
+
+        let btc_price = btc_price_or
+            .filter(|btc_price: &f64| btc_price.is_finite() && *btc_price > 0.0)
+            .ok_or_else(|| anyhow::anyhow!("{self} didn't return a price in {currency}"))?;
+
+        Ok(ExchangeRate {
+            currency,
+            btc_price,
+        })
+    }
+}
+
+impl Display for PriceSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Mempool => write!(f, "mempool.space"),
+            Self::Coinbase => write!(f, "Coinbase"),
+        }
+    }
+}
+
+impl FromStr for PriceSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mempool" => Ok(Self::Mempool),
+            "coinbase" => Ok(Self::Coinbase),
+            _ => Err(anyhow::anyhow!("Unknown price source: {s}")),
+        }
+    }
+}
+
+/// The price of bitcoin in a fiat currency.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExchangeRate {
+    pub currency: FiatCurrency,
+    /// The price of one bitcoin.
+    pub btc_price: f64,
+}
+
+impl ExchangeRate {
+    // TODO: Remove this clippy allow.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn to_fiat(&self, amount: Amount) -> f64 {
+        amount.msats as f64 / MSATS_PER_BTC * self.btc_price
+    }
+
+    /// Converts `fiat` into bitcoin, rounded to the nearest millisatoshi.
+    /// Returns `None` if it's negative or too large to be an amount.
+    // TODO: Remove this clippy allow.
+    #[allow(clippy::cast_precision_loss, clippy::cast_sign_loss)]
+    pub fn to_amount(&self, fiat: f64) -> Option<Amount> {
+        let msats = (fiat / self.btc_price * MSATS_PER_BTC).round();
+
+        (msats.is_finite() && msats >= 0.0 && msats < u64::MAX as f64)
+            .then(|| Amount::from_msats(msats as u64))
+    }
+
+    /// Formats what `amount` is worth, such as `12.34 USD`.
+    pub fn format_fiat(&self, amount: Amount) -> String {
+        format!(
+            "{:.*} {}",
+            self.currency.decimal_places(),
+            self.to_fiat(amount),
+            self.currency
+        )
+    }
+}
+
+/// Reads an amount of fiat typed by the user, such as `12.50`.
+pub fn parse_fiat_input(input: &str) -> Option<f64> {
+    input
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|fiat| fiat.is_finite() && *fiat >= 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_exchange_rate() {
+        let mempool_json = r#"{"time": 1729000000, "USD": 65000, "EUR": 60000, "JPY": 9700000}"#;
+
+        assert_eq!(
+            PriceSource::Mempool
+                .read_exchange_rate(mempool_json, FiatCurrency::Eur)
+                .unwrap(),
+            ExchangeRate {
+                currency: FiatCurrency::Eur,
+                btc_price: 60_000.0,
+            }
+        );
+        assert!(PriceSource::Mempool
+            .read_exchange_rate(mempool_json, FiatCurrency::Gbp)
+            .is_err());
+
+        assert_eq!(
+            PriceSource::Coinbase
+                .read_exchange_rate(
+                    r#"{"data": {"amount": "65000.50", "base": "BTC", "currency": "USD"}}"#,
+                    FiatCurrency::Usd
+                )
+                .unwrap(),
+            ExchangeRate {
+                currency: FiatCurrency::Usd,
+                btc_price: 65_000.5,
+            }
+        );
+        assert!(PriceSource::Coinbase
+            .read_exchange_rate(r#"{"data": {"amount": "-1"}}"#, FiatCurrency::Usd)
+            .is_err());
+    }
+
+    #[test]
+    fn test_conversion() {
+        let exchange_rate = ExchangeRate {
+            currency: FiatCurrency::Usd,
+            btc_price: 50_000.0,
+        };
+
+        assert_eq!(
+            exchange_rate.format_fiat(Amount::from_sats(100_000)),
+            "50.00 USD"
+        );
+        assert_eq!(
+            exchange_rate.to_amount(50.0),
+            Some(Amount::from_sats(100_000))
+        );
+        assert_eq!(exchange_rate.to_amount(-1.0), None);
+        assert_eq!(exchange_rate.to_amount(f64::INFINITY), None);
+
+        let exchange_rate = ExchangeRate {
+            currency: FiatCurrency::Jpy,
+            btc_price: 9_700_000.0,
+        };
+
+        assert_eq!(
+            exchange_rate.format_fiat(Amount::from_sats(100_000)),
+            "9700 JPY"
+        );
+
+        assert_eq!(parse_fiat_input(" 12.50 "), Some(12.5));
+        assert_eq!(parse_fiat_input("-5"), None);
+        assert_eq!(parse_fiat_input("NaN"), None);
+        assert_eq!(parse_fiat_input("twelve"), None);
+    }
+}
@@ -0,0 +1,3215 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::{Display, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use chrono::{DateTime, NaiveDateTime};
+use directories::ProjectDirs;
+use fedimint_aead::LessSafeKey;
+use fedimint_bip39::Bip39RootSecretStrategy;
+use fedimint_client::{
+    backup::Metadata,
+    db::ApiSecretKey,
+    derivable_secret::{ChildId, DerivableSecret},
+    secret::RootSecretStrategy,
+    Client, ClientHandle,
+};
+use fedimint_core::{
+    config::{ClientConfig, FederationId},
+    core::OperationId,
+    db::{Database, IDatabaseTransactionOpsCoreTyped},
+    encoding::Encodable,
+    invite_code::InviteCode,
+    Amount,
+};
+use fedimint_ln_client::{LightningClientModule, LnPayState, LnReceiveState, PayType};
+use fedimint_ln_common::{bitcoin::hashes::sha256, LightningGateway, LightningGatewayAnnouncement};
+use fedimint_mint_client::{MintClientModule, OOBNotes, ReissueExternalNotesState};
+use fedimint_rocksdb::RocksDb;
+use futures::future::join_all;
+use lightning_invoice::{
+    Bolt11Invoice, Bolt11InvoiceDescription, Description, RoutingFees, Sha256,
+};
+use nostr_sdk::{
+    bip39::Mnemonic,
+    bitcoin::{
+        bip32::{ChildNumber, DerivationPath, Xpriv},
+        secp256k1::Secp256k1,
+        Network,
+    },
+    PublicKey,
+};
+use secp256k1::rand::{seq::SliceRandom, Rng, RngCore};
+use tokio::sync::{mpsc, oneshot, watch, Mutex, MutexGuard};
+use tokio_stream::StreamExt;
+
+use crate::{
+    clock::Clock,
+    config::Settings,
+    exchange_rate::ExchangeRate,
+    maintenance::get_directory_size,
+    nostr::NostrModule,
+    spend_approval::{self, SpendApprovalPolicy},
+    util::format_amount,
+};
+
+const FEDIMINT_CLIENTS_DATA_DIR_NAME: &str = "fedimint_clients";
+
+// Imported client databases are copied to a folder whose name starts with this prefix,
+// and only moved to their federation's data directory once they've been checked.
+const IMPORT_DIR_PREFIX: &str = "import-";
+
+// Federations being recovered are kept in a folder whose name starts with this prefix,
+// and only moved to their federation's data directory once recovery has finished.
+const RECOVERY_DIR_PREFIX: &str = "recovery-";
+
+/// The Bitcoin network that the wallet operates on. Building with the `regtest`
+/// feature switches to a local regtest network, such as one started by devimint.
+pub const WALLET_NETWORK: Network = if cfg!(feature = "regtest") {
+    Network::Regtest
+} else {
+    Network::Bitcoin
+};
+
+/// URI scheme of federation invite links, such as `fedimint:fed1...`.
+/// Installers register Keystache as its handler.
+pub const INVITE_URI_SCHEME: &str = "fedimint";
+
+/// Environment variable containing the invite code of the local regtest federation.
+/// devimint sets this automatically.
+#[cfg(feature = "regtest")]
+const REGTEST_INVITE_CODE_ENV_VAR: &str = "FM_INVITE_CODE";
+
+// TODO: Figure out if we even want this. If we do, it probably shouldn't live here.
+// It'd make more sense for it to live wherever the key is maintained elsewhere, and
+// have `Wallet::new()` assume that the key is already derived.
+const FEDIMINT_DERIVATION_NUMBER: u32 = 1;
+
+// Child of the wallet's derivable secret used to encrypt e-cash backup files.
+const ECASH_BACKUP_ENCRYPTION_CHILD_ID: ChildId = ChildId(0x6261_636b);
+
+// How long a simulated payment takes, so that loading states can still be seen.
+const SIMULATED_PAYMENT_DURATION: Duration = Duration::from_secs(1);
+
+/// How long spent e-cash notes can go unredeemed before the wallet tries to take them back.
+const SPENT_NOTES_CANCEL_AFTER: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+
+// How far each wait between federation checks strays from the update interval, either way.
+// Waits of random length keep network observers from picking out Keystache by a fixed cadence.
+const WALLET_VIEW_UPDATE_JITTER: f64 = 0.25;
+
+// Federations whose guardians take longer than this to answer are treated as unreachable.
+const FEDERATION_REACHABILITY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The largest invoice that apps can have paid without the user confirming
+/// that it's over the cap, unless the user has set a different cap.
+pub const DEFAULT_APP_PAYMENT_CAP_SATS: u64 = 50_000;
+
+// Federation config metadata keys used by guardians to make announcements.
+const META_WELCOME_MESSAGE_KEY: &str = "welcome_message";
+const META_NOTICE_MESSAGE_KEY: &str = "popup_countdown_message";
+const META_NOTICE_END_TIMESTAMP_KEY: &str = "popup_end_timestamp";
+const META_EXPIRY_TIMESTAMP_KEY: &str = "federation_expiry_timestamp";
+const META_ICON_URL_KEY: &str = "federation_icon_url";
+
+/// Picks how long to wait before the next federation check, somewhere
+/// within [`WALLET_VIEW_UPDATE_JITTER`] of `update_interval`.
+fn jitter_interval(update_interval: Duration, rng: &mut dyn RngCore) -> Duration {
+    update_interval.mul_f64(
+        rng.gen_range((1.0 - WALLET_VIEW_UPDATE_JITTER)..=(1.0 + WALLET_VIEW_UPDATE_JITTER)),
+    )
+}
+
+/// Whether enough of the federation's guardians answer within
+/// [`FEDERATION_REACHABILITY_TIMEOUT`] to agree on the current session.
+async fn is_federation_reachable(client: &ClientHandle, clock: &Clock) -> bool {
+    tokio::select! {
+        result = client.api().session_count() => result.is_ok(),
+        () = clock.sleep(FEDERATION_REACHABILITY_TIMEOUT) => false,
+    }
+}
+
+pub(crate) fn exceeds_payment_cap(amount_msats_or: Option<u64>, cap: Amount) -> bool {
+    !amount_msats_or.is_some_and(|amount_msats| amount_msats <= cap.msats)
+}
+
+pub enum LightningReceiveCompletion {
+    Success,
+    Failure,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalletView {
+    /// Each federation's view is shared between successive wallet views for as long as
+    /// it doesn't change, so cloning a wallet view doesn't copy every federation.
+    pub federations: BTreeMap<FederationId, Arc<FederationView>>,
+    /// Federations that have been joined but aren't connected, such as
+    /// when their client failed to start. These aren't in `federations`.
+    pub disconnected_federation_ids: BTreeSet<FederationId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FederationView {
+    pub federation_id: FederationId,
+    pub metadata: FederationMetadata,
+    pub balance: Amount,
+    pub gateways: Vec<LightningGatewayAnnouncement>,
+    pub announcements: FederationAnnouncements,
+    /// Whether the federation's guardians answered when this view was made.
+    pub is_reachable: bool,
+}
+
+impl FederationView {
+    /// Estimates the most that can be paid over lightning from this federation once gateway fees
+    /// are taken out. If the pinned gateway isn't listed, the gateway is picked at random,
+    /// so the highest fees of the gateways that could be picked are assumed.
+    /// Returns `None` if the federation has no gateways.
+    pub fn max_sendable_amount_or(
+        &self,
+        pinned_gateway_id_or: Option<GatewayId>,
+    ) -> Option<Amount> {
+        let pinned_gateways: Vec<_> = self
+            .gateways
+            .iter()
+            .filter(|gateway_announcement| {
+                Some(gateway_announcement.info.gateway_id) == pinned_gateway_id_or
+            })
+            .collect();
+
+        let vetted_gateways: Vec<_> = self
+            .gateways
+            .iter()
+            .filter(|gateway_announcement| gateway_announcement.vetted)
+            .collect();
+
+        // Same order of preference as `Wallet::select_gateway()`.
+        let candidate_gateways = [pinned_gateways, vetted_gateways]
+            .into_iter()
+            .find(|gateways| !gateways.is_empty())
+            .unwrap_or_else(|| self.gateways.iter().collect());
+
+        candidate_gateways
+            .into_iter()
+            .map(|gateway_announcement| {
+                max_amount_after_fees(self.balance, gateway_announcement.info.fees)
+            })
+            .min()
+    }
+}
+
+/// The largest whole-sat amount that, with `fees` added, can be paid out of `balance`.
+/// Rounded down to a whole sat since most wallets can only create invoices in sats.
+fn max_amount_after_fees(balance: Amount, fees: RoutingFees) -> Amount {
+    let available_msats = u128::from(balance.msats.saturating_sub(u64::from(fees.base_msat)));
+
+    let max_msats =
+        available_msats * 1_000_000 / (1_000_000 + u128::from(fees.proportional_millionths));
+
+    // Can't overflow, since `max_msats` is no more than `available_msats`.
+    let max_msats = u64::try_from(max_msats).unwrap_or_default();
+
+    Amount::from_msats(max_msats - max_msats % 1000)
+}
+
+/// A step of joining a federation, reported while the join runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinFederationStage {
+    DownloadingConfig,
+    InitializingModules,
+    Syncing,
+}
+
+/// A step of leaving a federation, reported while the leave runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeaveFederationStage {
+    CheckingBalance,
+    ShuttingDownClient,
+    DeletingData,
+}
+
+/// How far along a federation join or leave is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FederationOperationProgress {
+    Joining(JoinFederationStage),
+    Leaving(LeaveFederationStage),
+}
+
+impl FederationOperationProgress {
+    /// The number of the current step, starting at 1, along with the total number of steps.
+    pub const fn step(self) -> (u8, u8) {
+        match self {
+            Self::Joining(JoinFederationStage::DownloadingConfig)
+            | Self::Leaving(LeaveFederationStage::CheckingBalance) => (1, 3),
+            Self::Joining(JoinFederationStage::InitializingModules)
+            | Self::Leaving(LeaveFederationStage::ShuttingDownClient) => (2, 3),
+            Self::Joining(JoinFederationStage::Syncing)
+            | Self::Leaving(LeaveFederationStage::DeletingData) => (3, 3),
+        }
+    }
+}
+
+impl Display for FederationOperationProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let description = match self {
+            Self::Joining(JoinFederationStage::DownloadingConfig) => {
+                "Downloading the federation's config"
+            }
+            Self::Joining(JoinFederationStage::InitializingModules) => "Initializing modules",
+            Self::Joining(JoinFederationStage::Syncing) => "Syncing balance and gateways",
+            Self::Leaving(LeaveFederationStage::CheckingBalance) => "Checking balance",
+            Self::Leaving(LeaveFederationStage::ShuttingDownClient) => "Shutting down the client",
+            Self::Leaving(LeaveFederationStage::DeletingData) => "Deleting federation data",
+        };
+
+        let (step, step_count) = self.step();
+
+        write!(f, "Step {step} of {step_count}: {description}")
+    }
+}
+
+/// What a federation is called and who runs it, from its config. Saved in the database
+/// so that federations can be shown before their clients connect, such as when offline.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FederationMetadata {
+    pub name_or: Option<String>,
+    /// The kinds of the federation's modules, such as `ln` and `mint`.
+    pub module_kinds: Vec<String>,
+    pub guardian_names: Vec<String>,
+    pub icon_url_or: Option<String>,
+}
+
+impl FederationMetadata {
+    fn from_config(config: &ClientConfig) -> Self {
+        Self {
+            name_or: config.global.federation_name().map(ToString::to_string),
+            module_kinds: config
+                .modules
+                .values()
+                .map(|module| module.kind.to_string())
+                .collect(),
+            guardian_names: config
+                .global
+                .api_endpoints
+                .values()
+                .map(|peer| peer.name.clone())
+                .collect(),
+            icon_url_or: config.meta::<String>(META_ICON_URL_KEY).ok().flatten(),
+        }
+    }
+}
+
+/// Announcements published by a federation's guardians through its config metadata.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FederationAnnouncements {
+    pub welcome_message_or: Option<String>,
+    /// A time-limited notice, such as planned maintenance or downtime.
+    /// Only set while the notice hasn't expired.
+    pub notice_or: Option<String>,
+    /// Unix timestamp (in seconds) at which the federation plans to shut down.
+    pub expiry_timestamp_or: Option<u64>,
+}
+
+impl FederationAnnouncements {
+    fn from_config(config: &ClientConfig, now_secs: u64) -> Self {
+        let notice_end_timestamp_or = config
+            .meta::<u64>(META_NOTICE_END_TIMESTAMP_KEY)
+            .ok()
+            .flatten();
+
+        Self {
+            welcome_message_or: config
+                .meta::<String>(META_WELCOME_MESSAGE_KEY)
+                .ok()
+                .flatten(),
+            notice_or: config
+                .meta::<String>(META_NOTICE_MESSAGE_KEY)
+                .ok()
+                .flatten()
+                .filter(|_| notice_end_timestamp_or.is_none_or(|end| end > now_secs)),
+            expiry_timestamp_or: config.meta::<u64>(META_EXPIRY_TIMESTAMP_KEY).ok().flatten(),
+        }
+    }
+
+    /// Human-readable description of the federation's planned shutdown, if any.
+    pub fn expiry_message(&self) -> Option<String> {
+        let expiry_timestamp = i64::try_from(self.expiry_timestamp_or?).ok()?;
+        let expiry_time = DateTime::from_timestamp(expiry_timestamp, 0)?;
+
+        Some(format!(
+            "This federation will shut down on {}. Move your funds out before then.",
+            expiry_time.format("%Y-%m-%d")
+        ))
+    }
+}
+
+impl Display for FederationView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name_or_id = self
+            .metadata
+            .name_or
+            .clone()
+            .unwrap_or_else(|| self.federation_id.to_string());
+
+        let balance = format_amount(self.balance);
+
+        write!(f, "{name_or_id} ({balance})")
+    }
+}
+
+impl WalletView {
+    /// How many joined federations are connected, out of how many have been joined.
+    pub fn connected_federation_counts(&self) -> (usize, usize) {
+        let connected_federation_count = self.federations.len();
+
+        (
+            connected_federation_count,
+            connected_federation_count + self.disconnected_federation_ids.len(),
+        )
+    }
+
+    /// Compares this view against a previous one and returns every federation
+    /// whose balance crossed one of its alert thresholds in between. Federations
+    /// that weren't present in `previous_view` are never reported.
+    pub fn get_balance_threshold_crossings<'a>(
+        &'a self,
+        previous_view: &Self,
+        thresholds: &BTreeMap<FederationId, BalanceThresholds>,
+    ) -> Vec<(&'a FederationView, BalanceThresholdCrossing)> {
+        self.federations
+            .iter()
+            .filter_map(|(federation_id, federation_view)| {
+                let previous_federation_view = previous_view.federations.get(federation_id)?;
+
+                thresholds
+                    .get(federation_id)?
+                    .get_crossing(previous_federation_view.balance, federation_view.balance)
+                    .map(|crossing| (federation_view.as_ref(), crossing))
+            })
+            .collect()
+    }
+
+    /// Whether the guardians of at least one connected federation answered when this view was made.
+    pub fn is_any_federation_reachable(&self) -> bool {
+        self.federations
+            .values()
+            .any(|federation_view| federation_view.is_reachable)
+    }
+
+    /// Compares this view against a previous one and returns every federation announcement
+    /// that appeared or changed in between. Federations that weren't present in
+    /// `previous_view` are never reported, since their announcements are shown when joining.
+    pub fn get_new_announcements<'a>(
+        &'a self,
+        previous_view: &Self,
+    ) -> Vec<(&'a FederationView, String)> {
+        let mut new_announcements = Vec::new();
+
+        for (federation_id, federation_view) in &self.federations {
+            let Some(previous_federation_view) = previous_view.federations.get(federation_id)
+            else {
+                continue;
+            };
+
+            let announcements = &federation_view.announcements;
+            let previous_announcements = &previous_federation_view.announcements;
+
+            if let Some(notice) = &announcements.notice_or {
+                if previous_announcements.notice_or.as_ref() != Some(notice) {
+                    new_announcements.push((federation_view.as_ref(), notice.clone()));
+                }
+            }
+
+            if announcements.expiry_timestamp_or != previous_announcements.expiry_timestamp_or {
+                if let Some(expiry_message) = announcements.expiry_message() {
+                    new_announcements.push((federation_view.as_ref(), expiry_message));
+                }
+            }
+        }
+
+        new_announcements
+    }
+
+    /// Replaces every federation view that's unchanged since `previous_view` with the
+    /// previous one, so that unchanged federations stay shared rather than copied.
+    fn share_unchanged_federations(&mut self, previous_view: &Self) {
+        for (federation_id, federation_view) in &mut self.federations {
+            if let Some(previous_federation_view) = previous_view.federations.get(federation_id) {
+                if previous_federation_view == federation_view {
+                    *federation_view = previous_federation_view.clone();
+                }
+            }
+        }
+    }
+}
+
+/// User-configured ways of reaching a single federation's guardians,
+/// for federations whose guardians require more than their config says.
+// TODO: Add per-federation guardian endpoint overrides once the Fedimint client can be given
+// its endpoints when it's built. fedimint-client 0.4 only reads them from the process-wide
+// FM_WS_API_CONNECT_OVERRIDES environment variable, which can't be set per federation without
+// racing other threads. Rewriting the endpoints in the stored config would change the
+// federation ID, since it's a hash of those endpoints.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FederationApiOverrides {
+    /// Sent to the guardians with every request, by federations that require one.
+    pub api_secret_or: Option<String>,
+}
+
+impl FederationApiOverrides {
+    /// Whether nothing is overridden, so the federation's config is used as is.
+    pub const fn is_empty(&self) -> bool {
+        self.api_secret_or.is_none()
+    }
+}
+
+/// User-configured balance bounds for a single federation.
+/// Crossing the low or high bound triggers an alert.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BalanceThresholds {
+    pub low_or: Option<Amount>,
+    pub high_or: Option<Amount>,
+    /// The most that should be held in the federation. Invoices that
+    /// would take the balance above it can't be created, so that users
+    /// don't trust a single federation with too much of their funds.
+    pub max_or: Option<Amount>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceThresholdCrossing {
+    /// The balance dropped below the contained low threshold.
+    BelowLow(Amount),
+    /// The balance rose above the contained high threshold.
+    AboveHigh(Amount),
+}
+
+impl BalanceThresholds {
+    /// Returns the threshold crossed when a balance changes from `old_balance`
+    /// to `new_balance`, if any. Only transitions are reported, so a balance
+    /// that stays above the high threshold won't be reported again.
+    pub fn get_crossing(
+        &self,
+        old_balance: Amount,
+        new_balance: Amount,
+    ) -> Option<BalanceThresholdCrossing> {
+        if let Some(high) = self.high_or {
+            if old_balance <= high && new_balance > high {
+                return Some(BalanceThresholdCrossing::AboveHigh(high));
+            }
+        }
+
+        if let Some(low) = self.low_or {
+            if old_balance >= low && new_balance < low {
+                return Some(BalanceThresholdCrossing::BelowLow(low));
+            }
+        }
+
+        None
+    }
+
+    /// Whether the low threshold, if any, is at or below the high threshold, if any.
+    /// Otherwise a balance between them would be reported as both too low and too high.
+    pub fn is_valid(&self) -> bool {
+        match (self.low_or, self.high_or) {
+            (Some(low), Some(high)) => low <= high,
+            _ => true,
+        }
+    }
+
+    /// Whether receiving `incoming_amount` would take `balance` above the maximum balance.
+    pub fn would_exceed_max(&self, balance: Amount, incoming_amount: Amount) -> bool {
+        self.max_or
+            .is_some_and(|max| balance.msats.saturating_add(incoming_amount.msats) > max.msats)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentDirection {
+    Incoming,
+    Outgoing,
+}
+
+impl PaymentDirection {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Incoming => "incoming",
+            Self::Outgoing => "outgoing",
+        }
+    }
+}
+
+impl FromStr for PaymentDirection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "incoming" => Ok(Self::Incoming),
+            "outgoing" => Ok(Self::Outgoing),
+            _ => Err(anyhow::anyhow!("Unknown payment direction: {s}")),
+        }
+    }
+}
+
+/// Developer setting that replaces real lightning payments with simulated ones,
+/// so that payment flows can be tried out without moving any funds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PaymentSimulation {
+    /// Payments are made normally.
+    #[default]
+    Disabled,
+    /// Every payment succeeds without moving any funds.
+    Succeed,
+    /// Every payment fails without moving any funds.
+    Fail,
+}
+
+impl PaymentSimulation {
+    pub const ALL: [Self; 3] = [Self::Disabled, Self::Succeed, Self::Fail];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Disabled => "disabled",
+            Self::Succeed => "succeed",
+            Self::Fail => "fail",
+        }
+    }
+}
+
+impl Display for PaymentSimulation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Disabled => write!(f, "Disabled"),
+            Self::Succeed => write!(f, "Simulate successful payments"),
+            Self::Fail => write!(f, "Simulate failed payments"),
+        }
+    }
+}
+
+impl FromStr for PaymentSimulation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "disabled" => Ok(Self::Disabled),
+            "succeed" => Ok(Self::Succeed),
+            "fail" => Ok(Self::Fail),
+            _ => Err(anyhow::anyhow!("Unknown payment simulation: {s}")),
+        }
+    }
+}
+
+/// The result of successfully paying a lightning invoice.
+#[derive(Debug, Clone)]
+pub struct LightningPaymentOutcome {
+    /// Gateway fee paid on top of the invoice amount.
+    pub fee: Amount,
+    /// Hex-encoded payment preimage, if the federation reported one.
+    pub preimage_or: Option<String>,
+    /// The gateway the payment went through. `None` for simulated payments.
+    pub gateway_id_or: Option<GatewayId>,
+    /// Whether the payment was simulated, meaning no funds were actually moved.
+    pub is_simulated: bool,
+}
+
+/// A completed lightning payment, as recorded in the payment log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentRecord {
+    pub id: i32,
+    pub federation_id: FederationId,
+    pub direction: PaymentDirection,
+    pub amount: Amount,
+    /// Gateway fee paid on top of `amount`. Always zero for incoming payments.
+    pub fee: Amount,
+    pub bolt11_invoice_or: Option<Bolt11Invoice>,
+    /// Hex-encoded preimage of outgoing payments, if the federation reported one.
+    pub preimage_or: Option<String>,
+    /// The app that requested the payment over Wallet Connect, if any.
+    pub requester_public_key_or: Option<PublicKey>,
+    /// The gateway that outgoing payments went through. `None` for incoming
+    /// and simulated payments, and for payments logged before gateways were recorded.
+    pub gateway_id_or: Option<GatewayId>,
+    /// Whether the payment was made with [`PaymentSimulation`] enabled.
+    pub is_simulated: bool,
+    /// The batch the payment was made in, if it was paid along with other invoices.
+    pub batch_id_or: Option<i32>,
+    pub create_time: NaiveDateTime,
+}
+
+/// Identifies a lightning gateway within a federation.
+pub type GatewayId = fedimint_core::secp256k1::PublicKey;
+
+/// The fee actually paid through a gateway for a single outgoing payment,
+/// as recorded in the payment log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GatewayFeeSample {
+    /// The invoice amount.
+    pub amount: Amount,
+    /// How much more than `amount` was debited from the federation balance.
+    pub fee: Amount,
+    pub create_time: NaiveDateTime,
+}
+
+impl GatewayFeeSample {
+    pub fn fee_rate_ppm(&self) -> u64 {
+        fee_rate_ppm(self.amount, self.fee)
+    }
+}
+
+/// Totals of the fees actually paid through a gateway.
+/// Used to steer gateway selection towards gateways that have been cheap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GatewayFeeStats {
+    pub payment_count: u64,
+    pub amount: Amount,
+    pub fee: Amount,
+}
+
+impl GatewayFeeStats {
+    pub fn record(&mut self, amount: Amount, fee: Amount) {
+        self.payment_count += 1;
+        self.amount = Amount::from_msats(self.amount.msats.saturating_add(amount.msats));
+        self.fee = Amount::from_msats(self.fee.msats.saturating_add(fee.msats));
+    }
+
+    /// The fee paid per million msats sent, weighted by payment amount.
+    pub fn fee_rate_ppm(&self) -> u64 {
+        fee_rate_ppm(self.amount, self.fee)
+    }
+}
+
+/// `fee` in parts per million of `amount`. Zero if `amount` is zero.
+fn fee_rate_ppm(amount: Amount, fee: Amount) -> u64 {
+    if amount.msats == 0 {
+        return 0;
+    }
+
+    u64::try_from(u128::from(fee.msats) * 1_000_000 / u128::from(amount.msats)).unwrap_or(u64::MAX)
+}
+
+/// How much higher than the cheapest candidate's historical fee rate a gateway's can be,
+/// in percent, for it to still be picked. Leaves some room so that payments aren't all
+/// routed through a single gateway over small differences in fees.
+const GATEWAY_FEE_RATE_TOLERANCE_PERCENT: u64 = 150;
+
+/// Whether a gateway whose historical fee rate is `fee_rate_ppm_or` should be picked over others,
+/// given the cheapest historical fee rate among the candidates. Gateways that haven't been paid
+/// through yet have no fee rate and are always kept, so that they get a chance to be tried.
+fn is_cheap_gateway(fee_rate_ppm_or: Option<u64>, cheapest_fee_rate_ppm_or: Option<u64>) -> bool {
+    match (fee_rate_ppm_or, cheapest_fee_rate_ppm_or) {
+        (Some(fee_rate_ppm), Some(cheapest_fee_rate_ppm)) => {
+            u128::from(fee_rate_ppm) * 100
+                <= u128::from(cheapest_fee_rate_ppm)
+                    * u128::from(GATEWAY_FEE_RATE_TOLERANCE_PERCENT)
+        }
+        _ => true,
+    }
+}
+
+/// Fields of operation log entries that hold e-cash or payment secrets. They're hidden
+/// from [`OperationLogRecord`]s, so that records can be shared when reporting problems.
+const REDACTED_OPERATION_FIELDS: [&str; 2] = ["oob_notes", "preimage"];
+
+/// An entry of a federation's client operation log, as stored by fedimint.
+/// Used to debug payments that never finish. See [`Wallet::list_operations`].
+#[derive(Debug, Clone)]
+pub struct OperationLogRecord {
+    pub operation_id: String,
+    pub creation_time: NaiveDateTime,
+    /// The client module that started the operation, such as `ln` or `mint`.
+    pub module_kind: String,
+    pub meta: serde_json::Value,
+    /// The operation's final state, which is only recorded once something has waited for it.
+    pub outcome_or: Option<serde_json::Value>,
+}
+
+impl OperationLogRecord {
+    /// The whole record as pretty-printed JSON, for pasting into bug reports.
+    pub fn to_json(&self) -> String {
+        format!(
+            "{:#}",
+            serde_json::json!({
+                "operation_id": self.operation_id,
+                "creation_time": self.creation_time.and_utc().to_rfc3339(),
+                "module_kind": self.module_kind,
+                "meta": self.meta,
+                "outcome": self.outcome_or,
+            })
+        )
+    }
+}
+
+/// Replaces the values of [`REDACTED_OPERATION_FIELDS`] anywhere in `value`.
+fn redact_operation_fields(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if REDACTED_OPERATION_FIELDS.contains(&name.as_str()) {
+                    *field = serde_json::Value::String("[redacted]".to_string());
+                } else {
+                    redact_operation_fields(field);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_operation_fields),
+        _ => {}
+    }
+}
+
+/// How a [`TransactionRecord`] moved funds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionKind {
+    Lightning,
+    Ecash,
+}
+
+impl TransactionKind {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Lightning => "lightning",
+            Self::Ecash => "ecash",
+        }
+    }
+}
+
+impl Display for TransactionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lightning => write!(f, "Lightning"),
+            Self::Ecash => write!(f, "E-cash"),
+        }
+    }
+}
+
+impl FromStr for TransactionKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lightning" => Ok(Self::Lightning),
+            "ecash" => Ok(Self::Ecash),
+            _ => Err(anyhow::anyhow!("Unknown transaction kind: {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionStatus {
+    /// Still in progress, or finished without anything having waited for its outcome.
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+impl TransactionStatus {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+        }
+    }
+
+    /// Reads the status from an operation's outcome, which is the final state of the
+    /// operation's state machine. States are matched ignoring case and underscores,
+    /// since fedimint's modules don't all name them the same way.
+    fn from_outcome(outcome_or: Option<&serde_json::Value>) -> Self {
+        match outcome_or.and_then(variant_name).as_deref() {
+            Some("success" | "claimed" | "done") => Self::Succeeded,
+            Some(
+                "canceled" | "refunded" | "unexpectederror" | "failed" | "usercanceledsuccess",
+            ) => Self::Failed,
+            _ => Self::Pending,
+        }
+    }
+}
+
+impl Display for TransactionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pending => write!(f, "Pending"),
+            Self::Succeeded => write!(f, "Succeeded"),
+            Self::Failed => write!(f, "Failed"),
+        }
+    }
+}
+
+impl FromStr for TransactionStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "succeeded" => Ok(Self::Succeeded),
+            "failed" => Ok(Self::Failed),
+            _ => Err(anyhow::anyhow!("Unknown transaction status: {s}")),
+        }
+    }
+}
+
+/// A send or receive in a federation, read from its client's operation log. Unlike the
+/// payment log, it includes payments that failed or never finished, and e-cash. They're
+/// saved to the database, so that they're still listed after leaving the federation.
+/// See [`Wallet::list_transactions`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionRecord {
+    pub operation_id: String,
+    pub federation_id: FederationId,
+    /// The federation's name when the transaction was last read, if it has one.
+    pub federation_name_or: Option<String>,
+    pub kind: TransactionKind,
+    pub direction: PaymentDirection,
+    pub amount: Amount,
+    /// Gateway fee paid on top of `amount`. Always zero for incoming and e-cash transactions.
+    pub fee: Amount,
+    pub status: TransactionStatus,
+    pub create_time: NaiveDateTime,
+    /// The exchange rate around when the transaction was made, if one was fetched then.
+    pub exchange_rate_or: Option<ExchangeRate>,
+}
+
+impl TransactionRecord {
+    /// Reads a transaction from an operation. Returns `None` for operations that don't
+    /// send or receive funds themselves, such as lightning claims, and for modules
+    /// that Keystache doesn't use.
+    pub fn from_operation(
+        federation_id: FederationId,
+        federation_name_or: Option<String>,
+        operation: &OperationLogRecord,
+    ) -> Option<Self> {
+        let variant = operation.meta.get("variant")?;
+
+        let (kind, direction, amount, fee) = match (
+            operation.module_kind.as_str(),
+            variant_name(variant)?.as_str(),
+        ) {
+            ("ln", "pay") => {
+                let pay = &variant["pay"];
+
+                (
+                    TransactionKind::Lightning,
+                    PaymentDirection::Outgoing,
+                    invoice_amount(&pay["invoice"])?,
+                    read_amount(&pay["fee"]).unwrap_or(Amount::ZERO),
+                )
+            }
+            ("ln", "receive") => (
+                TransactionKind::Lightning,
+                PaymentDirection::Incoming,
+                invoice_amount(&variant["receive"]["invoice"])?,
+                Amount::ZERO,
+            ),
+            ("mint", "reissuance") => (
+                TransactionKind::Ecash,
+                PaymentDirection::Incoming,
+                read_amount(&operation.meta["amount"])?,
+                Amount::ZERO,
+            ),
+            ("mint", "spendoob") => (
+                TransactionKind::Ecash,
+                PaymentDirection::Outgoing,
+                read_amount(&operation.meta["amount"])?,
+                Amount::ZERO,
+            ),
+            _ => return None,
+        };
+
+        Some(Self {
+            operation_id: operation.operation_id.clone(),
+            federation_id,
+            federation_name_or,
+            kind,
+            direction,
+            amount,
+            fee,
+            status: TransactionStatus::from_outcome(operation.outcome_or.as_ref()),
+            create_time: operation.creation_time,
+            exchange_rate_or: None,
+        })
+    }
+}
+
+/// The name of the enum variant that `value` holds, lowercased and without underscores.
+/// Serde writes unit variants as strings, and other variants as objects with a single key.
+fn variant_name(value: &serde_json::Value) -> Option<String> {
+    let name = match value {
+        serde_json::Value::String(name) => name,
+        serde_json::Value::Object(fields) if fields.len() == 1 => fields.keys().next()?,
+        _ => return None,
+    };
+
+    Some(name.replace('_', "").to_lowercase())
+}
+
+/// Reads an amount in msats, written either as a number or as an object with a `msats` field.
+fn read_amount(value: &serde_json::Value) -> Option<Amount> {
+    value
+        .as_u64()
+        .or_else(|| value["msats"].as_u64())
+        .map(Amount::from_msats)
+}
+
+/// Reads the amount of an invoice written as a string. Invoices without an amount have none.
+fn invoice_amount(value: &serde_json::Value) -> Option<Amount> {
+    Bolt11Invoice::from_str(value.as_str()?)
+        .ok()?
+        .amount_milli_satoshis()
+        .map(Amount::from_msats)
+}
+
+pub struct Wallet {
+    derivable_secret: DerivableSecret,
+    clients: Arc<Mutex<HashMap<FederationId, ClientHandle>>>,
+    fedimint_clients_data_dir: PathBuf,
+    view_update_receiver: watch::Receiver<WalletView>,
+    // Used to tell `Self.view_update_task` to immediately update the view.
+    // If the view has changed, the task will yield a new view message.
+    // Then the oneshot sender is used to tell the caller that the view
+    // is now up to date (even if no new value was yielded).
+    force_update_view_sender: mpsc::Sender<oneshot::Sender<()>>,
+    view_update_task: tokio::task::JoinHandle<()>,
+    // Tells `Self.view_update_task` to stop, see `Self::shutdown()`.
+    shutdown_sender: watch::Sender<bool>,
+    payment_simulation: RwLock<PaymentSimulation>,
+    pinned_gateways: RwLock<BTreeMap<FederationId, GatewayId>>,
+    api_overrides: RwLock<BTreeMap<FederationId, FederationApiOverrides>>,
+    gateway_fee_stats: RwLock<BTreeMap<GatewayId, GatewayFeeStats>>,
+    app_payment_cap: RwLock<Amount>,
+    spend_approval_policy_or: RwLock<Option<SpendApprovalPolicy>>,
+    // The Nostr connection that spend approvals are requested over.
+    nostr_module_or: RwLock<Option<NostrModule>>,
+    clock: Clock,
+}
+
+impl Drop for Wallet {
+    fn drop(&mut self) {
+        // Only needed if the wallet wasn't shut down with `Self::shutdown()`,
+        // in which case the task has already stopped.
+        self.view_update_task.abort();
+    }
+}
+
+impl Wallet {
+    /// Creates a wallet. How often it checks its federations for changes is
+    /// read from `settings_receiver`, and takes effect as soon as it changes.
+    pub fn new(
+        xprivkey: Xpriv,
+        network: Network,
+        project_dirs: &ProjectDirs,
+        settings_receiver: watch::Receiver<Settings>,
+    ) -> Self {
+        Self::new_with_data_dir(
+            xprivkey,
+            network,
+            get_fedimint_clients_data_dir(network, project_dirs),
+            settings_receiver,
+            Clock::default(),
+        )
+    }
+
+    /// Like [`Self::new`], but keeps the federation clients' data in `fedimint_clients_data_dir`
+    /// and uses `clock` for timing, such as for tests and examples that shouldn't touch the app's
+    /// data directory.
+    pub fn new_with_data_dir(
+        xprivkey: Xpriv,
+        network: Network,
+        fedimint_clients_data_dir: PathBuf,
+        mut settings_receiver: watch::Receiver<Settings>,
+        clock: Clock,
+    ) -> Self {
+        // Joined federations count as disconnected until their clients connect, so that
+        // they can be listed (by their cached metadata) right away.
+        // TODO: Log a warning if the joined federations fail to be listed.
+        let (view_update_sender, view_update_receiver) = watch::channel(WalletView {
+            federations: BTreeMap::new(),
+            disconnected_federation_ids: list_joined_federation_ids(&fedimint_clients_data_dir)
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+        });
+
+        let (force_update_view_sender, mut force_update_view_receiver) =
+            mpsc::channel::<oneshot::Sender<()>>(100);
+
+        let (shutdown_sender, mut shutdown_receiver) = watch::channel(false);
+
+        let clients = Arc::new(Mutex::new(HashMap::new()));
+
+        let clients_clone = clients.clone();
+        let fedimint_clients_data_dir_clone = fedimint_clients_data_dir.clone();
+        let clock_clone = clock.clone();
+        let view_update_task = tokio::spawn(async move {
+            let mut last_state_or = None;
+
+            // TODO: Optimize this. Repeated polling is not ideal.
+            loop {
+                // Federations are only checked on request when refreshing on demand.
+                let update_interval_or = {
+                    let settings = settings_receiver.borrow();
+
+                    (!settings.refresh_balances_on_demand).then(|| {
+                        clock_clone.with_rng(|rng| {
+                            jitter_interval(settings.effective_wallet_view_update_interval(), rng)
+                        })
+                    })
+                };
+
+                // Wait either for a force update or for a timeout. If a force update
+                // occurs, then `force_update_completed_oneshot_or` will be `Some`.
+                // If a timeout occurs, then `force_update_completed_oneshot_or` will be `None`.
+                // Changed settings also end the wait, so that a new interval applies right away.
+                let force_update_completed_oneshot_or = tokio::select! {
+                    Some(force_update_completed_oneshot) = force_update_view_receiver.recv() => Some(force_update_completed_oneshot),
+                    () = clock_clone.sleep(update_interval_or.unwrap_or_default()), if update_interval_or.is_some() => None,
+                    Ok(()) = settings_receiver.changed() => None,
+                    Ok(()) = shutdown_receiver.changed() => break,
+                    // Nothing is left to wake the task, since the wallet and settings are gone.
+                    else => break,
+                };
+
+                let mut current_state = Self::get_current_state(
+                    clients_clone.lock().await,
+                    &fedimint_clients_data_dir_clone,
+                    &clock_clone,
+                )
+                .await;
+
+                // Ignoring clippy lint here since the `match` provides better clarity.
+                #[allow(clippy::option_if_let_else)]
+                let has_changed = match &last_state_or {
+                    Some(last_state) => {
+                        current_state.share_unchanged_federations(last_state);
+                        &current_state != last_state
+                    }
+                    // If there was no last state, the state has changed.
+                    None => true,
+                };
+
+                if has_changed {
+                    last_state_or = Some(current_state.clone());
+
+                    // If all receivers have been dropped, stop the task.
+                    if view_update_sender.send(current_state).is_err() {
+                        break;
+                    }
+                }
+
+                // If this iteration was triggered by a force update, then send a message
+                // back to the caller to indicate that the view is now up to date.
+                if let Some(force_update_completed_oneshot) = force_update_completed_oneshot_or {
+                    let _ = force_update_completed_oneshot.send(());
+                }
+            }
+        });
+
+        Self {
+            derivable_secret: get_derivable_secret(&xprivkey, network),
+            clients,
+            fedimint_clients_data_dir,
+            view_update_receiver,
+            force_update_view_sender,
+            view_update_task,
+            shutdown_sender,
+            payment_simulation: RwLock::new(PaymentSimulation::default()),
+            pinned_gateways: RwLock::new(BTreeMap::new()),
+            api_overrides: RwLock::new(BTreeMap::new()),
+            gateway_fee_stats: RwLock::new(BTreeMap::new()),
+            app_payment_cap: RwLock::new(Amount::from_sats(DEFAULT_APP_PAYMENT_CAP_SATS)),
+            spend_approval_policy_or: RwLock::new(None),
+            nostr_module_or: RwLock::new(None),
+            clock,
+        }
+    }
+
+    pub fn get_payment_simulation(&self) -> PaymentSimulation {
+        *self
+            .payment_simulation
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    pub fn set_payment_simulation(&self, payment_simulation: PaymentSimulation) {
+        *self
+            .payment_simulation
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = payment_simulation;
+    }
+
+    /// The gateway pinned for lightning payments with a federation, if any.
+    pub fn get_pinned_gateway(&self, federation_id: &FederationId) -> Option<GatewayId> {
+        self.pinned_gateways
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(federation_id)
+            .copied()
+    }
+
+    /// Replaces the pinned gateway of every federation.
+    pub fn set_pinned_gateways(&self, pinned_gateways: BTreeMap<FederationId, GatewayId>) {
+        *self
+            .pinned_gateways
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = pinned_gateways;
+    }
+
+    /// How a federation's guardians are reached instead of as its config says, if at all.
+    pub fn get_api_overrides(&self, federation_id: &FederationId) -> FederationApiOverrides {
+        self.api_overrides
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(federation_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Replaces the API overrides of every federation. They only apply to clients built
+    /// afterwards, so this should be called before connecting to any federations.
+    /// Use [`Self::apply_api_overrides`] to change a connected federation's overrides.
+    pub fn set_api_overrides(&self, api_overrides: BTreeMap<FederationId, FederationApiOverrides>) {
+        *self
+            .api_overrides
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = api_overrides;
+    }
+
+    /// Changes a federation's API overrides and reconnects to it, so that they apply right away.
+    /// If the federation can't be reconnected to, it stays disconnected until the next unlock.
+    pub async fn apply_api_overrides(
+        &self,
+        federation_id: FederationId,
+        api_overrides: FederationApiOverrides,
+    ) -> anyhow::Result<()> {
+        let mut clients = self.clients.lock().await;
+
+        {
+            let mut all_api_overrides = self
+                .api_overrides
+                .write()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+            if api_overrides.is_empty() {
+                all_api_overrides.remove(&federation_id);
+            } else {
+                all_api_overrides.insert(federation_id, api_overrides);
+            }
+        }
+
+        if let Some(client) = clients.remove(&federation_id) {
+            client.shutdown().await;
+
+            let db: Database = RocksDb::open(
+                self.fedimint_clients_data_dir
+                    .join(federation_id.to_string()),
+            )?
+            .into();
+
+            let client_result = self
+                .build_client_from_federation_id(federation_id, db)
+                .await;
+
+            let client = match client_result {
+                Ok(client) => client,
+                Err(err) => {
+                    self.force_update_view(clients).await;
+                    return Err(err);
+                }
+            };
+
+            clients.insert(federation_id, client);
+        }
+
+        self.force_update_view(clients).await;
+
+        Ok(())
+    }
+
+    /// Replaces the fee history of every gateway, which steers gateway selection.
+    /// Payments made afterwards are added to it as they complete.
+    pub fn set_gateway_fee_stats(&self, gateway_fee_stats: BTreeMap<GatewayId, GatewayFeeStats>) {
+        *self
+            .gateway_fee_stats
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = gateway_fee_stats;
+    }
+
+    /// The largest invoice that [`Self::pay_app_invoice`] pays without confirmation.
+    pub fn get_app_payment_cap(&self) -> Amount {
+        *self
+            .app_payment_cap
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    pub fn set_app_payment_cap(&self, app_payment_cap: Amount) {
+        *self
+            .app_payment_cap
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = app_payment_cap;
+    }
+
+    /// The two-person rule that payments over a threshold have to pass, if any.
+    /// Paying invoices and spending e-cash wait for the approver when it applies.
+    pub fn get_spend_approval_policy(&self) -> Option<SpendApprovalPolicy> {
+        self.spend_approval_policy_or
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Sets the policy without asking anyone, such as when loading the saved policy.
+    /// Use [`Self::change_spend_approval_policy`] for changes that the user makes.
+    pub fn set_spend_approval_policy(&self, spend_approval_policy_or: Option<SpendApprovalPolicy>) {
+        *self
+            .spend_approval_policy_or
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = spend_approval_policy_or;
+    }
+
+    /// Changes the spend approval policy once the current approver approves the change,
+    /// if it would let more through without them. See
+    /// [`spend_approval::request_policy_change_approval_if_required`].
+    pub async fn change_spend_approval_policy(
+        &self,
+        new_policy_or: Option<SpendApprovalPolicy>,
+    ) -> anyhow::Result<()> {
+        if let Some(policy) = self.get_spend_approval_policy() {
+            spend_approval::request_policy_change_approval_if_required(
+                &self.get_spend_approver_connection()?,
+                &policy,
+                new_policy_or.as_ref(),
+            )
+            .await?;
+        }
+
+        self.set_spend_approval_policy(new_policy_or);
+
+        Ok(())
+    }
+
+    /// Sets the Nostr connection that spend approvals are requested over.
+    /// Until it's set, anything that needs approval is refused.
+    pub fn set_nostr_module(&self, nostr_module: NostrModule) {
+        *self
+            .nostr_module_or
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(nostr_module);
+    }
+
+    fn get_spend_approver_connection(&self) -> anyhow::Result<NostrModule> {
+        self.nostr_module_or
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("The spend approver can't be reached yet"))
+    }
+
+    /// Waits for the spend approver to approve paying `invoice`, if the policy requires it.
+    async fn request_invoice_approval_if_required(
+        &self,
+        invoice: &Bolt11Invoice,
+    ) -> anyhow::Result<()> {
+        match self.get_spend_approval_policy() {
+            Some(policy) if policy.requires_approval(invoice) => {
+                spend_approval::request_approval(
+                    &self.get_spend_approver_connection()?,
+                    &policy,
+                    &spend_approval::invoice_summary(invoice),
+                )
+                .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether `invoice` is over the app payment cap. Invoices without
+    /// an amount are counted as over it, since they could be for anything.
+    pub fn exceeds_app_payment_cap(&self, invoice: &Bolt11Invoice) -> bool {
+        exceeds_payment_cap(invoice.amount_milli_satoshis(), self.get_app_payment_cap())
+    }
+
+    /// Gets the size in bytes of the data stored on disk for each joined federation.
+    pub fn get_federation_data_sizes(&self) -> anyhow::Result<BTreeMap<FederationId, u64>> {
+        let mut sizes = BTreeMap::new();
+
+        for entry in std::fs::read_dir(&self.fedimint_clients_data_dir)? {
+            let entry = entry?;
+
+            // Skip anything that isn't a federation's data directory.
+            let Some(federation_id) = entry
+                .file_name()
+                .into_string()
+                .ok()
+                .and_then(|federation_id| federation_id.parse::<FederationId>().ok())
+            else {
+                continue;
+            };
+
+            sizes.insert(federation_id, get_directory_size(&entry.path())?);
+        }
+
+        Ok(sizes)
+    }
+
+    pub fn get_update_stream(&self) -> tokio_stream::wrappers::WatchStream<WalletView> {
+        tokio_stream::wrappers::WatchStream::new(self.view_update_receiver.clone())
+    }
+
+    /// Checks the federations for changes right away, and waits for the view to be updated.
+    /// This is how balances are refreshed when [`Settings::refresh_balances_on_demand`] is set.
+    pub async fn refresh_view(&self) {
+        self.force_update_view(self.clients.lock().await).await;
+    }
+
+    /// Stops updating the view and shuts down every federation client, so that their
+    /// databases are closed cleanly. Should be called before exiting, since dropping
+    /// the wallet can't wait for the clients to finish writing.
+    /// The wallet can't be used for payments afterwards.
+    pub async fn shutdown(&self) {
+        // If the task has already stopped, there's nothing to tell.
+        let _ = self.shutdown_sender.send(true);
+
+        let clients: Vec<ClientHandle> = self
+            .clients
+            .lock()
+            .await
+            .drain()
+            .map(|(_federation_id, client)| client)
+            .collect();
+
+        join_all(clients.into_iter().map(ClientHandle::shutdown)).await;
+    }
+
+    /// Tell `view_update_task` to update the view, and wait for it to complete.
+    /// This ensures any streams opened by `get_update_stream`  have yielded the
+    /// latest view. This function should be called at the end of any function
+    /// that modifies the view.
+    ///
+    /// Note: This function takes a `MutexGuard` to ensure that the lock isn't
+    /// held while waiting for the view to update, which could cause a deadlock.
+    async fn force_update_view(
+        &self,
+        clients: MutexGuard<'_, HashMap<FederationId, ClientHandle>>,
+    ) {
+        drop(clients);
+        let (sender, receiver) = oneshot::channel();
+        let _ = self.force_update_view_sender.send(sender).await;
+        let _ = receiver.await;
+    }
+
+    pub async fn connect_to_joined_federations(&self) -> anyhow::Result<()> {
+        // Note: We're intentionally locking the clients mutex earlier than
+        // necessary so that the lock is held while we're accessing the data directory.
+        let mut clients = self.clients.lock().await;
+
+        for federation_id in list_joined_federation_ids(&self.fedimint_clients_data_dir)? {
+            // Skip if we're already connected to this federation.
+            if clients.contains_key(&federation_id) {
+                continue;
+            }
+
+            let db: Database = RocksDb::open(
+                self.fedimint_clients_data_dir
+                    .join(federation_id.to_string()),
+            )?
+            .into();
+
+            let client = self
+                .build_client_from_federation_id(federation_id, db)
+                .await?;
+
+            clients.insert(federation_id, client);
+        }
+
+        self.force_update_view(clients).await;
+
+        Ok(())
+    }
+
+    /// Joins the local regtest federation whose invite code is set in the environment.
+    #[cfg(feature = "regtest")]
+    pub async fn join_regtest_federation(&self) -> anyhow::Result<FederationId> {
+        let invite_code = InviteCode::from_str(&std::env::var(REGTEST_INVITE_CODE_ENV_VAR)?)?;
+        let federation_id = invite_code.federation_id();
+
+        self.join_federation(invite_code, |_| {}).await?;
+
+        Ok(federation_id)
+    }
+
+    /// Joins the federation that `invite_code` is for.
+    /// `on_stage` is called as the join moves through each [`JoinFederationStage`].
+    pub async fn join_federation(
+        &self,
+        invite_code: InviteCode,
+        on_stage: impl Fn(JoinFederationStage) + Send + Sync,
+    ) -> anyhow::Result<()> {
+        // Note: We're intentionally locking the clients mutex earlier than
+        // necessary so that the lock is held while we're accessing the data directory.
+        let mut clients = self.clients.lock().await;
+
+        let federation_id = invite_code.federation_id();
+
+        let federation_data_dir = self
+            .fedimint_clients_data_dir
+            .join(federation_id.to_string());
+
+        // Short-circuit if we're already connected to this federation.
+        if federation_data_dir.is_dir() {
+            return Ok(());
+        }
+
+        let db: Database = RocksDb::open(federation_data_dir)?.into();
+
+        let client = self
+            .build_client_from_invite_code(invite_code, db, &on_stage)
+            .await?;
+
+        clients.insert(federation_id, client);
+
+        on_stage(JoinFederationStage::Syncing);
+
+        self.force_update_view(clients).await;
+
+        Ok(())
+    }
+
+    /// Imports a Fedimint client database made by another app, such as Fedi or `fedimint-cli`.
+    /// The database is copied into Keystache's data directory, so the other app should be closed
+    /// and should stop using the federation once the import has finished.
+    ///
+    /// `mnemonic` must be the seed phrase that the other app opened the client with.
+    /// If the database has a client secret stored in it, the two must match.
+    pub async fn import_federation(
+        &self,
+        source_dir: &Path,
+        mnemonic: &Mnemonic,
+    ) -> anyhow::Result<FederationId> {
+        // Note: We're intentionally locking the clients mutex earlier than
+        // necessary so that the lock is held while we're accessing the data directory.
+        let mut clients = self.clients.lock().await;
+
+        // The database is copied somewhere whose name isn't a federation ID, so that it isn't
+        // picked up by `connect_to_joined_federations()` until it's been checked.
+        let import_dir = self.fedimint_clients_data_dir.join(format!(
+            "{IMPORT_DIR_PREFIX}{:016x}",
+            self.clock.with_rng(|rng| rng.gen::<u64>())
+        ));
+
+        copy_directory(source_dir, &import_dir)?;
+
+        let federation_id_result = self.check_imported_client(&import_dir, mnemonic).await;
+
+        let federation_data_dir_or = federation_id_result.as_ref().ok().map(|federation_id| {
+            self.fedimint_clients_data_dir
+                .join(federation_id.to_string())
+        });
+
+        let federation_id = match (federation_id_result, federation_data_dir_or) {
+            (Ok(federation_id), Some(federation_data_dir)) if !federation_data_dir.exists() => {
+                std::fs::rename(&import_dir, federation_data_dir)?;
+                federation_id
+            }
+            (Ok(_), _) => {
+                std::fs::remove_dir_all(&import_dir)?;
+                return Err(anyhow::anyhow!("You've already joined this federation"));
+            }
+            (Err(err), _) => {
+                std::fs::remove_dir_all(&import_dir)?;
+                return Err(err);
+            }
+        };
+
+        let db: Database = RocksDb::open(
+            self.fedimint_clients_data_dir
+                .join(federation_id.to_string()),
+        )?
+        .into();
+
+        let client = self
+            .build_client_from_federation_id(federation_id, db)
+            .await?;
+
+        clients.insert(federation_id, client);
+
+        self.force_update_view(clients).await;
+
+        Ok(federation_id)
+    }
+
+    /// Opens an imported client database with the secret from `mnemonic`, storing the secret in
+    /// the database if it isn't already there. Returns the ID of the client's federation.
+    async fn check_imported_client(
+        &self,
+        import_dir: &Path,
+        mnemonic: &Mnemonic,
+    ) -> anyhow::Result<FederationId> {
+        let db: Database = RocksDb::open(import_dir)?.into();
+
+        let federation_id = Client::get_config_from_db(&db)
+            .await
+            .ok_or_else(|| {
+                anyhow::anyhow!("The folder doesn't contain a Fedimint client database")
+            })?
+            .calculate_federation_id();
+
+        match Client::load_decodable_client_secret::<Vec<u8>>(&db).await {
+            Ok(entropy) if entropy != mnemonic.to_entropy() => {
+                return Err(anyhow::anyhow!(
+                    "The seed phrase doesn't match the one the client database was made with"
+                ));
+            }
+            Ok(_) => {}
+            Err(_) => Client::store_encodable_client_secret(&db, mnemonic.to_entropy()).await?,
+        }
+
+        // Make sure the client opens with the modules that Keystache supports.
+        self.build_client_from_federation_id(federation_id, db)
+            .await?
+            .shutdown()
+            .await;
+
+        Ok(federation_id)
+    }
+
+    /// Joins a federation and recovers the e-cash that the wallet's seed held in it, such as
+    /// after restoring the wallet from its seed on a new device. Starts from the federation's
+    /// latest e-cash backup for the seed if it has one, and otherwise scans the federation's
+    /// whole history, which can take a long time. Returns once recovery has finished.
+    /// A recovery that was interrupted picks up where it left off when this is called again.
+    pub async fn recover_federation(&self, invite_code: InviteCode) -> anyhow::Result<()> {
+        let federation_id = invite_code.federation_id();
+
+        let federation_data_dir = self
+            .fedimint_clients_data_dir
+            .join(federation_id.to_string());
+
+        let recovery_dir = self
+            .fedimint_clients_data_dir
+            .join(format!("{RECOVERY_DIR_PREFIX}{federation_id}"));
+
+        let client = {
+            // Note: We're intentionally locking the clients mutex earlier than
+            // necessary so that the lock is held while we're accessing the data directory.
+            let _clients = self.clients.lock().await;
+
+            // Short-circuit if we're already connected to this federation.
+            if federation_data_dir.is_dir() {
+                return Ok(());
+            }
+
+            let db: Database = RocksDb::open(&recovery_dir)?.into();
+
+            let is_initialized = fedimint_client::Client::is_initialized(&db).await;
+
+            let api_overrides = self.get_api_overrides(&federation_id);
+
+            if let Some(api_secret) = &api_overrides.api_secret_or {
+                store_api_secret(&db, api_secret).await;
+            }
+
+            let mut client_builder = Client::builder(db).await?;
+
+            // Add lightning and e-cash modules. For now we don't support on-chain.
+            client_builder.with_module(fedimint_mint_client::MintClientInit);
+            client_builder.with_module(fedimint_ln_client::LightningClientInit::default());
+
+            client_builder.with_primary_module(1);
+
+            if is_initialized {
+                client_builder.open(self.derivable_secret.clone()).await?
+            } else {
+                let config = fedimint_api_client::download_from_invite_code(&invite_code).await?;
+
+                let api_secret_or = api_overrides
+                    .api_secret_or
+                    .or_else(|| invite_code.api_secret());
+
+                let backup_or = client_builder
+                    .download_backup_from_federation(
+                        &self.derivable_secret,
+                        &config,
+                        api_secret_or.clone(),
+                    )
+                    .await?;
+
+                client_builder
+                    .recover(
+                        self.derivable_secret.clone(),
+                        config,
+                        api_secret_or,
+                        backup_or,
+                    )
+                    .await?
+            }
+        };
+
+        // The clients mutex isn't held while recovering, since that would block the rest of the
+        // wallet until recovery has finished. The recovering client's folder isn't named after a
+        // federation ID, so it isn't picked up by `connect_to_joined_federations()` meanwhile.
+        client.wait_for_all_recoveries().await?;
+
+        // A recovered client only loads its modules once it's reopened.
+        client.shutdown().await;
+
+        let mut clients = self.clients.lock().await;
+
+        if federation_data_dir.exists() {
+            return Err(anyhow::anyhow!(
+                "The federation was joined while its e-cash was being recovered"
+            ));
+        }
+
+        std::fs::rename(&recovery_dir, &federation_data_dir)?;
+
+        let db: Database = RocksDb::open(&federation_data_dir)?.into();
+
+        let client = self
+            .build_client_from_federation_id(federation_id, db)
+            .await?;
+
+        clients.insert(federation_id, client);
+
+        self.force_update_view(clients).await;
+
+        Ok(())
+    }
+
+    // TODO: Call `ClientModule::leave()` for every module.
+    // https://docs.rs/fedimint-client/0.4.2/fedimint_client/module/trait.ClientModule.html#method.leave
+    // Currently it isn't implemented for the `LightningClientModule`, so for now we're just checking
+    // that the client has a zero balance.
+    /// Leaves a federation, deleting its client database. Fails if the federation has a balance.
+    /// `on_stage` is called as the leave moves through each [`LeaveFederationStage`].
+    pub async fn leave_federation(
+        &self,
+        federation_id: FederationId,
+        on_stage: impl Fn(LeaveFederationStage) + Send + Sync,
+    ) -> anyhow::Result<()> {
+        // Note: We're intentionally locking the clients mutex earlier than
+        // necessary so that the lock is held while we're accessing the data directory.
+        let mut clients = self.clients.lock().await;
+
+        on_stage(LeaveFederationStage::CheckingBalance);
+
+        if let Some(client) = clients.remove(&federation_id) {
+            if client.get_balance().await.msats != 0 {
+                // Re-insert the client back into the clients map.
+                clients.insert(federation_id, client);
+
+                return Err(anyhow::anyhow!(
+                    "Cannot leave federation with non-zero balance: {}",
+                    federation_id
+                ));
+            }
+
+            on_stage(LeaveFederationStage::ShuttingDownClient);
+
+            client.shutdown().await;
+
+            let federation_data_dir = self
+                .fedimint_clients_data_dir
+                .join(federation_id.to_string());
+
+            on_stage(LeaveFederationStage::DeletingData);
+
+            if federation_data_dir.is_dir() {
+                std::fs::remove_dir_all(federation_data_dir)?;
+            }
+        }
+
+        self.force_update_view(clients).await;
+
+        Ok(())
+    }
+
+    /// Constructs the current view of the wallet.
+    /// SHOULD ONLY BE CALLED FROM THE `view_update_task`.
+    /// This way, `view_update_task` can only yield values
+    /// when the view is changed, with the guarantee that
+    /// the view hasn't been updated elsewhere in a way that
+    /// could de-sync the view.
+    async fn get_current_state(
+        clients: MutexGuard<'_, HashMap<FederationId, ClientHandle>>,
+        fedimint_clients_data_dir: &Path,
+        clock: &Clock,
+    ) -> WalletView {
+        let mut federations = BTreeMap::new();
+
+        // Every federation is checked at once, so that a single unreachable
+        // federation delays the view by at most the timeout.
+        let reachable_federation_ids: BTreeSet<FederationId> =
+            join_all(clients.iter().map(|(federation_id, client)| async move {
+                is_federation_reachable(client, clock)
+                    .await
+                    .then_some(*federation_id)
+            }))
+            .await
+            .into_iter()
+            .flatten()
+            .collect();
+
+        for (federation_id, client) in clients.iter() {
+            let lightning_module = client.get_first_module::<LightningClientModule>();
+            let gateways = lightning_module.list_gateways().await;
+            let config = client.config().await;
+
+            federations.insert(
+                *federation_id,
+                Arc::new(FederationView {
+                    federation_id: *federation_id,
+                    metadata: FederationMetadata::from_config(&config),
+                    balance: client.get_balance().await,
+                    gateways,
+                    announcements: FederationAnnouncements::from_config(
+                        &config,
+                        clock.unix_timestamp_secs(),
+                    ),
+                    is_reachable: reachable_federation_ids.contains(federation_id),
+                }),
+            );
+        }
+
+        // TODO: Log a warning if the joined federations fail to be listed.
+        let disconnected_federation_ids = list_joined_federation_ids(fedimint_clients_data_dir)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|federation_id| !clients.contains_key(federation_id))
+            .collect();
+
+        WalletView {
+            federations,
+            disconnected_federation_ids,
+        }
+    }
+
+    /// Pays a lightning invoice from the given federation.
+    /// If the payment fails through the federation's pinned gateway before anything
+    /// is spent, or is refunded, it's retried once through a gateway picked as if
+    /// none were pinned.
+    /// Payments over the spend approval threshold wait for the approver first.
+    /// If [`PaymentSimulation`] is enabled, the payment is simulated instead.
+    pub async fn pay_invoice(
+        &self,
+        invoice: Bolt11Invoice,
+        federation_id: FederationId,
+    ) -> anyhow::Result<LightningPaymentOutcome> {
+        // The clients aren't locked while waiting, since the approver may take a while.
+        self.request_invoice_approval_if_required(&invoice).await?;
+
+        let payment_simulation = self.get_payment_simulation();
+
+        if payment_simulation != PaymentSimulation::Disabled {
+            return self.simulate_payment(payment_simulation).await;
+        }
+
+        let clients = self.clients.lock().await;
+
+        let client = clients
+            .get(&federation_id)
+            .ok_or_else(|| anyhow::anyhow!("Client for federation {} not found", federation_id))?;
+
+        let gateways = client
+            .get_first_module::<LightningClientModule>()
+            .list_gateways()
+            .await;
+
+        let pinned_gateway_id_or = self.get_pinned_gateway(&federation_id);
+        let gateway_or = self.select_gateway(&gateways, pinned_gateway_id_or);
+        let is_pinned_gateway = gateway_or.is_some()
+            && gateway_or.as_ref().map(|gateway| gateway.gateway_id) == pinned_gateway_id_or;
+
+        let payment_outcome = match self
+            .pay_invoice_through_gateway(client, gateway_or, invoice.clone())
+            .await
+        {
+            Ok(payment_outcome) => payment_outcome,
+            // Only payments that are known not to have gone through are tried again.
+            // A payment whose outcome isn't known could still succeed, and be paid twice.
+            Err(GatewayPaymentError::NotPaid(err)) if is_pinned_gateway => {
+                let unpinned_gateways: Vec<_> = gateways
+                    .into_iter()
+                    .filter(|gateway_announcement| {
+                        Some(gateway_announcement.info.gateway_id) != pinned_gateway_id_or
+                    })
+                    .collect();
+
+                if unpinned_gateways.is_empty() {
+                    return Err(err);
+                }
+
+                self.pay_invoice_through_gateway(
+                    client,
+                    self.select_gateway(&unpinned_gateways, None),
+                    invoice,
+                )
+                .await
+                .map_err(GatewayPaymentError::into_inner)?
+            }
+            Err(err) => return Err(err.into_inner()),
+        };
+
+        self.force_update_view(clients).await;
+
+        Ok(payment_outcome)
+    }
+
+    async fn pay_invoice_through_gateway(
+        &self,
+        client: &ClientHandle,
+        gateway_or: Option<LightningGateway>,
+        invoice: Bolt11Invoice,
+    ) -> Result<LightningPaymentOutcome, GatewayPaymentError> {
+        let lightning_module = client.get_first_module::<LightningClientModule>();
+
+        let gateway_id_or = gateway_or.as_ref().map(|gateway| gateway.gateway_id);
+        let amount = Amount::from_msats(invoice.amount_milli_satoshis().unwrap_or_default());
+
+        // Nothing has been paid until the outgoing contract is funded.
+        let payment_info = lightning_module
+            .pay_bolt11_invoice(gateway_or, invoice, ())
+            .await
+            .map_err(GatewayPaymentError::NotPaid)?;
+
+        let payment_result_or = match lightning_module
+            .wait_for_ln_payment(payment_info.payment_type, payment_info.contract_id, false)
+            .await
+        {
+            Ok(payment_result_or) => payment_result_or,
+            Err(err) => {
+                let is_refunded = match payment_info.payment_type {
+                    PayType::Lightning(operation_id) => {
+                        is_ln_payment_refunded(client, operation_id).await
+                    }
+                    // Internal payments don't go through a gateway, so another gateway can't help.
+                    PayType::Internal(_) => false,
+                };
+
+                return Err(if is_refunded {
+                    GatewayPaymentError::NotPaid(err)
+                } else {
+                    GatewayPaymentError::Unknown(err)
+                });
+            }
+        };
+
+        // The fee is the contract amount the federation debited, less the invoice amount.
+        if let Some(gateway_id) = gateway_id_or {
+            self.gateway_fee_stats
+                .write()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .entry(gateway_id)
+                .or_default()
+                .record(amount, payment_info.fee);
+        }
+
+        Ok(LightningPaymentOutcome {
+            fee: payment_info.fee,
+            preimage_or: payment_result_or.and_then(|payment_result| {
+                payment_result
+                    .get("preimage")
+                    .and_then(serde_json::Value::as_str)
+                    .map(ToString::to_string)
+            }),
+            gateway_id_or,
+            is_simulated: false,
+        })
+    }
+
+    /// Pays a lightning invoice that an app asked for, such as over Nostr Wallet Connect.
+    /// Invoices over the app payment cap are refused unless `is_confirmed_over_cap` is set,
+    /// which should only be done once the user has confirmed that specific payment.
+    /// The cap applies on top of any per-app budget or policy.
+    pub async fn pay_app_invoice(
+        &self,
+        invoice: Bolt11Invoice,
+        federation_id: FederationId,
+        is_confirmed_over_cap: bool,
+    ) -> anyhow::Result<LightningPaymentOutcome> {
+        if !is_confirmed_over_cap && self.exceeds_app_payment_cap(&invoice) {
+            anyhow::bail!(
+                "The invoice is over the {} sat cap for payments that apps ask for, so it has to be confirmed first",
+                self.get_app_payment_cap().sats_round_down()
+            );
+        }
+
+        self.pay_invoice(invoice, federation_id).await
+    }
+
+    async fn simulate_payment(
+        &self,
+        payment_simulation: PaymentSimulation,
+    ) -> anyhow::Result<LightningPaymentOutcome> {
+        self.clock.sleep(SIMULATED_PAYMENT_DURATION).await;
+
+        if payment_simulation == PaymentSimulation::Fail {
+            return Err(anyhow::anyhow!("Simulated payment failure"));
+        }
+
+        // The preimage is random, so it won't match the invoice's payment hash.
+        let preimage: [u8; 32] = self.clock.with_rng(|rng| rng.gen());
+
+        Ok(LightningPaymentOutcome {
+            fee: Amount::ZERO,
+            preimage_or: Some(preimage.iter().fold(String::new(), |mut hex, byte| {
+                // Writing to a `String` can't fail.
+                let _ = write!(hex, "{byte:02x}");
+                hex
+            })),
+            gateway_id_or: None,
+            is_simulated: true,
+        })
+    }
+
+    /// Creates an e-cash backup for every joined federation, for writing to backup files.
+    /// Each backup is encrypted with a key derived from the wallet's seed, so it can
+    /// only be restored by someone who has the seed.
+    pub async fn create_encrypted_ecash_backups(
+        &self,
+    ) -> anyhow::Result<Vec<(FederationId, Vec<u8>)>> {
+        let clients = self.clients.lock().await;
+
+        let key = LessSafeKey::new(
+            self.derivable_secret
+                .child_key(ECASH_BACKUP_ENCRYPTION_CHILD_ID)
+                .to_chacha20_poly1305_key(),
+        );
+
+        let mut backups = Vec::new();
+
+        for (federation_id, client) in clients.iter() {
+            let backup = client.create_backup(Metadata::empty()).await?;
+
+            backups.push((
+                *federation_id,
+                fedimint_aead::encrypt(backup.consensus_encode_to_vec(), &key)?,
+            ));
+        }
+
+        Ok(backups)
+    }
+
+    /// Spends at least `amount` of e-cash from a federation as out-of-band notes, which anyone
+    /// holding them can redeem. The notes include an invite code, so that the recipient can
+    /// join the federation first. Notes that nobody redeems within [`SPENT_NOTES_CANCEL_AFTER`]
+    /// are taken back into the wallet. If the notes come to more than the spend approval
+    /// threshold, they're only returned once the approver approves their actual amount,
+    /// and are taken back right away otherwise. See [`SpendApprovalPolicy`].
+    pub async fn spend_notes(
+        &self,
+        federation_id: FederationId,
+        amount: Amount,
+    ) -> anyhow::Result<OOBNotes> {
+        let clients = self.clients.lock().await;
+
+        let client = clients
+            .get(&federation_id)
+            .ok_or_else(|| anyhow::anyhow!("Client for federation {} not found", federation_id))?;
+
+        // The notes spent can be worth more than `amount`, since
+        // they're made up of notes that the wallet already holds.
+        let (operation_id, notes) = client
+            .get_first_module::<MintClientModule>()
+            .spend_notes(amount, SPENT_NOTES_CANCEL_AFTER, true, ())
+            .await?;
+
+        self.force_update_view(clients).await;
+
+        let Some(policy) = self
+            .get_spend_approval_policy()
+            .filter(|policy| notes.total_amount() > policy.threshold)
+        else {
+            return Ok(notes);
+        };
+
+        // Nobody else has the notes yet, so they can still be taken back if it isn't approved.
+        let approval_result = match self.get_spend_approver_connection() {
+            Ok(nostr_module) => {
+                spend_approval::request_approval(
+                    &nostr_module,
+                    &policy,
+                    &spend_approval::ecash_summary(notes.total_amount()),
+                )
+                .await
+            }
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = approval_result {
+            let clients = self.clients.lock().await;
+
+            if let Some(client) = clients.get(&federation_id) {
+                client
+                    .get_first_module::<MintClientModule>()
+                    .try_cancel_spend_notes(operation_id)
+                    .await;
+            }
+
+            self.force_update_view(clients).await;
+
+            return Err(err);
+        }
+
+        Ok(notes)
+    }
+
+    /// Redeems out-of-band e-cash notes into the joined federation that issued them, and
+    /// waits until the federation has reissued them to this wallet. Returns the amount redeemed.
+    pub async fn reissue_notes(&self, notes: OOBNotes) -> anyhow::Result<Amount> {
+        let clients = self.clients.lock().await;
+
+        let federation_id_prefix = notes.federation_id_prefix();
+
+        let client = clients
+            .iter()
+            .find(|(federation_id, _)| federation_id.to_prefix() == federation_id_prefix)
+            .map(|(_, client)| client)
+            .ok_or_else(|| {
+                anyhow::anyhow!("Join the federation that these notes are from to redeem them")
+            })?;
+
+        let amount = notes.total_amount();
+
+        let mut update_stream = {
+            let mint_module = client.get_first_module::<MintClientModule>();
+
+            let operation_id = mint_module.reissue_external_notes(notes, ()).await?;
+
+            mint_module
+                .subscribe_reissue_external_notes(operation_id)
+                .await?
+                .into_stream()
+        };
+
+        self.force_update_view(clients).await;
+
+        while let Some(update) = update_stream.next().await {
+            match update {
+                ReissueExternalNotesState::Done => {
+                    self.refresh_view().await;
+
+                    return Ok(amount);
+                }
+                ReissueExternalNotesState::Failed(err) => {
+                    return Err(anyhow::anyhow!("Failed to redeem the notes: {err}"));
+                }
+                _ => {}
+            }
+        }
+
+        Err(anyhow::anyhow!(
+            "Lost track of the notes before they were redeemed"
+        ))
+    }
+
+    /// Builds an invite code for each connected federation, from the first guardian in its
+    /// config, so that the federations can be rejoined elsewhere. See [`crate::db::Database::export_archive`].
+    pub async fn list_invite_codes(&self) -> Vec<InviteCode> {
+        let clients = self.clients.lock().await;
+
+        let mut invite_codes = Vec::new();
+
+        for (federation_id, client) in clients.iter() {
+            let config = client.config().await;
+
+            let Some((peer_id, peer_url)) = config.global.api_endpoints.iter().next() else {
+                continue;
+            };
+
+            // Federations that require an API secret can't be rejoined without it.
+            let api_secret_or = client
+                .db()
+                .begin_transaction_nc()
+                .await
+                .get_value(&ApiSecretKey)
+                .await;
+
+            invite_codes.push(InviteCode::new(
+                peer_url.url.clone(),
+                *peer_id,
+                *federation_id,
+                api_secret_or,
+            ));
+        }
+
+        invite_codes
+    }
+
+    /// Lists the newest `limit` operations in a federation's client operation log, newest first.
+    /// E-cash and payment secrets are redacted. See [`OperationLogRecord`].
+    pub async fn list_operations(
+        &self,
+        federation_id: &FederationId,
+        limit: usize,
+    ) -> anyhow::Result<Vec<OperationLogRecord>> {
+        let clients = self.clients.lock().await;
+
+        let client = clients
+            .get(federation_id)
+            .ok_or_else(|| anyhow::anyhow!("Client for federation {} not found", federation_id))?;
+
+        Ok(Self::read_operation_log(client, limit).await)
+    }
+
+    /// Lists the sends and receives in the newest `limit_per_federation` operations of every
+    /// connected federation, newest first. Federations that aren't connected are left out,
+    /// so callers should keep the transactions they've already seen. See [`TransactionRecord`].
+    pub async fn list_transactions(
+        &self,
+        limit_per_federation: usize,
+    ) -> anyhow::Result<Vec<TransactionRecord>> {
+        let clients = self.clients.lock().await;
+
+        let mut transactions = Vec::new();
+
+        for (federation_id, client) in clients.iter() {
+            let federation_name_or = client
+                .config()
+                .await
+                .global
+                .federation_name()
+                .map(ToString::to_string);
+
+            transactions.extend(
+                Self::read_operation_log(client, limit_per_federation)
+                    .await
+                    .iter()
+                    .filter_map(|operation| {
+                        TransactionRecord::from_operation(
+                            *federation_id,
+                            federation_name_or.clone(),
+                            operation,
+                        )
+                    }),
+            );
+        }
+
+        transactions.sort_by(|a, b| b.create_time.cmp(&a.create_time));
+
+        Ok(transactions)
+    }
+
+    async fn read_operation_log(client: &ClientHandle, limit: usize) -> Vec<OperationLogRecord> {
+        client
+            .operation_log()
+            .list_operations(limit, None)
+            .await
+            .into_iter()
+            .map(|(key, entry)| {
+                let mut meta = entry.meta::<serde_json::Value>();
+                redact_operation_fields(&mut meta);
+
+                let mut outcome_or = entry.outcome::<serde_json::Value>();
+                if let Some(outcome) = &mut outcome_or {
+                    redact_operation_fields(outcome);
+                }
+
+                OperationLogRecord {
+                    operation_id: key.operation_id.to_string(),
+                    creation_time: DateTime::<chrono::Utc>::from(key.creation_time).naive_utc(),
+                    module_kind: entry.operation_module_kind().to_string(),
+                    meta,
+                    outcome_or,
+                }
+            })
+            .collect()
+    }
+
+    pub async fn receive_payment(
+        &self,
+        federation_id: FederationId,
+        amount: Amount,
+        description: String,
+    ) -> anyhow::Result<(Bolt11Invoice, oneshot::Receiver<LightningReceiveCompletion>)> {
+        self.receive_payment_with_description(
+            federation_id,
+            amount,
+            Bolt11InvoiceDescription::Direct(&Description::new(description)?),
+        )
+        .await
+    }
+
+    /// Like [`Self::receive_payment`], but the invoice commits to the hex-encoded
+    /// SHA-256 `description_hash` instead of including a description.
+    pub async fn receive_payment_with_description_hash(
+        &self,
+        federation_id: FederationId,
+        amount: Amount,
+        description_hash: &str,
+    ) -> anyhow::Result<(Bolt11Invoice, oneshot::Receiver<LightningReceiveCompletion>)> {
+        let description_hash = Sha256(sha256::Hash::from_str(description_hash)?);
+
+        self.receive_payment_with_description(
+            federation_id,
+            amount,
+            Bolt11InvoiceDescription::Hash(&description_hash),
+        )
+        .await
+    }
+
+    async fn receive_payment_with_description(
+        &self,
+        federation_id: FederationId,
+        amount: Amount,
+        description: Bolt11InvoiceDescription<'_>,
+    ) -> anyhow::Result<(Bolt11Invoice, oneshot::Receiver<LightningReceiveCompletion>)> {
+        let clients = self.clients.lock().await;
+
+        let client = clients
+            .get(&federation_id)
+            .ok_or_else(|| anyhow::anyhow!("Client for federation {} not found", federation_id))?;
+
+        let lightning_module = client.get_first_module::<LightningClientModule>();
+
+        let gateways = lightning_module.list_gateways().await;
+
+        let (operation_id, invoice, _preimage) = lightning_module
+            .create_bolt11_invoice(
+                amount,
+                description,
+                None,
+                (),
+                self.select_gateway(gateways.as_slice(), self.get_pinned_gateway(&federation_id)),
+            )
+            .await?;
+
+        let mut update_stream = lightning_module
+            .subscribe_ln_receive(operation_id)
+            .await?
+            .into_stream();
+
+        let (payment_completion_sender, payment_completion_receiver) = oneshot::channel();
+
+        tokio::spawn(async move {
+            while let Some(update) = update_stream.next().await {
+                match update {
+                    LnReceiveState::Claimed => {
+                        // If receiver was dropped, we don't care about the result.
+                        let _ = payment_completion_sender.send(LightningReceiveCompletion::Success);
+                        break;
+                    }
+                    LnReceiveState::Canceled { .. } => {
+                        // If receiver was dropped, we don't care about the result.
+                        let _ = payment_completion_sender.send(LightningReceiveCompletion::Failure);
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        self.force_update_view(clients).await;
+
+        Ok((invoice, payment_completion_receiver))
+    }
+
+    async fn build_client_from_invite_code(
+        &self,
+        invite_code: InviteCode,
+        db: Database,
+        on_stage: &(impl Fn(JoinFederationStage) + Sync),
+    ) -> anyhow::Result<ClientHandle> {
+        let is_initialized = fedimint_client::Client::is_initialized(&db).await;
+
+        let api_overrides = self.get_api_overrides(&invite_code.federation_id());
+
+        if let Some(api_secret) = &api_overrides.api_secret_or {
+            store_api_secret(&db, api_secret).await;
+        }
+
+        let mut client_builder = Client::builder(db).await?;
+
+        // Add lightning and e-cash modules. For now we don't support on-chain.
+        client_builder.with_module(fedimint_mint_client::MintClientInit);
+        client_builder.with_module(fedimint_ln_client::LightningClientInit::default());
+
+        client_builder.with_primary_module(1);
+
+        let derivable_secret = self.derivable_secret.clone();
+
+        let client = if is_initialized {
+            on_stage(JoinFederationStage::InitializingModules);
+
+            client_builder.open(derivable_secret).await?
+        } else {
+            on_stage(JoinFederationStage::DownloadingConfig);
+
+            let config = fedimint_api_client::download_from_invite_code(&invite_code).await?;
+
+            on_stage(JoinFederationStage::InitializingModules);
+
+            client_builder
+                .join(
+                    derivable_secret,
+                    config,
+                    api_overrides
+                        .api_secret_or
+                        .or_else(|| invite_code.api_secret()),
+                )
+                .await?
+        };
+
+        Ok(client)
+    }
+
+    async fn build_client_from_federation_id(
+        &self,
+        federation_id: FederationId,
+        db: Database,
+    ) -> anyhow::Result<ClientHandle> {
+        let is_initialized = fedimint_client::Client::is_initialized(&db).await;
+
+        // Client databases imported from other apps are opened with the secret stored in them.
+        // See `Self::import_federation()`.
+        let derivable_secret = match Client::load_decodable_client_secret::<Vec<u8>>(&db).await {
+            Ok(entropy) => {
+                Bip39RootSecretStrategy::<12>::to_root_secret(&Mnemonic::from_entropy(&entropy)?)
+            }
+            Err(_) => self.derivable_secret.clone(),
+        };
+
+        let api_overrides = self.get_api_overrides(&federation_id);
+
+        if let Some(api_secret) = &api_overrides.api_secret_or {
+            store_api_secret(&db, api_secret).await;
+        }
+
+        let mut client_builder = Client::builder(db).await?;
+
+        // Add lightning and e-cash modules. For now we don't support on-chain.
+        client_builder.with_module(fedimint_mint_client::MintClientInit);
+        client_builder.with_module(fedimint_ln_client::LightningClientInit::default());
+
+        client_builder.with_primary_module(1);
+
+        let client = if is_initialized {
+            client_builder.open(derivable_secret).await?
+        } else {
+            return Err(anyhow::anyhow!(
+                "Federation with ID {} is not initialized.",
+                federation_id
+            ));
+        };
+
+        Ok(client)
+    }
+
+    /// Selects the gateway to use for a lightning payment. The pinned gateway is used
+    /// if the federation still lists it. Otherwise, a random vetted gateway is preferred,
+    /// leaving out gateways that have charged much more than others in the past.
+    fn select_gateway(
+        &self,
+        gateways: &[LightningGatewayAnnouncement],
+        pinned_gateway_id_or: Option<GatewayId>,
+    ) -> Option<LightningGateway> {
+        if let Some(pinned_gateway) = gateways.iter().find(|gateway_announcement| {
+            Some(gateway_announcement.info.gateway_id) == pinned_gateway_id_or
+        }) {
+            return Some(pinned_gateway.info.clone());
+        }
+
+        let vetted_gateways: Vec<_> = gateways
+            .iter()
+            .filter(|gateway_announcement| gateway_announcement.vetted)
+            .map(|gateway_announcement| &gateway_announcement.info)
+            .collect();
+
+        // If there are no vetted gateways, any gateway can be selected.
+        let candidate_gateways = if vetted_gateways.is_empty() {
+            gateways
+                .iter()
+                .map(|gateway_announcement| &gateway_announcement.info)
+                .collect()
+        } else {
+            vetted_gateways
+        };
+
+        let fee_rates_ppm: Vec<Option<u64>> = {
+            let gateway_fee_stats = self
+                .gateway_fee_stats
+                .read()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+            candidate_gateways
+                .iter()
+                .map(|gateway| {
+                    gateway_fee_stats
+                        .get(&gateway.gateway_id)
+                        .map(GatewayFeeStats::fee_rate_ppm)
+                })
+                .collect()
+        };
+
+        let cheapest_fee_rate_ppm_or = fee_rates_ppm.iter().flatten().min().copied();
+
+        let cheap_gateways: Vec<_> = candidate_gateways
+            .into_iter()
+            .zip(fee_rates_ppm)
+            .filter(|(_gateway, fee_rate_ppm_or)| {
+                is_cheap_gateway(*fee_rate_ppm_or, cheapest_fee_rate_ppm_or)
+            })
+            .map(|(gateway, _fee_rate_ppm_or)| gateway)
+            .collect();
+
+        self.clock
+            .with_rng(|rng| cheap_gateways.choose(rng).copied())
+            .cloned()
+    }
+}
+
+/// Why paying an invoice through a gateway failed.
+#[derive(Debug)]
+enum GatewayPaymentError {
+    /// The payment didn't go through and nothing was spent, so the
+    /// invoice can safely be paid again through another gateway.
+    NotPaid(anyhow::Error),
+    /// The payment may still go through, or whether it did isn't known.
+    Unknown(anyhow::Error),
+}
+
+impl GatewayPaymentError {
+    fn into_inner(self) -> anyhow::Error {
+        match self {
+            Self::NotPaid(err) | Self::Unknown(err) => err,
+        }
+    }
+}
+
+/// Whether a lightning payment has been refunded, going by its final state.
+/// Only called once the payment has failed, so its final state has been reached.
+async fn is_ln_payment_refunded(client: &ClientHandle, operation_id: OperationId) -> bool {
+    let Ok(updates) = client
+        .get_first_module::<LightningClientModule>()
+        .subscribe_ln_pay(operation_id)
+        .await
+    else {
+        return false;
+    };
+
+    let mut updates = updates.into_stream();
+
+    while let Some(state) = updates.next().await {
+        match state {
+            LnPayState::Refunded { .. } => return true,
+            LnPayState::Success { .. }
+            | LnPayState::Canceled
+            | LnPayState::UnexpectedError { .. } => return false,
+            _ => {}
+        }
+    }
+
+    false
+}
+
+/// Lists the federations that have been joined, from their data directories.
+fn list_joined_federation_ids(
+    fedimint_clients_data_dir: &Path,
+) -> anyhow::Result<Vec<FederationId>> {
+    Ok(std::fs::read_dir(fedimint_clients_data_dir)?
+        .filter_map(|entry| {
+            entry.ok().and_then(|entry| {
+                entry
+                    .file_name()
+                    .into_string()
+                    .ok()
+                    .and_then(|federation_id| federation_id.parse().ok())
+            })
+        })
+        .collect())
+}
+
+/// Where the federation clients for `network` keep their data.
+fn get_fedimint_clients_data_dir(network: Network, project_dirs: &ProjectDirs) -> PathBuf {
+    // Clients for other networks are kept separate so that
+    // they never get mixed up with clients holding real funds.
+    let fedimint_clients_data_dir_name = if network == Network::Bitcoin {
+        FEDIMINT_CLIENTS_DATA_DIR_NAME.to_string()
+    } else {
+        format!("{FEDIMINT_CLIENTS_DATA_DIR_NAME}_{network}")
+    };
+
+    project_dirs.data_dir().join(fedimint_clients_data_dir_name)
+}
+
+/// Whether any federation has been joined on `network`, whichever key it was joined with.
+pub fn has_joined_federations(network: Network, project_dirs: &ProjectDirs) -> bool {
+    // TODO: Log a warning if the clients' data directory can't be read.
+    list_joined_federation_ids(&get_fedimint_clients_data_dir(network, project_dirs))
+        .is_ok_and(|federation_ids| !federation_ids.is_empty())
+}
+
+/// Generates a new 12 word seed for the wallet.
+pub fn generate_wallet_mnemonic() -> Mnemonic {
+    let mut entropy = [0; 16];
+    secp256k1::rand::thread_rng().fill_bytes(&mut entropy);
+
+    // 128 bits is a valid entropy length, so this can never fail.
+    Mnemonic::from_entropy(&entropy).expect("16 bytes is a valid entropy length")
+}
+
+/// Derives the wallet's master key from its seed, with no passphrase.
+pub fn get_wallet_xpriv(mnemonic: &Mnemonic, network: Network) -> anyhow::Result<Xpriv> {
+    Ok(Xpriv::new_master(network, &mnemonic.to_seed(""))?)
+}
+
+/// Reads out-of-band e-cash notes, ignoring any whitespace around them.
+pub fn parse_ecash_notes(text: &str) -> Option<OOBNotes> {
+    OOBNotes::from_str(text.trim()).ok()
+}
+
+/// Finds the invite code in an invite link. Accepts `fedimint:` URIs, web links that
+/// embed an invite code in their path or query, and bare invite codes.
+pub fn parse_invite_link(link: &str) -> Option<InviteCode> {
+    let link = link.trim();
+
+    let without_scheme = link
+        .split_once(':')
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case(INVITE_URI_SCHEME))
+        .map_or(link, |(_, rest)| rest.trim_start_matches('/'));
+
+    if let Ok(invite_code) = InviteCode::from_str(without_scheme) {
+        return Some(invite_code);
+    }
+
+    // Invite codes are bech32, so they never contain URL delimiters or need escaping.
+    link.split(['/', '?', '&', '=', '#'])
+        .filter(|part| part.to_lowercase().starts_with("fed1"))
+        .find_map(|part| InviteCode::from_str(part).ok())
+}
+
+fn get_derivable_secret(xprivkey: &Xpriv, network: Network) -> DerivableSecret {
+    let context = Secp256k1::new();
+
+    let xpriv = xprivkey
+        .derive_priv(
+            &context,
+            &DerivationPath::from(vec![ChildNumber::Normal {
+                index: FEDIMINT_DERIVATION_NUMBER,
+            }]),
+        )
+        .expect("This can never fail. Should be fixed in future version of `bitcoin` crate.")
+        .derive_priv(
+            &context,
+            &[
+                ChildNumber::from_hardened_idx(coin_type_from_network(network))
+                    .expect("Should only fail if 2^31 <= index"),
+            ],
+        )
+        .expect("This can never fail. Should be fixed in future version of `bitcoin` crate.");
+
+    // `Mnemonic::from_entropy()` should only ever fail if the input is not of the correct length.
+    // Valid lengths are 128, 160, 192, 224, or 256 bits, and `SecretKey::secret_bytes()` is always 256 bits.
+    let mnemonic = Mnemonic::from_entropy(&xpriv.private_key.secret_bytes())
+        .expect("Private key should always be 32 bytes");
+
+    Bip39RootSecretStrategy::<12>::to_root_secret(&mnemonic)
+}
+
+/// Stores an API secret in a client database, which is where the client reads it from when opened.
+/// Replaces any secret stored when the federation was joined.
+async fn store_api_secret(db: &Database, api_secret: &str) {
+    let mut dbtx = db.begin_transaction().await;
+    dbtx.insert_entry(&ApiSecretKey, &api_secret.to_string())
+        .await;
+    dbtx.commit_tx().await;
+}
+
+/// Recursively copies the contents of `source` into `destination`, which must not exist yet.
+fn copy_directory(source: &Path, destination: &Path) -> anyhow::Result<()> {
+    std::fs::create_dir(destination)?;
+
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        let destination_path = destination.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            copy_directory(&entry.path(), &destination_path)?;
+        } else {
+            std::fs::copy(entry.path(), destination_path)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn coin_type_from_network(network: Network) -> u32 {
+    match network {
+        Network::Bitcoin => 0,
+        Network::Testnet => 1,
+        Network::Signet => 1,
+        Network::Regtest => 1,
+        net => panic!("Got unknown network: {net}!"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fedimint_core::{util::SafeUrl, PeerId};
+
+    use super::*;
+
+    #[test]
+    fn test_wallet_mnemonic() {
+        let mnemonic = generate_wallet_mnemonic();
+        assert_eq!(mnemonic.word_count(), 12);
+        assert_ne!(mnemonic, generate_wallet_mnemonic());
+
+        // The same seed always derives the same key, or funds would be lost between sessions.
+        assert_eq!(
+            get_wallet_xpriv(&mnemonic, Network::Bitcoin).unwrap(),
+            get_wallet_xpriv(&mnemonic, Network::Bitcoin).unwrap()
+        );
+        assert_ne!(
+            get_wallet_xpriv(&mnemonic, Network::Bitcoin).unwrap(),
+            get_wallet_xpriv(&generate_wallet_mnemonic(), Network::Bitcoin).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_exceeds_payment_cap() {
+        let cap = Amount::from_sats(50_000);
+
+        assert!(!exceeds_payment_cap(Some(1_000), cap));
+        // Invoices for exactly the cap can be paid.
+        assert!(!exceeds_payment_cap(Some(50_000_000), cap));
+        assert!(exceeds_payment_cap(Some(50_000_001), cap));
+        assert!(exceeds_payment_cap(None, cap));
+    }
+
+    #[test]
+    fn test_redact_operation_fields() {
+        let mut meta = serde_json::json!({
+            "amount": 1000,
+            "variant": {
+                "spend_oob": {
+                    "requested_amount": 1000,
+                    "oob_notes": "AgEEsuFO5gD3AwQD",
+                },
+            },
+            "payments": [{ "preimage": "00ff" }],
+        });
+
+        redact_operation_fields(&mut meta);
+
+        assert_eq!(
+            meta,
+            serde_json::json!({
+                "amount": 1000,
+                "variant": {
+                    "spend_oob": {
+                        "requested_amount": 1000,
+                        "oob_notes": "[redacted]",
+                    },
+                },
+                "payments": [{ "preimage": "[redacted]" }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_transaction_from_operation() {
+        let operation = |module_kind: &str, meta, outcome_or| OperationLogRecord {
+            operation_id: "00ff".to_string(),
+            creation_time: NaiveDateTime::default(),
+            module_kind: module_kind.to_string(),
+            meta,
+            outcome_or,
+        };
+
+        let spend = operation(
+            "mint",
+            serde_json::json!({
+                "amount": 1000,
+                "variant": { "spend_o_o_b": { "requested_amount": 1000, "oob_notes": "[redacted]" } },
+            }),
+            Some(serde_json::json!("success")),
+        );
+
+        assert_eq!(
+            TransactionRecord::from_operation(FederationId::dummy(), None, &spend),
+            Some(TransactionRecord {
+                operation_id: "00ff".to_string(),
+                federation_id: FederationId::dummy(),
+                federation_name_or: None,
+                kind: TransactionKind::Ecash,
+                direction: PaymentDirection::Outgoing,
+                amount: Amount::from_msats(1000),
+                fee: Amount::ZERO,
+                status: TransactionStatus::Succeeded,
+                create_time: NaiveDateTime::default(),
+                exchange_rate_or: None,
+            })
+        );
+
+        // Amounts can also be written as objects, and outcomes as struct variants.
+        let reissuance = operation(
+            "mint",
+            serde_json::json!({
+                "amount": { "msats": 2000 },
+                "variant": { "reissuance": { "txid": "00" } },
+            }),
+            Some(serde_json::json!({ "Failed": "Double spend" })),
+        );
+
+        let transaction =
+            TransactionRecord::from_operation(FederationId::dummy(), None, &reissuance).unwrap();
+        assert_eq!(transaction.direction, PaymentDirection::Incoming);
+        assert_eq!(transaction.amount, Amount::from_msats(2000));
+        assert_eq!(transaction.status, TransactionStatus::Failed);
+
+        // Operations that nothing waited for have no outcome.
+        let pending_reissuance = operation("mint", reissuance.meta.clone(), None);
+        assert_eq!(
+            TransactionRecord::from_operation(FederationId::dummy(), None, &pending_reissuance)
+                .unwrap()
+                .status,
+            TransactionStatus::Pending
+        );
+
+        // Claims don't move funds by themselves, and other modules aren't used.
+        let claim = operation(
+            "ln",
+            serde_json::json!({ "variant": { "claim": { "out_points": [] } } }),
+            None,
+        );
+        assert_eq!(
+            TransactionRecord::from_operation(FederationId::dummy(), None, &claim),
+            None
+        );
+
+        let deposit = operation(
+            "wallet",
+            serde_json::json!({ "variant": { "deposit": {} } }),
+            None,
+        );
+        assert_eq!(
+            TransactionRecord::from_operation(FederationId::dummy(), None, &deposit),
+            None
+        );
+    }
+
+    #[test]
+    fn test_gateway_fee_rates() {
+        let mut stats = GatewayFeeStats::default();
+        stats.record(Amount::from_sats(1_000), Amount::from_sats(10));
+        stats.record(Amount::from_sats(9_000), Amount::from_sats(10));
+
+        // Weighted by amount: 20 sats in fees over 10,000 sats sent.
+        assert_eq!(stats.payment_count, 2);
+        assert_eq!(stats.fee_rate_ppm(), 2_000);
+        assert_eq!(GatewayFeeStats::default().fee_rate_ppm(), 0);
+
+        // Within the tolerance of the cheapest gateway.
+        assert!(is_cheap_gateway(Some(2_000), Some(2_000)));
+        assert!(is_cheap_gateway(Some(3_000), Some(2_000)));
+        assert!(!is_cheap_gateway(Some(3_001), Some(2_000)));
+
+        // Gateways without history are always kept.
+        assert!(is_cheap_gateway(None, Some(2_000)));
+        assert!(is_cheap_gateway(Some(5_000), None));
+    }
+
+    #[test]
+    fn test_parse_invite_link() {
+        let invite_code = InviteCode::new(
+            SafeUrl::parse("wss://fedimint.example.com").unwrap(),
+            PeerId::from(0),
+            FederationId::dummy(),
+            None,
+        );
+        let code = invite_code.to_string();
+
+        assert_eq!(parse_invite_link(&code), Some(invite_code.clone()));
+        assert_eq!(
+            parse_invite_link(&format!("fedimint:{code}")),
+            Some(invite_code.clone())
+        );
+        assert_eq!(
+            parse_invite_link(&format!("FEDIMINT://{code}\n")),
+            Some(invite_code.clone())
+        );
+        assert_eq!(
+            parse_invite_link(&format!("https://example.com/join?invite={code}&ref=x")),
+            Some(invite_code.clone())
+        );
+        assert_eq!(
+            parse_invite_link(&format!("https://example.com/join/{code}#top")),
+            Some(invite_code)
+        );
+
+        assert_eq!(parse_invite_link("fedimint:"), None);
+        assert_eq!(
+            parse_invite_link("https://example.com/join?invite=fed1nope"),
+            None
+        );
+        assert_eq!(parse_invite_link("nostr:npub1abc"), None);
+    }
+
+    #[test]
+    fn test_federation_operation_progress() {
+        let progress = FederationOperationProgress::Joining(JoinFederationStage::DownloadingConfig);
+        assert_eq!(progress.step(), (1, 3));
+        assert_eq!(
+            progress.to_string(),
+            "Step 1 of 3: Downloading the federation's config"
+        );
+
+        let progress = FederationOperationProgress::Leaving(LeaveFederationStage::DeletingData);
+        assert_eq!(progress.step(), (3, 3));
+        assert_eq!(
+            progress.to_string(),
+            "Step 3 of 3: Deleting federation data"
+        );
+    }
+
+    #[test]
+    fn test_max_amount_after_fees() {
+        let no_fees = RoutingFees {
+            base_msat: 0,
+            proportional_millionths: 0,
+        };
+
+        assert_eq!(
+            max_amount_after_fees(Amount::from_msats(100_500), no_fees),
+            Amount::from_msats(100_000)
+        );
+
+        // 1 sat plus 1%.
+        let fees = RoutingFees {
+            base_msat: 1_000,
+            proportional_millionths: 10_000,
+        };
+
+        let max_amount = max_amount_after_fees(Amount::from_msats(100_000), fees);
+        assert_eq!(max_amount, Amount::from_msats(98_000));
+        assert!(max_amount.msats + 1_000 + max_amount.msats / 100 <= 100_000);
+
+        // The balance doesn't cover the base fee.
+        assert_eq!(
+            max_amount_after_fees(Amount::from_msats(500), fees),
+            Amount::ZERO
+        );
+    }
+
+    #[test]
+    fn test_balance_threshold_crossing() {
+        let thresholds = BalanceThresholds {
+            low_or: Some(Amount::from_sats(1_000)),
+            high_or: Some(Amount::from_sats(100_000)),
+            max_or: None,
+        };
+
+        // Crossing the high threshold is reported.
+        assert_eq!(
+            thresholds.get_crossing(Amount::from_sats(99_000), Amount::from_sats(101_000)),
+            Some(BalanceThresholdCrossing::AboveHigh(Amount::from_sats(
+                100_000
+            )))
+        );
+
+        // Staying above the high threshold is not reported again.
+        assert_eq!(
+            thresholds.get_crossing(Amount::from_sats(101_000), Amount::from_sats(102_000)),
+            None
+        );
+
+        // Crossing the low threshold is reported.
+        assert_eq!(
+            thresholds.get_crossing(Amount::from_sats(1_000), Amount::from_sats(999)),
+            Some(BalanceThresholdCrossing::BelowLow(Amount::from_sats(1_000)))
+        );
+
+        // Moving between thresholds is not reported.
+        assert_eq!(
+            thresholds.get_crossing(Amount::from_sats(5_000), Amount::from_sats(50_000)),
+            None
+        );
+
+        // No thresholds means nothing is ever reported.
+        assert_eq!(
+            BalanceThresholds::default().get_crossing(Amount::ZERO, Amount::from_sats(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_share_unchanged_federations() {
+        let federation_view = |federation_id, sats| {
+            Arc::new(FederationView {
+                federation_id,
+                metadata: FederationMetadata::default(),
+                balance: Amount::from_sats(sats),
+                gateways: Vec::new(),
+                announcements: FederationAnnouncements::default(),
+                is_reachable: true,
+            })
+        };
+
+        let unchanged_federation_id = FederationId::dummy();
+        let changed_federation_id = FederationId::from_str(&"ab".repeat(32)).unwrap();
+
+        let previous_view = WalletView {
+            federations: BTreeMap::from([
+                (
+                    unchanged_federation_id,
+                    federation_view(unchanged_federation_id, 1_000),
+                ),
+                (
+                    changed_federation_id,
+                    federation_view(changed_federation_id, 1_000),
+                ),
+            ]),
+            disconnected_federation_ids: BTreeSet::new(),
+        };
+
+        let mut current_view = WalletView {
+            federations: BTreeMap::from([
+                (
+                    unchanged_federation_id,
+                    federation_view(unchanged_federation_id, 1_000),
+                ),
+                (
+                    changed_federation_id,
+                    federation_view(changed_federation_id, 2_000),
+                ),
+            ]),
+            disconnected_federation_ids: BTreeSet::new(),
+        };
+
+        current_view.share_unchanged_federations(&previous_view);
+
+        assert!(Arc::ptr_eq(
+            &current_view.federations[&unchanged_federation_id],
+            &previous_view.federations[&unchanged_federation_id]
+        ));
+        assert!(!Arc::ptr_eq(
+            &current_view.federations[&changed_federation_id],
+            &previous_view.federations[&changed_federation_id]
+        ));
+        assert_eq!(
+            current_view.federations[&changed_federation_id].balance,
+            Amount::from_sats(2_000)
+        );
+    }
+
+    #[test]
+    fn test_balance_thresholds_is_valid() {
+        let thresholds = |low_sats: u64, high_sats: u64| BalanceThresholds {
+            low_or: Some(Amount::from_sats(low_sats)),
+            high_or: Some(Amount::from_sats(high_sats)),
+            max_or: None,
+        };
+
+        assert!(thresholds(1_000, 100_000).is_valid());
+        assert!(thresholds(1_000, 1_000).is_valid());
+        assert!(!thresholds(100_000, 1_000).is_valid());
+
+        // Either threshold can be set on its own.
+        assert!(BalanceThresholds {
+            high_or: Some(Amount::from_sats(1_000)),
+            ..Default::default()
+        }
+        .is_valid());
+    }
+
+    #[test]
+    fn test_balance_thresholds_would_exceed_max() {
+        let thresholds = BalanceThresholds {
+            max_or: Some(Amount::from_sats(50_000)),
+            ..Default::default()
+        };
+
+        // Reaching the maximum exactly is allowed.
+        assert!(!thresholds.would_exceed_max(Amount::from_sats(40_000), Amount::from_sats(10_000)));
+        assert!(thresholds.would_exceed_max(Amount::from_sats(40_000), Amount::from_sats(10_001)));
+
+        // Without a maximum, any amount can be received.
+        assert!(!BalanceThresholds::default()
+            .would_exceed_max(Amount::from_sats(40_000), Amount::from_sats(1_000_000)));
+    }
+
+    #[tokio::test]
+    async fn test_view_updates_wait_for_update_interval() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let clock = Clock::manual(std::time::UNIX_EPOCH, 0);
+        let update_interval = Settings::default().wallet_view_update_interval;
+
+        let wallet = Wallet::new_with_data_dir(
+            Xpriv::new_master(Network::Regtest, &[0; 32]).unwrap(),
+            Network::Regtest,
+            data_dir.path().to_path_buf(),
+            watch::channel(Settings::default()).1,
+            clock.clone(),
+        );
+        let mut view_update_receiver = wallet.view_update_receiver.clone();
+
+        // Let the view update task start waiting.
+        tokio::task::yield_now().await;
+
+        // The wait is jittered, but never shorter than the shortest jittered interval.
+        clock.advance(update_interval.mul_f64(1.0 - WALLET_VIEW_UPDATE_JITTER) / 2);
+        tokio::task::yield_now().await;
+        assert!(!view_update_receiver.has_changed().unwrap());
+
+        clock.advance(update_interval.mul_f64(1.0 + WALLET_VIEW_UPDATE_JITTER));
+        view_update_receiver.changed().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_view_updates_wait_for_refresh_on_demand() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let clock = Clock::manual(std::time::UNIX_EPOCH, 0);
+        let settings = Settings {
+            refresh_balances_on_demand: true,
+            ..Settings::default()
+        };
+
+        let wallet = Wallet::new_with_data_dir(
+            Xpriv::new_master(Network::Regtest, &[0; 32]).unwrap(),
+            Network::Regtest,
+            data_dir.path().to_path_buf(),
+            watch::channel(settings.clone()).1,
+            clock.clone(),
+        );
+        let mut view_update_receiver = wallet.view_update_receiver.clone();
+
+        tokio::task::yield_now().await;
+
+        // No amount of time passing checks the federations.
+        clock.advance(settings.wallet_view_update_interval * 100);
+        tokio::task::yield_now().await;
+        assert!(!view_update_receiver.has_changed().unwrap());
+
+        wallet.refresh_view().await;
+        assert!(view_update_receiver.has_changed().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_view_updates() {
+        let data_dir = tempfile::tempdir().unwrap();
+
+        let wallet = Wallet::new_with_data_dir(
+            Xpriv::new_master(Network::Regtest, &[0; 32]).unwrap(),
+            Network::Regtest,
+            data_dir.path().to_path_buf(),
+            watch::channel(Settings::default()).1,
+            Clock::manual(std::time::UNIX_EPOCH, 0),
+        );
+
+        tokio::task::yield_now().await;
+        assert!(!wallet.view_update_task.is_finished());
+
+        wallet.shutdown().await;
+        tokio::task::yield_now().await;
+        assert!(wallet.view_update_task.is_finished());
+    }
+
+    #[test]
+    fn test_jitter_interval() {
+        let clock = Clock::manual(std::time::UNIX_EPOCH, 0);
+        let update_interval = Duration::from_secs(5);
+
+        let intervals: BTreeSet<Duration> = (0..20)
+            .map(|_| clock.with_rng(|rng| jitter_interval(update_interval, rng)))
+            .collect();
+
+        // The intervals vary, but stay close to the update interval.
+        assert!(intervals.len() > 1);
+        assert!(intervals.iter().all(|interval| {
+            (Duration::from_millis(3_750)..=Duration::from_millis(6_250)).contains(interval)
+        }));
+    }
+}
+
+/// End-to-end tests against a local regtest federation and lightning gateway.
+/// Start devimint (`devimint dev-fed`) and run these from its environment with
+/// `cargo test --features regtest`.
+#[cfg(all(test, feature = "regtest"))]
+mod regtest_tests {
+    use std::process::Command;
+
+    use secp256k1::rand::{thread_rng, RngCore};
+
+    use super::*;
+
+    /// Environment variable set by devimint containing the `lncli`
+    /// command (with its flags) for the LND node connected to the gateway.
+    const LNCLI_ENV_VAR: &str = "FM_LNCLI";
+
+    fn run_lncli(args: &str) -> serde_json::Value {
+        let lncli = std::env::var(LNCLI_ENV_VAR).unwrap();
+
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(format!("{lncli} {args}"))
+            .output()
+            .unwrap();
+
+        assert!(output.status.success(), "lncli {args} failed");
+
+        serde_json::from_slice(&output.stdout).unwrap()
+    }
+
+    fn new_regtest_wallet(data_dir: &tempfile::TempDir) -> Wallet {
+        let mut seed = [0; 32];
+        thread_rng().fill_bytes(&mut seed);
+
+        Wallet::new_with_data_dir(
+            Xpriv::new_master(Network::Regtest, &seed).unwrap(),
+            Network::Regtest,
+            data_dir.path().to_path_buf(),
+            watch::channel(Settings::default()).1,
+            Clock::default(),
+        )
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_join_receive_and_pay() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let wallet = new_regtest_wallet(&data_dir);
+
+        let federation_id = wallet.join_regtest_federation().await.unwrap();
+
+        // Joining again is a no-op.
+        wallet.join_regtest_federation().await.unwrap();
+
+        // Receive from LND through the gateway.
+        let (invoice, payment_completion_receiver) = wallet
+            .receive_payment(
+                federation_id,
+                Amount::from_sats(10_000),
+                "Regtest receive".to_string(),
+            )
+            .await
+            .unwrap();
+
+        run_lncli(&format!("payinvoice --force --json {invoice}"));
+
+        assert!(matches!(
+            payment_completion_receiver.await.unwrap(),
+            LightningReceiveCompletion::Success
+        ));
+
+        // Pay LND back through the gateway.
+        let lnd_invoice = run_lncli("addinvoice --amt 1000");
+        let lnd_invoice =
+            Bolt11Invoice::from_str(lnd_invoice["payment_request"].as_str().unwrap()).unwrap();
+
+        let outcome = wallet
+            .pay_invoice(lnd_invoice, federation_id)
+            .await
+            .unwrap();
+
+        assert!(!outcome.is_simulated);
+        assert!(outcome.preimage_or.is_some());
+
+        let mut update_stream = wallet.get_update_stream();
+        let wallet_view = update_stream.next().await.unwrap();
+        let balance = wallet_view.federations[&federation_id].balance;
+
+        // The balance is what was received, minus what was paid and the gateway fees.
+        assert!(balance < Amount::from_sats(9_000));
+        assert!(balance > Amount::ZERO);
+    }
+}
@@ -0,0 +1,241 @@
+use std::fmt::Display;
+
+use nostr_sdk::{nips::nip46::Request, Timestamp, UnsignedEvent};
+
+/// The kind of NIP-94 file metadata events.
+pub const FILE_METADATA_KIND: u16 = 1063;
+
+/// The kind of Blossom authorization events, which apps send to Blossom
+/// servers to upload, list, or delete files on the user's behalf.
+pub const BLOSSOM_AUTHORIZATION_KIND: u16 = 24242;
+
+/// A file that an app asks the user to sign an event for. Signing one of these events
+/// lets the app publish or upload the file under the user's identity, so they're
+/// shown to the user in full rather than as raw JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileAttachment {
+    pub kind: FileAttachmentKind,
+    /// Hex-encoded SHA-256 hashes of the files the event covers. Blossom
+    /// authorizations that don't list any hashes cover any file.
+    pub sha256_hashes: Vec<String>,
+    /// Where the file is stored. For file metadata events, these are the file's
+    /// URL and any fallback URLs. For Blossom authorizations, the allowed servers.
+    pub server_urls: Vec<String>,
+    pub size_bytes_or: Option<u64>,
+    pub mime_type_or: Option<String>,
+    /// The event's content, which describes the file or the action.
+    pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileAttachmentKind {
+    /// A NIP-94 file metadata event, which publishes a file to the user's followers.
+    FileMetadata,
+    /// A Blossom authorization event. It lets whoever holds it perform `action` on
+    /// Blossom servers as the user, until it expires.
+    BlossomAuthorization {
+        action: BlossomAction,
+        expiration_or: Option<Timestamp>,
+    },
+}
+
+/// What a Blossom authorization event allows, from its `t` tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlossomAction {
+    Upload,
+    Get,
+    List,
+    Delete,
+    Other(String),
+}
+
+impl From<&str> for BlossomAction {
+    fn from(value: &str) -> Self {
+        match value {
+            "upload" => Self::Upload,
+            "get" => Self::Get,
+            "list" => Self::List,
+            "delete" => Self::Delete,
+            _ => Self::Other(value.to_string()),
+        }
+    }
+}
+
+impl Display for BlossomAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Upload => write!(f, "Upload files"),
+            Self::Get => write!(f, "Download files"),
+            Self::List => write!(f, "List files"),
+            Self::Delete => write!(f, "Delete files"),
+            Self::Other(action) => write!(f, "Unknown action \"{action}\""),
+        }
+    }
+}
+
+impl FileAttachment {
+    /// Reads the file details from a NIP-94 file metadata or Blossom authorization event.
+    /// Returns `None` for any other kind of event.
+    pub fn from_unsigned_event(unsigned_event: &UnsignedEvent) -> Option<Self> {
+        let kind = match unsigned_event.kind.as_u16() {
+            FILE_METADATA_KIND => FileAttachmentKind::FileMetadata,
+            BLOSSOM_AUTHORIZATION_KIND => FileAttachmentKind::BlossomAuthorization {
+                action: BlossomAction::from(
+                    get_tag_values(unsigned_event, "t")
+                        .first()
+                        .copied()
+                        .unwrap_or_default(),
+                ),
+                expiration_or: get_tag_values(unsigned_event, "expiration")
+                    .first()
+                    .and_then(|expiration| expiration.parse::<u64>().ok())
+                    .map(Timestamp::from),
+            },
+            _ => return None,
+        };
+
+        let server_tag_names: &[&str] = match kind {
+            FileAttachmentKind::FileMetadata => &["url", "fallback"],
+            FileAttachmentKind::BlossomAuthorization { .. } => &["server"],
+        };
+
+        Some(Self {
+            kind,
+            sha256_hashes: get_tag_values(unsigned_event, "x")
+                .into_iter()
+                .map(ToString::to_string)
+                .collect(),
+            server_urls: server_tag_names
+                .iter()
+                .flat_map(|tag_name| get_tag_values(unsigned_event, tag_name))
+                .map(ToString::to_string)
+                .collect(),
+            size_bytes_or: get_tag_values(unsigned_event, "size")
+                .first()
+                .and_then(|size| size.parse().ok()),
+            mime_type_or: get_tag_values(unsigned_event, "m")
+                .first()
+                .map(ToString::to_string),
+            description: unsigned_event.content.clone(),
+        })
+    }
+
+    /// Whether the signed event expires before `now`, after which servers reject it.
+    pub fn is_expired(&self, now: Timestamp) -> bool {
+        matches!(
+            self.kind,
+            FileAttachmentKind::BlossomAuthorization {
+                expiration_or: Some(expiration),
+                ..
+            } if expiration <= now
+        )
+    }
+}
+
+/// The files in a batch of NIP-46 requests, in order.
+pub fn file_attachments(requests: &[Request]) -> Vec<FileAttachment> {
+    requests
+        .iter()
+        .filter_map(|request| match request {
+            Request::SignEvent(unsigned_event) => {
+                FileAttachment::from_unsigned_event(unsigned_event)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn get_tag_values<'a>(unsigned_event: &'a UnsignedEvent, tag_name: &str) -> Vec<&'a str> {
+    unsigned_event
+        .tags
+        .iter()
+        .filter_map(|tag| match tag.as_slice() {
+            [name, value, ..] if name == tag_name => Some(value.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr_sdk::{EventBuilder, Keys, Kind, Tag};
+
+    use super::*;
+
+    fn unsigned_event(kind: u16, content: &str, tags: &[&[&str]]) -> UnsignedEvent {
+        EventBuilder::new(
+            Kind::from(kind),
+            content,
+            tags.iter().map(|tag| Tag::parse(*tag).unwrap()),
+        )
+        .to_unsigned_event(Keys::generate().public_key())
+    }
+
+    #[test]
+    fn test_file_metadata() {
+        let event = unsigned_event(
+            FILE_METADATA_KIND,
+            "Vacation photo",
+            &[
+                &["url", "https://cdn.example.com/photo.jpg"],
+                &["fallback", "https://mirror.example.com/photo.jpg"],
+                &["m", "image/jpeg"],
+                &["x", "abc123"],
+                &["size", "204800"],
+            ],
+        );
+
+        assert_eq!(
+            FileAttachment::from_unsigned_event(&event),
+            Some(FileAttachment {
+                kind: FileAttachmentKind::FileMetadata,
+                sha256_hashes: vec!["abc123".to_string()],
+                server_urls: vec![
+                    "https://cdn.example.com/photo.jpg".to_string(),
+                    "https://mirror.example.com/photo.jpg".to_string(),
+                ],
+                size_bytes_or: Some(204_800),
+                mime_type_or: Some("image/jpeg".to_string()),
+                description: "Vacation photo".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_blossom_authorization() {
+        let event = unsigned_event(
+            BLOSSOM_AUTHORIZATION_KIND,
+            "Upload photo.jpg",
+            &[
+                &["t", "upload"],
+                &["x", "abc123"],
+                &["x", "def456"],
+                &["server", "https://blossom.example.com"],
+                &["expiration", "1700000000"],
+            ],
+        );
+
+        let attachment = FileAttachment::from_unsigned_event(&event).unwrap();
+
+        assert_eq!(
+            attachment.kind,
+            FileAttachmentKind::BlossomAuthorization {
+                action: BlossomAction::Upload,
+                expiration_or: Some(Timestamp::from(1_700_000_000)),
+            }
+        );
+        assert_eq!(attachment.sha256_hashes, vec!["abc123", "def456"]);
+        assert_eq!(attachment.server_urls, vec!["https://blossom.example.com"]);
+        assert_eq!(attachment.size_bytes_or, None);
+
+        assert!(!attachment.is_expired(Timestamp::from(1_600_000_000)));
+        assert!(attachment.is_expired(Timestamp::from(1_700_000_000)));
+    }
+
+    #[test]
+    fn test_other_events_are_not_file_attachments() {
+        let event = unsigned_event(1, "Hello", &[&["x", "abc123"]]);
+
+        assert_eq!(FileAttachment::from_unsigned_event(&event), None);
+    }
+}
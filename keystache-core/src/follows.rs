@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use nostr_sdk::{Event, Filter, FromBech32, Kind, PublicKey};
+
+use crate::nostr::NostrModule;
+
+/// How long relays are given to return a follow list.
+const FOLLOW_LIST_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A key from a NIP-02 follow list (kind 3).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FollowedKey {
+    pub public_key: PublicKey,
+    /// The name the user gave the key in their follow list, if any.
+    pub petname_or: Option<String>,
+}
+
+/// A key whose zap requests are signed without prompting. See
+/// [`crate::policy::zap_request_recipients`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZapAllowlistEntry {
+    pub public_key: PublicKey,
+    pub petname_or: Option<String>,
+    pub create_time: NaiveDateTime,
+}
+
+impl TryFrom<(String, Option<String>, NaiveDateTime)> for ZapAllowlistEntry {
+    type Error = anyhow::Error;
+
+    fn try_from(
+        (npub, petname_or, create_time): (String, Option<String>, NaiveDateTime),
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            public_key: PublicKey::from_bech32(&npub)?,
+            petname_or,
+            create_time,
+        })
+    }
+}
+
+/// Reads the followed keys out of a follow list, in the order they're listed.
+/// Malformed and repeated `p` tags are skipped.
+pub fn parse_follow_list(event: &Event) -> Vec<FollowedKey> {
+    let mut followed_keys: Vec<FollowedKey> = Vec::new();
+
+    for tag in &event.tags {
+        let (public_key, petname_or) = match tag.as_slice() {
+            [name, public_key] | [name, public_key, _] if name == "p" => (public_key, None),
+            [name, public_key, _relay_url, petname, ..] if name == "p" => (
+                public_key,
+                Some(petname.trim()).filter(|petname| !petname.is_empty()),
+            ),
+            _ => continue,
+        };
+
+        let Ok(public_key) = PublicKey::from_hex(public_key) else {
+            continue;
+        };
+
+        if followed_keys
+            .iter()
+            .any(|followed_key| followed_key.public_key == public_key)
+        {
+            continue;
+        }
+
+        followed_keys.push(FollowedKey {
+            public_key,
+            petname_or: petname_or.map(ToString::to_string),
+        });
+    }
+
+    followed_keys
+}
+
+/// Fetches the newest follow list of `public_key` from relays.
+pub async fn fetch_follow_list(
+    nostr_module: &NostrModule,
+    public_key: PublicKey,
+) -> anyhow::Result<Vec<FollowedKey>> {
+    let events = nostr_module
+        .fetch_events(
+            vec![Filter::new()
+                .author(public_key)
+                .kind(Kind::ContactList)
+                .limit(1)],
+            FOLLOW_LIST_FETCH_TIMEOUT,
+        )
+        .await?;
+
+    // Relays could make up a follow list for any key, so only signed lists are used.
+    let event = events
+        .into_iter()
+        .filter(|event| event.pubkey == public_key && event.verify().is_ok())
+        .max_by_key(|event| event.created_at)
+        .ok_or_else(|| anyhow::anyhow!("No follow list found"))?;
+
+    Ok(parse_follow_list(&event))
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr_sdk::{EventBuilder, Keys, Tag};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_follow_list() {
+        let alice = Keys::generate().public_key();
+        let bob = Keys::generate().public_key();
+        let carol = Keys::generate().public_key();
+
+        let (alice_hex, bob_hex, carol_hex) = (alice.to_hex(), bob.to_hex(), carol.to_hex());
+
+        let follow_list = EventBuilder::new(
+            Kind::ContactList,
+            "",
+            [
+                Tag::parse(&["p", alice_hex.as_str(), "wss://relay.example.com", "alice"]).unwrap(),
+                Tag::parse(&["p", bob_hex.as_str()]).unwrap(),
+                Tag::parse(&["p", carol_hex.as_str(), "", " "]).unwrap(),
+                Tag::parse(&["p", "not a key"]).unwrap(),
+                Tag::parse(&["p", alice_hex.as_str()]).unwrap(),
+                Tag::parse(&["t", "nostr"]).unwrap(),
+            ],
+        )
+        .to_event(&Keys::generate())
+        .unwrap();
+
+        assert_eq!(
+            parse_follow_list(&follow_list),
+            vec![
+                FollowedKey {
+                    public_key: alice,
+                    petname_or: Some("alice".to_string()),
+                },
+                FollowedKey {
+                    public_key: bob,
+                    petname_or: None,
+                },
+                FollowedKey {
+                    public_key: carol,
+                    petname_or: None,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+/// Operations whose outcome would be lost if Keystache were closed before they
+/// finish, such as outgoing payments and invoices waiting to be paid.
+#[derive(Debug, Clone, Default)]
+pub struct InFlightOperations {
+    inner: Arc<Mutex<InFlightOperationsInner>>,
+}
+
+#[derive(Debug, Default)]
+struct InFlightOperationsInner {
+    next_id: u64,
+    description_by_id: BTreeMap<u64, String>,
+}
+
+impl InFlightOperations {
+    /// Marks an operation as in flight until the returned guard is dropped.
+    /// The guard should be moved into the task that runs the operation.
+    pub fn start(&self, description: impl Into<String>) -> InFlightOperationGuard {
+        let mut id = 0;
+
+        if let Ok(mut inner) = self.inner.lock() {
+            id = inner.next_id;
+            inner.next_id += 1;
+            inner.description_by_id.insert(id, description.into());
+        }
+
+        InFlightOperationGuard {
+            operations: self.clone(),
+            id,
+        }
+    }
+
+    /// Descriptions of the operations that are still in flight, oldest first.
+    pub fn descriptions(&self) -> Vec<String> {
+        self.inner
+            .lock()
+            .map(|inner| inner.description_by_id.values().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Keeps an operation marked as in flight for as long as it's alive.
+#[derive(Debug)]
+pub struct InFlightOperationGuard {
+    operations: InFlightOperations,
+    id: u64,
+}
+
+impl Drop for InFlightOperationGuard {
+    fn drop(&mut self) {
+        if let Ok(mut inner) = self.operations.inner.lock() {
+            inner.description_by_id.remove(&self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_flight_operations() {
+        let operations = InFlightOperations::default();
+        assert!(operations.descriptions().is_empty());
+
+        let payment = operations.start("Paying invoice");
+        let invoice = operations.clone().start("Waiting for invoice to be paid");
+        assert_eq!(
+            operations.descriptions(),
+            vec!["Paying invoice", "Waiting for invoice to be paid"]
+        );
+
+        drop(payment);
+        assert_eq!(
+            operations.descriptions(),
+            vec!["Waiting for invoice to be paid"]
+        );
+
+        drop(invoice);
+        assert!(operations.descriptions().is_empty());
+    }
+}
@@ -0,0 +1,85 @@
+//! Stores the database password in the operating system's keychain (Keychain on macOS,
+//! Credential Manager on Windows, and the Secret Service on Linux), so that Keystache
+//! can be unlocked without typing the password.
+//!
+//! Also stores the device key that binds the database to this device. See [`get_device_key`].
+
+use secp256k1::rand::{thread_rng, Rng};
+
+const KEYCHAIN_SERVICE: &str = "co.nodetec.keystache";
+const KEYCHAIN_USER: &str = "database-password";
+const KEYCHAIN_DEVICE_KEY_USER: &str = "database-device-key";
+
+fn get_entry(user: &str) -> anyhow::Result<keyring::Entry> {
+    Ok(keyring::Entry::new(KEYCHAIN_SERVICE, user)?)
+}
+
+fn get_secret(user: &str) -> anyhow::Result<Option<String>> {
+    match get_entry(user)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn delete_secret(user: &str) -> anyhow::Result<()> {
+    match get_entry(user)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Saves the database password to the keychain, replacing any password saved before.
+pub fn save_password(password: &str) -> anyhow::Result<()> {
+    get_entry(KEYCHAIN_USER)?.set_password(password)?;
+
+    Ok(())
+}
+
+/// Gets the database password from the keychain. Returns `None` if it isn't saved.
+/// The operating system may ask the user to approve this, such as with a fingerprint.
+pub fn get_password() -> anyhow::Result<Option<String>> {
+    get_secret(KEYCHAIN_USER)
+}
+
+/// Removes the database password from the keychain. Does nothing if it isn't saved.
+pub fn delete_password() -> anyhow::Result<()> {
+    delete_secret(KEYCHAIN_USER)
+}
+
+/// Whether the database password is saved in the keychain.
+pub fn has_password() -> bool {
+    // TODO: Log a warning if the keychain can't be read.
+    matches!(get_password(), Ok(Some(_)))
+}
+
+/// Generates a new random device key and saves it to the keychain,
+/// replacing any device key saved before.
+pub fn create_device_key() -> anyhow::Result<String> {
+    let device_key: String = thread_rng()
+        .gen::<[u8; 32]>()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+
+    get_entry(KEYCHAIN_DEVICE_KEY_USER)?.set_password(&device_key)?;
+
+    Ok(device_key)
+}
+
+/// Gets the device key from the keychain. Returns `None` if the database isn't bound to this device.
+///
+/// While the database is bound, its encryption key combines the password with the device key,
+/// so a copy of the database file can't be opened without this keychain, even with the password.
+/// The device key is an ordinary keychain entry like the saved password. It isn't wrapped by
+/// hardware such as a Secure Enclave or TPM, so it's only as safe as the keychain itself.
+/// Archives written by [`crate::db::Database::export_archive`] have their own password, so
+/// they can still be restored elsewhere. If the device key is lost, so is the database.
+pub fn get_device_key() -> anyhow::Result<Option<String>> {
+    get_secret(KEYCHAIN_DEVICE_KEY_USER)
+}
+
+/// Removes the device key from the keychain. Does nothing if it isn't saved.
+pub fn delete_device_key() -> anyhow::Result<()> {
+    delete_secret(KEYCHAIN_DEVICE_KEY_USER)
+}
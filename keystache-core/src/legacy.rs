@@ -0,0 +1,332 @@
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use diesel::{
+    connection::SimpleConnection,
+    prelude::*,
+    sql_query,
+    sql_types::{Nullable, Text},
+};
+use nostr_sdk::{
+    secp256k1::{Keypair, SECP256K1},
+    FromBech32, PublicKey, SecretKey, ToBech32,
+};
+
+use crate::db::{normalize_password, Database};
+
+/// The name of the database file used by the old Tauri build of Keystache.
+pub const LEGACY_DATABASE_NAME: &str = "keystache.db";
+
+// The Tauri build kept its data in a folder named after its bundle identifier.
+const LEGACY_APP_IDENTIFIER: &str = "com.nodetec.keystache";
+
+/// Looks for a database left behind by the old Tauri build of Keystache,
+/// in its own data folder or in the current one.
+pub fn find_legacy_database() -> Option<PathBuf> {
+    let legacy_app_data_dir_or = directories::BaseDirs::new()
+        .map(|base_dirs| base_dirs.data_dir().join(LEGACY_APP_IDENTIFIER));
+
+    [legacy_app_data_dir_or, Database::app_data_dir().ok()]
+        .into_iter()
+        .flatten()
+        .map(|folder| folder.join(LEGACY_DATABASE_NAME))
+        .find(|path| path.is_file())
+}
+
+/// The keys and apps found in a legacy database.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LegacyContents {
+    pub secret_keys: Vec<SecretKey>,
+    /// Apps that were registered to sign in over NIP-46.
+    pub app_public_keys: Vec<PublicKey>,
+}
+
+/// What was copied by [`LegacyContents::import_into`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LegacyImportSummary {
+    pub imported_keypair_count: usize,
+    pub imported_app_count: usize,
+    /// Keys and apps that were already in the database.
+    pub existing_count: usize,
+}
+
+impl LegacyContents {
+    /// Copies the keys and apps into `db`, skipping any that are already there.
+    pub fn import_into(&self, db: &Database) -> anyhow::Result<LegacyImportSummary> {
+        let mut summary = LegacyImportSummary::default();
+
+        for secret_key in &self.secret_keys {
+            let keypair = Keypair::from_secret_key(SECP256K1, secret_key);
+            let npub = PublicKey::from(keypair.x_only_public_key().0).to_bech32()?;
+
+            if db.get_keypair(&npub).is_ok() {
+                summary.existing_count += 1;
+            } else {
+                db.save_keypair(&keypair)?;
+                summary.imported_keypair_count += 1;
+            }
+        }
+
+        for public_key in &self.app_public_keys {
+            if db.get_nip46_app(public_key)?.is_some() {
+                summary.existing_count += 1;
+            } else {
+                db.register_nip46_app(public_key)?;
+                summary.imported_app_count += 1;
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+/// A database from the old Tauri build of Keystache, opened for importing.
+///
+/// Its schema differs from the current one and changed between releases, so rather
+/// than reading specific tables, every text value is checked for something importable.
+/// Secret keys are taken from anywhere in the database, and public keys only from
+/// tables of registered apps.
+pub struct LegacyDatabase {
+    connection: SqliteConnection,
+    path: PathBuf,
+}
+
+#[derive(QueryableByName)]
+struct NameRow {
+    #[diesel(sql_type = Text)]
+    name: String,
+}
+
+#[derive(QueryableByName)]
+struct ValueRow {
+    #[diesel(sql_type = Nullable<Text>)]
+    value: Option<String>,
+}
+
+impl LegacyDatabase {
+    /// Opens the legacy database at `path`. Databases that were encrypted
+    /// need their key, and fail to open without it.
+    pub fn open(path: &Path, encryption_key_or: Option<&str>) -> anyhow::Result<Self> {
+        let mut connection = SqliteConnection::establish(path.to_str().unwrap_or_default())?;
+
+        if let Some(encryption_key) = encryption_key_or {
+            let encryption_key = normalize_password(encryption_key);
+            connection.batch_execute(&format!("PRAGMA key='{encryption_key}'"))?;
+        }
+
+        // Reading fails here if the database is encrypted and the key is missing or wrong.
+        connection
+            .batch_execute("SELECT name FROM sqlite_master WHERE type='table'")
+            .map_err(|_| {
+                anyhow::anyhow!("Couldn't read the old database. Check that its key is correct.")
+            })?;
+
+        Ok(Self {
+            connection,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Reads every key and app out of the database.
+    pub fn read_contents(&mut self) -> anyhow::Result<LegacyContents> {
+        let mut contents = LegacyContents::default();
+
+        let table_names = sql_query(
+            "SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%'",
+        )
+        .load::<NameRow>(&mut self.connection)?;
+
+        for NameRow { name: table_name } in table_names {
+            let column_names = sql_query("SELECT name FROM pragma_table_info(?)")
+                .bind::<Text, _>(table_name.as_str())
+                .load::<NameRow>(&mut self.connection)?;
+
+            for NameRow { name: column_name } in column_names {
+                let values = sql_query(format!(
+                    "SELECT CAST({} AS TEXT) AS value FROM {}",
+                    quote_identifier(&column_name),
+                    quote_identifier(&table_name)
+                ))
+                .load::<ValueRow>(&mut self.connection)?;
+
+                for value in values.into_iter().filter_map(|row| row.value) {
+                    match parse_legacy_value(&table_name, &column_name, value.trim()) {
+                        Some(LegacyValue::SecretKey(secret_key))
+                            if !contents.secret_keys.contains(&secret_key) =>
+                        {
+                            contents.secret_keys.push(secret_key);
+                        }
+                        Some(LegacyValue::AppPublicKey(public_key))
+                            if !contents.app_public_keys.contains(&public_key) =>
+                        {
+                            contents.app_public_keys.push(public_key);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        Ok(contents)
+    }
+
+    /// Closes the database and renames its file, so that it isn't found
+    /// again but can still be recovered. Returns the new path of the file.
+    pub fn archive(self) -> anyhow::Result<PathBuf> {
+        let Self { connection, path } = self;
+        drop(connection);
+
+        let mut archived_file_name = path.clone().into_os_string();
+        archived_file_name.push(format!(".imported-{}", Utc::now().format("%Y%m%d%H%M%S")));
+        let archived_path = PathBuf::from(archived_file_name);
+
+        std::fs::rename(&path, &archived_path)?;
+
+        Ok(archived_path)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum LegacyValue {
+    SecretKey(SecretKey),
+    AppPublicKey(PublicKey),
+}
+
+/// Recognizes a value from a legacy database. Bech32 keys are recognized anywhere,
+/// but hex keys only in columns whose name says what they hold, since any 32 bytes
+/// of hex would otherwise be taken for a key.
+fn parse_legacy_value(table_name: &str, column_name: &str, value: &str) -> Option<LegacyValue> {
+    let table_name = table_name.to_lowercase();
+    let column_name = column_name.to_lowercase();
+
+    let is_hex_key = value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit());
+
+    if value.starts_with("nsec1") {
+        return SecretKey::from_bech32(value)
+            .ok()
+            .map(LegacyValue::SecretKey);
+    }
+
+    if is_hex_key
+        && ["nsec", "secret", "private"]
+            .iter()
+            .any(|name| column_name.contains(name))
+    {
+        return SecretKey::from_hex(value).ok().map(LegacyValue::SecretKey);
+    }
+
+    // The user's own public keys are kept alongside their secret keys,
+    // so only public keys in tables of apps are taken to be apps.
+    if !table_name.contains("app") {
+        return None;
+    }
+
+    if value.starts_with("npub1") {
+        return PublicKey::from_bech32(value)
+            .ok()
+            .map(LegacyValue::AppPublicKey);
+    }
+
+    if is_hex_key
+        && ["npub", "pubkey", "public"]
+            .iter()
+            .any(|name| column_name.contains(name))
+    {
+        return PublicKey::from_hex(value)
+            .ok()
+            .map(LegacyValue::AppPublicKey);
+    }
+
+    None
+}
+
+fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr_sdk::Keys;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_legacy_value() {
+        let keys = Keys::generate();
+        let secret_key = keys.secret_key().clone();
+        let public_key = keys.public_key();
+
+        let nsec = secret_key.to_bech32().unwrap();
+        let npub = public_key.to_bech32().unwrap();
+
+        assert_eq!(
+            parse_legacy_value("accounts", "key", &nsec),
+            Some(LegacyValue::SecretKey(secret_key.clone()))
+        );
+        assert_eq!(
+            parse_legacy_value("accounts", "secret_key", &secret_key.to_secret_hex()),
+            Some(LegacyValue::SecretKey(secret_key.clone()))
+        );
+        assert_eq!(
+            parse_legacy_value("applications", "pubkey", &npub),
+            Some(LegacyValue::AppPublicKey(public_key))
+        );
+        assert_eq!(
+            parse_legacy_value("applications", "pubkey", &public_key.to_hex()),
+            Some(LegacyValue::AppPublicKey(public_key))
+        );
+
+        // Public keys outside of app tables are the user's own.
+        assert_eq!(parse_legacy_value("accounts", "npub", &npub), None);
+
+        // Hex is only taken for a key in a column named for one.
+        assert_eq!(
+            parse_legacy_value("accounts", "id", &secret_key.to_secret_hex()),
+            None
+        );
+        assert_eq!(
+            parse_legacy_value("applications", "event_id", &public_key.to_hex()),
+            None
+        );
+
+        assert_eq!(parse_legacy_value("accounts", "name", "Alice"), None);
+    }
+
+    #[test]
+    fn test_read_and_archive_legacy_database() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join(LEGACY_DATABASE_NAME);
+
+        let keys = Keys::generate();
+        let app_keys = Keys::generate();
+
+        let mut connection = SqliteConnection::establish(path.to_str().unwrap()).unwrap();
+        connection
+            .batch_execute(&format!(
+                "CREATE TABLE accounts (id INTEGER PRIMARY KEY, npub TEXT, nsec TEXT);
+                INSERT INTO accounts (npub, nsec) VALUES ('{}', '{}');
+                CREATE TABLE applications (id INTEGER PRIMARY KEY, pubkey TEXT, name TEXT);
+                INSERT INTO applications (pubkey, name) VALUES ('{}', 'Some App');",
+                keys.public_key().to_bech32().unwrap(),
+                keys.secret_key().to_bech32().unwrap(),
+                app_keys.public_key().to_hex(),
+            ))
+            .unwrap();
+        drop(connection);
+
+        let mut legacy_db = LegacyDatabase::open(&path, None).unwrap();
+
+        assert_eq!(
+            legacy_db.read_contents().unwrap(),
+            LegacyContents {
+                secret_keys: vec![keys.secret_key().clone()],
+                app_public_keys: vec![app_keys.public_key()],
+            }
+        );
+
+        let archived_path = legacy_db.archive().unwrap();
+
+        assert!(!path.exists());
+        assert!(archived_path.is_file());
+    }
+}
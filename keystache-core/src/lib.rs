@@ -0,0 +1,88 @@
+//! The logic behind Keystache, independent of any frontend.
+//!
+//! Frontends open a [`db::Database`] with the user's password, then use it alongside
+//! [`fedimint::Wallet`] for payments and [`nostr::NostrModule`] for relay connections.
+//! NIP-46 requests are answered according to the [`policy`] and [`delegation`] rules
+//! saved in the database. The examples in `examples/` walk through signing an event
+//! and paying an invoice end to end, without touching the app's data directory.
+
+#![deny(clippy::pedantic, clippy::nursery)]
+// Errors that can happen at runtime are shown to the user rather than panicking.
+#![cfg_attr(not(test), deny(clippy::unwrap_used))]
+#![allow(clippy::cast_possible_truncation)]
+#![allow(clippy::match_same_arms)]
+#![allow(clippy::missing_const_for_fn)]
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::missing_panics_doc)]
+#![allow(clippy::module_name_repetitions)]
+#![allow(clippy::must_use_candidate)]
+#![allow(clippy::return_self_not_must_use)]
+#![allow(clippy::significant_drop_tightening)]
+
+/// Encrypted backups of the connected apps, stored on relays as NIP-78 app data.
+pub mod app_backup;
+/// Scheduled, encrypted copies of the database.
+pub mod backup;
+/// Multi-part QR codes (`BBQr`), for text too long to fit in a single QR code.
+pub mod bbqr;
+/// The source of time and randomness, which tests can control.
+pub mod clock;
+/// Settings that take effect while the app is running.
+pub mod config;
+/// The encrypted SQLite database that holds keys, settings, and payment history.
+pub mod db;
+/// NIP-26 delegation tokens.
+pub mod delegation;
+/// NIP-04 and NIP-44 encryption for apps that sign in over NIP-46.
+pub mod encryption;
+/// Bitcoin prices in fiat currencies, for entering and showing amounts in fiat.
+pub mod exchange_rate;
+/// The Fedimint wallet, which holds the user's federations and their payments.
+pub mod fedimint;
+/// Files that apps ask to publish or upload under the user's identity.
+pub mod file_attachment;
+/// Follow lists (NIP-02), and the followed keys that can be zapped without prompting.
+pub mod follows;
+/// Tracks operations that shouldn't be interrupted by closing the app.
+pub mod in_flight;
+pub mod keychain;
+/// Imports keys and apps from the old Tauri build of Keystache.
+pub mod legacy;
+/// Paying lightning addresses and LNURL-pay endpoints.
+pub mod lnurl;
+/// Housekeeping for the database and wallet data on disk.
+pub mod maintenance;
+/// Statistics about how NIP-46 requests were answered.
+pub mod metrics;
+/// NIP-49 encrypted secret keys (`ncryptsec`), for moving keys between signers.
+pub mod ncryptsec;
+/// Connections to Nostr relays.
+pub mod nostr;
+/// The user's private notes, such as about a federation.
+pub mod notes;
+/// Nostr Wallet Connect (NIP-47), which lets apps use the wallet.
+pub mod nwc;
+/// Rules for which NIP-46 requests are approved without prompting.
+pub mod policy;
+/// How much information invoices reveal about the user.
+pub mod privacy;
+/// Profiles (kind-0 metadata) of the user's keys.
+pub mod profile;
+/// Signed receipts for payments.
+pub mod receipt;
+/// Importing the user's NIP-65 relay list (kind 10002).
+pub mod relay_list;
+/// Notes on what's new in each release, shown after updating.
+pub mod release_notes;
+/// Records approved NIP-46 requests on a background task.
+pub mod signing_worker;
+/// A two-person rule for large payments, approved over Nostr direct messages.
+pub mod spend_approval;
+/// Experimental threshold (FROST) identities, whose secret key is split across devices.
+pub mod threshold_key;
+/// Failed password attempts, which make the user wait before trying again.
+pub mod unlock_attempts;
+/// Formatting helpers.
+pub mod util;
+/// Zap receipts (NIP-57) for zaps sent to the user's keys.
+pub mod zap;
@@ -0,0 +1,348 @@
+use std::str::FromStr;
+
+use fedimint_core::{config::FederationId, Amount};
+use lightning_invoice::{Bolt11Invoice, Bolt11InvoiceDescription};
+use nostr_sdk::{
+    bitcoin::{
+        bech32,
+        hashes::{sha256, Hash},
+    },
+    PublicKey, Url,
+};
+
+use crate::util::format_amount;
+
+/// URI schemes that are stripped from pasted LNURLs, such as `lightning:LNURL1...`.
+const URI_SCHEMES: [&str; 2] = ["lightning:", "lnurl:"];
+
+/// Reads a lightning address (LUD-16) or a bech32-encoded LNURL (LUD-01), and returns the URL
+/// that the payee's pay parameters are fetched from. `lnurlp://` links (LUD-17) are read too.
+/// Returns `None` for anything else, including BOLT11 invoices.
+pub fn parse_pay_url(input: &str) -> Option<Url> {
+    let input = input.trim();
+
+    let input = URI_SCHEMES
+        .iter()
+        .find_map(|scheme| {
+            input
+                .get(..scheme.len())
+                .filter(|prefix| prefix.eq_ignore_ascii_case(scheme))
+                .map(|_| &input[scheme.len()..])
+        })
+        .unwrap_or(input);
+
+    if let Some((username, domain)) = input.split_once('@') {
+        return lightning_address_url(username, domain);
+    }
+
+    if let Some(rest) = input.strip_prefix("lnurlp://") {
+        let scheme = if is_onion(rest) { "http" } else { "https" };
+        return Url::parse(&format!("{scheme}://{rest}")).ok();
+    }
+
+    let (hrp, data) = bech32::decode(input).ok()?;
+
+    if !hrp.as_str().eq_ignore_ascii_case("lnurl") {
+        return None;
+    }
+
+    let url = Url::parse(&String::from_utf8(data).ok()?).ok()?;
+
+    // LNURLs must be served over HTTPS, except for Tor hidden services.
+    let is_secure =
+        url.scheme() == "https" || (url.scheme() == "http" && url.host_str().is_some_and(is_onion));
+
+    is_secure.then_some(url)
+}
+
+// Lightning addresses are case-insensitive, but their usernames are only served in lowercase.
+fn lightning_address_url(username: &str, domain: &str) -> Option<Url> {
+    let username = username.to_lowercase();
+
+    let is_valid_username = !username.is_empty()
+        && username.chars().all(|c| {
+            c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_' | '.' | '+')
+        });
+
+    if !is_valid_username || domain.is_empty() || domain.contains(['/', '?', '#', '@']) {
+        return None;
+    }
+
+    let scheme = if is_onion(domain) { "http" } else { "https" };
+
+    Url::parse(&format!(
+        "{scheme}://{domain}/.well-known/lnurlp/{username}"
+    ))
+    .ok()
+    .filter(|url| url.host_str().is_some())
+}
+
+/// Encodes `url` as a bech32 LNURL (LUD-01). It's uppercase, which makes for a smaller QR code.
+pub fn encode_lnurl(url: &Url) -> anyhow::Result<String> {
+    Ok(
+        bech32::encode::<bech32::Bech32>(bech32::Hrp::parse("lnurl")?, url.as_str().as_bytes())?
+            .to_uppercase(),
+    )
+}
+
+fn is_onion(host: &str) -> bool {
+    host.split(['/', ':'])
+        .next()
+        .is_some_and(|host| host.ends_with(".onion"))
+}
+
+/// A lightning address that the user receives to. The provider that serves it asks for
+/// invoices over a Wallet Connect connection, and they're created in `federation_id`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceivingLightningAddress {
+    pub address: String,
+    pub federation_id: FederationId,
+    /// The client public key of the provider's Wallet Connect connection.
+    pub client_public_key: PublicKey,
+}
+
+/// What a payee accepts, as returned by their LNURL-pay endpoint (LUD-06).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayParams {
+    callback: Url,
+    pub min_sendable: Amount,
+    pub max_sendable: Amount,
+    // The raw metadata, which the invoice's description hash commits to.
+    metadata: String,
+    /// The payee's description of the payment, from the metadata.
+    pub description_or: Option<String>,
+    /// The longest comment that can be sent along with the payment (LUD-12).
+    /// Zero if comments aren't accepted.
+    pub comment_allowed: usize,
+}
+
+impl PayParams {
+    /// Reads the JSON response of an LNURL-pay endpoint.
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+
+        check_error_status(&value)?;
+
+        if value["tag"].as_str() != Some("payRequest") {
+            return Err(anyhow::anyhow!("This LNURL isn't for payments"));
+        }
+
+        let callback = Url::parse(
+            value["callback"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("The payee didn't say where to get an invoice"))?,
+        )?;
+
+        let read_amount = |key: &str| {
+            value[key]
+                .as_u64()
+                .map(Amount::from_msats)
+                .ok_or_else(|| anyhow::anyhow!("The payee didn't say how much they accept"))
+        };
+
+        let min_sendable = read_amount("minSendable")?;
+        let max_sendable = read_amount("maxSendable")?;
+
+        if min_sendable > max_sendable {
+            return Err(anyhow::anyhow!("The payee doesn't accept any amount"));
+        }
+
+        let metadata = value["metadata"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("The payee didn't describe the payment"))?
+            .to_string();
+
+        // The metadata is a JSON array of `[mime type, content]` pairs.
+        let description_or = serde_json::from_str::<Vec<(String, serde_json::Value)>>(&metadata)?
+            .into_iter()
+            .find(|(mime_type, _)| mime_type == "text/plain")
+            .and_then(|(_, content)| content.as_str().map(ToString::to_string));
+
+        Ok(Self {
+            callback,
+            min_sendable,
+            max_sendable,
+            metadata,
+            description_or,
+            comment_allowed: value["commentAllowed"]
+                .as_u64()
+                .and_then(|comment_allowed| usize::try_from(comment_allowed).ok())
+                .unwrap_or(0),
+        })
+    }
+
+    /// The URL to fetch an invoice for `amount` from, along with an optional comment.
+    /// Fails if the payee doesn't accept the amount or the comment.
+    pub fn invoice_url(&self, amount: Amount, comment: &str) -> anyhow::Result<Url> {
+        if amount < self.min_sendable || amount > self.max_sendable {
+            return Err(anyhow::anyhow!(
+                "The payee only accepts between {} and {}",
+                format_amount(self.min_sendable),
+                format_amount(self.max_sendable)
+            ));
+        }
+
+        if comment.chars().count() > self.comment_allowed {
+            return Err(anyhow::anyhow!(
+                "The payee only accepts comments of up to {} characters",
+                self.comment_allowed
+            ));
+        }
+
+        let mut url = self.callback.clone();
+
+        {
+            let mut query_pairs = url.query_pairs_mut();
+            query_pairs.append_pair("amount", &amount.msats.to_string());
+
+            if !comment.is_empty() {
+                query_pairs.append_pair("comment", comment);
+            }
+        }
+
+        Ok(url)
+    }
+
+    /// Reads the JSON response of the invoice callback, and checks that the invoice is for
+    /// `amount` and commits to the payee's metadata, so that it's the invoice that was asked for.
+    pub fn read_invoice(&self, json: &str, amount: Amount) -> anyhow::Result<Bolt11Invoice> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+
+        check_error_status(&value)?;
+
+        let invoice = Bolt11Invoice::from_str(
+            value["pr"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("The payee didn't send an invoice"))?,
+        )?;
+
+        if invoice.amount_milli_satoshis() != Some(amount.msats) {
+            return Err(anyhow::anyhow!(
+                "The payee sent an invoice for a different amount"
+            ));
+        }
+
+        let metadata_hash = sha256::Hash::hash(self.metadata.as_bytes());
+
+        let is_description_hash_valid = match invoice.description() {
+            Bolt11InvoiceDescription::Hash(hash) => {
+                hash.0.to_byte_array() == metadata_hash.to_byte_array()
+            }
+            Bolt11InvoiceDescription::Direct(_) => false,
+        };
+
+        if !is_description_hash_valid {
+            return Err(anyhow::anyhow!(
+                "The payee sent an invoice that doesn't match their payment description"
+            ));
+        }
+
+        Ok(invoice)
+    }
+}
+
+/// LNURL endpoints report errors as `{"status": "ERROR", "reason": "..."}`.
+fn check_error_status(value: &serde_json::Value) -> anyhow::Result<()> {
+    if value["status"]
+        .as_str()
+        .is_some_and(|status| status.eq_ignore_ascii_case("ERROR"))
+    {
+        return Err(anyhow::anyhow!(
+            "The payee returned an error: {}",
+            value["reason"].as_str().unwrap_or("No reason given")
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The example from LUD-01.
+    const LUD_01_LNURL: &str = "LNURL1DP68GURN8GHJ7UM9WFMXJCM99E3K7MF0V9CXJ0M385EKVCENXC6R2C35XVUKXEFCV5MKVV34X5EKZD3EV56NYD3HXQURZEPEXEJXXEPNXSCRVWFNV9NXZCN9XQ6XYEFHVGCXXCMYXYMNSERXFQ5FNS";
+
+    #[test]
+    fn test_parse_pay_url() {
+        assert_eq!(
+            parse_pay_url(LUD_01_LNURL).unwrap().as_str(),
+            "https://service.com/api?q=3fc3645b439ce8e7f2553a69e5267081d96dcd340693afabe04be7b0ccd178df"
+        );
+        assert_eq!(
+            parse_pay_url(&format!("lightning:{}", LUD_01_LNURL.to_lowercase())),
+            parse_pay_url(LUD_01_LNURL)
+        );
+
+        assert_eq!(
+            parse_pay_url(" satoshi@example.com ").unwrap().as_str(),
+            "https://example.com/.well-known/lnurlp/satoshi"
+        );
+        assert_eq!(
+            parse_pay_url("satoshi@example.onion").unwrap().as_str(),
+            "http://example.onion/.well-known/lnurlp/satoshi"
+        );
+        assert_eq!(
+            parse_pay_url("lnurlp://example.com/pay").unwrap().as_str(),
+            "https://example.com/pay"
+        );
+
+        assert_eq!(
+            parse_pay_url("Satoshi@Example.com").unwrap().as_str(),
+            "https://example.com/.well-known/lnurlp/satoshi"
+        );
+
+        assert_eq!(parse_pay_url("sat/oshi@example.com"), None);
+        assert_eq!(parse_pay_url("@example.com"), None);
+        assert_eq!(parse_pay_url("satoshi@"), None);
+        assert_eq!(parse_pay_url("lnbc1"), None);
+        assert_eq!(parse_pay_url(""), None);
+    }
+
+    #[test]
+    fn test_encode_lnurl() {
+        assert_eq!(
+            encode_lnurl(&parse_pay_url(LUD_01_LNURL).unwrap()).unwrap(),
+            LUD_01_LNURL
+        );
+    }
+
+    #[test]
+    fn test_pay_params() {
+        let params = PayParams::from_json(
+            r#"{
+                "tag": "payRequest",
+                "callback": "https://example.com/callback?id=1",
+                "minSendable": 1000,
+                "maxSendable": 5000000,
+                "metadata": "[[\"text/plain\",\"Tip jar\"],[\"text/identifier\",\"satoshi@example.com\"]]",
+                "commentAllowed": 10
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(params.description_or.as_deref(), Some("Tip jar"));
+        assert_eq!(params.comment_allowed, 10);
+
+        assert_eq!(
+            params
+                .invoice_url(Amount::from_sats(21), "thanks")
+                .unwrap()
+                .as_str(),
+            "https://example.com/callback?id=1&amount=21000&comment=thanks"
+        );
+        assert!(params.invoice_url(Amount::from_msats(999), "").is_err());
+        assert!(params.invoice_url(Amount::from_sats(5001), "").is_err());
+        assert!(params
+            .invoice_url(Amount::from_sats(21), "far too long a comment")
+            .is_err());
+
+        assert!(
+            PayParams::from_json(r#"{"status": "ERROR", "reason": "Not found"}"#)
+                .unwrap_err()
+                .to_string()
+                .contains("Not found")
+        );
+        assert!(PayParams::from_json(r#"{"tag": "withdrawRequest"}"#).is_err());
+    }
+}
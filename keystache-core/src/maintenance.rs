@@ -0,0 +1,69 @@
+use std::{path::Path, time::Duration};
+
+/// How often the database's write-ahead log is checkpointed and its query planner statistics are refreshed.
+pub const DATABASE_MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60 * 60 * 6);
+
+/// Gets the total size in bytes of every file in `path` and its subdirectories.
+/// Symlinks aren't followed.
+pub fn get_directory_size(path: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            size += get_directory_size(&entry.path())?;
+        } else if file_type.is_file() {
+            size += entry.metadata()?.len();
+        }
+    }
+
+    Ok(size)
+}
+
+/// Formats a size in bytes for display, such as `1.5 MB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1000 {
+        return format!("{bytes} B");
+    }
+
+    let mut divisor: u64 = 1000;
+    let mut unit_index = 0;
+
+    while bytes / divisor >= 1000 && unit_index < UNITS.len() - 1 {
+        divisor *= 1000;
+        unit_index += 1;
+    }
+
+    // Rounded to the nearest tenth of a unit.
+    let tenths = (u128::from(bytes) * 10 + u128::from(divisor) / 2) / u128::from(divisor);
+
+    format!("{}.{} {}", tenths / 10, tenths % 10, UNITS[unit_index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(999), "999 B");
+        assert_eq!(format_size(1_500), "1.5 KB");
+        assert_eq!(format_size(2_340_000), "2.3 MB");
+        assert_eq!(format_size(5_000_000_000_000_000), "5000.0 TB");
+    }
+
+    #[test]
+    fn test_get_directory_size() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a"), [0; 10]).unwrap();
+        std::fs::create_dir(dir.path().join("nested")).unwrap();
+        std::fs::write(dir.path().join("nested").join("b"), [0; 5]).unwrap();
+
+        assert_eq!(get_directory_size(dir.path()).unwrap(), 15);
+    }
+}
@@ -0,0 +1,153 @@
+use std::{collections::HashMap, time::Duration};
+
+use nostr_sdk::PublicKey;
+
+/// Requests that wait at least this long for the user are counted as slow.
+/// Many apps give up on a signer well before a minute has passed.
+pub const SLOW_NIP46_REQUEST_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// How a NIP-46 request was answered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Nip46RequestOutcome {
+    Approved,
+    /// Approved without prompting because of an approval grant.
+    AutoApproved,
+    Rejected,
+    /// Rejected without prompting because of one of the app's kind policies.
+    AutoRejected,
+    /// The transport stopped waiting for the response before it was sent,
+    /// usually because the request timed out or the app disconnected.
+    Dropped,
+    /// Rejected automatically after waiting longer than the expiry setting allows.
+    Expired,
+}
+
+/// Counts and timings of the NIP-46 requests from a single app.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AppSigningStats {
+    pub approved_count: usize,
+    pub auto_approved_count: usize,
+    pub rejected_count: usize,
+    pub auto_rejected_count: usize,
+    pub dropped_count: usize,
+    pub expired_count: usize,
+    pub slow_count: usize,
+    // Total time that prompted requests waited for the user.
+    total_wait: Duration,
+}
+
+impl AppSigningStats {
+    pub const fn request_count(&self) -> usize {
+        self.approved_count
+            + self.auto_approved_count
+            + self.rejected_count
+            + self.auto_rejected_count
+            + self.dropped_count
+            + self.expired_count
+    }
+
+    /// The average time that requests waited for the user to approve or reject them.
+    /// Automatically answered requests are left out, since they never wait.
+    pub fn average_wait_or(&self) -> Option<Duration> {
+        let prompted_count = u32::try_from(
+            self.approved_count + self.rejected_count + self.dropped_count + self.expired_count,
+        )
+        .ok()?;
+
+        (prompted_count > 0).then(|| self.total_wait / prompted_count)
+    }
+}
+
+/// NIP-46 request timings for each app, kept in memory for the current session.
+/// Used to debug reports of apps saying that the signer isn't responding.
+#[derive(Debug, Clone, Default)]
+pub struct SigningMetrics {
+    stats_by_public_key: HashMap<PublicKey, AppSigningStats>,
+}
+
+impl SigningMetrics {
+    /// Records how a request from `public_key` was answered
+    /// after waiting `wait` for the user.
+    pub fn record(&mut self, public_key: PublicKey, outcome: Nip46RequestOutcome, wait: Duration) {
+        let stats = self.stats_by_public_key.entry(public_key).or_default();
+
+        match outcome {
+            Nip46RequestOutcome::Approved => stats.approved_count += 1,
+            Nip46RequestOutcome::AutoApproved => stats.auto_approved_count += 1,
+            Nip46RequestOutcome::Rejected => stats.rejected_count += 1,
+            Nip46RequestOutcome::AutoRejected => stats.auto_rejected_count += 1,
+            Nip46RequestOutcome::Dropped => stats.dropped_count += 1,
+            Nip46RequestOutcome::Expired => stats.expired_count += 1,
+        }
+
+        if !matches!(
+            outcome,
+            Nip46RequestOutcome::AutoApproved | Nip46RequestOutcome::AutoRejected
+        ) {
+            stats.total_wait += wait;
+        }
+
+        if wait >= SLOW_NIP46_REQUEST_THRESHOLD {
+            stats.slow_count += 1;
+        }
+    }
+
+    /// Stats for every app that has sent a request this session, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&PublicKey, &AppSigningStats)> {
+        self.stats_by_public_key.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr_sdk::Keys;
+
+    use super::*;
+
+    #[test]
+    fn test_signing_metrics() {
+        let public_key = Keys::generate().public_key();
+
+        let mut metrics = SigningMetrics::default();
+        metrics.record(
+            public_key,
+            Nip46RequestOutcome::Approved,
+            Duration::from_secs(2),
+        );
+        metrics.record(
+            public_key,
+            Nip46RequestOutcome::Rejected,
+            Duration::from_secs(4),
+        );
+        metrics.record(
+            public_key,
+            Nip46RequestOutcome::AutoApproved,
+            Duration::ZERO,
+        );
+        metrics.record(
+            public_key,
+            Nip46RequestOutcome::AutoRejected,
+            Duration::ZERO,
+        );
+        metrics.record(
+            public_key,
+            Nip46RequestOutcome::Dropped,
+            Duration::from_secs(60),
+        );
+        metrics.record(
+            public_key,
+            Nip46RequestOutcome::Expired,
+            Duration::from_secs(90),
+        );
+
+        let (_, stats) = metrics.iter().next().unwrap();
+        assert_eq!(stats.request_count(), 6);
+        assert_eq!(stats.auto_rejected_count, 1);
+        assert_eq!(stats.dropped_count, 1);
+        assert_eq!(stats.expired_count, 1);
+        assert_eq!(stats.slow_count, 2);
+
+        // Automatically answered requests don't count towards the average wait.
+        assert_eq!(stats.average_wait_or(), Some(Duration::from_secs(39)));
+    }
+}
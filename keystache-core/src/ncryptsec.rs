@@ -0,0 +1,65 @@
+use nostr_sdk::{
+    nips::nip49::{EncryptedSecretKey, KeySecurity},
+    FromBech32, SecretKey, ToBech32,
+};
+
+/// How hard exported keys are to guess the passphrase of, as the log2 of scrypt's
+/// cost. NIP-49 suggests 16, which takes about 64 MiB and a fraction of a second.
+const EXPORT_LOG_N: u8 = 16;
+
+/// Encrypts `secret_key` with `passphrase` as an `ncryptsec` string.
+/// Deliberately slow, so call it off the UI thread.
+pub fn export_ncryptsec(secret_key: &SecretKey, passphrase: &str) -> anyhow::Result<String> {
+    encrypt(secret_key, passphrase, EXPORT_LOG_N)
+}
+
+/// Decrypts an `ncryptsec` string, such as one exported by another signer.
+/// Deliberately slow, so call it off the UI thread.
+pub fn import_ncryptsec(ncryptsec: &str, passphrase: &str) -> anyhow::Result<SecretKey> {
+    let ncryptsec = ncryptsec.trim();
+    let ncryptsec = ncryptsec.strip_prefix("nostr:").unwrap_or(ncryptsec);
+
+    let encrypted_secret_key = EncryptedSecretKey::from_bech32(ncryptsec)?;
+
+    encrypted_secret_key
+        .to_secret_key(passphrase)
+        .map_err(|_| anyhow::anyhow!("Wrong passphrase, or the ncryptsec is damaged"))
+}
+
+fn encrypt(secret_key: &SecretKey, passphrase: &str, log_n: u8) -> anyhow::Result<String> {
+    if passphrase.is_empty() {
+        anyhow::bail!("A passphrase is needed to encrypt the key");
+    }
+
+    // Keys can be imported from anywhere, so Keystache can't vouch for how they were handled.
+    let encrypted_secret_key =
+        EncryptedSecretKey::new(secret_key, passphrase, log_n, KeySecurity::Unknown)?;
+
+    Ok(encrypted_secret_key.to_bech32()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr_sdk::Keys;
+
+    use super::*;
+
+    #[test]
+    fn test_ncryptsec_round_trip() {
+        let keys = Keys::generate();
+        let secret_key = keys.secret_key();
+
+        // A low cost keeps the test fast. Decrypting reads the cost from the ncryptsec.
+        let ncryptsec = encrypt(secret_key, "correct horse", 4).unwrap();
+        assert!(ncryptsec.starts_with("ncryptsec1"));
+
+        assert_eq!(
+            &import_ncryptsec(&format!(" nostr:{ncryptsec} "), "correct horse").unwrap(),
+            secret_key
+        );
+        assert!(import_ncryptsec(&ncryptsec, "wrong horse").is_err());
+        assert!(import_ncryptsec("nsec1notencrypted", "correct horse").is_err());
+
+        assert!(encrypt(secret_key, "", 4).is_err());
+    }
+}
@@ -0,0 +1,1349 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::{Debug, Display};
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{NaiveDateTime, TimeDelta};
+
+use futures::{future::join_all, Stream};
+use nostr_relay_pool::{RelayOptions, RelayPoolNotification, RelayStatus, SubscribeOptions};
+use nostr_sdk::{
+    Event, EventSource, Filter, Keys, Kind, SubscriptionId, Timestamp, UnsignedEvent, Url,
+};
+use tokio::sync::{broadcast::error::RecvError, watch};
+
+use crate::clock::Clock;
+use crate::config::Settings;
+use crate::db::Database;
+
+/// How far the system clock can drift from relay time before the user is warned.
+const CLOCK_SKEW_WARNING_THRESHOLD: Duration = Duration::from_secs(5 * 60);
+
+// Relays are given time to connect before the first clock skew check.
+const CLOCK_SKEW_STARTUP_DELAY: Duration = Duration::from_secs(30);
+const CLOCK_SKEW_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const CLOCK_SKEW_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+// The clock skew is estimated from the most recent notes on the connected relays.
+// Busy relays receive notes every few seconds, so the newest notes were created at
+// roughly the current time. Taking the median ignores the occasional misdated note.
+const CLOCK_SKEW_SAMPLE_SIZE: usize = 10;
+
+/// The most events kept while waiting for a relay to reconnect.
+/// The oldest ones are dropped first.
+const OUTBOX_CAPACITY: usize = 100;
+
+/// How often events in the outbox are checked for another attempt at sending them.
+/// See [`NostrModule::retry_outbox`].
+pub const OUTBOX_RETRY_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+// Relays that refuse an event are asked again after this long, then twice as long after each
+// attempt that follows, up to the maximum. Events are given up on after the last attempt.
+const OUTBOX_RETRY_BASE_DELAY_SECS: i64 = 30;
+const MAX_OUTBOX_RETRY_DELAY_SECS: i64 = 60 * 60;
+const MAX_OUTBOX_ATTEMPT_COUNT: u32 = 10;
+
+/// How many of each relay's most recent reads its latency is averaged over.
+const RELAY_LATENCY_WINDOW: usize = 20;
+
+/// How often relays are checked for a lost connection. See [`NostrModule::reconnect_stream`].
+const RELAY_RECONNECT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Relays that lose their connection are reconnected to after this long, then twice as long
+// after each attempt that fails, up to the maximum. Unlike the outbox, relays aren't given up on.
+const RELAY_RECONNECT_BASE_DELAY_SECS: u64 = 5;
+const MAX_RELAY_RECONNECT_DELAY_SECS: u64 = 10 * 60;
+
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct NostrState {
+    pub relay_connections: BTreeMap<Url, RelayStatus>,
+    pub relay_latencies: BTreeMap<Url, RelayLatency>,
+    pub relay_data_usage: BTreeMap<Url, DataUsage>,
+    /// Relays that have lost their connection and are being reconnected to.
+    pub relay_reconnections: BTreeMap<Url, RelayReconnection>,
+}
+
+/// How much data has been sent to and received from a relay since it was added this session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DataUsage {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+impl DataUsage {
+    pub const fn total_bytes(self) -> u64 {
+        self.bytes_sent.saturating_add(self.bytes_received)
+    }
+}
+
+impl NostrState {
+    /// Relays that are connected now but weren't connected in `previous`.
+    pub fn newly_connected_relays(&self, previous: &Self) -> Vec<Url> {
+        self.relay_connections
+            .iter()
+            .filter(|(url, status)| {
+                **status == RelayStatus::Connected
+                    && previous.relay_connections.get(*url) != Some(&RelayStatus::Connected)
+            })
+            .map(|(url, _)| url.clone())
+            .collect()
+    }
+
+    /// How many relays are connected, out of how many have been added.
+    pub fn connected_relay_counts(&self) -> (usize, usize) {
+        let connected_relay_count = self
+            .relay_connections
+            .values()
+            .filter(|status| **status == RelayStatus::Connected)
+            .count();
+
+        (connected_relay_count, self.relay_connections.len())
+    }
+
+    /// The data used by every relay added this session, added together.
+    pub fn total_relay_data_usage(&self) -> DataUsage {
+        self.relay_data_usage
+            .values()
+            .fold(DataUsage::default(), |total, data_usage| DataUsage {
+                bytes_sent: total.bytes_sent.saturating_add(data_usage.bytes_sent),
+                bytes_received: total
+                    .bytes_received
+                    .saturating_add(data_usage.bytes_received),
+            })
+    }
+
+    /// Whether every relay has lost its connection. Relays that haven't
+    /// finished connecting for the first time aren't counted as lost.
+    pub fn has_lost_every_relay(&self) -> bool {
+        !self.relay_connections.is_empty()
+            && self
+                .relay_connections
+                .values()
+                .all(|status| matches!(status, RelayStatus::Disconnected | RelayStatus::Terminated))
+    }
+}
+
+/// What happened to an event passed to [`NostrModule::publish`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublishOutcome {
+    /// At least one relay accepted the event.
+    Sent,
+    /// No relay was connected, so the event will be sent once one reconnects.
+    Queued,
+}
+
+/// An event that Keystache published that hasn't been sent to any relay yet.
+/// Kept in the database, so that it's still sent after Keystache restarts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboxEvent {
+    pub event: Event,
+    /// How many times connected relays failed to accept the event.
+    pub attempt_count: u32,
+    pub next_attempt_time: NaiveDateTime,
+    pub create_time: NaiveDateTime,
+}
+
+/// How long to wait before sending an event again once connected relays
+/// have failed to accept it `attempt_count` times.
+fn outbox_retry_delay(attempt_count: u32) -> TimeDelta {
+    TimeDelta::seconds(
+        OUTBOX_RETRY_BASE_DELAY_SECS
+            .saturating_mul(2_i64.saturating_pow(attempt_count.saturating_sub(1)))
+            .min(MAX_OUTBOX_RETRY_DELAY_SECS),
+    )
+}
+
+/// A relay that has lost its connection, and when it's next reconnected to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RelayReconnection {
+    /// How many times the relay has been reconnected to since it last connected.
+    pub attempt_count: u32,
+    pub next_attempt_time: Instant,
+}
+
+/// How long to wait before reconnecting to a relay that has been
+/// reconnected to `attempt_count` times without connecting.
+fn relay_reconnect_delay(attempt_count: u32) -> Duration {
+    Duration::from_secs(
+        RELAY_RECONNECT_BASE_DELAY_SECS
+            .saturating_mul(2_u64.saturating_pow(attempt_count))
+            .min(MAX_RELAY_RECONNECT_DELAY_SECS),
+    )
+}
+
+/// Keeps track of which relays have lost their connection, and returns the ones
+/// that are due another attempt at reconnecting. Relays in `manual_relay_urls`
+/// have auto-reconnect turned off, so they're left alone.
+fn schedule_relay_reconnections(
+    relay_reconnections: &mut HashMap<Url, RelayReconnection>,
+    relay_statuses: &HashMap<Url, RelayStatus>,
+    manual_relay_urls: &[Url],
+    now: Instant,
+) -> Vec<(Url, RelayReconnection)> {
+    // Attempts are only counted from scratch once a relay has connected,
+    // since it's still connecting for a while after each attempt.
+    relay_reconnections.retain(|url, _| {
+        relay_statuses
+            .get(url)
+            .is_some_and(|status| *status != RelayStatus::Connected)
+            && !manual_relay_urls.contains(url)
+    });
+
+    let mut due_relays = Vec::new();
+
+    for (url, status) in relay_statuses {
+        if !matches!(status, RelayStatus::Disconnected | RelayStatus::Terminated)
+            || manual_relay_urls.contains(url)
+        {
+            continue;
+        }
+
+        let relay_reconnection =
+            relay_reconnections
+                .entry(url.clone())
+                .or_insert(RelayReconnection {
+                    attempt_count: 0,
+                    next_attempt_time: now + relay_reconnect_delay(0),
+                });
+
+        if relay_reconnection.next_attempt_time <= now {
+            relay_reconnection.attempt_count += 1;
+            relay_reconnection.next_attempt_time =
+                now + relay_reconnect_delay(relay_reconnection.attempt_count);
+
+            due_relays.push((url.clone(), *relay_reconnection));
+        }
+    }
+
+    due_relays
+}
+
+/// How quickly a relay has answered recent reads made with [`NostrModule::fetch_events`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RelayLatency {
+    // The most recent reads, oldest first, along with whether each one timed out.
+    // Reads that timed out are counted as taking the whole timeout.
+    samples: VecDeque<(Duration, bool)>,
+}
+
+impl RelayLatency {
+    fn record(&mut self, latency: Duration, is_timeout: bool) {
+        if self.samples.len() == RELAY_LATENCY_WINDOW {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back((latency, is_timeout));
+    }
+
+    /// The average latency of recent reads, or `None` if the relay hasn't been read from yet.
+    pub fn average_or(&self) -> Option<Duration> {
+        let sample_count = u32::try_from(self.samples.len()).ok()?;
+
+        (sample_count > 0).then(|| {
+            self.samples
+                .iter()
+                .map(|(latency, _)| *latency)
+                .sum::<Duration>()
+                / sample_count
+        })
+    }
+
+    /// How many recent reads timed out.
+    pub fn timeout_count(&self) -> usize {
+        self.samples
+            .iter()
+            .filter(|(_, is_timeout)| *is_timeout)
+            .count()
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+}
+
+/// Parses a relay URL as typed by the user. `wss://` is assumed if no scheme is given.
+pub fn parse_relay_url(input: &str) -> anyhow::Result<Url> {
+    let input = input.trim();
+
+    let url = if input.contains("://") {
+        Url::parse(input)?
+    } else {
+        Url::parse(&format!("wss://{input}"))?
+    };
+
+    if !matches!(url.scheme(), "ws" | "wss") || url.host_str().is_none() {
+        anyhow::bail!("Relay URLs must start with wss:// or ws://");
+    }
+
+    Ok(url)
+}
+
+/// Whether two relay URLs point at the same relay. Differences in case, trailing slashes,
+/// default ports, and `ws` versus `wss` are ignored.
+pub fn is_same_relay(a: &Url, b: &Url) -> bool {
+    a.host_str() == b.host_str()
+        && a.port() == b.port()
+        && a.path().trim_end_matches('/') == b.path().trim_end_matches('/')
+        && a.query() == b.query()
+}
+
+/// Picks up to `count` relays to read from, fastest first. Relays that haven't been read from
+/// yet come before the rest, so that every relay gets measured.
+fn select_read_relays(
+    mut relay_urls: Vec<Url>,
+    latencies: &HashMap<Url, RelayLatency>,
+    count: usize,
+) -> Vec<Url> {
+    relay_urls.sort_by_key(|url| latencies.get(url).and_then(RelayLatency::average_or));
+    relay_urls.truncate(count);
+
+    relay_urls
+}
+
+/// What a managed relay subscription is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SubscriptionPurpose {
+    Cosigning,
+    SpendApprovals,
+    WalletConnectRequests,
+    ZapReceipts,
+}
+
+impl Display for SubscriptionPurpose {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cosigning => write!(f, "Threshold key cosigning"),
+            Self::SpendApprovals => write!(f, "Spend approvals"),
+            Self::WalletConnectRequests => write!(f, "Wallet Connect requests"),
+            Self::ZapReceipts => write!(f, "Zap receipts"),
+        }
+    }
+}
+
+/// A long-lived relay subscription opened with [`NostrModule::subscribe`].
+#[derive(Debug, Clone)]
+pub struct ManagedSubscription {
+    pub purpose: SubscriptionPurpose,
+    filters: Vec<Filter>,
+    pub start_time: Instant,
+    pub event_count: u64,
+    /// Events that were dropped because their id or signature didn't match their content.
+    pub unverified_event_count: u64,
+    pub last_event_time_or: Option<Instant>,
+    /// How many times the subscription has been sent again to a relay that reconnected.
+    pub resubscribe_count: u64,
+}
+
+impl ManagedSubscription {
+    /// The average number of events received per minute since the subscription started.
+    pub fn events_per_minute(&self, now: Instant) -> f64 {
+        let elapsed_minutes = now.saturating_duration_since(self.start_time).as_secs_f64() / 60.0;
+
+        if elapsed_minutes <= 0.0 {
+            return 0.0;
+        }
+
+        f64::from(u32::try_from(self.event_count).unwrap_or(u32::MAX)) / elapsed_minutes
+    }
+}
+
+/// Removes a managed subscription from the registry and closes it on the relays once dropped.
+struct ManagedSubscriptionGuard {
+    client: nostr_sdk::Client,
+    subscriptions: Arc<Mutex<HashMap<SubscriptionId, ManagedSubscription>>>,
+    subscription_id: SubscriptionId,
+}
+
+impl Drop for ManagedSubscriptionGuard {
+    fn drop(&mut self) {
+        if let Ok(mut subscriptions) = self.subscriptions.lock() {
+            subscriptions.remove(&self.subscription_id);
+        }
+
+        let client = self.client.clone();
+        let subscription_id = self.subscription_id.clone();
+
+        tokio::spawn(async move {
+            client.unsubscribe(subscription_id).await;
+        });
+    }
+}
+
+/// The estimated difference between the system clock and relay time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockSkew {
+    /// Positive if the system clock is behind relay time, negative if it's ahead.
+    pub seconds: i64,
+}
+
+impl ClockSkew {
+    pub fn exceeds_warning_threshold(self) -> bool {
+        self.seconds.unsigned_abs() > CLOCK_SKEW_WARNING_THRESHOLD.as_secs()
+    }
+
+    /// Estimates the clock skew from the timestamps of recently received events.
+    /// Returns `None` if there aren't enough events to make an estimate.
+    fn estimate(mut event_timestamps: Vec<Timestamp>, now: Timestamp) -> Option<Self> {
+        if event_timestamps.len() < CLOCK_SKEW_SAMPLE_SIZE {
+            return None;
+        }
+
+        event_timestamps.sort_unstable_by(|a, b| b.cmp(a));
+
+        let median = event_timestamps[CLOCK_SKEW_SAMPLE_SIZE / 2];
+
+        Some(Self {
+            seconds: i64::try_from(median.as_u64()).ok()? - i64::try_from(now.as_u64()).ok()?,
+        })
+    }
+}
+
+impl Display for ClockSkew {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let minutes = self.seconds.unsigned_abs() / 60;
+
+        if self.seconds > 0 {
+            write!(f, "{minutes} minutes behind")
+        } else {
+            write!(f, "{minutes} minutes ahead")
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum NostrModuleMessage {
+    ConnectToRelay(String),
+    DisconnectFromRelay(String),
+}
+
+/// Why a [`NostrModuleMessage`] couldn't be carried out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelayUpdateError {
+    Add { url: String, reason: String },
+    Connect { url: String, reason: String },
+    Remove { url: String, reason: String },
+}
+
+impl Display for RelayUpdateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Add { url, reason } => write!(f, "Couldn't add relay {url}: {reason}"),
+            Self::Connect { url, reason } => write!(f, "Couldn't connect to relay {url}: {reason}"),
+            Self::Remove { url, reason } => write!(f, "Couldn't remove relay {url}: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for RelayUpdateError {}
+
+#[derive(Clone)]
+pub struct NostrModule {
+    client: nostr_sdk::Client,
+    subscriptions: Arc<Mutex<HashMap<SubscriptionId, ManagedSubscription>>>,
+    relay_latencies: Arc<Mutex<HashMap<Url, RelayLatency>>>,
+    relay_reconnections: Arc<Mutex<HashMap<Url, RelayReconnection>>>,
+    // Events that couldn't be sent are kept in the database's outbox.
+    // Its length is cached here, since it's shown on every frame.
+    db: Arc<Database>,
+    outbox_len: Arc<AtomicUsize>,
+    settings_receiver: watch::Receiver<Settings>,
+    clock: Clock,
+}
+
+impl NostrModule {
+    pub fn new(settings_receiver: watch::Receiver<Settings>, db: Arc<Database>) -> Self {
+        Self::new_with_clock(settings_receiver, db, Clock::default())
+    }
+
+    fn new_with_clock(
+        settings_receiver: watch::Receiver<Settings>,
+        db: Arc<Database>,
+        clock: Clock,
+    ) -> Self {
+        let nostr_module = Self {
+            client: nostr_sdk::Client::default(),
+            subscriptions: Arc::default(),
+            relay_latencies: Arc::default(),
+            relay_reconnections: Arc::default(),
+            db,
+            outbox_len: Arc::default(),
+            settings_receiver,
+            clock,
+        };
+
+        // Events left over from the last session are sent once a relay connects.
+        nostr_module.refresh_outbox_len();
+
+        nostr_module
+    }
+
+    pub const fn client(&self) -> &nostr_sdk::Client {
+        &self.client
+    }
+
+    /// Whether fetches that aren't needed right away should be put off. See [`Settings::low_data_mode`].
+    pub fn is_low_data_mode(&self) -> bool {
+        self.settings_receiver.borrow().low_data_mode
+    }
+
+    /// Fetches events matching `filters` from the connected relays that have answered
+    /// reads the fastest. Each relay is given up to `timeout` to answer, and is recorded
+    /// as slow if it doesn't. Returned events aren't verified.
+    pub async fn fetch_events(
+        &self,
+        filters: Vec<Filter>,
+        timeout: Duration,
+    ) -> anyhow::Result<Vec<Event>> {
+        let mut connected_relay_urls = Vec::new();
+
+        for (url, relay) in self.client.relays().await {
+            if relay.status().await == RelayStatus::Connected {
+                connected_relay_urls.push(url);
+            }
+        }
+
+        // Reads are only sent to the fastest few relays, since the
+        // slowest relay holds up the whole read.
+        let read_relay_count = self.settings_receiver.borrow().read_relay_count;
+
+        let relay_urls = self
+            .relay_latencies
+            .lock()
+            .map(|latencies| select_read_relays(connected_relay_urls, &latencies, read_relay_count))
+            .unwrap_or_default();
+
+        if relay_urls.is_empty() {
+            anyhow::bail!("No connected relays");
+        }
+
+        let results = join_all(relay_urls.into_iter().map(|url| {
+            let filters = filters.clone();
+
+            async move {
+                let start_time = self.clock.instant();
+
+                let result = self
+                    .client
+                    .get_events_from([url.clone()], filters, Some(timeout))
+                    .await;
+
+                (
+                    url,
+                    self.clock.instant().saturating_duration_since(start_time),
+                    result,
+                )
+            }
+        }))
+        .await;
+
+        let mut events_by_id = HashMap::new();
+        let mut last_err_or = None;
+
+        for (url, latency, result) in results {
+            if let Ok(mut latencies) = self.relay_latencies.lock() {
+                latencies
+                    .entry(url)
+                    .or_default()
+                    .record(latency.min(timeout), latency >= timeout);
+            }
+
+            match result {
+                Ok(events) => {
+                    events_by_id.extend(events.into_iter().map(|event| (event.id, event)));
+                }
+                Err(err) => last_err_or = Some(err),
+            }
+        }
+
+        // Only fail if no relay could be read from.
+        match last_err_or {
+            Some(err) if events_by_id.is_empty() => Err(err.into()),
+            _ => Ok(events_by_id.into_values().collect()),
+        }
+    }
+
+    /// Subscribes to events matching `filters` on every relay, and yields them as they arrive.
+    /// The subscription is kept open across relay reconnects (see [`Self::resubscribe`])
+    /// and is closed once the returned stream is dropped.
+    pub fn subscribe(
+        &self,
+        purpose: SubscriptionPurpose,
+        filters: Vec<Filter>,
+    ) -> impl Stream<Item = Event> {
+        let client = self.client.clone();
+        let subscriptions = self.subscriptions.clone();
+        let clock = self.clock.clone();
+
+        async_stream::stream! {
+            let subscription_id = SubscriptionId::generate();
+
+            let mut notifications = client.notifications();
+
+            // TODO: Log a warning if the subscription fails.
+            if client
+                .subscribe_with_id(subscription_id.clone(), filters.clone(), None)
+                .await
+                .is_err()
+            {
+                return;
+            }
+
+            if let Ok(mut subscriptions) = subscriptions.lock() {
+                subscriptions.insert(
+                    subscription_id.clone(),
+                    ManagedSubscription {
+                        purpose,
+                        filters,
+                        start_time: clock.instant(),
+                        event_count: 0,
+                        unverified_event_count: 0,
+                        last_event_time_or: None,
+                        resubscribe_count: 0,
+                    },
+                );
+            }
+
+            let _guard = ManagedSubscriptionGuard {
+                client: client.clone(),
+                subscriptions: subscriptions.clone(),
+                subscription_id: subscription_id.clone(),
+            };
+
+            loop {
+                let notification = match notifications.recv().await {
+                    Ok(notification) => notification,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                };
+
+                let RelayPoolNotification::Event {
+                    subscription_id: event_subscription_id,
+                    event,
+                    ..
+                } = notification
+                else {
+                    continue;
+                };
+
+                if event_subscription_id != subscription_id {
+                    continue;
+                }
+
+                // Relays can send anything, so events are never used without checking them first.
+                let is_verified = event.verify().is_ok();
+
+                if let Ok(mut subscriptions) = subscriptions.lock() {
+                    if let Some(subscription) = subscriptions.get_mut(&subscription_id) {
+                        if is_verified {
+                            subscription.event_count += 1;
+                            subscription.last_event_time_or = Some(clock.instant());
+                        } else {
+                            subscription.unverified_event_count += 1;
+                        }
+                    }
+                }
+
+                if is_verified {
+                    yield *event;
+                }
+            }
+        }
+    }
+
+    /// Sends `event` to the connected relays. If no relay is connected, such as while
+    /// offline, the event is queued in the outbox and sent by [`Self::flush_outbox`] instead.
+    pub async fn publish(&self, event: Event) -> anyhow::Result<PublishOutcome> {
+        let Err(err) = self.send(event.clone()).await else {
+            return Ok(PublishOutcome::Sent);
+        };
+
+        // Relays that are connected but refused the event would likely refuse it again.
+        if self.has_connected_relay().await {
+            return Err(err);
+        }
+
+        self.db
+            .save_outbox_event(&event, self.clock.now_utc(), OUTBOX_CAPACITY)?;
+        self.refresh_outbox_len();
+
+        Ok(PublishOutcome::Queued)
+    }
+
+    /// Signs `unsigned_event` with `keys` and publishes it with [`Self::publish`].
+    /// Fails without publishing if the event was built for a different key.
+    pub async fn sign_and_publish(
+        &self,
+        unsigned_event: UnsignedEvent,
+        keys: &Keys,
+    ) -> anyhow::Result<PublishOutcome> {
+        if unsigned_event.pubkey != keys.public_key() {
+            anyhow::bail!("The event was built for a different key");
+        }
+
+        self.publish(unsigned_event.sign(keys)?).await
+    }
+
+    async fn send(&self, event: Event) -> anyhow::Result<()> {
+        match self.client.send_event(event).await {
+            Ok(output) if !output.success.is_empty() => Ok(()),
+            Ok(_) => Err(anyhow::anyhow!("No relay accepted the event")),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// How many events are waiting in the outbox to be sent.
+    pub fn outbox_len(&self) -> usize {
+        self.outbox_len.load(Ordering::Relaxed)
+    }
+
+    fn refresh_outbox_len(&self) {
+        // TODO: Log a warning if the outbox fails to be counted.
+        if let Ok(outbox_len) = self.db.count_outbox_events() {
+            self.outbox_len.store(
+                usize::try_from(outbox_len).unwrap_or_default(),
+                Ordering::Relaxed,
+            );
+        }
+    }
+
+    /// Sends every event in the outbox. Should be called whenever a relay (re)connects.
+    pub fn flush_outbox(&self) {
+        self.send_outbox_events(false);
+    }
+
+    /// Sends the events in the outbox that are due another attempt.
+    /// Should be called every [`OUTBOX_RETRY_CHECK_INTERVAL`] while the outbox isn't empty.
+    pub fn retry_outbox(&self) {
+        self.send_outbox_events(true);
+    }
+
+    fn send_outbox_events(&self, is_due_only: bool) {
+        let now = self.clock.now_utc();
+
+        // TODO: Log a warning if the outbox fails to load.
+        let outbox_events: Vec<OutboxEvent> = self
+            .db
+            .list_outbox_events()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|outbox_event| !is_due_only || outbox_event.next_attempt_time <= now)
+            .collect();
+
+        if outbox_events.is_empty() {
+            return;
+        }
+
+        let nostr_module = self.clone();
+
+        tokio::spawn(async move {
+            for outbox_event in outbox_events {
+                let event_id = outbox_event.event.id;
+
+                // TODO: Log a warning if the outbox fails to be updated.
+                let _ = match nostr_module.send(outbox_event.event).await {
+                    Ok(()) => nostr_module.db.remove_outbox_event(&event_id),
+                    // Attempts made while offline don't count, since no relay saw the event.
+                    Err(_) if !nostr_module.has_connected_relay().await => Ok(()),
+                    Err(_) if outbox_event.attempt_count + 1 >= MAX_OUTBOX_ATTEMPT_COUNT => {
+                        nostr_module.db.remove_outbox_event(&event_id)
+                    }
+                    Err(_) => nostr_module.db.record_outbox_attempt(
+                        &event_id,
+                        nostr_module.clock.now_utc()
+                            + outbox_retry_delay(outbox_event.attempt_count + 1),
+                    ),
+                };
+            }
+
+            nostr_module.refresh_outbox_len();
+        });
+    }
+
+    async fn has_connected_relay(&self) -> bool {
+        for relay in self.client.relays().await.values() {
+            if relay.status().await == RelayStatus::Connected {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Sends every managed subscription to `relay_urls` again.
+    /// Relays forget their subscriptions when the connection drops,
+    /// so this should be called whenever a relay (re)connects.
+    pub fn resubscribe(&self, relay_urls: Vec<Url>) {
+        if relay_urls.is_empty() {
+            return;
+        }
+
+        let subscriptions: Vec<(SubscriptionId, Vec<Filter>)> = self
+            .subscriptions
+            .lock()
+            .map(|mut subscriptions| {
+                subscriptions
+                    .iter_mut()
+                    .map(|(subscription_id, subscription)| {
+                        subscription.resubscribe_count += 1;
+                        (subscription_id.clone(), subscription.filters.clone())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if subscriptions.is_empty() {
+            return;
+        }
+
+        let client = self.client.clone();
+
+        tokio::spawn(async move {
+            for relay_url in relay_urls {
+                let Ok(relay) = client.relay(relay_url).await else {
+                    continue;
+                };
+
+                for (subscription_id, filters) in &subscriptions {
+                    // TODO: Log a warning if the subscription fails.
+                    let _ = relay
+                        .subscribe_with_id(
+                            subscription_id.clone(),
+                            filters.clone(),
+                            SubscribeOptions::default(),
+                        )
+                        .await;
+                }
+            }
+        });
+    }
+
+    /// The currently open managed subscriptions, ordered by purpose.
+    pub fn list_subscriptions(&self) -> Vec<ManagedSubscription> {
+        let mut subscriptions: Vec<ManagedSubscription> = self
+            .subscriptions
+            .lock()
+            .map(|subscriptions| subscriptions.values().cloned().collect())
+            .unwrap_or_default();
+
+        subscriptions.sort_by_key(|subscription| (subscription.purpose, subscription.start_time));
+
+        subscriptions
+    }
+
+    /// Connects to or disconnects from a relay. The returned future
+    /// doesn't borrow the module, so it can be run on another task.
+    pub fn update(
+        &self,
+        message: NostrModuleMessage,
+    ) -> impl Future<Output = Result<(), RelayUpdateError>> {
+        let client = self.client.clone();
+
+        if let NostrModuleMessage::DisconnectFromRelay(url) = &message {
+            if let Ok(parsed_url) = Url::parse(url) {
+                if let Ok(mut relay_latencies) = self.relay_latencies.lock() {
+                    relay_latencies.remove(&parsed_url);
+                }
+
+                if let Ok(mut relay_reconnections) = self.relay_reconnections.lock() {
+                    relay_reconnections.remove(&parsed_url);
+                }
+            }
+        }
+
+        async move {
+            match message {
+                NostrModuleMessage::ConnectToRelay(url) => {
+                    // Relays are reconnected to by `reconnect_stream` instead,
+                    // so that it can back off and be turned off for each relay.
+                    client
+                        .add_relay_with_opts(&url, RelayOptions::new().reconnect(false))
+                        .await
+                        .map_err(|err| RelayUpdateError::Add {
+                            url: url.clone(),
+                            reason: err.to_string(),
+                        })?;
+
+                    client
+                        .connect_relay(&url)
+                        .await
+                        .map_err(|err| RelayUpdateError::Connect {
+                            url,
+                            reason: err.to_string(),
+                        })
+                }
+                NostrModuleMessage::DisconnectFromRelay(url) => client
+                    .remove_relay(&url)
+                    .await
+                    .map_err(|err| RelayUpdateError::Remove {
+                        url,
+                        reason: err.to_string(),
+                    }),
+            }
+        }
+    }
+
+    /// Yields the state of the Nostr client whenever it changes, such as when a relay connects.
+    pub fn state_stream(&self) -> impl Stream<Item = NostrState> {
+        const POLL_DURATION: Duration = Duration::from_millis(200);
+
+        let client = self.client.clone();
+        let relay_latencies = self.relay_latencies.clone();
+        let relay_reconnections = self.relay_reconnections.clone();
+        let clock = self.clock.clone();
+
+        async_stream::stream! {
+            let mut last_state = NostrState::default();
+            loop {
+                let new_state =
+                    Self::get_state(&client, &relay_latencies, &relay_reconnections).await;
+                if new_state != last_state {
+                    yield new_state.clone();
+                    last_state = new_state;
+                }
+
+                clock.sleep(POLL_DURATION).await;
+            }
+        }
+    }
+
+    /// Reconnects to relays that have lost their connection, backing off after each attempt
+    /// that fails. Relays that have auto-reconnect turned off are left disconnected.
+    /// Yields each relay as it's reconnected to.
+    pub fn reconnect_stream(&self) -> impl Stream<Item = (Url, RelayReconnection)> {
+        let client = self.client.clone();
+        let relay_reconnections = self.relay_reconnections.clone();
+        let db = self.db.clone();
+        let clock = self.clock.clone();
+
+        async_stream::stream! {
+            loop {
+                clock.sleep(RELAY_RECONNECT_CHECK_INTERVAL).await;
+
+                let mut relay_statuses = HashMap::new();
+                for (url, relay) in client.relays().await {
+                    relay_statuses.insert(url, relay.status().await);
+                }
+
+                // TODO: Log a warning if the relays fail to load.
+                let manual_relay_urls: Vec<Url> = db
+                    .iter_relays()
+                    .collect::<anyhow::Result<Vec<_>>>()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|relay| !relay.auto_reconnect)
+                    .filter_map(|relay| Url::parse(&relay.websocket_url).ok())
+                    .collect();
+
+                let now = clock.instant();
+
+                let due_relays = relay_reconnections
+                    .lock()
+                    .map(|mut relay_reconnections| {
+                        schedule_relay_reconnections(
+                            &mut relay_reconnections,
+                            &relay_statuses,
+                            &manual_relay_urls,
+                            now,
+                        )
+                    })
+                    .unwrap_or_default();
+
+                for (url, relay_reconnection) in due_relays {
+                    // TODO: Log a warning if the relay fails to reconnect.
+                    let _ = client.connect_relay(url.clone()).await;
+
+                    yield (url, relay_reconnection);
+                }
+            }
+        }
+    }
+
+    /// Periodically estimates how far the system clock is from relay time.
+    /// Nostr event timestamps and invoice expiries depend on an accurate clock.
+    /// Checks are skipped while in low data mode.
+    pub fn clock_skew_stream(&self) -> impl Stream<Item = ClockSkew> {
+        let client = self.client.clone();
+        let settings_receiver = self.settings_receiver.clone();
+        let clock = self.clock.clone();
+
+        async_stream::stream! {
+            clock.sleep(CLOCK_SKEW_STARTUP_DELAY).await;
+
+            loop {
+                if settings_receiver.borrow().low_data_mode {
+                    clock.sleep(CLOCK_SKEW_CHECK_INTERVAL).await;
+                    continue;
+                }
+
+                // TODO: Log a warning if the events fail to load.
+                if let Ok(events) = client
+                    .get_events_of(
+                        vec![Filter::new().kind(Kind::TextNote).limit(50)],
+                        EventSource::relays(Some(CLOCK_SKEW_FETCH_TIMEOUT)),
+                    )
+                    .await
+                {
+                    // Unverified events are left out, since anyone could have misdated them.
+                    let event_timestamps = events
+                        .iter()
+                        .filter(|event| event.verify().is_ok())
+                        .map(|event| event.created_at)
+                        .collect();
+
+                    let now = Timestamp::from(clock.unix_timestamp_secs());
+
+                    if let Some(clock_skew) = ClockSkew::estimate(event_timestamps, now) {
+                        yield clock_skew;
+                    }
+                }
+
+                clock.sleep(CLOCK_SKEW_CHECK_INTERVAL).await;
+            }
+        }
+    }
+
+    /// Fetches the current state of the Nostr SDK client.
+    /// Note: This is async because it's grabbing read locks
+    /// on the relay `RwLock`s. No network requests are made.
+    async fn get_state(
+        client: &nostr_sdk::Client,
+        relay_latencies: &Mutex<HashMap<Url, RelayLatency>>,
+        relay_reconnections: &Mutex<HashMap<Url, RelayReconnection>>,
+    ) -> NostrState {
+        let mut relay_connections = BTreeMap::new();
+        let mut relay_data_usage = BTreeMap::new();
+
+        for (url, relay) in client.relays().await {
+            relay_connections.insert(url.clone(), relay.status().await);
+
+            let stats = relay.stats();
+            relay_data_usage.insert(
+                url,
+                DataUsage {
+                    bytes_sent: u64::try_from(stats.bytes_sent()).unwrap_or(u64::MAX),
+                    bytes_received: u64::try_from(stats.bytes_received()).unwrap_or(u64::MAX),
+                },
+            );
+        }
+
+        let relay_latencies = relay_latencies
+            .lock()
+            .map(|relay_latencies| {
+                relay_latencies
+                    .iter()
+                    .map(|(url, latency)| (url.clone(), latency.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let relay_reconnections = relay_reconnections
+            .lock()
+            .map(|relay_reconnections| {
+                relay_reconnections
+                    .iter()
+                    .map(|(url, relay_reconnection)| (url.clone(), *relay_reconnection))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        NostrState {
+            relay_connections,
+            relay_latencies,
+            relay_data_usage,
+            relay_reconnections,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outbox_retry_delay() {
+        assert_eq!(outbox_retry_delay(1), TimeDelta::seconds(30));
+        assert_eq!(outbox_retry_delay(2), TimeDelta::seconds(60));
+        assert_eq!(outbox_retry_delay(4), TimeDelta::seconds(240));
+
+        // The delay stops growing at an hour, even after many attempts.
+        assert_eq!(outbox_retry_delay(8), TimeDelta::hours(1));
+        assert_eq!(outbox_retry_delay(u32::MAX), TimeDelta::hours(1));
+    }
+
+    #[test]
+    fn test_relay_reconnect_delay() {
+        assert_eq!(relay_reconnect_delay(0), Duration::from_secs(5));
+        assert_eq!(relay_reconnect_delay(1), Duration::from_secs(10));
+        assert_eq!(relay_reconnect_delay(3), Duration::from_secs(40));
+
+        // The delay stops growing at ten minutes, even after many attempts.
+        assert_eq!(relay_reconnect_delay(7), Duration::from_secs(10 * 60));
+        assert_eq!(
+            relay_reconnect_delay(u32::MAX),
+            Duration::from_secs(10 * 60)
+        );
+    }
+
+    #[test]
+    fn test_schedule_relay_reconnections() {
+        let relay_a = Url::parse("wss://a.example.com").unwrap();
+        let relay_b = Url::parse("wss://b.example.com").unwrap();
+
+        let mut relay_reconnections = HashMap::new();
+        let start = Instant::now();
+
+        let statuses = |status_a, status_b| {
+            HashMap::from([(relay_a.clone(), status_a), (relay_b.clone(), status_b)])
+        };
+
+        // Dropped relays aren't reconnected to straight away.
+        let disconnected = statuses(RelayStatus::Disconnected, RelayStatus::Terminated);
+        assert!(
+            schedule_relay_reconnections(&mut relay_reconnections, &disconnected, &[], start)
+                .is_empty()
+        );
+        assert_eq!(relay_reconnections.len(), 2);
+
+        // Relays with auto-reconnect turned off are left alone.
+        let due_relays = schedule_relay_reconnections(
+            &mut relay_reconnections,
+            &disconnected,
+            &[relay_b.clone()],
+            start + Duration::from_secs(5),
+        );
+        assert_eq!(due_relays.len(), 1);
+        assert_eq!(due_relays[0].0, relay_a);
+        assert_eq!(due_relays[0].1.attempt_count, 1);
+        assert!(!relay_reconnections.contains_key(&relay_b));
+
+        // The attempt count carries on while the relay is connecting,
+        // and backs off if it fails again.
+        let connecting = statuses(RelayStatus::Connecting, RelayStatus::Connected);
+        schedule_relay_reconnections(
+            &mut relay_reconnections,
+            &connecting,
+            &[],
+            start + Duration::from_secs(6),
+        );
+        assert!(schedule_relay_reconnections(
+            &mut relay_reconnections,
+            &disconnected,
+            &[relay_b.clone()],
+            start + Duration::from_secs(14),
+        )
+        .is_empty());
+        assert_eq!(
+            schedule_relay_reconnections(
+                &mut relay_reconnections,
+                &disconnected,
+                &[relay_b.clone()],
+                start + Duration::from_secs(15),
+            )[0]
+            .1
+            .attempt_count,
+            2
+        );
+
+        // Attempts start over once the relay connects.
+        let connected = statuses(RelayStatus::Connected, RelayStatus::Connected);
+        schedule_relay_reconnections(
+            &mut relay_reconnections,
+            &connected,
+            &[],
+            start + Duration::from_secs(16),
+        );
+        assert!(relay_reconnections.is_empty());
+    }
+
+    #[test]
+    fn test_newly_connected_relays() {
+        let relay_a = Url::parse("wss://a.example.com").unwrap();
+        let relay_b = Url::parse("wss://b.example.com").unwrap();
+        let relay_c = Url::parse("wss://c.example.com").unwrap();
+
+        let previous = NostrState {
+            relay_connections: BTreeMap::from([
+                (relay_a.clone(), RelayStatus::Connected),
+                (relay_b.clone(), RelayStatus::Disconnected),
+            ]),
+            relay_latencies: BTreeMap::new(),
+            relay_data_usage: BTreeMap::new(),
+            relay_reconnections: BTreeMap::new(),
+        };
+
+        let current = NostrState {
+            relay_connections: BTreeMap::from([
+                (relay_a, RelayStatus::Connected),
+                (relay_b.clone(), RelayStatus::Connected),
+                (relay_c.clone(), RelayStatus::Connected),
+            ]),
+            relay_latencies: BTreeMap::new(),
+            relay_data_usage: BTreeMap::new(),
+            relay_reconnections: BTreeMap::new(),
+        };
+
+        assert_eq!(
+            current.newly_connected_relays(&previous),
+            vec![relay_b, relay_c]
+        );
+    }
+
+    #[test]
+    fn test_is_same_relay() {
+        let same = |a: &str, b: &str| {
+            is_same_relay(&parse_relay_url(a).unwrap(), &parse_relay_url(b).unwrap())
+        };
+
+        assert!(same("wss://relay.example.com", "wss://relay.example.com/"));
+        assert!(same("wss://Relay.Example.com", "relay.example.com"));
+        assert!(same(
+            "wss://relay.example.com:443",
+            "ws://relay.example.com"
+        ));
+        assert!(same(
+            "wss://relay.example.com/nostr/",
+            "wss://relay.example.com/nostr"
+        ));
+
+        assert!(!same("wss://relay.example.com", "wss://other.example.com"));
+        assert!(!same(
+            "wss://relay.example.com",
+            "wss://relay.example.com:8080"
+        ));
+        assert!(!same(
+            "wss://relay.example.com/a",
+            "wss://relay.example.com/b"
+        ));
+
+        assert!(parse_relay_url("https://relay.example.com").is_err());
+        assert!(parse_relay_url("").is_err());
+    }
+
+    #[test]
+    fn test_has_lost_every_relay() {
+        let relay_a = Url::parse("wss://a.example.com").unwrap();
+        let relay_b = Url::parse("wss://b.example.com").unwrap();
+
+        let state = |status_a, status_b| NostrState {
+            relay_connections: BTreeMap::from([
+                (relay_a.clone(), status_a),
+                (relay_b.clone(), status_b),
+            ]),
+            relay_latencies: BTreeMap::new(),
+            relay_data_usage: BTreeMap::new(),
+            relay_reconnections: BTreeMap::new(),
+        };
+
+        assert!(!NostrState::default().has_lost_every_relay());
+        assert!(!state(RelayStatus::Connected, RelayStatus::Disconnected).has_lost_every_relay());
+        assert!(!state(RelayStatus::Connecting, RelayStatus::Disconnected).has_lost_every_relay());
+        assert!(state(RelayStatus::Disconnected, RelayStatus::Terminated).has_lost_every_relay());
+    }
+
+    #[test]
+    fn test_total_relay_data_usage() {
+        let state = NostrState {
+            relay_data_usage: BTreeMap::from([
+                (
+                    Url::parse("wss://a.example.com").unwrap(),
+                    DataUsage {
+                        bytes_sent: 100,
+                        bytes_received: 2_000,
+                    },
+                ),
+                (
+                    Url::parse("wss://b.example.com").unwrap(),
+                    DataUsage {
+                        bytes_sent: 50,
+                        bytes_received: 500,
+                    },
+                ),
+            ]),
+            ..NostrState::default()
+        };
+
+        assert_eq!(
+            state.total_relay_data_usage(),
+            DataUsage {
+                bytes_sent: 150,
+                bytes_received: 2_500,
+            }
+        );
+        assert_eq!(state.total_relay_data_usage().total_bytes(), 2_650);
+    }
+
+    #[test]
+    fn test_relay_latency() {
+        let mut latency = RelayLatency::default();
+        assert_eq!(latency.average_or(), None);
+
+        latency.record(Duration::from_millis(100), false);
+        latency.record(Duration::from_millis(300), false);
+        assert_eq!(latency.average_or(), Some(Duration::from_millis(200)));
+
+        // Only the most recent reads are counted.
+        for _ in 0..RELAY_LATENCY_WINDOW {
+            latency.record(Duration::from_secs(10), true);
+        }
+        assert_eq!(latency.average_or(), Some(Duration::from_secs(10)));
+        assert_eq!(latency.timeout_count(), RELAY_LATENCY_WINDOW);
+        assert_eq!(latency.sample_count(), RELAY_LATENCY_WINDOW);
+    }
+
+    #[test]
+    fn test_select_read_relays() {
+        let fast = Url::parse("wss://fast.example.com").unwrap();
+        let slow = Url::parse("wss://slow.example.com").unwrap();
+        let new = Url::parse("wss://new.example.com").unwrap();
+
+        let mut latencies = HashMap::new();
+        latencies
+            .entry(fast.clone())
+            .or_insert_with(RelayLatency::default)
+            .record(Duration::from_millis(50), false);
+        latencies
+            .entry(slow.clone())
+            .or_insert_with(RelayLatency::default)
+            .record(Duration::from_secs(5), true);
+
+        // Unmeasured relays come first, then the fastest ones.
+        assert_eq!(
+            select_read_relays(vec![slow.clone(), fast.clone(), new.clone()], &latencies, 2),
+            vec![new, fast]
+        );
+    }
+
+    #[test]
+    fn test_clock_skew_estimate() {
+        let now = Timestamp::from(1_700_000_000);
+        let timestamps = |offsets: &[i64]| {
+            offsets
+                .iter()
+                .map(|offset| Timestamp::from(now.as_u64().checked_add_signed(*offset).unwrap()))
+                .collect()
+        };
+
+        // Not enough events to make an estimate.
+        assert_eq!(ClockSkew::estimate(timestamps(&[0, -5, -10]), now), None);
+
+        // Recent events are within a few seconds of the current time,
+        // even if one of them is misdated far in the future.
+        let clock_skew = ClockSkew::estimate(
+            timestamps(&[9_999, 0, -1, -2, -3, -4, -5, -6, -7, -8, -9]),
+            now,
+        )
+        .unwrap();
+        assert!(!clock_skew.exceeds_warning_threshold());
+
+        // The most recent events are 10 minutes old, so the system clock is ahead.
+        let clock_skew = ClockSkew::estimate(
+            timestamps(&[-600, -601, -602, -603, -604, -605, -606, -607, -608, -609]),
+            now,
+        )
+        .unwrap();
+        assert!(clock_skew.exceeds_warning_threshold());
+        assert_eq!(clock_skew.to_string(), "10 minutes ahead");
+    }
+}
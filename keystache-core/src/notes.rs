@@ -0,0 +1,20 @@
+use std::fmt::Display;
+
+use fedimint_core::config::FederationId;
+
+/// What a private note is about. Notes are freeform text that the user writes,
+/// such as why they joined a federation or who its guardians are. They're kept in the
+/// database, so they're encrypted along with it and included in encrypted exports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteSubject {
+    Federation(FederationId),
+}
+
+/// The key that the note is saved under.
+impl Display for NoteSubject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Federation(federation_id) => write!(f, "federation:{federation_id}"),
+        }
+    }
+}
@@ -0,0 +1,571 @@
+use std::{collections::BTreeSet, fmt::Display, str::FromStr, sync::Arc, time::Duration};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use fedimint_core::Amount;
+use futures::{Stream, StreamExt};
+use lightning_invoice::Bolt11Invoice;
+use nostr_sdk::{
+    nips::{
+        nip04,
+        nip47::{
+            ErrorCode, GetBalanceResponseResult, MakeInvoiceRequestParams,
+            MakeInvoiceResponseResult, Method, NIP47Error, PayInvoiceRequestParams,
+            PayInvoiceResponseResult, Request, RequestParams, Response, ResponseResult,
+        },
+    },
+    EventBuilder, EventId, Filter, JsonUtil, Keys, Kind, PublicKey, SecretKey, Tag, Timestamp, Url,
+};
+
+use crate::{
+    db::{self, Database},
+    nostr::{NostrModule, SubscriptionPurpose},
+};
+
+/// How far back to look for requests from an app that hasn't sent any before.
+/// Otherwise, requests are picked up from the latest one that was handled,
+/// so that requests sent while Keystache was closed aren't missed.
+const FIRST_REQUEST_LOOKBACK: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// A NIP-47 method that a connection can be allowed to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NwcMethod {
+    PayInvoice,
+    MakeInvoice,
+    GetBalance,
+}
+
+impl NwcMethod {
+    pub const ALL: [Self; 3] = [Self::PayInvoice, Self::MakeInvoice, Self::GetBalance];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::PayInvoice => "pay_invoice",
+            Self::MakeInvoice => "make_invoice",
+            Self::GetBalance => "get_balance",
+        }
+    }
+}
+
+impl Display for NwcMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PayInvoice => write!(f, "Request payments"),
+            Self::MakeInvoice => write!(f, "Create invoices"),
+            Self::GetBalance => write!(f, "See balance"),
+        }
+    }
+}
+
+impl FromStr for NwcMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pay_invoice" => Ok(Self::PayInvoice),
+            "make_invoice" => Ok(Self::MakeInvoice),
+            "get_balance" => Ok(Self::GetBalance),
+            _ => Err(anyhow::anyhow!("Unknown Wallet Connect method: {s}")),
+        }
+    }
+}
+
+/// How often a connection's budget starts over.
+/// Budgets cover a rolling window rather than calendar periods.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BudgetPeriod {
+    Daily,
+    Weekly,
+    #[default]
+    Monthly,
+    /// The budget never starts over.
+    Total,
+}
+
+impl BudgetPeriod {
+    pub const ALL: [Self; 4] = [Self::Daily, Self::Weekly, Self::Monthly, Self::Total];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+            Self::Total => "total",
+        }
+    }
+
+    /// When the current budget window started, or `None` if it never starts over.
+    pub fn window_start_or(self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let days = match self {
+            Self::Daily => 1,
+            Self::Weekly => 7,
+            Self::Monthly => 30,
+            Self::Total => return None,
+        };
+
+        Some(now - chrono::Duration::days(days))
+    }
+}
+
+impl Display for BudgetPeriod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Daily => write!(f, "Per day"),
+            Self::Weekly => write!(f, "Per week"),
+            Self::Monthly => write!(f, "Per 30 days"),
+            Self::Total => write!(f, "In total"),
+        }
+    }
+}
+
+impl FromStr for BudgetPeriod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "daily" => Ok(Self::Daily),
+            "weekly" => Ok(Self::Weekly),
+            "monthly" => Ok(Self::Monthly),
+            "total" => Ok(Self::Total),
+            _ => Err(anyhow::anyhow!("Unknown budget period: {s}")),
+        }
+    }
+}
+
+/// The most that a connection's app can spend, including fees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NwcBudget {
+    pub amount: Amount,
+    pub period: BudgetPeriod,
+}
+
+/// A Nostr Wallet Connect (NIP-47) connection between Keystache
+/// (the wallet service) and a single client app.
+#[derive(Debug, Clone)]
+pub struct NwcConnection {
+    pub service_secret_key: SecretKey,
+    pub client_secret_key: SecretKey,
+    pub relay_url: Url,
+    /// A name for the app, to tell connections apart.
+    pub name: String,
+    pub allowed_methods: BTreeSet<NwcMethod>,
+    /// `None` if the app can spend as much as the user approves.
+    pub budget_or: Option<NwcBudget>,
+}
+
+impl NwcConnection {
+    pub fn generate(
+        relay_url: Url,
+        name: String,
+        allowed_methods: BTreeSet<NwcMethod>,
+        budget_or: Option<NwcBudget>,
+    ) -> Self {
+        Self {
+            service_secret_key: SecretKey::generate(),
+            client_secret_key: SecretKey::generate(),
+            relay_url,
+            name,
+            allowed_methods,
+            budget_or,
+        }
+    }
+
+    pub fn service_keys(&self) -> Keys {
+        Keys::new(self.service_secret_key.clone())
+    }
+
+    pub fn client_public_key(&self) -> PublicKey {
+        Keys::new(self.client_secret_key.clone()).public_key()
+    }
+
+    /// The `nostr+walletconnect://` URI to paste into a client app.
+    pub fn to_uri(&self) -> anyhow::Result<String> {
+        let mut uri = Url::parse(&format!(
+            "nostr+walletconnect://{}",
+            self.service_keys().public_key().to_hex()
+        ))?;
+
+        uri.query_pairs_mut()
+            .append_pair("relay", self.relay_url.as_str())
+            .append_pair("secret", &self.client_secret_key.to_secret_hex());
+
+        Ok(uri.to_string())
+    }
+}
+
+/// A Wallet Connect connection as saved in the database.
+#[derive(Debug, Clone)]
+pub struct NwcConnectionRecord {
+    pub id: i32,
+    pub connection: NwcConnection,
+    /// When the connection was revoked, if it has been.
+    /// Requests sent over revoked connections are ignored.
+    pub revoke_time_or: Option<NaiveDateTime>,
+    pub create_time: NaiveDateTime,
+}
+
+impl NwcConnectionRecord {
+    pub const fn is_revoked(&self) -> bool {
+        self.revoke_time_or.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentRequestStatus {
+    Pending,
+    Paid,
+    Rejected,
+}
+
+impl PaymentRequestStatus {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Paid => "paid",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+/// A `pay_invoice` request received from a client app.
+#[derive(Debug, Clone)]
+pub struct PayInvoiceRequest {
+    pub request_event_id: EventId,
+    pub requester_public_key: PublicKey,
+    pub invoice: Bolt11Invoice,
+}
+
+/// A `make_invoice` request received from a client app.
+/// Invoices are created without asking the user, since receiving can't lose funds.
+#[derive(Debug, Clone)]
+pub struct MakeInvoiceRequest {
+    pub request_event_id: EventId,
+    pub requester_public_key: PublicKey,
+    pub amount: Amount,
+    pub description: String,
+    /// The hex-encoded SHA-256 hash that the invoice should commit to instead of
+    /// `description`, such as the metadata of an LNURL-pay request (LUD-06).
+    pub description_hash_or: Option<String>,
+}
+
+/// A NIP-47 request that Keystache can handle.
+#[derive(Debug, Clone)]
+pub enum NwcRequest {
+    PayInvoice(PayInvoiceRequest),
+    MakeInvoice(MakeInvoiceRequest),
+    GetBalance {
+        request_event_id: EventId,
+        requester_public_key: PublicKey,
+    },
+}
+
+/// A pending `pay_invoice` request that has been persisted to the payment request inbox.
+#[derive(Debug, Clone)]
+pub struct PaymentRequest {
+    pub id: i32,
+    pub request: PayInvoiceRequest,
+    pub create_time: NaiveDateTime,
+}
+
+/// Listens for NIP-47 requests sent to `connection` and yields every `pay_invoice`,
+/// `make_invoice` and `get_balance` request that the connection is allowed to make.
+/// Requests for methods that the connection isn't allowed to use are answered immediately
+/// with a `RESTRICTED` error, and requests for any other method with a `NOT_IMPLEMENTED` error.
+/// Each request is recorded in `db` before it's handled, so requests that are received
+/// again are skipped, as are requests past their `expiration`.
+pub fn request_stream(
+    nostr_module: NostrModule,
+    db: Arc<Database>,
+    connection: NwcConnection,
+) -> impl Stream<Item = NwcRequest> {
+    async_stream::stream! {
+        let client = nostr_module.client().clone();
+        let service_public_key = connection.service_keys().public_key();
+        let client_public_key = connection.client_public_key();
+
+        // TODO: Log a warning if the relay can't be added.
+        if client.add_relay(connection.relay_url.as_str()).await.is_ok() {
+            let _ = client.connect_relay(connection.relay_url.as_str()).await;
+        }
+
+        // TODO: Log a warning if the last request time fails to load.
+        let since = db
+            .get_last_nwc_request_time(&client_public_key)
+            .ok()
+            .flatten()
+            .and_then(|last_request_time| {
+                u64::try_from(last_request_time.and_utc().timestamp()).ok()
+            })
+            .map_or_else(
+                || {
+                    Timestamp::from(
+                        Timestamp::now()
+                            .as_u64()
+                            .saturating_sub(FIRST_REQUEST_LOOKBACK.as_secs()),
+                    )
+                },
+                Timestamp::from,
+            );
+
+        let filter = Filter::new()
+            .kind(Kind::WalletConnectRequest)
+            .author(client_public_key)
+            .pubkey(service_public_key)
+            .since(since);
+
+        let mut events = Box::pin(
+            nostr_module.subscribe(SubscriptionPurpose::WalletConnectRequests, vec![filter]),
+        );
+
+        while let Some(event) = events.next().await {
+            if event.kind != Kind::WalletConnectRequest || event.pubkey != client_public_key {
+                continue;
+            }
+
+            // The app has given up on expired requests, so they're ignored.
+            if event.is_expired() {
+                continue;
+            }
+
+            // Requests are recorded before they're handled, so that a request is never
+            // handled twice, such as creating a second invoice for the same request.
+            // If the request can't be recorded, it's skipped for the same reason.
+            // TODO: Log a warning if the request fails to be recorded.
+            let Some(request_time) = i64::try_from(event.created_at.as_u64())
+                .ok()
+                .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+            else {
+                continue;
+            };
+            if !db::retry_while_busy(|| {
+                db.save_nwc_handled_request(&event.id, &event.pubkey, request_time.naive_utc())
+            })
+            .await
+            .unwrap_or(false)
+            {
+                continue;
+            }
+
+            let Ok(request) = nip04::decrypt(
+                &connection.service_secret_key,
+                &event.pubkey,
+                &event.content,
+            )
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Request::from_json(json).map_err(anyhow::Error::from)) else {
+                continue;
+            };
+
+            let method_or = match request.method {
+                Method::PayInvoice => Some(NwcMethod::PayInvoice),
+                Method::MakeInvoice => Some(NwcMethod::MakeInvoice),
+                Method::GetBalance => Some(NwcMethod::GetBalance),
+                _ => None,
+            };
+
+            if method_or.is_some_and(|method| !connection.allowed_methods.contains(&method)) {
+                // TODO: Log a warning if the response fails to send.
+                let _ = send_response(
+                    &nostr_module,
+                    &connection,
+                    event.id,
+                    &error_response(
+                        request.method,
+                        ErrorCode::Restricted,
+                        "This connection isn't allowed to use this method",
+                    ),
+                )
+                .await;
+                continue;
+            }
+
+            let error_response = match request.params {
+                RequestParams::PayInvoice(PayInvoiceRequestParams { invoice, .. }) => {
+                    match Bolt11Invoice::from_str(&invoice) {
+                        Ok(invoice) => {
+                            yield NwcRequest::PayInvoice(PayInvoiceRequest {
+                                request_event_id: event.id,
+                                requester_public_key: event.pubkey,
+                                invoice,
+                            });
+                            continue;
+                        }
+                        Err(_) => error_response(
+                            Method::PayInvoice,
+                            ErrorCode::Other,
+                            "Invalid invoice",
+                        ),
+                    }
+                }
+                RequestParams::MakeInvoice(MakeInvoiceRequestParams {
+                    amount,
+                    description,
+                    description_hash,
+                    ..
+                }) => {
+                    // TODO: Support `expiry`.
+                    yield NwcRequest::MakeInvoice(MakeInvoiceRequest {
+                        request_event_id: event.id,
+                        requester_public_key: event.pubkey,
+                        amount: Amount::from_msats(amount),
+                        description: description.unwrap_or_default(),
+                        description_hash_or: description_hash,
+                    });
+                    continue;
+                }
+                RequestParams::GetBalance => {
+                    yield NwcRequest::GetBalance {
+                        request_event_id: event.id,
+                        requester_public_key: event.pubkey,
+                    };
+                    continue;
+                }
+                _ => error_response(
+                    request.method,
+                    ErrorCode::NotImplemented,
+                    "Keystache only supports pay_invoice, make_invoice and get_balance",
+                ),
+            };
+
+            // TODO: Log a warning if the response fails to send.
+            let _ = send_response(&nostr_module, &connection, event.id, &error_response).await;
+        }
+    }
+}
+
+/// Encrypts and publishes a response to the request with the given event id.
+/// The response is queued if no relay is connected. See [`NostrModule::publish`].
+pub async fn send_response(
+    nostr_module: &NostrModule,
+    connection: &NwcConnection,
+    request_event_id: EventId,
+    response: &Response,
+) -> anyhow::Result<()> {
+    let client_public_key = connection.client_public_key();
+
+    let content = nip04::encrypt(
+        &connection.service_secret_key,
+        &client_public_key,
+        response.as_json(),
+    )?;
+
+    let event = EventBuilder::new(
+        Kind::WalletConnectResponse,
+        content,
+        [
+            Tag::public_key(client_public_key),
+            Tag::event(request_event_id),
+        ],
+    )
+    .to_event(&connection.service_keys())?;
+
+    nostr_module.publish(event).await?;
+
+    Ok(())
+}
+
+pub fn pay_invoice_response(preimage: String) -> Response {
+    Response {
+        result_type: Method::PayInvoice,
+        error: None,
+        result: Some(ResponseResult::PayInvoice(PayInvoiceResponseResult {
+            preimage,
+        })),
+    }
+}
+
+pub fn make_invoice_response(invoice: &Bolt11Invoice) -> Response {
+    Response {
+        result_type: Method::MakeInvoice,
+        error: None,
+        result: Some(ResponseResult::MakeInvoice(MakeInvoiceResponseResult {
+            invoice: invoice.to_string(),
+            payment_hash: invoice.payment_hash().to_string(),
+        })),
+    }
+}
+
+pub fn get_balance_response(balance: Amount) -> Response {
+    Response {
+        result_type: Method::GetBalance,
+        error: None,
+        result: Some(ResponseResult::GetBalance(GetBalanceResponseResult {
+            balance: balance.msats,
+        })),
+    }
+}
+
+pub fn error_response(method: Method, code: ErrorCode, message: &str) -> Response {
+    Response {
+        result_type: method,
+        error: Some(NIP47Error {
+            code,
+            message: message.to_string(),
+        }),
+        result: None,
+    }
+}
+
+/// Whether paying `amount` would take `connection` over its budget,
+/// counting what its app has already spent in the current budget window.
+pub fn would_exceed_budget(
+    db: &Database,
+    connection: &NwcConnection,
+    amount: Amount,
+) -> anyhow::Result<bool> {
+    let Some(budget) = connection.budget_or else {
+        return Ok(false);
+    };
+
+    let spent = db.get_nwc_connection_spent(
+        &connection.client_public_key(),
+        budget.period.window_start_or(Utc::now().naive_utc()),
+    )?;
+
+    Ok(exceeds_budget(Some(budget), spent, amount))
+}
+
+/// Whether paying `amount` would take a connection over its budget,
+/// given that its app has already spent `spent` in the current budget window.
+pub fn exceeds_budget(budget_or: Option<NwcBudget>, spent: Amount, amount: Amount) -> bool {
+    budget_or.is_some_and(|budget| spent.msats.saturating_add(amount.msats) > budget.amount.msats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exceeds_budget() {
+        let budget = NwcBudget {
+            amount: Amount::from_sats(1_000),
+            period: BudgetPeriod::Daily,
+        };
+
+        assert!(!exceeds_budget(
+            None,
+            Amount::from_sats(5_000),
+            Amount::from_sats(5_000)
+        ));
+        assert!(!exceeds_budget(
+            Some(budget),
+            Amount::from_sats(400),
+            Amount::from_sats(600)
+        ));
+        assert!(exceeds_budget(
+            Some(budget),
+            Amount::from_sats(400),
+            Amount::from_sats(601)
+        ));
+    }
+
+    #[test]
+    fn test_nwc_method_round_trip() {
+        for method in NwcMethod::ALL {
+            assert_eq!(NwcMethod::from_str(method.as_str()).unwrap(), method);
+        }
+
+        for period in BudgetPeriod::ALL {
+            assert_eq!(BudgetPeriod::from_str(period.as_str()).unwrap(), period);
+        }
+    }
+}
@@ -0,0 +1,719 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Display,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use chrono::NaiveDateTime;
+use nostr_sdk::{nips::nip46::Request, FromBech32, Kind, PublicKey};
+
+use crate::encryption;
+
+/// Apps must have signed at least this many events before a kind they haven't signed
+/// is treated as unusual. Until then, most kinds would be new and warnings would be noise.
+pub const MIN_SIGNED_EVENTS_FOR_UNUSUAL_KINDS: u64 = 5;
+
+/// How long a temporary approval grant lasts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalGrantDuration {
+    TenMinutes,
+    /// Until Keystache is closed.
+    Session,
+}
+
+impl ApprovalGrantDuration {
+    const fn as_duration_or(self) -> Option<Duration> {
+        match self {
+            Self::TenMinutes => Some(Duration::from_secs(10 * 60)),
+            Self::Session => None,
+        }
+    }
+}
+
+/// Temporary grants that automatically approve NIP-46 requests for a public key,
+/// so that batches of requests don't each need to be approved by hand.
+/// Grants are kept in memory only, and are lost when Keystache is closed.
+#[derive(Debug, Clone, Default)]
+pub struct ApprovalGrants {
+    // Expiry time of each grant, or `None` if it lasts for the rest of the session.
+    expiry_time_or_by_public_key: HashMap<PublicKey, Option<Instant>>,
+}
+
+impl ApprovalGrants {
+    /// Grants automatic approval for `public_key`, replacing any existing grant.
+    pub fn grant(&mut self, public_key: PublicKey, duration: ApprovalGrantDuration, now: Instant) {
+        self.expiry_time_or_by_public_key.insert(
+            public_key,
+            duration.as_duration_or().map(|duration| now + duration),
+        );
+    }
+
+    /// Whether requests for `public_key` should be approved without prompting.
+    /// Expired grants are removed.
+    pub fn is_granted(&mut self, public_key: &PublicKey, now: Instant) -> bool {
+        self.expiry_time_or_by_public_key
+            .retain(|_, expiry_time_or| {
+                !expiry_time_or.is_some_and(|expiry_time| expiry_time <= now)
+            });
+
+        self.expiry_time_or_by_public_key.contains_key(public_key)
+    }
+
+    /// Lists the grants that haven't expired, along with when each expires,
+    /// or `None` if it lasts for the rest of the session.
+    pub fn list(&self, now: Instant) -> Vec<(PublicKey, Option<Instant>)> {
+        self.expiry_time_or_by_public_key
+            .iter()
+            .filter(|(_, expiry_time_or)| {
+                !expiry_time_or.is_some_and(|expiry_time| expiry_time <= now)
+            })
+            .map(|(public_key, expiry_time_or)| (*public_key, *expiry_time_or))
+            .collect()
+    }
+
+    /// Removes the grant for `public_key`, so that its next request prompts again.
+    pub fn revoke(&mut self, public_key: &PublicKey) {
+        self.expiry_time_or_by_public_key.remove(public_key);
+    }
+}
+
+/// A standing answer to an app's requests to sign events of one kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KindPolicyDecision {
+    AlwaysApprove,
+    AlwaysReject,
+}
+
+impl KindPolicyDecision {
+    pub const ALL: [Self; 2] = [Self::AlwaysApprove, Self::AlwaysReject];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::AlwaysApprove => "always_approve",
+            Self::AlwaysReject => "always_reject",
+        }
+    }
+}
+
+impl FromStr for KindPolicyDecision {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "always_approve" => Ok(Self::AlwaysApprove),
+            "always_reject" => Ok(Self::AlwaysReject),
+            _ => Err(anyhow::anyhow!("Unknown kind policy decision: {s}")),
+        }
+    }
+}
+
+impl Display for KindPolicyDecision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AlwaysApprove => write!(f, "Always approve"),
+            Self::AlwaysReject => write!(f, "Always reject"),
+        }
+    }
+}
+
+/// A kind policy that the user has saved for an app. Unlike approval grants,
+/// kind policies are kept in the database until the user revokes them.
+/// See [`apply_kind_policies`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nip46KindPolicy {
+    pub public_key: PublicKey,
+    pub kind: Kind,
+    pub decision: KindPolicyDecision,
+    pub create_time: NaiveDateTime,
+}
+
+impl TryFrom<(String, i32, String, NaiveDateTime)> for Nip46KindPolicy {
+    type Error = anyhow::Error;
+
+    fn try_from(
+        (npub, kind, decision, create_time): (String, i32, String, NaiveDateTime),
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            public_key: PublicKey::from_bech32(&npub)?,
+            kind: Kind::from(u16::try_from(kind)?),
+            decision: decision.parse()?,
+            create_time,
+        })
+    }
+}
+
+/// An app that has signed in over NIP-46. Apps are registered
+/// the first time the user approves one of their requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nip46App {
+    pub public_key: PublicKey,
+    /// Whether `get_public_key` requests from the app are approved without prompting,
+    /// as long as that's also allowed globally.
+    pub auto_approve_public_key_reads: bool,
+    pub create_time: NaiveDateTime,
+    /// The saved keypair that the app signs as, if the user has chosen one. Requests
+    /// for any other keypair are never approved without prompting, and can't be
+    /// approved until the user switches the app to that keypair.
+    pub identity_or: Option<PublicKey>,
+    /// Whether NIP-04 and NIP-44 encryption requests with a peer that the user has already
+    /// approved this session are approved without prompting. Encryption requests don't say
+    /// which keypair they're for, so this only applies once the app has an `identity_or`.
+    pub remember_conversations: bool,
+}
+
+impl TryFrom<(String, bool, NaiveDateTime, Option<String>, bool)> for Nip46App {
+    type Error = anyhow::Error;
+
+    fn try_from(
+        (
+            npub,
+            auto_approve_public_key_reads,
+            create_time,
+            identity_npub_or,
+            remember_conversations,
+        ): (String, bool, NaiveDateTime, Option<String>, bool),
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            public_key: PublicKey::from_bech32(&npub)?,
+            auto_approve_public_key_reads,
+            create_time,
+            identity_or: identity_npub_or
+                .map(|identity_npub| PublicKey::from_bech32(&identity_npub))
+                .transpose()?,
+            remember_conversations,
+        })
+    }
+}
+
+/// Why a batch of NIP-46 requests wasn't signed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Nip46RejectionReason {
+    RejectedByUser,
+    /// The app stopped waiting before the requests were answered,
+    /// usually because they timed out or the app disconnected.
+    NotAnswered,
+    /// Nobody answered the requests before they expired, so they were rejected automatically.
+    Expired,
+    /// The requests signed a kind of event that the user always rejects for the app.
+    RejectedByPolicy,
+}
+
+impl Nip46RejectionReason {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::RejectedByUser => "rejected_by_user",
+            Self::NotAnswered => "not_answered",
+            Self::Expired => "expired",
+            Self::RejectedByPolicy => "rejected_by_policy",
+        }
+    }
+}
+
+impl FromStr for Nip46RejectionReason {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rejected_by_user" => Ok(Self::RejectedByUser),
+            "not_answered" => Ok(Self::NotAnswered),
+            "expired" => Ok(Self::Expired),
+            "rejected_by_policy" => Ok(Self::RejectedByPolicy),
+            _ => Err(anyhow::anyhow!("Unknown NIP-46 rejection reason: {s}")),
+        }
+    }
+}
+
+impl Display for Nip46RejectionReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RejectedByUser => write!(f, "Rejected by you"),
+            Self::NotAnswered => write!(f, "The app stopped waiting for an answer"),
+            Self::Expired => write!(f, "Expired before you answered"),
+            Self::RejectedByPolicy => write!(f, "Rejected by a rule you set for this app"),
+        }
+    }
+}
+
+/// A batch of NIP-46 requests that wasn't signed. These are kept
+/// so that an action that failed in an app can be traced back to why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nip46Rejection {
+    pub public_key: PublicKey,
+    pub reason: Nip46RejectionReason,
+    /// What the requests asked for. See [`describe_requests`].
+    pub requests_description: String,
+    pub create_time: NaiveDateTime,
+}
+
+impl TryFrom<(String, String, String, NaiveDateTime)> for Nip46Rejection {
+    type Error = anyhow::Error;
+
+    fn try_from(
+        (npub, reason, requests_description, create_time): (String, String, String, NaiveDateTime),
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            public_key: PublicKey::from_bech32(&npub)?,
+            reason: reason.parse()?,
+            requests_description,
+            create_time,
+        })
+    }
+}
+
+/// Whether a batch of NIP-46 requests only reads the user's public key.
+/// These requests can't sign anything, so they're safe to approve without prompting.
+pub fn is_public_key_read(requests: &[Request]) -> bool {
+    !requests.is_empty()
+        && requests
+            .iter()
+            .all(|request| matches!(request, Request::GetPublicKey))
+}
+
+/// The keypairs that a batch of NIP-46 requests asks to sign as. Only requests to sign
+/// events say which keypair they're for, so other requests aren't counted.
+pub fn requested_identities(requests: &[Request]) -> BTreeSet<PublicKey> {
+    requests
+        .iter()
+        .filter_map(|request| match request {
+            Request::SignEvent(unsigned_event) => Some(unsigned_event.pubkey),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether a batch of NIP-46 requests asks to sign as a different keypair than
+/// `identity_or`, the one chosen for its app. See [`Nip46App::identity_or`].
+pub fn is_other_identity(requests: &[Request], identity_or: Option<PublicKey>) -> bool {
+    identity_or.is_some_and(|identity| {
+        requested_identities(requests)
+            .iter()
+            .any(|requested_identity| *requested_identity != identity)
+    })
+}
+
+/// The peers that a batch of NIP-46 requests encrypts messages to or decrypts them from.
+/// Returns `None` if the batch asks for anything other than NIP-04 or NIP-44 encryption.
+pub fn encryption_peers(requests: &[Request]) -> Option<BTreeSet<PublicKey>> {
+    if requests.is_empty() {
+        return None;
+    }
+
+    requests.iter().map(encryption::encryption_peer).collect()
+}
+
+/// How an app's kind policies answer a batch of NIP-46 requests, or `None` if the user has
+/// to be asked. A single event of an always-rejected kind rejects the whole batch. The batch
+/// is only approved if every request signs an event of an always-approved kind, so that other
+/// requests, such as decrypting messages, still need the user's approval.
+pub fn apply_kind_policies(
+    requests: &[Request],
+    decisions_by_kind: &BTreeMap<Kind, KindPolicyDecision>,
+) -> Option<KindPolicyDecision> {
+    let kinds = signed_event_kinds(requests);
+
+    if kinds
+        .iter()
+        .any(|kind| decisions_by_kind.get(kind) == Some(&KindPolicyDecision::AlwaysReject))
+    {
+        return Some(KindPolicyDecision::AlwaysReject);
+    }
+
+    (!requests.is_empty()
+        && kinds.len() == requests.len()
+        && kinds
+            .iter()
+            .all(|kind| decisions_by_kind.get(kind) == Some(&KindPolicyDecision::AlwaysApprove)))
+    .then_some(KindPolicyDecision::AlwaysApprove)
+}
+
+/// The kinds of the events that a batch of NIP-46 requests asks to sign, in order.
+/// A kind is repeated for each event of that kind.
+pub fn signed_event_kinds(requests: &[Request]) -> Vec<Kind> {
+    requests
+        .iter()
+        .filter_map(|request| match request {
+            Request::SignEvent(unsigned_event) => Some(unsigned_event.kind),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The recipients of the zap requests (kind 9734) in a batch of NIP-46 requests, in order.
+/// Returns `None` if the batch asks for anything other than signing zap requests,
+/// or if a zap request doesn't name exactly one recipient.
+pub fn zap_request_recipients(requests: &[Request]) -> Option<Vec<PublicKey>> {
+    if requests.is_empty() {
+        return None;
+    }
+
+    requests
+        .iter()
+        .map(|request| match request {
+            Request::SignEvent(unsigned_event) if unsigned_event.kind == Kind::ZapRequest => {
+                let mut recipients =
+                    unsigned_event
+                        .tags
+                        .iter()
+                        .filter_map(|tag| match tag.as_slice() {
+                            [name, public_key, ..] if name == "p" => Some(public_key),
+                            _ => None,
+                        });
+
+                match (recipients.next(), recipients.next()) {
+                    (Some(public_key), None) => PublicKey::from_hex(public_key).ok(),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// The kinds of events in a batch of NIP-46 requests that the app has never signed before,
+/// given how many events of each kind it has signed. Nothing is unusual for apps that have
+/// signed fewer than [`MIN_SIGNED_EVENTS_FOR_UNUSUAL_KINDS`] events.
+pub fn unusual_event_kinds(
+    requests: &[Request],
+    sign_counts_by_kind: &BTreeMap<Kind, u64>,
+) -> Vec<Kind> {
+    if sign_counts_by_kind.values().sum::<u64>() < MIN_SIGNED_EVENTS_FOR_UNUSUAL_KINDS {
+        return Vec::new();
+    }
+
+    let mut seen_kinds = BTreeSet::new();
+
+    signed_event_kinds(requests)
+        .into_iter()
+        .filter(|kind| !sign_counts_by_kind.contains_key(kind) && seen_kinds.insert(*kind))
+        .collect()
+}
+
+/// A short description of what a batch of NIP-46 requests asks for, such as
+/// "sign kind 1 (note) x3, get_public_key". Repeated requests are counted rather than listed.
+pub fn describe_requests(requests: &[Request]) -> String {
+    let mut descriptions: Vec<(String, usize)> = Vec::new();
+
+    for request in requests {
+        let description = match request {
+            Request::SignEvent(unsigned_event) => {
+                format!("sign {}", describe_event_kind(unsigned_event.kind))
+            }
+            _ => request.method().to_string(),
+        };
+
+        match descriptions
+            .iter_mut()
+            .find(|(existing_description, _)| *existing_description == description)
+        {
+            Some((_, count)) => *count += 1,
+            None => descriptions.push((description, 1)),
+        }
+    }
+
+    descriptions
+        .into_iter()
+        .map(|(description, count)| {
+            if count > 1 {
+                format!("{description} x{count}")
+            } else {
+                description
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A short description of an event kind, such as "kind 0 (profile)".
+pub fn describe_event_kind(kind: Kind) -> String {
+    let kind_number = kind.as_u16();
+
+    let name_or = match kind_number {
+        0 => Some("profile"),
+        1 => Some("note"),
+        3 => Some("follow list"),
+        4 => Some("encrypted direct message"),
+        5 => Some("deletion"),
+        6 => Some("repost"),
+        7 => Some("reaction"),
+        1063 => Some("file metadata"),
+        9734 => Some("zap request"),
+        10002 => Some("relay list"),
+        22242 => Some("relay authentication"),
+        24242 => Some("Blossom authorization"),
+        30023 => Some("long-form article"),
+        _ => None,
+    };
+
+    match name_or {
+        Some(name) => format!("kind {kind_number} ({name})"),
+        None => format!("kind {kind_number}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr_sdk::{EventBuilder, Keys, Tag};
+
+    use super::*;
+
+    #[test]
+    fn test_approval_grants() {
+        let public_key = Keys::generate().public_key();
+        let other_public_key = Keys::generate().public_key();
+        let now = Instant::now();
+
+        let mut grants = ApprovalGrants::default();
+        assert!(!grants.is_granted(&public_key, now));
+
+        grants.grant(public_key, ApprovalGrantDuration::TenMinutes, now);
+        assert!(grants.is_granted(&public_key, now + Duration::from_secs(9 * 60)));
+        assert!(!grants.is_granted(&other_public_key, now));
+        assert!(!grants.is_granted(&public_key, now + Duration::from_secs(10 * 60)));
+
+        // Session grants never expire.
+        grants.grant(public_key, ApprovalGrantDuration::Session, now);
+        assert!(grants.is_granted(&public_key, now + Duration::from_secs(60 * 60 * 24)));
+        assert_eq!(grants.list(now), vec![(public_key, None)]);
+
+        grants.revoke(&public_key);
+        assert!(!grants.is_granted(&public_key, now));
+        assert!(grants.list(now).is_empty());
+    }
+
+    #[test]
+    fn test_apply_kind_policies() {
+        let public_key = Keys::generate().public_key();
+
+        let sign_event = |kind: Kind| {
+            Request::SignEvent(EventBuilder::new(kind, "", []).to_unsigned_event(public_key))
+        };
+
+        let decisions_by_kind = BTreeMap::from([
+            (Kind::TextNote, KindPolicyDecision::AlwaysApprove),
+            (Kind::Reaction, KindPolicyDecision::AlwaysApprove),
+            (
+                Kind::EncryptedDirectMessage,
+                KindPolicyDecision::AlwaysReject,
+            ),
+        ]);
+
+        assert_eq!(
+            apply_kind_policies(
+                &[sign_event(Kind::TextNote), sign_event(Kind::Reaction)],
+                &decisions_by_kind
+            ),
+            Some(KindPolicyDecision::AlwaysApprove)
+        );
+
+        // Rejecting wins over approving.
+        assert_eq!(
+            apply_kind_policies(
+                &[
+                    sign_event(Kind::TextNote),
+                    sign_event(Kind::EncryptedDirectMessage)
+                ],
+                &decisions_by_kind
+            ),
+            Some(KindPolicyDecision::AlwaysReject)
+        );
+
+        // Kinds without a policy, and requests that don't sign events, still prompt.
+        assert_eq!(
+            apply_kind_policies(
+                &[sign_event(Kind::TextNote), sign_event(Kind::Metadata)],
+                &decisions_by_kind
+            ),
+            None
+        );
+        assert_eq!(
+            apply_kind_policies(
+                &[sign_event(Kind::TextNote), Request::GetPublicKey],
+                &decisions_by_kind
+            ),
+            None
+        );
+        assert_eq!(apply_kind_policies(&[], &decisions_by_kind), None);
+    }
+
+    #[test]
+    fn test_is_public_key_read() {
+        assert!(is_public_key_read(&[Request::GetPublicKey]));
+        assert!(is_public_key_read(&[
+            Request::GetPublicKey,
+            Request::GetPublicKey
+        ]));
+        assert!(!is_public_key_read(&[]));
+        assert!(!is_public_key_read(&[
+            Request::GetPublicKey,
+            Request::GetRelays
+        ]));
+    }
+
+    #[test]
+    fn test_is_other_identity() {
+        let alice = Keys::generate().public_key();
+        let bob = Keys::generate().public_key();
+
+        let sign_event = |public_key: PublicKey| {
+            Request::SignEvent(
+                EventBuilder::new(Kind::TextNote, "", []).to_unsigned_event(public_key),
+            )
+        };
+
+        // Apps without a chosen identity can sign as any keypair.
+        assert!(!is_other_identity(
+            &[sign_event(alice), sign_event(bob)],
+            None
+        ));
+
+        assert!(!is_other_identity(
+            &[sign_event(alice), Request::GetPublicKey],
+            Some(alice)
+        ));
+        assert!(is_other_identity(&[sign_event(bob)], Some(alice)));
+        assert!(is_other_identity(
+            &[sign_event(alice), sign_event(bob)],
+            Some(alice)
+        ));
+        assert!(!is_other_identity(&[Request::GetPublicKey], Some(alice)));
+    }
+
+    #[test]
+    fn test_encryption_peers() {
+        let alice = Keys::generate().public_key();
+        let bob = Keys::generate().public_key();
+
+        assert_eq!(
+            encryption_peers(&[
+                Request::Nip44Decrypt {
+                    public_key: alice,
+                    ciphertext: String::new(),
+                },
+                Request::Nip04Encrypt {
+                    public_key: bob,
+                    text: String::new(),
+                },
+                Request::Nip44Encrypt {
+                    public_key: alice,
+                    text: String::new(),
+                },
+            ]),
+            Some(BTreeSet::from([alice, bob]))
+        );
+        assert_eq!(
+            encryption_peers(&[
+                Request::Nip44Decrypt {
+                    public_key: alice,
+                    ciphertext: String::new(),
+                },
+                Request::GetPublicKey,
+            ]),
+            None
+        );
+        assert_eq!(encryption_peers(&[]), None);
+    }
+
+    #[test]
+    fn test_zap_request_recipients() {
+        let keys = Keys::generate();
+        let alice = Keys::generate().public_key();
+        let bob = Keys::generate().public_key();
+
+        let sign_event = |kind: Kind, recipients: &[PublicKey]| {
+            Request::SignEvent(
+                EventBuilder::new(kind, "", recipients.iter().copied().map(Tag::public_key))
+                    .to_unsigned_event(keys.public_key()),
+            )
+        };
+
+        assert_eq!(
+            zap_request_recipients(&[
+                sign_event(Kind::ZapRequest, &[alice]),
+                sign_event(Kind::ZapRequest, &[bob]),
+            ]),
+            Some(vec![alice, bob])
+        );
+        assert_eq!(zap_request_recipients(&[]), None);
+        assert_eq!(
+            zap_request_recipients(&[
+                sign_event(Kind::ZapRequest, &[alice]),
+                sign_event(Kind::TextNote, &[alice]),
+            ]),
+            None
+        );
+        assert_eq!(
+            zap_request_recipients(&[sign_event(Kind::ZapRequest, &[alice, bob])]),
+            None
+        );
+        assert_eq!(
+            zap_request_recipients(&[sign_event(Kind::ZapRequest, &[])]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_unusual_event_kinds() {
+        let keys = Keys::generate();
+        let sign_event = |kind: Kind| {
+            Request::SignEvent(EventBuilder::new(kind, "", []).to_unsigned_event(keys.public_key()))
+        };
+
+        let requests = [
+            Request::GetPublicKey,
+            sign_event(Kind::TextNote),
+            sign_event(Kind::Metadata),
+            sign_event(Kind::Metadata),
+        ];
+
+        assert_eq!(
+            signed_event_kinds(&requests),
+            vec![Kind::TextNote, Kind::Metadata, Kind::Metadata]
+        );
+
+        // Kinds that the app has signed before aren't unusual, and each unusual kind is listed once.
+        let sign_counts_by_kind = BTreeMap::from([(Kind::TextNote, 20)]);
+        assert_eq!(
+            unusual_event_kinds(&requests, &sign_counts_by_kind),
+            vec![Kind::Metadata]
+        );
+
+        // Apps without much history don't have unusual kinds yet.
+        let sign_counts_by_kind = BTreeMap::from([(Kind::TextNote, 2)]);
+        assert!(unusual_event_kinds(&requests, &sign_counts_by_kind).is_empty());
+    }
+
+    #[test]
+    fn test_describe_requests() {
+        let keys = Keys::generate();
+        let sign_event = |kind: Kind| {
+            Request::SignEvent(EventBuilder::new(kind, "", []).to_unsigned_event(keys.public_key()))
+        };
+
+        assert_eq!(
+            describe_requests(&[
+                sign_event(Kind::TextNote),
+                Request::GetPublicKey,
+                sign_event(Kind::TextNote),
+                sign_event(Kind::Metadata),
+            ]),
+            "sign kind 1 (note) x2, get_public_key, sign kind 0 (profile)"
+        );
+        assert_eq!(describe_requests(&[]), "");
+    }
+
+    #[test]
+    fn test_nip46_rejection_reason_round_trip() {
+        for reason in [
+            Nip46RejectionReason::RejectedByUser,
+            Nip46RejectionReason::NotAnswered,
+            Nip46RejectionReason::Expired,
+            Nip46RejectionReason::RejectedByPolicy,
+        ] {
+            assert_eq!(
+                reason.as_str().parse::<Nip46RejectionReason>().unwrap(),
+                reason
+            );
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::{fmt::Display, str::FromStr};
+
+use lightning_invoice::{Bolt11Invoice, Bolt11InvoiceDescription};
+use nostr_sdk::bitcoin::hashes::{sha256, Hash};
+
+/// Controls how much invoice metadata is included when
+/// payment history leaves the app, such as in CSV exports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InvoicePrivacy {
+    /// Invoices and their descriptions are included unchanged.
+    Full,
+    /// Descriptions are replaced by their SHA-256 hash, so matching descriptions
+    /// can still be grouped together. Invoices are replaced by their payment hash.
+    #[default]
+    Hashed,
+    /// Descriptions are removed. Invoices are replaced by their payment hash.
+    Redacted,
+}
+
+impl InvoicePrivacy {
+    pub const ALL: [Self; 3] = [Self::Full, Self::Hashed, Self::Redacted];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Full => "full",
+            Self::Hashed => "hashed",
+            Self::Redacted => "redacted",
+        }
+    }
+
+    /// Scrubs private metadata from an invoice according to this privacy level.
+    pub fn scrub(self, invoice: &Bolt11Invoice) -> ScrubbedInvoice {
+        // Only a hash of the description is included in invoices that
+        // use description hashes, so there's nothing private to scrub.
+        let description = match invoice.description() {
+            Bolt11InvoiceDescription::Direct(description) => description.clone().into_inner(),
+            Bolt11InvoiceDescription::Hash(_) => String::new(),
+        };
+
+        let (description, bolt11_invoice) = match self {
+            Self::Full => (description, invoice.to_string()),
+            Self::Hashed if description.is_empty() => (description, String::new()),
+            Self::Hashed => (
+                sha256::Hash::hash(description.as_bytes()).to_string(),
+                String::new(),
+            ),
+            Self::Redacted => (String::new(), String::new()),
+        };
+
+        ScrubbedInvoice {
+            payment_hash: invoice.payment_hash().to_string(),
+            description,
+            bolt11_invoice,
+        }
+    }
+
+    /// Describes what `scrub` removes, so that exports can document what was redacted.
+    /// Returns `None` if nothing is removed.
+    pub const fn redaction_note(self) -> Option<&'static str> {
+        match self {
+            Self::Full => None,
+            Self::Hashed => Some(
+                "Invoice descriptions were replaced by their SHA-256 hash and invoices were removed. Payment hashes are included for reconciliation.",
+            ),
+            Self::Redacted => Some(
+                "Invoice descriptions and invoices were removed. Payment hashes are included for reconciliation.",
+            ),
+        }
+    }
+}
+
+impl Display for InvoicePrivacy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Full => write!(f, "Include invoices and descriptions"),
+            Self::Hashed => write!(f, "Hash descriptions"),
+            Self::Redacted => write!(f, "Remove descriptions"),
+        }
+    }
+}
+
+impl FromStr for InvoicePrivacy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(Self::Full),
+            "hashed" => Ok(Self::Hashed),
+            "redacted" => Ok(Self::Redacted),
+            _ => Err(anyhow::anyhow!("Unknown invoice privacy level: {s}")),
+        }
+    }
+}
+
+/// Invoice metadata that is safe to export at a given [`InvoicePrivacy`] level.
+/// Fields that were scrubbed are empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScrubbedInvoice {
+    pub payment_hash: String,
+    pub description: String,
+    pub bolt11_invoice: String,
+}
@@ -0,0 +1,246 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use nostr_sdk::{EventBuilder, Filter, JsonUtil, Kind, Metadata, PublicKey, UnsignedEvent, Url};
+
+use crate::nostr::NostrModule;
+
+/// How long relays are given to return a profile.
+const PROFILE_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Profiles (kind-0 metadata) of the user's keys, loaded from relays.
+/// Kept in memory only, so they're loaded again after Keystache restarts.
+#[derive(Debug, Clone, Default)]
+pub struct Profiles {
+    metadata_by_public_key: HashMap<PublicKey, Metadata>,
+    // Public keys whose profile has already been requested, whether or not one was found.
+    requested_public_keys: HashSet<PublicKey>,
+}
+
+impl Profiles {
+    /// Marks `public_keys` as requested, and returns the ones that weren't requested before.
+    pub fn take_unrequested(
+        &mut self,
+        public_keys: impl IntoIterator<Item = PublicKey>,
+    ) -> Vec<PublicKey> {
+        public_keys
+            .into_iter()
+            .filter(|public_key| self.requested_public_keys.insert(*public_key))
+            .collect()
+    }
+
+    pub fn insert(&mut self, public_key: PublicKey, metadata: Metadata) {
+        self.requested_public_keys.insert(public_key);
+        self.metadata_by_public_key.insert(public_key, metadata);
+    }
+
+    pub fn get(&self, public_key: &PublicKey) -> Option<&Metadata> {
+        self.metadata_by_public_key.get(public_key)
+    }
+
+    /// The name that `public_key` goes by, preferring its display name.
+    pub fn name_or(&self, public_key: &PublicKey) -> Option<&str> {
+        let metadata = self.get(public_key)?;
+
+        [&metadata.display_name, &metadata.name]
+            .into_iter()
+            .filter_map(|name_or| name_or.as_deref().map(str::trim))
+            .find(|name| !name.is_empty())
+    }
+}
+
+/// A profile field that can be edited in Keystache.
+/// Any other fields are kept as they are when a profile is edited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileField {
+    Name,
+    DisplayName,
+    About,
+    Picture,
+    Banner,
+    Website,
+    Nip05,
+    Lud16,
+}
+
+impl ProfileField {
+    pub const ALL: [Self; 8] = [
+        Self::Name,
+        Self::DisplayName,
+        Self::About,
+        Self::Picture,
+        Self::Banner,
+        Self::Website,
+        Self::Nip05,
+        Self::Lud16,
+    ];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Name => "Name",
+            Self::DisplayName => "Display Name",
+            Self::About => "About",
+            Self::Picture => "Picture URL",
+            Self::Banner => "Banner URL",
+            Self::Website => "Website",
+            Self::Nip05 => "NIP-05 Address",
+            Self::Lud16 => "Lightning Address",
+        }
+    }
+
+    const fn is_url(self) -> bool {
+        matches!(self, Self::Picture | Self::Banner | Self::Website)
+    }
+
+    pub fn get(self, metadata: &Metadata) -> Option<&str> {
+        match self {
+            Self::Name => metadata.name.as_deref(),
+            Self::DisplayName => metadata.display_name.as_deref(),
+            Self::About => metadata.about.as_deref(),
+            Self::Picture => metadata.picture.as_deref(),
+            Self::Banner => metadata.banner.as_deref(),
+            Self::Website => metadata.website.as_deref(),
+            Self::Nip05 => metadata.nip05.as_deref(),
+            Self::Lud16 => metadata.lud16.as_deref(),
+        }
+    }
+
+    pub fn set(self, metadata: &mut Metadata, value: String) {
+        *self.field_mut(metadata) = Some(value);
+    }
+
+    fn field_mut(self, metadata: &mut Metadata) -> &mut Option<String> {
+        match self {
+            Self::Name => &mut metadata.name,
+            Self::DisplayName => &mut metadata.display_name,
+            Self::About => &mut metadata.about,
+            Self::Picture => &mut metadata.picture,
+            Self::Banner => &mut metadata.banner,
+            Self::Website => &mut metadata.website,
+            Self::Nip05 => &mut metadata.nip05,
+            Self::Lud16 => &mut metadata.lud16,
+        }
+    }
+}
+
+/// Fetches the newest profile of `public_key` from relays.
+/// Returns `None` if no relay has a profile signed by the key.
+pub async fn fetch_profile(
+    nostr_module: &NostrModule,
+    public_key: PublicKey,
+) -> anyhow::Result<Option<Metadata>> {
+    let events = nostr_module
+        .fetch_events(
+            vec![Filter::new()
+                .author(public_key)
+                .kind(Kind::Metadata)
+                .limit(1)],
+            PROFILE_FETCH_TIMEOUT,
+        )
+        .await?;
+
+    // Relays could make up a profile for any key, so only signed profiles are used.
+    let Some(event) = events
+        .into_iter()
+        .filter(|event| event.pubkey == public_key && event.verify().is_ok())
+        .max_by_key(|event| event.created_at)
+    else {
+        return Ok(None);
+    };
+
+    Ok(Some(Metadata::from_json(&event.content)?))
+}
+
+/// Builds a profile for `public_key` to sign from edited `metadata`. Edited fields are
+/// trimmed, and left out if they're empty. The profile replaces the key's previous one,
+/// so `metadata` should start from the newest profile, including fields that aren't edited.
+pub fn build_profile(
+    public_key: PublicKey,
+    mut metadata: Metadata,
+) -> anyhow::Result<UnsignedEvent> {
+    for field in ProfileField::ALL {
+        let value_or = field_value_or(field, &metadata);
+
+        if field.is_url() {
+            if let Some(value) = &value_or {
+                if let Err(err) = Url::parse(value) {
+                    anyhow::bail!("{} isn't a valid URL: {err}", field.label());
+                }
+            }
+        }
+
+        *field.field_mut(&mut metadata) = value_or;
+    }
+
+    Ok(EventBuilder::metadata(&metadata).to_unsigned_event(public_key))
+}
+
+fn field_value_or(field: ProfileField, metadata: &Metadata) -> Option<String> {
+    field
+        .get(metadata)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(ToString::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr_sdk::Keys;
+
+    use super::*;
+
+    #[test]
+    fn test_profiles() {
+        let alice = Keys::generate().public_key();
+        let bob = Keys::generate().public_key();
+
+        let mut profiles = Profiles::default();
+
+        assert_eq!(profiles.take_unrequested([alice, bob]), vec![alice, bob]);
+        assert!(profiles.take_unrequested([alice]).is_empty());
+
+        profiles.insert(alice, Metadata::new().name("alice").display_name(" "));
+        assert_eq!(profiles.name_or(&alice), Some("alice"));
+
+        profiles.insert(alice, Metadata::new().name("alice").display_name("Alice"));
+        assert_eq!(profiles.name_or(&alice), Some("Alice"));
+
+        assert_eq!(profiles.name_or(&bob), None);
+    }
+
+    #[test]
+    fn test_build_profile() {
+        let keys = Keys::generate();
+
+        let mut metadata = Metadata::new()
+            .name("alice")
+            .lud06("lnurl1dp68gurn8ghj7")
+            .custom_field("bot", false);
+        ProfileField::About.set(&mut metadata, "  Hello  ".to_string());
+        ProfileField::DisplayName.set(&mut metadata, " ".to_string());
+
+        let profile = build_profile(keys.public_key(), metadata)
+            .unwrap()
+            .sign(&keys)
+            .unwrap();
+
+        assert_eq!(profile.kind, Kind::Metadata);
+
+        // Fields that can't be edited in Keystache are kept.
+        assert_eq!(
+            Metadata::from_json(&profile.content).unwrap(),
+            Metadata::new()
+                .name("alice")
+                .about("Hello")
+                .lud06("lnurl1dp68gurn8ghj7")
+                .custom_field("bot", false)
+        );
+
+        let mut metadata = Metadata::new();
+        ProfileField::Picture.set(&mut metadata, "not a url".to_string());
+
+        assert!(build_profile(keys.public_key(), metadata).is_err());
+    }
+}
@@ -0,0 +1,132 @@
+use anyhow::bail;
+use chrono::{NaiveDateTime, SecondsFormat};
+use lightning_invoice::Bolt11Invoice;
+use nostr_sdk::{
+    bitcoin::hashes::{hex::FromHex, sha256, Hash},
+    secp256k1::{Keypair, Message, SECP256K1},
+    PublicKey, ToBech32,
+};
+use serde_json::json;
+
+/// Proof that a lightning invoice was paid. Only the payer can know the preimage
+/// whose hash matches the invoice's payment hash, so anyone holding the invoice
+/// can check the receipt without trusting Keystache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentReceipt {
+    invoice: Bolt11Invoice,
+    preimage: String,
+    paid_time: NaiveDateTime,
+}
+
+impl PaymentReceipt {
+    /// Creates a receipt for a paid invoice.
+    /// Fails if `preimage` doesn't match the invoice's payment hash.
+    pub fn new(
+        invoice: Bolt11Invoice,
+        preimage: String,
+        paid_time: NaiveDateTime,
+    ) -> anyhow::Result<Self> {
+        if !is_preimage_for_payment_hash(&preimage, &invoice.payment_hash().to_string())? {
+            bail!("Preimage doesn't match the invoice's payment hash");
+        }
+
+        Ok(Self {
+            invoice,
+            preimage,
+            paid_time,
+        })
+    }
+
+    /// Formats the receipt as JSON. If `signer_or` is set, the receipt is also
+    /// signed with that key. See [`sign_receipt`] for what the signature covers.
+    pub fn to_json(&self, signer_or: Option<&Keypair>) -> anyhow::Result<String> {
+        let mut receipt = json!({
+            "bolt11_invoice": self.invoice.to_string(),
+            "payment_hash": self.invoice.payment_hash().to_string(),
+            "preimage": self.preimage,
+            "paid_at": self
+                .paid_time
+                .and_utc()
+                .to_rfc3339_opts(SecondsFormat::Secs, true),
+        });
+
+        if let Some(signer) = signer_or {
+            sign_receipt(&mut receipt, signer)?;
+        }
+
+        Ok(serde_json::to_string_pretty(&receipt)?)
+    }
+}
+
+/// Whether the hex-encoded `preimage` hashes to the hex-encoded `payment_hash`.
+fn is_preimage_for_payment_hash(preimage: &str, payment_hash: &str) -> anyhow::Result<bool> {
+    let preimage_bytes = Vec::<u8>::from_hex(preimage)?;
+
+    Ok(sha256::Hash::hash(&preimage_bytes).to_string() == payment_hash)
+}
+
+/// Adds a `signature` field to `receipt`, holding the signer's npub and a Schnorr
+/// signature of the SHA-256 hash of the receipt's compact JSON with keys sorted.
+fn sign_receipt(receipt: &mut serde_json::Value, signer: &Keypair) -> anyhow::Result<()> {
+    let public_key: PublicKey = signer.x_only_public_key().0.into();
+    let signature = SECP256K1.sign_schnorr_no_aux_rand(&to_signed_message(receipt), signer);
+
+    receipt["signature"] = json!({
+        "npub": public_key.to_bech32()?,
+        "sig": signature.to_string(),
+    });
+
+    Ok(())
+}
+
+fn to_signed_message(unsigned_receipt: &serde_json::Value) -> Message {
+    Message::from_digest(
+        sha256::Hash::hash(unsigned_receipt.to_string().as_bytes()).to_byte_array(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use nostr_sdk::secp256k1::{rand::thread_rng, schnorr::Signature};
+
+    use super::*;
+
+    #[test]
+    fn test_is_preimage_for_payment_hash() {
+        let payment_hash = sha256::Hash::hash(&[7; 32]).to_string();
+
+        assert!(is_preimage_for_payment_hash(&"07".repeat(32), &payment_hash).unwrap());
+        assert!(!is_preimage_for_payment_hash(&"00".repeat(32), &payment_hash).unwrap());
+        assert!(is_preimage_for_payment_hash("not hex", &payment_hash).is_err());
+    }
+
+    #[test]
+    fn test_sign_receipt() {
+        let unsigned_receipt = json!({
+            "payment_hash": "abc",
+            "preimage": "def",
+        });
+        let signer = Keypair::new_global(&mut thread_rng());
+
+        let mut signed_receipt = unsigned_receipt.clone();
+        sign_receipt(&mut signed_receipt, &signer).unwrap();
+
+        // The signature covers every other field.
+        let signature = signed_receipt
+            .as_object_mut()
+            .unwrap()
+            .remove("signature")
+            .unwrap();
+        assert_eq!(signed_receipt, unsigned_receipt);
+
+        assert!(SECP256K1
+            .verify_schnorr(
+                &Signature::from_str(signature["sig"].as_str().unwrap()).unwrap(),
+                &to_signed_message(&unsigned_receipt),
+                &signer.x_only_public_key().0,
+            )
+            .is_ok());
+    }
+}
@@ -0,0 +1,219 @@
+use std::time::Duration;
+
+use nostr_sdk::{EventBuilder, Filter, Kind, PublicKey, Tag, UnsignedEvent};
+
+use crate::nostr::{is_same_relay, parse_relay_url, NostrModule};
+
+/// How long relays are given to return a relay list.
+const RELAY_LIST_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A relay from a NIP-65 relay list (kind 10002).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayListEntry {
+    pub websocket_url: String,
+    /// Whether the user reads events about them from the relay.
+    pub read: bool,
+    /// Whether the user publishes their events to the relay.
+    pub write: bool,
+}
+
+/// Reads the relays out of the tags of a relay list, in the order they're listed. Relays
+/// without a marker are used for both reading and writing. Relays listed more than once are merged.
+pub fn parse_relay_list(tags: &[Tag]) -> Vec<RelayListEntry> {
+    let mut entries: Vec<RelayListEntry> = Vec::new();
+
+    for tag in tags {
+        let (websocket_url, read, write) = match tag.as_slice() {
+            [name, websocket_url] if name == "r" => (websocket_url, true, true),
+            [name, websocket_url, marker, ..] if name == "r" => match marker.as_str() {
+                "read" => (websocket_url, true, false),
+                "write" => (websocket_url, false, true),
+                _ => (websocket_url, true, true),
+            },
+            _ => continue,
+        };
+
+        let Ok(url) = parse_relay_url(websocket_url) else {
+            continue;
+        };
+
+        if let Some(entry) = entries.iter_mut().find(|entry| {
+            parse_relay_url(&entry.websocket_url)
+                .is_ok_and(|existing| is_same_relay(&existing, &url))
+        }) {
+            entry.read |= read;
+            entry.write |= write;
+            continue;
+        }
+
+        entries.push(RelayListEntry {
+            websocket_url: url.to_string(),
+            read,
+            write,
+        });
+    }
+
+    entries
+}
+
+/// Points `entries` at the relays in `saved_websocket_urls` that they're the same as,
+/// so that importing a relay list updates relays that were saved under a slightly
+/// different URL rather than adding them again.
+pub fn match_saved_relays(
+    entries: Vec<RelayListEntry>,
+    saved_websocket_urls: &[String],
+) -> Vec<RelayListEntry> {
+    entries
+        .into_iter()
+        .map(|entry| {
+            let Ok(url) = parse_relay_url(&entry.websocket_url) else {
+                return entry;
+            };
+
+            let saved_websocket_url_or = saved_websocket_urls.iter().find(|saved| {
+                parse_relay_url(saved).is_ok_and(|saved| is_same_relay(&saved, &url))
+            });
+
+            RelayListEntry {
+                websocket_url: saved_websocket_url_or
+                    .cloned()
+                    .unwrap_or(entry.websocket_url),
+                ..entry
+            }
+        })
+        .collect()
+}
+
+/// Builds a relay list for `public_key` to sign, listing each relay with the marker that
+/// matches what it's used for. Relays that are used for neither reading nor writing are left out.
+pub fn build_relay_list(
+    public_key: PublicKey,
+    entries: &[RelayListEntry],
+) -> anyhow::Result<UnsignedEvent> {
+    let tags = entries
+        .iter()
+        .filter_map(|entry| {
+            let websocket_url = entry.websocket_url.as_str();
+
+            match (entry.read, entry.write) {
+                (true, true) => Some(Tag::parse(&["r", websocket_url])),
+                (true, false) => Some(Tag::parse(&["r", websocket_url, "read"])),
+                (false, true) => Some(Tag::parse(&["r", websocket_url, "write"])),
+                (false, false) => None,
+            }
+        })
+        .collect::<Result<Vec<Tag>, _>>()?;
+
+    Ok(EventBuilder::new(Kind::RelayList, "", tags).to_unsigned_event(public_key))
+}
+
+/// Fetches the newest relay list of `public_key` from relays.
+pub async fn fetch_relay_list(
+    nostr_module: &NostrModule,
+    public_key: PublicKey,
+) -> anyhow::Result<Vec<RelayListEntry>> {
+    let events = nostr_module
+        .fetch_events(
+            vec![Filter::new()
+                .author(public_key)
+                .kind(Kind::RelayList)
+                .limit(1)],
+            RELAY_LIST_FETCH_TIMEOUT,
+        )
+        .await?;
+
+    // Relays could make up a relay list for any key, so only signed lists are used.
+    let event = events
+        .into_iter()
+        .filter(|event| event.pubkey == public_key && event.verify().is_ok())
+        .max_by_key(|event| event.created_at)
+        .ok_or_else(|| anyhow::anyhow!("No relay list found"))?;
+
+    Ok(parse_relay_list(&event.tags))
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr_sdk::{EventBuilder, Keys, Tag};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_relay_list() {
+        let relay_list = EventBuilder::new(
+            Kind::RelayList,
+            "",
+            [
+                Tag::parse(&["r", "wss://both.example.com"]).unwrap(),
+                Tag::parse(&["r", "wss://read.example.com", "read"]).unwrap(),
+                Tag::parse(&["r", "wss://write.example.com", "write"]).unwrap(),
+                Tag::parse(&["r", "wss://write.example.com/", "read"]).unwrap(),
+                Tag::parse(&["r", "https://not-a-relay.example.com"]).unwrap(),
+                Tag::parse(&["p", "wss://other.example.com"]).unwrap(),
+            ],
+        )
+        .to_event(&Keys::generate())
+        .unwrap();
+
+        let entries = parse_relay_list(&relay_list.tags);
+
+        assert_eq!(
+            entries,
+            vec![
+                RelayListEntry {
+                    websocket_url: "wss://both.example.com/".to_string(),
+                    read: true,
+                    write: true,
+                },
+                RelayListEntry {
+                    websocket_url: "wss://read.example.com/".to_string(),
+                    read: true,
+                    write: false,
+                },
+                RelayListEntry {
+                    websocket_url: "wss://write.example.com/".to_string(),
+                    read: true,
+                    write: true,
+                },
+            ]
+        );
+
+        let matched_entries = match_saved_relays(entries, &["wss://Read.Example.com".to_string()]);
+
+        assert_eq!(matched_entries[1].websocket_url, "wss://Read.Example.com");
+        assert_eq!(matched_entries[0].websocket_url, "wss://both.example.com/");
+    }
+
+    #[test]
+    fn test_build_relay_list() {
+        let keys = Keys::generate();
+
+        let entries = vec![
+            RelayListEntry {
+                websocket_url: "wss://both.example.com/".to_string(),
+                read: true,
+                write: true,
+            },
+            RelayListEntry {
+                websocket_url: "wss://write.example.com/".to_string(),
+                read: false,
+                write: true,
+            },
+            RelayListEntry {
+                websocket_url: "wss://unused.example.com/".to_string(),
+                read: false,
+                write: false,
+            },
+        ];
+
+        let relay_list = build_relay_list(keys.public_key(), &entries)
+            .unwrap()
+            .sign(&keys)
+            .unwrap();
+
+        assert_eq!(relay_list.kind, Kind::RelayList);
+
+        // Published relay lists read back the same, apart from unused relays.
+        assert_eq!(parse_relay_list(&relay_list.tags), entries[..2]);
+    }
+}
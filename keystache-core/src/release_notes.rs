@@ -0,0 +1,61 @@
+use crate::db::Database;
+
+/// The highlights of a release, as shown on the "What's New" page after updating to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReleaseNotes {
+    pub version: &'static str,
+    pub highlights: &'static [&'static str],
+}
+
+/// The notes of every release, newest first. Add an entry here when bumping the version.
+pub const RELEASE_NOTES: &[ReleaseNotes] = &[ReleaseNotes {
+    version: "0.1.0-beta-dev",
+    highlights: &[
+        "Apps can use your wallet through Nostr Wallet Connect, with a budget for each connection.",
+        "Lightning payments prefer gateways with lower fees. Each federation shows the fees you've paid over time.",
+        "Long invoices are shown as animated QR codes, and animated codes can be scanned on the send page.",
+        "Connected apps can be backed up to your relays and restored on another device.",
+        "NIP-46 requests can be approved by holding Enter.",
+        "Low data mode saves data on metered connections.",
+    ],
+}];
+
+/// The notes of `current_version` and every release before it, newest first. Notes of
+/// newer releases are left out, in case Keystache has been downgraded since they were run.
+pub fn release_notes_up_to(current_version: &str) -> &'static [ReleaseNotes] {
+    let start = RELEASE_NOTES
+        .iter()
+        .position(|release_notes| release_notes.version == current_version)
+        .unwrap_or(0);
+
+    &RELEASE_NOTES[start..]
+}
+
+/// Saves `current_version` as the last version of Keystache that was run, and returns
+/// whether a different version was run before it, meaning that Keystache has been
+/// updated since. Returns `false` on the first run, since there's nothing new to
+/// someone who has just installed Keystache.
+pub fn record_app_version(db: &Database, current_version: &str) -> anyhow::Result<bool> {
+    let last_run_version_or = db.get_last_run_version()?;
+
+    if last_run_version_or.as_deref() == Some(current_version) {
+        return Ok(false);
+    }
+
+    db.save_last_run_version(current_version)?;
+
+    Ok(last_run_version_or.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_notes_up_to() {
+        assert_eq!(release_notes_up_to(RELEASE_NOTES[0].version), RELEASE_NOTES);
+
+        // Unreleased versions show every release's notes.
+        assert_eq!(release_notes_up_to("0.0.0-unreleased"), RELEASE_NOTES);
+    }
+}
@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use futures::Stream;
+use nostr_sdk::{nips::nip46::Request, PublicKey};
+use tokio::sync::{mpsc, watch};
+
+use crate::{
+    db::{self, Database},
+    policy,
+};
+
+/// How many answered requests can wait to be recorded. Once the queue
+/// is full, submitting waits until the worker has made room.
+const SIGNING_QUEUE_CAPACITY: usize = 1000;
+
+/// How far along recording the current batch of signed events is. The events are
+/// signed by the NIP-55 server as soon as their requests are approved, so this only
+/// counts the signing history being saved afterwards.
+/// A batch lasts until every event in it has been recorded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SigningProgress {
+    pub recorded_event_count: u64,
+    pub total_event_count: u64,
+}
+
+impl SigningProgress {
+    pub const fn is_in_progress(&self) -> bool {
+        self.recorded_event_count < self.total_event_count
+    }
+}
+
+struct SigningJob {
+    public_key: PublicKey,
+    requests: Vec<Request>,
+    register_app: bool,
+}
+
+impl SigningJob {
+    fn event_count(&self) -> u64 {
+        policy::signed_event_kinds(&self.requests).len() as u64
+    }
+
+    fn record(&self, db: &Database) -> anyhow::Result<()> {
+        if self.register_app {
+            db.register_nip46_app(&self.public_key)?;
+        }
+
+        db.record_nip46_app_signed_event_kinds(
+            &self.public_key,
+            &policy::signed_event_kinds(&self.requests),
+        )
+    }
+}
+
+/// Does the bookkeeping for approved NIP-46 requests on a background task, so that large
+/// batches of requests (such as an app re-signing a follow list) don't stall the caller.
+#[derive(Clone)]
+pub struct SigningWorker {
+    job_sender: mpsc::Sender<SigningJob>,
+    progress_sender: Arc<watch::Sender<SigningProgress>>,
+}
+
+impl SigningWorker {
+    pub fn new(db: Arc<Database>) -> Self {
+        let (job_sender, mut job_receiver) = mpsc::channel::<SigningJob>(SIGNING_QUEUE_CAPACITY);
+        let progress_sender = Arc::new(watch::channel(SigningProgress::default()).0);
+
+        let progress_sender_clone = progress_sender.clone();
+        tokio::spawn(async move {
+            while let Some(job) = job_receiver.recv().await {
+                let event_count = job.event_count();
+
+                let db = db.clone();
+                let runtime = tokio::runtime::Handle::current();
+                // The database is blocking, so it's kept off the async worker threads.
+                // That includes waiting to retry while the database is busy.
+                // TODO: Log a warning if the job fails to be recorded or panics.
+                let _ = tokio::task::spawn_blocking(move || {
+                    runtime.block_on(db::retry_while_busy(|| job.record(&db)))
+                })
+                .await;
+
+                progress_sender_clone.send_modify(|progress| {
+                    progress.recorded_event_count += event_count;
+                });
+            }
+        });
+
+        Self {
+            job_sender,
+            progress_sender,
+        }
+    }
+
+    /// Records that `public_key` was sent signatures for `requests`. Also registers
+    /// the app if `register_app` is set, such as when the user approved it by hand.
+    /// Waits while the queue is full, rather than recording on the caller's thread.
+    pub async fn submit(&self, public_key: PublicKey, requests: Vec<Request>, register_app: bool) {
+        let job = SigningJob {
+            public_key,
+            requests,
+            register_app,
+        };
+        let event_count = job.event_count();
+
+        self.progress_sender.send_modify(|progress| {
+            // A new batch starts once the previous one has finished.
+            if !progress.is_in_progress() {
+                *progress = SigningProgress::default();
+            }
+            progress.total_event_count += event_count;
+        });
+
+        // The worker only stops once every sender is dropped, so this doesn't fail in practice.
+        // TODO: Log a warning if the job fails to be queued.
+        if self.job_sender.send(job).await.is_err() {
+            self.progress_sender.send_modify(|progress| {
+                progress.total_event_count -= event_count;
+            });
+        }
+    }
+
+    pub fn progress(&self) -> SigningProgress {
+        *self.progress_sender.borrow()
+    }
+
+    /// Yields the progress of the current batch whenever it changes.
+    pub fn progress_stream(&self) -> impl Stream<Item = SigningProgress> {
+        let mut progress_receiver = self.progress_sender.subscribe();
+
+        async_stream::stream! {
+            while progress_receiver.changed().await.is_ok() {
+                let progress = *progress_receiver.borrow_and_update();
+                yield progress;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signing_progress() {
+        assert!(!SigningProgress::default().is_in_progress());
+        assert!(SigningProgress {
+            recorded_event_count: 120,
+            total_event_count: 500,
+        }
+        .is_in_progress());
+        assert!(!SigningProgress {
+            recorded_event_count: 500,
+            total_event_count: 500,
+        }
+        .is_in_progress());
+    }
+}
@@ -0,0 +1,339 @@
+use std::time::Duration;
+
+use fedimint_core::Amount;
+use futures::StreamExt;
+use lightning_invoice::{Bolt11Invoice, Bolt11InvoiceDescription};
+use nostr_sdk::{
+    nips::nip04, EventBuilder, Filter, Keys, Kind, PublicKey, SecretKey, Tag, Timestamp, ToBech32,
+};
+use secp256k1::rand::{thread_rng, Rng};
+use serde_json::json;
+
+use crate::{
+    fedimint::exceeds_payment_cap,
+    nostr::{NostrModule, SubscriptionPurpose},
+    util::format_amount,
+};
+
+/// Bumped whenever the format of saved policies changes, so that older
+/// versions of Keystache don't misread newer policies.
+const SPEND_APPROVAL_POLICY_VERSION: u64 = 1;
+
+/// How long the approver is given to answer before the payment is abandoned.
+const SPEND_APPROVAL_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// A two-person rule for the wallet: payments over `threshold` are only made once `approver`
+/// has approved them by replying to an encrypted direct message (NIP-04), so that one device
+/// alone can't spend large amounts. Any Nostr client that can send direct messages can answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpendApprovalPolicy {
+    /// Who approves payments over the threshold.
+    pub approver: PublicKey,
+    /// The largest payment made without approval. Invoices without an amount always need it.
+    pub threshold: Amount,
+    // The key that requests are sent from and replies are read with. It's kept for as long as
+    // the policy, so that the approver can recognize requests and answers them in one thread.
+    sender_secret_key: SecretKey,
+}
+
+impl SpendApprovalPolicy {
+    /// Creates a policy with a new key to send requests from.
+    pub fn new(approver: PublicKey, threshold: Amount) -> Self {
+        Self {
+            approver,
+            threshold,
+            sender_secret_key: Keys::generate().secret_key().clone(),
+        }
+    }
+
+    /// Changes who approves payments and the threshold, keeping the key that requests are sent from.
+    pub fn with_approver_and_threshold(self, approver: PublicKey, threshold: Amount) -> Self {
+        Self {
+            approver,
+            threshold,
+            ..self
+        }
+    }
+
+    /// The key that approval requests come from, which the approver should expect.
+    pub fn sender_public_key(&self) -> PublicKey {
+        self.sender_keys().public_key()
+    }
+
+    fn sender_keys(&self) -> Keys {
+        Keys::new(self.sender_secret_key.clone())
+    }
+
+    /// Whether paying `invoice` needs the approver's approval.
+    pub fn requires_approval(&self, invoice: &Bolt11Invoice) -> bool {
+        exceeds_payment_cap(invoice.amount_milli_satoshis(), self.threshold)
+    }
+
+    /// Serializes the policy, sender key included, for the database.
+    pub fn to_json(&self) -> String {
+        json!({
+            "version": SPEND_APPROVAL_POLICY_VERSION,
+            "approver": self.approver.to_hex(),
+            "threshold_msats": self.threshold.msats,
+            "sender_secret_key": self.sender_secret_key.to_secret_hex(),
+        })
+        .to_string()
+    }
+
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+
+        let malformed = || anyhow::anyhow!("Malformed spend approval policy");
+
+        if value["version"].as_u64() != Some(SPEND_APPROVAL_POLICY_VERSION) {
+            anyhow::bail!("Unsupported spend approval policy version");
+        }
+
+        Ok(Self {
+            approver: PublicKey::from_hex(value["approver"].as_str().ok_or_else(malformed)?)?,
+            threshold: Amount::from_msats(value["threshold_msats"].as_u64().ok_or_else(malformed)?),
+            sender_secret_key: SecretKey::from_hex(
+                value["sender_secret_key"].as_str().ok_or_else(malformed)?,
+            )?,
+        })
+    }
+}
+
+/// Asks the approver of `policy` to approve changing the policy to `new_policy_or`, if the
+/// change would let more through without them: turning the policy off, handing it to another
+/// approver, or raising the threshold. Otherwise, the policy can be changed right away.
+pub async fn request_policy_change_approval_if_required(
+    nostr_module: &NostrModule,
+    policy: &SpendApprovalPolicy,
+    new_policy_or: Option<&SpendApprovalPolicy>,
+) -> anyhow::Result<()> {
+    match policy_change_summary(policy, new_policy_or)? {
+        Some(summary) => request_approval(nostr_module, policy, &summary).await,
+        None => Ok(()),
+    }
+}
+
+/// Sends the approver a direct message with `summary` of what needs approving, then waits for
+/// them to reply with the code it contains. The reply has to be signed by the approver, so
+/// nobody else can approve it. Returns once the approver approves, and fails if they deny it
+/// or don't answer within [`SPEND_APPROVAL_TIMEOUT`].
+pub async fn request_approval(
+    nostr_module: &NostrModule,
+    policy: &SpendApprovalPolicy,
+    summary: &str,
+) -> anyhow::Result<()> {
+    let sender_keys = policy.sender_keys();
+
+    // The code ties the reply to this payment, so that
+    // an old reply can't approve a different one.
+    let code = format!("{:06}", thread_rng().gen_range(0..1_000_000));
+
+    // Relays keep the replies, so a reply that arrives before the
+    // subscription has reached a relay is still picked up.
+    let filter = Filter::new()
+        .kind(Kind::EncryptedDirectMessage)
+        .author(policy.approver)
+        .pubkey(sender_keys.public_key())
+        .since(Timestamp::now());
+
+    let mut replies =
+        Box::pin(nostr_module.subscribe(SubscriptionPurpose::SpendApprovals, vec![filter]));
+
+    let content = nip04::encrypt(
+        sender_keys.secret_key(),
+        &policy.approver,
+        request_message(summary, &code),
+    )?;
+
+    let expiration = Timestamp::from(
+        Timestamp::now()
+            .as_u64()
+            .saturating_add(SPEND_APPROVAL_TIMEOUT.as_secs()),
+    );
+
+    let event = EventBuilder::new(
+        Kind::EncryptedDirectMessage,
+        content,
+        [
+            Tag::public_key(policy.approver),
+            Tag::expiration(expiration),
+        ],
+    )
+    .to_event(&sender_keys)?;
+
+    nostr_module.publish(event).await?;
+
+    let wait_for_reply = async {
+        while let Some(event) = replies.next().await {
+            if event.pubkey != policy.approver {
+                continue;
+            }
+
+            let Ok(reply) = nip04::decrypt(sender_keys.secret_key(), &event.pubkey, &event.content)
+            else {
+                continue;
+            };
+
+            match parse_reply(&reply, &code) {
+                Some(true) => return Ok(()),
+                Some(false) => anyhow::bail!("The approver denied it"),
+                None => {}
+            }
+        }
+
+        anyhow::bail!("Lost the connection to relays")
+    };
+
+    tokio::time::timeout(SPEND_APPROVAL_TIMEOUT, wait_for_reply)
+        .await
+        .map_err(|_| anyhow::anyhow!("The approver didn't answer in time"))?
+}
+
+/// Describes paying `invoice` to the approver.
+pub fn invoice_summary(invoice: &Bolt11Invoice) -> String {
+    let amount = invoice.amount_milli_satoshis().map_or_else(
+        || "an invoice without an amount".to_string(),
+        |amount_msats| format_amount(Amount::from_msats(amount_msats)),
+    );
+
+    let description = match invoice.description() {
+        Bolt11InvoiceDescription::Direct(description) => description.clone().into_inner(),
+        Bolt11InvoiceDescription::Hash(_) => String::new(),
+    };
+
+    let purpose = if description.is_empty() {
+        String::new()
+    } else {
+        format!(" for \"{description}\"")
+    };
+
+    format!(
+        "Keystache wants to pay {amount}{purpose}.\n\nPayment hash: {}",
+        invoice.payment_hash()
+    )
+}
+
+/// Describes spending `amount` of e-cash as notes to the approver.
+pub fn ecash_summary(amount: Amount) -> String {
+    format!(
+        "Keystache wants to send {} of e-cash.",
+        format_amount(amount)
+    )
+}
+
+/// Describes changing `policy` to `new_policy_or` to its approver,
+/// or `None` if the change doesn't need their approval.
+fn policy_change_summary(
+    policy: &SpendApprovalPolicy,
+    new_policy_or: Option<&SpendApprovalPolicy>,
+) -> anyhow::Result<Option<String>> {
+    Ok(match new_policy_or {
+        None => Some("Keystache wants to stop asking you to approve payments.".to_string()),
+        Some(new_policy) if new_policy.approver != policy.approver => Some(format!(
+            "Keystache wants {} to approve payments over {} instead of you.",
+            new_policy.approver.to_bech32()?,
+            format_amount(new_policy.threshold)
+        )),
+        Some(new_policy) if new_policy.threshold > policy.threshold => Some(format!(
+            "Keystache wants to only ask you about payments over {} instead of {}.",
+            format_amount(new_policy.threshold),
+            format_amount(policy.threshold)
+        )),
+        Some(_) => None,
+    })
+}
+
+/// The direct message that asks the approver about what `summary` describes.
+fn request_message(summary: &str, code: &str) -> String {
+    format!(
+        "{summary}\n\nReply \"approve {code}\" to allow it, or \"deny {code}\" to stop it. Nothing happens if you don't answer within {} minutes.",
+        SPEND_APPROVAL_TIMEOUT.as_secs() / 60
+    )
+}
+
+/// Reads a reply to a request with `code`: `Some(true)` to approve,
+/// `Some(false)` to deny, or `None` if it isn't an answer to the request.
+fn parse_reply(reply: &str, code: &str) -> Option<bool> {
+    let mut words = reply.split_whitespace();
+
+    let is_approved = match words.next()?.to_lowercase().as_str() {
+        "approve" | "yes" => true,
+        "deny" | "no" => false,
+        _ => return None,
+    };
+
+    (words.next()? == code && words.next().is_none()).then_some(is_approved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reply() {
+        assert_eq!(parse_reply("approve 012345", "012345"), Some(true));
+        assert_eq!(parse_reply("  Approve 012345\n", "012345"), Some(true));
+        assert_eq!(parse_reply("yes 012345", "012345"), Some(true));
+        assert_eq!(parse_reply("deny 012345", "012345"), Some(false));
+        assert_eq!(parse_reply("NO 012345", "012345"), Some(false));
+
+        // Replies to other requests, or without a code, aren't answers.
+        assert_eq!(parse_reply("approve 543210", "012345"), None);
+        assert_eq!(parse_reply("approve", "012345"), None);
+        assert_eq!(parse_reply("approve 012345 and 543210", "012345"), None);
+        assert_eq!(parse_reply("sure 012345", "012345"), None);
+        assert_eq!(parse_reply("", "012345"), None);
+    }
+
+    #[test]
+    fn test_policy_json_round_trip() {
+        let policy =
+            SpendApprovalPolicy::new(Keys::generate().public_key(), Amount::from_sats(50_000));
+
+        assert_eq!(
+            SpendApprovalPolicy::from_json(&policy.to_json()).unwrap(),
+            policy
+        );
+        assert!(SpendApprovalPolicy::from_json("{\"version\":2}").is_err());
+    }
+
+    #[test]
+    fn test_policy_change_summary() {
+        let approver = Keys::generate().public_key();
+        let policy = SpendApprovalPolicy::new(approver, Amount::from_sats(50_000));
+
+        // Changes that let more through without the approver need their approval.
+        assert!(policy_change_summary(&policy, None).unwrap().is_some());
+        assert!(policy_change_summary(
+            &policy,
+            Some(&policy.clone().with_approver_and_threshold(
+                Keys::generate().public_key(),
+                Amount::from_sats(50_000)
+            ))
+        )
+        .unwrap()
+        .is_some());
+        assert!(policy_change_summary(
+            &policy,
+            Some(
+                &policy
+                    .clone()
+                    .with_approver_and_threshold(approver, Amount::from_sats(50_001))
+            )
+        )
+        .unwrap()
+        .is_some());
+
+        // Lowering the threshold only asks the approver about more payments.
+        assert!(policy_change_summary(
+            &policy,
+            Some(
+                &policy
+                    .clone()
+                    .with_approver_and_threshold(approver, Amount::from_sats(10_000))
+            )
+        )
+        .unwrap()
+        .is_none());
+    }
+}
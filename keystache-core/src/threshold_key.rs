@@ -0,0 +1,922 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use frost_secp256k1_tr as frost;
+use futures::{Stream, StreamExt};
+use nostr_sdk::{
+    nips::nip44, secp256k1::schnorr::Signature, Event, EventBuilder, EventId, Filter, JsonUtil,
+    Keys, Kind, PublicKey, SecretKey, Tag, Timestamp, UnsignedEvent,
+};
+use secp256k1::rand::{thread_rng, Rng};
+use serde_json::{json, Value};
+
+use crate::nostr::{NostrModule, SubscriptionPurpose};
+
+/// Event kind that cosigning messages are sent as. It isn't registered in any NIP, since the
+/// protocol is an experiment that only Keystache speaks. It's a regular kind rather than an
+/// ephemeral one, so that answers sent before a subscription reaches a relay aren't lost.
+const COSIGNING_KIND: Kind = Kind::Custom(8_242);
+
+/// Bumped whenever the format of shares changes, so that older
+/// versions of Keystache don't misread newer shares.
+const THRESHOLD_SHARE_VERSION: u64 = 1;
+
+/// How long cosigners are given to answer each round of signing.
+/// The first round includes the time it takes cosigners' users to approve.
+const COSIGNING_ROUND_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How far back cosigners look for requests when they start listening,
+/// so that a request sent just before they came online is still answered.
+const COSIGNING_REQUEST_LOOKBACK: Duration = Duration::from_secs(60);
+
+/// How long relays are asked to keep cosigning messages for. See NIP-40.
+const COSIGNING_MESSAGE_LIFETIME: Duration = Duration::from_secs(60 * 60);
+
+/// One share of a threshold key: a Nostr identity whose secret key has been split with FROST,
+/// so that signing for it takes several devices. The coordinator's share starts signing with
+/// [`sign_event`], and cosigners' shares answer it through [`Cosigner`].
+#[derive(Debug, Clone)]
+pub struct ThresholdShare {
+    /// The identity's public key, which signatures made by enough shares verify against.
+    pub public_key: PublicKey,
+    pub is_coordinator: bool,
+    key_package: frost::keys::KeyPackage,
+    public_key_package: frost::keys::PublicKeyPackage,
+    // The key that this share's holder sends and receives cosigning messages with.
+    // It's unrelated to the identity, so relays can't link the messages to it.
+    transport_secret_key: SecretKey,
+    // Where the other holders are reached, by share identifier. The
+    // coordinator knows every cosigner, while cosigners only know the coordinator.
+    peers: BTreeMap<frost::Identifier, PublicKey>,
+}
+
+impl ThresholdShare {
+    /// How many shares it takes to sign, including this one.
+    pub fn threshold(&self) -> u16 {
+        *self.key_package.min_signers()
+    }
+
+    /// How many shares the secret key was split into.
+    pub fn share_count(&self) -> usize {
+        self.public_key_package.verifying_shares().len()
+    }
+
+    fn identifier(&self) -> frost::Identifier {
+        *self.key_package.identifier()
+    }
+
+    fn transport_keys(&self) -> Keys {
+        Keys::new(self.transport_secret_key.clone())
+    }
+
+    /// The identity's public key, as given by the shares themselves.
+    fn group_public_key(&self) -> anyhow::Result<PublicKey> {
+        let verifying_key = self.public_key_package.verifying_key().serialize()?;
+
+        // The last 32 bytes are the x coordinate, whether or not the point is compressed.
+        Ok(PublicKey::from_slice(
+            &verifying_key[verifying_key.len().saturating_sub(32)..],
+        )?)
+    }
+
+    /// Makes the nonces and commitments for the first round of signing. The nonces must only be
+    /// used for a single signature, since signing twice with the same nonces leaks the share.
+    fn commit(
+        &self,
+    ) -> (
+        frost::round1::SigningNonces,
+        frost::round1::SigningCommitments,
+    ) {
+        frost::round1::commit(self.key_package.signing_share(), &mut thread_rng())
+    }
+
+    /// Makes this share's part of the signature for `event_id`. Refuses to sign
+    /// anything else, whatever the signing package that the coordinator sent says.
+    fn sign(
+        &self,
+        signing_package: &frost::SigningPackage,
+        nonces: &frost::round1::SigningNonces,
+        event_id: EventId,
+    ) -> anyhow::Result<frost::round2::SignatureShare> {
+        if signing_package.message() != event_id.as_bytes() {
+            anyhow::bail!("Asked to sign a different event than the one that was approved");
+        }
+
+        Ok(frost::round2::sign(
+            signing_package,
+            nonces,
+            &self.key_package,
+        )?)
+    }
+
+    /// Serializes the share, secrets included, for the database or for handing to a cosigner.
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        let peers = self
+            .peers
+            .iter()
+            .map(|(identifier, public_key)| {
+                Ok(json!({
+                    "identifier": serde_json::to_value(identifier)?,
+                    "public_key": public_key.to_hex(),
+                }))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(json!({
+            "version": THRESHOLD_SHARE_VERSION,
+            "public_key": self.public_key.to_hex(),
+            "is_coordinator": self.is_coordinator,
+            "key_package": serde_json::to_value(&self.key_package)?,
+            "public_key_package": serde_json::to_value(&self.public_key_package)?,
+            "transport_secret_key": self.transport_secret_key.to_secret_hex(),
+            "peers": peers,
+        })
+        .to_string())
+    }
+
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        let value: Value = serde_json::from_str(json.trim())?;
+
+        match value.get("version").and_then(Value::as_u64) {
+            Some(THRESHOLD_SHARE_VERSION) => {}
+            Some(version) if version > THRESHOLD_SHARE_VERSION => {
+                anyhow::bail!("The share was made by a newer version of Keystache");
+            }
+            _ => anyhow::bail!("The share is malformed"),
+        }
+
+        let malformed = || anyhow::anyhow!("The share is malformed");
+        let read_str = |value: &Value, key: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(malformed)
+        };
+        let read_value = |value: &Value, key: &str| value.get(key).cloned().ok_or_else(malformed);
+
+        let share = Self {
+            public_key: PublicKey::from_hex(read_str(&value, "public_key")?)?,
+            is_coordinator: value
+                .get("is_coordinator")
+                .and_then(Value::as_bool)
+                .ok_or_else(malformed)?,
+            key_package: serde_json::from_value(read_value(&value, "key_package")?)?,
+            public_key_package: serde_json::from_value(read_value(&value, "public_key_package")?)?,
+            transport_secret_key: SecretKey::from_hex(read_str(&value, "transport_secret_key")?)?,
+            peers: value
+                .get("peers")
+                .and_then(Value::as_array)
+                .ok_or_else(malformed)?
+                .iter()
+                .map(|peer| {
+                    Ok((
+                        serde_json::from_value(read_value(peer, "identifier")?)?,
+                        PublicKey::from_hex(read_str(peer, "public_key")?)?,
+                    ))
+                })
+                .collect::<anyhow::Result<_>>()?,
+        };
+
+        if share.group_public_key()? != share.public_key {
+            anyhow::bail!("The share doesn't belong to the key it names");
+        }
+
+        Ok(share)
+    }
+}
+
+/// Splits `secret_key` into shares, any `threshold` of which can sign for its identity. The first
+/// share is for this device, which coordinates signing, and there's one more for each of
+/// `cosigner_count` cosigners. Cosigner shares must be handed to each cosigner privately, using
+/// [`ThresholdShare::to_json`]. The identity is only protected once the secret key itself has
+/// been deleted, since it can still sign on its own.
+pub fn split_secret_key(
+    secret_key: &SecretKey,
+    threshold: u16,
+    cosigner_count: u16,
+) -> anyhow::Result<(ThresholdShare, Vec<ThresholdShare>)> {
+    let share_count = cosigner_count
+        .checked_add(1)
+        .ok_or_else(|| anyhow::anyhow!("Too many cosigners"))?;
+
+    if threshold < 2 {
+        anyhow::bail!("The threshold must be at least 2, or any single share could sign");
+    }
+
+    if threshold > share_count {
+        anyhow::bail!("The threshold can't be more than the number of shares");
+    }
+
+    let signing_key = frost::SigningKey::deserialize(&secret_key.secret_bytes())?;
+
+    let (secret_shares, public_key_package) = frost::keys::split(
+        &signing_key,
+        share_count,
+        threshold,
+        frost::keys::IdentifierList::Default,
+        &mut thread_rng(),
+    )?;
+
+    let public_key = Keys::new(secret_key.clone()).public_key();
+
+    let mut shares = secret_shares
+        .into_values()
+        .map(|secret_share| {
+            Ok(ThresholdShare {
+                public_key,
+                is_coordinator: false,
+                key_package: frost::keys::KeyPackage::try_from(secret_share)?,
+                public_key_package: public_key_package.clone(),
+                transport_secret_key: Keys::generate().secret_key().clone(),
+                peers: BTreeMap::new(),
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut coordinator_share = shares.remove(0);
+    coordinator_share.is_coordinator = true;
+
+    for cosigner_share in &mut shares {
+        coordinator_share.peers.insert(
+            cosigner_share.identifier(),
+            cosigner_share.transport_keys().public_key(),
+        );
+        cosigner_share.peers.insert(
+            coordinator_share.identifier(),
+            coordinator_share.transport_keys().public_key(),
+        );
+    }
+
+    Ok((coordinator_share, shares))
+}
+
+/// A message between the coordinator and a cosigner of a threshold key, sent NIP-44 encrypted
+/// as a [`COSIGNING_KIND`] event. Signing takes two rounds: cosigners first commit to signing
+/// an event, then sign it once the coordinator has collected enough commitments.
+#[derive(Debug, Clone)]
+enum CosigningMessage {
+    /// Asks a cosigner to commit to signing `event`, which its user should approve first.
+    CommitRequest {
+        session_id: String,
+        event: UnsignedEvent,
+    },
+    Commitments {
+        session_id: String,
+        commitments: frost::round1::SigningCommitments,
+    },
+    /// The cosigner's user didn't approve signing.
+    Refusal { session_id: String },
+    SignRequest {
+        session_id: String,
+        signing_package: frost::SigningPackage,
+    },
+    SignatureShare {
+        session_id: String,
+        signature_share: frost::round2::SignatureShare,
+    },
+}
+
+impl CosigningMessage {
+    fn to_json(&self) -> anyhow::Result<String> {
+        let value = match self {
+            Self::CommitRequest { session_id, event } => json!({
+                "type": "commit_request",
+                "session_id": session_id,
+                "event": event.as_json(),
+            }),
+            Self::Commitments {
+                session_id,
+                commitments,
+            } => json!({
+                "type": "commitments",
+                "session_id": session_id,
+                "commitments": serde_json::to_value(commitments)?,
+            }),
+            Self::Refusal { session_id } => json!({
+                "type": "refusal",
+                "session_id": session_id,
+            }),
+            Self::SignRequest {
+                session_id,
+                signing_package,
+            } => json!({
+                "type": "sign_request",
+                "session_id": session_id,
+                "signing_package": serde_json::to_value(signing_package)?,
+            }),
+            Self::SignatureShare {
+                session_id,
+                signature_share,
+            } => json!({
+                "type": "signature_share",
+                "session_id": session_id,
+                "signature_share": serde_json::to_value(signature_share)?,
+            }),
+        };
+
+        Ok(value.to_string())
+    }
+
+    fn from_json(json: &str) -> anyhow::Result<Self> {
+        let value: Value = serde_json::from_str(json)?;
+
+        let malformed = || anyhow::anyhow!("The cosigning message is malformed");
+        let read_value = |key: &str| value.get(key).cloned().ok_or_else(malformed);
+
+        let session_id = value
+            .get("session_id")
+            .and_then(Value::as_str)
+            .ok_or_else(malformed)?
+            .to_string();
+
+        Ok(match value.get("type").and_then(Value::as_str) {
+            Some("commit_request") => Self::CommitRequest {
+                session_id,
+                event: UnsignedEvent::from_json(
+                    value
+                        .get("event")
+                        .and_then(Value::as_str)
+                        .ok_or_else(malformed)?,
+                )?,
+            },
+            Some("commitments") => Self::Commitments {
+                session_id,
+                commitments: serde_json::from_value(read_value("commitments")?)?,
+            },
+            Some("refusal") => Self::Refusal { session_id },
+            Some("sign_request") => Self::SignRequest {
+                session_id,
+                signing_package: serde_json::from_value(read_value("signing_package")?)?,
+            },
+            Some("signature_share") => Self::SignatureShare {
+                session_id,
+                signature_share: serde_json::from_value(read_value("signature_share")?)?,
+            },
+            _ => return Err(malformed()),
+        })
+    }
+}
+
+/// Encrypts `message` to `recipient` and publishes it. The message is
+/// queued if no relay is connected. See [`NostrModule::publish`].
+async fn send_message(
+    nostr_module: &NostrModule,
+    share: &ThresholdShare,
+    recipient: PublicKey,
+    message: &CosigningMessage,
+) -> anyhow::Result<()> {
+    let transport_keys = share.transport_keys();
+
+    let content = nip44::encrypt(
+        transport_keys.secret_key(),
+        &recipient,
+        message.to_json()?,
+        nip44::Version::V2,
+    )?;
+
+    let expiration = Timestamp::from(
+        Timestamp::now()
+            .as_u64()
+            .saturating_add(COSIGNING_MESSAGE_LIFETIME.as_secs()),
+    );
+
+    let event = EventBuilder::new(
+        COSIGNING_KIND,
+        content,
+        [Tag::public_key(recipient), Tag::expiration(expiration)],
+    )
+    .to_event(&transport_keys)?;
+
+    nostr_module.publish(event).await?;
+
+    Ok(())
+}
+
+/// Subscribes to the cosigning messages that `share`'s peers send it from `since` onwards, along
+/// with the identifier of the peer that sent each. Messages that can't be read are skipped.
+fn message_stream(
+    nostr_module: &NostrModule,
+    share: &ThresholdShare,
+    since: Timestamp,
+) -> impl Stream<Item = (frost::Identifier, CosigningMessage)> {
+    let transport_keys = share.transport_keys();
+    let peers = share.peers.clone();
+
+    let filter = Filter::new()
+        .kind(COSIGNING_KIND)
+        .authors(peers.values().copied())
+        .pubkey(transport_keys.public_key())
+        .since(since);
+
+    nostr_module
+        .subscribe(SubscriptionPurpose::Cosigning, vec![filter])
+        .filter_map(move |event| {
+            let message_or = peers
+                .iter()
+                .find(|(_, public_key)| **public_key == event.pubkey)
+                .and_then(|(identifier, public_key)| {
+                    let json =
+                        nip44::decrypt(transport_keys.secret_key(), public_key, &event.content)
+                            .ok()?;
+
+                    Some((*identifier, CosigningMessage::from_json(&json).ok()?))
+                });
+
+            futures::future::ready(message_or)
+        })
+}
+
+fn get_event_id(unsigned_event: &UnsignedEvent) -> EventId {
+    EventId::new(
+        &unsigned_event.pubkey,
+        &unsigned_event.created_at,
+        &unsigned_event.kind,
+        &unsigned_event.tags,
+        &unsigned_event.content,
+    )
+}
+
+/// The coordinator's side of signing a single event. Kept apart from the relay
+/// traffic in [`sign_event`], so that the signing itself can be tested without relays.
+struct SigningSession<'a> {
+    share: &'a ThresholdShare,
+    unsigned_event: UnsignedEvent,
+    event_id: EventId,
+    nonces: frost::round1::SigningNonces,
+    // Starts out with the coordinator's own commitments.
+    commitments: BTreeMap<frost::Identifier, frost::round1::SigningCommitments>,
+    refusals: BTreeSet<frost::Identifier>,
+    signing_package_or: Option<frost::SigningPackage>,
+    signature_shares: BTreeMap<frost::Identifier, frost::round2::SignatureShare>,
+}
+
+impl<'a> SigningSession<'a> {
+    fn new(share: &'a ThresholdShare, unsigned_event: UnsignedEvent) -> anyhow::Result<Self> {
+        if unsigned_event.pubkey != share.public_key {
+            anyhow::bail!("The event isn't for this threshold key");
+        }
+
+        let (nonces, commitments) = share.commit();
+
+        Ok(Self {
+            share,
+            event_id: get_event_id(&unsigned_event),
+            unsigned_event,
+            nonces,
+            commitments: BTreeMap::from([(share.identifier(), commitments)]),
+            refusals: BTreeSet::new(),
+            signing_package_or: None,
+            signature_shares: BTreeMap::new(),
+        })
+    }
+
+    /// Adds a cosigner's commitments. Only the first cosigners to commit are asked to sign,
+    /// so commitments that arrive once there are enough are ignored.
+    fn add_commitments(
+        &mut self,
+        identifier: frost::Identifier,
+        commitments: frost::round1::SigningCommitments,
+    ) {
+        if self.share.peers.contains_key(&identifier)
+            && !self.has_enough_commitments()
+            && self.signing_package_or.is_none()
+        {
+            self.commitments.insert(identifier, commitments);
+        }
+    }
+
+    fn add_refusal(&mut self, identifier: frost::Identifier) {
+        if self.share.peers.contains_key(&identifier) {
+            self.refusals.insert(identifier);
+        }
+    }
+
+    fn has_enough_commitments(&self) -> bool {
+        self.commitments.len() >= usize::from(self.share.threshold())
+    }
+
+    /// Whether enough shares are left that haven't refused to sign.
+    fn can_get_enough_commitments(&self) -> bool {
+        self.share.share_count().saturating_sub(self.refusals.len())
+            >= usize::from(self.share.threshold())
+    }
+
+    /// Ends the first round, signing with the coordinator's own share. Returns the signing
+    /// package, which every cosigner that committed has to be sent for the second round.
+    fn start_signing(&mut self) -> anyhow::Result<frost::SigningPackage> {
+        if !self.has_enough_commitments() {
+            anyhow::bail!("Not enough cosigners have committed to signing");
+        }
+
+        let signing_package =
+            frost::SigningPackage::new(self.commitments.clone(), self.event_id.as_bytes());
+
+        let signature_share = self
+            .share
+            .sign(&signing_package, &self.nonces, self.event_id)?;
+
+        self.signature_shares
+            .insert(self.share.identifier(), signature_share);
+        self.signing_package_or = Some(signing_package.clone());
+
+        Ok(signing_package)
+    }
+
+    /// The cosigners whose commitments went into the signing package.
+    fn committed_cosigners(&self) -> impl Iterator<Item = &frost::Identifier> {
+        let own_identifier = self.share.identifier();
+
+        self.commitments
+            .keys()
+            .filter(move |identifier| **identifier != own_identifier)
+    }
+
+    fn add_signature_share(
+        &mut self,
+        identifier: frost::Identifier,
+        signature_share: frost::round2::SignatureShare,
+    ) {
+        if self.commitments.contains_key(&identifier) {
+            self.signature_shares.insert(identifier, signature_share);
+        }
+    }
+
+    fn has_every_signature_share(&self) -> bool {
+        self.signing_package_or.is_some() && self.signature_shares.len() == self.commitments.len()
+    }
+
+    /// Puts the signature shares together into the signed event.
+    fn finish(self) -> anyhow::Result<Event> {
+        let Some(signing_package) = self.signing_package_or else {
+            anyhow::bail!("Signing hasn't started");
+        };
+
+        let signature = frost::aggregate(
+            &signing_package,
+            &self.signature_shares,
+            &self.share.public_key_package,
+        )?;
+
+        let event = self
+            .unsigned_event
+            .add_signature(Signature::from_slice(&signature.serialize()?)?)?;
+
+        event.verify()?;
+
+        Ok(event)
+    }
+}
+
+/// Signs `unsigned_event` with the coordinator's share, asking the cosigners over relays for
+/// the rest of the signature. The first cosigners to commit are the ones asked to sign, so
+/// signing only fails if too few of them approve within [`COSIGNING_ROUND_TIMEOUT`].
+pub async fn sign_event(
+    nostr_module: &NostrModule,
+    share: &ThresholdShare,
+    unsigned_event: UnsignedEvent,
+) -> anyhow::Result<Event> {
+    if !share.is_coordinator {
+        anyhow::bail!("Only the coordinator's share can start signing");
+    }
+
+    let mut session = SigningSession::new(share, unsigned_event.clone())?;
+
+    let session_id = format!("{:032x}", thread_rng().gen::<u128>());
+
+    // Relays keep the messages, so answers that arrive before the
+    // subscription has reached a relay are still picked up.
+    let mut messages = Box::pin(message_stream(nostr_module, share, Timestamp::now()));
+
+    for recipient in share.peers.values() {
+        send_message(
+            nostr_module,
+            share,
+            *recipient,
+            &CosigningMessage::CommitRequest {
+                session_id: session_id.clone(),
+                event: unsigned_event.clone(),
+            },
+        )
+        .await?;
+    }
+
+    let commit_round = async {
+        while let Some((identifier, message)) = messages.next().await {
+            match message {
+                CosigningMessage::Commitments {
+                    session_id: message_session_id,
+                    commitments,
+                } if message_session_id == session_id => {
+                    session.add_commitments(identifier, commitments);
+
+                    if session.has_enough_commitments() {
+                        return Ok(());
+                    }
+                }
+                CosigningMessage::Refusal {
+                    session_id: message_session_id,
+                } if message_session_id == session_id => {
+                    session.add_refusal(identifier);
+
+                    if !session.can_get_enough_commitments() {
+                        anyhow::bail!("Too many cosigners refused to sign");
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        anyhow::bail!("Lost the connection to relays")
+    };
+
+    tokio::time::timeout(COSIGNING_ROUND_TIMEOUT, commit_round)
+        .await
+        .map_err(|_| anyhow::anyhow!("Too few cosigners approved in time"))??;
+
+    let signing_package = session.start_signing()?;
+
+    let committed_cosigner_public_keys: Vec<PublicKey> = session
+        .committed_cosigners()
+        .filter_map(|identifier| share.peers.get(identifier).copied())
+        .collect();
+
+    for recipient in committed_cosigner_public_keys {
+        send_message(
+            nostr_module,
+            share,
+            recipient,
+            &CosigningMessage::SignRequest {
+                session_id: session_id.clone(),
+                signing_package: signing_package.clone(),
+            },
+        )
+        .await?;
+    }
+
+    let sign_round = async {
+        while let Some((identifier, message)) = messages.next().await {
+            if let CosigningMessage::SignatureShare {
+                session_id: message_session_id,
+                signature_share,
+            } = message
+            {
+                if message_session_id == session_id {
+                    session.add_signature_share(identifier, signature_share);
+
+                    if session.has_every_signature_share() {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+
+        anyhow::bail!("Lost the connection to relays")
+    };
+
+    tokio::time::timeout(COSIGNING_ROUND_TIMEOUT, sign_round)
+        .await
+        .map_err(|_| anyhow::anyhow!("Cosigners didn't finish signing in time"))??;
+
+    session.finish()
+}
+
+// A session that a cosigner's user approved, waiting for the coordinator to ask for a signature.
+struct ApprovedSession {
+    event_id: EventId,
+    nonces: frost::round1::SigningNonces,
+}
+
+/// Answers the coordinator of a threshold key with a cosigner's share.
+#[derive(Clone)]
+pub struct Cosigner {
+    share: Arc<ThresholdShare>,
+    nostr_module: NostrModule,
+    // Each session's nonces are removed once they've been used, since signing twice with the same
+    // nonces leaks the share. They're only kept in memory, so restarting drops any open sessions.
+    approved_sessions: Arc<Mutex<HashMap<String, ApprovedSession>>>,
+}
+
+impl Debug for Cosigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Cosigner")
+            .field("public_key", &self.share.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Cosigner {
+    pub fn new(nostr_module: NostrModule, share: ThresholdShare) -> anyhow::Result<Self> {
+        if share.is_coordinator {
+            anyhow::bail!("The coordinator's share can't be used as a cosigner");
+        }
+
+        Ok(Self {
+            share: Arc::new(share),
+            nostr_module,
+            approved_sessions: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Listens for the coordinator's messages. Requests to commit to signing are yielded for the
+    /// user to approve or refuse. Requests to sign are answered right away if their session was
+    /// approved, and ignored otherwise.
+    pub fn request_stream(&self) -> impl Stream<Item = CosigningRequest> {
+        let cosigner = self.clone();
+
+        async_stream::stream! {
+            let since = Timestamp::from(
+                Timestamp::now()
+                    .as_u64()
+                    .saturating_sub(COSIGNING_REQUEST_LOOKBACK.as_secs()),
+            );
+
+            let mut messages =
+                Box::pin(message_stream(&cosigner.nostr_module, &cosigner.share, since));
+
+            // Several relays can send the same request.
+            let mut seen_session_ids = HashSet::new();
+
+            while let Some((_, message)) = messages.next().await {
+                match message {
+                    CosigningMessage::CommitRequest { session_id, event }
+                        if event.pubkey == cosigner.share.public_key =>
+                    {
+                        if seen_session_ids.insert(session_id.clone()) {
+                            yield CosigningRequest {
+                                public_key: cosigner.share.public_key,
+                                session_id,
+                                event,
+                                cosigner: cosigner.clone(),
+                            };
+                        }
+                    }
+                    CosigningMessage::SignRequest {
+                        session_id,
+                        signing_package,
+                    } => {
+                        // TODO: Log a warning if the signature share fails to send.
+                        let _ = cosigner.answer_sign_request(session_id, &signing_package).await;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    async fn answer_sign_request(
+        &self,
+        session_id: String,
+        signing_package: &frost::SigningPackage,
+    ) -> anyhow::Result<()> {
+        let Some(approved_session) = self
+            .approved_sessions
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .remove(&session_id)
+        else {
+            anyhow::bail!("The session wasn't approved");
+        };
+
+        let signature_share = self.share.sign(
+            signing_package,
+            &approved_session.nonces,
+            approved_session.event_id,
+        )?;
+
+        self.send_to_coordinator(&CosigningMessage::SignatureShare {
+            session_id,
+            signature_share,
+        })
+        .await
+    }
+
+    async fn send_to_coordinator(&self, message: &CosigningMessage) -> anyhow::Result<()> {
+        for recipient in self.share.peers.values() {
+            send_message(&self.nostr_module, &self.share, *recipient, message).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A request from the coordinator of a threshold key for a cosigner to help sign an event.
+/// Answer it with [`Self::approve`] or [`Self::refuse`].
+#[derive(Debug, Clone)]
+pub struct CosigningRequest {
+    /// The identity that the event would be signed as.
+    pub public_key: PublicKey,
+    pub session_id: String,
+    pub event: UnsignedEvent,
+    cosigner: Cosigner,
+}
+
+impl CosigningRequest {
+    /// Commits to signing the event. The signature share is sent
+    /// once the coordinator asks for it, as long as Keystache is open.
+    pub async fn approve(&self) -> anyhow::Result<()> {
+        let (nonces, commitments) = self.cosigner.share.commit();
+
+        self.cosigner
+            .approved_sessions
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .insert(
+                self.session_id.clone(),
+                ApprovedSession {
+                    event_id: get_event_id(&self.event),
+                    nonces,
+                },
+            );
+
+        self.cosigner
+            .send_to_coordinator(&CosigningMessage::Commitments {
+                session_id: self.session_id.clone(),
+                commitments,
+            })
+            .await
+    }
+
+    /// Tells the coordinator not to wait for this cosigner.
+    pub async fn refuse(&self) -> anyhow::Result<()> {
+        self.cosigner
+            .send_to_coordinator(&CosigningMessage::Refusal {
+                session_id: self.session_id.clone(),
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr_sdk::EventBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_threshold_signing() {
+        let keys = Keys::generate();
+
+        let (coordinator_share, cosigner_shares) =
+            split_secret_key(keys.secret_key(), 2, 2).unwrap();
+
+        assert_eq!(cosigner_shares.len(), 2);
+        assert_eq!(coordinator_share.threshold(), 2);
+        assert_eq!(coordinator_share.share_count(), 3);
+
+        // Shares survive being saved or handed to a cosigner.
+        for share in cosigner_shares.iter().chain([&coordinator_share]) {
+            let json = share.to_json().unwrap();
+            assert_eq!(
+                ThresholdShare::from_json(&json).unwrap().to_json().unwrap(),
+                json
+            );
+        }
+
+        let unsigned_event = EventBuilder::text_note("Signed by 2 of 3 shares", [])
+            .to_unsigned_event(keys.public_key());
+
+        let mut session = SigningSession::new(&coordinator_share, unsigned_event).unwrap();
+
+        // The coordinator can't sign alone.
+        assert!(session.start_signing().is_err());
+
+        let cosigner_share = &cosigner_shares[1];
+        let (nonces, commitments) = cosigner_share.commit();
+        session.add_commitments(cosigner_share.identifier(), commitments);
+
+        let signing_package = session.start_signing().unwrap();
+        assert_eq!(
+            session.committed_cosigners().collect::<Vec<_>>(),
+            [&cosigner_share.identifier()]
+        );
+
+        // Cosigners only sign the event that they approved.
+        assert!(cosigner_share
+            .sign(&signing_package, &nonces, EventId::all_zeros())
+            .is_err());
+
+        let signature_share = cosigner_share
+            .sign(&signing_package, &nonces, session.event_id)
+            .unwrap();
+        session.add_signature_share(cosigner_share.identifier(), signature_share);
+        assert!(session.has_every_signature_share());
+
+        let event = session.finish().unwrap();
+        assert_eq!(event.pubkey, keys.public_key());
+        assert!(event.verify().is_ok());
+    }
+
+    #[test]
+    fn test_split_secret_key_checks_threshold() {
+        let keys = Keys::generate();
+
+        assert!(split_secret_key(keys.secret_key(), 1, 2).is_err());
+        assert!(split_secret_key(keys.secret_key(), 4, 2).is_err());
+        assert!(split_secret_key(keys.secret_key(), 3, 2).is_ok());
+    }
+}
@@ -0,0 +1,210 @@
+use std::{path::Path, time::Duration};
+
+use chrono::{DateTime, NaiveDateTime};
+
+/// Failed attempts are recorded in this file, next to the database. It isn't
+/// encrypted, since it has to be read before the database can be unlocked.
+const FAILED_UNLOCK_ATTEMPTS_FILE_NAME: &str = "failed-unlock-attempts";
+
+/// The number of failed attempts allowed before the user has to wait between attempts.
+/// Leaves room for typos.
+const FREE_FAILED_UNLOCK_ATTEMPTS: u32 = 3;
+
+/// How long to wait after the first failed attempt past the free ones.
+/// Each further failed attempt doubles the wait, up to `MAX_UNLOCK_COOLDOWN`.
+const BASE_UNLOCK_COOLDOWN: Duration = Duration::from_secs(5);
+const MAX_UNLOCK_COOLDOWN: Duration = Duration::from_secs(60 * 15);
+
+/// Password attempts that failed since the database was last unlocked. Repeated failures
+/// make the user wait longer and longer before trying again, which slows down guessing
+/// the password through the app. This doesn't protect against guesses made directly
+/// against a copy of the database file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FailedUnlockAttempts {
+    pub count: u32,
+    pub last_failure_time_or: Option<NaiveDateTime>,
+}
+
+impl FailedUnlockAttempts {
+    /// Loads the failed attempts recorded in `folder`.
+    /// There are none if the file is missing or can't be read.
+    pub fn load(folder: &Path) -> Self {
+        std::fs::read_to_string(folder.join(FAILED_UNLOCK_ATTEMPTS_FILE_NAME))
+            .ok()
+            .and_then(|contents| Self::from_file_contents(&contents))
+            .unwrap_or_default()
+    }
+
+    /// Records a failed attempt at `now` and saves it to `folder`.
+    pub fn record_failure(&mut self, folder: &Path, now: NaiveDateTime) -> anyhow::Result<()> {
+        self.count = self.count.saturating_add(1);
+        self.last_failure_time_or = Some(now);
+
+        std::fs::create_dir_all(folder)?;
+        std::fs::write(
+            folder.join(FAILED_UNLOCK_ATTEMPTS_FILE_NAME),
+            self.to_file_contents(),
+        )?;
+
+        Ok(())
+    }
+
+    /// Forgets the failed attempts recorded in `folder`, such as after a successful unlock.
+    pub fn clear(folder: &Path) -> anyhow::Result<()> {
+        match std::fs::remove_file(folder.join(FAILED_UNLOCK_ATTEMPTS_FILE_NAME)) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// How long the user has to wait after the most recent failed attempt.
+    pub fn cooldown(&self) -> Duration {
+        let Some(exponent) = self.count.checked_sub(FREE_FAILED_UNLOCK_ATTEMPTS) else {
+            return Duration::ZERO;
+        };
+
+        2_u32
+            .checked_pow(exponent)
+            .and_then(|multiplier| BASE_UNLOCK_COOLDOWN.checked_mul(multiplier))
+            .map_or(MAX_UNLOCK_COOLDOWN, |cooldown| {
+                cooldown.min(MAX_UNLOCK_COOLDOWN)
+            })
+    }
+
+    /// How much longer the user has to wait at `now` before trying again.
+    /// `None` if they can try now.
+    pub fn remaining_cooldown_or(&self, now: NaiveDateTime) -> Option<Duration> {
+        let last_failure_time = self.last_failure_time_or?;
+        let cooldown = self.cooldown();
+
+        // If the clock was turned back, the full cooldown still has to pass.
+        let elapsed = now
+            .signed_duration_since(last_failure_time)
+            .to_std()
+            .unwrap_or_default();
+
+        Some(cooldown.saturating_sub(elapsed)).filter(|remaining| !remaining.is_zero())
+    }
+
+    fn to_file_contents(self) -> String {
+        format!(
+            "{} {}",
+            self.count,
+            self.last_failure_time_or
+                .map_or(0, |last_failure_time| last_failure_time
+                    .and_utc()
+                    .timestamp())
+        )
+    }
+
+    fn from_file_contents(contents: &str) -> Option<Self> {
+        let (count, last_failure_timestamp) = contents.trim().split_once(' ')?;
+
+        let last_failure_timestamp: i64 = last_failure_timestamp.parse().ok()?;
+
+        Some(Self {
+            count: count.parse().ok()?,
+            last_failure_time_or: if last_failure_timestamp == 0 {
+                None
+            } else {
+                Some(DateTime::from_timestamp(last_failure_timestamp, 0)?.naive_utc())
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cooldown() {
+        let cooldown_after = |count| {
+            FailedUnlockAttempts {
+                count,
+                last_failure_time_or: None,
+            }
+            .cooldown()
+        };
+
+        assert_eq!(cooldown_after(0), Duration::ZERO);
+        assert_eq!(
+            cooldown_after(FREE_FAILED_UNLOCK_ATTEMPTS - 1),
+            Duration::ZERO
+        );
+        assert_eq!(
+            cooldown_after(FREE_FAILED_UNLOCK_ATTEMPTS),
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            cooldown_after(FREE_FAILED_UNLOCK_ATTEMPTS + 1),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            cooldown_after(FREE_FAILED_UNLOCK_ATTEMPTS + 2),
+            Duration::from_secs(20)
+        );
+        assert_eq!(cooldown_after(50), MAX_UNLOCK_COOLDOWN);
+        assert_eq!(cooldown_after(u32::MAX), MAX_UNLOCK_COOLDOWN);
+    }
+
+    #[test]
+    fn test_remaining_cooldown() {
+        let last_failure_time = DateTime::from_timestamp(1_700_000_000, 0)
+            .unwrap()
+            .naive_utc();
+        let attempts = FailedUnlockAttempts {
+            count: FREE_FAILED_UNLOCK_ATTEMPTS + 1,
+            last_failure_time_or: Some(last_failure_time),
+        };
+
+        assert_eq!(
+            attempts.remaining_cooldown_or(last_failure_time + chrono::Duration::seconds(4)),
+            Some(Duration::from_secs(6))
+        );
+        assert_eq!(
+            attempts.remaining_cooldown_or(last_failure_time + chrono::Duration::seconds(10)),
+            None
+        );
+
+        // Turning the clock back doesn't shorten the wait.
+        assert_eq!(
+            attempts.remaining_cooldown_or(last_failure_time - chrono::Duration::hours(1)),
+            Some(Duration::from_secs(10))
+        );
+    }
+
+    #[test]
+    fn test_record_and_clear_failures() {
+        let folder = tempfile::tempdir().unwrap();
+        let now = DateTime::from_timestamp(1_700_000_000, 0)
+            .unwrap()
+            .naive_utc();
+
+        assert_eq!(
+            FailedUnlockAttempts::load(folder.path()),
+            FailedUnlockAttempts::default()
+        );
+
+        let mut attempts = FailedUnlockAttempts::default();
+        attempts.record_failure(folder.path(), now).unwrap();
+        attempts.record_failure(folder.path(), now).unwrap();
+
+        assert_eq!(
+            FailedUnlockAttempts::load(folder.path()),
+            FailedUnlockAttempts {
+                count: 2,
+                last_failure_time_or: Some(now),
+            }
+        );
+
+        FailedUnlockAttempts::clear(folder.path()).unwrap();
+        assert_eq!(
+            FailedUnlockAttempts::load(folder.path()),
+            FailedUnlockAttempts::default()
+        );
+
+        // Clearing when nothing is recorded is fine.
+        FailedUnlockAttempts::clear(folder.path()).unwrap();
+    }
+}
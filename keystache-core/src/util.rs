@@ -0,0 +1,204 @@
+use chrono::{Local, NaiveDateTime, TimeZone};
+use fedimint_core::Amount;
+
+use crate::config::ClockFormat;
+
+/// Formats an amount in sats, such as `1,234.567 sats`.
+/// Millisats are shown as decimal places, without trailing zeros.
+pub fn format_amount(amount: Amount) -> String {
+    let amount_sats = amount.msats / 1000;
+    let sub_sat_msats = amount.msats % 1000;
+
+    if amount_sats == 1 && sub_sat_msats == 0 {
+        return "1 sat".to_string();
+    }
+
+    let comma_formatted_sats = amount_sats
+        .to_string()
+        .as_bytes()
+        .rchunks(3)
+        .rev()
+        .map(String::from_utf8_lossy)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let msats_str = if sub_sat_msats == 0 {
+        String::new()
+    } else {
+        let mut sub_sat_msats_str = format!(".{sub_sat_msats:03}");
+        while sub_sat_msats_str.ends_with('0') {
+            sub_sat_msats_str.pop();
+        }
+        sub_sat_msats_str
+    };
+
+    format!("{comma_formatted_sats}{msats_str} sats")
+}
+
+/// Formats a UTC time for display, relative to `now` and then in the local time zone,
+/// such as `3 days ago (2024-10-13 2:05 PM)`.
+pub fn format_time(time: NaiveDateTime, now: NaiveDateTime, clock_format: ClockFormat) -> String {
+    format!(
+        "{} ({})",
+        format_relative_time(time, now),
+        format_time_in_zone(time, &Local, clock_format)
+    )
+}
+
+/// Formats a UTC time relative to `now`, such as `3 days ago` or `in 2 hours`.
+/// Only the largest whole unit is shown.
+pub fn format_relative_time(time: NaiveDateTime, now: NaiveDateTime) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+    const MONTH: u64 = 30 * DAY;
+    const YEAR: u64 = 365 * DAY;
+
+    let seconds = (now - time).num_seconds();
+    let abs_seconds = seconds.unsigned_abs();
+
+    if abs_seconds < MINUTE {
+        return "just now".to_string();
+    }
+
+    let (count, unit) = match abs_seconds {
+        s if s < HOUR => (s / MINUTE, "minute"),
+        s if s < DAY => (s / HOUR, "hour"),
+        s if s < MONTH => (s / DAY, "day"),
+        s if s < YEAR => (s / MONTH, "month"),
+        s => (s / YEAR, "year"),
+    };
+
+    let plural_suffix = if count == 1 { "" } else { "s" };
+
+    if seconds < 0 {
+        format!("in {count} {unit}{plural_suffix}")
+    } else {
+        format!("{count} {unit}{plural_suffix} ago")
+    }
+}
+
+/// Formats a UTC time in the given time zone, such as `2024-10-13 14:05`.
+fn format_time_in_zone<Tz: TimeZone>(
+    time: NaiveDateTime,
+    time_zone: &Tz,
+    clock_format: ClockFormat,
+) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let format = match clock_format {
+        ClockFormat::TwelveHour => "%Y-%m-%d %-I:%M %p",
+        ClockFormat::TwentyFourHour => "%Y-%m-%d %H:%M",
+    };
+
+    time_zone
+        .from_utc_datetime(&time)
+        .format(format)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{FixedOffset, NaiveDate, TimeDelta, Utc};
+
+    use super::*;
+
+    #[test]
+    fn test_format_relative_time() {
+        let now = NaiveDate::from_ymd_opt(2024, 10, 16)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+
+        assert_eq!(format_relative_time(now, now), "just now");
+        assert_eq!(
+            format_relative_time(now - TimeDelta::seconds(59), now),
+            "just now"
+        );
+        assert_eq!(
+            format_relative_time(now - TimeDelta::minutes(1), now),
+            "1 minute ago"
+        );
+        assert_eq!(
+            format_relative_time(now - TimeDelta::hours(5), now),
+            "5 hours ago"
+        );
+        assert_eq!(
+            format_relative_time(now - TimeDelta::days(3), now),
+            "3 days ago"
+        );
+        assert_eq!(
+            format_relative_time(now - TimeDelta::days(65), now),
+            "2 months ago"
+        );
+        assert_eq!(
+            format_relative_time(now - TimeDelta::days(800), now),
+            "2 years ago"
+        );
+
+        // Future times, such as when something expires.
+        assert_eq!(
+            format_relative_time(now + TimeDelta::hours(2), now),
+            "in 2 hours"
+        );
+    }
+
+    #[test]
+    fn test_format_time_in_zone() {
+        let time = NaiveDate::from_ymd_opt(2024, 10, 13)
+            .unwrap()
+            .and_hms_opt(14, 5, 0)
+            .unwrap();
+
+        assert_eq!(
+            format_time_in_zone(time, &Utc, ClockFormat::TwentyFourHour),
+            "2024-10-13 14:05"
+        );
+        assert_eq!(
+            format_time_in_zone(time, &Utc, ClockFormat::TwelveHour),
+            "2024-10-13 2:05 PM"
+        );
+
+        // The date rolls over in time zones ahead of UTC.
+        let time_zone = FixedOffset::east_opt(11 * 60 * 60).unwrap();
+        assert_eq!(
+            format_time_in_zone(time, &time_zone, ClockFormat::TwentyFourHour),
+            "2024-10-14 01:05"
+        );
+    }
+
+    #[test]
+    fn test_format_amount_sats() {
+        // 0 sats is plural.
+        assert_eq!(format_amount(Amount::from_sats(0)), "0 sats");
+
+        // 1 sat is singular.
+        assert_eq!(format_amount(Amount::from_sats(1)), "1 sat");
+
+        // Digits are ordered correctly.
+        assert_eq!(format_amount(Amount::from_sats(1234)), "1,234 sats");
+
+        // Commas are placed correctly.
+        assert_eq!(format_amount(Amount::from_sats(10)), "10 sats");
+        assert_eq!(format_amount(Amount::from_sats(100)), "100 sats");
+        assert_eq!(format_amount(Amount::from_sats(1_000)), "1,000 sats");
+        assert_eq!(format_amount(Amount::from_sats(10_000)), "10,000 sats");
+        assert_eq!(format_amount(Amount::from_sats(100_000)), "100,000 sats");
+        assert_eq!(
+            format_amount(Amount::from_sats(1_000_000)),
+            "1,000,000 sats"
+        );
+
+        // Millisats are displayed as sub-sats, without extra zeros.
+        assert_eq!(format_amount(Amount::from_msats(1)), "0.001 sats");
+        assert_eq!(format_amount(Amount::from_msats(10)), "0.01 sats");
+        assert_eq!(format_amount(Amount::from_msats(100)), "0.1 sats");
+
+        // Millisats are displayed properly with sats
+        assert_eq!(
+            format_amount(Amount::from_msats(123456789)),
+            "123,456.789 sats"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use std::{str::FromStr, time::Duration};
+
+use chrono::NaiveDateTime;
+use fedimint_core::Amount;
+use futures::{Stream, StreamExt};
+use lightning_invoice::Bolt11Invoice;
+use nostr_sdk::{Event, EventId, Filter, JsonUtil, Kind, PublicKey, Timestamp};
+
+use crate::nostr::{NostrModule, SubscriptionPurpose};
+
+/// How far back to look for zap receipts when (re)subscribing.
+/// Zaps received while Keystache was closed are picked up as long
+/// as they arrived within this window.
+const ZAP_RECEIPT_LOOKBACK: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// A NIP-57 zap receipt for a zap sent to one of the user's keys.
+///
+/// Receipts are published by the recipient's LNURL server, and aren't checked against
+/// the server's key. So the amount can only be trusted if the invoice was paid into
+/// one of the user's federations. See [`ZapRecord::payment_id_or`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZapReceipt {
+    pub event_id: EventId,
+    pub recipient_public_key: PublicKey,
+    /// The key that sent the zap. `None` for anonymous zaps.
+    pub sender_public_key_or: Option<PublicKey>,
+    /// The note that was zapped. `None` if the zap was for the recipient's profile.
+    pub zapped_event_id_or: Option<EventId>,
+    pub amount: Amount,
+    pub bolt11_invoice: Bolt11Invoice,
+    pub comment: String,
+}
+
+impl ZapReceipt {
+    /// Parses a zap receipt (kind 9735) event.
+    pub fn from_event(event: &Event) -> anyhow::Result<Self> {
+        if event.kind != Kind::ZapReceipt {
+            return Err(anyhow::anyhow!("Event isn't a zap receipt"));
+        }
+
+        let recipient_public_key = PublicKey::from_hex(
+            get_tag_value(event, "p").ok_or_else(|| anyhow::anyhow!("Missing recipient"))?,
+        )?;
+
+        let zapped_event_id_or = get_tag_value(event, "e")
+            .map(EventId::from_hex)
+            .transpose()?;
+
+        let bolt11_invoice = Bolt11Invoice::from_str(
+            get_tag_value(event, "bolt11").ok_or_else(|| anyhow::anyhow!("Missing invoice"))?,
+        )?;
+
+        let amount = Amount::from_msats(
+            bolt11_invoice
+                .amount_milli_satoshis()
+                .ok_or_else(|| anyhow::anyhow!("Invoice has no amount"))?,
+        );
+
+        let (sender_public_key_or, comment) = get_tag_value(event, "description")
+            .map(parse_zap_request)
+            .unwrap_or_default();
+
+        Ok(Self {
+            event_id: event.id,
+            recipient_public_key,
+            sender_public_key_or,
+            zapped_event_id_or,
+            amount,
+            bolt11_invoice,
+            comment,
+        })
+    }
+}
+
+/// A zap receipt as saved in the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZapRecord {
+    pub id: i32,
+    pub receipt: ZapReceipt,
+    /// The incoming payment whose invoice the zap paid, if Keystache made the invoice.
+    pub payment_id_or: Option<i32>,
+    pub create_time: NaiveDateTime,
+}
+
+/// Listens for zap receipts sent to any of `recipient_public_keys`.
+pub fn receipt_stream(
+    nostr_module: NostrModule,
+    recipient_public_keys: Vec<PublicKey>,
+) -> impl Stream<Item = ZapReceipt> {
+    async_stream::stream! {
+        if recipient_public_keys.is_empty() {
+            return;
+        }
+
+        let filter = Filter::new()
+            .kind(Kind::ZapReceipt)
+            .pubkeys(recipient_public_keys.clone())
+            .since(Timestamp::from(
+                Timestamp::now()
+                    .as_u64()
+                    .saturating_sub(ZAP_RECEIPT_LOOKBACK.as_secs()),
+            ));
+
+        let mut events =
+            Box::pin(nostr_module.subscribe(SubscriptionPurpose::ZapReceipts, vec![filter]));
+
+        while let Some(event) = events.next().await {
+            // TODO: Log a warning if the receipt fails to parse.
+            let Ok(receipt) = ZapReceipt::from_event(&event) else {
+                continue;
+            };
+
+            if recipient_public_keys.contains(&receipt.recipient_public_key) {
+                yield receipt;
+            }
+        }
+    }
+}
+
+fn get_tag_value<'a>(event: &'a Event, tag_name: &str) -> Option<&'a str> {
+    event.tags.iter().find_map(|tag| match tag.as_slice() {
+        [name, value, ..] if name == tag_name => Some(value.as_str()),
+        _ => None,
+    })
+}
+
+/// Gets the sender and comment from the zap request (kind 9734) embedded in a zap receipt.
+/// Zap requests that aren't signed by their sender are treated as anonymous,
+/// so that nobody can make a zap look like it came from someone else.
+fn parse_zap_request(description: &str) -> (Option<PublicKey>, String) {
+    match Event::from_json(description) {
+        Ok(zap_request) if zap_request.kind == Kind::ZapRequest && zap_request.verify().is_ok() => {
+            (Some(zap_request.pubkey), zap_request.content)
+        }
+        _ => (None, String::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr_sdk::{EventBuilder, Keys};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_zap_request() {
+        let sender = Keys::generate();
+
+        let zap_request = EventBuilder::new(Kind::ZapRequest, "Great post!", [])
+            .to_event(&sender)
+            .unwrap();
+
+        assert_eq!(
+            parse_zap_request(&zap_request.as_json()),
+            (Some(sender.public_key()), "Great post!".to_string())
+        );
+
+        // Anything other than a zap request is treated as an anonymous zap.
+        let text_note = EventBuilder::text_note("Great post!", [])
+            .to_event(&sender)
+            .unwrap();
+
+        assert_eq!(
+            parse_zap_request(&text_note.as_json()),
+            (None, String::new())
+        );
+        assert_eq!(parse_zap_request("not json"), (None, String::new()));
+
+        // Zap requests whose content was changed after signing are treated as anonymous.
+        let tampered_zap_request = zap_request.as_json().replace("Great post!", "Bad post!");
+
+        assert_eq!(
+            parse_zap_request(&tampered_zap_request),
+            (None, String::new())
+        );
+    }
+}
@@ -0,0 +1,2385 @@
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::{NaiveDateTime, Utc};
+use fedimint_core::{config::FederationId, invite_code::InviteCode, Amount};
+use iced::{
+    futures::StreamExt,
+    keyboard,
+    widget::{
+        column, container, progress_bar, row, scrollable,
+        scrollable::{AbsoluteOffset, Viewport},
+        stack, text, Column,
+    },
+    window, Alignment, Element, Length, Task, Theme,
+};
+use nip_55::nip_46::{Nip46OverNip55ServerStream, Nip46RequestApproval};
+use nostr_sdk::{
+    nips::nip47::{ErrorCode, Method},
+    EventId, FromBech32, Metadata, PublicKey, ToBech32, Url,
+};
+
+use crate::{
+    backup::{self, BACKUP_CHECK_INTERVAL},
+    clipboard::{Clipboard, ClipboardBackend},
+    clock::Clock,
+    config::AppTheme,
+    db::{self, Database},
+    exchange_rate::{ExchangeRate, FiatCurrency, PriceSource},
+    fedimint::{
+        BalanceThresholdCrossing, FederationView, LightningReceiveCompletion, PaymentDirection,
+        Wallet, WalletView,
+    },
+    keychain,
+    maintenance::DATABASE_MAINTENANCE_INTERVAL,
+    metrics::Nip46RequestOutcome,
+    nostr::{
+        ClockSkew, NostrModuleMessage, NostrState, RelayReconnection, OUTBOX_RETRY_CHECK_INTERVAL,
+    },
+    nwc::{
+        self, MakeInvoiceRequest, NwcConnection, NwcConnectionRecord, NwcRequest, PayInvoiceRequest,
+    },
+    policy::{self, ApprovalGrantDuration, KindPolicyDecision, Nip46RejectionReason},
+    routes::{self, bitcoin_wallet, settings, unlock, Loadable, Nip46Identity, Route, RouteName},
+    signing_worker::{SigningProgress, SigningWorker},
+    threshold_key::{Cosigner, CosigningRequest},
+    ui_components::{
+        icon_button, sidebar, KeyHold, PaletteColor, ShownToast, SvgIcon, Toast, ToastHistory,
+        ToastManager, ToastStatus,
+    },
+    unlock_attempts::FailedUnlockAttempts,
+    util::{format_amount, notification::show_desktop_notification, truncate_text},
+    zap::{self, ZapReceipt},
+};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Routes(routes::Message),
+
+    DbDeleteAllData,
+
+    UpdateWalletView(WalletView),
+
+    NostrModule(NostrModuleMessage),
+    UpdateNostrState(NostrState),
+    ClockSkewEstimated(ClockSkew),
+    RelayReconnectAttempted(Url, RelayReconnection),
+    SigningProgressUpdated(SigningProgress),
+    Nip55SocketStatusChanged(bool),
+    RetryNostrOutbox,
+    FlushNostrOutbox,
+
+    CopyStringToClipboard(String),
+
+    Scrolled(Viewport),
+
+    IncomingNip46Request(
+        Arc<(
+            Vec<nostr_sdk::nips::nip46::Request>,
+            PublicKey,
+            iced::futures::channel::oneshot::Sender<Nip46RequestApproval>,
+        )>,
+    ),
+    ApproveFirstIncomingNip46Request,
+    ApproveFirstIncomingNip46RequestFor(ApprovalGrantDuration),
+    RejectFirstIncomingNip46Request,
+    SetNip46AppIdentity(PublicKey, String),
+    ExpireStaleNip46Requests,
+    Nip46ApprovalKeyPressed,
+    Nip46ApprovalKeyReleased,
+    Nip46ApprovalHoldTick,
+
+    AvatarLoaded(PublicKey, Vec<u8>),
+    AvatarUnverified(PublicKey),
+    ProfileLoaded(PublicKey, Metadata),
+
+    IncomingNwcPayInvoiceRequest(PayInvoiceRequest),
+    IncomingNwcMakeInvoiceRequest(MakeInvoiceRequest),
+    IncomingNwcGetBalanceRequest {
+        request_event_id: EventId,
+        requester_public_key: PublicKey,
+    },
+    NwcConnectionsChanged,
+
+    ZapReceiptReceived(ZapReceipt),
+
+    IncomingCosigningRequest(CosigningRequest),
+    ThresholdSharesChanged,
+    AnswerCosigningRequest {
+        session_id: String,
+        approve: bool,
+    },
+
+    BackupTick,
+    RunBackup,
+    BackupFinished(Result<NaiveDateTime, String>),
+
+    RunDatabaseMaintenance,
+
+    ExchangeRateFetched(Result<ExchangeRate, String>),
+
+    AddToast(Toast),
+    CloseToast(usize),
+    ToggleToastHistory,
+
+    WindowCloseRequested(window::Id),
+    CloseWindowWhenIdle,
+    CloseWindowIfIdle,
+    MinimizeWindow,
+    ForceCloseWindow,
+    CancelCloseWindow,
+    ShutdownFinished,
+}
+
+/// How often to check whether in-flight operations have finished
+/// while waiting to close the window.
+const CLOSE_WHEN_IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long to wait for the wallet to shut down before exiting anyway,
+/// so that a stuck federation client can't keep Keystache open.
+const WALLET_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often the wait after failed password attempts is counted down on the unlock page.
+const UNLOCK_COOLDOWN_TICK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long Enter has to be held down to approve a NIP-46 request from the keyboard.
+/// A single press isn't enough, so that a stray key press can't approve anything.
+const NIP46_APPROVAL_HOLD_DURATION: Duration = Duration::from_secs(1);
+
+// Often enough for the progress ring to fill smoothly.
+// How often requests waiting for the user are checked against the expiry setting.
+// Also keeps the countdown on the request prompt up to date.
+const NIP46_REQUEST_EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+const NIP46_APPROVAL_HOLD_TICK_INTERVAL: Duration = Duration::from_millis(16);
+
+/// How often the price of bitcoin is fetched while a fiat currency is set.
+const EXCHANGE_RATE_FETCH_INTERVAL: Duration = Duration::from_secs(600);
+
+// Price sources that take longer than this to answer are tried again at the next fetch.
+const EXCHANGE_RATE_FETCH_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// How far down a page has to be scrolled, from 0 to 1, before its list loads more rows.
+const LOAD_MORE_SCROLL_THRESHOLD: f32 = 0.9;
+
+/// A request to close the window that is waiting on in-flight operations.
+#[derive(Debug, Clone, Copy)]
+struct CloseRequest {
+    window_id: window::Id,
+    // Whether to close the window as soon as all operations have finished.
+    is_waiting: bool,
+}
+
+pub struct App {
+    pub page: Route,
+    toasts: Vec<ShownToast>,
+    toast_history: ToastHistory,
+    is_toast_history_open: bool,
+    close_request_or: Option<CloseRequest>,
+    // Whether the wallet is shutting down before Keystache exits.
+    is_shutting_down: bool,
+    // Where each visited route was last scrolled to.
+    scroll_offsets: Vec<(RouteName, AbsoluteOffset)>,
+    clock_skew_or: Option<ClockSkew>,
+    clipboard: Clipboard,
+    // Invite code from a link that Keystache was opened with.
+    // The Join page is opened with it once Keystache is unlocked.
+    pending_invite_code_or: Option<InviteCode>,
+    // Times toasts, so that tests can control when they time out.
+    clock: Clock,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self {
+            page: Route::new_locked(),
+            toasts: Vec::new(),
+            toast_history: ToastHistory::default(),
+            is_toast_history_open: false,
+            close_request_or: None,
+            is_shutting_down: false,
+            scroll_offsets: Vec::new(),
+            clock_skew_or: None,
+            clipboard: Clipboard::default(),
+            pending_invite_code_or: None,
+            clock: Clock::default(),
+        }
+    }
+}
+
+/// Opens the Join Federation page with `invite_code` filled in.
+pub fn open_join_federation_page(invite_code: &InviteCode) -> Task<Message> {
+    Task::done(Message::Routes(routes::Message::Navigate(
+        RouteName::BitcoinWallet(bitcoin_wallet::SubrouteName::Add),
+    )))
+    .chain(Task::done(Message::Routes(
+        routes::Message::BitcoinWalletPage(
+            bitcoin_wallet::Message::JoinFederationInviteCodeInputChanged(invite_code.to_string()),
+        ),
+    )))
+}
+
+fn page_scrollable_id() -> scrollable::Id {
+    scrollable::Id::new("page")
+}
+
+impl App {
+    pub fn new(pending_invite_code_or: Option<InviteCode>) -> Self {
+        Self {
+            pending_invite_code_or,
+            ..Self::default()
+        }
+    }
+
+    /// The theme chosen in settings. The default theme is used while locked.
+    pub fn theme(&self) -> Theme {
+        let app_theme = self
+            .page
+            .get_connected_state()
+            .map(|connected_state| connected_state.settings.get().theme)
+            .unwrap_or_default();
+
+        match app_theme {
+            AppTheme::Dark => Theme::Dark,
+            AppTheme::Light => Theme::Light,
+        }
+    }
+
+    pub const fn is_toast_history_open(&self) -> bool {
+        self.is_toast_history_open
+    }
+
+    fn in_flight_operation_descriptions(&self) -> Vec<String> {
+        self.page
+            .get_connected_state()
+            .map(|connected_state| connected_state.in_flight_operations.descriptions())
+            .unwrap_or_default()
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<Message> {
+        match msg {
+            Message::Routes(routes_msg) => {
+                let previous_route_name = self.page.to_name();
+
+                let mut task = self.page.update(routes_msg);
+
+                let route_name = self.page.to_name();
+
+                if route_name == previous_route_name {
+                    return task;
+                }
+
+                if previous_route_name == RouteName::Unlock {
+                    if let Some(invite_code) = self.pending_invite_code_or.take() {
+                        task = task.chain(open_join_federation_page(&invite_code));
+                    }
+                }
+
+                if route_name == RouteName::Unlock {
+                    self.scroll_offsets.clear();
+
+                    // Alerts can mention keys and payments, so they shouldn't outlive the session.
+                    self.toast_history.clear();
+                    self.is_toast_history_open = false;
+                }
+
+                // Scroll back to where the user left off the last time they were on this route.
+                let offset = self
+                    .scroll_offsets
+                    .iter()
+                    .find(|(name, _)| name == &route_name)
+                    .map(|(_, offset)| *offset)
+                    .unwrap_or_default();
+
+                Task::batch([task, scrollable::scroll_to(page_scrollable_id(), offset)])
+            }
+            Message::DbDeleteAllData => {
+                if let Route::Unlock(unlock::Page {
+                    db_already_exists,
+                    has_keychain_password,
+                    failed_unlock_attempts,
+                    remaining_cooldown_or,
+                    ..
+                }) = &mut self.page
+                {
+                    if let Err(err) = Database::delete() {
+                        return Task::done(Message::AddToast(Toast {
+                            title: "Failed to delete data".to_string(),
+                            body: err.to_string(),
+                            status: ToastStatus::Bad,
+                        }));
+                    }
+                    *db_already_exists = false;
+
+                    // TODO: Log a warning if the failed attempts fail to be cleared.
+                    if let Ok(app_data_dir) = Database::app_data_dir() {
+                        let _ = FailedUnlockAttempts::clear(&app_data_dir);
+                    }
+                    *failed_unlock_attempts = FailedUnlockAttempts::default();
+                    *remaining_cooldown_or = None;
+
+                    // TODO: Log a warning if the password fails to be removed.
+                    let _ = keychain::delete_password();
+                    *has_keychain_password = false;
+
+                    // The device key is useless without the database it was bound to.
+                    // TODO: Log a warning if the device key fails to be removed.
+                    let _ = keychain::delete_device_key();
+                }
+
+                Task::none()
+            }
+            Message::UpdateWalletView(wallet_view) => {
+                let mut tasks = Vec::new();
+
+                if let Some(connected_state) = self.page.get_connected_state_mut() {
+                    if let Loadable::Loaded(previous_wallet_view) =
+                        &connected_state.loadable_wallet_view
+                    {
+                        for (federation_view, crossing) in wallet_view
+                            .get_balance_threshold_crossings(
+                                previous_wallet_view,
+                                &connected_state.balance_thresholds,
+                            )
+                        {
+                            let toast = balance_threshold_crossing_toast(federation_view, crossing);
+                            let (summary, body) = (toast.title.clone(), toast.body.clone());
+
+                            tasks.push(
+                                Task::future(async move {
+                                    // TODO: Log a warning if the notification fails to show.
+                                    let _ = show_desktop_notification(summary, body).await;
+                                })
+                                .discard(),
+                            );
+                            tasks.push(Task::done(Message::AddToast(toast)));
+                        }
+
+                        for (federation_view, announcement) in
+                            wallet_view.get_new_announcements(previous_wallet_view)
+                        {
+                            tasks.push(Task::done(Message::AddToast(Toast {
+                                title: format!(
+                                    "Announcement from {}",
+                                    federation_view
+                                        .metadata
+                                        .name_or
+                                        .clone()
+                                        .unwrap_or_else(|| "Unnamed Federation".to_string())
+                                ),
+                                body: announcement,
+                                status: ToastStatus::Neutral,
+                            })));
+                        }
+                    }
+
+                    cache_federation_metadata(connected_state, &wallet_view);
+
+                    let was_offline = connected_state.is_offline();
+                    connected_state.loadable_wallet_view = Loadable::Loaded(wallet_view.clone());
+
+                    if let Some(toast) =
+                        connectivity_change_toast_or(was_offline, connected_state.is_offline())
+                    {
+                        tasks.push(Task::done(Message::AddToast(toast)));
+                    }
+                }
+
+                if let Route::BitcoinWallet(bitcoin_wallet) = &mut self.page {
+                    tasks.push(
+                        bitcoin_wallet
+                            .update(bitcoin_wallet::Message::UpdateWalletView(wallet_view)),
+                    );
+                }
+
+                Task::batch(tasks)
+            }
+            Message::NostrModule(nostr_module_message) => {
+                let Some(connected_state) = self.page.get_connected_state_mut() else {
+                    return Task::none();
+                };
+
+                let update = connected_state.nostr_module.update(nostr_module_message);
+
+                Task::future(async move { update.await.err() }).and_then(|err| {
+                    Task::done(Message::AddToast(Toast {
+                        title: "Relay update failed".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    }))
+                })
+            }
+            Message::UpdateNostrState(nostr_state) => {
+                let Some(connected_state) = self.page.get_connected_state_mut() else {
+                    return Task::none();
+                };
+
+                let newly_connected_relays =
+                    nostr_state.newly_connected_relays(&connected_state.nostr_state);
+
+                // Events published while offline are sent as soon as any relay is back.
+                if !newly_connected_relays.is_empty() {
+                    connected_state.nostr_module.flush_outbox();
+                }
+
+                connected_state.nostr_module.resubscribe(newly_connected_relays);
+
+                let was_offline = connected_state.is_offline();
+                connected_state.nostr_state = nostr_state;
+
+                connectivity_change_toast_or(was_offline, connected_state.is_offline())
+                    .map_or_else(Task::none, |toast| Task::done(Message::AddToast(toast)))
+            }
+            Message::ClockSkewEstimated(clock_skew) => {
+                self.clock_skew_or = Some(clock_skew);
+
+                Task::none()
+            }
+            // Reconnections are shown from `NostrState`, which already keeps track of them.
+            Message::RelayReconnectAttempted(_, _) => Task::none(),
+            // The progress is read from the signing worker when rendering,
+            // so this message only needs to trigger a redraw.
+            Message::SigningProgressUpdated(_) => Task::none(),
+            Message::Nip55SocketStatusChanged(is_listening) => {
+                if let Some(connected_state) = self.page.get_connected_state_mut() {
+                    connected_state.is_nip55_socket_listening = is_listening;
+                }
+
+                Task::none()
+            }
+            Message::CopyStringToClipboard(text) => match self.clipboard.copy_text(&text) {
+                Ok(ClipboardBackend::Arboard) => Task::done(Message::AddToast(Toast {
+                    title: "Copied to clipboard".to_string(),
+                    body: "The text has been copied to your clipboard.".to_string(),
+                    status: ToastStatus::Good,
+                })),
+                Ok(backend) => Task::done(Message::AddToast(Toast {
+                    title: "Copied to clipboard".to_string(),
+                    body: format!(
+                        "The system clipboard wasn't available, so the text was copied using {backend} instead."
+                    ),
+                    status: ToastStatus::Good,
+                })),
+                Err(e) => Task::done(Message::AddToast(Toast {
+                    title: "Failed to copy to clipboard".to_string(),
+                    body: e.to_string(),
+                    status: ToastStatus::Bad,
+                })),
+            },
+            Message::Scrolled(viewport) => {
+                let route_name = self.page.to_name();
+                let offset = viewport.absolute_offset();
+
+                if let Some((_, saved_offset)) = self
+                    .scroll_offsets
+                    .iter_mut()
+                    .find(|(name, _)| name == &route_name)
+                {
+                    *saved_offset = offset;
+                } else {
+                    self.scroll_offsets.push((route_name, offset));
+                }
+
+                if viewport.relative_offset().y >= LOAD_MORE_SCROLL_THRESHOLD {
+                    return self.page.load_more_rows();
+                }
+
+                Task::none()
+            }
+            Message::IncomingNip46Request(data) => {
+                if let Some(connected_state) = self.page.get_connected_state_mut() {
+                    // TODO: Log a warning if the app fails to load.
+                    let app_identity_or = connected_state
+                        .db
+                        .get_nip46_app(&data.1)
+                        .ok()
+                        .flatten()
+                        .and_then(|app| app.identity_or);
+
+                    // Requests to sign as a keypair other than the one chosen for
+                    // the app always need the user's attention, whatever was granted.
+                    let is_other_identity = policy::is_other_identity(&data.0, app_identity_or);
+
+                    // TODO: Log a warning if the app's kind policies fail to load.
+                    let kind_decision_or = policy::apply_kind_policies(
+                        &data.0,
+                        &connected_state
+                            .db
+                            .get_nip46_kind_policies(&data.1)
+                            .unwrap_or_default(),
+                    );
+
+                    // A rule to always reject a kind applies whichever keypair is requested,
+                    // and before anything that was granted for the session.
+                    if kind_decision_or == Some(KindPolicyDecision::AlwaysReject) {
+                        return answer_nip46_request(
+                            connected_state,
+                            data,
+                            Instant::now(),
+                            Nip46RequestOutcome::AutoRejected,
+                        );
+                    }
+
+                    if !is_other_identity
+                        && (kind_decision_or == Some(KindPolicyDecision::AlwaysApprove)
+                            || connected_state
+                            .approval_grants
+                            .is_granted(&data.1, Instant::now())
+                            || should_auto_approve_public_key_read(
+                                connected_state,
+                                &data.0,
+                                &data.1,
+                            )
+                            || should_auto_approve_zap_requests(connected_state, &data.0, &data.1)
+                            || should_auto_approve_known_conversations(
+                                connected_state,
+                                &data.0,
+                                &data.1,
+                            ))
+                    {
+                        return answer_nip46_request(
+                            connected_state,
+                            data,
+                            Instant::now(),
+                            Nip46RequestOutcome::AutoApproved,
+                        );
+                    }
+
+                    let public_key = data.1;
+
+                    // TODO: Log a warning if the app's signing history fails to load.
+                    let unusual_event_kinds = connected_state
+                        .db
+                        .get_nip46_app_sign_counts_by_kind(&public_key)
+                        .map(|sign_counts_by_kind| {
+                            policy::unusual_event_kinds(&data.0, &sign_counts_by_kind)
+                        })
+                        .unwrap_or_default();
+
+                    connected_state.in_flight_nip46_requests.push_back((
+                        data,
+                        Instant::now(),
+                        unusual_event_kinds,
+                        app_identity_or,
+                    ));
+
+                    // TODO: Log a warning if the keypairs fail to load.
+                    connected_state.nip46_identities =
+                        Nip46Identity::list_all(&connected_state.db).unwrap_or_default();
+
+                    return connected_state
+                        .avatars
+                        .request([public_key], &connected_state.nostr_module);
+                }
+
+                Task::none()
+            }
+            Message::AvatarLoaded(public_key, picture_bytes) => {
+                if let Some(connected_state) = self.page.get_connected_state_mut() {
+                    connected_state.avatars.insert(public_key, picture_bytes);
+                }
+
+                Task::none()
+            }
+            Message::AvatarUnverified(public_key) => {
+                if let Some(connected_state) = self.page.get_connected_state_mut() {
+                    connected_state.avatars.mark_unverified(public_key);
+                }
+
+                Task::none()
+            }
+            Message::ProfileLoaded(public_key, metadata) => {
+                if let Some(connected_state) = self.page.get_connected_state_mut() {
+                    connected_state.profiles.insert(public_key, metadata);
+                }
+
+                Task::none()
+            }
+            Message::ApproveFirstIncomingNip46Request => {
+                if let Some(connected_state) = self.page.get_connected_state_mut() {
+                    if connected_state.is_first_nip46_request_for_other_identity() {
+                        connected_state.spend_nip46_approval_hold();
+
+                        return other_identity_toast();
+                    }
+
+                    if let Some((req, received_time, _, _)) =
+                        connected_state.in_flight_nip46_requests.pop_front()
+                    {
+                        connected_state.spend_nip46_approval_hold();
+
+                        return answer_nip46_request(
+                            connected_state,
+                            req,
+                            received_time,
+                            Nip46RequestOutcome::Approved,
+                        );
+                    }
+                }
+
+                Task::none()
+            }
+            Message::ApproveFirstIncomingNip46RequestFor(duration) => {
+                let mut tasks = Vec::new();
+
+                if let Some(connected_state) = self.page.get_connected_state_mut() {
+                    if connected_state.is_first_nip46_request_for_other_identity() {
+                        return other_identity_toast();
+                    }
+
+                    if let Some((req, received_time, _, _)) =
+                        connected_state.in_flight_nip46_requests.pop_front()
+                    {
+                        let public_key = req.1;
+
+                        connected_state.spend_nip46_approval_hold();
+
+                        tasks.push(answer_nip46_request(
+                            connected_state,
+                            req,
+                            received_time,
+                            Nip46RequestOutcome::Approved,
+                        ));
+
+                        connected_state
+                            .approval_grants
+                            .grant(public_key, duration, Instant::now());
+
+                        // Requests that were already queued are covered by the new grant too,
+                        // except those to sign as a keypair other than the app's.
+                        let (granted_requests, remaining_requests) = connected_state
+                            .in_flight_nip46_requests
+                            .drain(..)
+                            .partition(|(req, _, _, app_identity_or)| {
+                                req.1 == public_key
+                                    && !policy::is_other_identity(&req.0, *app_identity_or)
+                            });
+
+                        connected_state.in_flight_nip46_requests = remaining_requests;
+
+                        for (req, received_time, _, _) in granted_requests {
+                            tasks.push(answer_nip46_request(
+                                connected_state,
+                                req,
+                                received_time,
+                                Nip46RequestOutcome::AutoApproved,
+                            ));
+                        }
+                    }
+                }
+
+                Task::batch(tasks)
+            }
+            Message::RejectFirstIncomingNip46Request => {
+                if let Some(connected_state) = self.page.get_connected_state_mut() {
+                    if let Some((req, received_time, _, _)) =
+                        connected_state.in_flight_nip46_requests.pop_front()
+                    {
+                        connected_state.spend_nip46_approval_hold();
+
+                        return answer_nip46_request(
+                            connected_state,
+                            req,
+                            received_time,
+                            Nip46RequestOutcome::Rejected,
+                        );
+                    }
+                }
+
+                Task::none()
+            }
+            Message::SetNip46AppIdentity(public_key, identity_npub) => {
+                let Some(connected_state) = self.page.get_connected_state_mut() else {
+                    return Task::none();
+                };
+
+                let result = PublicKey::from_bech32(&identity_npub)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|identity| {
+                        connected_state
+                            .db
+                            .save_nip46_app_identity(&public_key, Some(&identity))
+                            .map(|()| identity)
+                    });
+
+                match result {
+                    Ok(identity) => {
+                        for (req, _, _, app_identity_or) in
+                            &mut connected_state.in_flight_nip46_requests
+                        {
+                            if req.1 == public_key {
+                                *app_identity_or = Some(identity);
+                            }
+                        }
+
+                        Task::none()
+                    }
+                    Err(err) => Task::done(Message::AddToast(Toast {
+                        title: "Failed to switch keypair".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::ExpireStaleNip46Requests => {
+                let Some(connected_state) = self.page.get_connected_state_mut() else {
+                    return Task::none();
+                };
+
+                let nip46_request_expiry = connected_state.settings.get().nip46_request_expiry;
+
+                let (expired_requests, remaining_requests): (VecDeque<_>, VecDeque<_>) =
+                    connected_state
+                        .in_flight_nip46_requests
+                        .drain(..)
+                        .partition(|(_, received_time, _, _)| {
+                            received_time.elapsed() >= nip46_request_expiry
+                        });
+
+                connected_state.in_flight_nip46_requests = remaining_requests;
+
+                if expired_requests.is_empty() {
+                    return Task::none();
+                }
+
+                // Requests are queued in the order they arrived, so the request on
+                // screen is always among the expired ones. Any hold on Enter was meant
+                // for it, and shouldn't carry over to the next request.
+                connected_state.spend_nip46_approval_hold();
+
+                let expired_count = expired_requests.len();
+
+                let mut tasks: Vec<_> = expired_requests
+                    .into_iter()
+                    .map(|(req, received_time, _, _)| {
+                        answer_nip46_request(
+                            connected_state,
+                            req,
+                            received_time,
+                            Nip46RequestOutcome::Expired,
+                        )
+                    })
+                    .collect();
+
+                let (plural_suffix, verb) = if expired_count == 1 {
+                    ("", "was")
+                } else {
+                    ("s", "were")
+                };
+
+                tasks.push(Task::done(Message::AddToast(Toast {
+                    title: "Signing request expired".to_string(),
+                    body: format!(
+                        "{expired_count} unanswered request{plural_suffix} {verb} rejected after waiting {} seconds. See Settings > Connected Apps for details.",
+                        nip46_request_expiry.as_secs()
+                    ),
+                    status: ToastStatus::Neutral,
+                })));
+
+                Task::batch(tasks)
+            }
+            Message::Nip46ApprovalKeyPressed => {
+                let now = self.clock.instant();
+
+                if let Some(connected_state) = self.page.get_connected_state_mut() {
+                    // Held keys repeat, so only the first press starts a hold.
+                    if connected_state.nip46_approval_hold_or.is_none()
+                        && !connected_state.in_flight_nip46_requests.is_empty()
+                    {
+                        connected_state.nip46_approval_hold_or = Some(KeyHold::new(now));
+                    }
+                }
+
+                Task::none()
+            }
+            Message::Nip46ApprovalKeyReleased => {
+                if let Some(connected_state) = self.page.get_connected_state_mut() {
+                    connected_state.nip46_approval_hold_or = None;
+                }
+
+                Task::none()
+            }
+            Message::Nip46ApprovalHoldTick => {
+                let now = self.clock.instant();
+
+                if let Some(nip46_approval_hold) = self
+                    .page
+                    .get_connected_state_mut()
+                    .and_then(|connected_state| connected_state.nip46_approval_hold_or.as_mut())
+                {
+                    if !nip46_approval_hold.is_complete()
+                        && nip46_approval_hold.update(now, NIP46_APPROVAL_HOLD_DURATION)
+                    {
+                        return Task::done(Message::ApproveFirstIncomingNip46Request);
+                    }
+                }
+
+                Task::none()
+            }
+            Message::IncomingNwcPayInvoiceRequest(request) => {
+                let Some(connected_state) = self.page.get_connected_state() else {
+                    return Task::none();
+                };
+
+                let Some(record) =
+                    get_active_nwc_connection(connected_state, &request.requester_public_key)
+                else {
+                    return Task::none();
+                };
+
+                // Requests that the app can't afford are declined right away,
+                // rather than cluttering the inbox with requests that can't be approved.
+                // TODO: Log a warning if the budget fails to load.
+                let amount =
+                    Amount::from_msats(request.invoice.amount_milli_satoshis().unwrap_or_default());
+                if nwc::would_exceed_budget(&connected_state.db, &record.connection, amount)
+                    .unwrap_or(true)
+                {
+                    let nostr_module = connected_state.nostr_module.clone();
+                    let request_event_id = request.request_event_id;
+                    let connection = record.connection.clone();
+
+                    return Task::future(async move {
+                        // TODO: Log a warning if the response fails to send.
+                        let _ = nwc::send_response(
+                            &nostr_module,
+                            &connection,
+                            request_event_id,
+                            &nwc::error_response(
+                                Method::PayInvoice,
+                                ErrorCode::QuotaExceeded,
+                                "The payment would exceed this connection's budget",
+                            ),
+                        )
+                        .await;
+                    })
+                    .discard()
+                    .chain(Task::done(Message::AddToast(Toast {
+                        title: "Payment request declined".to_string(),
+                        body: format!(
+                            "{} requested a payment of {}, which would exceed its budget.",
+                            record.connection.name,
+                            format_amount(amount)
+                        ),
+                        status: ToastStatus::Bad,
+                    })));
+                }
+
+                // Requests are persisted so they can still be approved
+                // later, even if they arrive while the inbox isn't open.
+                match connected_state.db.save_payment_request(&request) {
+                    Ok(true) => {
+                        let toast_task = Task::done(Message::AddToast(new_payment_request_toast(
+                            &request,
+                            &record.connection.name,
+                        )));
+
+                        if let Route::BitcoinWallet(bitcoin_wallet) = &mut self.page {
+                            Task::batch([
+                                toast_task,
+                                bitcoin_wallet
+                                    .update(bitcoin_wallet::Message::PaymentRequestReceived),
+                            ])
+                        } else {
+                            toast_task
+                        }
+                    }
+                    // The request was already in the inbox.
+                    Ok(false) => Task::none(),
+                    Err(err) => Task::done(Message::AddToast(Toast {
+                        title: "Failed to save payment request".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::IncomingNwcMakeInvoiceRequest(request) => {
+                let Some(connected_state) = self.page.get_connected_state() else {
+                    return Task::none();
+                };
+
+                let Some(record) =
+                    get_active_nwc_connection(connected_state, &request.requester_public_key)
+                else {
+                    return Task::none();
+                };
+
+                make_invoice_for_app(connected_state, record.connection, request)
+            }
+            Message::IncomingNwcGetBalanceRequest {
+                request_event_id,
+                requester_public_key,
+            } => {
+                let Some(connected_state) = self.page.get_connected_state() else {
+                    return Task::none();
+                };
+
+                let Some(NwcConnectionRecord { connection, .. }) =
+                    get_active_nwc_connection(connected_state, &requester_public_key)
+                else {
+                    return Task::none();
+                };
+
+                let Some(wallet_view) = connected_state.loadable_wallet_view.as_ref_option() else {
+                    return Task::none();
+                };
+
+                // Apps see the balance of the default federation, or
+                // the total balance if no default federation is set.
+                // TODO: Log a warning if the default federation fails to load.
+                let balance = match connected_state.db.get_default_federation().ok().flatten() {
+                    Some(default_federation_id) => wallet_view
+                        .federations
+                        .get(&default_federation_id)
+                        .map(|federation_view| federation_view.balance)
+                        .unwrap_or_default(),
+                    None => Amount::from_msats(
+                        wallet_view
+                            .federations
+                            .values()
+                            .map(|federation_view| federation_view.balance.msats)
+                            .sum(),
+                    ),
+                };
+
+                let nostr_module = connected_state.nostr_module.clone();
+
+                Task::future(async move {
+                    // TODO: Log a warning if the response fails to send.
+                    let _ = nwc::send_response(
+                        &nostr_module,
+                        &connection,
+                        request_event_id,
+                        &nwc::get_balance_response(balance),
+                    )
+                    .await;
+                })
+                .discard()
+            }
+            Message::NwcConnectionsChanged => {
+                if let Some(connected_state) = self.page.get_connected_state_mut() {
+                    connected_state.reload_nwc_connections();
+                }
+
+                Task::none()
+            }
+            Message::ZapReceiptReceived(receipt) => {
+                let Some(connected_state) = self.page.get_connected_state() else {
+                    return Task::none();
+                };
+
+                // Receipts are picked up again whenever the subscription restarts,
+                // so only newly saved ones are shown.
+                // TODO: Log a warning if the receipt fails to save.
+                if !connected_state
+                    .db
+                    .save_zap_receipt(&receipt)
+                    .unwrap_or_default()
+                {
+                    return Task::none();
+                }
+
+                let sender = receipt.sender_public_key_or.map_or_else(
+                    || "someone anonymous".to_string(),
+                    |public_key| {
+                        public_key.to_bech32().map_or_else(
+                            |_| public_key.to_string(),
+                            |npub| truncate_text(&npub, 23, true),
+                        )
+                    },
+                );
+
+                let zapped = if receipt.zapped_event_id_or.is_some() {
+                    "one of your notes"
+                } else {
+                    "your profile"
+                };
+
+                let mut body = format!(
+                    "{} from {sender} for {zapped}.",
+                    format_amount(receipt.amount)
+                );
+
+                // TODO: Log a warning if the payment fails to load.
+                if let Some(federation_id) = connected_state
+                    .db
+                    .get_incoming_payment_id_for_invoice(&receipt.bolt11_invoice)
+                    .ok()
+                    .flatten()
+                    .and_then(|payment_id| connected_state.db.get_payment(payment_id).ok())
+                    .map(|payment| payment.federation_id)
+                {
+                    let federation_name = connected_state
+                        .loadable_wallet_view
+                        .as_ref_option()
+                        .and_then(|wallet_view| wallet_view.federations.get(&federation_id))
+                        .and_then(|federation_view| federation_view.metadata.name_or.clone())
+                        .unwrap_or_else(|| truncate_text(&federation_id.to_string(), 21, true));
+
+                    body.push_str(&format!(" Paid into {federation_name}."));
+                }
+
+                if !receipt.comment.is_empty() {
+                    body.push_str(&format!(" \"{}\"", receipt.comment));
+                }
+
+                Task::done(Message::AddToast(Toast {
+                    title: "Zap received".to_string(),
+                    body,
+                    status: ToastStatus::Good,
+                }))
+            }
+            Message::ThresholdSharesChanged => {
+                if let Some(connected_state) = self.page.get_connected_state_mut() {
+                    connected_state.reload_cosigner_shares();
+                }
+
+                Task::none()
+            }
+            Message::IncomingCosigningRequest(request) => {
+                let Some(connected_state) = self.page.get_connected_state_mut() else {
+                    return Task::none();
+                };
+
+                let body = format!(
+                    "A kind {} event needs your approval to be signed as {}. Answer it under Keys > Threshold Keys.",
+                    request.event.kind,
+                    request.public_key.to_bech32().map_or_else(
+                        |_| request.public_key.to_string(),
+                        |npub| truncate_text(&npub, 23, true),
+                    )
+                );
+
+                connected_state.cosigning_requests.push(request);
+
+                Task::done(Message::AddToast(Toast {
+                    title: "Cosigning request".to_string(),
+                    body,
+                    status: ToastStatus::Neutral,
+                }))
+            }
+            Message::AnswerCosigningRequest {
+                session_id,
+                approve,
+            } => {
+                let Some(connected_state) = self.page.get_connected_state_mut() else {
+                    return Task::none();
+                };
+
+                let Some(index) = connected_state
+                    .cosigning_requests
+                    .iter()
+                    .position(|request| request.session_id == session_id)
+                else {
+                    return Task::none();
+                };
+
+                let request = connected_state.cosigning_requests.remove(index);
+
+                Task::future(async move {
+                    let result = if approve {
+                        request.approve().await
+                    } else {
+                        request.refuse().await
+                    };
+
+                    Message::AddToast(match result {
+                        Ok(()) if approve => Toast {
+                            title: "Approved cosigning request".to_string(),
+                            body: "Keystache will help sign the event once enough cosigners have approved it.".to_string(),
+                            status: ToastStatus::Good,
+                        },
+                        Ok(()) => Toast {
+                            title: "Refused cosigning request".to_string(),
+                            body: "The event won't be signed with this device's share.".to_string(),
+                            status: ToastStatus::Good,
+                        },
+                        Err(err) => Toast {
+                            title: "Failed to answer cosigning request".to_string(),
+                            body: err.to_string(),
+                            status: ToastStatus::Bad,
+                        },
+                    })
+                })
+            }
+            Message::ExchangeRateFetched(result) => {
+                let Some(connected_state) = self.page.get_connected_state_mut() else {
+                    return Task::none();
+                };
+
+                // TODO: Log a warning if the exchange rate fails to be fetched.
+                if let Ok(exchange_rate) = result {
+                    // TODO: Log a warning if the exchange rate fails to be saved.
+                    let _ = connected_state
+                        .db
+                        .save_exchange_rate(&exchange_rate, Utc::now().naive_utc());
+
+                    connected_state.exchange_rate_or = Some(exchange_rate);
+                }
+
+                Task::none()
+            }
+            Message::BackupTick => {
+                let Some(connected_state) = self.page.get_connected_state() else {
+                    return Task::none();
+                };
+
+                // TODO: Log a warning if the backup status fails to load.
+                if connected_state
+                    .db
+                    .get_backup_status()
+                    .unwrap_or_default()
+                    .is_backup_due(Utc::now().naive_utc())
+                {
+                    Task::done(Message::RunBackup)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::RunBackup => {
+                let Some(connected_state) = self.page.get_connected_state() else {
+                    return Task::none();
+                };
+
+                let backup_settings = match connected_state.db.get_backup_settings() {
+                    Ok(backup_settings) => backup_settings,
+                    Err(err) => return Task::done(Message::BackupFinished(Err(err.to_string()))),
+                };
+
+                let Some(directory) = backup_settings.directory_or else {
+                    return Task::none();
+                };
+
+                let db = connected_state.db.clone();
+                let wallet = connected_state.wallet.clone();
+                let backup_time = self.clock.now_utc();
+                let in_flight_operation = connected_state
+                    .in_flight_operations
+                    .start("Making a backup");
+
+                Task::perform(
+                    async move {
+                        let _in_flight_operation = in_flight_operation;
+
+                        backup::run_backup(
+                            db,
+                            wallet,
+                            directory,
+                            backup_settings.rotation_count,
+                            backup_time,
+                        )
+                        .await
+                        .map(|()| backup_time)
+                    },
+                    |result| Message::BackupFinished(result.map_err(|err| err.to_string())),
+                )
+            }
+            Message::RunDatabaseMaintenance => {
+                let Some(connected_state) = self.page.get_connected_state() else {
+                    return Task::none();
+                };
+
+                match connected_state.db.run_maintenance() {
+                    Ok(()) => Task::none(),
+                    Err(err) => Task::done(Message::AddToast(Toast {
+                        title: "Database maintenance failed".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::RetryNostrOutbox => {
+                if let Some(connected_state) = self.page.get_connected_state() {
+                    connected_state.nostr_module.retry_outbox();
+                }
+
+                Task::none()
+            }
+            Message::FlushNostrOutbox => {
+                if let Some(connected_state) = self.page.get_connected_state() {
+                    connected_state.nostr_module.flush_outbox();
+                }
+
+                Task::none()
+            }
+            Message::BackupFinished(result) => {
+                let Some(connected_state) = self.page.get_connected_state() else {
+                    return Task::none();
+                };
+
+                let mut tasks = Vec::new();
+
+                // TODO: Log a warning if the backup result fails to save.
+                if let Some(toast) =
+                    Toast::database_busy_or(&connected_state.db.save_backup_result(&result))
+                {
+                    tasks.push(Task::done(Message::AddToast(toast)));
+                }
+
+                if let Err(err) = result {
+                    tasks.push(Task::done(Message::AddToast(Toast {
+                        title: "Backup failed".to_string(),
+                        body: err,
+                        status: ToastStatus::Bad,
+                    })));
+                }
+
+                if let Route::Settings(settings) = &mut self.page {
+                    tasks.push(settings.update(settings::Message::BackupStatusChanged));
+                }
+
+                Task::batch(tasks)
+            }
+            Message::AddToast(toast) => {
+                self.toast_history.push(toast.clone(), self.clock.now_utc());
+                ShownToast::push_deduplicated(&mut self.toasts, toast, self.clock.instant());
+
+                Task::none()
+            }
+            Message::CloseToast(index) => {
+                self.toasts.remove(index);
+
+                Task::none()
+            }
+            Message::ToggleToastHistory => {
+                self.is_toast_history_open = !self.is_toast_history_open;
+
+                Task::none()
+            }
+            Message::WindowCloseRequested(window_id) => {
+                if self.in_flight_operation_descriptions().is_empty() {
+                    return self.shut_down_and_exit();
+                }
+
+                self.close_request_or = Some(CloseRequest {
+                    window_id,
+                    is_waiting: false,
+                });
+
+                Task::none()
+            }
+            Message::CloseWindowWhenIdle => {
+                if let Some(close_request) = &mut self.close_request_or {
+                    close_request.is_waiting = true;
+                }
+
+                Task::done(Message::CloseWindowIfIdle)
+            }
+            Message::CloseWindowIfIdle => {
+                if self
+                    .close_request_or
+                    .is_some_and(|close_request| close_request.is_waiting)
+                    && self.in_flight_operation_descriptions().is_empty()
+                {
+                    self.shut_down_and_exit()
+                } else {
+                    Task::none()
+                }
+            }
+            Message::MinimizeWindow => {
+                // Keystache has no tray icon, so minimizing is the closest it
+                // can get to running in the background.
+                self.close_request_or
+                    .take()
+                    .map_or_else(Task::none, |close_request| {
+                        window::minimize(close_request.window_id, true)
+                    })
+            }
+            Message::ForceCloseWindow => self.shut_down_and_exit(),
+            Message::CancelCloseWindow => {
+                self.close_request_or = None;
+
+                Task::none()
+            }
+            Message::ShutdownFinished => iced::exit(),
+        }
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let Self { page, .. } = self;
+
+        let page_view = if self.is_shutting_down {
+            text("Closing Keystache...").size(25).into()
+        } else {
+            match self.close_request_or {
+                Some(close_request) => self.close_request_view(close_request).into(),
+                None => page.view(),
+            }
+        };
+
+        let mut content: Element<Message> = Element::new(
+            scrollable(
+                container(column![page_view].spacing(20).padding(20)).center_x(Length::Fill),
+            )
+            .id(page_scrollable_id())
+            .on_scroll(Message::Scrolled),
+        );
+
+        if let Some(clock_skew) = self
+            .clock_skew_or
+            .filter(|clock_skew| clock_skew.exceeds_warning_threshold())
+        {
+            content = Element::new(column![
+                container(
+                    text(format!(
+                        "Your system clock appears to be {clock_skew} compared to Nostr relays. This can cause signed events to be rejected and invoices to expire unexpectedly. Please sync your clock."
+                    ))
+                    .style(text::danger)
+                )
+                .padding(10)
+                .width(Length::Fill)
+                .style(container::rounded_box),
+                content
+            ]);
+        }
+
+        if let Some(connected_state) = page
+            .get_connected_state()
+            .filter(|connected_state| connected_state.is_offline())
+        {
+            let queued_event_count = connected_state.nostr_module.outbox_len();
+
+            let queued_events_description = if queued_event_count == 0 {
+                String::new()
+            } else {
+                format!(
+                    " {queued_event_count} outgoing events will be sent once a relay reconnects."
+                )
+            };
+
+            content = Element::new(column![
+                container(
+                    text(format!(
+                        "You appear to be offline. Every relay and federation has lost its connection, so payments and joining federations are paused.{queued_events_description}"
+                    ))
+                    .style(text::danger)
+                )
+                .padding(10)
+                .width(Length::Fill)
+                .style(container::rounded_box),
+                content
+            ]);
+        } else if let Some(queued_event_count) = page
+            .get_connected_state()
+            .map(|connected_state| connected_state.nostr_module.outbox_len())
+            .filter(|queued_event_count| *queued_event_count > 0)
+        {
+            content = Element::new(column![
+                container(
+                    row![
+                        text(format!(
+                            "{queued_event_count} outgoing events haven't been sent yet. Relays are asked again every so often, and as soon as one reconnects."
+                        ))
+                        .width(Length::Fill),
+                        icon_button("Retry Now", SvgIcon::Send, PaletteColor::Primary)
+                            .on_press(Message::FlushNostrOutbox),
+                    ]
+                    .spacing(10)
+                    .align_y(Alignment::Center)
+                )
+                .padding(10)
+                .width(Length::Fill)
+                .style(container::rounded_box),
+                content
+            ]);
+        }
+
+        if let Some(signing_progress) = page
+            .get_connected_state()
+            .map(|connected_state| connected_state.signing_worker.progress())
+            .filter(SigningProgress::is_in_progress)
+        {
+            // TODO: Remove this clippy allow.
+            #[allow(clippy::cast_precision_loss)]
+            let (recorded_event_count, total_event_count) = (
+                signing_progress.recorded_event_count as f32,
+                signing_progress.total_event_count as f32,
+            );
+
+            content = Element::new(column![
+                container(
+                    column![
+                        text(format!(
+                            "Saving signing history: {}/{} events",
+                            signing_progress.recorded_event_count,
+                            signing_progress.total_event_count
+                        )),
+                        progress_bar(0.0..=total_event_count, recorded_event_count).height(10),
+                    ]
+                    .spacing(5)
+                )
+                .padding(10)
+                .width(Length::Fill)
+                .style(container::rounded_box),
+                content
+            ]);
+        }
+
+        if page.to_name() != RouteName::Unlock {
+            content = if self.is_toast_history_open {
+                Element::new(row![
+                    sidebar(self),
+                    content,
+                    scrollable(self.toast_history.view().padding(20))
+                ])
+            } else {
+                Element::new(row![sidebar(self), content])
+            };
+        };
+
+        let content: Element<_, _, _> = container(content).center_y(Length::Fill).into();
+        let toast_manager: Element<_, _, _> =
+            ToastManager::new(&self.toasts, self.clock.clone(), Message::CloseToast).into();
+
+        stack![content, toast_manager].into()
+    }
+
+    /// Shuts down the wallet, then exits. Exiting right away could leave
+    /// the federation clients' databases in an inconsistent state.
+    fn shut_down_and_exit(&mut self) -> Task<Message> {
+        if self.is_shutting_down {
+            return Task::none();
+        }
+
+        let Some(wallet) = self
+            .page
+            .get_connected_state()
+            .map(|connected_state| connected_state.wallet.clone())
+        else {
+            return iced::exit();
+        };
+
+        self.is_shutting_down = true;
+
+        Task::perform(
+            async move {
+                // TODO: Log a warning if the wallet fails to shut down in time.
+                let _ = tokio::time::timeout(WALLET_SHUTDOWN_TIMEOUT, wallet.shutdown()).await;
+            },
+            |()| Message::ShutdownFinished,
+        )
+    }
+
+    fn close_request_view(&self, close_request: CloseRequest) -> Column<Message> {
+        let mut column = Column::new()
+            .push(text("Close Keystache?").size(25))
+            .push(text(
+                "These operations haven't finished yet. If Keystache closes now, their outcome may be lost.",
+            ))
+            .spacing(10)
+            .align_x(Alignment::Center);
+
+        for description in self.in_flight_operation_descriptions() {
+            column = column.push(text(format!("• {description}")));
+        }
+
+        if close_request.is_waiting {
+            column = column.push(text(
+                "Keystache will close once these operations have finished.",
+            ));
+        }
+
+        column.push(
+            row![
+                icon_button("Close When Finished", SvgIcon::Lock, PaletteColor::Primary)
+                    .on_press_maybe(
+                        (!close_request.is_waiting).then_some(Message::CloseWindowWhenIdle)
+                    ),
+                icon_button("Minimize", SvgIcon::ArrowDownward, PaletteColor::Background)
+                    .on_press(Message::MinimizeWindow),
+                icon_button("Close Now", SvgIcon::Close, PaletteColor::Danger)
+                    .on_press(Message::ForceCloseWindow),
+                icon_button("Cancel", SvgIcon::ArrowBack, PaletteColor::Background)
+                    .on_press(Message::CancelCloseWindow),
+            ]
+            .spacing(10),
+        )
+    }
+
+    pub fn subscription(&self) -> iced::Subscription<Message> {
+        let close_requests_sub = window::close_requests().map(Message::WindowCloseRequested);
+
+        let Some(connected_state) = self.page.get_connected_state() else {
+            // Counts down the wait after failed password attempts.
+            if let Route::Unlock(unlock::Page {
+                remaining_cooldown_or: Some(_),
+                ..
+            }) = &self.page
+            {
+                return iced::Subscription::batch([
+                    close_requests_sub,
+                    iced::time::every(UNLOCK_COOLDOWN_TICK_INTERVAL).map(|_| {
+                        Message::Routes(routes::Message::UnlockPage(unlock::Message::CooldownTick))
+                    }),
+                ]);
+            }
+
+            return close_requests_sub;
+        };
+
+        let wallet = connected_state.wallet.clone();
+
+        let db = connected_state.db.clone();
+
+        let wallet_sub = iced::Subscription::run_with_id(
+            std::any::TypeId::of::<Wallet>(),
+            // We're wrapping `stream` in a `stream!` macro to make it lazy (meaning `stream` isn't
+            // created unless the outer `stream!` is actually used). This is necessary because the
+            // outer `stream!` is created on every update, but will only be polled if the subscription
+            // ID is new.
+            async_stream::stream! {
+                let mut stream = wallet.get_update_stream().map(Message::UpdateWalletView);
+
+                while let Some(msg) = stream.next().await {
+                    yield msg;
+                }
+            },
+        );
+
+        // The socket path is part of the ID, so that
+        // the server is restarted when the path changes.
+        let nip55_socket_path = connected_state.settings.get().nip55_socket_path;
+
+        let nip46_sub = iced::Subscription::run_with_id(
+            (
+                std::any::TypeId::of::<Nip46OverNip55ServerStream>(),
+                nip55_socket_path.clone(),
+            ),
+            // We're wrapping `stream` in a `stream!` macro to make it lazy (meaning `stream` isn't
+            // created unless the outer `stream!` is actually used). This is necessary because the
+            // outer `stream!` is created on every update, but will only be polled if the subscription
+            // ID is new.
+            async_stream::stream! {
+                // TODO: Log a warning if the server fails to start.
+                let Ok(server_stream) = Nip46OverNip55ServerStream::start(&nip55_socket_path, db) else {
+                    yield Message::Nip55SocketStatusChanged(false);
+                    return;
+                };
+
+                yield Message::Nip55SocketStatusChanged(true);
+
+                let mut stream = server_stream
+                    .map(|(request_list, public_key, response_sender)| {
+                        Message::IncomingNip46Request(Arc::new((
+                            request_list,
+                            public_key,
+                            response_sender,
+                        )))
+                    });
+
+                while let Some(msg) = stream.next().await {
+                    yield msg;
+                }
+
+                // The server only stops once its socket has closed.
+                yield Message::Nip55SocketStatusChanged(false);
+            },
+        );
+
+        let nostr_module = connected_state.nostr_module.clone();
+        let nostr_sub = iced::Subscription::run_with_id(
+            std::any::TypeId::of::<NostrState>(),
+            // We're wrapping `stream` in a `stream!` macro to make it lazy (meaning `stream` isn't
+            // created unless the outer `stream!` is actually used). This is necessary because the
+            // outer `stream!` is created on every update, but will only be polled if the subscription
+            // ID is new.
+            async_stream::stream! {
+                let mut stream =
+                    Box::pin(nostr_module.state_stream().map(Message::UpdateNostrState));
+
+                while let Some(msg) = stream.next().await {
+                    yield msg;
+                }
+            },
+        );
+
+        let nostr_module = connected_state.nostr_module.clone();
+        let clock_skew_sub = iced::Subscription::run_with_id(
+            std::any::TypeId::of::<ClockSkew>(),
+            // See `nostr_sub` for why this is wrapped in `stream!`.
+            async_stream::stream! {
+                let mut stream =
+                    Box::pin(nostr_module.clock_skew_stream().map(Message::ClockSkewEstimated));
+
+                while let Some(msg) = stream.next().await {
+                    yield msg;
+                }
+            },
+        );
+
+        let nostr_module = connected_state.nostr_module.clone();
+        let relay_reconnect_sub = iced::Subscription::run_with_id(
+            std::any::TypeId::of::<RelayReconnection>(),
+            // See `nostr_sub` for why this is wrapped in `stream!`.
+            async_stream::stream! {
+                let mut stream = Box::pin(nostr_module.reconnect_stream().map(
+                    |(url, relay_reconnection)| {
+                        Message::RelayReconnectAttempted(url, relay_reconnection)
+                    },
+                ));
+
+                while let Some(msg) = stream.next().await {
+                    yield msg;
+                }
+            },
+        );
+
+        let signing_worker = connected_state.signing_worker.clone();
+        let signing_progress_sub = iced::Subscription::run_with_id(
+            std::any::TypeId::of::<SigningWorker>(),
+            // See `nostr_sub` for why this is wrapped in `stream!`.
+            async_stream::stream! {
+                let mut stream = Box::pin(
+                    signing_worker
+                        .progress_stream()
+                        .map(Message::SigningProgressUpdated),
+                );
+
+                while let Some(msg) = stream.next().await {
+                    yield msg;
+                }
+            },
+        );
+
+        let mut subscriptions = vec![
+            close_requests_sub,
+            nip46_sub,
+            wallet_sub,
+            nostr_sub,
+            clock_skew_sub,
+            relay_reconnect_sub,
+            signing_progress_sub,
+        ];
+
+        if !connected_state.in_flight_nip46_requests.is_empty() {
+            subscriptions.push(
+                iced::time::every(NIP46_REQUEST_EXPIRY_CHECK_INTERVAL)
+                    .map(|_| Message::ExpireStaleNip46Requests),
+            );
+
+            subscriptions.push(keyboard::on_key_press(|key, modifiers| {
+                (key == keyboard::Key::Named(keyboard::key::Named::Enter) && modifiers.is_empty())
+                    .then_some(Message::Nip46ApprovalKeyPressed)
+            }));
+        }
+
+        if let Some(nip46_approval_hold) = connected_state.nip46_approval_hold_or {
+            subscriptions.push(keyboard::on_key_release(|key, _modifiers| {
+                (key == keyboard::Key::Named(keyboard::key::Named::Enter))
+                    .then_some(Message::Nip46ApprovalKeyReleased)
+            }));
+
+            if !nip46_approval_hold.is_complete() {
+                subscriptions.push(
+                    iced::time::every(NIP46_APPROVAL_HOLD_TICK_INTERVAL)
+                        .map(|_| Message::Nip46ApprovalHoldTick),
+                );
+            }
+        }
+
+        if let Route::BitcoinWallet(bitcoin_wallet) = &self.page {
+            subscriptions.push(bitcoin_wallet.subscription());
+        }
+
+        if self
+            .close_request_or
+            .is_some_and(|close_request| close_request.is_waiting)
+        {
+            subscriptions.push(
+                iced::time::every(CLOSE_WHEN_IDLE_CHECK_INTERVAL)
+                    .map(|_| Message::CloseWindowIfIdle),
+            );
+        }
+
+        if !connected_state.zap_recipient_public_keys.is_empty() {
+            let nostr_module = connected_state.nostr_module.clone();
+            let zap_recipient_public_keys = connected_state.zap_recipient_public_keys.clone();
+
+            subscriptions.push(iced::Subscription::run_with_id(
+                // The keys are part of the ID so that the subscription
+                // restarts whenever a key is added or removed.
+                (
+                    std::any::TypeId::of::<ZapReceipt>(),
+                    zap_recipient_public_keys.clone(),
+                ),
+                // We're wrapping `stream` in a `stream!` macro to make it lazy (meaning `stream` isn't
+                // created unless the outer `stream!` is actually used). This is necessary because the
+                // outer `stream!` is created on every update, but will only be polled if the subscription
+                // ID is new.
+                async_stream::stream! {
+                    let mut stream = Box::pin(
+                        zap::receipt_stream(nostr_module, zap_recipient_public_keys)
+                            .map(Message::ZapReceiptReceived),
+                    );
+
+                    while let Some(msg) = stream.next().await {
+                        yield msg;
+                    }
+                },
+            ));
+        }
+
+        subscriptions.push(
+            iced::time::every(DATABASE_MAINTENANCE_INTERVAL)
+                .map(|_| Message::RunDatabaseMaintenance),
+        );
+
+        if connected_state.nostr_module.outbox_len() > 0 {
+            subscriptions.push(
+                iced::time::every(OUTBOX_RETRY_CHECK_INTERVAL).map(|_| Message::RetryNostrOutbox),
+            );
+        }
+
+        if connected_state.backup_settings.directory_or.is_some() {
+            subscriptions
+                .push(iced::time::every(BACKUP_CHECK_INTERVAL).map(|_| Message::BackupTick));
+        }
+
+        let settings = connected_state.settings.get();
+
+        if let Some(fiat_currency) = settings.fiat_currency_or {
+            let price_source = settings.price_source;
+
+            subscriptions.push(iced::Subscription::run_with_id(
+                // Restarted whenever the currency or source changes, so that the new
+                // price is fetched straight away rather than at the next interval.
+                (
+                    std::any::TypeId::of::<ExchangeRate>(),
+                    fiat_currency,
+                    price_source,
+                ),
+                async_stream::stream! {
+                    loop {
+                        yield Message::ExchangeRateFetched(
+                            fetch_exchange_rate(price_source, fiat_currency)
+                                .await
+                                .map_err(|err| err.to_string()),
+                        );
+
+                        tokio::time::sleep(EXCHANGE_RATE_FETCH_INTERVAL).await;
+                    }
+                },
+            ));
+        }
+
+        for record in &connected_state.nwc_connections {
+            let nostr_module = connected_state.nostr_module.clone();
+            let db = connected_state.db.clone();
+            let nwc_connection = record.connection.clone();
+
+            subscriptions.push(iced::Subscription::run_with_id(
+                // The allowed methods are checked by the stream itself,
+                // so it's restarted whenever they change.
+                (
+                    std::any::TypeId::of::<NwcConnection>(),
+                    record.id,
+                    nwc_connection.allowed_methods.clone(),
+                ),
+                // We're wrapping `stream` in a `stream!` macro to make it lazy (meaning `stream` isn't
+                // created unless the outer `stream!` is actually used). This is necessary because the
+                // outer `stream!` is created on every update, but will only be polled if the subscription
+                // ID is new.
+                async_stream::stream! {
+                    let mut stream = Box::pin(
+                        nwc::request_stream(nostr_module, db, nwc_connection).map(
+                            |request| match request {
+                                NwcRequest::PayInvoice(request) => {
+                                    Message::IncomingNwcPayInvoiceRequest(request)
+                                }
+                                NwcRequest::MakeInvoice(request) => {
+                                    Message::IncomingNwcMakeInvoiceRequest(request)
+                                }
+                                NwcRequest::GetBalance {
+                                    request_event_id,
+                                    requester_public_key,
+                                } => Message::IncomingNwcGetBalanceRequest {
+                                    request_event_id,
+                                    requester_public_key,
+                                },
+                            },
+                        ),
+                    );
+
+                    while let Some(msg) = stream.next().await {
+                        yield msg;
+                    }
+                },
+            ));
+        }
+
+        for share in &connected_state.cosigner_shares {
+            let nostr_module = connected_state.nostr_module.clone();
+            let share = share.clone();
+
+            subscriptions.push(iced::Subscription::run_with_id(
+                (std::any::TypeId::of::<Cosigner>(), share.public_key),
+                // We're wrapping `stream` in a `stream!` macro to make it lazy (meaning `stream` isn't
+                // created unless the outer `stream!` is actually used). This is necessary because the
+                // outer `stream!` is created on every update, but will only be polled if the subscription
+                // ID is new.
+                async_stream::stream! {
+                    let Ok(cosigner) = Cosigner::new(nostr_module, share) else {
+                        return;
+                    };
+
+                    let mut stream = Box::pin(
+                        cosigner
+                            .request_stream()
+                            .map(Message::IncomingCosigningRequest),
+                    );
+
+                    while let Some(msg) = stream.next().await {
+                        yield msg;
+                    }
+                },
+            ));
+        }
+
+        iced::Subscription::batch(subscriptions)
+    }
+}
+
+/// Fetches the price of bitcoin in `currency` from `price_source`.
+async fn fetch_exchange_rate(
+    price_source: PriceSource,
+    currency: FiatCurrency,
+) -> anyhow::Result<ExchangeRate> {
+    let json = reqwest::Client::builder()
+        .timeout(EXCHANGE_RATE_FETCH_TIMEOUT)
+        .build()?
+        .get(price_source.price_url(currency))
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    price_source.read_exchange_rate(&json, currency)
+}
+
+/// Whether a request that only reads the user's public key can be approved without prompting.
+/// Only apps that the user has approved before are trusted with this, and either the global
+/// or the per-app setting can turn it off. Failing to load either setting means prompting.
+fn should_auto_approve_public_key_read(
+    connected_state: &routes::ConnectedState,
+    requests: &[nostr_sdk::nips::nip46::Request],
+    public_key: &PublicKey,
+) -> bool {
+    policy::is_public_key_read(requests)
+        && connected_state
+            .db
+            .get_auto_approve_public_key_reads()
+            .unwrap_or(false)
+        && connected_state
+            .db
+            .get_nip46_app(public_key)
+            .ok()
+            .flatten()
+            .is_some_and(|app| app.auto_approve_public_key_reads)
+}
+
+/// Whether a batch of requests only encrypts or decrypts messages with peers that the user
+/// already approved for the app this session. The app has to remember conversations, and
+/// failing to load it means prompting. See [`policy::Nip46App::remember_conversations`].
+fn should_auto_approve_known_conversations(
+    connected_state: &routes::ConnectedState,
+    requests: &[nostr_sdk::nips::nip46::Request],
+    public_key: &PublicKey,
+) -> bool {
+    let Some(peers) = policy::encryption_peers(requests) else {
+        return false;
+    };
+
+    connected_state
+        .db
+        .get_nip46_app(public_key)
+        .ok()
+        .flatten()
+        .filter(|app| app.remember_conversations)
+        .and_then(|app| app.identity_or)
+        .is_some_and(|identity| {
+            peers.iter().all(|peer| {
+                connected_state
+                    .remembered_conversations
+                    .is_remembered(public_key, &identity, peer)
+            })
+        })
+}
+
+/// Remembers the peers of an approved batch of encryption requests, if the app
+/// remembers conversations, so that its next requests with the same peers don't prompt.
+fn remember_conversations(
+    connected_state: &mut routes::ConnectedState,
+    requests: &[nostr_sdk::nips::nip46::Request],
+    public_key: &PublicKey,
+) {
+    let Some(peers) = policy::encryption_peers(requests) else {
+        return;
+    };
+
+    // TODO: Log a warning if the app fails to load.
+    let Some(identity) = connected_state
+        .db
+        .get_nip46_app(public_key)
+        .ok()
+        .flatten()
+        .filter(|app| app.remember_conversations)
+        .and_then(|app| app.identity_or)
+    else {
+        return;
+    };
+
+    for peer in peers {
+        connected_state
+            .remembered_conversations
+            .remember(*public_key, identity, peer);
+    }
+}
+
+/// Saves the metadata of connected federations whenever it changes, so that they can be
+/// listed by name on the next unlock before they've connected. Federations that are no
+/// longer joined, such as ones that were left, are dropped from the cache.
+fn cache_federation_metadata(
+    connected_state: &mut routes::ConnectedState,
+    wallet_view: &WalletView,
+) {
+    for (federation_id, federation_view) in &wallet_view.federations {
+        if connected_state.federation_metadata.get(federation_id) == Some(&federation_view.metadata)
+        {
+            continue;
+        }
+
+        // TODO: Log a warning if the metadata fails to save.
+        let _ = connected_state
+            .db
+            .save_federation_metadata(federation_id, &federation_view.metadata);
+
+        connected_state
+            .federation_metadata
+            .insert(*federation_id, federation_view.metadata.clone());
+    }
+
+    let db = &connected_state.db;
+    connected_state
+        .federation_metadata
+        .retain(|federation_id, _| {
+            let is_joined = wallet_view.federations.contains_key(federation_id)
+                || wallet_view
+                    .disconnected_federation_ids
+                    .contains(federation_id);
+
+            if !is_joined {
+                // TODO: Log a warning if the metadata fails to be removed.
+                let _ = db.remove_federation_metadata(federation_id);
+            }
+
+            is_joined
+        });
+}
+
+/// Whether a batch of requests only signs zap requests to keys on the zap allowlist.
+/// Signing a zap request doesn't pay anything, since the invoice is still paid separately.
+/// Only apps that the user has approved before are trusted with this, and failing to load
+/// the app or the allowlist means prompting.
+fn should_auto_approve_zap_requests(
+    connected_state: &routes::ConnectedState,
+    requests: &[nostr_sdk::nips::nip46::Request],
+    public_key: &PublicKey,
+) -> bool {
+    policy::zap_request_recipients(requests).is_some_and(|recipients| {
+        recipients.iter().all(|recipient| {
+            connected_state
+                .db
+                .is_on_zap_allowlist(recipient)
+                .unwrap_or(false)
+        })
+    }) && connected_state
+        .db
+        .get_nip46_app(public_key)
+        .ok()
+        .flatten()
+        .is_some()
+}
+
+/// Explains why the request on screen can't be approved as it is.
+fn other_identity_toast() -> Task<Message> {
+    Task::done(Message::AddToast(Toast {
+        title: "Can't approve request".to_string(),
+        body: "This app asked to sign as a different keypair than the one chosen for it. Switch the app to that keypair first, or reject the request.".to_string(),
+        status: ToastStatus::Bad,
+    }))
+}
+
+/// Sends the user's answer to a NIP-46 request and records how long it waited.
+/// If the transport already stopped waiting for the answer, the user is warned instead.
+#[allow(clippy::type_complexity)]
+fn answer_nip46_request(
+    connected_state: &mut routes::ConnectedState,
+    req: Arc<(
+        Vec<nostr_sdk::nips::nip46::Request>,
+        PublicKey,
+        iced::futures::channel::oneshot::Sender<Nip46RequestApproval>,
+    )>,
+    received_time: Instant,
+    outcome: Nip46RequestOutcome,
+) -> Task<Message> {
+    // Requests are only wrapped in an `Arc` so that messages can be cloned,
+    // so this only fails if a copy of the message is still around.
+    let Ok((requests, public_key, response_sender)) = Arc::try_unwrap(req) else {
+        return Task::done(Message::AddToast(Toast {
+            title: "Failed to answer request".to_string(),
+            body: "A copy of the request is still in use, so it was left unanswered.".to_string(),
+            status: ToastStatus::Bad,
+        }));
+    };
+
+    let rejection_reason_or = match outcome {
+        Nip46RequestOutcome::Rejected => Some(Nip46RejectionReason::RejectedByUser),
+        Nip46RequestOutcome::Expired => Some(Nip46RejectionReason::Expired),
+        Nip46RequestOutcome::AutoRejected => Some(Nip46RejectionReason::RejectedByPolicy),
+        Nip46RequestOutcome::Approved
+        | Nip46RequestOutcome::AutoApproved
+        | Nip46RequestOutcome::Dropped => None,
+    };
+
+    // The nip-55 server builds the NIP-46 response itself and its approval channel only
+    // takes approve or reject, so the app gets its generic rejection error. The reason is
+    // kept in the app's signing history instead.
+    // TODO: Send the reason in the NIP-46 error response once nip-55 can carry one.
+    let approval = if rejection_reason_or.is_some() {
+        Nip46RequestApproval::Reject
+    } else {
+        Nip46RequestApproval::Approve
+    };
+
+    let wait = received_time.elapsed();
+
+    if response_sender.send(approval).is_ok() {
+        connected_state
+            .signing_metrics
+            .record(public_key, outcome, wait);
+
+        // Recording the request touches the database, so it's left to the signing
+        // worker rather than holding up the UI during large batches of requests.
+        if let Some(rejection_reason) = rejection_reason_or {
+            // TODO: Log a warning if the rejection fails to be recorded.
+            let record_result =
+                connected_state
+                    .db
+                    .record_nip46_rejection(&public_key, rejection_reason, &requests);
+
+            if let Some(toast) = Toast::database_busy_or(&record_result) {
+                return Task::done(Message::AddToast(toast));
+            }
+        } else {
+            remember_conversations(connected_state, &requests, &public_key);
+
+            let signing_worker = connected_state.signing_worker.clone();
+            let register_app = outcome == Nip46RequestOutcome::Approved;
+
+            return Task::future(async move {
+                signing_worker
+                    .submit(public_key, requests, register_app)
+                    .await;
+            })
+            .discard();
+        }
+
+        return Task::none();
+    }
+
+    connected_state
+        .signing_metrics
+        .record(public_key, Nip46RequestOutcome::Dropped, wait);
+
+    // TODO: Log a warning if the rejection fails to be recorded.
+    let record_result = connected_state.db.record_nip46_rejection(
+        &public_key,
+        Nip46RejectionReason::NotAnswered,
+        &requests,
+    );
+
+    let mut tasks = vec![Task::done(Message::AddToast(Toast {
+        title: "App stopped waiting for a response".to_string(),
+        body: format!(
+            "A request from {} was dropped after {} seconds, so the app may report that the signer isn't responding. See Settings > Connected Apps for details.",
+            public_key.to_bech32().map_or_else(
+                |_| public_key.to_string(),
+                |npub| truncate_text(&npub, 20, true)
+            ),
+            wait.as_secs()
+        ),
+        status: ToastStatus::Bad,
+    }))];
+
+    if let Some(toast) = Toast::database_busy_or(&record_result) {
+        tasks.push(Task::done(Message::AddToast(toast)));
+    }
+
+    Task::batch(tasks)
+}
+
+/// The toast to show when Keystache goes offline or comes back online.
+/// See [`routes::ConnectedState::is_offline`].
+fn connectivity_change_toast_or(was_offline: bool, is_offline: bool) -> Option<Toast> {
+    match (was_offline, is_offline) {
+        (false, true) => Some(Toast {
+            title: "Offline".to_string(),
+            body: "Every relay and federation has lost its connection. Payments are paused until the connection is back.".to_string(),
+            status: ToastStatus::Bad,
+        }),
+        (true, false) => Some(Toast {
+            title: "Back online".to_string(),
+            body: "A relay or federation has reconnected.".to_string(),
+            status: ToastStatus::Good,
+        }),
+        _ => None,
+    }
+}
+
+fn balance_threshold_crossing_toast(
+    federation_view: &FederationView,
+    crossing: BalanceThresholdCrossing,
+) -> Toast {
+    let federation_name = federation_view
+        .metadata
+        .name_or
+        .clone()
+        .unwrap_or_else(|| "Unnamed Federation".to_string());
+
+    match crossing {
+        BalanceThresholdCrossing::BelowLow(threshold) => Toast {
+            title: format!("Low balance in {federation_name}"),
+            body: format!(
+                "Your balance dropped below {}. Consider topping up this federation.",
+                format_amount(threshold)
+            ),
+            status: ToastStatus::Neutral,
+        },
+        BalanceThresholdCrossing::AboveHigh(threshold) => Toast {
+            title: format!("High balance in {federation_name}"),
+            body: format!(
+                "Your balance is over {}. Consider withdrawing some funds from this federation.",
+                format_amount(threshold)
+            ),
+            status: ToastStatus::Neutral,
+        },
+    }
+}
+
+/// Creates an invoice requested by an app over Wallet Connect and responds with it.
+/// The user is notified when the invoice is created, and again once it's paid.
+/// Invoices requested for the user's lightning address go to the federation chosen for it.
+fn make_invoice_for_app(
+    connected_state: &routes::ConnectedState,
+    connection: NwcConnection,
+    request: MakeInvoiceRequest,
+) -> Task<Message> {
+    let db = connected_state.db.clone();
+    let wallet = connected_state.wallet.clone();
+    let nostr_module = connected_state.nostr_module.clone();
+
+    // TODO: Log a warning if the lightning address fails to load.
+    let lightning_address_federation_id_or = connected_state
+        .db
+        .get_receiving_lightning_address()
+        .ok()
+        .flatten()
+        .filter(|lightning_address| {
+            lightning_address.client_public_key == request.requester_public_key
+        })
+        .map(|lightning_address| lightning_address.federation_id);
+
+    let federation_id_or = select_federation_for_app_invoice(
+        connected_state,
+        request.amount,
+        lightning_address_federation_id_or,
+    );
+    let in_flight_operation = connected_state
+        .in_flight_operations
+        .start("Waiting for an invoice requested by an app to be paid");
+
+    Task::stream(async_stream::stream! {
+        let _in_flight_operation = in_flight_operation;
+
+        let Some(federation_id) = federation_id_or else {
+            // TODO: Log a warning if the response fails to send.
+            let _ = nwc::send_response(
+                &nostr_module,
+                &connection,
+                request.request_event_id,
+                &nwc::error_response(
+                    Method::MakeInvoice,
+                    ErrorCode::Other,
+                    "No federation can receive this amount",
+                ),
+            )
+            .await;
+
+            yield Message::AddToast(Toast {
+                title: "Invoice request declined".to_string(),
+                body: format!(
+                    "An app requested an invoice for {}, but none of your federations can receive it.",
+                    format_amount(request.amount)
+                ),
+                status: ToastStatus::Bad,
+            });
+            return;
+        };
+
+        let receive_result = match &request.description_hash_or {
+            Some(description_hash) => {
+                wallet
+                    .receive_payment_with_description_hash(
+                        federation_id,
+                        request.amount,
+                        description_hash,
+                    )
+                    .await
+            }
+            None => {
+                wallet
+                    .receive_payment(federation_id, request.amount, request.description.clone())
+                    .await
+            }
+        };
+
+        let (invoice, payment_completion_receiver) = match receive_result {
+            Ok(invoice_and_receiver) => invoice_and_receiver,
+            Err(err) => {
+                // TODO: Log a warning if the response fails to send.
+                let _ = nwc::send_response(
+                    &nostr_module,
+                    &connection,
+                    request.request_event_id,
+                    &nwc::error_response(Method::MakeInvoice, ErrorCode::Internal, &err.to_string()),
+                )
+                .await;
+
+                yield Message::AddToast(Toast {
+                    title: "Failed to create invoice".to_string(),
+                    body: format!("Failed to create an invoice requested by an app: {err}"),
+                    status: ToastStatus::Bad,
+                });
+                return;
+            }
+        };
+
+        // TODO: Log a warning if the response fails to send.
+        let _ = nwc::send_response(
+            &nostr_module,
+            &connection,
+            request.request_event_id,
+            &nwc::make_invoice_response(&invoice),
+        )
+        .await;
+
+        yield Message::AddToast(Toast {
+            title: "Invoice created".to_string(),
+            body: format!(
+                "An app requested an invoice for {}.",
+                format_amount(request.amount)
+            ),
+            status: ToastStatus::Neutral,
+        });
+
+        if matches!(
+            payment_completion_receiver.await,
+            Ok(LightningReceiveCompletion::Success)
+        ) {
+            // TODO: Notify the user if the payment fails to be recorded.
+            let save_result = db::retry_while_busy(|| {
+                db.save_payment(
+                    &federation_id,
+                    PaymentDirection::Incoming,
+                    request.amount,
+                    Amount::ZERO,
+                    Some(&invoice),
+                    None,
+                    Some(&request.requester_public_key),
+                    None,
+                    false,
+                    None,
+                )
+            })
+            .await;
+
+            if let Some(toast) = Toast::database_busy_or(&save_result) {
+                yield Message::AddToast(toast);
+            }
+
+            yield Message::AddToast(Toast {
+                title: "Payment received".to_string(),
+                body: format!(
+                    "Received {} from an invoice requested by an app.",
+                    format_amount(request.amount)
+                ),
+                status: ToastStatus::Good,
+            });
+        }
+    })
+}
+
+/// Picks the federation that receives an invoice requested by an app.
+/// `preferred_federation_id_or` is preferred, then the default federation,
+/// and federations that would go over their maximum balance are skipped.
+fn select_federation_for_app_invoice(
+    connected_state: &routes::ConnectedState,
+    amount: Amount,
+    preferred_federation_id_or: Option<FederationId>,
+) -> Option<FederationId> {
+    let wallet_view = connected_state.loadable_wallet_view.as_ref_option()?;
+
+    // TODO: Log a warning if the default federation fails to load.
+    let default_federation_id_or = connected_state.db.get_default_federation().ok().flatten();
+
+    let preferred_federation_view_or = preferred_federation_id_or
+        .and_then(|preferred_federation_id| wallet_view.federations.get(&preferred_federation_id));
+
+    let default_federation_view_or = default_federation_id_or
+        .and_then(|default_federation_id| wallet_view.federations.get(&default_federation_id));
+
+    preferred_federation_view_or
+        .into_iter()
+        .chain(default_federation_view_or)
+        .chain(wallet_view.federations.values())
+        .find(|federation_view| {
+            !connected_state
+                .get_balance_thresholds(&federation_view.federation_id)
+                .would_exceed_max(federation_view.balance, amount)
+        })
+        .map(|federation_view| federation_view.federation_id)
+}
+
+/// Gets the Wallet Connect connection used by the app with `requester_public_key`.
+/// Returns `None` if it has been revoked, since its requests shouldn't be answered.
+fn get_active_nwc_connection(
+    connected_state: &routes::ConnectedState,
+    requester_public_key: &PublicKey,
+) -> Option<NwcConnectionRecord> {
+    // TODO: Log a warning if the connection fails to load.
+    connected_state
+        .db
+        .get_nwc_connection_by_client_public_key(requester_public_key)
+        .ok()
+        .flatten()
+        .filter(|record| !record.is_revoked())
+}
+
+fn new_payment_request_toast(request: &PayInvoiceRequest, connection_name: &str) -> Toast {
+    let amount_str = request.invoice.amount_milli_satoshis().map_or_else(
+        || "an unspecified amount".to_string(),
+        |msats| format_amount(Amount::from_msats(msats)),
+    );
+
+    Toast {
+        title: "New payment request".to_string(),
+        body: format!(
+            "{connection_name} is requesting a payment of {amount_str}. Review it in your payment request inbox."
+        ),
+        status: ToastStatus::Neutral,
+    }
+}
@@ -0,0 +1,147 @@
+use std::{
+    fmt::Display,
+    io::{IsTerminal, Write},
+    process::{Command, Stdio},
+};
+
+use base64::Engine;
+
+/// A way of putting text on the user's clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardBackend {
+    /// The system clipboard, through `arboard`.
+    Arboard,
+    /// The `wl-copy` command from `wl-clipboard`, for Wayland sessions that `arboard` can't reach.
+    WlCopy,
+    /// An OSC 52 escape sequence written to the terminal that Keystache was started from.
+    /// Supported by most terminals, including over SSH.
+    Osc52,
+}
+
+impl Display for ClipboardBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Arboard => write!(f, "system clipboard"),
+            Self::WlCopy => write!(f, "wl-copy"),
+            Self::Osc52 => write!(f, "terminal (OSC 52)"),
+        }
+    }
+}
+
+/// Copies text to the clipboard, falling back to other backends when
+/// the system clipboard can't be reached, such as on some Wayland or remote sessions.
+#[derive(Default)]
+pub struct Clipboard {
+    // Kept alive because on Linux, copied text is only available
+    // for as long as the clipboard that copied it exists.
+    arboard_or: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    /// Copies `text`, trying each backend in turn.
+    /// Returns the backend that was used, or every backend's error if none of them worked.
+    pub fn copy_text(&mut self, text: &str) -> anyhow::Result<ClipboardBackend> {
+        let arboard_err = match self.copy_with_arboard(text) {
+            Ok(()) => return Ok(ClipboardBackend::Arboard),
+            Err(err) => err,
+        };
+
+        let wl_copy_err = match copy_with_wl_copy(text) {
+            Ok(()) => return Ok(ClipboardBackend::WlCopy),
+            Err(err) => err,
+        };
+
+        let osc52_err = match copy_with_osc52(text) {
+            Ok(()) => return Ok(ClipboardBackend::Osc52),
+            Err(err) => err,
+        };
+
+        Err(anyhow::anyhow!(
+            "{}: {arboard_err}. {}: {wl_copy_err}. {}: {osc52_err}.",
+            ClipboardBackend::Arboard,
+            ClipboardBackend::WlCopy,
+            ClipboardBackend::Osc52
+        ))
+    }
+
+    fn copy_with_arboard(&mut self, text: &str) -> anyhow::Result<()> {
+        let clipboard = match &mut self.arboard_or {
+            Some(clipboard) => clipboard,
+            None => self.arboard_or.insert(arboard::Clipboard::new()?),
+        };
+
+        if let Err(err) = clipboard.set_text(text) {
+            // The clipboard may have been lost, such as when the display server restarted.
+            // So it's recreated on the next copy.
+            self.arboard_or = None;
+
+            return Err(err.into());
+        }
+
+        Ok(())
+    }
+}
+
+fn copy_with_wl_copy(text: &str) -> anyhow::Result<()> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_none() {
+        return Err(anyhow::anyhow!("Not a Wayland session"));
+    }
+
+    let mut child = Command::new("wl-copy")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    // The pipe is closed when `stdin` is dropped, which tells `wl-copy` that the text is complete.
+    {
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Failed to open wl-copy's input"))?;
+        stdin.write_all(text.as_bytes())?;
+    }
+
+    let status = child.wait()?;
+
+    if !status.success() {
+        return Err(anyhow::anyhow!("wl-copy exited with {status}"));
+    }
+
+    Ok(())
+}
+
+fn copy_with_osc52(text: &str) -> anyhow::Result<()> {
+    let mut stdout = std::io::stdout().lock();
+
+    if !stdout.is_terminal() {
+        return Err(anyhow::anyhow!("Not running in a terminal"));
+    }
+
+    stdout.write_all(osc52_sequence(text).as_bytes())?;
+    stdout.flush()?;
+
+    Ok(())
+}
+
+/// Builds the escape sequence that asks the terminal to put `text` on the clipboard.
+fn osc52_sequence(text: &str) -> String {
+    format!(
+        "\x1b]52;c;{}\x07",
+        base64::engine::general_purpose::STANDARD.encode(text)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osc52_sequence() {
+        assert_eq!(osc52_sequence(""), "\x1b]52;c;\x07");
+        assert_eq!(
+            osc52_sequence("nostr:npub1"),
+            "\x1b]52;c;bm9zdHI6bnB1YjE=\x07"
+        );
+    }
+}
@@ -1,4 +1,6 @@
 #![deny(clippy::pedantic, clippy::nursery)]
+// Errors that can happen at runtime are shown to the user rather than panicking.
+#![cfg_attr(not(test), deny(clippy::unwrap_used))]
 #![allow(clippy::cast_possible_truncation)]
 #![allow(clippy::match_same_arms)]
 #![allow(clippy::missing_const_for_fn)]
@@ -6,27 +8,34 @@
 #![allow(clippy::significant_drop_tightening)]
 
 mod app;
-mod db;
-mod fedimint;
-mod nostr;
+mod clipboard;
 mod routes;
 mod ui_components;
 mod util;
 
 use app::App;
 
-use fedimint::Wallet;
-use iced::widget::Theme;
 use iced::window::settings::PlatformSpecific;
 use iced::window::Settings;
-use iced::Size;
+use iced::{Size, Task};
+use keystache_core::{
+    backup, bbqr, clock, config, db, delegation, encryption, exchange_rate, fedimint,
+    file_attachment, follows, in_flight, keychain, legacy, lnurl, maintenance, metrics, ncryptsec,
+    nostr, notes, nwc, policy, privacy, profile, receipt, relay_list, signing_worker,
+    spend_approval, threshold_key, unlock_attempts, zap,
+};
 
 fn main() -> iced::Result {
     tracing_subscriber::fmt::init();
 
+    // Clicking a `fedimint:` link opens Keystache with the link as an argument.
+    let launch_invite_code_or = std::env::args()
+        .skip(1)
+        .find_map(|arg| fedimint::parse_invite_link(&arg));
+
     iced::application("Keystache", App::update, App::view)
         .subscription(App::subscription)
-        .theme(|_| Theme::Dark)
+        .theme(App::theme)
         .window(Settings {
             size: iced::Size {
                 width: 800.0,
@@ -45,7 +54,8 @@ fn main() -> iced::Result {
             level: iced::window::Level::Normal,
             icon: None,                                     // TODO: Set icon.
             platform_specific: PlatformSpecific::default(), // TODO: Set platform specific settings for each platform.
-            exit_on_close_request: true,
+            // Closing is confirmed first if any operations are in flight.
+            exit_on_close_request: false,
         })
-        .run()
+        .run_with(move || (App::new(launch_invite_code_or), Task::none()))
 }
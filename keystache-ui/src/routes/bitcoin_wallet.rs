@@ -0,0 +1,1864 @@
+use std::{collections::BTreeMap, str::FromStr, sync::Arc, time::Duration};
+
+use fedimint_core::{
+    config::{ClientConfig, FederationId, META_FEDERATION_NAME_KEY},
+    invite_code::InviteCode,
+    Amount,
+};
+use iced::{
+    widget::{
+        checkbox, column, container::Style, horizontal_space, progress_bar, row, text_editor,
+        Column, Container, Row, Space, Text,
+    },
+    Alignment, Border, Length, Shadow, Subscription, Task, Theme,
+};
+use nostr_sdk::Url;
+
+use crate::{
+    app,
+    exchange_rate::{parse_fiat_input, ExchangeRate},
+    fedimint::{
+        BalanceThresholds, FederationApiOverrides, FederationOperationProgress, FederationView,
+        GatewayFeeSample, GatewayFeeStats, GatewayId, JoinFederationStage, LeaveFederationStage,
+        WalletView,
+    },
+    notes::NoteSubject,
+    ui_components::{
+        bech32_input, icon_button, sparkline, text_input, Bech32Kind, PaletteColor, SvgIcon, Toast,
+        ToastStatus,
+    },
+    util::{debounce_search_input, format_amount, lighten, rank_by_fuzzy_match, truncate_text},
+};
+
+use super::{container, ConnectedState, Loadable, RouteName};
+
+mod batch_send;
+mod connections;
+mod import;
+mod operation_log;
+mod payment_details;
+mod payment_requests;
+mod receive;
+mod receive_ecash;
+mod send;
+mod send_ecash;
+mod spend_approval;
+mod stats;
+mod transactions;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    FederationSearchInputChanged(String),
+    FederationSearchDebounced(String),
+
+    JoinFederationInviteCodeInputChanged(String),
+    ClearJoinFederationInviteCode,
+
+    LoadedFederationConfigFromInviteCode {
+        // The invite code that was used to load the federation config.
+        config_invite_code: InviteCode,
+        // The loaded federation config.
+        config: ClientConfig,
+    },
+    FailedToLoadFederationConfigFromInviteCode {
+        // The invite code that was used to attempt to load the federation config.
+        config_invite_code: InviteCode,
+    },
+
+    JoinFederation(InviteCode),
+    JoinedFederation(InviteCode),
+
+    LeaveFederation(FederationId),
+    LeftFederation(FederationId),
+
+    FederationOperationProgressed(FederationId, FederationOperationProgress),
+    FederationOperationFinished(FederationId),
+
+    LowBalanceThresholdInputChanged(String),
+    HighBalanceThresholdInputChanged(String),
+    MaxBalanceInputChanged(String),
+    SaveBalanceThresholds(FederationId, BalanceThresholds),
+    SetDefaultFederation(Option<FederationId>),
+    PinGateway(FederationId, Option<GatewayId>),
+    NotesEdited(text_editor::Action),
+    SaveNotes(FederationId),
+    ToggleAdvancedFederationSettings,
+    ApiSecretInputChanged(String),
+    SaveApiOverrides(FederationId, FederationApiOverrides),
+
+    Send(send::Message),
+    BatchSend(batch_send::Message),
+    Receive(receive::Message),
+    SendEcash(send_ecash::Message),
+    ReceiveEcash(receive_ecash::Message),
+    Stats(stats::Message),
+    PaymentDetails(payment_details::Message),
+    PaymentRequests(payment_requests::Message),
+    Connections(connections::Message),
+    Import(import::Message),
+    OperationLog(operation_log::Message),
+    SpendApproval(spend_approval::Message),
+    Transactions(transactions::Message),
+
+    PaymentRequestReceived,
+    RefreshWalletView,
+    UpdateWalletView(WalletView),
+}
+
+pub struct Page {
+    pub connected_state: ConnectedState,
+    pub subroute: Subroute,
+}
+
+impl Page {
+    // TODO: Remove this clippy allow.
+    #[allow(clippy::too_many_lines)]
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::FederationSearchInputChanged(input) => {
+                if let Subroute::List(List { search_input, .. }) = &mut self.subroute {
+                    search_input.clone_from(&input);
+                }
+
+                Task::perform(debounce_search_input(input), |input| {
+                    app::Message::Routes(super::Message::BitcoinWalletPage(
+                        Message::FederationSearchDebounced(input),
+                    ))
+                })
+            }
+            Message::FederationSearchDebounced(input) => {
+                if let Subroute::List(list) = &mut self.subroute {
+                    // Only search once the user has stopped typing.
+                    if list.search_input == input {
+                        list.search_query = input;
+                    }
+                }
+
+                Task::none()
+            }
+            Message::JoinFederationInviteCodeInputChanged(new_federation_invite_code) => {
+                let Subroute::Add(Add {
+                    federation_invite_code,
+                    parsed_federation_invite_code_state_or,
+                }) = &mut self.subroute
+                else {
+                    return Task::none();
+                };
+
+                // Keep a draft so the invite code isn't lost when navigating away.
+                self.connected_state
+                    .drafts
+                    .federation_invite_code
+                    .clone_from(&new_federation_invite_code);
+
+                *federation_invite_code = new_federation_invite_code;
+
+                if let Ok(invite_code) = InviteCode::from_str(federation_invite_code) {
+                    *parsed_federation_invite_code_state_or =
+                        Some(ParsedFederationInviteCodeState {
+                            invite_code: invite_code.clone(),
+                            loadable_federation_config: Loadable::Loading,
+                        });
+
+                    Task::perform(
+                        async move {
+                            match fedimint_api_client::download_from_invite_code(&invite_code).await
+                            {
+                                Ok(config) => {
+                                    app::Message::Routes(super::Message::BitcoinWalletPage(
+                                        Message::LoadedFederationConfigFromInviteCode {
+                                            config_invite_code: invite_code,
+                                            config,
+                                        },
+                                    ))
+                                }
+                                // TODO: Include error in message and display it in the UI.
+                                Err(_err) => {
+                                    app::Message::Routes(super::Message::BitcoinWalletPage(
+                                        Message::FailedToLoadFederationConfigFromInviteCode {
+                                            config_invite_code: invite_code,
+                                        },
+                                    ))
+                                }
+                            }
+                        },
+                        |msg| msg,
+                    )
+                } else {
+                    *parsed_federation_invite_code_state_or = None;
+
+                    Task::none()
+                }
+            }
+            Message::ClearJoinFederationInviteCode => {
+                Task::done(app::Message::Routes(super::Message::BitcoinWalletPage(
+                    Message::JoinFederationInviteCodeInputChanged(String::new()),
+                )))
+            }
+            Message::LoadedFederationConfigFromInviteCode {
+                config_invite_code,
+                config,
+            } => {
+                let Subroute::Add(Add {
+                    parsed_federation_invite_code_state_or,
+                    ..
+                }) = &mut self.subroute
+                else {
+                    return Task::none();
+                };
+
+                if let Some(ParsedFederationInviteCodeState {
+                    invite_code,
+                    loadable_federation_config: maybe_loading_federation_config,
+                }) = parsed_federation_invite_code_state_or
+                {
+                    // If the invite code has changed since the request was made, ignore the response.
+                    if &config_invite_code == invite_code {
+                        *maybe_loading_federation_config = Loadable::Loaded(config);
+                    }
+                }
+
+                Task::none()
+            }
+            Message::FailedToLoadFederationConfigFromInviteCode { config_invite_code } => {
+                let Subroute::Add(Add {
+                    parsed_federation_invite_code_state_or,
+                    ..
+                }) = &mut self.subroute
+                else {
+                    return Task::none();
+                };
+
+                if let Some(ParsedFederationInviteCodeState {
+                    invite_code,
+                    loadable_federation_config: maybe_loading_federation_config,
+                }) = parsed_federation_invite_code_state_or
+                {
+                    // If the invite code has changed since the request was made, ignore the response.
+                    // Also only update the state if the config hasn't already been loaded.
+                    if &config_invite_code == invite_code
+                        && matches!(maybe_loading_federation_config, Loadable::Loading)
+                    {
+                        *maybe_loading_federation_config = Loadable::Failed;
+                    }
+                }
+
+                Task::none()
+            }
+            Message::JoinFederation(invite_code) => {
+                let wallet = self.connected_state.wallet.clone();
+                let federation_id = invite_code.federation_id();
+
+                self.connected_state.federation_operations.insert(
+                    federation_id,
+                    FederationOperationProgress::Joining(JoinFederationStage::DownloadingConfig),
+                );
+
+                Task::stream(async_stream::stream! {
+                    let (stage_sender, mut stage_receiver) = tokio::sync::mpsc::unbounded_channel();
+
+                    let join_handle = tokio::spawn({
+                        let invite_code = invite_code.clone();
+
+                        async move {
+                            wallet
+                                .join_federation(invite_code, move |stage| {
+                                    // If the receiver was dropped, we don't care about the progress.
+                                    let _ = stage_sender.send(stage);
+                                })
+                                .await
+                        }
+                    });
+
+                    // The channel closes once the join has finished.
+                    while let Some(stage) = stage_receiver.recv().await {
+                        yield app::Message::Routes(super::Message::BitcoinWalletPage(
+                            Message::FederationOperationProgressed(
+                                federation_id,
+                                FederationOperationProgress::Joining(stage),
+                            )
+                        ));
+                    }
+
+                    yield app::Message::Routes(super::Message::BitcoinWalletPage(
+                        Message::FederationOperationFinished(federation_id)
+                    ));
+
+                    match join_handle.await.map_err(anyhow::Error::from).and_then(|result| result) {
+                        Ok(()) => {
+                            yield app::Message::AddToast(Toast {
+                                title: "Joined federation".to_string(),
+                                body: "You have successfully joined the federation.".to_string(),
+                                status: ToastStatus::Good,
+                            });
+
+                            yield app::Message::Routes(super::Message::BitcoinWalletPage(
+                                Message::JoinedFederation(invite_code)
+                            ));
+                        }
+                        Err(err) => {
+                            yield app::Message::AddToast(Toast {
+                                title: "Failed to join federation".to_string(),
+                                body: format!("Failed to join the federation: {err}"),
+                                status: ToastStatus::Bad,
+                            });
+                        }
+                    }
+                })
+            }
+            Message::JoinedFederation(invite_code) => {
+                if self.connected_state.drafts.federation_invite_code == invite_code.to_string() {
+                    self.connected_state.drafts.federation_invite_code.clear();
+                }
+
+                // A verbose way of saying "if the user is currently on the Add page and the invite code matches the one that was just joined, navigate back to the List page".
+                if let Subroute::Add(add) = &self.subroute {
+                    if let Some(invite_code_state) = &add.parsed_federation_invite_code_state_or {
+                        if invite_code_state.invite_code == invite_code {
+                            return Task::done(app::Message::Routes(super::Message::Navigate(
+                                RouteName::BitcoinWallet(SubrouteName::List),
+                            )));
+                        }
+                    }
+                }
+
+                Task::none()
+            }
+            Message::LeaveFederation(federation_id) => {
+                let wallet = self.connected_state.wallet.clone();
+
+                self.connected_state.federation_operations.insert(
+                    federation_id,
+                    FederationOperationProgress::Leaving(LeaveFederationStage::CheckingBalance),
+                );
+
+                Task::stream(async_stream::stream! {
+                    let (stage_sender, mut stage_receiver) = tokio::sync::mpsc::unbounded_channel();
+
+                    let leave_handle = tokio::spawn(async move {
+                        wallet
+                            .leave_federation(federation_id, move |stage| {
+                                // If the receiver was dropped, we don't care about the progress.
+                                let _ = stage_sender.send(stage);
+                            })
+                            .await
+                    });
+
+                    // The channel closes once the leave has finished.
+                    while let Some(stage) = stage_receiver.recv().await {
+                        yield app::Message::Routes(super::Message::BitcoinWalletPage(
+                            Message::FederationOperationProgressed(
+                                federation_id,
+                                FederationOperationProgress::Leaving(stage),
+                            )
+                        ));
+                    }
+
+                    yield app::Message::Routes(super::Message::BitcoinWalletPage(
+                        Message::FederationOperationFinished(federation_id)
+                    ));
+
+                    match leave_handle.await.map_err(anyhow::Error::from).and_then(|result| result) {
+                        Ok(()) => {
+                            yield app::Message::AddToast(Toast {
+                                title: "Left federation".to_string(),
+                                body: "You have successfully left the federation.".to_string(),
+                                status: ToastStatus::Good,
+                            });
+
+                            yield app::Message::Routes(super::Message::BitcoinWalletPage(
+                                Message::LeftFederation(federation_id)
+                            ));
+                        }
+                        Err(err) => {
+                            yield app::Message::AddToast(Toast {
+                                title: "Failed to leave federation".to_string(),
+                                body: format!("Failed to leave the federation: {err}"),
+                                status: ToastStatus::Bad,
+                            });
+                        }
+                    }
+                })
+            }
+            Message::LeftFederation(federation_id) => {
+                // A verbose way of saying "if the user is currently on the FederationDetails page and the federation ID matches the one that was just left, navigate back to the List page".
+                if let Subroute::FederationDetails(federation_details) = &self.subroute {
+                    if federation_details.view.federation_id == federation_id {
+                        return Task::done(app::Message::Routes(super::Message::Navigate(
+                            RouteName::BitcoinWallet(SubrouteName::List),
+                        )));
+                    }
+                }
+
+                Task::none()
+            }
+            Message::FederationOperationProgressed(federation_id, progress) => {
+                self.connected_state
+                    .federation_operations
+                    .insert(federation_id, progress);
+
+                Task::none()
+            }
+            Message::FederationOperationFinished(federation_id) => {
+                self.connected_state
+                    .federation_operations
+                    .remove(&federation_id);
+
+                Task::none()
+            }
+            Message::LowBalanceThresholdInputChanged(input) => {
+                if let Subroute::FederationDetails(federation_details) = &mut self.subroute {
+                    federation_details.low_balance_threshold_input = input;
+                }
+
+                Task::none()
+            }
+            Message::HighBalanceThresholdInputChanged(input) => {
+                if let Subroute::FederationDetails(federation_details) = &mut self.subroute {
+                    federation_details.high_balance_threshold_input = input;
+                }
+
+                Task::none()
+            }
+            Message::MaxBalanceInputChanged(input) => {
+                if let Subroute::FederationDetails(federation_details) = &mut self.subroute {
+                    federation_details.max_balance_input = input;
+                }
+
+                Task::none()
+            }
+            Message::SaveBalanceThresholds(federation_id, thresholds) => {
+                match self
+                    .connected_state
+                    .db
+                    .save_federation_balance_thresholds(&federation_id, thresholds)
+                {
+                    Ok(()) => {
+                        self.connected_state
+                            .balance_thresholds
+                            .insert(federation_id, thresholds);
+
+                        Task::done(app::Message::AddToast(Toast {
+                            title: "Saved balance limits".to_string(),
+                            body: "The balance alert thresholds and maximum balance were successfully saved.".to_string(),
+                            status: ToastStatus::Good,
+                        }))
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to save balance limits".to_string(),
+                        body: format!("Failed to save the balance limits: {err}"),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::NotesEdited(action) => {
+                if let Subroute::FederationDetails(federation_details) = &mut self.subroute {
+                    federation_details.notes.perform(action);
+                }
+
+                Task::none()
+            }
+            Message::SaveNotes(federation_id) => {
+                let Subroute::FederationDetails(federation_details) = &self.subroute else {
+                    return Task::none();
+                };
+
+                match self.connected_state.db.save_note(
+                    NoteSubject::Federation(federation_id),
+                    &federation_details.notes.text(),
+                ) {
+                    Ok(()) => Task::done(app::Message::AddToast(Toast {
+                        title: "Saved notes".to_string(),
+                        body: "Your notes about this federation were successfully saved."
+                            .to_string(),
+                        status: ToastStatus::Good,
+                    })),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to save notes".to_string(),
+                        body: format!("Failed to save the notes: {err}"),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::ToggleAdvancedFederationSettings => {
+                if let Subroute::FederationDetails(federation_details) = &mut self.subroute {
+                    federation_details.show_advanced_settings =
+                        !federation_details.show_advanced_settings;
+                }
+
+                Task::none()
+            }
+            Message::ApiSecretInputChanged(input) => {
+                if let Subroute::FederationDetails(federation_details) = &mut self.subroute {
+                    federation_details.api_secret_input = input;
+                }
+
+                Task::none()
+            }
+            Message::SaveApiOverrides(federation_id, api_overrides) => {
+                if let Err(err) = self
+                    .connected_state
+                    .db
+                    .save_federation_api_overrides(&federation_id, &api_overrides)
+                {
+                    return Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to save connection settings".to_string(),
+                        body: format!("Failed to save the connection settings: {err}"),
+                        status: ToastStatus::Bad,
+                    }));
+                }
+
+                let wallet = self.connected_state.wallet.clone();
+
+                Task::perform(
+                    async move {
+                        wallet
+                            .apply_api_overrides(federation_id, api_overrides)
+                            .await
+                    },
+                    |result| {
+                        app::Message::AddToast(match result {
+                            Ok(()) => Toast {
+                                title: "Saved connection settings".to_string(),
+                                body: "Reconnected to the federation with the new connection settings.".to_string(),
+                                status: ToastStatus::Good,
+                            },
+                            Err(err) => Toast {
+                                title: "Failed to reconnect to federation".to_string(),
+                                body: format!("The connection settings were saved, but the federation couldn't be reconnected to with them: {err}"),
+                                status: ToastStatus::Bad,
+                            },
+                        })
+                    },
+                )
+            }
+            Message::SetDefaultFederation(federation_id_or) => {
+                match self
+                    .connected_state
+                    .db
+                    .save_default_federation(federation_id_or.as_ref())
+                {
+                    Ok(()) => {
+                        if let Subroute::FederationDetails(federation_details) = &mut self.subroute
+                        {
+                            federation_details.is_default =
+                                federation_id_or == Some(federation_details.view.federation_id);
+                        }
+
+                        Task::none()
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to set default federation".to_string(),
+                        body: format!("Failed to save the default federation: {err}"),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::PinGateway(federation_id, gateway_id_or) => {
+                let db = &self.connected_state.db;
+
+                match db
+                    .save_pinned_gateway(&federation_id, gateway_id_or.as_ref())
+                    .and_then(|()| db.list_pinned_gateways())
+                {
+                    Ok(pinned_gateways) => {
+                        self.connected_state
+                            .wallet
+                            .set_pinned_gateways(pinned_gateways);
+
+                        if let Subroute::FederationDetails(federation_details) = &mut self.subroute
+                        {
+                            if federation_details.view.federation_id == federation_id {
+                                federation_details.pinned_gateway_id_or = gateway_id_or;
+                            }
+                        }
+
+                        Task::none()
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to pin gateway".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::Send(send_message) => {
+                if let Subroute::Send(send_page) = &mut self.subroute {
+                    let task = send_page.update(send_message);
+
+                    // Keep a draft so the invoice isn't lost when navigating away.
+                    self.connected_state.drafts.send_lightning_invoice =
+                        send_page.lightning_invoice_input().to_string();
+
+                    task
+                } else {
+                    Task::none()
+                }
+            }
+            Message::BatchSend(batch_send_message) => {
+                if let Subroute::BatchSend(batch_send_page) = &mut self.subroute {
+                    batch_send_page.update(batch_send_message)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::Receive(receive_message) => {
+                if let Subroute::Receive(receive_page) = &mut self.subroute {
+                    let task = receive_page.update(receive_message);
+
+                    // Keep a draft so the amount isn't lost when navigating away.
+                    self.connected_state.drafts.receive_amount =
+                        receive_page.amount_input().to_string();
+
+                    task
+                } else {
+                    Task::none()
+                }
+            }
+            Message::SendEcash(send_ecash_message) => {
+                if let Subroute::SendEcash(send_ecash_page) = &mut self.subroute {
+                    send_ecash_page.update(send_ecash_message)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::ReceiveEcash(receive_ecash_message) => {
+                if let Subroute::ReceiveEcash(receive_ecash_page) = &mut self.subroute {
+                    receive_ecash_page.update(receive_ecash_message)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::Stats(stats_message) => {
+                if let Subroute::Stats(stats_page) = &mut self.subroute {
+                    stats_page.update(stats_message)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::PaymentDetails(payment_details_message) => {
+                if let Subroute::PaymentDetails(payment_details_page) = &mut self.subroute {
+                    payment_details_page.update(payment_details_message)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::PaymentRequests(payment_requests_message) => {
+                if let Subroute::PaymentRequests(payment_requests_page) = &mut self.subroute {
+                    payment_requests_page.update(payment_requests_message)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::Connections(connections_message) => {
+                if let Subroute::Connections(connections_page) = &mut self.subroute {
+                    connections_page.update(connections_message)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::Import(import_message) => {
+                if let Subroute::Import(import_page) = &mut self.subroute {
+                    import_page.update(import_message)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::OperationLog(operation_log_message) => {
+                if let Subroute::OperationLog(operation_log_page) = &mut self.subroute {
+                    operation_log_page.update(operation_log_message)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::SpendApproval(spend_approval_message) => {
+                if let Subroute::SpendApproval(spend_approval_page) = &mut self.subroute {
+                    spend_approval_page.update(spend_approval_message)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::Transactions(transactions_message) => {
+                if let Subroute::Transactions(transactions_page) = &mut self.subroute {
+                    transactions_page.update(transactions_message)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::PaymentRequestReceived => {
+                if let Subroute::PaymentRequests(payment_requests_page) = &mut self.subroute {
+                    payment_requests_page.update(payment_requests::Message::ReloadPaymentRequests)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::RefreshWalletView => {
+                let wallet = self.connected_state.wallet.clone();
+
+                // The new view arrives through the wallet's update stream.
+                Task::future(async move { wallet.refresh_view().await }).discard()
+            }
+            Message::UpdateWalletView(wallet_view) => match &mut self.subroute {
+                Subroute::Send(send_page) => {
+                    send_page.update(send::Message::UpdateWalletView(wallet_view))
+                }
+                Subroute::BatchSend(batch_send_page) => {
+                    batch_send_page.update(batch_send::Message::UpdateWalletView(wallet_view))
+                }
+                Subroute::Receive(receive_page) => {
+                    receive_page.update(receive::Message::UpdateWalletView(wallet_view))
+                }
+                Subroute::SendEcash(send_ecash_page) => {
+                    send_ecash_page.update(send_ecash::Message::UpdateWalletView(wallet_view))
+                }
+                Subroute::ReceiveEcash(receive_ecash_page) => {
+                    receive_ecash_page.update(receive_ecash::Message::UpdateWalletView(wallet_view))
+                }
+                Subroute::PaymentRequests(payment_requests_page) => payment_requests_page
+                    .update(payment_requests::Message::UpdateWalletView(wallet_view)),
+                _ => Task::none(),
+            },
+        }
+    }
+
+    /// Starts loading the operation log or syncing transactions, if either is the current
+    /// subroute. Both have to wait for the wallet, so they're loaded once the page is shown.
+    pub fn load_from_wallet(&mut self) -> Task<app::Message> {
+        match &mut self.subroute {
+            Subroute::OperationLog(operation_log_page) => operation_log_page.load_operations(),
+            Subroute::Transactions(transactions_page) => transactions_page.sync_transactions(),
+            _ => Task::none(),
+        }
+    }
+
+    pub fn subscription(&self) -> Subscription<app::Message> {
+        match &self.subroute {
+            Subroute::Receive(receive) => receive.subscription(),
+            Subroute::SendEcash(send_ecash) => send_ecash.subscription(),
+            _ => Subscription::none(),
+        }
+    }
+
+    pub fn view(&self) -> Column<app::Message> {
+        match &self.subroute {
+            Subroute::List(list) => list.view(&self.connected_state),
+            Subroute::FederationDetails(federation_details) => {
+                federation_details.view(&self.connected_state)
+            }
+            Subroute::Add(add) => add.view(&self.connected_state),
+            Subroute::Send(send) => send.view(
+                self.connected_state.is_offline(),
+                self.connected_state.exchange_rate(),
+            ),
+            Subroute::BatchSend(batch_send) => batch_send.view(self.connected_state.is_offline()),
+            Subroute::Receive(receive) => receive.view(
+                self.connected_state.is_offline(),
+                self.connected_state.exchange_rate(),
+                &self.connected_state.balance_thresholds,
+            ),
+            Subroute::SendEcash(send_ecash) => send_ecash.view(self.connected_state.is_offline()),
+            Subroute::ReceiveEcash(receive_ecash) => {
+                receive_ecash.view(self.connected_state.is_offline())
+            }
+            Subroute::Stats(stats) => stats.view(),
+            Subroute::PaymentDetails(payment_details) => payment_details.view(),
+            Subroute::PaymentRequests(payment_requests) => payment_requests.view(),
+            Subroute::Connections(connections) => connections.view(),
+            Subroute::Import(import) => import.view(),
+            Subroute::OperationLog(operation_log) => operation_log.view(),
+            Subroute::SpendApproval(spend_approval) => spend_approval.view(),
+            Subroute::Transactions(transactions) => transactions.view(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubrouteName {
+    List,
+    FederationDetails(Arc<FederationView>),
+    Add,
+    Send,
+    BatchSend,
+    Receive,
+    SendEcash,
+    ReceiveEcash,
+    Stats,
+    PaymentDetails(i32),
+    PaymentRequests,
+    Connections,
+    Import,
+    OperationLog(Arc<FederationView>),
+    SpendApproval,
+    Transactions,
+}
+
+impl SubrouteName {
+    pub fn to_default_subroute(&self, connected_state: &ConnectedState) -> Subroute {
+        match self {
+            Self::List => Subroute::List(List {
+                search_input: String::new(),
+                search_query: String::new(),
+            }),
+            Self::FederationDetails(federation_view) => {
+                let thresholds =
+                    connected_state.get_balance_thresholds(&federation_view.federation_id);
+
+                let api_overrides = connected_state
+                    .wallet
+                    .get_api_overrides(&federation_view.federation_id);
+
+                Subroute::FederationDetails(FederationDetails {
+                    view: federation_view.clone(),
+                    low_balance_threshold_input: amount_to_sats_input(thresholds.low_or),
+                    high_balance_threshold_input: amount_to_sats_input(thresholds.high_or),
+                    max_balance_input: amount_to_sats_input(thresholds.max_or),
+                    // TODO: Log a warning if the default federation fails to load.
+                    is_default: connected_state.db.get_default_federation().ok().flatten()
+                        == Some(federation_view.federation_id),
+                    pinned_gateway_id_or: connected_state
+                        .wallet
+                        .get_pinned_gateway(&federation_view.federation_id),
+                    // TODO: Log a warning if the fee history fails to load.
+                    gateway_fee_history: connected_state
+                        .db
+                        .list_gateway_fee_history(&federation_view.federation_id)
+                        .unwrap_or_default(),
+                    // TODO: Log a warning if the notes fail to load.
+                    notes: text_editor::Content::with_text(
+                        &connected_state
+                            .db
+                            .get_note(NoteSubject::Federation(federation_view.federation_id))
+                            .unwrap_or_default(),
+                    ),
+                    show_advanced_settings: !api_overrides.is_empty(),
+                    api_secret_input: api_overrides.api_secret_or.unwrap_or_default(),
+                })
+            }
+            // The invite code draft is parsed once the page is shown. See `Route::update()`.
+            Self::Add => Subroute::Add(Add {
+                federation_invite_code: connected_state.drafts.federation_invite_code.clone(),
+                parsed_federation_invite_code_state_or: None,
+            }),
+            Self::Send => Subroute::Send(send::Page::new(connected_state)),
+            Self::BatchSend => Subroute::BatchSend(batch_send::Page::new(connected_state)),
+            Self::Receive => Subroute::Receive(receive::Page::new(connected_state)),
+            Self::SendEcash => Subroute::SendEcash(send_ecash::Page::new(connected_state)),
+            Self::ReceiveEcash => Subroute::ReceiveEcash(receive_ecash::Page::new(connected_state)),
+            Self::Stats => Subroute::Stats(stats::Page::new(connected_state)),
+            Self::PaymentDetails(payment_id) => {
+                Subroute::PaymentDetails(payment_details::Page::new(connected_state, *payment_id))
+            }
+            Self::PaymentRequests => {
+                Subroute::PaymentRequests(payment_requests::Page::new(connected_state))
+            }
+            Self::Connections => Subroute::Connections(connections::Page::new(connected_state)),
+            Self::Import => Subroute::Import(import::Page::new(connected_state)),
+            // The operations are loaded once the page is shown. See `Route::update()`.
+            Self::OperationLog(federation_view) => Subroute::OperationLog(
+                operation_log::Page::new(connected_state, federation_view.clone()),
+            ),
+            Self::SpendApproval => {
+                Subroute::SpendApproval(spend_approval::Page::new(connected_state))
+            }
+            // New transactions are synced once the page is shown. See `Route::update()`.
+            Self::Transactions => Subroute::Transactions(transactions::Page::new(connected_state)),
+        }
+    }
+}
+
+pub enum Subroute {
+    List(List),
+    FederationDetails(FederationDetails),
+    Add(Add),
+    Send(send::Page),
+    BatchSend(batch_send::Page),
+    Receive(receive::Page),
+    SendEcash(send_ecash::Page),
+    ReceiveEcash(receive_ecash::Page),
+    Stats(stats::Page),
+    PaymentDetails(payment_details::Page),
+    PaymentRequests(payment_requests::Page),
+    Connections(connections::Page),
+    Import(import::Page),
+    OperationLog(operation_log::Page),
+    SpendApproval(spend_approval::Page),
+    Transactions(transactions::Page),
+}
+
+impl Subroute {
+    pub fn to_name(&self) -> SubrouteName {
+        match self {
+            Self::List(_) => SubrouteName::List,
+            Self::FederationDetails(federation_details) => {
+                SubrouteName::FederationDetails(federation_details.view.clone())
+            }
+            Self::Add(_) => SubrouteName::Add,
+            Self::Send(_) => SubrouteName::Send,
+            Self::BatchSend(_) => SubrouteName::BatchSend,
+            Self::Receive(_) => SubrouteName::Receive,
+            Self::SendEcash(_) => SubrouteName::SendEcash,
+            Self::ReceiveEcash(_) => SubrouteName::ReceiveEcash,
+            Self::Stats(_) => SubrouteName::Stats,
+            Self::PaymentDetails(payment_details) => {
+                SubrouteName::PaymentDetails(payment_details.payment_id())
+            }
+            Self::PaymentRequests(_) => SubrouteName::PaymentRequests,
+            Self::Connections(_) => SubrouteName::Connections,
+            Self::Import(_) => SubrouteName::Import,
+            Self::OperationLog(operation_log) => {
+                SubrouteName::OperationLog(operation_log.federation_view())
+            }
+            Self::SpendApproval(_) => SubrouteName::SpendApproval,
+            Self::Transactions(_) => SubrouteName::Transactions,
+        }
+    }
+}
+
+pub struct List {
+    search_input: String,
+    // The search input, applied once the user stops typing.
+    search_query: String,
+}
+
+impl List {
+    fn view<'a>(&self, connected_state: &ConnectedState) -> Column<'a, app::Message> {
+        let mut container = container("Wallet");
+
+        match &connected_state.loadable_wallet_view {
+            Loadable::Loading => {
+                container = container.push(Text::new("Loading federations...").size(25));
+            }
+            Loadable::Loaded(wallet_view) => {
+                container =
+                    container
+                        .push(
+                            Text::new(format_amount(Amount::from_msats(
+                                wallet_view
+                                    .federations
+                                    .values()
+                                    .map(|view| view.balance.msats)
+                                    .sum::<u64>(),
+                            )))
+                            .size(35),
+                        )
+                        .push(row![
+                            icon_button("Send", SvgIcon::ArrowUpward, PaletteColor::Primary)
+                                .on_press(app::Message::Routes(super::Message::Navigate(
+                                    RouteName::BitcoinWallet(SubrouteName::Send)
+                                ))),
+                            Space::with_width(10.0),
+                            icon_button("Receive", SvgIcon::ArrowDownward, PaletteColor::Primary)
+                                .on_press(app::Message::Routes(super::Message::Navigate(
+                                    RouteName::BitcoinWallet(SubrouteName::Receive)
+                                ))),
+                            Space::with_width(10.0),
+                            icon_button("E-Cash", SvgIcon::ArrowUpward, PaletteColor::Primary)
+                                .on_press(app::Message::Routes(super::Message::Navigate(
+                                    RouteName::BitcoinWallet(SubrouteName::SendEcash)
+                                ))),
+                            Space::with_width(10.0),
+                            icon_button(
+                                "Redeem E-Cash",
+                                SvgIcon::ArrowDownward,
+                                PaletteColor::Primary
+                            )
+                            .on_press(app::Message::Routes(
+                                super::Message::Navigate(RouteName::BitcoinWallet(
+                                    SubrouteName::ReceiveEcash
+                                ))
+                            )),
+                            Space::with_width(10.0),
+                            icon_button("Transactions", SvgIcon::Hub, PaletteColor::Primary)
+                                .on_press(app::Message::Routes(super::Message::Navigate(
+                                    RouteName::BitcoinWallet(SubrouteName::Transactions)
+                                ))),
+                            Space::with_width(10.0),
+                            icon_button("Statistics", SvgIcon::Info, PaletteColor::Primary)
+                                .on_press(app::Message::Routes(super::Message::Navigate(
+                                    RouteName::BitcoinWallet(SubrouteName::Stats)
+                                ))),
+                            Space::with_width(10.0),
+                            icon_button("Payment Requests", SvgIcon::Hub, PaletteColor::Primary)
+                                .on_press(app::Message::Routes(super::Message::Navigate(
+                                    RouteName::BitcoinWallet(SubrouteName::PaymentRequests)
+                                )))
+                        ])
+                        .push_maybe(
+                            connected_state
+                                .settings
+                                .get()
+                                .refresh_balances_on_demand
+                                .then(refresh_on_demand_view),
+                        )
+                        .push(Text::new("Federations").size(25))
+                        .push(
+                            text_input("Search by federation name", &self.search_input)
+                                .on_input(|input| {
+                                    app::Message::Routes(super::Message::BitcoinWalletPage(
+                                        Message::FederationSearchInputChanged(input),
+                                    ))
+                                })
+                                .padding(10)
+                                .size(20),
+                        );
+
+                let federation_views = rank_by_fuzzy_match(
+                    &self.search_query,
+                    wallet_view.federations.values().collect(),
+                    |view| view.metadata.name_or.clone().unwrap_or_default(),
+                );
+
+                for view in federation_views {
+                    let column: Column<_, Theme, _> = Column::new()
+                        .push(
+                            Text::new(
+                                view.metadata
+                                    .name_or
+                                    .clone()
+                                    .unwrap_or_else(|| "Unnamed Federation".to_string()),
+                            )
+                            .size(25),
+                        )
+                        .push(Text::new(format_amount(view.balance)));
+
+                    container = container.push(
+                        Container::new(row![
+                            column,
+                            horizontal_space(),
+                            icon_button("Details", SvgIcon::ChevronRight, PaletteColor::Background)
+                                .on_press(app::Message::Routes(super::Message::Navigate(
+                                    RouteName::BitcoinWallet(SubrouteName::FederationDetails(
+                                        view.clone()
+                                    ))
+                                )))
+                        ])
+                        .padding(10)
+                        .width(Length::Fill)
+                        .style(federation_card_style),
+                    );
+                }
+
+                // Federations that haven't connected yet, such as right after unlocking or
+                // while offline, are shown by the metadata cached when they last connected.
+                let disconnected_federations = rank_by_fuzzy_match(
+                    &self.search_query,
+                    wallet_view
+                        .disconnected_federation_ids
+                        .iter()
+                        .map(|federation_id| {
+                            let name_or = connected_state
+                                .federation_metadata
+                                .get(federation_id)
+                                .and_then(|metadata| metadata.name_or.clone());
+
+                            (federation_id, name_or)
+                        })
+                        .collect(),
+                    |(_, name_or)| name_or.clone().unwrap_or_default(),
+                );
+
+                for (federation_id, name_or) in disconnected_federations {
+                    let column: Column<_, Theme, _> = Column::new()
+                        .push(
+                            Text::new(name_or.unwrap_or_else(|| {
+                                truncate_text(&federation_id.to_string(), 21, true)
+                            }))
+                            .size(25),
+                        )
+                        .push(Text::new("Not connected"));
+
+                    container = container.push(
+                        Container::new(column)
+                            .padding(10)
+                            .width(Length::Fill)
+                            .style(federation_card_style),
+                    );
+                }
+            }
+            Loadable::Failed => {
+                container =
+                    container.push(Text::new("Failed to load federation config views.").size(25));
+            }
+        }
+
+        container = container
+            .push(
+                icon_button("Join Federation", SvgIcon::Add, PaletteColor::Primary).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::BitcoinWallet(
+                        SubrouteName::Add,
+                    ))),
+                ),
+            )
+            .push(
+                icon_button(
+                    "Import Federation",
+                    SvgIcon::FileCopy,
+                    PaletteColor::Background,
+                )
+                .on_press(app::Message::Routes(super::Message::Navigate(
+                    RouteName::BitcoinWallet(SubrouteName::Import),
+                ))),
+            )
+            .push(
+                icon_button("Spend Approval", SvgIcon::Lock, PaletteColor::Background).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::BitcoinWallet(
+                        SubrouteName::SpendApproval,
+                    ))),
+                ),
+            );
+
+        container
+    }
+}
+
+fn federation_card_style(theme: &Theme) -> Style {
+    Style {
+        text_color: None,
+        background: Some(lighten(theme.palette().background, 0.05).into()),
+        border: Border {
+            color: iced::Color::WHITE,
+            width: 0.0,
+            radius: (8.0).into(),
+        },
+        shadow: Shadow::default(),
+    }
+}
+
+pub struct FederationDetails {
+    view: Arc<FederationView>,
+    low_balance_threshold_input: String,
+    high_balance_threshold_input: String,
+    max_balance_input: String,
+    is_default: bool,
+    pinned_gateway_id_or: Option<GatewayId>,
+    gateway_fee_history: BTreeMap<GatewayId, Vec<GatewayFeeSample>>,
+    notes: text_editor::Content,
+    show_advanced_settings: bool,
+    api_secret_input: String,
+}
+
+impl FederationDetails {
+    fn view<'a>(&'a self, connected_state: &ConnectedState) -> Column<'a, app::Message> {
+        let mut container = container("Federation Details")
+            .push(
+                Text::new(
+                    self.view
+                        .metadata
+                        .name_or
+                        .clone()
+                        .unwrap_or_else(|| "Unnamed Federation".to_string()),
+                )
+                .size(25),
+            )
+            .push(Text::new(format!(
+                "Federation ID: {}",
+                truncate_text(&self.view.federation_id.to_string(), 23, true)
+            )))
+            .push(Text::new(format!(
+                "Guardians: {}",
+                self.view.metadata.guardian_names.join(", ")
+            )))
+            .push(Text::new(format!(
+                "Modules: {}",
+                self.view.metadata.module_kinds.join(", ")
+            )))
+            .push(Text::new(format_amount(self.view.balance)));
+
+        container = if self.is_default {
+            container
+                .push(Text::new(
+                    "This is your default federation. It's used for receiving and by apps connected with Wallet Connect.",
+                ))
+                .push(
+                    icon_button("Unset as Default", SvgIcon::Close, PaletteColor::Background)
+                        .on_press(app::Message::Routes(super::Message::BitcoinWalletPage(
+                            Message::SetDefaultFederation(None),
+                        ))),
+                )
+        } else {
+            container.push(
+                icon_button("Set as Default", SvgIcon::Save, PaletteColor::Primary).on_press(
+                    app::Message::Routes(super::Message::BitcoinWalletPage(
+                        Message::SetDefaultFederation(Some(self.view.federation_id)),
+                    )),
+                ),
+            )
+        };
+
+        let announcements = &self.view.announcements;
+
+        let expiry_message_or = announcements.expiry_message();
+
+        if announcements.welcome_message_or.is_some()
+            || announcements.notice_or.is_some()
+            || expiry_message_or.is_some()
+        {
+            container = container.push(Text::new("Announcements").size(20));
+
+            for announcement in [
+                announcements.notice_or.clone(),
+                expiry_message_or,
+                announcements.welcome_message_or.clone(),
+            ]
+            .into_iter()
+            .flatten()
+            {
+                container = container.push(
+                    Container::new(Text::new(announcement))
+                        .padding(10)
+                        .width(Length::Fill)
+                        .style(|theme| -> Style {
+                            Style {
+                                text_color: None,
+                                background: Some(lighten(theme.palette().background, 0.05).into()),
+                                border: Border {
+                                    color: iced::Color::WHITE,
+                                    width: 0.0,
+                                    radius: (8.0).into(),
+                                },
+                                shadow: Shadow::default(),
+                            }
+                        }),
+                );
+            }
+        }
+
+        container = container.push(Text::new("Gateways").size(20)).push(Text::new(
+            "Lightning payments go through a gateway. Vetted gateways are vouched for by the federation's guardians. Pin a gateway to always use it with this federation while the federation lists it. If a payment through the pinned gateway fails and is refunded, it's retried once through another gateway. Otherwise, gateways that have charged much more than others are avoided.",
+        ));
+
+        if let Some(pinned_gateway_id) = self.pinned_gateway_id_or {
+            if !self
+                .view
+                .gateways
+                .iter()
+                .any(|gateway| gateway.info.gateway_id == pinned_gateway_id)
+            {
+                container = container
+                    .push(Text::new(format!(
+                        "The pinned gateway {} isn't listed by this federation right now, so gateways are picked automatically.",
+                        truncate_text(&pinned_gateway_id.to_string(), 23, true)
+                    )))
+                    .push(
+                        icon_button("Unpin", SvgIcon::Close, PaletteColor::Background).on_press(
+                            app::Message::Routes(super::Message::BitcoinWalletPage(
+                                Message::PinGateway(self.view.federation_id, None),
+                            )),
+                        ),
+                    );
+            }
+        }
+
+        for gateway in &self.view.gateways {
+            let vetted_text = if gateway.vetted {
+                "Vetted"
+            } else {
+                "Not Vetted"
+            };
+
+            let is_pinned = self.pinned_gateway_id_or == Some(gateway.info.gateway_id);
+
+            let column: Column<_, Theme, _> = column![
+                Text::new(format!(
+                    "Gateway ID: {}",
+                    truncate_text(&gateway.info.gateway_id.to_string(), 43, true)
+                )),
+                Text::new(format!(
+                    "Lightning Node Alias: {}",
+                    truncate_text(&gateway.info.lightning_alias.to_string(), 43, true)
+                )),
+                Text::new(format!(
+                    "Lightning Node Public Key: {}",
+                    truncate_text(&gateway.info.node_pub_key.to_string(), 43, true)
+                )),
+                Text::new(format!(
+                    "Fees: {} + {}",
+                    format_amount(Amount::from_msats(u64::from(gateway.info.fees.base_msat))),
+                    format_proportional_fee(gateway.info.fees.proportional_millionths)
+                )),
+                Text::new(vetted_text),
+            ]
+            .push_maybe(
+                self.gateway_fee_history
+                    .get(&gateway.info.gateway_id)
+                    .map(Vec::as_slice)
+                    .map(gateway_fee_history_view),
+            )
+            .push(if is_pinned {
+                icon_button("Unpin", SvgIcon::Close, PaletteColor::Background).on_press(
+                    app::Message::Routes(super::Message::BitcoinWalletPage(Message::PinGateway(
+                        self.view.federation_id,
+                        None,
+                    ))),
+                )
+            } else {
+                icon_button("Pin", SvgIcon::Save, PaletteColor::Primary).on_press(
+                    app::Message::Routes(super::Message::BitcoinWalletPage(Message::PinGateway(
+                        self.view.federation_id,
+                        Some(gateway.info.gateway_id),
+                    ))),
+                )
+            });
+
+            container = container.push(
+                Container::new(column)
+                    .padding(10)
+                    .width(Length::Fill)
+                    .style(|theme| -> Style {
+                        Style {
+                            text_color: None,
+                            background: Some(lighten(theme.palette().background, 0.05).into()),
+                            border: Border {
+                                color: iced::Color::WHITE,
+                                width: 0.0,
+                                radius: (8.0).into(),
+                            },
+                            shadow: Shadow::default(),
+                        }
+                    }),
+            );
+        }
+
+        container = container
+            .push(Text::new("Balance Alerts").size(20))
+            .push(
+                text_input(
+                    "Low balance alert (sats)",
+                    &self.low_balance_threshold_input,
+                )
+                .on_input(|input| {
+                    app::Message::Routes(super::Message::BitcoinWalletPage(
+                        Message::LowBalanceThresholdInputChanged(input),
+                    ))
+                })
+                .padding(10)
+                .size(20),
+            )
+            .push(
+                text_input(
+                    "High balance alert (sats)",
+                    &self.high_balance_threshold_input,
+                )
+                .on_input(|input| {
+                    app::Message::Routes(super::Message::BitcoinWalletPage(
+                        Message::HighBalanceThresholdInputChanged(input),
+                    ))
+                })
+                .padding(10)
+                .size(20),
+            )
+            .push_maybe(self.has_inverted_balance_thresholds().then(|| {
+                Text::new("The low balance alert has to be at or below the high balance alert.")
+            }))
+            .push(Text::new("Maximum Balance").size(20))
+            .push(Text::new(
+                "Invoices that would take your balance in this federation above this amount can't be created.",
+            ))
+            .push(
+                text_input("Maximum balance (sats)", &self.max_balance_input)
+                    .on_input(|input| {
+                        app::Message::Routes(super::Message::BitcoinWalletPage(
+                            Message::MaxBalanceInputChanged(input),
+                        ))
+                    })
+                    .padding(10)
+                    .size(20),
+            )
+            .push(
+                icon_button("Save Balance Limits", SvgIcon::Save, PaletteColor::Primary)
+                    .on_press_maybe(self.parse_balance_thresholds().map(|thresholds| {
+                        app::Message::Routes(super::Message::BitcoinWalletPage(
+                            Message::SaveBalanceThresholds(self.view.federation_id, thresholds),
+                        ))
+                    })),
+            )
+            .push(Text::new("Notes").size(20))
+            .push(Text::new(
+                "Only you can see these notes, such as why you joined or who the guardians are. They're kept encrypted along with the rest of your data.",
+            ))
+            .push(
+                text_editor(&self.notes)
+                    .placeholder("Notes about this federation")
+                    .on_action(|action| {
+                        app::Message::Routes(super::Message::BitcoinWalletPage(
+                            Message::NotesEdited(action),
+                        ))
+                    })
+                    .height(150)
+                    .padding(10),
+            )
+            .push(
+                icon_button("Save Notes", SvgIcon::Save, PaletteColor::Primary).on_press(
+                    app::Message::Routes(super::Message::BitcoinWalletPage(Message::SaveNotes(
+                        self.view.federation_id,
+                    ))),
+                ),
+            )
+            .push(
+                icon_button(
+                    if self.show_advanced_settings {
+                        "Hide Advanced Settings"
+                    } else {
+                        "Show Advanced Settings"
+                    },
+                    SvgIcon::Settings,
+                    PaletteColor::Background,
+                )
+                .on_press(app::Message::Routes(
+                    super::Message::BitcoinWalletPage(Message::ToggleAdvancedFederationSettings),
+                )),
+            );
+
+        if self.show_advanced_settings {
+            container = container.push(self.advanced_settings_view());
+        }
+
+        // TODO: Add a function to `Wallet` to check whether we can safely leave a federation.
+        // Call it here rather and get rid of `has_zero_balance`.
+        let has_zero_balance = self.view.balance.msats == 0;
+
+        if !has_zero_balance {
+            container = container.push(
+                Text::new("Must have a zero balance in this federation in order to leave.")
+                    .size(20),
+            );
+        }
+
+        let progress_or = connected_state
+            .federation_operations
+            .get(&self.view.federation_id)
+            .copied();
+
+        container = container.push_maybe(progress_or.map(federation_operation_progress_view));
+
+        container = container.push(
+            icon_button("Leave Federation", SvgIcon::Delete, PaletteColor::Danger).on_press_maybe(
+                (has_zero_balance && progress_or.is_none()).then(|| {
+                    app::Message::Routes(super::Message::BitcoinWalletPage(
+                        Message::LeaveFederation(self.view.federation_id),
+                    ))
+                }),
+            ),
+        );
+
+        container = container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(super::Message::Navigate(RouteName::BitcoinWallet(
+                    SubrouteName::List,
+                ))),
+            ),
+        );
+
+        container
+    }
+
+    /// Parses the threshold inputs. Empty inputs clear their threshold.
+    /// Returns `None` if any input isn't a valid number of sats,
+    /// or if the low threshold is above the high threshold.
+    fn parse_balance_thresholds(&self) -> Option<BalanceThresholds> {
+        Some(BalanceThresholds {
+            low_or: sats_input_to_amount(&self.low_balance_threshold_input)?,
+            high_or: sats_input_to_amount(&self.high_balance_threshold_input)?,
+            max_or: sats_input_to_amount(&self.max_balance_input)?,
+        })
+        .filter(BalanceThresholds::is_valid)
+    }
+
+    /// Whether both alert inputs are valid amounts, but the low one is above the high one.
+    fn has_inverted_balance_thresholds(&self) -> bool {
+        let (Some(low_or), Some(high_or)) = (
+            sats_input_to_amount(&self.low_balance_threshold_input),
+            sats_input_to_amount(&self.high_balance_threshold_input),
+        ) else {
+            return false;
+        };
+
+        !BalanceThresholds {
+            low_or,
+            high_or,
+            max_or: None,
+        }
+        .is_valid()
+    }
+
+    fn advanced_settings_view(&self) -> Column<app::Message> {
+        Column::new()
+            .push(Text::new("Guardian Connection").size(20))
+            .push(Text::new(
+                "Only change this if the federation's guardians have told you to. The API secret is sent to the guardians with every request, for federations that require one. Clearing it keeps using the last secret that was set. Saving reconnects to the federation.",
+            ))
+            .push(
+                text_input("API secret", &self.api_secret_input)
+                    .on_input(|input| {
+                        app::Message::Routes(super::Message::BitcoinWalletPage(
+                            Message::ApiSecretInputChanged(input),
+                        ))
+                    })
+                    .secure(true)
+                    .padding(10)
+                    .size(20),
+            )
+            .push(
+                icon_button(
+                    "Save Connection Settings",
+                    SvgIcon::Save,
+                    PaletteColor::Primary,
+                )
+                .on_press(app::Message::Routes(
+                    super::Message::BitcoinWalletPage(Message::SaveApiOverrides(
+                        self.view.federation_id,
+                        self.parse_api_overrides(),
+                    )),
+                )),
+            )
+            .push(Text::new("Debugging").size(20))
+            .push(
+                icon_button("Operation Log", SvgIcon::Info, PaletteColor::Background).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::BitcoinWallet(
+                        SubrouteName::OperationLog(self.view.clone()),
+                    ))),
+                ),
+            )
+            .spacing(20)
+            .align_x(Alignment::Center)
+    }
+
+    /// Parses the guardian connection input. An empty API secret input clears the secret.
+    fn parse_api_overrides(&self) -> FederationApiOverrides {
+        let api_secret = self.api_secret_input.trim();
+
+        FederationApiOverrides {
+            api_secret_or: (!api_secret.is_empty()).then(|| api_secret.to_string()),
+        }
+    }
+}
+
+/// Offers to refresh balances, which otherwise stay as they are while
+/// [`crate::config::Settings::refresh_balances_on_demand`] is set.
+fn refresh_on_demand_view<'a>() -> Row<'a, app::Message> {
+    row![
+        Text::new("Balances only refresh when you ask, or after a payment."),
+        Space::with_width(10.0),
+        icon_button("Refresh", SvgIcon::Refresh, PaletteColor::Background).on_press(
+            app::Message::Routes(super::Message::BitcoinWalletPage(
+                Message::RefreshWalletView
+            )),
+        ),
+    ]
+    .align_y(Alignment::Center)
+}
+
+/// The view of the default federation, if one is set and it has been joined.
+fn get_default_federation_view(connected_state: &ConnectedState) -> Option<Arc<FederationView>> {
+    // TODO: Log a warning if the default federation fails to load.
+    let default_federation_id = connected_state.db.get_default_federation().ok()??;
+
+    connected_state
+        .loadable_wallet_view
+        .as_ref_option()?
+        .federations
+        .get(&default_federation_id)
+        .cloned()
+}
+
+/// A checkbox that switches an amount input between bitcoin and fiat, along with what the
+/// typed amount is worth in the other unit. `None` if the input is in bitcoin and there's
+/// no exchange rate, since amounts can't be entered in fiat without one.
+fn fiat_amount_toggle<'a>(
+    amount_or: Option<Amount>,
+    is_fiat_input: bool,
+    exchange_rate_or: Option<ExchangeRate>,
+    on_toggle: impl Fn(bool) -> app::Message + 'a,
+) -> Option<Row<'a, app::Message>> {
+    if exchange_rate_or.is_none() && !is_fiat_input {
+        return None;
+    }
+
+    let currency_label = exchange_rate_or.map_or_else(
+        || "fiat".to_string(),
+        |exchange_rate| exchange_rate.currency.to_string(),
+    );
+
+    let converted_text = match exchange_rate_or {
+        None => "The price of bitcoin hasn't been fetched yet.".to_string(),
+        Some(exchange_rate) => match amount_or {
+            Some(amount) if is_fiat_input => format!("= {}", format_amount(amount)),
+            Some(amount) => format!("≈ {}", exchange_rate.format_fiat(amount)),
+            None => String::new(),
+        },
+    };
+
+    Some(
+        row![
+            checkbox(format!("Enter in {currency_label}"), is_fiat_input).on_toggle(on_toggle),
+            Text::new(converted_text),
+        ]
+        .spacing(20)
+        .align_y(Alignment::Center),
+    )
+}
+
+/// Reads an amount typed in fiat, converted to bitcoin at `exchange_rate_or`.
+fn fiat_input_to_amount(input: &str, exchange_rate_or: Option<ExchangeRate>) -> Option<Amount> {
+    exchange_rate_or?.to_amount(parse_fiat_input(input)?)
+}
+
+// LNURL endpoints that take longer than this to answer are treated as unreachable.
+const LNURL_FETCH_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Fetches an LNURL endpoint's JSON response.
+async fn fetch_lnurl(url: Url) -> anyhow::Result<String> {
+    Ok(reqwest::Client::builder()
+        .timeout(LNURL_FETCH_TIMEOUT)
+        .build()?
+        .get(url.as_str())
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?)
+}
+
+/// Formats a fee in parts per million as a percentage, such as `0.05%`.
+/// How many of a gateway's most recent payments are charted in its fee trend.
+const GATEWAY_FEE_TREND_PAYMENT_COUNT: usize = 30;
+
+/// The fees actually paid through a gateway, with a chart of
+/// the fee rate of each recent payment from oldest to newest.
+fn gateway_fee_history_view<'a>(fee_samples: &[GatewayFeeSample]) -> Column<'a, app::Message> {
+    let mut fee_stats = GatewayFeeStats::default();
+    for fee_sample in fee_samples {
+        fee_stats.record(fee_sample.amount, fee_sample.fee);
+    }
+
+    let recent_fee_rates = fee_samples[fee_samples
+        .len()
+        .saturating_sub(GATEWAY_FEE_TREND_PAYMENT_COUNT)..]
+        .iter()
+        .map(GatewayFeeSample::fee_rate_ppm)
+        .collect();
+
+    Column::new()
+        .push(Text::new(format!(
+            "Fees Paid: {} in {} payments ({} on average)",
+            format_amount(fee_stats.fee),
+            fee_stats.payment_count,
+            format_proportional_fee(u32::try_from(fee_stats.fee_rate_ppm()).unwrap_or(u32::MAX))
+        )))
+        .push(sparkline(recent_fee_rates, 30.0))
+        .spacing(5)
+}
+
+fn format_proportional_fee(proportional_millionths: u32) -> String {
+    let whole_percent = proportional_millionths / 10_000;
+    let fractional_percent = format!("{:04}", proportional_millionths % 10_000);
+    let fractional_percent = fractional_percent.trim_end_matches('0');
+
+    if fractional_percent.is_empty() {
+        format!("{whole_percent}%")
+    } else {
+        format!("{whole_percent}.{fractional_percent}%")
+    }
+}
+
+fn federation_operation_progress_view<'a>(
+    progress: FederationOperationProgress,
+) -> Column<'a, app::Message> {
+    let (step, step_count) = progress.step();
+
+    Column::new()
+        .push(Text::new(progress.to_string()))
+        .push(progress_bar(0.0..=f32::from(step_count), f32::from(step)).height(10))
+        .spacing(5)
+}
+
+fn amount_to_sats_input(amount_or: Option<Amount>) -> String {
+    amount_or
+        .map(|amount| (amount.msats / 1000).to_string())
+        .unwrap_or_default()
+}
+
+/// Parses a whole number of sats from a text input. Returns `Some(None)` for an empty input,
+/// and `None` if the input isn't a valid number.
+fn sats_input_to_amount(input: &str) -> Option<Option<Amount>> {
+    if input.is_empty() {
+        return Some(None);
+    }
+
+    input.parse().ok().map(|sats| Some(Amount::from_sats(sats)))
+}
+
+pub struct Add {
+    federation_invite_code: String,
+    parsed_federation_invite_code_state_or: Option<ParsedFederationInviteCodeState>,
+}
+
+pub struct ParsedFederationInviteCodeState {
+    invite_code: InviteCode,
+    loadable_federation_config: Loadable<ClientConfig>,
+}
+
+impl Add {
+    fn view<'a>(&self, connected_state: &ConnectedState) -> Column<'a, app::Message> {
+        let progress_or = self
+            .parsed_federation_invite_code_state_or
+            .as_ref()
+            .and_then(|parsed_federation_invite_code_state| {
+                connected_state.federation_operations.get(
+                    &parsed_federation_invite_code_state
+                        .invite_code
+                        .federation_id(),
+                )
+            })
+            .copied();
+
+        // The same federation can be reached through different invite codes,
+        // such as ones pointing at different guardians.
+        let joined_federation_or = self
+            .parsed_federation_invite_code_state_or
+            .as_ref()
+            .zip(connected_state.loadable_wallet_view.as_ref_option())
+            .and_then(|(parsed_federation_invite_code_state, wallet_view)| {
+                wallet_view.federations.get(
+                    &parsed_federation_invite_code_state
+                        .invite_code
+                        .federation_id(),
+                )
+            })
+            .filter(|_| progress_or.is_none())
+            .cloned();
+
+        let mut container = container("Join Federation")
+            .push(bech32_input(
+                "Federation Invite Code",
+                &self.federation_invite_code,
+                &[Bech32Kind::FederationInviteCode],
+                |input| {
+                    app::Message::Routes(super::Message::BitcoinWalletPage(
+                        Message::JoinFederationInviteCodeInputChanged(input),
+                    ))
+                },
+            ))
+            .push(
+                icon_button("Clear", SvgIcon::Close, PaletteColor::Background).on_press_maybe(
+                    (!self.federation_invite_code.is_empty()).then_some(app::Message::Routes(
+                        super::Message::BitcoinWalletPage(Message::ClearJoinFederationInviteCode),
+                    )),
+                ),
+            )
+            .push(
+                icon_button("Join Federation", SvgIcon::Groups, PaletteColor::Primary)
+                    .on_press_maybe(
+                        self.parsed_federation_invite_code_state_or
+                            .as_ref()
+                            .filter(|_| {
+                                progress_or.is_none()
+                                    && joined_federation_or.is_none()
+                                    && !connected_state.is_offline()
+                            })
+                            .map(|parsed_federation_invite_code_state| {
+                                app::Message::Routes(super::Message::BitcoinWalletPage(
+                                    Message::JoinFederation(
+                                        parsed_federation_invite_code_state.invite_code.clone(),
+                                    ),
+                                ))
+                            }),
+                    ),
+            )
+            .push_maybe(progress_or.map(federation_operation_progress_view));
+
+        if let Some(joined_federation) = joined_federation_or {
+            container = container
+                .push(Text::new(format!(
+                    "You've already joined this federation{}. This invite code leads to the same federation, so there's nothing new to join.",
+                    joined_federation
+                        .metadata
+                        .name_or
+                        .as_ref()
+                        .map(|name| format!(" as {name}"))
+                        .unwrap_or_default()
+                )))
+                .push(
+                    icon_button("Open Federation", SvgIcon::ChevronRight, PaletteColor::Primary)
+                        .on_press(app::Message::Routes(super::Message::Navigate(
+                            RouteName::BitcoinWallet(SubrouteName::FederationDetails(
+                                joined_federation,
+                            )),
+                        ))),
+                );
+        }
+
+        if let Some(parsed_federation_invite_code_state) =
+            &self.parsed_federation_invite_code_state_or
+        {
+            container = container
+                .push(Text::new("Federation ID").size(25))
+                .push(Text::new(truncate_text(
+                    &parsed_federation_invite_code_state
+                        .invite_code
+                        .federation_id()
+                        .to_string(),
+                    21,
+                    true,
+                )));
+
+            match &parsed_federation_invite_code_state.loadable_federation_config {
+                Loadable::Loading => {
+                    container = container.push(Text::new("Loading..."));
+                }
+                Loadable::Loaded(client_config) => {
+                    container = container
+                        .push(Text::new("Federation Name").size(25))
+                        .push(Text::new(
+                            client_config
+                                .meta::<String>(META_FEDERATION_NAME_KEY)
+                                .ok()
+                                .flatten()
+                                .unwrap_or_default(),
+                        ))
+                        .push(Text::new("Modules").size(25))
+                        .push(Text::new(
+                            client_config
+                                .modules
+                                .values()
+                                .map(|module| module.kind().to_string())
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                        ))
+                        .push(Text::new("Guardians").size(25));
+                    for peer_url in client_config.global.api_endpoints.values() {
+                        container = container
+                            .push(Text::new(format!("{} ({})", peer_url.name, peer_url.url)));
+                    }
+                }
+                Loadable::Failed => {
+                    container = container.push(Text::new("Failed to load client config"));
+                }
+            }
+        }
+
+        container = container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(super::Message::Navigate(RouteName::BitcoinWallet(
+                    SubrouteName::List,
+                ))),
+            ),
+        );
+
+        container
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_proportional_fee() {
+        assert_eq!(format_proportional_fee(0), "0%");
+        assert_eq!(format_proportional_fee(1), "0.0001%");
+        assert_eq!(format_proportional_fee(500), "0.05%");
+        assert_eq!(format_proportional_fee(25_000), "2.5%");
+        assert_eq!(format_proportional_fee(1_000_000), "100%");
+    }
+}
@@ -0,0 +1,707 @@
+use std::{collections::BTreeSet, str::FromStr, sync::Arc};
+
+use fedimint_core::{config::FederationId, Amount};
+use iced::{
+    widget::{checkbox, combo_box, text_editor, Column, Text},
+    Task,
+};
+use lightning_invoice::Bolt11Invoice;
+use nostr_sdk::ToBech32;
+
+use crate::{
+    app,
+    db::{self, Database},
+    fedimint::{FederationView, PaymentDirection, PaymentSimulation, Wallet, WalletView},
+    in_flight::InFlightOperations,
+    nostr::NostrModule,
+    nwc::{self, NwcConnection, NwcConnectionRecord, PaymentRequest, PaymentRequestStatus},
+    routes::{self, container, RouteName},
+    ui_components::{
+        icon_button, normalize_bech32_input, PaletteColor, SvgIcon, Toast, ToastStatus,
+    },
+    util::{format_amount, truncate_text},
+};
+
+use super::{payment_requests::send_paid_response, ConnectedState, SubrouteName};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    // Batch input fields.
+    InvoicesEdited(text_editor::Action),
+    PaymentRequestToggled(i32, bool),
+    FederationComboBoxSelected(Arc<FederationView>),
+
+    // Batch actions.
+    PayBatch(FederationId),
+    ItemPaid(usize),
+    ItemFailed(usize, Arc<anyhow::Error>),
+    BatchFinished,
+    StartOver,
+
+    UpdateWalletView(WalletView),
+}
+
+/// An invoice in a batch, along with the payment request it came from, if any.
+#[derive(Debug, Clone)]
+struct BatchItem {
+    invoice: Bolt11Invoice,
+    payment_request_or: Option<PaymentRequest>,
+    status: BatchItemStatus,
+}
+
+#[derive(Debug, Clone)]
+enum BatchItemStatus {
+    Waiting,
+    Paid,
+    Failed(Arc<anyhow::Error>),
+}
+
+pub struct Page {
+    db: Arc<Database>,
+    wallet: Arc<Wallet>,
+    in_flight_operations: InFlightOperations,
+    nostr_module: NostrModule,
+    // Invoices pasted one per line.
+    invoices: text_editor::Content,
+    connections: Vec<NwcConnectionRecord>,
+    pending_payment_requests: Vec<PaymentRequest>,
+    selected_payment_request_ids: BTreeSet<i32>,
+    federation_combo_box_state: combo_box::State<Arc<FederationView>>,
+    federation_combo_box_selected_federation: Option<Arc<FederationView>>,
+    // The batch being paid, or the last batch paid, in the order its invoices are paid.
+    batch_items: Vec<BatchItem>,
+    is_paying: bool,
+}
+
+impl Page {
+    pub fn new(connected_state: &ConnectedState) -> Self {
+        let mut page = Self {
+            db: connected_state.db.clone(),
+            wallet: connected_state.wallet.clone(),
+            in_flight_operations: connected_state.in_flight_operations.clone(),
+            nostr_module: connected_state.nostr_module.clone(),
+            invoices: text_editor::Content::new(),
+            connections: Vec::new(),
+            pending_payment_requests: Vec::new(),
+            selected_payment_request_ids: BTreeSet::new(),
+            federation_combo_box_state: combo_box::State::new(
+                connected_state
+                    .loadable_wallet_view
+                    .as_ref_option()
+                    .cloned()
+                    .map(|wallet_view| wallet_view.federations)
+                    .unwrap_or_default()
+                    .into_values()
+                    .collect(),
+            ),
+            federation_combo_box_selected_federation: super::get_default_federation_view(
+                connected_state,
+            ),
+            batch_items: Vec::new(),
+            is_paying: false,
+        };
+
+        page.load_payment_requests();
+
+        page
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::InvoicesEdited(action) => {
+                self.invoices.perform(action);
+
+                Task::none()
+            }
+            Message::PaymentRequestToggled(id, is_selected) => {
+                if is_selected {
+                    self.selected_payment_request_ids.insert(id);
+                } else {
+                    self.selected_payment_request_ids.remove(&id);
+                }
+
+                Task::none()
+            }
+            Message::FederationComboBoxSelected(federation) => {
+                self.federation_combo_box_selected_federation = Some(federation);
+
+                Task::none()
+            }
+            Message::PayBatch(federation_id) => {
+                let (invoices, _unreadable_entries) = parse_invoices(&self.invoices.text());
+
+                let payment_request_items =
+                    self.selected_payment_requests()
+                        .into_iter()
+                        .map(|payment_request| BatchItem {
+                            invoice: payment_request.request.invoice.clone(),
+                            payment_request_or: Some(payment_request),
+                            status: BatchItemStatus::Waiting,
+                        });
+
+                self.batch_items = invoices
+                    .into_iter()
+                    .map(|invoice| BatchItem {
+                        invoice,
+                        payment_request_or: None,
+                        status: BatchItemStatus::Waiting,
+                    })
+                    .chain(payment_request_items)
+                    .collect();
+
+                let batch_id = match self.db.create_payment_batch(&federation_id) {
+                    Ok(batch_id) => batch_id,
+                    Err(err) => {
+                        self.batch_items.clear();
+
+                        return Task::done(app::Message::AddToast(Toast {
+                            title: "Failed to start batch".to_string(),
+                            body: err.to_string(),
+                            status: ToastStatus::Bad,
+                        }));
+                    }
+                };
+
+                self.is_paying = true;
+
+                self.pay_batch(federation_id, batch_id)
+            }
+            Message::ItemPaid(index) => {
+                if let Some(item) = self.batch_items.get_mut(index) {
+                    item.status = BatchItemStatus::Paid;
+                }
+
+                Task::none()
+            }
+            Message::ItemFailed(index, err) => {
+                if let Some(item) = self.batch_items.get_mut(index) {
+                    item.status = BatchItemStatus::Failed(err);
+                }
+
+                Task::none()
+            }
+            Message::BatchFinished => {
+                self.is_paying = false;
+
+                let paid_count = self
+                    .batch_items
+                    .iter()
+                    .filter(|item| matches!(item.status, BatchItemStatus::Paid))
+                    .count();
+                let failed_count = self.batch_items.len() - paid_count;
+
+                // Keep the pasted invoices that failed, so they can be retried.
+                self.invoices = text_editor::Content::with_text(
+                    &self
+                        .batch_items
+                        .iter()
+                        .filter(|item| {
+                            item.payment_request_or.is_none()
+                                && !matches!(item.status, BatchItemStatus::Paid)
+                        })
+                        .map(|item| item.invoice.to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                );
+                self.load_payment_requests();
+
+                Task::done(app::Message::AddToast(if failed_count == 0 {
+                    Toast {
+                        title: "Batch paid".to_string(),
+                        body: format!("All {paid_count} invoices were paid"),
+                        status: ToastStatus::Good,
+                    }
+                } else {
+                    Toast {
+                        title: "Batch partly paid".to_string(),
+                        body: format!("{paid_count} invoices were paid and {failed_count} failed"),
+                        status: ToastStatus::Bad,
+                    }
+                }))
+            }
+            Message::StartOver => {
+                self.batch_items.clear();
+
+                Task::none()
+            }
+            Message::UpdateWalletView(wallet_view) => {
+                self.federation_combo_box_selected_federation = self
+                    .federation_combo_box_selected_federation
+                    .as_ref()
+                    .and_then(|selected_federation| {
+                        wallet_view
+                            .federations
+                            .get(&selected_federation.federation_id)
+                            .cloned()
+                    });
+
+                self.federation_combo_box_state =
+                    combo_box::State::new(wallet_view.federations.into_values().collect());
+
+                Task::none()
+            }
+        }
+    }
+
+    /// Paying is disabled while `is_offline`, since it needs to reach the federation.
+    pub fn view(&self, is_offline: bool) -> Column<app::Message> {
+        let mut container = container("Batch Pay");
+
+        if self.wallet.get_payment_simulation() != PaymentSimulation::Disabled {
+            container = container.push(Text::new(
+                "Payment simulation is enabled in developer settings. No funds will be moved.",
+            ));
+        }
+
+        container = if self.batch_items.is_empty() {
+            self.batch_input_view(container, is_offline)
+        } else {
+            self.batch_summary_view(container)
+        };
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(routes::Message::Navigate(RouteName::BitcoinWallet(
+                    SubrouteName::Send,
+                ))),
+            ),
+        )
+    }
+
+    fn batch_input_view<'a>(
+        &'a self,
+        mut container: Column<'a, app::Message>,
+        is_offline: bool,
+    ) -> Column<'a, app::Message> {
+        let (invoices, unreadable_entries) = parse_invoices(&self.invoices.text());
+        let selected_payment_requests = self.selected_payment_requests();
+
+        // Fedimint clients can only pay invoices that specify an amount.
+        let amountless_invoice_count = invoices
+            .iter()
+            .filter(|invoice| invoice.amount_milli_satoshis().is_none())
+            .count();
+
+        let invoice_count = invoices.len() + selected_payment_requests.len();
+        let total = Amount::from_msats(
+            invoices
+                .iter()
+                .chain(
+                    selected_payment_requests
+                        .iter()
+                        .map(|payment_request| &payment_request.request.invoice),
+                )
+                .map(|invoice| invoice_amount(invoice).msats)
+                .sum(),
+        );
+
+        let pay_message_or = self
+            .federation_combo_box_selected_federation
+            .as_ref()
+            .filter(|_| {
+                invoice_count > 0
+                    && unreadable_entries.is_empty()
+                    && amountless_invoice_count == 0
+                    && !is_offline
+                    && !self.is_paying
+            })
+            .map(|federation| send_message(Message::PayBatch(federation.federation_id)));
+
+        container = container
+            .push(Text::new(
+                "Invoices are paid one at a time, in order. Payments can't be undone, so a failed payment doesn't stop the rest of the batch.",
+            ))
+            .push(Text::new("Invoices").size(25))
+            .push(
+                text_editor(&self.invoices)
+                    .placeholder("Paste invoices, one per line")
+                    .on_action(|action| send_message(Message::InvoicesEdited(action)))
+                    .height(150)
+                    .padding(10),
+            )
+            .push_maybe((!unreadable_entries.is_empty()).then(|| {
+                Text::new(format!(
+                    "These aren't lightning invoices: {}",
+                    unreadable_entries
+                        .iter()
+                        .map(|entry| truncate_text(entry, 20, true))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+                .style(iced::widget::text::danger)
+            }))
+            .push_maybe((amountless_invoice_count > 0).then(|| {
+                Text::new(format!(
+                    "{amountless_invoice_count} invoices have no amount. Keystache can only pay invoices that specify an amount, so ask the payee for new invoices."
+                ))
+                .style(iced::widget::text::danger)
+            }))
+            .push(Text::new("Payment Requests").size(25));
+
+        container = if self.pending_payment_requests.is_empty() {
+            container.push(Text::new("No payment requests that can be paid"))
+        } else {
+            self.pending_payment_requests
+                .iter()
+                .fold(container, |container, payment_request| {
+                    container.push(self.payment_request_checkbox(payment_request))
+                })
+        };
+
+        container
+            .push(combo_box(
+                &self.federation_combo_box_state,
+                "Federation to pay from",
+                self.federation_combo_box_selected_federation.as_ref(),
+                Self::on_combo_box_change,
+            ))
+            .push(Text::new(format!(
+                "{invoice_count} invoices, totaling {} before gateway fees",
+                format_amount(total)
+            )))
+            .push(
+                icon_button("Pay Batch", SvgIcon::Send, PaletteColor::Primary)
+                    .on_press_maybe(pay_message_or),
+            )
+    }
+
+    fn payment_request_checkbox<'a>(
+        &self,
+        payment_request: &PaymentRequest,
+    ) -> Column<'a, app::Message> {
+        let id = payment_request.id;
+        let invoice = &payment_request.request.invoice;
+
+        let requester_public_key = &payment_request.request.requester_public_key;
+        let requester_text = self
+            .connections
+            .iter()
+            .find(|record| &record.connection.client_public_key() == requester_public_key)
+            .map_or_else(
+                || {
+                    truncate_text(
+                        &requester_public_key.to_bech32().unwrap_or_default(),
+                        23,
+                        true,
+                    )
+                },
+                |record| record.connection.name.clone(),
+            );
+
+        Column::new()
+            .push(
+                checkbox(
+                    format!(
+                        "{} from {requester_text}",
+                        format_amount(invoice_amount(invoice))
+                    ),
+                    self.selected_payment_request_ids.contains(&id),
+                )
+                .on_toggle(move |is_selected| {
+                    send_message(Message::PaymentRequestToggled(id, is_selected))
+                }),
+            )
+            .push_maybe(self.wallet.exceeds_app_payment_cap(invoice).then(|| {
+                Text::new(format!(
+                    "This is over the {} cap for payments that apps ask for. Selecting it confirms that you mean to pay it.",
+                    format_amount(self.wallet.get_app_payment_cap())
+                ))
+                .style(iced::widget::text::danger)
+            }))
+            .spacing(5)
+    }
+
+    fn batch_summary_view<'a>(
+        &'a self,
+        mut container: Column<'a, app::Message>,
+    ) -> Column<'a, app::Message> {
+        let paid_items: Vec<&BatchItem> = self
+            .batch_items
+            .iter()
+            .filter(|item| matches!(item.status, BatchItemStatus::Paid))
+            .collect();
+        let failed_count = self
+            .batch_items
+            .iter()
+            .filter(|item| matches!(item.status, BatchItemStatus::Failed(_)))
+            .count();
+
+        container = container
+            .push(Text::new(format!(
+                "Paid {} of {} invoices, {} failed",
+                paid_items.len(),
+                self.batch_items.len(),
+                failed_count
+            )))
+            .push(Text::new(format!(
+                "Total paid: {}",
+                format_amount(Amount::from_msats(
+                    paid_items
+                        .iter()
+                        .map(|item| invoice_amount(&item.invoice).msats)
+                        .sum()
+                ))
+            )));
+
+        for item in &self.batch_items {
+            let status_text = match &item.status {
+                BatchItemStatus::Waiting if self.is_paying => "Waiting...".to_string(),
+                BatchItemStatus::Waiting => "Not paid".to_string(),
+                BatchItemStatus::Paid => "Paid".to_string(),
+                BatchItemStatus::Failed(err) => format!("Failed: {err}"),
+            };
+
+            container = container.push(
+                Column::new()
+                    .push(
+                        Text::new(format!(
+                            "{} to {}",
+                            format_amount(invoice_amount(&item.invoice)),
+                            truncate_text(&item.invoice.to_string(), 23, true)
+                        ))
+                        .size(20),
+                    )
+                    .push(Text::new(status_text))
+                    .spacing(5),
+            );
+        }
+
+        container.push(
+            icon_button("Start Over", SvgIcon::Close, PaletteColor::Background)
+                .on_press_maybe((!self.is_paying).then(|| send_message(Message::StartOver))),
+        )
+    }
+
+    /// Pays every invoice of the batch in turn from `federation_id`,
+    /// grouping the payments under `batch_id` in the payment log.
+    fn pay_batch(&self, federation_id: FederationId, batch_id: i32) -> Task<app::Message> {
+        let db = self.db.clone();
+        let wallet = self.wallet.clone();
+        let nostr_module = self.nostr_module.clone();
+        let batch_items = self.batch_items.clone();
+        let in_flight_operation = self
+            .in_flight_operations
+            .start("Paying a batch of invoices");
+
+        // Apps are only told about payments over connections that are still active.
+        let connections: Vec<NwcConnection> = self
+            .connections
+            .iter()
+            .filter(|record| !record.is_revoked())
+            .map(|record| record.connection.clone())
+            .collect();
+
+        Task::stream(async_stream::stream! {
+            let _in_flight_operation = in_flight_operation;
+
+            for (index, item) in batch_items.iter().enumerate() {
+                let message = match pay_batch_item(
+                    &db,
+                    &wallet,
+                    &nostr_module,
+                    &connections,
+                    item,
+                    federation_id,
+                    batch_id,
+                )
+                .await
+                {
+                    Ok(()) => Message::ItemPaid(index),
+                    Err(err) => Message::ItemFailed(index, Arc::from(err)),
+                };
+
+                yield send_message(message);
+            }
+
+            yield send_message(Message::BatchFinished);
+        })
+    }
+
+    /// Only payment requests that can still be paid are offered for a batch. Expired requests
+    /// and requests over revoked connections can be dismissed from the payment request inbox.
+    fn load_payment_requests(&mut self) {
+        // TODO: Log a warning if the connections fail to load.
+        self.connections = self.db.list_nwc_connections().unwrap_or_default();
+
+        // TODO: Add pagination.
+        // TODO: Log a warning if the payment requests fail to load.
+        self.pending_payment_requests = self
+            .db
+            .list_pending_payment_requests(999, 0)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|payment_request| {
+                let requester_public_key = &payment_request.request.requester_public_key;
+
+                !payment_request.request.invoice.is_expired()
+                    && !self.connections.iter().any(|record| {
+                        record.is_revoked()
+                            && &record.connection.client_public_key() == requester_public_key
+                    })
+            })
+            .collect();
+
+        // Requests that were paid or rejected elsewhere can't be selected anymore.
+        let pending_ids: BTreeSet<i32> = self
+            .pending_payment_requests
+            .iter()
+            .map(|payment_request| payment_request.id)
+            .collect();
+        self.selected_payment_request_ids
+            .retain(|id| pending_ids.contains(id));
+    }
+
+    fn selected_payment_requests(&self) -> Vec<PaymentRequest> {
+        self.pending_payment_requests
+            .iter()
+            .filter(|payment_request| {
+                self.selected_payment_request_ids
+                    .contains(&payment_request.id)
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn on_combo_box_change(federation_view: Arc<FederationView>) -> app::Message {
+        send_message(Message::FederationComboBoxSelected(federation_view))
+    }
+}
+
+/// Pays a single invoice of a batch. Invoices from payment requests are checked against
+/// their app's budget, and the app is sent the preimage once its invoice is paid.
+async fn pay_batch_item(
+    db: &Database,
+    wallet: &Wallet,
+    nostr_module: &NostrModule,
+    connections: &[NwcConnection],
+    item: &BatchItem,
+    federation_id: FederationId,
+    batch_id: i32,
+) -> anyhow::Result<()> {
+    let invoice = item.invoice.clone();
+
+    let Some(payment_request) = &item.payment_request_or else {
+        let outcome = wallet.pay_invoice(invoice.clone(), federation_id).await?;
+
+        // TODO: Notify the user if the payment fails to be recorded.
+        let _ = db::retry_while_busy(|| {
+            db.save_payment(
+                &federation_id,
+                PaymentDirection::Outgoing,
+                invoice_amount(&invoice),
+                outcome.fee,
+                Some(&invoice),
+                outcome.preimage_or.as_deref(),
+                None,
+                outcome.gateway_id_or.as_ref(),
+                outcome.is_simulated,
+                Some(batch_id),
+            )
+        })
+        .await;
+
+        return Ok(());
+    };
+
+    let requester_public_key = &payment_request.request.requester_public_key;
+    let connection_or = connections
+        .iter()
+        .find(|connection| &connection.client_public_key() == requester_public_key);
+
+    // The app may have spent more since the request arrived.
+    if let Some(connection) = connection_or {
+        if nwc::would_exceed_budget(db, connection, invoice_amount(&invoice))? {
+            anyhow::bail!("Paying this would take {} over its budget", connection.name);
+        }
+    }
+
+    // The user saw the amount of the request when selecting it for the
+    // batch, which confirms paying it even if it's over the app payment cap.
+    let outcome = wallet
+        .pay_app_invoice(invoice.clone(), federation_id, true)
+        .await?;
+
+    // TODO: Notify the user if the payment fails to be recorded.
+    let _ = db::retry_while_busy(|| {
+        db.save_payment(
+            &federation_id,
+            PaymentDirection::Outgoing,
+            invoice_amount(&invoice),
+            outcome.fee,
+            Some(&invoice),
+            outcome.preimage_or.as_deref(),
+            Some(requester_public_key),
+            outcome.gateway_id_or.as_ref(),
+            outcome.is_simulated,
+            Some(batch_id),
+        )
+    })
+    .await;
+    let _ = db::retry_while_busy(|| {
+        db.set_payment_request_status(payment_request.id, PaymentRequestStatus::Paid)
+    })
+    .await;
+
+    if let Some(connection) = connection_or {
+        send_paid_response(
+            nostr_module,
+            connection,
+            payment_request,
+            outcome.preimage_or,
+        )
+        .await;
+    }
+
+    Ok(())
+}
+
+/// Reads invoices pasted one per line, or separated by spaces or commas. An invoice that's
+/// pasted more than once is only kept once, since it can only be paid once. Returns the
+/// invoices in the order they were pasted, along with any entries that aren't invoices.
+fn parse_invoices(input: &str) -> (Vec<Bolt11Invoice>, Vec<String>) {
+    let mut invoices: Vec<Bolt11Invoice> = Vec::new();
+    let mut unreadable_entries = Vec::new();
+
+    for entry in input
+        .split(|character: char| character.is_whitespace() || character == ',')
+        .filter(|entry| !entry.is_empty())
+    {
+        match Bolt11Invoice::from_str(&normalize_bech32_input(entry)) {
+            Ok(invoice) if invoices.contains(&invoice) => {}
+            Ok(invoice) => invoices.push(invoice),
+            Err(_err) => unreadable_entries.push(entry.to_string()),
+        }
+    }
+
+    (invoices, unreadable_entries)
+}
+
+fn invoice_amount(invoice: &Bolt11Invoice) -> Amount {
+    Amount::from_msats(invoice.amount_milli_satoshis().unwrap_or_default())
+}
+
+fn send_message(message: Message) -> app::Message {
+    app::Message::Routes(routes::Message::BitcoinWalletPage(
+        super::Message::BatchSend(message),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vector from BOLT 11.
+    const INVOICE: &str = "lnbc2500u1pvjluezsp5zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zygspp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdq5xysxxatsyp3k7enxv4jsxqzpu9qrsgquk0rl77nj30yxdy8j9vdx85fkpmdla2087ne0xh8nhedh8w27kyke0lp53ut353s06fv3qfegext0eh0ymjpf39tuven09sam30g4vgpfna3rh";
+
+    #[test]
+    fn test_parse_invoices() {
+        assert_eq!(parse_invoices(" \n, "), (Vec::new(), Vec::new()));
+
+        // Pasting the same invoice twice, with or without a URI scheme, only pays it once.
+        let (invoices, unreadable_entries) =
+            parse_invoices(&format!("{INVOICE}\nlightning:{INVOICE}, lnbc1nope\n\n"));
+
+        assert_eq!(invoices, vec![Bolt11Invoice::from_str(INVOICE).unwrap()]);
+        assert_eq!(unreadable_entries, vec!["lnbc1nope".to_string()]);
+    }
+}
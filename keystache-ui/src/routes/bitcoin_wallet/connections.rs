@@ -0,0 +1,448 @@
+use std::{collections::BTreeSet, sync::Arc};
+
+use chrono::Utc;
+use fedimint_core::Amount;
+use iced::{
+    widget::{checkbox, pick_list, qr_code::Data, Column, Container, QRCode, Text},
+    Task,
+};
+use nostr_sdk::Url;
+
+use crate::{
+    app,
+    config::ClockFormat,
+    db::Database,
+    fedimint::Wallet,
+    nwc::{BudgetPeriod, NwcBudget, NwcConnection, NwcConnectionRecord, NwcMethod},
+    routes::{self, container, Loadable, RouteName},
+    ui_components::{icon_button, text_input, PaletteColor, SvgIcon, Toast, ToastStatus},
+    util::{format_amount, format_time, truncate_text},
+};
+
+use super::{sats_input_to_amount, ConnectedState, SubrouteName};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    NameInputChanged(String),
+    MethodToggled(NwcMethod, bool),
+    BudgetInputChanged(String),
+    BudgetPeriodSelected(BudgetPeriod),
+    CreateConnection(NewConnectionInput),
+    RevokeConnection(i32),
+    PaymentCapInputChanged(String),
+    SavePaymentCap(Amount),
+}
+
+/// The parsed inputs for a new connection.
+#[derive(Debug, Clone)]
+pub struct NewConnectionInput {
+    name: String,
+    allowed_methods: BTreeSet<NwcMethod>,
+    budget_or: Option<NwcBudget>,
+}
+
+/// A saved connection, along with how much its app has spent in the current budget window.
+struct ConnectionItem {
+    record: NwcConnectionRecord,
+    uri: String,
+    spent: Amount,
+}
+
+pub struct Page {
+    db: Arc<Database>,
+    wallet: Arc<Wallet>,
+    clock_format: ClockFormat,
+    loadable_connections: Loadable<Vec<ConnectionItem>>,
+    name_input: String,
+    allowed_methods: BTreeSet<NwcMethod>,
+    budget_input: String,
+    budget_period: BudgetPeriod,
+    // The connection URI that was just created, so that it can be scanned.
+    new_connection_or: Option<(String, Data)>,
+    payment_cap_input: String,
+}
+
+impl Page {
+    pub fn new(connected_state: &ConnectedState) -> Self {
+        let mut page = Self {
+            db: connected_state.db.clone(),
+            wallet: connected_state.wallet.clone(),
+            clock_format: connected_state.settings.get().clock_format,
+            loadable_connections: Loadable::Loading,
+            name_input: String::new(),
+            allowed_methods: NwcMethod::ALL.into_iter().collect(),
+            budget_input: String::new(),
+            budget_period: BudgetPeriod::default(),
+            new_connection_or: None,
+            payment_cap_input: connected_state
+                .wallet
+                .get_app_payment_cap()
+                .sats_round_down()
+                .to_string(),
+        };
+
+        page.load_connections();
+
+        page
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::NameInputChanged(input) => {
+                self.name_input = input;
+
+                Task::none()
+            }
+            Message::MethodToggled(method, is_allowed) => {
+                if is_allowed {
+                    self.allowed_methods.insert(method);
+                } else {
+                    self.allowed_methods.remove(&method);
+                }
+
+                Task::none()
+            }
+            Message::BudgetInputChanged(input) => {
+                self.budget_input = input;
+
+                Task::none()
+            }
+            Message::BudgetPeriodSelected(budget_period) => {
+                self.budget_period = budget_period;
+
+                Task::none()
+            }
+            Message::CreateConnection(input) => match self.create_connection(input) {
+                Ok(()) => {
+                    self.name_input.clear();
+                    self.budget_input.clear();
+
+                    Task::batch([
+                        Task::done(app::Message::NwcConnectionsChanged),
+                        Task::done(app::Message::AddToast(Toast {
+                            title: "Connection created".to_string(),
+                            body: "Scan the QR code or paste the connection URI into the app."
+                                .to_string(),
+                            status: ToastStatus::Good,
+                        })),
+                    ])
+                }
+                Err(err) => Task::done(app::Message::AddToast(Toast {
+                    title: "Failed to create connection".to_string(),
+                    body: err.to_string(),
+                    status: ToastStatus::Bad,
+                })),
+            },
+            Message::RevokeConnection(id) => {
+                match self.db.revoke_nwc_connection(id, Utc::now().naive_utc()) {
+                    Ok(()) => {
+                        self.new_connection_or = None;
+                        self.load_connections();
+
+                        Task::batch([
+                            Task::done(app::Message::NwcConnectionsChanged),
+                            Task::done(app::Message::AddToast(Toast {
+                                title: "Connection revoked".to_string(),
+                                body: "The app can no longer make requests to this wallet."
+                                    .to_string(),
+                                status: ToastStatus::Good,
+                            })),
+                        ])
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to revoke connection".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::PaymentCapInputChanged(input) => {
+                self.payment_cap_input = input;
+
+                Task::none()
+            }
+            Message::SavePaymentCap(payment_cap) => {
+                match self.db.save_app_payment_cap(payment_cap) {
+                    Ok(()) => {
+                        self.wallet.set_app_payment_cap(payment_cap);
+
+                        Task::done(app::Message::AddToast(Toast {
+                            title: "Saved payment cap".to_string(),
+                            body: format!(
+                                "Payments over {} that apps ask for will have to be confirmed.",
+                                format_amount(payment_cap)
+                            ),
+                            status: ToastStatus::Good,
+                        }))
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to save payment cap".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+        }
+    }
+
+    pub fn view(&self) -> Column<app::Message> {
+        let mut container = container("Connections").push(Text::new(
+            "Each app connects to this wallet over Nostr Wallet Connect with its own connection. Revoking a connection only disconnects its app.",
+        ));
+
+        if let Some((uri, qr_code_data)) = &self.new_connection_or {
+            container = container
+                .push(Text::new("New Connection").size(25))
+                .push(QRCode::new(qr_code_data))
+                .push(Text::new(truncate_text(uri, 43, true)))
+                .push(
+                    icon_button(
+                        "Copy Connection URI",
+                        SvgIcon::ContentCopy,
+                        PaletteColor::Primary,
+                    )
+                    .on_press(app::Message::CopyStringToClipboard(uri.clone())),
+                );
+        }
+
+        container = container.push(Text::new("Connected Apps").size(25));
+
+        match &self.loadable_connections {
+            Loadable::Loading => {
+                container = container.push(Text::new("Loading..."));
+            }
+            Loadable::Loaded(connection_items) if connection_items.is_empty() => {
+                container = container.push(Text::new("No connections"));
+            }
+            Loadable::Loaded(connection_items) => {
+                for connection_item in connection_items {
+                    container =
+                        container.push(connection_item_view(connection_item, self.clock_format));
+                }
+            }
+            Loadable::Failed => {
+                container = container.push(Text::new("Failed to load connections"));
+            }
+        }
+
+        let payment_cap_or = self
+            .payment_cap_input
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .map(Amount::from_sats)
+            .filter(|payment_cap| *payment_cap != self.wallet.get_app_payment_cap());
+
+        container = container
+            .push(Text::new("Payment Cap").size(25))
+            .push(Text::new(
+                "Invoices over the cap, or without an amount, always have to be confirmed a second time before they're paid, whatever an app's budget.",
+            ))
+            .push(
+                text_input("Cap in sats", &self.payment_cap_input)
+                    .on_input(|input| connections_message(Message::PaymentCapInputChanged(input)))
+                    .padding(10)
+                    .size(20),
+            )
+            .push(
+                icon_button("Save Cap", SvgIcon::Save, PaletteColor::Primary).on_press_maybe(
+                    payment_cap_or
+                        .map(|payment_cap| connections_message(Message::SavePaymentCap(payment_cap))),
+                ),
+            );
+
+        container = container
+            .push(Text::new("Connect an App").size(25))
+            .push(
+                text_input("App name", &self.name_input)
+                    .on_input(|input| connections_message(Message::NameInputChanged(input)))
+                    .padding(10)
+                    .size(20),
+            )
+            .push(Text::new("The app may:"));
+
+        for method in NwcMethod::ALL {
+            container = container.push(
+                checkbox(method.to_string(), self.allowed_methods.contains(&method)).on_toggle(
+                    move |is_allowed| {
+                        connections_message(Message::MethodToggled(method, is_allowed))
+                    },
+                ),
+            );
+        }
+
+        container
+            .push(Text::new(
+                "The budget limits how much the app can spend, including fees. Payment requests are still approved by you. Leave it empty for no limit.",
+            ))
+            .push(
+                text_input("Budget in sats", &self.budget_input)
+                    .on_input(|input| connections_message(Message::BudgetInputChanged(input)))
+                    .padding(10)
+                    .size(20),
+            )
+            .push(pick_list(
+                BudgetPeriod::ALL,
+                Some(self.budget_period),
+                |budget_period| connections_message(Message::BudgetPeriodSelected(budget_period)),
+            ))
+            .push(
+                icon_button("Create Connection", SvgIcon::Add, PaletteColor::Primary)
+                    .on_press_maybe(
+                        self.parse_inputs()
+                            .map(|input| connections_message(Message::CreateConnection(input))),
+                    ),
+            )
+            .push(
+                icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                    app::Message::Routes(routes::Message::Navigate(RouteName::BitcoinWallet(
+                        SubrouteName::PaymentRequests,
+                    ))),
+                ),
+            )
+    }
+
+    /// Parses the inputs for a new connection, returning `None` if they're invalid.
+    fn parse_inputs(&self) -> Option<NewConnectionInput> {
+        let name = self.name_input.trim();
+
+        if name.is_empty() || self.allowed_methods.is_empty() {
+            return None;
+        }
+
+        let budget_or = sats_input_to_amount(self.budget_input.trim())?.map(|amount| NwcBudget {
+            amount,
+            period: self.budget_period,
+        });
+
+        Some(NewConnectionInput {
+            name: name.to_string(),
+            allowed_methods: self.allowed_methods.clone(),
+            budget_or,
+        })
+    }
+
+    fn create_connection(&mut self, input: NewConnectionInput) -> anyhow::Result<()> {
+        // TODO: Let the user choose which relay to use.
+        let relay = self
+            .db
+            .list_relays(1, 0)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Add a Nostr relay before connecting an app."))?;
+
+        let connection = NwcConnection::generate(
+            Url::parse(&relay.websocket_url)?,
+            input.name,
+            input.allowed_methods,
+            input.budget_or,
+        );
+
+        let uri = connection.to_uri()?;
+
+        self.db.save_nwc_connection(&connection)?;
+
+        self.new_connection_or = Data::new(&uri).ok().map(|qr_code_data| (uri, qr_code_data));
+        self.load_connections();
+
+        Ok(())
+    }
+
+    fn load_connections(&mut self) {
+        let now = Utc::now().naive_utc();
+
+        self.loadable_connections = self
+            .db
+            .list_nwc_connections()
+            .and_then(|records| {
+                records
+                    .into_iter()
+                    // Show the newest connections first.
+                    .rev()
+                    .map(|record| {
+                        let spent = self.db.get_nwc_connection_spent(
+                            &record.connection.client_public_key(),
+                            record
+                                .connection
+                                .budget_or
+                                .and_then(|budget| budget.period.window_start_or(now)),
+                        )?;
+
+                        Ok(ConnectionItem {
+                            uri: record.connection.to_uri()?,
+                            record,
+                            spent,
+                        })
+                    })
+                    .collect()
+            })
+            .map_or(Loadable::Failed, Loadable::Loaded);
+    }
+}
+
+fn connection_item_view(
+    connection_item: &ConnectionItem,
+    clock_format: ClockFormat,
+) -> Container<app::Message> {
+    let now = Utc::now().naive_utc();
+
+    let ConnectionItem { record, uri, spent } = connection_item;
+    let connection = &record.connection;
+
+    let budget_text = connection.budget_or.map_or_else(
+        || format!("No budget. Spent {} in total.", format_amount(*spent)),
+        |budget| {
+            format!(
+                "Budget: {} {}. Spent {}.",
+                format_amount(budget.amount),
+                budget.period.to_string().to_lowercase(),
+                format_amount(*spent)
+            )
+        },
+    );
+
+    let allowed_methods_text = connection
+        .allowed_methods
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut column = Column::new()
+        .push(Text::new(connection.name.clone()).size(20))
+        .push(Text::new(format!(
+            "Created: {}",
+            format_time(record.create_time, now, clock_format)
+        )))
+        .push(Text::new(format!("Allowed: {allowed_methods_text}")))
+        .push(Text::new(budget_text))
+        .spacing(5);
+
+    column = match record.revoke_time_or {
+        Some(revoke_time) => column.push(Text::new(format!(
+            "Revoked: {}",
+            format_time(revoke_time, now, clock_format)
+        ))),
+        None => column
+            .push(
+                icon_button(
+                    "Copy Connection URI",
+                    SvgIcon::ContentCopy,
+                    PaletteColor::Background,
+                )
+                .on_press(app::Message::CopyStringToClipboard(uri.clone())),
+            )
+            .push(
+                icon_button("Revoke", SvgIcon::Delete, PaletteColor::Danger)
+                    .on_press(connections_message(Message::RevokeConnection(record.id))),
+            ),
+    };
+
+    Container::new(column).padding(10)
+}
+
+fn connections_message(message: Message) -> app::Message {
+    app::Message::Routes(routes::Message::BitcoinWalletPage(
+        super::Message::Connections(message),
+    ))
+}
@@ -0,0 +1,177 @@
+use std::{path::PathBuf, str::FromStr, sync::Arc};
+
+use iced::{
+    widget::{Column, Text},
+    Task,
+};
+use nostr_sdk::bip39::Mnemonic;
+
+use crate::{
+    app,
+    fedimint::Wallet,
+    routes::{self, container, RouteName},
+    ui_components::{icon_button, text_input, PaletteColor, SvgIcon, Toast, ToastStatus},
+    util::truncate_text,
+};
+
+use super::{ConnectedState, SubrouteName};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    DirectoryInputChanged(String),
+    MnemonicInputChanged(String),
+    Import,
+    ImportFinished { is_success: bool },
+}
+
+pub struct Page {
+    wallet: Arc<Wallet>,
+    directory_input: String,
+    mnemonic_input: String,
+    is_importing: bool,
+}
+
+impl Page {
+    pub fn new(connected_state: &ConnectedState) -> Self {
+        Self {
+            wallet: connected_state.wallet.clone(),
+            directory_input: String::new(),
+            mnemonic_input: String::new(),
+            is_importing: false,
+        }
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::DirectoryInputChanged(input) => {
+                self.directory_input = input;
+
+                Task::none()
+            }
+            Message::MnemonicInputChanged(input) => {
+                self.mnemonic_input = input;
+
+                Task::none()
+            }
+            Message::Import => {
+                let mnemonic = match Mnemonic::from_str(self.mnemonic_input.trim()) {
+                    Ok(mnemonic) => mnemonic,
+                    Err(err) => {
+                        return Task::done(app::Message::AddToast(Toast {
+                            title: "Invalid seed phrase".to_string(),
+                            body: err.to_string(),
+                            status: ToastStatus::Bad,
+                        }));
+                    }
+                };
+
+                let source_dir = PathBuf::from(self.directory_input.trim());
+                let wallet = self.wallet.clone();
+
+                self.is_importing = true;
+
+                // The toasts are sent from here rather than from `Message::ImportFinished`
+                // so that they're still shown if the user navigates away while importing.
+                Task::stream(async_stream::stream! {
+                    match wallet.import_federation(&source_dir, &mnemonic).await {
+                        Ok(federation_id) => {
+                            yield app::Message::AddToast(Toast {
+                                title: "Imported federation".to_string(),
+                                body: format!(
+                                    "Imported federation {}. Don't use it from the other wallet anymore, or your balances may get out of sync.",
+                                    truncate_text(&federation_id.to_string(), 21, true)
+                                ),
+                                status: ToastStatus::Good,
+                            });
+
+                            yield import_message(Message::ImportFinished { is_success: true });
+                        }
+                        Err(err) => {
+                            yield app::Message::AddToast(Toast {
+                                title: "Failed to import federation".to_string(),
+                                body: err.to_string(),
+                                status: ToastStatus::Bad,
+                            });
+
+                            yield import_message(Message::ImportFinished { is_success: false });
+                        }
+                    }
+                })
+            }
+            Message::ImportFinished { is_success } => {
+                self.is_importing = false;
+
+                if is_success {
+                    self.mnemonic_input.clear();
+
+                    Task::done(app::Message::Routes(routes::Message::Navigate(
+                        RouteName::BitcoinWallet(SubrouteName::List),
+                    )))
+                } else {
+                    Task::none()
+                }
+            }
+        }
+    }
+
+    pub fn view<'a>(&self) -> Column<'a, app::Message> {
+        let can_import = !self.is_importing
+            && !self.directory_input.trim().is_empty()
+            && !self.mnemonic_input.trim().is_empty();
+
+        container("Import Federation")
+            .push(Text::new(
+                "Move a federation over from another Fedimint wallet, such as Fedi or fedimint-cli, without having to withdraw your funds first.",
+            ))
+            .push(Text::new("1. Close the other wallet").size(25))
+            .push(Text::new(
+                "The other wallet must not be running while its data is copied.",
+            ))
+            .push(Text::new("2. Find the client database").size(25))
+            .push(
+                text_input(
+                    "Path to the federation's client database folder",
+                    &self.directory_input,
+                )
+                .on_input(|input| import_message(Message::DirectoryInputChanged(input)))
+                .padding(10)
+                .size(20),
+            )
+            .push(Text::new("3. Enter the other wallet's seed phrase").size(25))
+            .push(Text::new(
+                "The client database is checked against it before it's imported. Like the other wallet, Keystache keeps the seed phrase in the federation's client database so that it can open it later.",
+            ))
+            .push(
+                text_input("Seed phrase", &self.mnemonic_input)
+                    .on_input(|input| import_message(Message::MnemonicInputChanged(input)))
+                    .secure(true)
+                    .padding(10)
+                    .size(20),
+            )
+            .push(
+                icon_button(
+                    if self.is_importing {
+                        "Importing..."
+                    } else {
+                        "Import Federation"
+                    },
+                    SvgIcon::Groups,
+                    PaletteColor::Primary,
+                )
+                .on_press_maybe(can_import.then(|| import_message(Message::Import))),
+            )
+            .push(
+                icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                    app::Message::Routes(routes::Message::Navigate(RouteName::BitcoinWallet(
+                        SubrouteName::List,
+                    ))),
+                ),
+            )
+    }
+}
+
+fn import_message(message: Message) -> app::Message {
+    app::Message::Routes(routes::Message::BitcoinWalletPage(super::Message::Import(
+        message,
+    )))
+}
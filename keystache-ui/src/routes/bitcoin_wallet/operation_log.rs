@@ -0,0 +1,225 @@
+use std::{collections::BTreeSet, sync::Arc};
+
+use chrono::Utc;
+use iced::{
+    widget::{row, Column, Text},
+    Alignment, Font, Task,
+};
+
+use crate::{
+    app,
+    config::ClockFormat,
+    fedimint::{FederationView, OperationLogRecord, Wallet},
+    routes::{self, container, Loadable, RouteName},
+    ui_components::{icon_button, PaletteColor, SvgIcon, Toast, ToastStatus},
+    util::{format_time, truncate_text},
+};
+
+use super::{ConnectedState, SubrouteName};
+
+// How many operations are loaded at first, and how many more each time the user asks.
+const OPERATION_PAGE_SIZE: usize = 50;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    LoadOperations,
+    LoadMoreOperations,
+    OperationsLoaded(Result<Vec<OperationLogRecord>, String>),
+    ToggleOperationDetails(String),
+}
+
+pub struct Page {
+    wallet: Arc<Wallet>,
+    clock_format: ClockFormat,
+    federation_view: Arc<FederationView>,
+    limit: usize,
+    loadable_operations: Loadable<Vec<OperationLogRecord>>,
+    // The ids of the operations whose JSON is shown.
+    expanded_operation_ids: BTreeSet<String>,
+}
+
+impl Page {
+    pub fn new(connected_state: &ConnectedState, federation_view: Arc<FederationView>) -> Self {
+        Self {
+            wallet: connected_state.wallet.clone(),
+            clock_format: connected_state.settings.get().clock_format,
+            federation_view,
+            limit: OPERATION_PAGE_SIZE,
+            loadable_operations: Loadable::Loading,
+            expanded_operation_ids: BTreeSet::new(),
+        }
+    }
+
+    pub fn federation_view(&self) -> Arc<FederationView> {
+        self.federation_view.clone()
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::LoadOperations => self.load_operations(),
+            Message::LoadMoreOperations => {
+                self.limit += OPERATION_PAGE_SIZE;
+
+                self.load_operations()
+            }
+            Message::OperationsLoaded(result) => match result {
+                Ok(operations) => {
+                    self.loadable_operations = Loadable::Loaded(operations);
+
+                    Task::none()
+                }
+                Err(err) => {
+                    self.loadable_operations = Loadable::Failed;
+
+                    Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to load operations".to_string(),
+                        body: err,
+                        status: ToastStatus::Bad,
+                    }))
+                }
+            },
+            Message::ToggleOperationDetails(operation_id) => {
+                if !self.expanded_operation_ids.remove(&operation_id) {
+                    self.expanded_operation_ids.insert(operation_id);
+                }
+
+                Task::none()
+            }
+        }
+    }
+
+    /// Loads the operation log from the federation's client, which has to wait for the wallet.
+    pub fn load_operations(&mut self) -> Task<app::Message> {
+        self.loadable_operations = Loadable::Loading;
+
+        let wallet = self.wallet.clone();
+        let federation_id = self.federation_view.federation_id;
+        let limit = self.limit;
+
+        Task::perform(
+            async move {
+                wallet
+                    .list_operations(&federation_id, limit)
+                    .await
+                    .map_err(|err| err.to_string())
+            },
+            |result| operation_log_message(Message::OperationsLoaded(result)),
+        )
+    }
+
+    pub fn view(&self) -> Column<app::Message> {
+        let mut container = container("Operation Log")
+            .push(Text::new(format!(
+                "The operations that the fedimint client has recorded for {}, newest first. If a payment is stuck, copy its operation when reporting it to the federation or to Keystache's developers. E-cash and payment preimages are hidden.",
+                self.federation_view
+                    .metadata
+                    .name_or
+                    .clone()
+                    .unwrap_or_else(|| "this federation".to_string())
+            )))
+            .push(
+                icon_button("Refresh", SvgIcon::Refresh, PaletteColor::Background)
+                    .on_press(operation_log_message(Message::LoadOperations)),
+            );
+
+        match &self.loadable_operations {
+            Loadable::Loading => {
+                container = container.push(Text::new("Loading..."));
+            }
+            Loadable::Loaded(operations) if operations.is_empty() => {
+                container = container.push(Text::new("No operations"));
+            }
+            Loadable::Loaded(operations) => {
+                let now = Utc::now().naive_utc();
+
+                for operation in operations {
+                    container = container.push(self.operation_view(operation, now));
+                }
+
+                // A full page means there may be older operations.
+                if operations.len() >= self.limit {
+                    container = container.push(
+                        icon_button(
+                            "Load More",
+                            SvgIcon::ArrowDownward,
+                            PaletteColor::Background,
+                        )
+                        .on_press(operation_log_message(Message::LoadMoreOperations)),
+                    );
+                }
+            }
+            Loadable::Failed => {
+                container = container.push(Text::new("Failed to load operations"));
+            }
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(routes::Message::Navigate(RouteName::BitcoinWallet(
+                    SubrouteName::FederationDetails(self.federation_view.clone()),
+                ))),
+            ),
+        )
+    }
+
+    fn operation_view<'a>(
+        &self,
+        operation: &OperationLogRecord,
+        now: chrono::NaiveDateTime,
+    ) -> Column<'a, app::Message> {
+        let is_expanded = self
+            .expanded_operation_ids
+            .contains(&operation.operation_id);
+
+        let toggle_label = if is_expanded {
+            "Hide JSON"
+        } else {
+            "Show JSON"
+        };
+
+        let state = if operation.outcome_or.is_some() {
+            "Finished"
+        } else {
+            "No outcome recorded"
+        };
+
+        Column::new()
+            .push(Text::new(truncate_text(&operation.operation_id, 24, true)))
+            .push(
+                Text::new(format!(
+                    "{} module, {state}, started {}",
+                    operation.module_kind,
+                    format_time(operation.creation_time, now, self.clock_format)
+                ))
+                .size(14),
+            )
+            .push(
+                row![
+                    icon_button(
+                        toggle_label,
+                        SvgIcon::ChevronRight,
+                        PaletteColor::Background
+                    )
+                    .on_press(operation_log_message(
+                        Message::ToggleOperationDetails(operation.operation_id.clone())
+                    )),
+                    icon_button("Copy JSON", SvgIcon::ContentCopy, PaletteColor::Background)
+                        .on_press(app::Message::CopyStringToClipboard(operation.to_json())),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+            )
+            .push_maybe(is_expanded.then(|| {
+                Text::new(operation.to_json())
+                    .font(Font::MONOSPACE)
+                    .size(12)
+            }))
+            .spacing(5)
+    }
+}
+
+fn operation_log_message(message: Message) -> app::Message {
+    app::Message::Routes(routes::Message::BitcoinWalletPage(
+        super::Message::OperationLog(message),
+    ))
+}
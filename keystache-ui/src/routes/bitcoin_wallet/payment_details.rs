@@ -0,0 +1,240 @@
+use std::{fmt::Display, sync::Arc};
+
+use chrono::Utc;
+use iced::{
+    widget::{pick_list, Column, Text},
+    Task,
+};
+use nostr_sdk::ToBech32;
+
+use crate::{
+    app,
+    config::ClockFormat,
+    db::Database,
+    fedimint::{PaymentDirection, PaymentRecord},
+    receipt::PaymentReceipt,
+    routes::{self, container, Loadable, RouteName},
+    ui_components::{icon_button, PaletteColor, SvgIcon, Toast, ToastStatus},
+    util::{format_amount, format_time, truncate_text},
+};
+
+use super::{ConnectedState, SubrouteName};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    ReceiptSignerSelected(ReceiptSigner),
+    CopyReceipt,
+}
+
+/// Which Nostr key, if any, signs a copied receipt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReceiptSigner {
+    Unsigned,
+    Npub(String),
+}
+
+impl Display for ReceiptSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unsigned => write!(f, "Don't sign"),
+            Self::Npub(npub) => write!(f, "Sign with {}", truncate_text(npub, 20, true)),
+        }
+    }
+}
+
+pub struct Page {
+    db: Arc<Database>,
+    clock_format: ClockFormat,
+    payment_id: i32,
+    loadable_payment: Loadable<PaymentRecord>,
+    // `None` if the payment has no proof of payment, such as incoming payments.
+    receipt_or: Option<PaymentReceipt>,
+    receipt_signers: Vec<ReceiptSigner>,
+    selected_receipt_signer: ReceiptSigner,
+}
+
+impl Page {
+    pub fn new(connected_state: &ConnectedState, payment_id: i32) -> Self {
+        let loadable_payment = match connected_state.db.get_payment(payment_id) {
+            Ok(payment) => Loadable::Loaded(payment),
+            Err(_err) => Loadable::Failed,
+        };
+
+        let receipt_or = loadable_payment.as_ref_option().and_then(to_receipt_or);
+
+        // TODO: Log a warning if the keys fail to load.
+        let npubs = connected_state
+            .db
+            .list_public_keys("", i64::MAX, 0)
+            .unwrap_or_default();
+
+        Self {
+            db: connected_state.db.clone(),
+            clock_format: connected_state.settings.get().clock_format,
+            payment_id,
+            loadable_payment,
+            receipt_or,
+            receipt_signers: std::iter::once(ReceiptSigner::Unsigned)
+                .chain(npubs.into_iter().map(ReceiptSigner::Npub))
+                .collect(),
+            selected_receipt_signer: ReceiptSigner::Unsigned,
+        }
+    }
+
+    pub const fn payment_id(&self) -> i32 {
+        self.payment_id
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::ReceiptSignerSelected(receipt_signer) => {
+                self.selected_receipt_signer = receipt_signer;
+
+                Task::none()
+            }
+            Message::CopyReceipt => {
+                let Some(receipt) = &self.receipt_or else {
+                    return Task::none();
+                };
+
+                let receipt_json_result = match &self.selected_receipt_signer {
+                    ReceiptSigner::Unsigned => receipt.to_json(None),
+                    ReceiptSigner::Npub(npub) => self
+                        .db
+                        .get_keypair(npub)
+                        .and_then(|keypair| receipt.to_json(Some(&keypair))),
+                };
+
+                match receipt_json_result {
+                    Ok(receipt_json) => {
+                        Task::done(app::Message::CopyStringToClipboard(receipt_json))
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to create receipt".to_string(),
+                        body: format!("Failed to create the receipt: {err}"),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+        }
+    }
+
+    pub fn view(&self) -> Column<app::Message> {
+        let mut container = container("Payment");
+
+        match &self.loadable_payment {
+            Loadable::Loading => {
+                container = container.push(Text::new("Loading..."));
+            }
+            Loadable::Loaded(payment) => {
+                container =
+                    container
+                        .push(Text::new(match payment.direction {
+                            PaymentDirection::Incoming => {
+                                format!("Received {}", format_amount(payment.amount))
+                            }
+                            PaymentDirection::Outgoing => {
+                                format!("Sent {}", format_amount(payment.amount))
+                            }
+                        }))
+                        .push(Text::new(format!("Fee: {}", format_amount(payment.fee))))
+                        .push(Text::new(format!(
+                            "Time: {}",
+                            format_time(
+                                payment.create_time,
+                                Utc::now().naive_utc(),
+                                self.clock_format
+                            )
+                        )))
+                        .push(Text::new(format!(
+                            "Federation: {}",
+                            truncate_text(&payment.federation_id.to_string(), 23, true)
+                        )))
+                        .push_maybe(payment.bolt11_invoice_or.as_ref().map(|invoice| {
+                            Text::new(format!("Payment hash: {}", invoice.payment_hash()))
+                        }))
+                        .push_maybe(payment.gateway_id_or.map(|gateway_id| {
+                            Text::new(format!(
+                                "Gateway: {}",
+                                truncate_text(&gateway_id.to_string(), 23, true)
+                            ))
+                        }))
+                        .push_maybe(payment.requester_public_key_or.map(|public_key| {
+                            Text::new(format!(
+                                "Requested by app: {}",
+                                public_key.to_bech32().map_or_else(
+                                    |_| public_key.to_string(),
+                                    |npub| truncate_text(&npub, 23, true)
+                                )
+                            ))
+                        }))
+                        .push_maybe(payment.is_simulated.then(|| {
+                            Text::new("This payment was simulated. No funds were moved.")
+                        }));
+
+                container = container.push(self.receipt_view());
+            }
+            Loadable::Failed => {
+                container = container.push(Text::new("Failed to load payment"));
+            }
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(routes::Message::Navigate(RouteName::BitcoinWallet(
+                    SubrouteName::Stats,
+                ))),
+            ),
+        )
+    }
+
+    fn receipt_view(&self) -> Column<app::Message> {
+        let column = Column::new()
+            .push(Text::new("Receipt").size(25))
+            .spacing(10);
+
+        if self.receipt_or.is_none() {
+            return column.push(Text::new(
+                "No receipt is available. Receipts are only available for payments you sent where the federation reported the payment preimage.",
+            ));
+        }
+
+        column
+            .push(Text::new(
+                "A receipt contains the invoice, the payment preimage and the time of payment. Anyone with the invoice can check that the preimage matches it. Signing the receipt proves which Nostr key it came from.",
+            ))
+            .push(pick_list(
+                self.receipt_signers.as_slice(),
+                Some(self.selected_receipt_signer.clone()),
+                |receipt_signer| {
+                    app::Message::Routes(routes::Message::BitcoinWalletPage(
+                        super::Message::PaymentDetails(Message::ReceiptSignerSelected(
+                            receipt_signer,
+                        )),
+                    ))
+                },
+            ))
+            .push(
+                icon_button("Copy Receipt", SvgIcon::ContentCopy, PaletteColor::Primary).on_press(
+                    app::Message::Routes(routes::Message::BitcoinWalletPage(
+                        super::Message::PaymentDetails(Message::CopyReceipt),
+                    )),
+                ),
+            )
+    }
+}
+
+/// Creates a receipt for an outgoing payment whose preimage is known.
+/// Simulated payments have a made-up preimage, so they never get a receipt.
+fn to_receipt_or(payment: &PaymentRecord) -> Option<PaymentReceipt> {
+    if payment.direction != PaymentDirection::Outgoing || payment.is_simulated {
+        return None;
+    }
+
+    PaymentReceipt::new(
+        payment.bolt11_invoice_or.clone()?,
+        payment.preimage_or.clone()?,
+        payment.create_time,
+    )
+    .ok()
+}
@@ -0,0 +1,567 @@
+use std::{collections::BTreeSet, sync::Arc};
+
+use chrono::Utc;
+use fedimint_core::{config::FederationId, Amount};
+use iced::{
+    widget::{combo_box, row, Column, Container, Space, Text},
+    Task,
+};
+use nostr_sdk::{
+    nips::nip47::{ErrorCode, Method},
+    PublicKey, ToBech32,
+};
+
+use crate::{
+    app,
+    config::ClockFormat,
+    db::{self, Database},
+    fedimint::{FederationView, PaymentDirection, Wallet, WalletView},
+    in_flight::{InFlightOperationGuard, InFlightOperations},
+    nostr::NostrModule,
+    nwc::{self, NwcConnection, NwcConnectionRecord, PaymentRequest, PaymentRequestStatus},
+    routes::{self, container, Loadable, RouteName},
+    ui_components::{icon_button, PaletteColor, SvgIcon, Toast, ToastStatus},
+    util::{format_amount, format_time, truncate_text},
+};
+
+use super::{ConnectedState, SubrouteName};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    FederationComboBoxSelected(Arc<FederationView>),
+
+    Approve(PaymentRequest, FederationId),
+    // Sent once the user has confirmed paying an invoice over the app payment cap.
+    ApproveOverCap(PaymentRequest, FederationId),
+    ApproveFailed(i32, Arc<anyhow::Error>),
+    Reject(PaymentRequest),
+    Resolved(i32),
+
+    ReloadPaymentRequests,
+    UpdateWalletView(WalletView),
+}
+
+pub struct Page {
+    db: Arc<Database>,
+    clock_format: ClockFormat,
+    wallet: Arc<Wallet>,
+    in_flight_operations: InFlightOperations,
+    nostr_module: NostrModule,
+    connections: Vec<NwcConnectionRecord>,
+    loadable_payment_requests: Loadable<Vec<PaymentRequest>>,
+    federation_combo_box_state: combo_box::State<Arc<FederationView>>,
+    federation_combo_box_selected_federation: Option<Arc<FederationView>>,
+    in_progress_request_ids: BTreeSet<i32>,
+    // Requests over the app payment cap that are waiting for the user to confirm them.
+    over_cap_request_ids: BTreeSet<i32>,
+}
+
+impl Page {
+    pub fn new(connected_state: &ConnectedState) -> Self {
+        let mut page = Self {
+            db: connected_state.db.clone(),
+            clock_format: connected_state.settings.get().clock_format,
+            wallet: connected_state.wallet.clone(),
+            in_flight_operations: connected_state.in_flight_operations.clone(),
+            nostr_module: connected_state.nostr_module.clone(),
+            connections: Vec::new(),
+            loadable_payment_requests: Loadable::Loading,
+            federation_combo_box_state: combo_box::State::new(
+                connected_state
+                    .loadable_wallet_view
+                    .as_ref_option()
+                    .cloned()
+                    .map(|wallet_view| wallet_view.federations)
+                    .unwrap_or_default()
+                    .into_values()
+                    .collect(),
+            ),
+            federation_combo_box_selected_federation: super::get_default_federation_view(
+                connected_state,
+            ),
+            in_progress_request_ids: BTreeSet::new(),
+            over_cap_request_ids: BTreeSet::new(),
+        };
+
+        page.load_connections();
+        page.load_payment_requests();
+
+        page
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::FederationComboBoxSelected(federation) => {
+                self.federation_combo_box_selected_federation = Some(federation);
+
+                Task::none()
+            }
+            Message::Approve(payment_request, federation_id) => {
+                self.approve(payment_request, federation_id, false)
+            }
+            Message::ApproveOverCap(payment_request, federation_id) => {
+                self.approve(payment_request, federation_id, true)
+            }
+            Message::ApproveFailed(id, err) => {
+                self.in_progress_request_ids.remove(&id);
+
+                // The request stays in the inbox so it can be retried.
+                Task::done(app::Message::AddToast(Toast {
+                    title: "Payment failed".to_string(),
+                    body: format!("Failed to pay invoice: {err}"),
+                    status: ToastStatus::Bad,
+                }))
+            }
+            Message::Reject(payment_request) => {
+                self.over_cap_request_ids.remove(&payment_request.id);
+
+                if let Err(err) = self
+                    .db
+                    .set_payment_request_status(payment_request.id, PaymentRequestStatus::Rejected)
+                {
+                    return Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to reject payment request".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    }));
+                }
+
+                self.load_payment_requests();
+
+                let Some(connection) = self
+                    .get_active_connection(&payment_request.request.requester_public_key)
+                    .cloned()
+                else {
+                    return Task::none();
+                };
+
+                let nostr_module = self.nostr_module.clone();
+
+                Task::future(async move {
+                    // TODO: Log a warning if the response fails to send.
+                    let _ = nwc::send_response(
+                        &nostr_module,
+                        &connection,
+                        payment_request.request.request_event_id,
+                        &nwc::error_response(
+                            Method::PayInvoice,
+                            ErrorCode::Restricted,
+                            "The payment request was rejected",
+                        ),
+                    )
+                    .await;
+                })
+                .discard()
+            }
+            Message::Resolved(id) => {
+                self.in_progress_request_ids.remove(&id);
+                self.load_payment_requests();
+
+                Task::none()
+            }
+            Message::ReloadPaymentRequests => {
+                self.load_connections();
+                self.load_payment_requests();
+
+                Task::none()
+            }
+            Message::UpdateWalletView(wallet_view) => {
+                self.federation_combo_box_selected_federation = self
+                    .federation_combo_box_selected_federation
+                    .as_ref()
+                    .and_then(|selected_federation| {
+                        wallet_view
+                            .federations
+                            .get(&selected_federation.federation_id)
+                            .cloned()
+                    });
+
+                self.federation_combo_box_state =
+                    combo_box::State::new(wallet_view.federations.into_values().collect());
+
+                Task::none()
+            }
+        }
+    }
+
+    pub fn view(&self) -> Column<app::Message> {
+        let active_connection_count = self
+            .connections
+            .iter()
+            .filter(|record| !record.is_revoked())
+            .count();
+
+        let mut container = container("Payment Requests")
+            .push(Text::new("Wallet Connect").size(25))
+            .push(Text::new(if active_connection_count == 0 {
+                "Create a Wallet Connect connection to let apps send payment requests to this wallet.".to_string()
+            } else {
+                format!(
+                    "{active_connection_count} connected apps can request payments, which will appear below."
+                )
+            }))
+            .push(
+                icon_button("Manage Connections", SvgIcon::Groups, PaletteColor::Primary).on_press(
+                    app::Message::Routes(routes::Message::Navigate(RouteName::BitcoinWallet(
+                        SubrouteName::Connections,
+                    ))),
+                ),
+            );
+
+        container = container
+            .push(Text::new("Inbox").size(25))
+            .push(combo_box(
+                &self.federation_combo_box_state,
+                "Federation to pay from",
+                self.federation_combo_box_selected_federation.as_ref(),
+                Self::on_combo_box_change,
+            ))
+            .push(
+                icon_button(
+                    "Pay Several at Once",
+                    SvgIcon::Send,
+                    PaletteColor::Background,
+                )
+                .on_press(app::Message::Routes(routes::Message::Navigate(
+                    RouteName::BitcoinWallet(SubrouteName::BatchSend),
+                ))),
+            );
+
+        match &self.loadable_payment_requests {
+            Loadable::Loading => {
+                container = container.push(Text::new("Loading..."));
+            }
+            Loadable::Loaded(payment_requests) if payment_requests.is_empty() => {
+                container = container.push(Text::new("No pending payment requests"));
+            }
+            Loadable::Loaded(payment_requests) => {
+                for payment_request in payment_requests {
+                    container = container.push(self.payment_request_view(payment_request));
+                }
+            }
+            Loadable::Failed => {
+                container = container.push(Text::new("Failed to load payment requests"));
+            }
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(routes::Message::Navigate(RouteName::BitcoinWallet(
+                    SubrouteName::List,
+                ))),
+            ),
+        )
+    }
+
+    fn payment_request_view<'a>(
+        &self,
+        payment_request: &PaymentRequest,
+    ) -> Container<'a, app::Message> {
+        let invoice = &payment_request.request.invoice;
+
+        let is_expired = invoice.is_expired();
+        let is_in_progress = self.in_progress_request_ids.contains(&payment_request.id);
+
+        let requester_public_key = &payment_request.request.requester_public_key;
+        let connection_record_or = self
+            .connections
+            .iter()
+            .find(|record| &record.connection.client_public_key() == requester_public_key);
+        let is_revoked = connection_record_or.is_some_and(NwcConnectionRecord::is_revoked);
+
+        let expiry_text = if is_expired {
+            "Expired".to_string()
+        } else {
+            format!(
+                "Expires in {} minutes",
+                invoice.duration_until_expiry().as_secs() / 60
+            )
+        };
+
+        let is_awaiting_over_cap_confirmation =
+            self.over_cap_request_ids.contains(&payment_request.id);
+
+        let approve_message_or = self
+            .federation_combo_box_selected_federation
+            .as_ref()
+            .filter(|_| !is_expired && !is_revoked && !is_in_progress)
+            .map(|federation| {
+                let message = if is_awaiting_over_cap_confirmation {
+                    Message::ApproveOverCap(payment_request.clone(), federation.federation_id)
+                } else {
+                    Message::Approve(payment_request.clone(), federation.federation_id)
+                };
+
+                app::Message::Routes(routes::Message::BitcoinWalletPage(
+                    super::Message::PaymentRequests(message),
+                ))
+            });
+
+        // Expired invoices and requests over revoked connections can't be paid,
+        // so the only option left is to dismiss them.
+        let reject_button_text = if is_expired || is_revoked {
+            "Dismiss"
+        } else {
+            "Reject"
+        };
+
+        let requester_text = match connection_record_or {
+            Some(record) if record.is_revoked() => {
+                format!("{} (connection revoked)", record.connection.name)
+            }
+            Some(record) => record.connection.name.clone(),
+            None => truncate_text(
+                &requester_public_key.to_bech32().unwrap_or_default(),
+                23,
+                true,
+            ),
+        };
+
+        let column = Column::new()
+            .push(
+                Text::new(invoice.amount_milli_satoshis().map_or_else(
+                    || "No amount".to_string(),
+                    |msats| format_amount(Amount::from_msats(msats)),
+                ))
+                .size(20),
+            )
+            .push(Text::new(format!("From: {requester_text}")))
+            .push(Text::new(format!(
+                "Received: {}",
+                format_time(
+                    payment_request.create_time,
+                    Utc::now().naive_utc(),
+                    self.clock_format
+                )
+            )))
+            .push(Text::new(expiry_text))
+            .push_maybe(is_awaiting_over_cap_confirmation.then(|| {
+                Text::new(format!(
+                    "This is over the {} cap for payments that apps ask for. Confirm that you meant to pay it.",
+                    format_amount(self.wallet.get_app_payment_cap())
+                ))
+                .style(iced::widget::text::danger)
+            }))
+            .push(row![
+                if is_awaiting_over_cap_confirmation {
+                    icon_button("Confirm Payment", SvgIcon::ThumbUp, PaletteColor::Danger)
+                } else {
+                    icon_button("Approve", SvgIcon::ThumbUp, PaletteColor::Primary)
+                }
+                .on_press_maybe(approve_message_or),
+                Space::with_width(10.0),
+                icon_button(reject_button_text, SvgIcon::ThumbDown, PaletteColor::Danger)
+                    .on_press_maybe((!is_in_progress).then(|| {
+                        app::Message::Routes(routes::Message::BitcoinWalletPage(
+                            super::Message::PaymentRequests(Message::Reject(
+                                payment_request.clone(),
+                            )),
+                        ))
+                    })),
+            ])
+            .spacing(5);
+
+        Container::new(column).padding(10)
+    }
+
+    /// Pays a payment request once the user has approved it. Requests over the app payment
+    /// cap are held back until the user confirms them with `is_confirmed_over_cap`.
+    fn approve(
+        &mut self,
+        payment_request: PaymentRequest,
+        federation_id: FederationId,
+        is_confirmed_over_cap: bool,
+    ) -> Task<app::Message> {
+        let connection_or = self
+            .get_active_connection(&payment_request.request.requester_public_key)
+            .cloned();
+
+        // The app may have spent more since the request arrived.
+        if let Some(connection) = &connection_or {
+            let amount = Amount::from_msats(
+                payment_request
+                    .request
+                    .invoice
+                    .amount_milli_satoshis()
+                    .unwrap_or_default(),
+            );
+
+            // TODO: Log a warning if the budget fails to load.
+            if nwc::would_exceed_budget(&self.db, connection, amount).unwrap_or(true) {
+                return Task::done(app::Message::AddToast(Toast {
+                    title: "Payment would exceed budget".to_string(),
+                    body: format!(
+                        "Paying this would take {} over its budget. Raise its budget by creating a new connection, or reject the request.",
+                        connection.name
+                    ),
+                    status: ToastStatus::Bad,
+                }));
+            }
+        }
+
+        if !is_confirmed_over_cap
+            && self
+                .wallet
+                .exceeds_app_payment_cap(&payment_request.request.invoice)
+        {
+            self.over_cap_request_ids.insert(payment_request.id);
+
+            return Task::none();
+        }
+
+        self.over_cap_request_ids.remove(&payment_request.id);
+        self.in_progress_request_ids.insert(payment_request.id);
+
+        pay_payment_request(
+            self.db.clone(),
+            self.wallet.clone(),
+            self.nostr_module.clone(),
+            connection_or,
+            payment_request,
+            federation_id,
+            is_confirmed_over_cap,
+            self.in_flight_operations.start("Paying a payment request"),
+        )
+    }
+
+    fn load_connections(&mut self) {
+        // TODO: Log a warning if the connections fail to load.
+        self.connections = self.db.list_nwc_connections().unwrap_or_default();
+    }
+
+    /// Gets the connection used by the app with `requester_public_key`, unless it's been revoked.
+    fn get_active_connection(&self, requester_public_key: &PublicKey) -> Option<&NwcConnection> {
+        self.connections
+            .iter()
+            .filter(|record| !record.is_revoked())
+            .map(|record| &record.connection)
+            .find(|connection| &connection.client_public_key() == requester_public_key)
+    }
+
+    fn load_payment_requests(&mut self) {
+        // TODO: Add pagination.
+        self.loadable_payment_requests = match self.db.list_pending_payment_requests(999, 0) {
+            Ok(payment_requests) => Loadable::Loaded(payment_requests),
+            Err(_err) => Loadable::Failed,
+        };
+    }
+
+    fn on_combo_box_change(federation_view: Arc<FederationView>) -> app::Message {
+        app::Message::Routes(routes::Message::BitcoinWalletPage(
+            super::Message::PaymentRequests(Message::FederationComboBoxSelected(federation_view)),
+        ))
+    }
+}
+
+/// Pays a payment request and, if its app's connection is still active,
+/// responds to the app with the payment preimage.
+// TODO: Remove this clippy allow.
+#[allow(clippy::too_many_arguments)]
+fn pay_payment_request(
+    db: Arc<Database>,
+    wallet: Arc<Wallet>,
+    nostr_module: NostrModule,
+    connection_or: Option<NwcConnection>,
+    payment_request: PaymentRequest,
+    federation_id: FederationId,
+    is_confirmed_over_cap: bool,
+    in_flight_operation: InFlightOperationGuard,
+) -> Task<app::Message> {
+    Task::stream(async_stream::stream! {
+        let _in_flight_operation = in_flight_operation;
+
+        let invoice = payment_request.request.invoice.clone();
+
+        let payment_result = wallet
+            .pay_app_invoice(invoice.clone(), federation_id, is_confirmed_over_cap)
+            .await;
+
+        match payment_result {
+            Ok(outcome) => {
+                // TODO: Notify the user if the payment fails to be recorded.
+                let save_result = db::retry_while_busy(|| {
+                    db.save_payment(
+                        &federation_id,
+                        PaymentDirection::Outgoing,
+                        Amount::from_msats(invoice.amount_milli_satoshis().unwrap_or_default()),
+                        outcome.fee,
+                        Some(&invoice),
+                        outcome.preimage_or.as_deref(),
+                        Some(&payment_request.request.requester_public_key),
+                        outcome.gateway_id_or.as_ref(),
+                        outcome.is_simulated,
+                        None,
+                    )
+                })
+                .await;
+                let status_result = db::retry_while_busy(|| {
+                    db.set_payment_request_status(payment_request.id, PaymentRequestStatus::Paid)
+                })
+                .await;
+
+                if let Some(toast) = Toast::database_busy_or(&save_result.and(status_result)) {
+                    yield app::Message::AddToast(toast);
+                }
+
+                if let Some(connection) = connection_or {
+                    send_paid_response(
+                        &nostr_module,
+                        &connection,
+                        &payment_request,
+                        outcome.preimage_or,
+                    )
+                    .await;
+                }
+
+                yield app::Message::AddToast(Toast {
+                    title: "Payment succeeded".to_string(),
+                    body: "The payment request was successfully paid".to_string(),
+                    status: ToastStatus::Good,
+                });
+
+                yield app::Message::Routes(routes::Message::BitcoinWalletPage(
+                    super::Message::PaymentRequests(Message::Resolved(
+                        payment_request.id,
+                    )),
+                ));
+            }
+            Err(err) => {
+                yield app::Message::Routes(routes::Message::BitcoinWalletPage(
+                    super::Message::PaymentRequests(Message::ApproveFailed(
+                        payment_request.id,
+                        Arc::from(err),
+                    )),
+                ));
+            }
+        }
+    })
+}
+
+/// Tells the app that requested `payment_request` that it was paid, along with
+/// the preimage as proof of payment.
+pub async fn send_paid_response(
+    nostr_module: &NostrModule,
+    connection: &NwcConnection,
+    payment_request: &PaymentRequest,
+    preimage_or: Option<String>,
+) {
+    let response = preimage_or.map_or_else(
+        || {
+            nwc::error_response(
+                Method::PayInvoice,
+                ErrorCode::Other,
+                "The invoice was paid but no preimage is available",
+            )
+        },
+        nwc::pay_invoice_response,
+    );
+
+    // TODO: Log a warning if the response fails to send.
+    let _ = nwc::send_response(
+        nostr_module,
+        connection,
+        payment_request.request.request_event_id,
+        &response,
+    )
+    .await;
+}
@@ -0,0 +1,766 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use chrono::Utc;
+use fedimint_core::{config::FederationId, Amount};
+use fedimint_ln_common::bitcoin::Denomination;
+use iced::{
+    widget::{combo_box, qr_code::Data, row, Column, QRCode, Text},
+    Subscription, Task,
+};
+use lightning_invoice::Bolt11Invoice;
+use nostr_sdk::Url;
+
+use crate::{
+    app,
+    db::{self, Database},
+    exchange_rate::ExchangeRate,
+    fedimint::{
+        BalanceThresholds, FederationView, LightningReceiveCompletion, PaymentDirection, Wallet,
+        WalletView,
+    },
+    in_flight::InFlightOperations,
+    lnurl::{self, PayParams, ReceivingLightningAddress},
+    nwc::{NwcConnection, NwcMethod},
+    routes::{self, container, Loadable, RouteName},
+    ui_components::{
+        icon_button, text_input, AnimatedQrCode, PaletteColor, SvgIcon, Toast, ToastStatus,
+        ANIMATED_QR_CODE_FRAME_INTERVAL,
+    },
+    util::{format_amount, truncate_text},
+};
+
+use super::{fetch_lnurl, fiat_amount_toggle, fiat_input_to_amount, ConnectedState, SubrouteName};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    // Invoice creation fields.
+    AmountInputChanged(String),
+    ClearAmountInput,
+    FiatAmountInputToggled(bool),
+    DenominationComboBoxSelected(Denomination),
+    FederationComboBoxSelected(Arc<FederationView>),
+
+    // Invoice creation and payment.
+    CreateInvoice(Amount, FederationId),
+    InvoiceCreated(Bolt11Invoice),
+    FailedToCreateInvoice,
+    PaymentSuccess(Bolt11Invoice),
+    PaymentFailure(Bolt11Invoice),
+    ShowNextQrCodePart,
+
+    // Lightning address.
+    LightningAddressInputChanged(String),
+    SetUpLightningAddress(String, FederationId),
+    CheckLightningAddress(Url),
+    LightningAddressChecked(Result<PayParams, String>),
+    RemoveLightningAddress,
+
+    UpdateWalletView(WalletView),
+}
+
+pub struct Page {
+    db: Arc<Database>,
+    wallet: Arc<Wallet>,
+    in_flight_operations: InFlightOperations,
+    amount_input: String,
+    // Whether the amount is typed in fiat rather than in the selected denomination.
+    is_fiat_amount_input: bool,
+    denomination_combo_box_state: combo_box::State<Denomination>,
+    denomination_combo_box_selected_denomination: Option<Denomination>,
+    federation_combo_box_state: combo_box::State<Arc<FederationView>>,
+    federation_combo_box_selected_federation: Option<Arc<FederationView>>,
+    federations: BTreeMap<FederationId, Arc<FederationView>>,
+    loadable_lightning_invoice_data_or:
+        Option<Loadable<(Bolt11Invoice, AnimatedQrCode, Loadable<()>)>>,
+    // The lightning address that the user receives to, and its QR code.
+    lightning_address_or: Option<(ReceivingLightningAddress, Option<Data>)>,
+    lightning_address_input: String,
+    // The URI that the lightning address's provider connects with. Only shown right after
+    // setting the address up, like new connections on the Connections page.
+    lightning_address_connection_uri_or: Option<String>,
+    loadable_lightning_address_pay_params_or: Option<Loadable<PayParams>>,
+}
+
+impl Page {
+    pub fn new(connected_state: &ConnectedState) -> Self {
+        let federations = connected_state
+            .loadable_wallet_view
+            .as_ref_option()
+            .map(|wallet_view| wallet_view.federations.clone())
+            .unwrap_or_default();
+
+        let mut page = Self {
+            db: connected_state.db.clone(),
+            wallet: connected_state.wallet.clone(),
+            in_flight_operations: connected_state.in_flight_operations.clone(),
+            amount_input: connected_state.drafts.receive_amount.clone(),
+            is_fiat_amount_input: false,
+            denomination_combo_box_state: combo_box::State::new(vec![
+                Denomination::MilliSatoshi,
+                Denomination::Satoshi,
+                Denomination::Bitcoin,
+            ]),
+            denomination_combo_box_selected_denomination: Some(Denomination::Satoshi),
+            federation_combo_box_state: combo_box::State::new(
+                federations.values().cloned().collect(),
+            ),
+            federation_combo_box_selected_federation: super::get_default_federation_view(
+                connected_state,
+            ),
+            federations,
+            loadable_lightning_invoice_data_or: None,
+            lightning_address_or: None,
+            lightning_address_input: String::new(),
+            lightning_address_connection_uri_or: None,
+            loadable_lightning_address_pay_params_or: None,
+        };
+
+        page.load_lightning_address();
+
+        page
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::AmountInputChanged(new_amount_input) => {
+                self.amount_input = new_amount_input;
+
+                Task::none()
+            }
+            Message::ClearAmountInput => {
+                self.amount_input.clear();
+
+                Task::none()
+            }
+            Message::FiatAmountInputToggled(is_fiat_amount_input) => {
+                self.is_fiat_amount_input = is_fiat_amount_input;
+                self.amount_input.clear();
+
+                Task::none()
+            }
+            Message::DenominationComboBoxSelected(denomination) => {
+                self.denomination_combo_box_selected_denomination = Some(denomination);
+
+                Task::none()
+            }
+            Message::FederationComboBoxSelected(federation) => {
+                self.federation_combo_box_selected_federation = Some(federation);
+
+                Task::none()
+            }
+            Message::CreateInvoice(amount, federation_id) => {
+                self.loadable_lightning_invoice_data_or = Some(Loadable::Loading);
+
+                let db = self.db.clone();
+                let wallet = self.wallet.clone();
+                let in_flight_operation = self
+                    .in_flight_operations
+                    .start("Waiting for an invoice to be paid");
+
+                Task::stream(async_stream::stream! {
+                    let _in_flight_operation = in_flight_operation;
+
+                    match wallet
+                        .receive_payment(federation_id, amount, String::new())
+                        .await
+                    {
+                        Ok((invoice, payment_completion_receiver)) => {
+                            yield app::Message::Routes(routes::Message::BitcoinWalletPage(super::Message::Receive(
+                                Message::InvoiceCreated(
+                                invoice.clone(),
+                            ))));
+
+                            match payment_completion_receiver.await {
+                                Ok(lightning_receive_completion) => {
+                                    match lightning_receive_completion {
+                                        LightningReceiveCompletion::Success => {
+                                            // TODO: Notify the user if the payment fails to be recorded.
+                                            let save_result = db::retry_while_busy(|| {
+                                                db.save_payment(
+                                                    &federation_id,
+                                                    PaymentDirection::Incoming,
+                                                    amount,
+                                                    Amount::ZERO,
+                                                    Some(&invoice),
+                                                    None,
+                                                    None,
+                                                    None,
+                                                    false,
+                                                    None,
+                                                )
+                                            })
+                                            .await;
+
+                                            if let Some(toast) = Toast::database_busy_or(&save_result) {
+                                                yield app::Message::AddToast(toast);
+                                            }
+
+                                            yield app::Message::Routes(routes::Message::BitcoinWalletPage(super::Message::Receive(
+                                                Message::PaymentSuccess(invoice))));
+                                        }
+                                        LightningReceiveCompletion::Failure => {
+                                            yield app::Message::Routes(routes::Message::BitcoinWalletPage(super::Message::Receive(
+                                                Message::PaymentFailure(invoice))));
+                                        }
+                                    }
+                                }
+                                Err(_) => {
+                                    println!("Payment receive completion receiver was cancelled. This is a bug!");
+                                }
+                            };
+                        }
+                        Err(_) => {
+                            yield app::Message::Routes(routes::Message::BitcoinWalletPage(super::Message::Receive(
+                                Message::FailedToCreateInvoice)));
+                        }
+                    }
+                })
+            }
+            Message::InvoiceCreated(invoice) => {
+                // The invoice has been created, so there's no need to keep the amount as a draft.
+                self.amount_input.clear();
+
+                let qr_code = match AnimatedQrCode::new(&invoice.to_string()) {
+                    Ok(qr_code) => qr_code,
+                    Err(err) => {
+                        self.loadable_lightning_invoice_data_or = Some(Loadable::Failed);
+
+                        return Task::done(app::Message::AddToast(Toast {
+                            title: "Failed to show invoice".to_string(),
+                            body: err.to_string(),
+                            status: ToastStatus::Bad,
+                        }));
+                    }
+                };
+
+                self.loadable_lightning_invoice_data_or =
+                    Some(Loadable::Loaded((invoice, qr_code, Loadable::Loading)));
+
+                Task::none()
+            }
+            Message::FailedToCreateInvoice => {
+                self.loadable_lightning_invoice_data_or = Some(Loadable::Failed);
+
+                Task::none()
+            }
+            Message::PaymentSuccess(succeeded_invoice) => {
+                if let Some(Loadable::Loaded((invoice, _, loadable_invoice_payment))) =
+                    &mut self.loadable_lightning_invoice_data_or
+                {
+                    if invoice == &succeeded_invoice {
+                        *loadable_invoice_payment = Loadable::Loaded(());
+                    }
+                }
+
+                Task::none()
+            }
+            Message::PaymentFailure(failed_invoice) => {
+                if let Some(Loadable::Loaded((invoice, _, loadable_invoice_payment))) =
+                    &mut self.loadable_lightning_invoice_data_or
+                {
+                    if invoice == &failed_invoice {
+                        *loadable_invoice_payment = Loadable::Failed;
+                    }
+                }
+
+                Task::none()
+            }
+            Message::ShowNextQrCodePart => {
+                if let Some(Loadable::Loaded((_, qr_code, _))) =
+                    &mut self.loadable_lightning_invoice_data_or
+                {
+                    qr_code.show_next_part();
+                }
+
+                Task::none()
+            }
+            Message::LightningAddressInputChanged(input) => {
+                self.lightning_address_input = input;
+
+                Task::none()
+            }
+            Message::SetUpLightningAddress(address, federation_id) => {
+                match self.set_up_lightning_address(address, federation_id) {
+                    Ok(()) => {
+                        self.lightning_address_input.clear();
+
+                        Task::batch([
+                            Task::done(app::Message::NwcConnectionsChanged),
+                            Task::done(app::Message::AddToast(Toast {
+                                title: "Lightning address set up".to_string(),
+                                body:
+                                    "Give the connection URI to your provider to start receiving."
+                                        .to_string(),
+                                status: ToastStatus::Good,
+                            })),
+                        ])
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to set up lightning address".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::CheckLightningAddress(url) => {
+                self.loadable_lightning_address_pay_params_or = Some(Loadable::Loading);
+
+                Task::perform(
+                    async move { PayParams::from_json(&fetch_lnurl(url).await?) },
+                    |result| {
+                        receive_message(Message::LightningAddressChecked(
+                            result.map_err(|err| err.to_string()),
+                        ))
+                    },
+                )
+            }
+            Message::LightningAddressChecked(result) => match result {
+                Ok(pay_params) => {
+                    self.loadable_lightning_address_pay_params_or =
+                        Some(Loadable::Loaded(pay_params));
+
+                    Task::none()
+                }
+                Err(err) => {
+                    self.loadable_lightning_address_pay_params_or = Some(Loadable::Failed);
+
+                    Task::done(app::Message::AddToast(Toast {
+                        title: "Lightning address isn't reachable".to_string(),
+                        body: err,
+                        status: ToastStatus::Bad,
+                    }))
+                }
+            },
+            Message::RemoveLightningAddress => match self.remove_lightning_address() {
+                Ok(()) => Task::batch([
+                    Task::done(app::Message::NwcConnectionsChanged),
+                    Task::done(app::Message::AddToast(Toast {
+                        title: "Lightning address removed".to_string(),
+                        body: "Its provider can no longer create invoices for this wallet."
+                            .to_string(),
+                        status: ToastStatus::Good,
+                    })),
+                ]),
+                Err(err) => Task::done(app::Message::AddToast(Toast {
+                    title: "Failed to remove lightning address".to_string(),
+                    body: err.to_string(),
+                    status: ToastStatus::Bad,
+                })),
+            },
+            Message::UpdateWalletView(wallet_view) => {
+                self.federation_combo_box_selected_federation = self
+                    .federation_combo_box_selected_federation
+                    .as_ref()
+                    .and_then(|selected_federation| {
+                        wallet_view
+                            .federations
+                            .get(&selected_federation.federation_id)
+                            .cloned()
+                    });
+
+                self.federation_combo_box_state =
+                    combo_box::State::new(wallet_view.federations.values().cloned().collect());
+                self.federations = wallet_view.federations;
+
+                Task::none()
+            }
+        }
+    }
+
+    /// The amount typed in the selected denomination. Empty while the amount
+    /// is typed in fiat, since the draft is restored in the denomination.
+    pub fn amount_input(&self) -> &str {
+        if self.is_fiat_amount_input {
+            ""
+        } else {
+            &self.amount_input
+        }
+    }
+
+    /// Cycles through the parts of the invoice's QR code while it's waiting to be paid,
+    /// if the invoice is too long for a single QR code.
+    pub fn subscription(&self) -> Subscription<app::Message> {
+        match &self.loadable_lightning_invoice_data_or {
+            Some(Loadable::Loaded((_, qr_code, Loadable::Loading))) if qr_code.is_animated() => {
+                iced::time::every(ANIMATED_QR_CODE_FRAME_INTERVAL).map(|_| {
+                    app::Message::Routes(routes::Message::BitcoinWalletPage(
+                        super::Message::Receive(Message::ShowNextQrCodePart),
+                    ))
+                })
+            }
+            _ => Subscription::none(),
+        }
+    }
+
+    /// Creating invoices is disabled while `is_offline`, since it needs to reach the federation.
+    /// Amounts can be typed in fiat if there's an `exchange_rate_or`.
+    pub fn view(
+        &self,
+        is_offline: bool,
+        exchange_rate_or: Option<ExchangeRate>,
+        balance_thresholds: &BTreeMap<FederationId, BalanceThresholds>,
+    ) -> Column<app::Message> {
+        let mut container = container("Receive");
+
+        let amount_or = if self.is_fiat_amount_input {
+            fiat_input_to_amount(&self.amount_input, exchange_rate_or)
+        } else {
+            self.denomination_combo_box_selected_denomination
+                .and_then(|denomination| Amount::from_str_in(&self.amount_input, denomination).ok())
+        };
+
+        // If the inputted amount to receive is valid and a federation
+        // is selected, then we can proceed to pay the invoice.
+        let mut parsed_amount_and_selected_federation_id_or = amount_or.and_then(|invoice| {
+            self.federation_combo_box_selected_federation
+                .as_ref()
+                .map(|selected_federation| (invoice, selected_federation.federation_id))
+        });
+
+        let max_balance_warning_or = self.get_max_balance_warning(amount_or, balance_thresholds);
+
+        // Refuse to create invoices that would take the balance above the maximum.
+        if max_balance_warning_or.is_some() || is_offline {
+            parsed_amount_and_selected_federation_id_or = None;
+        }
+
+        container = if let Some(loadable_lightning_invoice_data) =
+            &self.loadable_lightning_invoice_data_or
+        {
+            match loadable_lightning_invoice_data {
+                Loadable::Loading => container.push(Text::new("Loading...")),
+                Loadable::Loaded((lightning_invoice, qr_code, is_paid)) => {
+                    if is_paid == &Loadable::Loaded(()) {
+                        container.push(Text::new("Payment successful!"))
+                    } else {
+                        container.push(qr_code.view()).push(
+                            icon_button(
+                                "Copy Invoice",
+                                SvgIcon::ContentCopy,
+                                PaletteColor::Primary,
+                            )
+                            .on_press(
+                                app::Message::CopyStringToClipboard(lightning_invoice.to_string()),
+                            ),
+                        )
+                    }
+                }
+                Loadable::Failed => container.push(Text::new("Failed to create invoice")),
+            }
+        } else {
+            container
+                .push(
+                    text_input("Amount to receive", &self.amount_input)
+                        .on_input(|input| {
+                            app::Message::Routes(routes::Message::BitcoinWalletPage(
+                                super::Message::Receive(Message::AmountInputChanged(input)),
+                            ))
+                        })
+                        .padding(10)
+                        .size(30),
+                )
+                .push(
+                    icon_button("Clear", SvgIcon::Close, PaletteColor::Background).on_press_maybe(
+                        (!self.amount_input.is_empty()).then_some(app::Message::Routes(
+                            routes::Message::BitcoinWalletPage(super::Message::Receive(
+                                Message::ClearAmountInput,
+                            )),
+                        )),
+                    ),
+                )
+                .push_maybe(fiat_amount_toggle(
+                    amount_or,
+                    self.is_fiat_amount_input,
+                    exchange_rate_or,
+                    |is_fiat_amount_input| {
+                        receive_message(Message::FiatAmountInputToggled(is_fiat_amount_input))
+                    },
+                ))
+                .push_maybe((!self.is_fiat_amount_input).then(|| {
+                    combo_box(
+                        &self.denomination_combo_box_state,
+                        "Denomination",
+                        self.denomination_combo_box_selected_denomination.as_ref(),
+                        Self::on_denomination_combo_box_change,
+                    )
+                }))
+                .push(combo_box(
+                    &self.federation_combo_box_state,
+                    "Federation to receive to",
+                    self.federation_combo_box_selected_federation.as_ref(),
+                    Self::on_federation_combo_box_change,
+                ))
+                .push_maybe(max_balance_warning_or.map(Text::new))
+                .push(
+                    icon_button("Create Invoice", SvgIcon::Send, PaletteColor::Primary)
+                        .on_press_maybe(parsed_amount_and_selected_federation_id_or.map(
+                            |(amount, federation_id)| {
+                                app::Message::Routes(routes::Message::BitcoinWalletPage(
+                                    super::Message::Receive(Message::CreateInvoice(
+                                        amount,
+                                        federation_id,
+                                    )),
+                                ))
+                            },
+                        )),
+                )
+        };
+
+        container = container.push(self.lightning_address_view(is_offline));
+
+        container = container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(routes::Message::Navigate(RouteName::BitcoinWallet(
+                    SubrouteName::List,
+                ))),
+            ),
+        );
+
+        container
+    }
+
+    /// Checking the lightning address is disabled while `is_offline`,
+    /// since it needs to reach the provider.
+    fn lightning_address_view(&self, is_offline: bool) -> Column<app::Message> {
+        let column = Column::new()
+            .push(Text::new("Lightning Address").size(25))
+            .spacing(10);
+
+        let Some((lightning_address, qr_code_data_or)) = &self.lightning_address_or else {
+            let address = self.lightning_address_input.trim().to_lowercase();
+
+            let is_valid_address =
+                address.contains('@') && lnurl::parse_pay_url(&address).is_some();
+
+            let set_up_message_or = self
+                .federation_combo_box_selected_federation
+                .as_ref()
+                .filter(|_| is_valid_address)
+                .map(|federation| {
+                    receive_message(Message::SetUpLightningAddress(
+                        address.clone(),
+                        federation.federation_id,
+                    ))
+                });
+
+            return column
+                .push(Text::new(
+                    "Receive to a reusable lightning address from a provider that supports Nostr Wallet Connect. The provider asks this wallet for an invoice whenever someone pays the address, so payments only arrive while Keystache is open. They go to the federation chosen above.",
+                ))
+                .push(
+                    text_input("Lightning address", &self.lightning_address_input)
+                        .on_input(|input| receive_message(Message::LightningAddressInputChanged(input)))
+                        .padding(10),
+                )
+                .push_maybe((!is_valid_address && !address.is_empty()).then(|| {
+                    Text::new("This isn't a valid lightning address")
+                        .style(iced::widget::text::danger)
+                }))
+                .push(
+                    icon_button("Set Up", SvgIcon::Add, PaletteColor::Primary)
+                        .on_press_maybe(set_up_message_or),
+                );
+        };
+
+        let federation_text = match self.federations.get(&lightning_address.federation_id) {
+            Some(federation) => Text::new(format!(
+                "Payments are received to {}.",
+                federation
+                    .metadata
+                    .name_or
+                    .clone()
+                    .unwrap_or_else(|| truncate_text(
+                        &federation.federation_id.to_string(),
+                        16,
+                        true
+                    ))
+            )),
+            None => Text::new(
+                "You've left the federation this address received to, so payments go to your default federation instead.",
+            )
+            .style(iced::widget::text::danger),
+        };
+
+        let pay_params_text_or =
+            self.loadable_lightning_address_pay_params_or
+                .as_ref()
+                .map(|loadable_pay_params| match loadable_pay_params {
+                    Loadable::Loading => Text::new("Checking..."),
+                    Loadable::Loaded(pay_params) => Text::new(format!(
+                        "Your provider accepts payments of {} to {}.",
+                        format_amount(pay_params.min_sendable),
+                        format_amount(pay_params.max_sendable)
+                    ))
+                    .style(iced::widget::text::success),
+                    Loadable::Failed => Text::new("Your provider couldn't be reached.")
+                        .style(iced::widget::text::danger),
+                });
+
+        let check_message_or = lnurl::parse_pay_url(&lightning_address.address)
+            .filter(|_| {
+                !is_offline
+                    && !matches!(
+                        self.loadable_lightning_address_pay_params_or,
+                        Some(Loadable::Loading)
+                    )
+            })
+            .map(|url| receive_message(Message::CheckLightningAddress(url)));
+
+        column
+            .push(Text::new(lightning_address.address.clone()).size(20))
+            .push(federation_text)
+            .push_maybe(qr_code_data_or.as_ref().map(QRCode::new))
+            .push_maybe(self.lightning_address_connection_uri_or.as_ref().map(|uri| {
+                Column::new()
+                    .push(Text::new(
+                        "Give this connection URI to your provider so that it can ask for invoices. It can't spend from your wallet.",
+                    ))
+                    .push(
+                        icon_button(
+                            "Copy Connection URI",
+                            SvgIcon::ContentCopy,
+                            PaletteColor::Primary,
+                        )
+                        .on_press(app::Message::CopyStringToClipboard(uri.clone())),
+                    )
+                    .spacing(10)
+            }))
+            .push_maybe(pay_params_text_or)
+            .push(
+                row![
+                    icon_button(
+                        "Copy Address",
+                        SvgIcon::ContentCopy,
+                        PaletteColor::Background
+                    )
+                    .on_press(app::Message::CopyStringToClipboard(
+                        lightning_address.address.clone()
+                    )),
+                    icon_button("Check", SvgIcon::Refresh, PaletteColor::Background)
+                        .on_press_maybe(check_message_or),
+                    icon_button("Remove", SvgIcon::Delete, PaletteColor::Danger)
+                        .on_press(receive_message(Message::RemoveLightningAddress)),
+                ]
+                .spacing(10),
+            )
+    }
+
+    fn set_up_lightning_address(
+        &mut self,
+        address: String,
+        federation_id: FederationId,
+    ) -> anyhow::Result<()> {
+        // TODO: Let the user choose which relay to use.
+        let relay = self
+            .db
+            .list_relays(1, 0)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                anyhow::anyhow!("Add a Nostr relay before setting up a lightning address.")
+            })?;
+
+        // The provider only needs to create invoices, so it isn't allowed anything else.
+        let connection = NwcConnection::generate(
+            Url::parse(&relay.websocket_url)?,
+            format!("Lightning address {address}"),
+            BTreeSet::from([NwcMethod::MakeInvoice]),
+            None,
+        );
+
+        let uri = connection.to_uri()?;
+
+        self.db.save_nwc_connection(&connection)?;
+        self.db
+            .save_receiving_lightning_address(&ReceivingLightningAddress {
+                address,
+                federation_id,
+                client_public_key: connection.client_public_key(),
+            })?;
+
+        self.lightning_address_connection_uri_or = Some(uri);
+        self.load_lightning_address();
+
+        Ok(())
+    }
+
+    /// Forgets the lightning address and revokes its provider's connection.
+    fn remove_lightning_address(&mut self) -> anyhow::Result<()> {
+        if let Some((lightning_address, _)) = &self.lightning_address_or {
+            if let Some(record) = self
+                .db
+                .get_nwc_connection_by_client_public_key(&lightning_address.client_public_key)?
+            {
+                self.db
+                    .revoke_nwc_connection(record.id, Utc::now().naive_utc())?;
+            }
+        }
+
+        self.db.remove_receiving_lightning_address()?;
+
+        self.lightning_address_connection_uri_or = None;
+        self.loadable_lightning_address_pay_params_or = None;
+        self.load_lightning_address();
+
+        Ok(())
+    }
+
+    fn load_lightning_address(&mut self) {
+        // TODO: Log a warning if the lightning address fails to load.
+        self.lightning_address_or = self
+            .db
+            .get_receiving_lightning_address()
+            .ok()
+            .flatten()
+            .map(|lightning_address| {
+                // The QR code holds the address's LNURL, which more wallets can scan.
+                let qr_code_data_or = lnurl::parse_pay_url(&lightning_address.address)
+                    .and_then(|url| lnurl::encode_lnurl(&url).ok())
+                    .and_then(|lnurl| Data::new(lnurl).ok());
+
+                (lightning_address, qr_code_data_or)
+            });
+    }
+
+    /// Explains why an invoice for `amount_or` can't be created if it would
+    /// take the selected federation's balance above its maximum balance.
+    fn get_max_balance_warning(
+        &self,
+        amount_or: Option<Amount>,
+        balance_thresholds: &BTreeMap<FederationId, BalanceThresholds>,
+    ) -> Option<String> {
+        let amount = amount_or?;
+        let federation = self.federation_combo_box_selected_federation.as_ref()?;
+        let thresholds = balance_thresholds.get(&federation.federation_id)?;
+
+        if !thresholds.would_exceed_max(federation.balance, amount) {
+            return None;
+        }
+
+        Some(format!(
+            "This would take your balance in this federation above the maximum of {} that you set. Consider receiving to a different federation.",
+            format_amount(thresholds.max_or?)
+        ))
+    }
+
+    fn on_denomination_combo_box_change(denomination: Denomination) -> app::Message {
+        app::Message::Routes(routes::Message::BitcoinWalletPage(super::Message::Receive(
+            Message::DenominationComboBoxSelected(denomination),
+        )))
+    }
+
+    fn on_federation_combo_box_change(federation_view: Arc<FederationView>) -> app::Message {
+        app::Message::Routes(routes::Message::BitcoinWalletPage(super::Message::Receive(
+            Message::FederationComboBoxSelected(federation_view),
+        )))
+    }
+}
+
+fn receive_message(message: Message) -> app::Message {
+    app::Message::Routes(routes::Message::BitcoinWalletPage(super::Message::Receive(
+        message,
+    )))
+}
@@ -0,0 +1,187 @@
+use std::sync::Arc;
+
+use fedimint_core::{config::FederationId, invite_code::InviteCode, Amount};
+use fedimint_mint_client::OOBNotes;
+use iced::{
+    widget::{Column, Text},
+    Task,
+};
+
+use crate::{
+    app,
+    fedimint::{parse_ecash_notes, Wallet, WalletView},
+    in_flight::InFlightOperations,
+    routes::{self, container, RouteName},
+    ui_components::{icon_button, text_input, PaletteColor, SvgIcon, Toast, ToastStatus},
+    util::format_amount,
+};
+
+use super::{ConnectedState, SubrouteName};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    NotesInputChanged(String),
+    RedeemNotes(OOBNotes),
+    NotesRedeemed(Result<Amount, String>),
+    JoinFederation(InviteCode),
+
+    UpdateWalletView(WalletView),
+}
+
+pub struct Page {
+    wallet: Arc<Wallet>,
+    in_flight_operations: InFlightOperations,
+    notes_input: String,
+    parsed_notes_or: Option<OOBNotes>,
+    joined_federation_ids: Vec<FederationId>,
+    is_redeeming: bool,
+}
+
+impl Page {
+    pub fn new(connected_state: &ConnectedState) -> Self {
+        Self {
+            wallet: connected_state.wallet.clone(),
+            in_flight_operations: connected_state.in_flight_operations.clone(),
+            notes_input: String::new(),
+            parsed_notes_or: None,
+            joined_federation_ids: connected_state
+                .loadable_wallet_view
+                .as_ref_option()
+                .map(|wallet_view| wallet_view.federations.keys().copied().collect())
+                .unwrap_or_default(),
+            is_redeeming: false,
+        }
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::NotesInputChanged(input) => {
+                self.parsed_notes_or = parse_ecash_notes(&input);
+                self.notes_input = input;
+
+                Task::none()
+            }
+            Message::RedeemNotes(notes) => {
+                self.is_redeeming = true;
+
+                let wallet = self.wallet.clone();
+                let in_flight_operation = self.in_flight_operations.start("Redeeming e-cash");
+
+                Task::perform(
+                    async move {
+                        let _in_flight_operation = in_flight_operation;
+
+                        wallet
+                            .reissue_notes(notes)
+                            .await
+                            .map_err(|err| err.to_string())
+                    },
+                    |result| receive_ecash_message(Message::NotesRedeemed(result)),
+                )
+            }
+            Message::NotesRedeemed(result) => {
+                self.is_redeeming = false;
+
+                match result {
+                    Ok(amount) => {
+                        self.notes_input.clear();
+                        self.parsed_notes_or = None;
+
+                        Task::done(app::Message::AddToast(Toast {
+                            title: "Redeemed e-cash".to_string(),
+                            body: format!("{} was added to your wallet.", format_amount(amount)),
+                            status: ToastStatus::Good,
+                        }))
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to redeem e-cash".to_string(),
+                        body: err,
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::JoinFederation(invite_code) => app::open_join_federation_page(&invite_code),
+            Message::UpdateWalletView(wallet_view) => {
+                self.joined_federation_ids = wallet_view.federations.keys().copied().collect();
+
+                Task::none()
+            }
+        }
+    }
+
+    /// Redeeming is disabled while `is_offline`, since it needs to reach the federation.
+    pub fn view(&self, is_offline: bool) -> Column<app::Message> {
+        let mut container = container("Receive E-Cash")
+            .push(Text::new(
+                "Paste e-cash notes that someone sent you to add them to your wallet. Until they're redeemed, whoever sent them can still spend them.",
+            ))
+            .push(
+                text_input("E-cash notes", &self.notes_input)
+                    .on_input(|input| receive_ecash_message(Message::NotesInputChanged(input)))
+                    .padding(10)
+                    .size(30),
+            );
+
+        let mut redeem_message_or = None;
+
+        if let Some(notes) = &self.parsed_notes_or {
+            let is_joined = self
+                .joined_federation_ids
+                .iter()
+                .any(|federation_id| federation_id.to_prefix() == notes.federation_id_prefix());
+
+            container = container.push(Text::new(format!(
+                "These notes are worth {}.",
+                format_amount(notes.total_amount())
+            )));
+
+            if is_joined {
+                redeem_message_or = (!self.is_redeeming && !is_offline)
+                    .then(|| receive_ecash_message(Message::RedeemNotes(notes.clone())));
+            } else {
+                container = container
+                    .push(
+                        Text::new(
+                            "They're from a federation you haven't joined. Join it to redeem them.",
+                        )
+                        .style(iced::widget::text::danger),
+                    )
+                    .push_maybe(notes.federation_invite().map(|invite_code| {
+                        icon_button("Join Federation", SvgIcon::Add, PaletteColor::Primary)
+                            .on_press(receive_ecash_message(Message::JoinFederation(invite_code)))
+                    }));
+            }
+        } else if !self.notes_input.trim().is_empty() {
+            container = container.push(
+                Text::new("These aren't valid e-cash notes").style(iced::widget::text::danger),
+            );
+        }
+
+        container
+            .push(
+                icon_button(
+                    if self.is_redeeming {
+                        "Redeeming..."
+                    } else {
+                        "Redeem"
+                    },
+                    SvgIcon::ArrowDownward,
+                    PaletteColor::Primary,
+                )
+                .on_press_maybe(redeem_message_or),
+            )
+            .push(
+                icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                    app::Message::Routes(routes::Message::Navigate(RouteName::BitcoinWallet(
+                        SubrouteName::List,
+                    ))),
+                ),
+            )
+    }
+}
+
+fn receive_ecash_message(message: Message) -> app::Message {
+    app::Message::Routes(routes::Message::BitcoinWalletPage(
+        super::Message::ReceiveEcash(message),
+    ))
+}
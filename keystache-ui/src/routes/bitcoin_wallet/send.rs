@@ -0,0 +1,723 @@
+use std::{str::FromStr, sync::Arc};
+
+use fedimint_core::{config::FederationId, Amount};
+use iced::{
+    widget::{combo_box, row, Column, Text},
+    Alignment, Task,
+};
+use lightning_invoice::Bolt11Invoice;
+use nostr_sdk::Url;
+
+use crate::{
+    app, bbqr,
+    db::{self, Database},
+    exchange_rate::ExchangeRate,
+    fedimint::{FederationView, PaymentDirection, PaymentSimulation, Wallet, WalletView},
+    in_flight::InFlightOperations,
+    lnurl::{self, PayParams},
+    routes::{self, container, Loadable, RouteName},
+    ui_components::{
+        bech32_input, icon_button, normalize_bech32_input, text_input, Bech32Kind, PaletteColor,
+        SvgIcon, Toast, ToastStatus,
+    },
+    util::format_amount,
+};
+
+use super::{fetch_lnurl, fiat_amount_toggle, fiat_input_to_amount, ConnectedState, SubrouteName};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    // Payment input fields.
+    LightningInvoiceInputChanged(String),
+    ClearLightningInvoiceInput,
+    QrCodePartInputChanged(String),
+    AddQrCodePart,
+    ResetQrCodeParts,
+    FederationComboBoxSelected(Arc<FederationView>),
+    SendMax,
+
+    // Paying a lightning address or LNURL, which is turned into an invoice first.
+    LookUpPayParams(Url),
+    PayParamsLoaded(Result<PayParams, String>),
+    LnurlAmountInputChanged(String),
+    LnurlFiatAmountInputToggled(bool),
+    LnurlCommentInputChanged(String),
+    RequestLnurlInvoice(Amount),
+    LnurlInvoiceReceived(Result<Bolt11Invoice, String>),
+
+    // Payment actions.
+    PayInvoice(Bolt11Invoice, FederationId),
+    PayInvoiceSucceeded(Bolt11Invoice),
+    PayInvoiceFailed((Bolt11Invoice, Arc<anyhow::Error>)),
+
+    UpdateWalletView(WalletView),
+}
+
+pub struct Page {
+    db: Arc<Database>,
+    wallet: Arc<Wallet>,
+    in_flight_operations: InFlightOperations,
+    lightning_invoice_input: String,
+    // Parts of an animated QR code, entered one at a time by a scanner that types
+    // what it reads, or by pasting. See `bbqr`.
+    qr_code_part_input: String,
+    qr_code_joiner: bbqr::Joiner,
+    federation_combo_box_state: combo_box::State<Arc<FederationView>>,
+    federation_combo_box_selected_federation: Option<Arc<FederationView>>,
+    loadable_invoice_payment_or: Option<Loadable<()>>,
+    // The most that can be sent from the selected federation,
+    // shown once the user asks for it.
+    max_sendable_amount_or: Option<Amount>,
+    // What the lightning address or LNURL in the input accepts, once it's been looked up.
+    loadable_pay_params_or: Option<Loadable<PayParams>>,
+    lnurl_amount_input: String,
+    // Whether the LNURL amount is typed in fiat rather than in sats.
+    is_lnurl_fiat_amount_input: bool,
+    lnurl_comment_input: String,
+    is_requesting_lnurl_invoice: bool,
+}
+
+impl Page {
+    pub fn new(connected_state: &ConnectedState) -> Self {
+        Self {
+            db: connected_state.db.clone(),
+            wallet: connected_state.wallet.clone(),
+            in_flight_operations: connected_state.in_flight_operations.clone(),
+            lightning_invoice_input: connected_state.drafts.send_lightning_invoice.clone(),
+            qr_code_part_input: String::new(),
+            qr_code_joiner: bbqr::Joiner::default(),
+            federation_combo_box_state: combo_box::State::new(
+                connected_state
+                    .loadable_wallet_view
+                    .as_ref_option()
+                    .cloned()
+                    .map(|wallet_view| wallet_view.federations)
+                    .unwrap_or_default()
+                    .into_values()
+                    .collect(),
+            ),
+            federation_combo_box_selected_federation: None,
+            loadable_invoice_payment_or: None,
+            max_sendable_amount_or: None,
+            loadable_pay_params_or: None,
+            lnurl_amount_input: String::new(),
+            is_lnurl_fiat_amount_input: false,
+            lnurl_comment_input: String::new(),
+            is_requesting_lnurl_invoice: false,
+        }
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::LightningInvoiceInputChanged(new_lightning_invoice_input) => {
+                self.lightning_invoice_input = new_lightning_invoice_input;
+                self.loadable_pay_params_or = None;
+
+                Task::none()
+            }
+            Message::ClearLightningInvoiceInput => {
+                self.lightning_invoice_input.clear();
+                self.loadable_pay_params_or = None;
+
+                Task::none()
+            }
+            Message::QrCodePartInputChanged(new_qr_code_part_input) => {
+                self.qr_code_part_input = new_qr_code_part_input;
+
+                Task::none()
+            }
+            Message::AddQrCodePart => {
+                let qr_code_part = std::mem::take(&mut self.qr_code_part_input);
+
+                match self.qr_code_joiner.add_part(&qr_code_part) {
+                    Ok(Some(text)) => {
+                        self.qr_code_joiner = bbqr::Joiner::default();
+                        self.lightning_invoice_input = normalize_bech32_input(&text);
+
+                        Task::none()
+                    }
+                    Ok(None) => Task::none(),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to read QR code part".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::ResetQrCodeParts => {
+                self.qr_code_joiner = bbqr::Joiner::default();
+
+                Task::none()
+            }
+            Message::FederationComboBoxSelected(federation) => {
+                self.federation_combo_box_selected_federation = Some(federation);
+                self.max_sendable_amount_or = None;
+
+                Task::none()
+            }
+            Message::SendMax => {
+                let Some(federation) = &self.federation_combo_box_selected_federation else {
+                    return Task::none();
+                };
+
+                self.max_sendable_amount_or = federation.max_sendable_amount_or(
+                    self.wallet.get_pinned_gateway(&federation.federation_id),
+                );
+
+                if self.max_sendable_amount_or.is_some() {
+                    Task::none()
+                } else {
+                    Task::done(app::Message::AddToast(Toast {
+                        title: "No lightning gateways".to_string(),
+                        body: "This federation has no gateways to pay invoices through."
+                            .to_string(),
+                        status: ToastStatus::Bad,
+                    }))
+                }
+            }
+            Message::LookUpPayParams(url) => {
+                self.loadable_pay_params_or = Some(Loadable::Loading);
+
+                Task::perform(
+                    async move { PayParams::from_json(&fetch_lnurl(url).await?) },
+                    |result| {
+                        send_message(Message::PayParamsLoaded(
+                            result.map_err(|err| err.to_string()),
+                        ))
+                    },
+                )
+            }
+            Message::PayParamsLoaded(result) => match result {
+                Ok(pay_params) => {
+                    self.loadable_pay_params_or = Some(Loadable::Loaded(pay_params));
+
+                    Task::none()
+                }
+                Err(err) => {
+                    self.loadable_pay_params_or = Some(Loadable::Failed);
+
+                    Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to look up payee".to_string(),
+                        body: err,
+                        status: ToastStatus::Bad,
+                    }))
+                }
+            },
+            Message::LnurlAmountInputChanged(input) => {
+                self.lnurl_amount_input = input;
+
+                Task::none()
+            }
+            Message::LnurlFiatAmountInputToggled(is_fiat_amount_input) => {
+                self.is_lnurl_fiat_amount_input = is_fiat_amount_input;
+                self.lnurl_amount_input.clear();
+
+                Task::none()
+            }
+            Message::LnurlCommentInputChanged(input) => {
+                self.lnurl_comment_input = input;
+
+                Task::none()
+            }
+            Message::RequestLnurlInvoice(amount) => {
+                let Some(Loadable::Loaded(pay_params)) = &self.loadable_pay_params_or else {
+                    return Task::none();
+                };
+
+                let invoice_url = match pay_params.invoice_url(amount, &self.lnurl_comment_input) {
+                    Ok(invoice_url) => invoice_url,
+                    Err(err) => {
+                        return Task::done(app::Message::AddToast(Toast {
+                            title: "Failed to request invoice".to_string(),
+                            body: err.to_string(),
+                            status: ToastStatus::Bad,
+                        }));
+                    }
+                };
+
+                self.is_requesting_lnurl_invoice = true;
+
+                let pay_params = pay_params.clone();
+
+                Task::perform(
+                    async move { pay_params.read_invoice(&fetch_lnurl(invoice_url).await?, amount) },
+                    |result| {
+                        send_message(Message::LnurlInvoiceReceived(
+                            result.map_err(|err| err.to_string()),
+                        ))
+                    },
+                )
+            }
+            Message::LnurlInvoiceReceived(result) => {
+                self.is_requesting_lnurl_invoice = false;
+
+                match result {
+                    // The invoice is paid like any other, so that the federation
+                    // is chosen and the payment approved the same way.
+                    Ok(invoice) => {
+                        self.lightning_invoice_input = invoice.to_string();
+                        self.loadable_pay_params_or = None;
+                        self.lnurl_amount_input.clear();
+                        self.lnurl_comment_input.clear();
+
+                        Task::none()
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to request invoice".to_string(),
+                        body: err,
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::PayInvoice(invoice, federation_id) => {
+                self.loadable_invoice_payment_or = Some(Loadable::Loading);
+
+                let db = self.db.clone();
+                let wallet = self.wallet.clone();
+                let in_flight_operation = self.in_flight_operations.start("Paying an invoice");
+
+                Task::future(async move {
+                    let _in_flight_operation = in_flight_operation;
+
+                    let payment_result = wallet.pay_invoice(invoice.clone(), federation_id).await;
+
+                    match payment_result {
+                        Ok(outcome) => {
+                            // TODO: Notify the user if the payment fails to be recorded.
+                            let _ = db::retry_while_busy(|| {
+                                db.save_payment(
+                                    &federation_id,
+                                    PaymentDirection::Outgoing,
+                                    Amount::from_msats(
+                                        invoice.amount_milli_satoshis().unwrap_or_default(),
+                                    ),
+                                    outcome.fee,
+                                    Some(&invoice),
+                                    outcome.preimage_or.as_deref(),
+                                    None,
+                                    outcome.gateway_id_or.as_ref(),
+                                    outcome.is_simulated,
+                                    None,
+                                )
+                            })
+                            .await;
+
+                            app::Message::Routes(routes::Message::BitcoinWalletPage(
+                                super::Message::Send(Message::PayInvoiceSucceeded(invoice)),
+                            ))
+                        }
+                        Err(err) => app::Message::Routes(routes::Message::BitcoinWalletPage(
+                            super::Message::Send(Message::PayInvoiceFailed((
+                                invoice,
+                                Arc::from(err),
+                            ))),
+                        )),
+                    }
+                })
+            }
+            Message::PayInvoiceSucceeded(invoice) => {
+                let invoice_or = Bolt11Invoice::from_str(&self.lightning_invoice_input).ok();
+
+                if Some(invoice) == invoice_or {
+                    self.loadable_invoice_payment_or = Some(Loadable::Loaded(()));
+
+                    // The invoice can't be paid again, so there's no need to keep it as a draft.
+                    self.lightning_invoice_input.clear();
+                }
+
+                Task::done(app::Message::AddToast(Toast {
+                    title: "Payment succeeded".to_string(),
+                    body: "Invoice was successfully paid".to_string(),
+                    status: ToastStatus::Good,
+                }))
+            }
+            Message::PayInvoiceFailed((invoice, err)) => {
+                let invoice_or = Bolt11Invoice::from_str(&self.lightning_invoice_input).ok();
+
+                if Some(invoice) == invoice_or {
+                    self.loadable_invoice_payment_or = Some(Loadable::Failed);
+                }
+
+                Task::done(app::Message::AddToast(Toast {
+                    title: "Payment failed".to_string(),
+                    body: format!("Failed to pay invoice: {err}"),
+                    status: ToastStatus::Bad,
+                }))
+            }
+            Message::UpdateWalletView(wallet_view) => {
+                self.federation_combo_box_selected_federation = self
+                    .federation_combo_box_selected_federation
+                    .as_ref()
+                    .and_then(|selected_federation| {
+                        wallet_view
+                            .federations
+                            .get(&selected_federation.federation_id)
+                            .cloned()
+                    });
+
+                // Keep the shown max up to date with the federation's balance and gateways.
+                if self.max_sendable_amount_or.is_some() {
+                    self.max_sendable_amount_or = self
+                        .federation_combo_box_selected_federation
+                        .as_ref()
+                        .and_then(|federation| {
+                            federation.max_sendable_amount_or(
+                                self.wallet.get_pinned_gateway(&federation.federation_id),
+                            )
+                        });
+                }
+
+                self.federation_combo_box_state =
+                    combo_box::State::new(wallet_view.federations.into_values().collect());
+
+                Task::none()
+            }
+        }
+    }
+
+    /// Whether paying `invoice_or` has to be approved first. See [`crate::spend_approval`].
+    fn requires_spend_approval(&self, invoice_or: Option<&Bolt11Invoice>) -> bool {
+        invoice_or.is_some_and(|invoice| {
+            self.wallet
+                .get_spend_approval_policy()
+                .is_some_and(|policy| policy.requires_approval(invoice))
+        })
+    }
+
+    pub fn lightning_invoice_input(&self) -> &str {
+        &self.lightning_invoice_input
+    }
+
+    /// Paying is disabled while `is_offline`, since it needs to reach the federation.
+    /// Amounts are also shown and can be typed in fiat if there's an `exchange_rate_or`.
+    pub fn view(
+        &self,
+        is_offline: bool,
+        exchange_rate_or: Option<ExchangeRate>,
+    ) -> Column<app::Message> {
+        let mut container = container("Send");
+
+        if self.wallet.get_payment_simulation() != PaymentSimulation::Disabled {
+            container = container.push(Text::new(
+                "Payment simulation is enabled in developer settings. No funds will be moved.",
+            ));
+        }
+
+        let invoice_or = Bolt11Invoice::from_str(&self.lightning_invoice_input).ok();
+
+        // Fedimint clients can only pay invoices that specify an amount.
+        let is_invoice_amountless = invoice_or
+            .as_ref()
+            .is_some_and(|invoice| invoice.amount_milli_satoshis().is_none());
+
+        // If the inputted invoice is valid and a federation is
+        // selected, then we can proceed to pay the invoice.
+        let parsed_invoice_and_selected_federation_id_or = invoice_or
+            .clone()
+            .filter(|_| !is_invoice_amountless && !is_offline)
+            .and_then(|invoice| {
+                self.federation_combo_box_selected_federation
+                    .as_ref()
+                    .map(|selected_federation| (invoice, selected_federation.federation_id))
+            });
+
+        container = match &self.loadable_invoice_payment_or {
+            Some(Loadable::Loading) if self.requires_spend_approval(invoice_or.as_ref()) => {
+                container.push(Text::new(
+                    "Waiting for the payment to be approved. The approver has been sent a direct message with its details.",
+                ))
+            }
+            Some(Loadable::Loading) => container.push(Text::new("Loading...")),
+            Some(Loadable::Loaded(())) => container.push(Text::new("Payment successful!")),
+            Some(Loadable::Failed) => container.push(Text::new("Payment failed")),
+            None => container
+                .push(
+                    bech32_input(
+                        "Lightning invoice, lightning address, or LNURL",
+                        &self.lightning_invoice_input,
+                        &[
+                            Bech32Kind::LightningInvoice,
+                            Bech32Kind::LightningAddress,
+                            Bech32Kind::Lnurl,
+                        ],
+                        |input| {
+                            app::Message::Routes(routes::Message::BitcoinWalletPage(
+                                super::Message::Send(Message::LightningInvoiceInputChanged(input)),
+                            ))
+                        },
+                    ),
+                )
+                .push(
+                    icon_button("Clear", SvgIcon::Close, PaletteColor::Background).on_press_maybe(
+                        (!self.lightning_invoice_input.is_empty()).then_some(app::Message::Routes(
+                            routes::Message::BitcoinWalletPage(super::Message::Send(
+                                Message::ClearLightningInvoiceInput,
+                            )),
+                        )),
+                    ),
+                )
+                .push_maybe(
+                    invoice_or
+                        .as_ref()
+                        .and_then(Bolt11Invoice::amount_milli_satoshis)
+                        .zip(exchange_rate_or)
+                        .map(|(msats, exchange_rate)| {
+                            Text::new(format!(
+                                "Worth about {}",
+                                exchange_rate.format_fiat(Amount::from_msats(msats))
+                            ))
+                        }),
+                )
+                .push_maybe(self.lnurl_view(is_offline, exchange_rate_or))
+                .push(self.qr_code_parts_view())
+                .push(combo_box(
+                    &self.federation_combo_box_state,
+                    "Federation to pay from",
+                    self.federation_combo_box_selected_federation.as_ref(),
+                    Self::on_combo_box_change,
+                ))
+                .push(
+                    icon_button("Send Max", SvgIcon::CurrencyBitcoin, PaletteColor::Background)
+                        .on_press_maybe(
+                            self.federation_combo_box_selected_federation
+                                .is_some()
+                                .then_some(send_message(Message::SendMax)),
+                        ),
+                )
+                .push_maybe(self.max_sendable_amount_or.map(|max_sendable_amount| {
+                    max_sendable_amount_view(max_sendable_amount, invoice_or.as_ref())
+                }))
+                .push_maybe(is_invoice_amountless.then(|| {
+                    Text::new(
+                        "This invoice has no amount. Keystache can only pay invoices that specify an amount, so ask the payee for a new invoice.",
+                    )
+                }))
+                .push_maybe(
+                    (!is_invoice_amountless && self.requires_spend_approval(invoice_or.as_ref()))
+                        .then(|| {
+                            Text::new(
+                                "This payment is over the spend approval threshold, so it's only made once the approver replies to approve it.",
+                            )
+                        }),
+                )
+                .push(
+                    icon_button("Pay Invoice", SvgIcon::Send, PaletteColor::Primary)
+                        .on_press_maybe(parsed_invoice_and_selected_federation_id_or.map(
+                            |(invoice, federation_id)| {
+                                app::Message::Routes(routes::Message::BitcoinWalletPage(
+                                    super::Message::Send(Message::PayInvoice(
+                                        invoice,
+                                        federation_id,
+                                    )),
+                                ))
+                            },
+                        )),
+                ),
+        };
+
+        container = container.push(
+            icon_button(
+                "Pay Several Invoices",
+                SvgIcon::Send,
+                PaletteColor::Background,
+            )
+            .on_press(app::Message::Routes(routes::Message::Navigate(
+                RouteName::BitcoinWallet(SubrouteName::BatchSend),
+            ))),
+        );
+
+        container = container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(routes::Message::Navigate(RouteName::BitcoinWallet(
+                    SubrouteName::List,
+                ))),
+            ),
+        );
+
+        container
+    }
+
+    /// Looks up the lightning address or LNURL in the input, then asks the payee for an invoice
+    /// once an amount has been entered. `None` if the input isn't a lightning address or LNURL.
+    fn lnurl_view(
+        &self,
+        is_offline: bool,
+        exchange_rate_or: Option<ExchangeRate>,
+    ) -> Option<Column<app::Message>> {
+        let url = lnurl::parse_pay_url(&self.lightning_invoice_input)?;
+
+        let column = Column::new().spacing(10);
+
+        Some(match &self.loadable_pay_params_or {
+            None | Some(Loadable::Failed) => column.push(
+                icon_button("Look Up Payee", SvgIcon::Refresh, PaletteColor::Primary)
+                    .on_press_maybe(
+                        (!is_offline).then(|| send_message(Message::LookUpPayParams(url))),
+                    ),
+            ),
+            Some(Loadable::Loading) => column.push(Text::new("Looking up payee...")),
+            Some(Loadable::Loaded(pay_params)) => {
+                let amount_or = if self.is_lnurl_fiat_amount_input {
+                    fiat_input_to_amount(&self.lnurl_amount_input, exchange_rate_or)
+                } else {
+                    self.lnurl_amount_input
+                        .trim()
+                        .parse::<u64>()
+                        .ok()
+                        .map(Amount::from_sats)
+                }
+                .filter(|amount| {
+                    (pay_params.min_sendable..=pay_params.max_sendable).contains(amount)
+                });
+
+                let is_comment_too_long =
+                    self.lnurl_comment_input.chars().count() > pay_params.comment_allowed;
+
+                column
+                    .push_maybe(
+                        pay_params
+                            .description_or
+                            .as_ref()
+                            .map(|description| Text::new(format!("Paying \"{description}\""))),
+                    )
+                    .push(Text::new(format!(
+                        "The payee accepts between {} and {}.",
+                        format_amount(pay_params.min_sendable),
+                        format_amount(pay_params.max_sendable)
+                    )))
+                    .push(
+                        text_input(
+                            if self.is_lnurl_fiat_amount_input {
+                                "Amount"
+                            } else {
+                                "Amount in sats"
+                            },
+                            &self.lnurl_amount_input,
+                        )
+                        .on_input(|input| send_message(Message::LnurlAmountInputChanged(input)))
+                        .padding(10)
+                        .size(30),
+                    )
+                    .push_maybe(fiat_amount_toggle(
+                        amount_or,
+                        self.is_lnurl_fiat_amount_input,
+                        exchange_rate_or,
+                        |is_fiat_amount_input| {
+                            send_message(Message::LnurlFiatAmountInputToggled(is_fiat_amount_input))
+                        },
+                    ))
+                    .push_maybe((pay_params.comment_allowed > 0).then(|| {
+                        text_input(
+                            &format!("Comment (up to {} characters)", pay_params.comment_allowed),
+                            &self.lnurl_comment_input,
+                        )
+                        .on_input(|input| send_message(Message::LnurlCommentInputChanged(input)))
+                        .padding(10)
+                        .size(30)
+                    }))
+                    .push(
+                        icon_button(
+                            if self.is_requesting_lnurl_invoice {
+                                "Requesting..."
+                            } else {
+                                "Request Invoice"
+                            },
+                            SvgIcon::ArrowDownward,
+                            PaletteColor::Primary,
+                        )
+                        .on_press_maybe(
+                            amount_or
+                                .filter(|_| {
+                                    !is_offline
+                                        && !is_comment_too_long
+                                        && !self.is_requesting_lnurl_invoice
+                                })
+                                .map(|amount| send_message(Message::RequestLnurlInvoice(amount))),
+                        ),
+                    )
+            }
+        })
+    }
+
+    /// Takes the parts of an animated QR code one at a time, for invoices too long for a single
+    /// QR code. Scanners that type what they read usually press Enter after each code.
+    fn qr_code_parts_view(&self) -> Column<app::Message> {
+        let (scanned_part_count, part_count) = self.qr_code_joiner.progress();
+
+        Column::new()
+            .push(
+                text_input(
+                    "Scan or paste animated QR code parts",
+                    &self.qr_code_part_input,
+                )
+                .on_input(|input| send_message(Message::QrCodePartInputChanged(input)))
+                .on_submit(send_message(Message::AddQrCodePart)),
+            )
+            .push_maybe((part_count > 0).then(|| {
+                row![
+                    Text::new(format!(
+                        "Scanned {scanned_part_count} of {part_count} parts"
+                    )),
+                    icon_button("Start Over", SvgIcon::Close, PaletteColor::Background)
+                        .on_press(send_message(Message::ResetQrCodeParts)),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center)
+            }))
+            .spacing(10)
+    }
+
+    fn on_combo_box_change(federation_view: Arc<FederationView>) -> app::Message {
+        app::Message::Routes(routes::Message::BitcoinWalletPage(super::Message::Send(
+            Message::FederationComboBoxSelected(federation_view),
+        )))
+    }
+}
+
+/// Shows the most that can be sent, along with what the payee should do with it.
+fn max_sendable_amount_view<'a>(
+    max_sendable_amount: Amount,
+    invoice_or: Option<&Bolt11Invoice>,
+) -> Column<'a, app::Message> {
+    let invoice_amount_or = invoice_or
+        .and_then(Bolt11Invoice::amount_milli_satoshis)
+        .map(Amount::from_msats);
+
+    let guidance = match invoice_amount_or {
+        Some(invoice_amount) if invoice_amount > max_sendable_amount => format!(
+            "This invoice is for {}, which is more than you can send. Ask the payee for an invoice of {} or less.",
+            format_amount(invoice_amount),
+            format_amount(max_sendable_amount)
+        ),
+        Some(_) => "This invoice is within what you can send.".to_string(),
+        None => format!(
+            "To send your whole balance, ask the payee for an invoice of exactly {}.",
+            format_amount(max_sendable_amount)
+        ),
+    };
+
+    Column::new()
+        .push(Text::new(format!(
+            "You can send up to {} after gateway fees.",
+            format_amount(max_sendable_amount)
+        )))
+        .push(Text::new(guidance))
+        .push(
+            icon_button(
+                "Copy Amount",
+                SvgIcon::ContentCopy,
+                PaletteColor::Background,
+            )
+            .on_press(
+                // Copied in sats, since that's what most wallets ask for when creating an invoice.
+                app::Message::CopyStringToClipboard((max_sendable_amount.msats / 1000).to_string()),
+            ),
+        )
+        .spacing(10)
+}
+
+fn send_message(message: Message) -> app::Message {
+    app::Message::Routes(routes::Message::BitcoinWalletPage(super::Message::Send(
+        message,
+    )))
+}
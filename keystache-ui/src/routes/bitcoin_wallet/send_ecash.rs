@@ -0,0 +1,230 @@
+use std::sync::Arc;
+
+use fedimint_core::{config::FederationId, Amount};
+use iced::{
+    widget::{combo_box, Column, Text},
+    Subscription, Task,
+};
+
+use crate::{
+    app,
+    fedimint::{FederationView, Wallet, WalletView},
+    in_flight::InFlightOperations,
+    routes::{self, container, Loadable, RouteName},
+    ui_components::{
+        icon_button, text_input, AnimatedQrCode, PaletteColor, SvgIcon, Toast, ToastStatus,
+        ANIMATED_QR_CODE_FRAME_INTERVAL,
+    },
+    util::format_amount,
+};
+
+use super::{ConnectedState, SubrouteName};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    AmountInputChanged(String),
+    FederationComboBoxSelected(Arc<FederationView>),
+
+    SpendNotes(FederationId, Amount),
+    NotesSpent(Result<(String, Amount), String>),
+    ShowNextQrCodePart,
+
+    UpdateWalletView(WalletView),
+}
+
+pub struct Page {
+    wallet: Arc<Wallet>,
+    in_flight_operations: InFlightOperations,
+    amount_input: String,
+    federation_combo_box_state: combo_box::State<Arc<FederationView>>,
+    federation_combo_box_selected_federation: Option<Arc<FederationView>>,
+    // The spent notes, how much they're worth, and their QR code.
+    loadable_notes_or: Option<Loadable<(String, Amount, AnimatedQrCode)>>,
+}
+
+impl Page {
+    pub fn new(connected_state: &ConnectedState) -> Self {
+        Self {
+            wallet: connected_state.wallet.clone(),
+            in_flight_operations: connected_state.in_flight_operations.clone(),
+            amount_input: String::new(),
+            federation_combo_box_state: combo_box::State::new(
+                connected_state
+                    .loadable_wallet_view
+                    .as_ref_option()
+                    .cloned()
+                    .map(|wallet_view| wallet_view.federations)
+                    .unwrap_or_default()
+                    .into_values()
+                    .collect(),
+            ),
+            federation_combo_box_selected_federation: super::get_default_federation_view(
+                connected_state,
+            ),
+            loadable_notes_or: None,
+        }
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::AmountInputChanged(input) => {
+                self.amount_input = input;
+
+                Task::none()
+            }
+            Message::FederationComboBoxSelected(federation) => {
+                self.federation_combo_box_selected_federation = Some(federation);
+
+                Task::none()
+            }
+            Message::SpendNotes(federation_id, amount) => {
+                self.loadable_notes_or = Some(Loadable::Loading);
+
+                let wallet = self.wallet.clone();
+                let in_flight_operation = self.in_flight_operations.start("Spending e-cash");
+
+                Task::perform(
+                    async move {
+                        let _in_flight_operation = in_flight_operation;
+
+                        wallet
+                            .spend_notes(federation_id, amount)
+                            .await
+                            .map(|notes| (notes.to_string(), notes.total_amount()))
+                            .map_err(|err| err.to_string())
+                    },
+                    |result| send_ecash_message(Message::NotesSpent(result)),
+                )
+            }
+            Message::NotesSpent(result) => {
+                let qr_code_result = result.and_then(|(notes, amount)| {
+                    AnimatedQrCode::new(&notes)
+                        .map(|qr_code| (notes, amount, qr_code))
+                        .map_err(|err| err.to_string())
+                });
+
+                match qr_code_result {
+                    Ok(notes_data) => {
+                        self.amount_input.clear();
+                        self.loadable_notes_or = Some(Loadable::Loaded(notes_data));
+
+                        Task::none()
+                    }
+                    Err(err) => {
+                        self.loadable_notes_or = Some(Loadable::Failed);
+
+                        Task::done(app::Message::AddToast(Toast {
+                            title: "Failed to send e-cash".to_string(),
+                            body: err,
+                            status: ToastStatus::Bad,
+                        }))
+                    }
+                }
+            }
+            Message::ShowNextQrCodePart => {
+                if let Some(Loadable::Loaded((_, _, qr_code))) = &mut self.loadable_notes_or {
+                    qr_code.show_next_part();
+                }
+
+                Task::none()
+            }
+            Message::UpdateWalletView(wallet_view) => {
+                self.federation_combo_box_selected_federation = self
+                    .federation_combo_box_selected_federation
+                    .as_ref()
+                    .and_then(|selected_federation| {
+                        wallet_view
+                            .federations
+                            .get(&selected_federation.federation_id)
+                            .cloned()
+                    });
+
+                self.federation_combo_box_state =
+                    combo_box::State::new(wallet_view.federations.into_values().collect());
+
+                Task::none()
+            }
+        }
+    }
+
+    /// Cycles through the parts of the notes' QR code, since notes rarely fit in one.
+    pub fn subscription(&self) -> Subscription<app::Message> {
+        match &self.loadable_notes_or {
+            Some(Loadable::Loaded((_, _, qr_code))) if qr_code.is_animated() => {
+                iced::time::every(ANIMATED_QR_CODE_FRAME_INTERVAL)
+                    .map(|_| send_ecash_message(Message::ShowNextQrCodePart))
+            }
+            _ => Subscription::none(),
+        }
+    }
+
+    /// Spending is disabled while `is_offline`, since it needs to reach the federation.
+    pub fn view(&self, is_offline: bool) -> Column<app::Message> {
+        let mut container = container("Send E-Cash");
+
+        container = match &self.loadable_notes_or {
+            Some(Loadable::Loading) => container.push(Text::new("Spending...")),
+            Some(Loadable::Loaded((notes, amount, qr_code))) => container
+                .push(Text::new(format!(
+                    "These notes are worth {}. Anyone who has them can redeem them, so only share them with whoever you're paying. If they aren't redeemed within a week, they're returned to your wallet.",
+                    format_amount(*amount)
+                )))
+                .push(qr_code.view())
+                .push(
+                    icon_button("Copy Notes", SvgIcon::ContentCopy, PaletteColor::Primary)
+                        .on_press(app::Message::CopyStringToClipboard(notes.clone())),
+                ),
+            Some(Loadable::Failed) | None => {
+                let amount_or = self
+                    .amount_input
+                    .trim()
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|sats| *sats > 0)
+                    .map(Amount::from_sats);
+
+                let spend_message_or = amount_or
+                    .zip(self.federation_combo_box_selected_federation.as_ref())
+                    .filter(|(amount, federation)| !is_offline && *amount <= federation.balance)
+                    .map(|(amount, federation)| {
+                        send_ecash_message(Message::SpendNotes(federation.federation_id, amount))
+                    });
+
+                container
+                    .push(Text::new(
+                        "Send e-cash directly as a string of text or a QR code, without Lightning. The recipient redeems it with any Fedimint wallet that can reach the federation.",
+                    ))
+                    .push(
+                        text_input("Amount in sats", &self.amount_input)
+                            .on_input(|input| send_ecash_message(Message::AmountInputChanged(input)))
+                            .padding(10)
+                            .size(30),
+                    )
+                    .push(combo_box(
+                        &self.federation_combo_box_state,
+                        "Federation to send from",
+                        self.federation_combo_box_selected_federation.as_ref(),
+                        |federation| send_ecash_message(Message::FederationComboBoxSelected(federation)),
+                    ))
+                    .push(
+                        icon_button("Create Notes", SvgIcon::Send, PaletteColor::Primary)
+                            .on_press_maybe(spend_message_or),
+                    )
+            }
+        };
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(routes::Message::Navigate(RouteName::BitcoinWallet(
+                    SubrouteName::List,
+                ))),
+            ),
+        )
+    }
+}
+
+fn send_ecash_message(message: Message) -> app::Message {
+    app::Message::Routes(routes::Message::BitcoinWalletPage(
+        super::Message::SendEcash(message),
+    ))
+}
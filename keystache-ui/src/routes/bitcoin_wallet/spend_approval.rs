@@ -0,0 +1,242 @@
+use std::sync::Arc;
+
+use fedimint_core::Amount;
+use iced::{
+    widget::{row, Column, Text},
+    Alignment, Task,
+};
+use nostr_sdk::{FromBech32, PublicKey, ToBech32};
+
+use crate::{
+    app,
+    db::Database,
+    fedimint::Wallet,
+    routes::{self, container, RouteName},
+    spend_approval::SpendApprovalPolicy,
+    ui_components::{
+        bech32_input, icon_button, text_input, Bech32Kind, PaletteColor, SvgIcon, Toast,
+        ToastStatus,
+    },
+    util::{format_amount, truncate_text},
+};
+
+use super::{ConnectedState, SubrouteName};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    ApproverInputChanged(String),
+    ThresholdInputChanged(String),
+    SavePolicy(PublicKey, Amount),
+    RemovePolicy,
+    PolicyChanged(Result<Option<SpendApprovalPolicy>, String>),
+}
+
+pub struct Page {
+    db: Arc<Database>,
+    wallet: Arc<Wallet>,
+    approver_input: String,
+    threshold_input: String,
+    // Whether a policy change is waiting for the current approver.
+    is_changing_policy: bool,
+}
+
+impl Page {
+    pub fn new(connected_state: &ConnectedState) -> Self {
+        let policy_or = connected_state.wallet.get_spend_approval_policy();
+
+        Self {
+            db: connected_state.db.clone(),
+            wallet: connected_state.wallet.clone(),
+            approver_input: policy_or
+                .as_ref()
+                .and_then(|policy| policy.approver.to_bech32().ok())
+                .unwrap_or_default(),
+            threshold_input: policy_or
+                .map(|policy| policy.threshold.sats_round_down().to_string())
+                .unwrap_or_default(),
+            is_changing_policy: false,
+        }
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::ApproverInputChanged(input) => {
+                self.approver_input = input;
+
+                Task::none()
+            }
+            Message::ThresholdInputChanged(input) => {
+                self.threshold_input = input;
+
+                Task::none()
+            }
+            Message::SavePolicy(approver, threshold) => {
+                // The sender key is kept when the policy changes, so
+                // that the approver keeps getting requests from it.
+                let policy = match self.wallet.get_spend_approval_policy() {
+                    Some(policy) => policy.with_approver_and_threshold(approver, threshold),
+                    None => SpendApprovalPolicy::new(approver, threshold),
+                };
+
+                self.change_policy(Some(policy))
+            }
+            Message::RemovePolicy => self.change_policy(None),
+            Message::PolicyChanged(result) => {
+                self.is_changing_policy = false;
+
+                match result {
+                    Ok(Some(policy)) => Task::done(app::Message::AddToast(Toast {
+                        title: "Saved spend approval".to_string(),
+                        body: format!(
+                            "Payments over {} will have to be approved first.",
+                            format_amount(policy.threshold)
+                        ),
+                        status: ToastStatus::Good,
+                    })),
+                    Ok(None) => {
+                        self.approver_input.clear();
+                        self.threshold_input.clear();
+
+                        Task::done(app::Message::AddToast(Toast {
+                            title: "Removed spend approval".to_string(),
+                            body: "Payments no longer have to be approved.".to_string(),
+                            status: ToastStatus::Good,
+                        }))
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to change spend approval".to_string(),
+                        body: err,
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+        }
+    }
+
+    /// Changes the policy once the current approver approves it, if the change
+    /// would let more through without them, and saves it.
+    fn change_policy(&mut self, new_policy_or: Option<SpendApprovalPolicy>) -> Task<app::Message> {
+        self.is_changing_policy = true;
+
+        let db = self.db.clone();
+        let wallet = self.wallet.clone();
+
+        Task::perform(
+            async move {
+                wallet
+                    .change_spend_approval_policy(new_policy_or.clone())
+                    .await?;
+
+                db.save_spend_approval_policy(new_policy_or.as_ref())?;
+
+                Ok(new_policy_or)
+            },
+            |result: anyhow::Result<_>| {
+                spend_approval_message(Message::PolicyChanged(
+                    result.map_err(|err| err.to_string()),
+                ))
+            },
+        )
+    }
+
+    pub fn view(&self) -> Column<app::Message> {
+        let policy_or = self.wallet.get_spend_approval_policy();
+
+        let mut container = container("Spend Approval").push(Text::new(
+            "Payments over the threshold are only made once a second person approves them. Keystache sends them a direct message with the payment's details, and waits for them to reply from any Nostr client. Invoices without an amount always have to be approved, and so do changes that would let more through without them.",
+        ));
+
+        if self.is_changing_policy {
+            container = container.push(Text::new(
+                "Waiting for the approver to approve the change...",
+            ));
+        }
+
+        if let Some(policy) = &policy_or {
+            let sender_npub = policy.sender_public_key().to_bech32().unwrap_or_default();
+
+            container = container
+                .push(Text::new(format!(
+                    "Payments over {} have to be approved by {}.",
+                    format_amount(policy.threshold),
+                    truncate_text(
+                        &policy.approver.to_bech32().unwrap_or_default(),
+                        24,
+                        true
+                    )
+                )))
+                .push(Text::new(
+                    "Requests come from the key below, so the approver should follow it or add it as a contact so that their client doesn't hide its messages.",
+                ))
+                .push(
+                    row![
+                        Text::new(truncate_text(&sender_npub, 24, true)),
+                        icon_button("Copy", SvgIcon::ContentCopy, PaletteColor::Background)
+                            .on_press(app::Message::CopyStringToClipboard(sender_npub)),
+                    ]
+                    .spacing(10)
+                    .align_y(Alignment::Center),
+                )
+                .push(
+                    icon_button("Remove Spend Approval", SvgIcon::Delete, PaletteColor::Danger)
+                        .on_press_maybe(
+                            (!self.is_changing_policy)
+                                .then(|| spend_approval_message(Message::RemovePolicy)),
+                        ),
+                );
+        }
+
+        let approver_or = PublicKey::from_bech32(self.approver_input.trim()).ok();
+
+        let threshold_or = self
+            .threshold_input
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .map(Amount::from_sats);
+
+        let save_message_or = approver_or
+            .zip(threshold_or)
+            .filter(|_| !self.is_changing_policy)
+            .filter(|(approver, threshold)| {
+                policy_or.as_ref().is_none_or(|policy| {
+                    policy.approver != *approver || policy.threshold != *threshold
+                })
+            })
+            .map(|(approver, threshold)| {
+                spend_approval_message(Message::SavePolicy(approver, threshold))
+            });
+
+        container
+            .push(Text::new("Approver").size(25))
+            .push(bech32_input(
+                "Approver's npub",
+                &self.approver_input,
+                &[Bech32Kind::Npub],
+                |input| spend_approval_message(Message::ApproverInputChanged(input)),
+            ))
+            .push(
+                text_input("Threshold in sats", &self.threshold_input)
+                    .on_input(|input| spend_approval_message(Message::ThresholdInputChanged(input)))
+                    .padding(10)
+                    .size(20),
+            )
+            .push(
+                icon_button("Save", SvgIcon::Save, PaletteColor::Primary)
+                    .on_press_maybe(save_message_or),
+            )
+            .push(
+                icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                    app::Message::Routes(routes::Message::Navigate(RouteName::BitcoinWallet(
+                        SubrouteName::List,
+                    ))),
+                ),
+            )
+    }
+}
+
+fn spend_approval_message(message: Message) -> app::Message {
+    app::Message::Routes(routes::Message::BitcoinWalletPage(
+        super::Message::SpendApproval(message),
+    ))
+}
@@ -0,0 +1,631 @@
+use std::{collections::BTreeMap, fmt::Write, sync::Arc};
+
+use chrono::{NaiveDateTime, Utc};
+use fedimint_core::Amount;
+use iced::{
+    widget::{container::Style, row, Column, Container, Row, Space, Text},
+    Alignment, Length, Task,
+};
+use nostr_sdk::ToBech32;
+
+use crate::{
+    app,
+    config::ClockFormat,
+    db::Database,
+    fedimint::{PaymentDirection, PaymentRecord},
+    privacy::InvoicePrivacy,
+    routes::{self, container, Loadable, RouteName},
+    ui_components::{icon_button, PaletteColor, SvgIcon, Toast, ToastStatus},
+    util::{format_amount, format_time, truncate_text},
+    zap::ZapRecord,
+};
+
+use super::{ConnectedState, SubrouteName};
+
+const CSV_EXPORT_FILE_NAME: &str = "keystache_payments.csv";
+
+const RECENT_PAYMENTS_COUNT: usize = 10;
+
+const RECENT_ZAPS_COUNT: usize = 10;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    ExportCsv,
+}
+
+pub struct Page {
+    db: Arc<Database>,
+    clock_format: ClockFormat,
+    loadable_payments_and_stats: Loadable<(Vec<PaymentRecord>, PaymentStats)>,
+    loadable_zaps: Loadable<Vec<ZapRecord>>,
+}
+
+impl Page {
+    pub fn new(connected_state: &ConnectedState) -> Self {
+        // TODO: Add pagination.
+        let loadable_payments_and_stats = match connected_state.db.list_payments(999, 0) {
+            Ok(payments) => {
+                let stats = PaymentStats::from_payments(&payments);
+                Loadable::Loaded((payments, stats))
+            }
+            Err(_err) => Loadable::Failed,
+        };
+
+        // TODO: Add pagination.
+        let loadable_zaps = match connected_state.db.list_zap_receipts(999, 0) {
+            Ok(zaps) => Loadable::Loaded(zaps),
+            Err(_err) => Loadable::Failed,
+        };
+
+        Self {
+            db: connected_state.db.clone(),
+            clock_format: connected_state.settings.get().clock_format,
+            loadable_payments_and_stats,
+            loadable_zaps,
+        }
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::ExportCsv => {
+                let Loadable::Loaded((payments, _)) = &self.loadable_payments_and_stats else {
+                    return Task::none();
+                };
+
+                let export_result = self
+                    .db
+                    .get_invoice_privacy()
+                    .and_then(|invoice_privacy| export_csv(payments, invoice_privacy));
+
+                match export_result {
+                    Ok(path) => Task::done(app::Message::AddToast(Toast {
+                        title: "Exported payments".to_string(),
+                        body: format!("Your payments were exported to {}", path.display()),
+                        status: ToastStatus::Good,
+                    })),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to export payments".to_string(),
+                        body: format!("Failed to export payments: {err}"),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+        }
+    }
+
+    pub fn view(&self) -> Column<app::Message> {
+        let now = Utc::now().naive_utc();
+
+        let mut container = container("Statistics");
+
+        match &self.loadable_payments_and_stats {
+            Loadable::Loading => {
+                container = container.push(Text::new("Loading..."));
+            }
+            Loadable::Loaded((payments, stats)) => {
+                container = container
+                    .push(Text::new(format!("Payments: {}", payments.len())))
+                    .push(Text::new(format!(
+                        "Total gateway fees paid: {}",
+                        format_amount(stats.total_fees)
+                    )))
+                    .push(Text::new(format!(
+                        "Average payment size: {}",
+                        stats
+                            .average_payment_size_or
+                            .map_or_else(|| "N/A".to_string(), format_amount)
+                    )))
+                    .push(Text::new("Monthly Totals").size(25));
+
+                let max_monthly_msats = stats
+                    .monthly_totals
+                    .values()
+                    .map(|totals| totals.sent.msats.max(totals.received.msats))
+                    .max()
+                    .unwrap_or_default();
+
+                for (month, totals) in &stats.monthly_totals {
+                    container = container.push(
+                        Column::new()
+                            .push(Text::new(month.clone()).size(20))
+                            .push(bar_chart_row(
+                                "Sent",
+                                totals.sent,
+                                max_monthly_msats,
+                                PaletteColor::Danger,
+                            ))
+                            .push(bar_chart_row(
+                                "Received",
+                                totals.received,
+                                max_monthly_msats,
+                                PaletteColor::Success,
+                            ))
+                            .spacing(5),
+                    );
+                }
+
+                // TODO: Show zaps sent per npub once zaps are tracked in the payment log.
+
+                container = container.push(Text::new("Recent Payments").size(25));
+
+                for payment_group in group_recent_payments(payments, RECENT_PAYMENTS_COUNT) {
+                    container = container.push(match payment_group.as_slice() {
+                        [payment] => {
+                            Column::new().push(recent_payment_row(payment, now, self.clock_format))
+                        }
+                        batch => recent_payment_batch_view(batch, now, self.clock_format),
+                    });
+                }
+
+                container = container.push(
+                    icon_button("Export CSV", SvgIcon::FileCopy, PaletteColor::Primary).on_press(
+                        app::Message::Routes(routes::Message::BitcoinWalletPage(
+                            super::Message::Stats(Message::ExportCsv),
+                        )),
+                    ),
+                );
+            }
+            Loadable::Failed => {
+                container = container.push(Text::new("Failed to load payments"));
+            }
+        }
+
+        container = container.push(Text::new("Recent Zaps").size(25));
+
+        match &self.loadable_zaps {
+            Loadable::Loading => {
+                container = container.push(Text::new("Loading..."));
+            }
+            Loadable::Loaded(zaps) if zaps.is_empty() => {
+                container = container.push(Text::new("No zaps received"));
+            }
+            Loadable::Loaded(zaps) => {
+                // Zaps are ordered oldest first.
+                for zap in zaps.iter().rev().take(RECENT_ZAPS_COUNT) {
+                    container = container.push(recent_zap_row(zap, now, self.clock_format));
+                }
+            }
+            Loadable::Failed => {
+                container = container.push(Text::new("Failed to load zaps"));
+            }
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(routes::Message::Navigate(RouteName::BitcoinWallet(
+                    SubrouteName::List,
+                ))),
+            ),
+        )
+    }
+}
+
+/// A single labelled horizontal bar, sized relative to `max_msats`.
+fn bar_chart_row<'a>(
+    label: &str,
+    amount: Amount,
+    max_msats: u64,
+    palette_color: PaletteColor,
+) -> Row<'a, app::Message> {
+    let bar_portion = if max_msats == 0 {
+        0
+    } else {
+        u16::try_from(amount.msats * 100 / max_msats).unwrap_or(100)
+    };
+
+    let mut bar_row = Row::new();
+
+    if bar_portion > 0 {
+        bar_row = bar_row.push(
+            Container::new(Space::new(Length::Fill, 12.0))
+                .width(Length::FillPortion(bar_portion))
+                .style(move |theme| Style {
+                    background: Some(palette_color.to_color(theme).into()),
+                    ..Style::default()
+                }),
+        );
+    }
+
+    if bar_portion < 100 {
+        bar_row = bar_row.push(Space::with_width(Length::FillPortion(100 - bar_portion)));
+    }
+
+    row![
+        Text::new(label.to_string()).width(80),
+        bar_row.width(Length::Fill),
+        Text::new(format_amount(amount)).width(150),
+    ]
+    .spacing(10)
+}
+
+fn recent_payment_row<'a>(
+    payment: &PaymentRecord,
+    now: NaiveDateTime,
+    clock_format: ClockFormat,
+) -> Row<'a, app::Message> {
+    let direction_text = match payment.direction {
+        PaymentDirection::Incoming => "Received",
+        PaymentDirection::Outgoing => "Sent",
+    };
+
+    row![
+        Text::new(format_time(payment.create_time, now, clock_format)).width(300),
+        Text::new(format!(
+            "{direction_text} {}",
+            format_amount(payment.amount)
+        ))
+        .width(Length::Fill),
+        icon_button("Details", SvgIcon::ChevronRight, PaletteColor::Background).on_press(
+            app::Message::Routes(routes::Message::Navigate(RouteName::BitcoinWallet(
+                SubrouteName::PaymentDetails(payment.id),
+            ))),
+        ),
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center)
+}
+
+/// A batch of payments made together, shown as a single entry with its total
+/// above the payments that it's made up of.
+fn recent_payment_batch_view<'a>(
+    batch: &[&PaymentRecord],
+    now: NaiveDateTime,
+    clock_format: ClockFormat,
+) -> Column<'a, app::Message> {
+    let total = Amount::from_msats(batch.iter().map(|payment| payment.amount.msats).sum());
+    let total_fee = Amount::from_msats(batch.iter().map(|payment| payment.fee.msats).sum());
+
+    let mut column = Column::new().push(
+        row![
+            Text::new(format_time(batch[0].create_time, now, clock_format)).width(300),
+            Text::new(format!(
+                "Paid {} invoices in a batch, {} with {} in fees",
+                batch.len(),
+                format_amount(total),
+                format_amount(total_fee)
+            ))
+            .width(Length::Fill),
+        ]
+        .spacing(10),
+    );
+
+    for payment in batch {
+        column = column
+            .push(Container::new(recent_payment_row(payment, now, clock_format)).padding([0, 20]));
+    }
+
+    column.spacing(5)
+}
+
+/// Groups the most recent payments into history entries, newest first. Payments made in the
+/// same batch share an entry, so a batch only takes up one of the `entry_count` entries.
+fn group_recent_payments(
+    payments: &[PaymentRecord],
+    entry_count: usize,
+) -> Vec<Vec<&PaymentRecord>> {
+    let mut entries: Vec<Vec<&PaymentRecord>> = Vec::new();
+
+    // Payments are ordered oldest first.
+    for payment in payments.iter().rev() {
+        let batch_entry_or = payment.batch_id_or.and_then(|batch_id| {
+            entries
+                .iter_mut()
+                .find(|entry| entry[0].batch_id_or == Some(batch_id))
+        });
+
+        match batch_entry_or {
+            Some(entry) => entry.push(payment),
+            None if entries.len() < entry_count => entries.push(vec![payment]),
+            None => {}
+        }
+    }
+
+    // Show the payments within a batch in the order they were made.
+    for entry in &mut entries {
+        entry.reverse();
+    }
+
+    entries
+}
+
+fn recent_zap_row<'a>(
+    zap: &ZapRecord,
+    now: NaiveDateTime,
+    clock_format: ClockFormat,
+) -> Row<'a, app::Message> {
+    let sender = zap.receipt.sender_public_key_or.map_or_else(
+        || "Anonymous".to_string(),
+        |public_key| {
+            public_key.to_bech32().map_or_else(
+                |_| public_key.to_string(),
+                |npub| truncate_text(&npub, 15, true),
+            )
+        },
+    );
+
+    // Zaps whose invoice wasn't paid into one of the user's federations can't be confirmed,
+    // since the receipt could have been published by anyone.
+    let details_button_or = zap.payment_id_or.map(|payment_id| {
+        icon_button("Details", SvgIcon::ChevronRight, PaletteColor::Background).on_press(
+            app::Message::Routes(routes::Message::Navigate(RouteName::BitcoinWallet(
+                SubrouteName::PaymentDetails(payment_id),
+            ))),
+        )
+    });
+
+    row![
+        Text::new(format_time(zap.create_time, now, clock_format)).width(300),
+        Text::new(format!(
+            "{} from {sender}",
+            format_amount(zap.receipt.amount)
+        ))
+        .width(Length::Fill),
+    ]
+    .push_maybe(details_button_or)
+    .push_maybe(
+        zap.payment_id_or
+            .is_none()
+            .then(|| Text::new("Unconfirmed")),
+    )
+    .spacing(10)
+    .align_y(Alignment::Center)
+}
+
+/// Writes all payments as CSV into the user's downloads directory,
+/// scrubbing invoice metadata according to `invoice_privacy`.
+/// Returns the path of the written file.
+fn export_csv(
+    payments: &[PaymentRecord],
+    invoice_privacy: InvoicePrivacy,
+) -> anyhow::Result<std::path::PathBuf> {
+    let user_dirs = directories::UserDirs::new()
+        .ok_or_else(|| anyhow::anyhow!("Could not determine user directories."))?;
+
+    let folder = user_dirs
+        .download_dir()
+        .unwrap_or_else(|| user_dirs.home_dir());
+
+    let path = folder.join(CSV_EXPORT_FILE_NAME);
+
+    std::fs::write(&path, payments_to_csv(payments, invoice_privacy))?;
+
+    Ok(path)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MonthlyTotals {
+    pub sent: Amount,
+    pub received: Amount,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PaymentStats {
+    /// Sent and received totals, keyed by month in `YYYY-MM` format.
+    pub monthly_totals: BTreeMap<String, MonthlyTotals>,
+    pub total_fees: Amount,
+    pub average_payment_size_or: Option<Amount>,
+}
+
+impl PaymentStats {
+    pub fn from_payments(payments: &[PaymentRecord]) -> Self {
+        let mut monthly_totals: BTreeMap<String, MonthlyTotals> = BTreeMap::new();
+        let mut total_fee_msats = 0;
+        let mut total_amount_msats = 0;
+
+        for payment in payments {
+            let totals = monthly_totals
+                .entry(payment.create_time.format("%Y-%m").to_string())
+                .or_default();
+
+            match payment.direction {
+                PaymentDirection::Incoming => {
+                    totals.received =
+                        Amount::from_msats(totals.received.msats + payment.amount.msats);
+                }
+                PaymentDirection::Outgoing => {
+                    totals.sent = Amount::from_msats(totals.sent.msats + payment.amount.msats);
+                }
+            }
+
+            total_fee_msats += payment.fee.msats;
+            total_amount_msats += payment.amount.msats;
+        }
+
+        let average_payment_size_or = u64::try_from(payments.len())
+            .ok()
+            .filter(|payment_count| *payment_count > 0)
+            .map(|payment_count| Amount::from_msats(total_amount_msats / payment_count));
+
+        Self {
+            monthly_totals,
+            total_fees: Amount::from_msats(total_fee_msats),
+            average_payment_size_or,
+        }
+    }
+}
+
+fn payments_to_csv(payments: &[PaymentRecord], invoice_privacy: InvoicePrivacy) -> String {
+    let mut csv = String::new();
+
+    // Document what was scrubbed so that whoever receives the export knows why fields are empty.
+    if let Some(redaction_note) = invoice_privacy.redaction_note() {
+        // Writing to a `String` can't fail.
+        let _ = writeln!(csv, "# {redaction_note}");
+    }
+
+    csv.push_str(
+        "time,federation_id,direction,amount_msats,fee_msats,payment_hash,description,bolt11_invoice,simulated,requested_by,batch_id\n",
+    );
+
+    for payment in payments {
+        let scrubbed_invoice_or = payment
+            .bolt11_invoice_or
+            .as_ref()
+            .map(|invoice| invoice_privacy.scrub(invoice));
+
+        let (payment_hash, description, bolt11_invoice) = scrubbed_invoice_or
+            .map(|scrubbed_invoice| {
+                (
+                    scrubbed_invoice.payment_hash,
+                    scrubbed_invoice.description,
+                    scrubbed_invoice.bolt11_invoice,
+                )
+            })
+            .unwrap_or_default();
+
+        let requested_by = payment
+            .requester_public_key_or
+            .and_then(|public_key| public_key.to_bech32().ok())
+            .unwrap_or_default();
+
+        // Writing to a `String` can't fail.
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            payment.create_time,
+            payment.federation_id,
+            payment.direction.as_str(),
+            payment.amount.msats,
+            payment.fee.msats,
+            payment_hash,
+            escape_csv_field(&description),
+            bolt11_invoice,
+            payment.is_simulated,
+            requested_by,
+            payment
+                .batch_id_or
+                .map(|batch_id| batch_id.to_string())
+                .unwrap_or_default()
+        );
+    }
+
+    csv
+}
+
+/// Quotes a CSV field if it contains characters that would otherwise break the row.
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::NaiveDate;
+    use fedimint_core::config::FederationId;
+
+    use super::*;
+
+    fn payment(direction: PaymentDirection, sats: u64, fee_sats: u64, month: u32) -> PaymentRecord {
+        PaymentRecord {
+            id: 1,
+            federation_id: FederationId::from_str(
+                "15db8cb4f1ec8e484d73b889372bec94812580f929e8148b7437d359af422cd3",
+            )
+            .unwrap(),
+            direction,
+            amount: Amount::from_sats(sats),
+            fee: Amount::from_sats(fee_sats),
+            bolt11_invoice_or: None,
+            preimage_or: None,
+            requester_public_key_or: None,
+            gateway_id_or: None,
+            is_simulated: false,
+            batch_id_or: None,
+            create_time: NaiveDate::from_ymd_opt(2024, month, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_payment_stats() {
+        // No payments means no averages or totals.
+        assert_eq!(PaymentStats::from_payments(&[]), PaymentStats::default());
+
+        let stats = PaymentStats::from_payments(&[
+            payment(PaymentDirection::Outgoing, 1_000, 10, 9),
+            payment(PaymentDirection::Incoming, 5_000, 0, 9),
+            payment(PaymentDirection::Outgoing, 3_000, 20, 10),
+        ]);
+
+        assert_eq!(stats.total_fees, Amount::from_sats(30));
+        assert_eq!(
+            stats.average_payment_size_or,
+            Some(Amount::from_sats(3_000))
+        );
+        assert_eq!(
+            stats.monthly_totals.get("2024-09"),
+            Some(&MonthlyTotals {
+                sent: Amount::from_sats(1_000),
+                received: Amount::from_sats(5_000),
+            })
+        );
+        assert_eq!(
+            stats.monthly_totals.get("2024-10"),
+            Some(&MonthlyTotals {
+                sent: Amount::from_sats(3_000),
+                received: Amount::ZERO,
+            })
+        );
+    }
+
+    #[test]
+    fn test_group_recent_payments() {
+        let mut payments: Vec<PaymentRecord> = (1..=5)
+            .map(|id| PaymentRecord {
+                id,
+                ..payment(PaymentDirection::Outgoing, 1_000, 10, 9)
+            })
+            .collect();
+
+        // An incoming payment lands in the middle of a batch.
+        payments[1].batch_id_or = Some(7);
+        payments[3].batch_id_or = Some(7);
+        payments[4].batch_id_or = Some(7);
+
+        let ids = |entries: Vec<Vec<&PaymentRecord>>| -> Vec<Vec<i32>> {
+            entries
+                .iter()
+                .map(|entry| entry.iter().map(|payment| payment.id).collect())
+                .collect()
+        };
+
+        assert_eq!(
+            ids(group_recent_payments(&payments, 10)),
+            vec![vec![2, 4, 5], vec![3], vec![1]]
+        );
+
+        // A batch counts as a single entry.
+        assert_eq!(
+            ids(group_recent_payments(&payments, 2)),
+            vec![vec![2, 4, 5], vec![3]]
+        );
+    }
+
+    #[test]
+    fn test_payments_to_csv() {
+        let payments = [payment(PaymentDirection::Outgoing, 1_000, 10, 9)];
+
+        // Nothing is redacted, so there's no redaction note.
+        assert!(payments_to_csv(&payments, InvoicePrivacy::Full).starts_with("time,"));
+
+        // Redacted exports document what was removed.
+        assert!(payments_to_csv(&payments, InvoicePrivacy::Redacted).starts_with("# "));
+    }
+
+    #[test]
+    fn test_escape_csv_field() {
+        assert_eq!(escape_csv_field("Coffee"), "Coffee");
+        assert_eq!(escape_csv_field("Coffee, large"), "\"Coffee, large\"");
+        assert_eq!(
+            escape_csv_field("A \"big\" coffee"),
+            "\"A \"\"big\"\" coffee\""
+        );
+    }
+}
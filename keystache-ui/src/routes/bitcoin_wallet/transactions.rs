@@ -0,0 +1,301 @@
+use std::sync::Arc;
+
+use chrono::{NaiveDateTime, Utc};
+use fedimint_core::Amount;
+use iced::{
+    widget::{row, Column, Row, Text},
+    Alignment, Task,
+};
+
+use crate::{
+    app,
+    config::ClockFormat,
+    db::Database,
+    exchange_rate::FiatCurrency,
+    fedimint::{PaymentDirection, TransactionRecord, TransactionStatus, Wallet},
+    routes::{self, container, Loadable, RouteName},
+    ui_components::{icon_button, PaletteColor, SvgIcon, Toast, ToastStatus, PAGE_SIZE},
+    util::{format_amount, format_time, truncate_text},
+};
+
+use super::{ConnectedState, SubrouteName};
+
+// How many of each federation's newest operations are read when syncing. Older
+// transactions were saved by earlier syncs, so this only has to cover recent ones.
+const SYNC_OPERATION_LIMIT: usize = 500;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SyncTransactions,
+    TransactionsSynced(Result<Vec<TransactionRecord>, String>),
+    PageChanged(i64),
+}
+
+pub struct Page {
+    db: Arc<Database>,
+    wallet: Arc<Wallet>,
+    clock_format: ClockFormat,
+    // New transactions are given the saved price of bitcoin in this currency.
+    fiat_currency_or: Option<FiatCurrency>,
+    page_index: i64,
+    is_syncing: bool,
+    // The transactions on the current page, and how many are saved in total.
+    loadable_transactions: Loadable<(Vec<TransactionRecord>, i64)>,
+}
+
+impl Page {
+    pub fn new(connected_state: &ConnectedState) -> Self {
+        let mut page = Self {
+            db: connected_state.db.clone(),
+            wallet: connected_state.wallet.clone(),
+            clock_format: connected_state.settings.get().clock_format,
+            fiat_currency_or: connected_state.settings.get().fiat_currency_or,
+            page_index: 0,
+            is_syncing: false,
+            loadable_transactions: Loadable::Loading,
+        };
+
+        page.load_transactions();
+
+        page
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::SyncTransactions => self.sync_transactions(),
+            Message::TransactionsSynced(result) => {
+                self.is_syncing = false;
+
+                let result = result.and_then(|transactions| {
+                    self.db
+                        .save_transactions(&transactions, self.fiat_currency_or)
+                        .map_err(|err| err.to_string())
+                });
+
+                self.load_transactions();
+
+                match result {
+                    Ok(()) => Task::none(),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to sync transactions".to_string(),
+                        body: err,
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::PageChanged(page_index) => {
+                self.page_index = page_index;
+                self.load_transactions();
+
+                Task::none()
+            }
+        }
+    }
+
+    /// Reads new transactions from every connected federation's operation log,
+    /// which has to wait for the wallet. Saved transactions are shown meanwhile.
+    pub fn sync_transactions(&mut self) -> Task<app::Message> {
+        self.is_syncing = true;
+
+        let wallet = self.wallet.clone();
+
+        Task::perform(
+            async move {
+                wallet
+                    .list_transactions(SYNC_OPERATION_LIMIT)
+                    .await
+                    .map_err(|err| err.to_string())
+            },
+            |result| transactions_message(Message::TransactionsSynced(result)),
+        )
+    }
+
+    fn load_transactions(&mut self) {
+        self.loadable_transactions = match self.db.count_transactions() {
+            Ok(count) => {
+                self.page_index = clamp_page_index(self.page_index, count);
+
+                match self
+                    .db
+                    .list_transactions(PAGE_SIZE, self.page_index * PAGE_SIZE)
+                {
+                    Ok(transactions) => Loadable::Loaded((transactions, count)),
+                    Err(_err) => Loadable::Failed,
+                }
+            }
+            Err(_err) => Loadable::Failed,
+        };
+    }
+
+    pub fn view(&self) -> Column<app::Message> {
+        let sync_label = if self.is_syncing {
+            "Syncing..."
+        } else {
+            "Sync"
+        };
+
+        let mut container = container("Transactions")
+            .push(Text::new(
+                "Every send and receive recorded by your federations, including payments that failed or haven't finished yet. Transactions stay listed after leaving a federation.",
+            ))
+            .push(
+                icon_button(sync_label, SvgIcon::Refresh, PaletteColor::Background)
+                    .on_press_maybe(
+                        (!self.is_syncing)
+                            .then(|| transactions_message(Message::SyncTransactions)),
+                    ),
+            );
+
+        match &self.loadable_transactions {
+            Loadable::Loading => {
+                container = container.push(Text::new("Loading..."));
+            }
+            Loadable::Loaded((transactions, _)) if transactions.is_empty() => {
+                container = container.push(Text::new("No transactions"));
+            }
+            Loadable::Loaded((transactions, count)) => {
+                let now = Utc::now().naive_utc();
+
+                for transaction in transactions {
+                    container = container.push(self.transaction_view(transaction, now));
+                }
+
+                container =
+                    container.push(pagination_controls(self.page_index, *count, |page_index| {
+                        transactions_message(Message::PageChanged(page_index))
+                    }));
+            }
+            Loadable::Failed => {
+                container = container.push(Text::new("Failed to load transactions"));
+            }
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(routes::Message::Navigate(RouteName::BitcoinWallet(
+                    SubrouteName::List,
+                ))),
+            ),
+        )
+    }
+
+    fn transaction_view<'a>(
+        &self,
+        transaction: &TransactionRecord,
+        now: NaiveDateTime,
+    ) -> Column<'a, app::Message> {
+        let (direction, sign) = match transaction.direction {
+            PaymentDirection::Incoming => ("Received", "+"),
+            PaymentDirection::Outgoing => ("Sent", "-"),
+        };
+
+        let fee_text = if transaction.fee == Amount::ZERO {
+            String::new()
+        } else {
+            format!(" + {} fee", format_amount(transaction.fee))
+        };
+
+        let fiat_text = transaction
+            .exchange_rate_or
+            .map(|exchange_rate| {
+                format!(
+                    " (≈ {} at the time)",
+                    exchange_rate.format_fiat(transaction.amount)
+                )
+            })
+            .unwrap_or_default();
+
+        let federation_name = transaction
+            .federation_name_or
+            .clone()
+            .unwrap_or_else(|| truncate_text(&transaction.federation_id.to_string(), 16, true));
+
+        let status_text = Text::new(transaction.status.to_string());
+
+        let status_text = match transaction.status {
+            TransactionStatus::Pending => status_text,
+            TransactionStatus::Succeeded => status_text.style(iced::widget::text::success),
+            TransactionStatus::Failed => status_text.style(iced::widget::text::danger),
+        };
+
+        Column::new()
+            .push(
+                row![
+                    Text::new(format!(
+                        "{sign}{}{fee_text}{fiat_text}",
+                        format_amount(transaction.amount)
+                    )),
+                    status_text,
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+            )
+            .push(
+                Text::new(format!(
+                    "{direction} via {} in {federation_name}, {}",
+                    transaction.kind,
+                    format_time(transaction.create_time, now, self.clock_format)
+                ))
+                .size(14),
+            )
+            .spacing(5)
+    }
+}
+
+fn transactions_message(message: Message) -> app::Message {
+    app::Message::Routes(routes::Message::BitcoinWalletPage(
+        super::Message::Transactions(message),
+    ))
+}
+
+/// Returns the number of pages needed to show `item_count` items. Always at least 1.
+const fn page_count(item_count: i64) -> i64 {
+    if item_count <= 0 {
+        1
+    } else {
+        (item_count + PAGE_SIZE - 1) / PAGE_SIZE
+    }
+}
+
+/// Clamps `page_index` to a valid page, such as after the last item on the final page was deleted.
+fn clamp_page_index(page_index: i64, item_count: i64) -> i64 {
+    page_index.clamp(0, page_count(item_count) - 1)
+}
+
+/// "Previous" and "Next" buttons with the current page number between them.
+/// `page_index` is zero-based and should already be clamped with [`clamp_page_index`].
+fn pagination_controls<'a>(
+    page_index: i64,
+    item_count: i64,
+    on_page_change: impl Fn(i64) -> app::Message,
+) -> Row<'a, app::Message> {
+    let page_count = page_count(item_count);
+
+    row![
+        icon_button("Previous", SvgIcon::ArrowBack, PaletteColor::Background)
+            .on_press_maybe((page_index > 0).then(|| on_page_change(page_index - 1))),
+        Text::new(format!("Page {} of {page_count}", page_index + 1)),
+        icon_button("Next", SvgIcon::ChevronRight, PaletteColor::Background)
+            .on_press_maybe((page_index + 1 < page_count).then(|| on_page_change(page_index + 1))),
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_count_and_clamping() {
+        // An empty list still has a single (empty) page.
+        assert_eq!(page_count(0), 1);
+        assert_eq!(page_count(PAGE_SIZE), 1);
+        assert_eq!(page_count(PAGE_SIZE + 1), 2);
+
+        assert_eq!(clamp_page_index(-1, PAGE_SIZE * 3), 0);
+        assert_eq!(clamp_page_index(1, PAGE_SIZE * 3), 1);
+        assert_eq!(clamp_page_index(5, PAGE_SIZE * 3), 2);
+        assert_eq!(clamp_page_index(5, 0), 0);
+    }
+}
@@ -0,0 +1,832 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::{Debug, Display},
+    str::FromStr,
+    sync::Arc,
+    time::Instant,
+};
+
+use chrono::Utc;
+use fedimint_core::config::FederationId;
+use iced::{
+    widget::{column, pick_list, row, text, Column, Text},
+    Alignment, Element, Task,
+};
+use nip_55::nip_46::Nip46RequestApproval;
+use nostr_sdk::{nips::nip46::Request, Kind, PublicKey, Timestamp, ToBech32};
+
+use crate::{
+    app,
+    backup::BackupSettings,
+    config::SettingsHandle,
+    db::Database,
+    encryption::RememberedConversations,
+    exchange_rate::ExchangeRate,
+    fedimint::{
+        BalanceThresholds, FederationMetadata, FederationOperationProgress, Wallet, WalletView,
+    },
+    file_attachment::{FileAttachment, FileAttachmentKind},
+    in_flight::InFlightOperations,
+    keychain,
+    maintenance::format_size,
+    metrics::SigningMetrics,
+    nostr::{NostrModule, NostrState},
+    nwc::NwcConnectionRecord,
+    policy::{self, describe_event_kind, ApprovalGrantDuration, ApprovalGrants},
+    profile::Profiles,
+    signing_worker::SigningWorker,
+    threshold_key::{CosigningRequest, ThresholdShare},
+    ui_components::{avatar, icon_button, progress_ring, Avatars, KeyHold, PaletteColor, SvgIcon},
+    unlock_attempts::FailedUnlockAttempts,
+    util::truncate_text,
+};
+
+pub mod bitcoin_wallet;
+mod home;
+pub mod nostr_keypairs;
+pub mod nostr_relays;
+pub mod settings;
+pub mod unlock;
+
+#[derive(Clone)]
+pub struct ConnectedState {
+    pub db: Arc<Database>,
+    pub wallet: Arc<Wallet>,
+    pub settings: SettingsHandle,
+    // Each request is kept along with the time it was received, the event kinds
+    // in it that are unusual for its app, and the keypair chosen for its app.
+    #[allow(clippy::type_complexity)]
+    pub in_flight_nip46_requests: VecDeque<(
+        Arc<(
+            Vec<nostr_sdk::nips::nip46::Request>,
+            PublicKey,
+            iced::futures::channel::oneshot::Sender<Nip46RequestApproval>,
+        )>,
+        Instant,
+        Vec<Kind>,
+        Option<PublicKey>,
+    )>,
+    // The saved keypairs that an app can be switched to while approving its
+    // requests. They're reloaded whenever a request has to be approved.
+    pub nip46_identities: Vec<Nip46Identity>,
+    // Enter being held down to approve the first in-flight request.
+    pub nip46_approval_hold_or: Option<KeyHold>,
+    pub approval_grants: ApprovalGrants,
+    pub remembered_conversations: RememberedConversations,
+    pub signing_metrics: SigningMetrics,
+    pub signing_worker: SigningWorker,
+    pub avatars: Avatars,
+    pub profiles: Profiles,
+    pub drafts: Drafts,
+    pub in_flight_operations: InFlightOperations,
+    // Federations that are being joined or left, and how far along each one is.
+    pub federation_operations: BTreeMap<FederationId, FederationOperationProgress>,
+    pub loadable_wallet_view: Loadable<WalletView>,
+    // Metadata of joined federations, kept from when they last connected, so that
+    // federations that aren't connected yet can still be shown by name.
+    pub federation_metadata: BTreeMap<FederationId, FederationMetadata>,
+    pub nostr_module: NostrModule,
+    pub nostr_state: NostrState,
+    // Whether the NIP-55 socket that apps send signing requests over is open.
+    pub is_nip55_socket_listening: bool,
+    // Requests to help sign for threshold keys that this device holds a cosigner share of.
+    pub cosigning_requests: Vec<CosigningRequest>,
+    // The latest price of bitcoin, if a fiat currency is set and it has been fetched.
+    pub exchange_rate_or: Option<ExchangeRate>,
+    // The saved public keys that zap receipts are listened for. They're only
+    // reloaded when keypairs are saved or deleted, since the subscriptions that
+    // use them are rebuilt after every update.
+    pub zap_recipient_public_keys: Vec<PublicKey>,
+    // Kept so that the backup subscription doesn't read them on every update.
+    // Updated whenever they're saved.
+    pub backup_settings: BackupSettings,
+    // The app connections that requests are listened for, reloaded
+    // through [`app::Message::NwcConnectionsChanged`] when they change.
+    pub nwc_connections: Vec<NwcConnectionRecord>,
+    // The cosigner shares that signing requests are listened for, reloaded
+    // through [`app::Message::ThresholdSharesChanged`] when they change.
+    pub cosigner_shares: Vec<ThresholdShare>,
+    // Each federation's balance alert thresholds and maximum balance, so that wallet
+    // view updates don't read them. Updated whenever they're saved.
+    pub balance_thresholds: BTreeMap<FederationId, BalanceThresholds>,
+}
+
+impl ConnectedState {
+    /// Whether Keystache appears to have no network, because every relay has lost its
+    /// connection and no connected federation's guardians answered the last check.
+    pub fn is_offline(&self) -> bool {
+        self.nostr_state.has_lost_every_relay()
+            && !self
+                .loadable_wallet_view
+                .as_ref_option()
+                .is_some_and(WalletView::is_any_federation_reachable)
+    }
+
+    /// The latest exchange rate, unless it's for a different currency than the one now set.
+    pub fn exchange_rate(&self) -> Option<ExchangeRate> {
+        let fiat_currency = self.settings.get().fiat_currency_or?;
+
+        self.exchange_rate_or
+            .filter(|exchange_rate| exchange_rate.currency == fiat_currency)
+    }
+
+    /// Whether the request on screen asks to sign as a different keypair than the one chosen for
+    /// its app, in which case it can't be approved until the app is switched to that keypair.
+    pub fn is_first_nip46_request_for_other_identity(&self) -> bool {
+        self.in_flight_nip46_requests
+            .front()
+            .is_some_and(|(req, _, _, app_identity_or)| {
+                policy::is_other_identity(&req.0, *app_identity_or)
+            })
+    }
+
+    /// Reloads [`Self::zap_recipient_public_keys`] after keypairs are saved or deleted.
+    pub fn reload_zap_recipient_public_keys(&mut self) {
+        self.zap_recipient_public_keys = list_zap_recipient_public_keys(&self.db);
+    }
+
+    /// Reloads [`Self::nwc_connections`] after a connection is created or revoked.
+    pub fn reload_nwc_connections(&mut self) {
+        self.nwc_connections = list_nwc_connections(&self.db);
+    }
+
+    /// The balance alert thresholds and maximum balance saved for a federation.
+    pub fn get_balance_thresholds(&self, federation_id: &FederationId) -> BalanceThresholds {
+        self.balance_thresholds
+            .get(federation_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Reloads [`Self::cosigner_shares`] after a share is imported or removed.
+    pub fn reload_cosigner_shares(&mut self) {
+        self.cosigner_shares = list_cosigner_shares(&self.db);
+    }
+
+    /// Spends any approval hold in progress once the request it was for has been answered,
+    /// so that the key has to be released before the next request can be approved.
+    pub fn spend_nip46_approval_hold(&mut self) {
+        if let Some(nip46_approval_hold) = &mut self.nip46_approval_hold_or {
+            nip46_approval_hold.complete();
+        }
+    }
+}
+
+/// Lists the public keys of the saved keypairs, which zap receipts are listened for.
+pub fn list_zap_recipient_public_keys(db: &Database) -> Vec<PublicKey> {
+    // TODO: Log a warning if the keys fail to load.
+    db.list_public_keys("", i64::MAX, 0)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|npub| PublicKey::from_str(npub).ok())
+        .collect()
+}
+
+/// Lists the app connections that haven't been revoked.
+pub fn list_nwc_connections(db: &Database) -> Vec<NwcConnectionRecord> {
+    // TODO: Log a warning if the connections fail to load.
+    db.list_nwc_connections()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|record| !record.is_revoked())
+        .collect()
+}
+
+/// Lists the threshold shares that this device cosigns with. Coordinator
+/// shares only listen for answers while they're signing, so they're left out.
+pub fn list_cosigner_shares(db: &Database) -> Vec<ThresholdShare> {
+    // TODO: Log a warning if the shares fail to load.
+    db.list_threshold_shares()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|share| !share.is_coordinator)
+        .collect()
+}
+
+/// Text typed into forms that is kept when navigating away,
+/// so that it can be restored when the user returns to the form.
+#[derive(Debug, Clone, Default)]
+pub struct Drafts {
+    pub federation_invite_code: String,
+    pub send_lightning_invoice: String,
+    pub receive_amount: String,
+}
+
+// TODO: Clean up this implementation.
+impl Debug for ConnectedState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ConnectedState")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Navigate(RouteName),
+    NavigateHomeAndSetConnectedState(ConnectedState),
+
+    UnlockPage(unlock::Message),
+    NostrKeypairsPage(nostr_keypairs::Message),
+    NostrRelaysPage(nostr_relays::Message),
+    BitcoinWalletPage(bitcoin_wallet::Message),
+    SettingsPage(settings::Message),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteName {
+    Unlock,
+    Home,
+    NostrKeypairs(nostr_keypairs::SubrouteName),
+    NostrRelays(nostr_relays::SubrouteName),
+    BitcoinWallet(bitcoin_wallet::SubrouteName),
+    Settings(settings::SubrouteName),
+}
+
+impl RouteName {
+    pub fn is_same_top_level_route_as(&self, other: &Self) -> bool {
+        match self {
+            Self::Unlock => other == &Self::Unlock,
+            Self::Home => other == &Self::Home,
+            Self::NostrKeypairs(_) => matches!(other, Self::NostrKeypairs(_)),
+            Self::NostrRelays(_) => matches!(other, Self::NostrRelays(_)),
+            Self::BitcoinWallet(_) => matches!(other, Self::BitcoinWallet(_)),
+            Self::Settings(_) => matches!(other, Self::Settings(_)),
+        }
+    }
+}
+
+pub enum Route {
+    Unlock(unlock::Page),
+    Home(home::Page),
+    NostrKeypairs(nostr_keypairs::Page),
+    NostrRelays(nostr_relays::Page),
+    BitcoinWallet(bitcoin_wallet::Page),
+    Settings(settings::Page),
+}
+
+impl Route {
+    pub fn new_locked() -> Self {
+        // TODO: Log a warning if the data directory can't be found.
+        let db_already_exists = Database::exists().unwrap_or_default();
+        let failed_unlock_attempts = Database::app_data_dir()
+            .map(|app_data_dir| FailedUnlockAttempts::load(&app_data_dir))
+            .unwrap_or_default();
+
+        Self::Unlock(unlock::Page {
+            password: String::new(),
+            is_secure: true,
+            db_already_exists,
+            remember_password: false,
+            has_keychain_password: keychain::has_password(),
+            failed_unlock_attempts,
+            remaining_cooldown_or: failed_unlock_attempts
+                .remaining_cooldown_or(Utc::now().naive_utc()),
+            restore_path_input: String::new(),
+            restore_seed_input: String::new(),
+            restore_invite_codes_input: String::new(),
+        })
+    }
+
+    pub fn to_name(&self) -> RouteName {
+        match self {
+            Self::Unlock(_) => RouteName::Unlock,
+            Self::Home(_) => RouteName::Home,
+            Self::NostrKeypairs(nostr_keypairs) => {
+                RouteName::NostrKeypairs(nostr_keypairs.subroute.to_name())
+            }
+            Self::NostrRelays(nostr_relays) => {
+                RouteName::NostrRelays(nostr_relays.subroute.to_name())
+            }
+            Self::BitcoinWallet(bitcoin_wallet) => {
+                RouteName::BitcoinWallet(bitcoin_wallet.subroute.to_name())
+            }
+            Self::Settings(settings) => RouteName::Settings(settings.subroute.to_name()),
+        }
+    }
+
+    /// Shows more rows of the current page's list, if it loads rows as the user scrolls.
+    pub fn load_more_rows(&mut self) -> Task<app::Message> {
+        match self {
+            Self::NostrKeypairs(nostr_keypairs) => {
+                nostr_keypairs.update(nostr_keypairs::Message::LoadMoreKeypairs)
+            }
+            Self::NostrRelays(nostr_relays) => {
+                nostr_relays.update(nostr_relays::Message::LoadMoreRelays)
+            }
+            _ => Task::none(),
+        }
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::Navigate(route_name) => {
+                let is_join_federation_route =
+                    route_name == RouteName::BitcoinWallet(bitcoin_wallet::SubrouteName::Add);
+
+                let new_self_or = match route_name {
+                    RouteName::Unlock => Some(Self::new_locked()),
+                    RouteName::Home => self.get_connected_state().map(|connected_state| {
+                        Self::Home(home::Page {
+                            connected_state: connected_state.clone(),
+                        })
+                    }),
+                    RouteName::NostrKeypairs(subroute_name) => {
+                        self.get_connected_state().map(|connected_state| {
+                            Self::NostrKeypairs(nostr_keypairs::Page {
+                                connected_state: connected_state.clone(),
+                                subroute: subroute_name.to_default_subroute(connected_state),
+                            })
+                        })
+                    }
+                    RouteName::NostrRelays(subroute_name) => {
+                        self.get_connected_state().map(|connected_state| {
+                            Self::NostrRelays(nostr_relays::Page {
+                                connected_state: connected_state.clone(),
+                                subroute: subroute_name.to_default_subroute(connected_state),
+                            })
+                        })
+                    }
+                    RouteName::BitcoinWallet(subroute_name) => {
+                        self.get_connected_state().map(|connected_state| {
+                            Self::BitcoinWallet(bitcoin_wallet::Page {
+                                connected_state: connected_state.clone(),
+                                subroute: subroute_name.to_default_subroute(connected_state),
+                            })
+                        })
+                    }
+                    RouteName::Settings(subroute_name) => {
+                        self.get_connected_state().map(|connected_state| {
+                            Self::Settings(settings::Page {
+                                connected_state: connected_state.clone(),
+                                subroute: subroute_name.to_default_subroute(connected_state),
+                            })
+                        })
+                    }
+                };
+
+                if let Some(new_self) = new_self_or {
+                    *self = new_self;
+                } else {
+                    // TODO: Log warning that navigation failed.
+                }
+
+                let profiles_task = if let Self::NostrKeypairs(nostr_keypairs_page) = self {
+                    nostr_keypairs_page.request_profiles()
+                } else {
+                    Task::none()
+                };
+
+                let wallet_task = if let Self::BitcoinWallet(bitcoin_wallet_page) = self {
+                    bitcoin_wallet_page.load_from_wallet()
+                } else {
+                    Task::none()
+                };
+
+                // A restored invite code draft needs its federation config to be loaded again.
+                let draft_task = match self.get_connected_state() {
+                    Some(connected_state)
+                        if is_join_federation_route
+                            && !connected_state.drafts.federation_invite_code.is_empty() =>
+                    {
+                        Task::done(app::Message::Routes(Message::BitcoinWalletPage(
+                            bitcoin_wallet::Message::JoinFederationInviteCodeInputChanged(
+                                connected_state.drafts.federation_invite_code.clone(),
+                            ),
+                        )))
+                    }
+                    _ => Task::none(),
+                };
+
+                Task::batch([profiles_task, wallet_task, draft_task])
+            }
+            Message::NavigateHomeAndSetConnectedState(connected_state) => {
+                *self = Self::Home(home::Page { connected_state });
+
+                Task::none()
+            }
+            Message::UnlockPage(unlock_message) => {
+                if let Self::Unlock(unlock_page) = self {
+                    unlock_page.update(unlock_message)
+                } else {
+                    // TODO: Log a warning that the unlock page is not active.
+                    Task::none()
+                }
+            }
+            Message::NostrKeypairsPage(nostr_keypairs_message) => {
+                if let Self::NostrKeypairs(nostr_keypairs_page) = self {
+                    nostr_keypairs_page.update(nostr_keypairs_message)
+                } else {
+                    // TODO: Log a warning that the keypairs page is not active.
+                    Task::none()
+                }
+            }
+            Message::NostrRelaysPage(nostr_relays_message) => {
+                if let Self::NostrRelays(nostr_relays_page) = self {
+                    nostr_relays_page.update(nostr_relays_message)
+                } else {
+                    // TODO: Log a warning that the relays page is not active.
+                    Task::none()
+                }
+            }
+            Message::BitcoinWalletPage(bitcoin_wallet_message) => {
+                if let Self::BitcoinWallet(bitcoin_wallet_page) = self {
+                    bitcoin_wallet_page.update(bitcoin_wallet_message)
+                } else {
+                    // TODO: Log a warning that the bitcoin wallet page is not active.
+                    Task::none()
+                }
+            }
+            Message::SettingsPage(settings_message) => {
+                if let Self::Settings(settings_page) = self {
+                    settings_page.update(settings_message)
+                } else {
+                    // TODO: Log a warning that the settings page is not active.
+                    Task::none()
+                }
+            }
+        }
+    }
+
+    pub fn view(&self) -> Element<app::Message> {
+        // If there are any incoming NIP46 requests, display the first one over the rest of the UI.
+        if let Some(connected_state) = self.get_connected_state() {
+            if let Some((req, received_time, unusual_event_kinds, app_identity_or)) =
+                connected_state.in_flight_nip46_requests.front()
+            {
+                let can_approve = !connected_state.is_first_nip46_request_for_other_identity();
+
+                // A hold that has completed was spent on an earlier request.
+                let nip46_approval_hold_progress = connected_state
+                    .nip46_approval_hold_or
+                    .filter(|nip46_approval_hold| !nip46_approval_hold.is_complete())
+                    .as_ref()
+                    .map_or(0.0, KeyHold::progress);
+
+                return Column::new()
+                    .push(Text::new("Incoming NIP-46 request"))
+                    .push(
+                        row![
+                            avatar(&req.1, &connected_state.avatars, 48.0),
+                            Text::new(req.1.to_bech32().map_or_else(
+                                |_| req.1.to_string(),
+                                |npub| truncate_text(&npub, 24, true)
+                            )),
+                        ]
+                        .spacing(10)
+                        .align_y(Alignment::Center),
+                    )
+                    .push_maybe(connected_state.avatars.is_unverified(&req.1).then(|| {
+                        Text::new(
+                            "This app's profile on relays isn't signed by its key, so it isn't shown. Check the npub before approving.",
+                        )
+                        .style(iced::widget::text::danger)
+                    }))
+                    .push_maybe((!unusual_event_kinds.is_empty()).then(|| {
+                        Text::new(format!(
+                            "Unusual request: this app has never signed a {} event before. Make sure you expected it to.",
+                            unusual_event_kinds
+                                .iter()
+                                .map(|kind| describe_event_kind(*kind))
+                                .collect::<Vec<_>>()
+                                .join(" or ")
+                        ))
+                        .style(iced::widget::text::danger)
+                    }))
+                    .push(nip46_identity_view(
+                        req.1,
+                        &req.0,
+                        *app_identity_or,
+                        &connected_state.nip46_identities,
+                    ))
+                    .push(nip46_requests_view(&req.0))
+                    .push(
+                        Text::new(format!(
+                            "Rejected automatically in {} seconds",
+                            connected_state
+                                .settings
+                                .get()
+                                .nip46_request_expiry
+                                .saturating_sub(received_time.elapsed())
+                                .as_secs()
+                        ))
+                        .size(14),
+                    )
+                    .push(
+                        row![
+                            icon_button("Approve", SvgIcon::ThumbUp, PaletteColor::Primary)
+                                .on_press_maybe(can_approve.then_some(
+                                    app::Message::ApproveFirstIncomingNip46Request
+                                )),
+                            icon_button("Reject", SvgIcon::ThumbDown, PaletteColor::Primary)
+                                .on_press(app::Message::RejectFirstIncomingNip46Request),
+                        ]
+                        .spacing(20),
+                    )
+                    .push(
+                        row![
+                            progress_ring(nip46_approval_hold_progress, 24.0),
+                            Text::new("Or hold Enter to approve"),
+                        ]
+                        .spacing(10)
+                        .align_y(Alignment::Center),
+                    )
+                    .push(
+                        row![
+                            icon_button(
+                                "Approve for 10 minutes",
+                                SvgIcon::ThumbUp,
+                                PaletteColor::Background
+                            )
+                            .on_press_maybe(can_approve.then_some(
+                                app::Message::ApproveFirstIncomingNip46RequestFor(
+                                    ApprovalGrantDuration::TenMinutes
+                                )
+                            )),
+                            icon_button(
+                                "Approve for this session",
+                                SvgIcon::ThumbUp,
+                                PaletteColor::Background
+                            )
+                            .on_press_maybe(can_approve.then_some(
+                                app::Message::ApproveFirstIncomingNip46RequestFor(
+                                    ApprovalGrantDuration::Session
+                                )
+                            )),
+                        ]
+                        .spacing(20),
+                    )
+                    .align_x(Alignment::Center)
+                    .into();
+            }
+        }
+
+        match self {
+            Self::Unlock(unlock) => unlock.view(),
+            Self::Home(home) => home.view(),
+            Self::NostrKeypairs(nostr_keypairs) => nostr_keypairs.view(),
+            Self::NostrRelays(nostr_relays) => nostr_relays.view(),
+            Self::BitcoinWallet(bitcoin_wallet) => bitcoin_wallet.view(),
+            Self::Settings(settings) => settings.view(),
+        }
+        .into()
+    }
+
+    pub fn get_connected_state(&self) -> Option<&ConnectedState> {
+        match self {
+            Self::Unlock { .. } => None,
+            Self::Home(home::Page { connected_state }) => Some(connected_state),
+            Self::NostrKeypairs(nostr_keypairs::Page {
+                connected_state, ..
+            }) => Some(connected_state),
+            Self::NostrRelays(nostr_relays::Page {
+                connected_state, ..
+            }) => Some(connected_state),
+            Self::BitcoinWallet(bitcoin_wallet::Page {
+                connected_state, ..
+            }) => Some(connected_state),
+            Self::Settings(settings::Page {
+                connected_state, ..
+            }) => Some(connected_state),
+        }
+    }
+
+    pub fn get_connected_state_mut(&mut self) -> Option<&mut ConnectedState> {
+        match self {
+            Self::Unlock { .. } => None,
+            Self::Home(home::Page { connected_state }) => Some(connected_state),
+            Self::NostrKeypairs(nostr_keypairs::Page {
+                connected_state, ..
+            }) => Some(connected_state),
+            Self::NostrRelays(nostr_relays::Page {
+                connected_state, ..
+            }) => Some(connected_state),
+            Self::BitcoinWallet(bitcoin_wallet::Page {
+                connected_state, ..
+            }) => Some(connected_state),
+            Self::Settings(settings::Page {
+                connected_state, ..
+            }) => Some(connected_state),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Loadable<T> {
+    Loading,
+    Loaded(T),
+    Failed,
+}
+
+impl<T> Loadable<T> {
+    pub fn as_ref_option(&self) -> Option<&T> {
+        match self {
+            Self::Loaded(data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+/// A saved keypair that NIP-46 requests can be signed as, shown by its display name if it has one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nip46Identity {
+    pub npub: String,
+    pub display_name_or: Option<String>,
+}
+
+impl Nip46Identity {
+    /// Loads every saved keypair, in the order they were added.
+    pub fn list_all(db: &Database) -> anyhow::Result<Vec<Self>> {
+        db.iter_keypairs()
+            .map(|keypair_result| {
+                keypair_result.map(|keypair| Self {
+                    npub: keypair.npub,
+                    display_name_or: keypair.display_name,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Display for Nip46Identity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.display_name_or {
+            Some(display_name) => {
+                write!(
+                    f,
+                    "{display_name} ({})",
+                    truncate_text(&self.npub, 12, true)
+                )
+            }
+            None => write!(f, "{}", self.npub),
+        }
+    }
+}
+
+/// Shows which keypair a batch of NIP-46 requests asks to sign as, and lets the user choose
+/// the keypair that its app signs as. Requests for any other keypair can't be approved.
+fn nip46_identity_view<'a>(
+    app_public_key: PublicKey,
+    requests: &[Request],
+    app_identity_or: Option<PublicKey>,
+    identities: &'a [Nip46Identity],
+) -> Column<'a, app::Message> {
+    let find_identity = |public_key: &PublicKey| {
+        let npub = public_key.to_bech32().ok()?;
+
+        identities.iter().find(|identity| identity.npub == npub)
+    };
+
+    // Keypairs with a display name are shown by it, so the user can tell which one is asked for.
+    let format_npub = |public_key: &PublicKey| match find_identity(public_key) {
+        Some(identity) if identity.display_name_or.is_some() => identity.to_string(),
+        _ => public_key.to_bech32().map_or_else(
+            |_| public_key.to_string(),
+            |npub| truncate_text(&npub, 24, true),
+        ),
+    };
+
+    let requested_identities = policy::requested_identities(requests);
+
+    // Only requests to sign events say which keypair they're for.
+    let requested_identities_text_or = (!requested_identities.is_empty()).then(|| {
+        Text::new(format!(
+            "Signing as {}",
+            requested_identities
+                .iter()
+                .map(format_npub)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    });
+
+    Column::new()
+        .push_maybe(requested_identities_text_or)
+        .push(
+            row![
+                Text::new("App signs as"),
+                pick_list(
+                    identities,
+                    app_identity_or.and_then(|identity| find_identity(&identity).cloned()),
+                    move |identity| {
+                        app::Message::SetNip46AppIdentity(app_public_key, identity.npub)
+                    },
+                )
+                .placeholder("Any keypair"),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+        )
+        .push_maybe(policy::is_other_identity(requests, app_identity_or).then(|| {
+            Text::new(
+                "This app asked to sign as a different keypair than the one chosen for it. Switch the app to that keypair to approve, or reject the requests.",
+            )
+            .style(iced::widget::text::danger)
+        }))
+        .spacing(10)
+        .align_x(Alignment::Center)
+}
+
+/// Shows a batch of NIP-46 requests. File attachments get a view of their own,
+/// since signing them lets the app publish or upload files as the user.
+fn nip46_requests_view<'a>(requests: &[Request]) -> Column<'a, app::Message> {
+    let now = Timestamp::now();
+
+    requests
+        .iter()
+        .fold(Column::new().spacing(10), |column, request| {
+            let file_attachment_or = match request {
+                Request::SignEvent(unsigned_event) => {
+                    FileAttachment::from_unsigned_event(unsigned_event)
+                }
+                _ => None,
+            };
+
+            column.push(match file_attachment_or {
+                Some(file_attachment) => file_attachment_view(&file_attachment, now),
+                None => Column::new().push(Text::new(format!("{request:?}"))),
+            })
+        })
+}
+
+fn file_attachment_view<'a>(
+    file_attachment: &FileAttachment,
+    now: Timestamp,
+) -> Column<'a, app::Message> {
+    let (title, consequence, expiration_or) = match &file_attachment.kind {
+        FileAttachmentKind::FileMetadata => (
+            "Publish a file (NIP-94)".to_string(),
+            "Signing this publishes the file below under your identity.",
+            None,
+        ),
+        FileAttachmentKind::BlossomAuthorization {
+            action,
+            expiration_or,
+        } => (
+            format!("Blossom authorization: {action}"),
+            "Signing this lets the app do this on Blossom servers as you until it expires.",
+            Some(*expiration_or),
+        ),
+    };
+
+    // Blossom authorizations without hashes or servers aren't limited to any.
+    let (any_file, any_server) = if expiration_or.is_some() {
+        ("Any file", "Any server")
+    } else {
+        ("Not given", "Not given")
+    };
+
+    let sha256_hashes = if file_attachment.sha256_hashes.is_empty() {
+        any_file.to_string()
+    } else {
+        file_attachment.sha256_hashes.join(", ")
+    };
+
+    let server_urls = if file_attachment.server_urls.is_empty() {
+        any_server.to_string()
+    } else {
+        file_attachment.server_urls.join(", ")
+    };
+
+    // Only Blossom authorizations expire.
+    let expiration_text_or = expiration_or.map(|expiration_or| match expiration_or {
+        Some(_) if file_attachment.is_expired(now) => {
+            Text::new("Already expired, so servers will reject it.")
+        }
+        Some(expiration) => Text::new(format!(
+            "Expires in {} minutes",
+            expiration
+                .as_u64()
+                .saturating_sub(now.as_u64())
+                .div_ceil(60)
+        )),
+        None => Text::new("Never expires").style(iced::widget::text::danger),
+    });
+
+    Column::new()
+        .push(Text::new(title).size(20))
+        .push(Text::new(consequence).style(iced::widget::text::danger))
+        .push_maybe(
+            (!file_attachment.description.is_empty())
+                .then(|| Text::new(file_attachment.description.clone())),
+        )
+        .push(Text::new(format!("SHA-256: {sha256_hashes}")))
+        .push(Text::new(format!("Server: {server_urls}")))
+        .push_maybe(
+            file_attachment
+                .size_bytes_or
+                .map(|size_bytes| Text::new(format!("Size: {}", format_size(size_bytes)))),
+        )
+        .push_maybe(
+            file_attachment
+                .mime_type_or
+                .clone()
+                .map(|mime_type| Text::new(format!("Type: {mime_type}"))),
+        )
+        .push_maybe(expiration_text_or)
+        .spacing(5)
+}
+
+fn container<'a>(title: &str) -> Column<'a, app::Message> {
+    column![text(title.to_string()).size(35)]
+        .spacing(20)
+        .align_x(iced::Alignment::Center)
+}
@@ -0,0 +1,847 @@
+use std::{path::PathBuf, str::FromStr};
+
+use chrono::Utc;
+use iced::{
+    widget::{row, Column, Row, Text},
+    Alignment, Element, Task,
+};
+use nostr_sdk::{
+    secp256k1::{rand::thread_rng, Keypair},
+    FromBech32, PublicKey, SecretKey,
+};
+use secp256k1::Secp256k1;
+
+use crate::{
+    app,
+    db::{Database, NostrKeypairSummary},
+    ncryptsec,
+    nostr::NostrModule,
+    profile::{fetch_profile, Profiles},
+    ui_components::{
+        avatar, bech32_input, icon_button, load_more_controls, selectable_list, text_input,
+        Bech32Kind, PaletteColor, SelectableListMessage, SelectableListState, SvgIcon, Toast,
+        ToastStatus, PAGE_SIZE,
+    },
+    util::{debounce_search_input, format_time, qr, rank_by_fuzzy_match, truncate_text},
+};
+
+use super::{container, ConnectedState, RouteName};
+
+mod app_identities;
+mod delegations;
+mod export_encrypted;
+mod profile;
+mod threshold_keys;
+mod zap_allowlist;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SaveKeypair(Keypair),
+    SaveKeypairNsecInputChanged(String),
+    QrImagePathInputChanged(String),
+    ScanQrImage,
+    ScanWebcam,
+    QrCodeScanned(Result<String, String>),
+    NcryptsecInputChanged(String),
+    NcryptsecPassphraseInputChanged(String),
+    DecryptNcryptsec,
+    NcryptsecDecrypted(Result<SecretKey, String>),
+    DeleteKeypair { public_key: String },
+    KeypairSelection(SelectableListMessage<String>),
+    LoadMoreKeypairs,
+    SearchInputChanged(String),
+    SearchDebounced(String),
+    DeleteKeypairs { public_keys: Vec<String> },
+
+    AppIdentities(app_identities::Message),
+    Delegations(delegations::Message),
+    ExportEncrypted(export_encrypted::Message),
+    Profile(profile::Message),
+    ThresholdKeys(threshold_keys::Message),
+    ZapAllowlist(zap_allowlist::Message),
+}
+
+pub struct Page {
+    pub connected_state: ConnectedState,
+    pub subroute: Subroute,
+}
+
+impl Page {
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::SaveKeypair(keypair) => match self.connected_state.db.save_keypair(&keypair) {
+                Ok(()) => {
+                    self.connected_state.reload_zap_recipient_public_keys();
+
+                    Task::batch([
+                        Task::done(app::Message::AddToast(Toast {
+                            title: "Saved keypair".to_string(),
+                            body: "The keypair was successfully saved.".to_string(),
+                            status: ToastStatus::Good,
+                        })),
+                        self.connected_state.avatars.request(
+                            [keypair.x_only_public_key().0.into()],
+                            &self.connected_state.nostr_module,
+                        ),
+                    ])
+                }
+                Err(_err) => Task::done(app::Message::AddToast(Toast {
+                    title: "Failed to save keypair".to_string(),
+                    body: "The keypair was not saved.".to_string(),
+                    status: ToastStatus::Bad,
+                })),
+            },
+            Message::SaveKeypairNsecInputChanged(new_nsec) => {
+                if let Subroute::Add(Add {
+                    nsec, keypair_or, ..
+                }) = &mut self.subroute
+                {
+                    *nsec = new_nsec;
+
+                    // Set `keypair_or` to `Some` if `nsec` is a valid secret key, `None` otherwise.
+                    *keypair_or = SecretKey::from_str(nsec).map_or(None, |secret_key| {
+                        Some(Keypair::from_secret_key(&Secp256k1::new(), &secret_key))
+                    });
+                }
+
+                Task::none()
+            }
+            Message::QrImagePathInputChanged(input) => {
+                if let Subroute::Add(Add {
+                    qr_image_path_input,
+                    ..
+                }) = &mut self.subroute
+                {
+                    *qr_image_path_input = input;
+                }
+
+                Task::none()
+            }
+            Message::ScanQrImage => {
+                let Subroute::Add(add) = &self.subroute else {
+                    return Task::none();
+                };
+
+                let path = PathBuf::from(add.qr_image_path_input.trim());
+
+                Task::perform(run_blocking(move || qr::decode_file(&path)), |result| {
+                    app::Message::Routes(super::Message::NostrKeypairsPage(Message::QrCodeScanned(
+                        result,
+                    )))
+                })
+            }
+            Message::ScanWebcam => {
+                let Subroute::Add(add) = &mut self.subroute else {
+                    return Task::none();
+                };
+
+                add.is_scanning_webcam = true;
+
+                Task::perform(run_blocking(qr::scan_webcam), |result| {
+                    app::Message::Routes(super::Message::NostrKeypairsPage(Message::QrCodeScanned(
+                        result,
+                    )))
+                })
+            }
+            Message::QrCodeScanned(result) => {
+                if let Subroute::Add(add) = &mut self.subroute {
+                    add.is_scanning_webcam = false;
+                }
+
+                let content = match result {
+                    Ok(content) => content,
+                    Err(err) => {
+                        return Task::done(app::Message::AddToast(Toast {
+                            title: "Failed to read QR code".to_string(),
+                            body: err,
+                            status: ToastStatus::Bad,
+                        }));
+                    }
+                };
+
+                // Apps often show keys as NIP-21 `nostr:` URIs.
+                let nsec = content.trim();
+                let nsec = nsec.strip_prefix("nostr:").unwrap_or(nsec).to_string();
+
+                // Encrypted keys still need their passphrase before they can be added.
+                if nsec.starts_with("ncryptsec1") {
+                    return self.update(Message::NcryptsecInputChanged(nsec));
+                }
+
+                // The QR code's text isn't shown, since it may be someone else's secret.
+                if SecretKey::from_str(&nsec).is_err() {
+                    return Task::done(app::Message::AddToast(Toast {
+                        title: "No secret key in QR code".to_string(),
+                        body: "The QR code doesn't hold an nSec, so there's no keypair to add."
+                            .to_string(),
+                        status: ToastStatus::Bad,
+                    }));
+                }
+
+                self.update(Message::SaveKeypairNsecInputChanged(nsec))
+            }
+            Message::NcryptsecInputChanged(input) => {
+                if let Subroute::Add(add) = &mut self.subroute {
+                    add.ncryptsec_input = input;
+                }
+
+                Task::none()
+            }
+            Message::NcryptsecPassphraseInputChanged(input) => {
+                if let Subroute::Add(add) = &mut self.subroute {
+                    add.ncryptsec_passphrase_input = input;
+                }
+
+                Task::none()
+            }
+            Message::DecryptNcryptsec => {
+                let Subroute::Add(add) = &mut self.subroute else {
+                    return Task::none();
+                };
+
+                add.is_decrypting_ncryptsec = true;
+
+                let ncryptsec = add.ncryptsec_input.clone();
+                let passphrase = add.ncryptsec_passphrase_input.clone();
+
+                Task::perform(
+                    run_blocking(move || ncryptsec::import_ncryptsec(&ncryptsec, &passphrase)),
+                    |result| {
+                        app::Message::Routes(super::Message::NostrKeypairsPage(
+                            Message::NcryptsecDecrypted(result),
+                        ))
+                    },
+                )
+            }
+            Message::NcryptsecDecrypted(result) => {
+                if let Subroute::Add(add) = &mut self.subroute {
+                    add.is_decrypting_ncryptsec = false;
+                }
+
+                match result {
+                    Ok(secret_key) => {
+                        if let Subroute::Add(add) = &mut self.subroute {
+                            add.ncryptsec_input.clear();
+                            add.ncryptsec_passphrase_input.clear();
+                        }
+
+                        self.update(Message::SaveKeypair(Keypair::from_secret_key(
+                            &Secp256k1::new(),
+                            &secret_key,
+                        )))
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to decrypt key".to_string(),
+                        body: err,
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::DeleteKeypair { public_key } => {
+                match self.connected_state.db.remove_keypair(&public_key) {
+                    Ok(()) => {
+                        self.connected_state.reload_zap_recipient_public_keys();
+
+                        Task::done(app::Message::AddToast(Toast {
+                            title: "Deleted keypair".to_string(),
+                            body: "The keypair was successfully deleted.".to_string(),
+                            status: ToastStatus::Good,
+                        }))
+                    }
+                    Err(_err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to delete keypair".to_string(),
+                        body: "The keypair was not deleted.".to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::KeypairSelection(selection_message) => {
+                if let Subroute::List(List { selection, .. }) = &mut self.subroute {
+                    selection.update(selection_message);
+                }
+
+                Task::none()
+            }
+            Message::LoadMoreKeypairs => {
+                let Subroute::List(list) = &mut self.subroute else {
+                    return Task::none();
+                };
+
+                // Scrolling near the end asks for more rows each time, even once all are shown.
+                // TODO: Log a warning if the keys fail to count.
+                let count = self
+                    .connected_state
+                    .db
+                    .count_keypairs(&list.search_query)
+                    .unwrap_or_default();
+                if list.row_limit >= count {
+                    return Task::none();
+                }
+
+                list.row_limit += PAGE_SIZE;
+
+                self.request_profiles()
+            }
+            Message::SearchInputChanged(input) => {
+                if let Subroute::List(List { search_input, .. }) = &mut self.subroute {
+                    search_input.clone_from(&input);
+                }
+
+                Task::perform(debounce_search_input(input), |input| {
+                    app::Message::Routes(super::Message::NostrKeypairsPage(
+                        Message::SearchDebounced(input),
+                    ))
+                })
+            }
+            Message::SearchDebounced(input) => {
+                if let Subroute::List(list) = &mut self.subroute {
+                    // Only search once the user has stopped typing.
+                    if list.search_input == input {
+                        list.search_query = input;
+                        list.row_limit = PAGE_SIZE;
+                    }
+                }
+
+                self.request_profiles()
+            }
+            Message::DeleteKeypairs { public_keys } => {
+                if let Subroute::List(List { selection, .. }) = &mut self.subroute {
+                    selection.take_selected_keys();
+                }
+
+                match self.connected_state.db.remove_keypairs(&public_keys) {
+                    Ok(()) => {
+                        self.connected_state.reload_zap_recipient_public_keys();
+
+                        Task::done(app::Message::AddToast(Toast {
+                            title: "Deleted keypairs".to_string(),
+                            body: format!(
+                                "{} keypairs were successfully deleted.",
+                                public_keys.len()
+                            ),
+                            status: ToastStatus::Good,
+                        }))
+                    }
+                    Err(_err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to delete keypairs".to_string(),
+                        body: "The keypairs were not deleted.".to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::Delegations(delegations_message) => {
+                if let Subroute::Delegations(delegations_page) = &mut self.subroute {
+                    delegations_page.update(delegations_message)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::AppIdentities(app_identities_message) => {
+                if let Subroute::AppIdentities(app_identities_page) = &mut self.subroute {
+                    app_identities_page.update(app_identities_message)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::ExportEncrypted(export_encrypted_message) => {
+                if let Subroute::ExportEncrypted(export_encrypted_page) = &mut self.subroute {
+                    export_encrypted_page.update(export_encrypted_message)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::Profile(profile_message) => {
+                if let Subroute::Profile(profile_page) = &mut self.subroute {
+                    profile_page.update(profile_message)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::ThresholdKeys(threshold_keys_message) => {
+                if let Subroute::ThresholdKeys(threshold_keys_page) = &mut self.subroute {
+                    threshold_keys_page.update(threshold_keys_message)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::ZapAllowlist(zap_allowlist_message) => {
+                if let Subroute::ZapAllowlist(zap_allowlist_page) = &mut self.subroute {
+                    zap_allowlist_page.update(zap_allowlist_message)
+                } else {
+                    Task::none()
+                }
+            }
+        }
+    }
+
+    /// Starts loading the avatars and profiles of the keys on the current page of the
+    /// list, or the newest profile of the key being edited.
+    pub fn request_profiles(&mut self) -> Task<app::Message> {
+        let list = match &mut self.subroute {
+            Subroute::List(list) => list,
+            Subroute::Profile(profile_page) => return profile_page.fetch_profile(),
+            _ => return Task::none(),
+        };
+
+        // TODO: Log a warning if the keys fail to load.
+        let Ok((_, keypairs)) = list.load_keypairs(&self.connected_state.db) else {
+            return Task::none();
+        };
+
+        let public_keys: Vec<PublicKey> = keypairs
+            .iter()
+            .filter_map(|keypair| PublicKey::from_bech32(&keypair.npub).ok())
+            .collect();
+
+        Task::batch([
+            self.connected_state
+                .avatars
+                .request(public_keys.clone(), &self.connected_state.nostr_module),
+            request_names(
+                &mut self.connected_state.profiles,
+                public_keys,
+                &self.connected_state.nostr_module,
+            ),
+        ])
+    }
+
+    pub fn view(&self) -> Column<app::Message> {
+        match &self.subroute {
+            Subroute::List(list) => list.view(&self.connected_state),
+            Subroute::Add(add) => add.view(),
+            Subroute::AppIdentities(app_identities) => app_identities.view(),
+            Subroute::Delegations(delegations) => delegations.view(),
+            Subroute::ExportEncrypted(export_encrypted) => export_encrypted.view(),
+            Subroute::Profile(profile) => profile.view(&self.connected_state),
+            Subroute::ThresholdKeys(threshold_keys) => threshold_keys.view(&self.connected_state),
+            Subroute::ZapAllowlist(zap_allowlist) => zap_allowlist.view(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubrouteName {
+    List,
+    Add,
+    AppIdentities,
+    Delegations,
+    // The keypair whose secret key is being exported.
+    ExportEncrypted(PublicKey),
+    // The keypair whose profile is being edited.
+    Profile(PublicKey),
+    ThresholdKeys,
+    ZapAllowlist,
+}
+
+impl SubrouteName {
+    pub fn to_default_subroute(&self, connected_state: &ConnectedState) -> Subroute {
+        match self {
+            Self::List => Subroute::List(List {
+                selection: SelectableListState::default(),
+                row_limit: PAGE_SIZE,
+                search_input: String::new(),
+                search_query: String::new(),
+            }),
+            Self::Add => Subroute::Add(Add {
+                nsec: String::new(),
+                keypair_or: None,
+                qr_image_path_input: String::new(),
+                is_scanning_webcam: false,
+                ncryptsec_input: String::new(),
+                ncryptsec_passphrase_input: String::new(),
+                is_decrypting_ncryptsec: false,
+            }),
+            Self::AppIdentities => {
+                Subroute::AppIdentities(app_identities::Page::new(connected_state))
+            }
+            Self::Delegations => Subroute::Delegations(delegations::Page::new(connected_state)),
+            Self::ExportEncrypted(public_key) => Subroute::ExportEncrypted(
+                export_encrypted::Page::new(*public_key, connected_state.db.clone()),
+            ),
+            Self::Profile(public_key) => {
+                Subroute::Profile(profile::Page::new(*public_key, connected_state))
+            }
+            Self::ThresholdKeys => {
+                Subroute::ThresholdKeys(threshold_keys::Page::new(connected_state))
+            }
+            Self::ZapAllowlist => Subroute::ZapAllowlist(zap_allowlist::Page::new(connected_state)),
+        }
+    }
+}
+
+pub enum Subroute {
+    List(List),
+    Add(Add),
+    AppIdentities(app_identities::Page),
+    Delegations(delegations::Page),
+    ExportEncrypted(export_encrypted::Page),
+    Profile(profile::Page),
+    ThresholdKeys(threshold_keys::Page),
+    ZapAllowlist(zap_allowlist::Page),
+}
+
+impl Subroute {
+    pub fn to_name(&self) -> SubrouteName {
+        match self {
+            Self::List(_) => SubrouteName::List,
+            Self::Add(_) => SubrouteName::Add,
+            Self::AppIdentities(_) => SubrouteName::AppIdentities,
+            Self::Delegations(_) => SubrouteName::Delegations,
+            Self::ExportEncrypted(export_encrypted) => {
+                SubrouteName::ExportEncrypted(export_encrypted.public_key())
+            }
+            Self::Profile(profile) => SubrouteName::Profile(profile.public_key()),
+            Self::ThresholdKeys(_) => SubrouteName::ThresholdKeys,
+            Self::ZapAllowlist(_) => SubrouteName::ZapAllowlist,
+        }
+    }
+}
+
+pub struct List {
+    selection: SelectableListState<String>,
+    // How many keys are shown. More are loaded as the user scrolls down.
+    row_limit: i64,
+    search_input: String,
+    // The search input, applied once the user stops typing.
+    search_query: String,
+}
+
+impl List {
+    /// Loads the keys that are shown, along with the total number of matching keys.
+    fn load_keypairs(&self, db: &Database) -> anyhow::Result<(i64, Vec<NostrKeypairSummary>)> {
+        let count = db.count_keypairs(&self.search_query)?;
+
+        let keypairs = db.search_keypairs(&self.search_query, self.row_limit, 0)?;
+
+        Ok((
+            count,
+            rank_by_fuzzy_match(&self.search_query, keypairs, |keypair| {
+                format!(
+                    "{} {}",
+                    keypair.display_name.as_deref().unwrap_or_default(),
+                    keypair.npub
+                )
+            }),
+        ))
+    }
+
+    fn view<'a>(&self, connected_state: &ConnectedState) -> Column<'a, app::Message> {
+        let Ok((count, keypairs)) = self.load_keypairs(&connected_state.db) else {
+            return container("Keys").push("Failed to load keys");
+        };
+
+        let now = Utc::now().naive_utc();
+        let clock_format = connected_state.settings.get().clock_format;
+
+        let shown_count = keypairs.len();
+
+        let rows = keypairs
+            .into_iter()
+            .map(|keypair| {
+                let NostrKeypairSummary {
+                    npub: public_key,
+                    display_name: display_name_or,
+                    create_time,
+                    ..
+                } = keypair;
+
+                let parsed_public_key_or = PublicKey::from_bech32(&public_key).ok();
+
+                // Keys are shown by the name given to them in Keystache, or else the
+                // name in their profile, with the npub underneath.
+                let name_or = display_name_or.or_else(|| {
+                    parsed_public_key_or
+                        .and_then(|parsed_public_key| {
+                            connected_state.profiles.name_or(&parsed_public_key)
+                        })
+                        .map(ToString::to_string)
+                });
+                let npub_size = if name_or.is_some() { 14 } else { 20 };
+
+                let row: Element<'a, app::Message> = Row::new()
+                    .push_maybe(parsed_public_key_or.map(|parsed_public_key| {
+                        avatar(&parsed_public_key, &connected_state.avatars, 32.0)
+                    }))
+                    .push(
+                        Column::new()
+                            .push_maybe(name_or.map(|name| Text::new(name).size(20)))
+                            .push(Text::new(truncate_text(&public_key, 12, true)).size(npub_size)),
+                    )
+                    .push(
+                        Text::new(format!(
+                            "Added {}",
+                            format_time(create_time, now, clock_format)
+                        ))
+                        .size(14),
+                    )
+                    .push(
+                        icon_button("Profile", SvgIcon::Settings, PaletteColor::Background)
+                            .on_press_maybe(parsed_public_key_or.map(|parsed_public_key| {
+                                app::Message::Routes(super::Message::Navigate(
+                                    RouteName::NostrKeypairs(SubrouteName::Profile(
+                                        parsed_public_key,
+                                    )),
+                                ))
+                            })),
+                    )
+                    .push(
+                        icon_button("Export Encrypted", SvgIcon::Lock, PaletteColor::Background)
+                            .on_press_maybe(parsed_public_key_or.map(|parsed_public_key| {
+                                app::Message::Routes(super::Message::Navigate(
+                                    RouteName::NostrKeypairs(SubrouteName::ExportEncrypted(
+                                        parsed_public_key,
+                                    )),
+                                ))
+                            })),
+                    )
+                    .push(
+                        icon_button("Delete", SvgIcon::Delete, PaletteColor::Danger).on_press(
+                            app::Message::Routes(super::Message::NostrKeypairsPage(
+                                Message::DeleteKeypair {
+                                    public_key: public_key.clone(),
+                                },
+                            )),
+                        ),
+                    )
+                    .spacing(10)
+                    .align_y(Alignment::Center)
+                    .into();
+
+                (public_key, row)
+            })
+            .collect();
+
+        let mut container = container("Keys")
+            .push(
+                text_input("Search by npub or name", &self.search_input)
+                    .on_input(|input| {
+                        app::Message::Routes(super::Message::NostrKeypairsPage(
+                            Message::SearchInputChanged(input),
+                        ))
+                    })
+                    .padding(10)
+                    .size(20),
+            )
+            .push(selectable_list(
+                rows,
+                &self.selection,
+                |selection_message| {
+                    app::Message::Routes(super::Message::NostrKeypairsPage(
+                        Message::KeypairSelection(selection_message),
+                    ))
+                },
+                "Delete",
+                |public_keys| {
+                    app::Message::Routes(super::Message::NostrKeypairsPage(
+                        Message::DeleteKeypairs { public_keys },
+                    ))
+                },
+            ));
+
+        container = container.push(load_more_controls(
+            shown_count,
+            count,
+            app::Message::Routes(super::Message::NostrKeypairsPage(Message::LoadMoreKeypairs)),
+        ));
+
+        let navigate = |subroute_name| {
+            app::Message::Routes(super::Message::Navigate(RouteName::NostrKeypairs(
+                subroute_name,
+            )))
+        };
+
+        container = container.push(
+            row![
+                icon_button("Add Keypair", SvgIcon::Add, PaletteColor::Primary)
+                    .on_press(navigate(SubrouteName::Add)),
+                icon_button("Delegations", SvgIcon::Groups, PaletteColor::Background)
+                    .on_press(navigate(SubrouteName::Delegations)),
+                icon_button("Threshold Keys", SvgIcon::Lock, PaletteColor::Background)
+                    .on_press(navigate(SubrouteName::ThresholdKeys)),
+                icon_button("Zap Allowlist", SvgIcon::ThumbUp, PaletteColor::Background)
+                    .on_press(navigate(SubrouteName::ZapAllowlist)),
+                icon_button("App Keypairs", SvgIcon::Key, PaletteColor::Background)
+                    .on_press(navigate(SubrouteName::AppIdentities)),
+            ]
+            .spacing(10),
+        );
+
+        container
+    }
+}
+
+pub struct Add {
+    nsec: String,
+    keypair_or: Option<Keypair>, // Parsed from nsec on any update. `Some` if nsec is valid, `None` otherwise.
+    // An image file with the nsec's QR code in it, such as a screenshot.
+    qr_image_path_input: String,
+    is_scanning_webcam: bool,
+    // A NIP-49 encrypted secret key, such as one exported by another signer.
+    ncryptsec_input: String,
+    ncryptsec_passphrase_input: String,
+    is_decrypting_ncryptsec: bool,
+}
+
+impl Add {
+    fn view<'a>(&self) -> Column<'a, app::Message> {
+        let can_decrypt_ncryptsec = !self.is_decrypting_ncryptsec
+            && !self.ncryptsec_input.trim().is_empty()
+            && !self.ncryptsec_passphrase_input.is_empty();
+
+        container("Add Keypair")
+            .push(bech32_input(
+                "nSec",
+                &self.nsec,
+                &[Bech32Kind::Nsec],
+                |input| {
+                    app::Message::Routes(super::Message::NostrKeypairsPage(
+                        Message::SaveKeypairNsecInputChanged(input),
+                    ))
+                },
+            ))
+            .push(
+                icon_button("Save", SvgIcon::Save, PaletteColor::Primary).on_press_maybe(
+                    self.keypair_or.map(|keypair| {
+                        app::Message::Routes(super::Message::NostrKeypairsPage(
+                            Message::SaveKeypair(keypair),
+                        ))
+                    }),
+                ),
+            )
+            .push(Text::new("Or scan the nSec's QR code").size(20))
+            .push(
+                row![
+                    text_input("Image file", &self.qr_image_path_input)
+                        .on_input(|input| {
+                            app::Message::Routes(super::Message::NostrKeypairsPage(
+                                Message::QrImagePathInputChanged(input),
+                            ))
+                        })
+                        .padding(10)
+                        .size(20),
+                    icon_button("Load Image", SvgIcon::FileCopy, PaletteColor::Background)
+                        .on_press_maybe((!self.qr_image_path_input.trim().is_empty()).then(|| {
+                            app::Message::Routes(super::Message::NostrKeypairsPage(
+                                Message::ScanQrImage,
+                            ))
+                        },)),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+            )
+            .push(
+                icon_button("Scan with Webcam", SvgIcon::Key, PaletteColor::Background)
+                    .on_press_maybe((!self.is_scanning_webcam).then(|| {
+                        app::Message::Routes(super::Message::NostrKeypairsPage(Message::ScanWebcam))
+                    })),
+            )
+            .push_maybe(
+                self.is_scanning_webcam
+                    .then(|| Text::new("Hold the QR code up to your webcam...")),
+            )
+            .push(Text::new("Or import an encrypted key (ncryptsec)").size(20))
+            .push(
+                text_input("ncryptsec1...", &self.ncryptsec_input)
+                    .on_input(|input| {
+                        app::Message::Routes(super::Message::NostrKeypairsPage(
+                            Message::NcryptsecInputChanged(input),
+                        ))
+                    })
+                    .padding(10)
+                    .size(20),
+            )
+            .push(
+                row![
+                    text_input("Passphrase", &self.ncryptsec_passphrase_input)
+                        .secure(true)
+                        .on_input(|input| {
+                            app::Message::Routes(super::Message::NostrKeypairsPage(
+                                Message::NcryptsecPassphraseInputChanged(input),
+                            ))
+                        })
+                        .on_submit_maybe(can_decrypt_ncryptsec.then(|| {
+                            app::Message::Routes(super::Message::NostrKeypairsPage(
+                                Message::DecryptNcryptsec,
+                            ))
+                        }))
+                        .padding(10)
+                        .size(20),
+                    icon_button("Decrypt and Save", SvgIcon::LockOpen, PaletteColor::Primary)
+                        .on_press_maybe(can_decrypt_ncryptsec.then(|| {
+                            app::Message::Routes(super::Message::NostrKeypairsPage(
+                                Message::DecryptNcryptsec,
+                            ))
+                        })),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+            )
+            .push_maybe(
+                self.is_decrypting_ncryptsec
+                    .then(|| Text::new("Decrypting...")),
+            )
+            .push(
+                icon_button(
+                    "Generate New Keypair",
+                    SvgIcon::Casino,
+                    PaletteColor::Primary,
+                )
+                .on_press(app::Message::Routes(super::Message::NostrKeypairsPage(
+                    Message::SaveKeypair(Keypair::new_global(&mut thread_rng())),
+                ))),
+            )
+            .push(
+                icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::NostrKeypairs(
+                        SubrouteName::List,
+                    ))),
+                ),
+            )
+    }
+}
+
+/// Runs work that blocks, such as reading a QR code from the webcam or decrypting a key,
+/// off the UI thread.
+async fn run_blocking<T: Send + 'static>(
+    work: impl FnOnce() -> anyhow::Result<T> + Send + 'static,
+) -> Result<T, String> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|err| err.to_string())?
+        .map_err(|err| err.to_string())
+}
+
+/// Starts loading the profiles of any public keys that haven't been requested yet, so that
+/// they can be shown by name. Each profile that loads is passed back through
+/// [`app::Message::ProfileLoaded`]. Like avatars, nothing is loaded in low data mode.
+fn request_names(
+    profiles: &mut Profiles,
+    public_keys: impl IntoIterator<Item = PublicKey>,
+    nostr_module: &NostrModule,
+) -> Task<app::Message> {
+    if nostr_module.is_low_data_mode() {
+        return Task::none();
+    }
+
+    Task::batch(
+        profiles
+            .take_unrequested(public_keys)
+            .into_iter()
+            .map(|public_key| {
+                let nostr_module = nostr_module.clone();
+
+                Task::future(async move {
+                    match fetch_profile(&nostr_module, public_key).await {
+                        Ok(Some(metadata)) => {
+                            Some(app::Message::ProfileLoaded(public_key, metadata))
+                        }
+                        // Keys without a profile are shown by their npub alone.
+                        Ok(None) => None,
+                        // TODO: Log a warning if the profile fails to load.
+                        Err(_) => None,
+                    }
+                })
+                .and_then(Task::done)
+            }),
+    )
+}
@@ -0,0 +1,146 @@
+use std::sync::Arc;
+
+use iced::{
+    widget::{pick_list, row, Column, Text},
+    Alignment, Task,
+};
+use nostr_sdk::{FromBech32, PublicKey, ToBech32};
+
+use crate::{
+    app,
+    db::Database,
+    policy::Nip46App,
+    routes::{self, container, Loadable, RouteName},
+    ui_components::{icon_button, PaletteColor, SvgIcon, Toast, ToastStatus},
+    util::truncate_text,
+};
+
+use super::{ConnectedState, SubrouteName};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    IdentitySelected(PublicKey, String),
+    ClearIdentity(PublicKey),
+}
+
+pub struct Page {
+    db: Arc<Database>,
+    npubs: Vec<String>,
+    loadable_apps: Loadable<Vec<Nip46App>>,
+}
+
+impl Page {
+    pub fn new(connected_state: &ConnectedState) -> Self {
+        // TODO: Log a warning if the keys fail to load.
+        let npubs = connected_state
+            .db
+            .list_public_keys("", i64::MAX, 0)
+            .unwrap_or_default();
+
+        let mut page = Self {
+            db: connected_state.db.clone(),
+            npubs,
+            loadable_apps: Loadable::Loading,
+        };
+
+        page.load_apps();
+
+        page
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        let result = match msg {
+            Message::IdentitySelected(public_key, npub) => PublicKey::from_bech32(&npub)
+                .map_err(anyhow::Error::from)
+                .and_then(|identity| {
+                    self.db
+                        .save_nip46_app_identity(&public_key, Some(&identity))
+                }),
+            Message::ClearIdentity(public_key) => {
+                self.db.save_nip46_app_identity(&public_key, None)
+            }
+        };
+
+        self.load_apps();
+
+        match result {
+            Ok(()) => Task::none(),
+            Err(err) => Task::done(app::Message::AddToast(Toast {
+                title: "Failed to save app keypair".to_string(),
+                body: err.to_string(),
+                status: ToastStatus::Bad,
+            })),
+        }
+    }
+
+    fn load_apps(&mut self) {
+        self.loadable_apps = match self.db.list_nip46_apps() {
+            Ok(apps) => Loadable::Loaded(apps),
+            Err(_err) => Loadable::Failed,
+        };
+    }
+
+    pub fn view(&self) -> Column<app::Message> {
+        let mut container = container("App Keypairs").push(Text::new(
+            "Choose which keypair each app signs as. Requests for any other keypair are never approved automatically, and can only be approved after switching the app to that keypair. Apps without a keypair can sign as any of them.",
+        ));
+
+        match &self.loadable_apps {
+            Loadable::Loading => {
+                container = container.push(Text::new("Loading..."));
+            }
+            Loadable::Loaded(apps) if apps.is_empty() => {
+                container = container.push(Text::new("No apps have connected yet"));
+            }
+            Loadable::Loaded(apps) => {
+                for app in apps {
+                    let public_key = app.public_key;
+
+                    let app_npub = public_key.to_bech32().map_or_else(
+                        |_| public_key.to_string(),
+                        |npub| truncate_text(&npub, 24, true),
+                    );
+
+                    let identity_npub_or = app
+                        .identity_or
+                        .and_then(|identity| identity.to_bech32().ok());
+
+                    let clear_message_or = app
+                        .identity_or
+                        .map(|_| app_identities_message(Message::ClearIdentity(public_key)));
+
+                    container = container.push(
+                        row![
+                            Text::new(app_npub),
+                            pick_list(self.npubs.as_slice(), identity_npub_or, move |npub| {
+                                app_identities_message(Message::IdentitySelected(public_key, npub))
+                            })
+                            .placeholder("Any keypair"),
+                            icon_button("Clear", SvgIcon::Delete, PaletteColor::Background)
+                                .on_press_maybe(clear_message_or),
+                        ]
+                        .spacing(10)
+                        .align_y(Alignment::Center),
+                    );
+                }
+            }
+            Loadable::Failed => {
+                container = container.push(Text::new("Failed to load apps"));
+            }
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(routes::Message::Navigate(RouteName::NostrKeypairs(
+                    SubrouteName::List,
+                ))),
+            ),
+        )
+    }
+}
+
+fn app_identities_message(message: Message) -> app::Message {
+    app::Message::Routes(routes::Message::NostrKeypairsPage(
+        super::Message::AppIdentities(message),
+    ))
+}
@@ -0,0 +1,331 @@
+use std::{str::FromStr, sync::Arc};
+
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use iced::{
+    widget::{pick_list, row, Column, Text},
+    Alignment, Task,
+};
+use nostr_sdk::{PublicKey, ToBech32};
+
+use crate::{
+    app,
+    config::ClockFormat,
+    db::Database,
+    delegation::{sign_delegation, Delegation, DelegationConditions},
+    routes::{self, container, Loadable, RouteName},
+    ui_components::{icon_button, text_input, PaletteColor, SvgIcon, Toast, ToastStatus},
+    util::{format_time, truncate_text},
+};
+
+use super::{ConnectedState, SubrouteName};
+
+const DEFAULT_VALID_DAYS: &str = "30";
+
+// Delegations can't be revoked once they've been handed out,
+// so they shouldn't be allowed to stay valid for too long.
+const MAX_VALID_DAYS: i64 = 365;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    DelegatorSelected(String),
+    DelegateeInputChanged(String),
+    KindsInputChanged(String),
+    ValidDaysInputChanged(String),
+    IssueDelegation,
+    RevokeDelegation(i32),
+}
+
+pub struct Page {
+    db: Arc<Database>,
+    clock_format: ClockFormat,
+    delegator_npubs: Vec<String>,
+    selected_delegator_npub_or: Option<String>,
+    delegatee_input: String,
+    kinds_input: String,
+    valid_days_input: String,
+    loadable_delegations: Loadable<Vec<Delegation>>,
+}
+
+impl Page {
+    pub fn new(connected_state: &ConnectedState) -> Self {
+        // TODO: Log a warning if the keys fail to load.
+        let delegator_npubs = connected_state
+            .db
+            .list_public_keys("", i64::MAX, 0)
+            .unwrap_or_default();
+
+        let mut page = Self {
+            db: connected_state.db.clone(),
+            clock_format: connected_state.settings.get().clock_format,
+            selected_delegator_npub_or: delegator_npubs.first().cloned(),
+            delegator_npubs,
+            delegatee_input: String::new(),
+            kinds_input: String::new(),
+            valid_days_input: DEFAULT_VALID_DAYS.to_string(),
+            loadable_delegations: Loadable::Loading,
+        };
+
+        page.load_delegations();
+
+        page
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::DelegatorSelected(npub) => {
+                self.selected_delegator_npub_or = Some(npub);
+
+                Task::none()
+            }
+            Message::DelegateeInputChanged(input) => {
+                self.delegatee_input = input;
+
+                Task::none()
+            }
+            Message::KindsInputChanged(input) => {
+                self.kinds_input = input;
+
+                Task::none()
+            }
+            Message::ValidDaysInputChanged(input) => {
+                self.valid_days_input = input;
+
+                Task::none()
+            }
+            Message::IssueDelegation => match self.issue_delegation() {
+                Ok(()) => {
+                    self.delegatee_input.clear();
+                    self.load_delegations();
+
+                    Task::done(app::Message::AddToast(Toast {
+                        title: "Issued delegation".to_string(),
+                        body: "Copy the delegation tag into the app that uses the delegated key."
+                            .to_string(),
+                        status: ToastStatus::Good,
+                    }))
+                }
+                Err(err) => Task::done(app::Message::AddToast(Toast {
+                    title: "Failed to issue delegation".to_string(),
+                    body: err.to_string(),
+                    status: ToastStatus::Bad,
+                })),
+            },
+            Message::RevokeDelegation(id) => {
+                match self.db.revoke_delegation(id, Utc::now().naive_utc()) {
+                    Ok(()) => {
+                        self.load_delegations();
+
+                        Task::done(app::Message::AddToast(Toast {
+                            title: "Revoked delegation".to_string(),
+                            body: "Stop using the delegated key. Events it already signed stay valid until the delegation expires.".to_string(),
+                            status: ToastStatus::Good,
+                        }))
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to revoke delegation".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+        }
+    }
+
+    fn issue_delegation(&self) -> anyhow::Result<()> {
+        let delegator_npub = self
+            .selected_delegator_npub_or
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Select the key to delegate from"))?;
+
+        let delegator = self.db.get_keypair(delegator_npub)?;
+
+        let delegatee_public_key = PublicKey::from_str(self.delegatee_input.trim())
+            .map_err(|_| anyhow::anyhow!("The delegated key must be an npub or hex public key"))?;
+
+        let conditions = self.parse_conditions(Utc::now().naive_utc())?;
+
+        let signature = sign_delegation(&delegator, &delegatee_public_key, &conditions);
+
+        self.db.save_delegation(
+            &delegator.x_only_public_key().0.into(),
+            &delegatee_public_key,
+            &conditions,
+            &signature,
+        )
+    }
+
+    fn parse_conditions(&self, now: NaiveDateTime) -> anyhow::Result<DelegationConditions> {
+        let kinds = self
+            .kinds_input
+            .split(',')
+            .map(str::trim)
+            .filter(|kind| !kind.is_empty())
+            .map(|kind| {
+                kind.parse::<u16>()
+                    .map_err(|_| anyhow::anyhow!("\"{kind}\" isn't a valid event kind"))
+            })
+            .collect::<anyhow::Result<Vec<u16>>>()?;
+
+        let valid_days = self
+            .valid_days_input
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .filter(|valid_days| (1..=MAX_VALID_DAYS).contains(valid_days))
+            .ok_or_else(|| {
+                anyhow::anyhow!("The delegation must be valid for 1 to {MAX_VALID_DAYS} days")
+            })?;
+
+        Ok(DelegationConditions {
+            kinds,
+            valid_from: now,
+            valid_until: now + TimeDelta::days(valid_days),
+        })
+    }
+
+    fn load_delegations(&mut self) {
+        // TODO: Add pagination.
+        self.loadable_delegations = match self.db.list_delegations(999, 0) {
+            Ok(delegations) => Loadable::Loaded(delegations),
+            Err(_err) => Loadable::Failed,
+        };
+    }
+
+    pub fn view(&self) -> Column<app::Message> {
+        let mut container = container("Delegations")
+            .push(Text::new(
+                "A delegation (NIP-26) lets an app publish events on behalf of one of your keys using a separate key, so your main key never has to leave Keystache.",
+            ))
+            .push(Text::new("Issue Delegation").size(25))
+            .push(pick_list(
+                self.delegator_npubs.as_slice(),
+                self.selected_delegator_npub_or.clone(),
+                |npub| delegations_message(Message::DelegatorSelected(npub)),
+            ))
+            .push(
+                text_input("Delegated npub", &self.delegatee_input)
+                    .on_input(|input| delegations_message(Message::DelegateeInputChanged(input)))
+                    .padding(10)
+                    .size(20),
+            )
+            .push(
+                text_input(
+                    "Allowed event kinds, separated by commas (leave empty to allow any kind)",
+                    &self.kinds_input,
+                )
+                .on_input(|input| delegations_message(Message::KindsInputChanged(input)))
+                .padding(10)
+                .size(20),
+            )
+            .push(
+                row![
+                    text_input("Days", &self.valid_days_input)
+                        .on_input(|input| delegations_message(Message::ValidDaysInputChanged(
+                            input
+                        )))
+                        .padding(10)
+                        .size(20)
+                        .width(100.0),
+                    Text::new("days until the delegation expires"),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+            )
+            .push(
+                icon_button("Issue Delegation", SvgIcon::Key, PaletteColor::Primary)
+                    .on_press_maybe(
+                        (self.selected_delegator_npub_or.is_some()
+                            && !self.delegatee_input.trim().is_empty())
+                        .then(|| delegations_message(Message::IssueDelegation)),
+                    ),
+            )
+            .push(Text::new("Issued Delegations").size(25))
+            .push(Text::new(
+                "Relays can't be told about a revoked delegation. Revoking only marks it in Keystache, and events signed with it stay valid until it expires, so keep expiry times short.",
+            ));
+
+        match &self.loadable_delegations {
+            Loadable::Loading => {
+                container = container.push(Text::new("Loading..."));
+            }
+            Loadable::Loaded(delegations) if delegations.is_empty() => {
+                container = container.push(Text::new("No delegations issued"));
+            }
+            Loadable::Loaded(delegations) => {
+                let now = Utc::now().naive_utc();
+
+                for delegation in delegations {
+                    container = container.push(delegation_view(delegation, now, self.clock_format));
+                }
+            }
+            Loadable::Failed => {
+                container = container.push(Text::new("Failed to load delegations"));
+            }
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(routes::Message::Navigate(RouteName::NostrKeypairs(
+                    SubrouteName::List,
+                ))),
+            ),
+        )
+    }
+}
+
+fn delegation_view<'a>(
+    delegation: &Delegation,
+    now: NaiveDateTime,
+    clock_format: ClockFormat,
+) -> Column<'a, app::Message> {
+    let to_display_npub = |public_key: &PublicKey| {
+        public_key.to_bech32().map_or_else(
+            |_| public_key.to_string(),
+            |npub| truncate_text(&npub, 23, true),
+        )
+    };
+
+    let status = match delegation.revoke_time_or {
+        Some(revoke_time) => format!("Revoked {}", format_time(revoke_time, now, clock_format)),
+        None if delegation.is_expired(now) => format!(
+            "Expired {}",
+            format_time(delegation.valid_until, now, clock_format)
+        ),
+        None => format!(
+            "Expires {}",
+            format_time(delegation.valid_until, now, clock_format)
+        ),
+    };
+
+    let is_active = delegation.revoke_time_or.is_none() && !delegation.is_expired(now);
+
+    Column::new()
+        .push(Text::new(format!(
+            "From {} to {}",
+            to_display_npub(&delegation.delegator_public_key),
+            to_display_npub(&delegation.delegatee_public_key)
+        )))
+        .push(Text::new(format!("Conditions: {}", delegation.conditions)).size(14))
+        .push(Text::new(status).size(14))
+        .push(
+            row![
+                icon_button("Copy Tag", SvgIcon::ContentCopy, PaletteColor::Background)
+                    .on_press_maybe(is_active.then(|| {
+                        app::Message::CopyStringToClipboard(delegation.to_tag_json())
+                    })),
+                icon_button("Revoke", SvgIcon::Delete, PaletteColor::Danger).on_press_maybe(
+                    is_active.then_some(delegations_message(Message::RevokeDelegation(
+                        delegation.id
+                    )))
+                ),
+            ]
+            .spacing(10),
+        )
+        .spacing(5)
+}
+
+fn delegations_message(message: Message) -> app::Message {
+    app::Message::Routes(routes::Message::NostrKeypairsPage(
+        super::Message::Delegations(message),
+    ))
+}
@@ -0,0 +1,193 @@
+use std::sync::Arc;
+
+use iced::{
+    widget::{qr_code::Data, row, Column, QRCode, Text},
+    Alignment, Task,
+};
+use nostr_sdk::{PublicKey, SecretKey, ToBech32};
+
+use crate::{
+    app,
+    db::Database,
+    ncryptsec::export_ncryptsec,
+    routes::{self, container, RouteName},
+    ui_components::{icon_button, text_input, PaletteColor, SvgIcon, Toast, ToastStatus},
+    util::truncate_text,
+};
+
+use super::{run_blocking, SubrouteName};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    PassphraseInputChanged(String),
+    ConfirmPassphraseInputChanged(String),
+    Encrypt,
+    Encrypted(Result<String, String>),
+}
+
+pub struct Page {
+    db: Arc<Database>,
+    npub: String,
+    public_key: PublicKey,
+    passphrase_input: String,
+    confirm_passphrase_input: String,
+    is_encrypting: bool,
+    // The exported key, along with its QR code.
+    ncryptsec_or: Option<(String, Data)>,
+}
+
+impl Page {
+    pub fn new(public_key: PublicKey, db: Arc<Database>) -> Self {
+        let npub = public_key
+            .to_bech32()
+            .unwrap_or_else(|_| public_key.to_string());
+
+        Self {
+            db,
+            npub,
+            public_key,
+            passphrase_input: String::new(),
+            confirm_passphrase_input: String::new(),
+            is_encrypting: false,
+            ncryptsec_or: None,
+        }
+    }
+
+    pub const fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::PassphraseInputChanged(input) => {
+                self.passphrase_input = input;
+
+                Task::none()
+            }
+            Message::ConfirmPassphraseInputChanged(input) => {
+                self.confirm_passphrase_input = input;
+
+                Task::none()
+            }
+            Message::Encrypt => {
+                let secret_key = match self.db.get_keypair(&self.npub) {
+                    Ok(keypair) => SecretKey::from(keypair.secret_key()),
+                    Err(err) => {
+                        return Task::done(app::Message::AddToast(Toast {
+                            title: "Failed to export keypair".to_string(),
+                            body: err.to_string(),
+                            status: ToastStatus::Bad,
+                        }));
+                    }
+                };
+
+                self.is_encrypting = true;
+                self.ncryptsec_or = None;
+
+                let passphrase = self.passphrase_input.clone();
+
+                Task::perform(
+                    run_blocking(move || export_ncryptsec(&secret_key, &passphrase)),
+                    |result| export_encrypted_message(Message::Encrypted(result)),
+                )
+            }
+            Message::Encrypted(result) => {
+                self.is_encrypting = false;
+
+                match result.and_then(|ncryptsec| {
+                    let qr_code = Data::new(&ncryptsec).map_err(|err| err.to_string())?;
+
+                    Ok((ncryptsec, qr_code))
+                }) {
+                    Ok(ncryptsec) => {
+                        // The passphrase isn't needed anymore, so it isn't kept around.
+                        self.passphrase_input.clear();
+                        self.confirm_passphrase_input.clear();
+                        self.ncryptsec_or = Some(ncryptsec);
+
+                        Task::none()
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to export keypair".to_string(),
+                        body: err,
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+        }
+    }
+
+    pub fn view(&self) -> Column<app::Message> {
+        let mut container = container("Export Encrypted")
+            .push(Text::new(truncate_text(&self.npub, 24, true)))
+            .push(Text::new(
+                "The secret key is encrypted with a passphrase (NIP-49), so it can be moved to another signer without being exposed. Anyone with the passphrase can decrypt it, so choose one that's hard to guess.",
+            ));
+
+        if let Some((ncryptsec, qr_code)) = &self.ncryptsec_or {
+            container = container
+                .push(QRCode::new(qr_code))
+                .push(Text::new(truncate_text(ncryptsec, 40, true)))
+                .push(
+                    icon_button("Copy", SvgIcon::ContentCopy, PaletteColor::Primary)
+                        .on_press(app::Message::CopyStringToClipboard(ncryptsec.clone())),
+                );
+        } else {
+            let passphrases_match = self.passphrase_input == self.confirm_passphrase_input;
+            let can_encrypt =
+                !self.is_encrypting && !self.passphrase_input.is_empty() && passphrases_match;
+
+            container = container
+                .push(
+                    text_input("Passphrase", &self.passphrase_input)
+                        .secure(true)
+                        .on_input(|input| {
+                            export_encrypted_message(Message::PassphraseInputChanged(input))
+                        })
+                        .padding(10)
+                        .size(20),
+                )
+                .push(
+                    row![
+                        text_input("Confirm Passphrase", &self.confirm_passphrase_input)
+                            .secure(true)
+                            .on_input(|input| {
+                                export_encrypted_message(Message::ConfirmPassphraseInputChanged(
+                                    input,
+                                ))
+                            })
+                            .on_submit_maybe(
+                                can_encrypt.then(|| export_encrypted_message(Message::Encrypt)),
+                            )
+                            .padding(10)
+                            .size(20),
+                        icon_button("Encrypt", SvgIcon::Lock, PaletteColor::Primary)
+                            .on_press_maybe(
+                                can_encrypt.then(|| export_encrypted_message(Message::Encrypt)),
+                            ),
+                    ]
+                    .spacing(10)
+                    .align_y(Alignment::Center),
+                )
+                .push_maybe(
+                    (!passphrases_match && !self.confirm_passphrase_input.is_empty())
+                        .then(|| Text::new("The passphrases don't match.")),
+                )
+                .push_maybe(self.is_encrypting.then(|| Text::new("Encrypting...")));
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(routes::Message::Navigate(RouteName::NostrKeypairs(
+                    SubrouteName::List,
+                ))),
+            ),
+        )
+    }
+}
+
+fn export_encrypted_message(message: Message) -> app::Message {
+    app::Message::Routes(routes::Message::NostrKeypairsPage(
+        super::Message::ExportEncrypted(message),
+    ))
+}
@@ -0,0 +1,446 @@
+use std::sync::Arc;
+
+use iced::{
+    widget::{qr_code::Data, row, Column, QRCode, Text},
+    Alignment, Task,
+};
+use nostr_sdk::{JsonUtil, Keys, Metadata, PublicKey, SecretKey, ToBech32, UnsignedEvent};
+
+use crate::{
+    app,
+    db::Database,
+    nostr::{NostrModule, PublishOutcome},
+    policy::describe_event_kind,
+    profile::{build_profile, fetch_profile, ProfileField},
+    routes::{self, container, Loadable, RouteName},
+    ui_components::{avatar, icon_button, text_input, PaletteColor, SvgIcon, Toast, ToastStatus},
+    util::truncate_text,
+};
+
+use super::{ConnectedState, SubrouteName};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    DisplayNameInputChanged(String),
+    SaveDisplayName,
+    FetchProfile,
+    ProfileFetched(Result<Option<Metadata>, String>),
+    FieldChanged(ProfileField, String),
+    ReviewProfile,
+    ApproveProfile,
+    RejectProfile,
+    ProfilePublished(Result<PublishOutcome, String>),
+    RevealNsecQrCode,
+    ConfirmRevealNsecQrCode,
+    HideNsecQrCode,
+}
+
+pub struct Page {
+    db: Arc<Database>,
+    nostr_module: NostrModule,
+    npub: String,
+    public_key: PublicKey,
+    // The name the keypair is shown by in Keystache, which isn't published.
+    display_name_input: String,
+    // The newest profile on relays, or `None` if the key has none yet.
+    // Edits start from it, so that a newer profile isn't overwritten.
+    loadable_profile: Loadable<Option<Metadata>>,
+    draft: Metadata,
+    // The profile waiting to be approved, before it's signed.
+    pending_profile_or: Option<UnsignedEvent>,
+    // The profile that's being sent to relays.
+    publishing_profile_or: Option<Metadata>,
+    npub_qr_code_or: Option<Data>,
+    // Only loaded once the user confirms they want the secret key shown.
+    nsec_qr_code_or: Option<Data>,
+    is_confirming_nsec_reveal: bool,
+}
+
+impl Page {
+    pub fn new(public_key: PublicKey, connected_state: &ConnectedState) -> Self {
+        let npub = public_key
+            .to_bech32()
+            .unwrap_or_else(|_| public_key.to_string());
+
+        // TODO: Log a warning if the display names fail to load.
+        let display_name_input = connected_state
+            .db
+            .list_keypair_display_names()
+            .unwrap_or_default()
+            .remove(&npub)
+            .unwrap_or_default();
+
+        // TODO: Log a warning if the QR code can't be made.
+        let npub_qr_code_or = Data::new(&npub).ok();
+
+        Self {
+            db: connected_state.db.clone(),
+            nostr_module: connected_state.nostr_module.clone(),
+            npub,
+            public_key,
+            display_name_input,
+            loadable_profile: Loadable::Loading,
+            draft: Metadata::new(),
+            pending_profile_or: None,
+            publishing_profile_or: None,
+            npub_qr_code_or,
+            nsec_qr_code_or: None,
+            is_confirming_nsec_reveal: false,
+        }
+    }
+
+    pub const fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+
+    /// Starts fetching the newest profile of the key from relays.
+    pub fn fetch_profile(&mut self) -> Task<app::Message> {
+        self.loadable_profile = Loadable::Loading;
+
+        let nostr_module = self.nostr_module.clone();
+        let public_key = self.public_key;
+
+        Task::perform(
+            async move {
+                fetch_profile(&nostr_module, public_key)
+                    .await
+                    .map_err(|err| err.to_string())
+            },
+            |result| profile_message(Message::ProfileFetched(result)),
+        )
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::DisplayNameInputChanged(input) => {
+                self.display_name_input = input;
+
+                Task::none()
+            }
+            Message::SaveDisplayName => {
+                match self
+                    .db
+                    .set_keypair_display_name(&self.npub, &self.display_name_input)
+                {
+                    Ok(()) => {
+                        self.display_name_input = self.display_name_input.trim().to_string();
+
+                        Task::done(app::Message::AddToast(Toast {
+                            title: "Saved name".to_string(),
+                            body: "The keypair's name was saved.".to_string(),
+                            status: ToastStatus::Good,
+                        }))
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to save name".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::FetchProfile => self.fetch_profile(),
+            Message::ProfileFetched(result) => match result {
+                Ok(metadata_or) => {
+                    self.draft = metadata_or.clone().unwrap_or_default();
+                    self.loadable_profile = Loadable::Loaded(metadata_or.clone());
+
+                    match metadata_or {
+                        Some(metadata) => {
+                            Task::done(app::Message::ProfileLoaded(self.public_key, metadata))
+                        }
+                        None => Task::none(),
+                    }
+                }
+                Err(err) => {
+                    self.loadable_profile = Loadable::Failed;
+
+                    Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to fetch profile".to_string(),
+                        body: err,
+                        status: ToastStatus::Bad,
+                    }))
+                }
+            },
+            Message::FieldChanged(field, value) => {
+                field.set(&mut self.draft, value);
+
+                Task::none()
+            }
+            Message::ReviewProfile => match build_profile(self.public_key, self.draft.clone()) {
+                Ok(unsigned_event) => {
+                    self.pending_profile_or = Some(unsigned_event);
+
+                    Task::none()
+                }
+                Err(err) => Task::done(app::Message::AddToast(Toast {
+                    title: "Invalid profile".to_string(),
+                    body: err.to_string(),
+                    status: ToastStatus::Bad,
+                })),
+            },
+            Message::ApproveProfile => {
+                let Some(unsigned_event) = self.pending_profile_or.take() else {
+                    return Task::none();
+                };
+
+                let keys = match self.db.get_keypair(&self.npub) {
+                    Ok(keypair) => Keys::new(keypair.secret_key().into()),
+                    Err(err) => {
+                        return Task::done(app::Message::AddToast(Toast {
+                            title: "Failed to publish profile".to_string(),
+                            body: err.to_string(),
+                            status: ToastStatus::Bad,
+                        }));
+                    }
+                };
+
+                // TODO: Log a warning if the profile can't be read back.
+                self.publishing_profile_or = Metadata::from_json(&unsigned_event.content).ok();
+
+                let nostr_module = self.nostr_module.clone();
+
+                Task::perform(
+                    async move {
+                        nostr_module
+                            .sign_and_publish(unsigned_event, &keys)
+                            .await
+                            .map_err(|err| err.to_string())
+                    },
+                    |result| profile_message(Message::ProfilePublished(result)),
+                )
+            }
+            Message::RejectProfile => {
+                self.pending_profile_or = None;
+
+                Task::none()
+            }
+            Message::ProfilePublished(result) => {
+                let metadata_or = self.publishing_profile_or.take();
+
+                let toast = match result {
+                    Ok(PublishOutcome::Sent) => Toast {
+                        title: "Published profile".to_string(),
+                        body: "Your profile was sent to your relays.".to_string(),
+                        status: ToastStatus::Good,
+                    },
+                    Ok(PublishOutcome::Queued) => Toast {
+                        title: "Profile queued".to_string(),
+                        body: "No relay is connected, so your profile will be sent once one is."
+                            .to_string(),
+                        status: ToastStatus::Neutral,
+                    },
+                    Err(err) => {
+                        return Task::done(app::Message::AddToast(Toast {
+                            title: "Failed to publish profile".to_string(),
+                            body: err,
+                            status: ToastStatus::Bad,
+                        }));
+                    }
+                };
+
+                // The published profile is now the newest one, so later edits start from it.
+                let profile_task = match metadata_or {
+                    Some(metadata) => {
+                        self.draft = metadata.clone();
+                        self.loadable_profile = Loadable::Loaded(Some(metadata.clone()));
+
+                        Task::done(app::Message::ProfileLoaded(self.public_key, metadata))
+                    }
+                    None => Task::none(),
+                };
+
+                Task::batch([Task::done(app::Message::AddToast(toast)), profile_task])
+            }
+            Message::RevealNsecQrCode => {
+                self.is_confirming_nsec_reveal = true;
+
+                Task::none()
+            }
+            Message::ConfirmRevealNsecQrCode => {
+                self.is_confirming_nsec_reveal = false;
+
+                let nsec_qr_code_result = self.db.get_keypair(&self.npub).and_then(|keypair| {
+                    let nsec = SecretKey::from(keypair.secret_key()).to_bech32()?;
+
+                    Ok(Data::new(nsec)?)
+                });
+
+                match nsec_qr_code_result {
+                    Ok(nsec_qr_code) => {
+                        self.nsec_qr_code_or = Some(nsec_qr_code);
+
+                        Task::none()
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to show nSec".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::HideNsecQrCode => {
+                self.is_confirming_nsec_reveal = false;
+                self.nsec_qr_code_or = None;
+
+                Task::none()
+            }
+        }
+    }
+
+    pub fn view<'a>(&'a self, connected_state: &ConnectedState) -> Column<'a, app::Message> {
+        let mut container = container("Profile").push(
+            row![
+                avatar(&self.public_key, &connected_state.avatars, 48.0),
+                Text::new(truncate_text(&self.npub, 24, true)),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+        );
+
+        // Like a NIP-46 request, the profile is shown exactly as it will be signed.
+        if let Some(unsigned_event) = &self.pending_profile_or {
+            container = container
+                .push(Text::new("Sign this profile?").size(25))
+                .push(Text::new(describe_event_kind(unsigned_event.kind)));
+
+            // TODO: Log a warning if the profile can't be read back.
+            let metadata = Metadata::from_json(&unsigned_event.content).unwrap_or_default();
+
+            for field in ProfileField::ALL {
+                if let Some(value) = field.get(&metadata) {
+                    container = container.push(Text::new(format!("{}: {value}", field.label())));
+                }
+            }
+
+            return container.push(
+                row![
+                    icon_button("Approve", SvgIcon::ThumbUp, PaletteColor::Primary)
+                        .on_press(profile_message(Message::ApproveProfile)),
+                    icon_button("Reject", SvgIcon::ThumbDown, PaletteColor::Primary)
+                        .on_press(profile_message(Message::RejectProfile)),
+                ]
+                .spacing(20),
+            );
+        }
+
+        container = container
+            .push(Text::new("QR Codes").size(25))
+            .push(Text::new("Scan the npub to share this keypair's public key."))
+            .push_maybe(self.npub_qr_code_or.as_ref().map(QRCode::new))
+            .push(self.nsec_qr_code_view())
+            .push(Text::new("Name in Keystache").size(25))
+            .push(Text::new(
+                "Shown in Keystache only, such as when an app asks to sign with this keypair. It isn't published.",
+            ))
+            .push(
+                row![
+                    text_input("Name", &self.display_name_input)
+                        .on_input(|input| profile_message(Message::DisplayNameInputChanged(input)))
+                        .on_submit(profile_message(Message::SaveDisplayName))
+                        .padding(10)
+                        .size(20),
+                    icon_button("Save", SvgIcon::Save, PaletteColor::Primary)
+                        .on_press(profile_message(Message::SaveDisplayName)),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+            )
+            .push(Text::new("Public Profile").size(25))
+            .push(Text::new(
+                "Your profile (kind 0) is what apps show for this keypair. Publishing it replaces the profile on your relays.",
+            ));
+
+        match &self.loadable_profile {
+            Loadable::Loading => {
+                container = container.push(Text::new("Fetching profile..."));
+            }
+            Loadable::Loaded(metadata_or) => {
+                if metadata_or.is_none() {
+                    container = container.push(Text::new(
+                        "No profile was found on your relays, so publishing creates one.",
+                    ));
+                }
+
+                for field in ProfileField::ALL {
+                    container = container.push(
+                        Column::new()
+                            .push(Text::new(field.label()).size(14))
+                            .push(
+                                text_input(field.label(), field.get(&self.draft).unwrap_or(""))
+                                    .on_input(move |input| {
+                                        profile_message(Message::FieldChanged(field, input))
+                                    })
+                                    .padding(10)
+                                    .size(20),
+                            )
+                            .spacing(5),
+                    );
+                }
+
+                let is_publishing = self.publishing_profile_or.is_some();
+
+                container = container
+                    .push(
+                        icon_button("Review and Sign", SvgIcon::Key, PaletteColor::Primary)
+                            .on_press_maybe(
+                                (!is_publishing).then(|| profile_message(Message::ReviewProfile)),
+                            ),
+                    )
+                    .push_maybe(is_publishing.then(|| Text::new("Talking to your relays...")));
+            }
+            Loadable::Failed => {
+                // Without the newest profile, publishing could overwrite fields set elsewhere.
+                container = container.push(Text::new("Failed to fetch profile")).push(
+                    icon_button("Try Again", SvgIcon::Refresh, PaletteColor::Primary)
+                        .on_press(profile_message(Message::FetchProfile)),
+                );
+            }
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(routes::Message::Navigate(RouteName::NostrKeypairs(
+                    SubrouteName::List,
+                ))),
+            ),
+        )
+    }
+
+    fn nsec_qr_code_view(&self) -> Column<app::Message> {
+        let column = Column::new().spacing(10);
+
+        if let Some(nsec_qr_code) = &self.nsec_qr_code_or {
+            return column.push(QRCode::new(nsec_qr_code)).push(
+                icon_button("Hide nSec", SvgIcon::Lock, PaletteColor::Primary)
+                    .on_press(profile_message(Message::HideNsecQrCode)),
+            );
+        }
+
+        if self.is_confirming_nsec_reveal {
+            return column
+                .push(Text::new(
+                    "Anyone who sees or photographs the nSec can sign as this keypair. Make sure no one is watching your screen.",
+                ))
+                .push(
+                    row![
+                        icon_button("Show nSec", SvgIcon::LockOpen, PaletteColor::Danger)
+                            .on_press(profile_message(Message::ConfirmRevealNsecQrCode)),
+                        icon_button("Cancel", SvgIcon::Close, PaletteColor::Background)
+                            .on_press(profile_message(Message::HideNsecQrCode)),
+                    ]
+                    .spacing(10),
+                );
+        }
+
+        column.push(
+            icon_button("Reveal nSec", SvgIcon::LockOpen, PaletteColor::Background)
+                .on_press(profile_message(Message::RevealNsecQrCode)),
+        )
+    }
+}
+
+fn profile_message(message: Message) -> app::Message {
+    app::Message::Routes(routes::Message::NostrKeypairsPage(super::Message::Profile(
+        message,
+    )))
+}
@@ -0,0 +1,563 @@
+use std::sync::Arc;
+
+use iced::{
+    widget::{pick_list, row, text_editor, Column, Text},
+    Alignment, Task,
+};
+use nostr_sdk::{JsonUtil, PublicKey, ToBech32, UnsignedEvent};
+
+use crate::{
+    app,
+    db::Database,
+    nostr::NostrModule,
+    routes::{self, container, Loadable, RouteName},
+    threshold_key::{self, ThresholdShare},
+    ui_components::{icon_button, text_input, PaletteColor, SvgIcon, Toast, ToastStatus},
+    util::truncate_text,
+};
+
+use super::{ConnectedState, SubrouteName};
+
+const DEFAULT_THRESHOLD: &str = "2";
+const DEFAULT_COSIGNER_COUNT: &str = "2";
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    KeypairSelected(String),
+    ThresholdInputChanged(String),
+    CosignerCountInputChanged(String),
+    SplitKeypair,
+    ImportInputChanged(String),
+    ImportShare,
+    RemoveShare(PublicKey),
+    SigningShareSelected(String),
+    UnsignedEventEdited(text_editor::Action),
+    SignAndPublish,
+    SignedAndPublished(Result<String, String>),
+}
+
+pub struct Page {
+    db: Arc<Database>,
+    nostr_module: NostrModule,
+    keypair_npubs: Vec<String>,
+    selected_keypair_npub_or: Option<String>,
+    threshold_input: String,
+    cosigner_count_input: String,
+    // The key that was last split, and the shares to hand to its cosigners. These are
+    // only kept until leaving the page, so that they don't linger on this device.
+    split_npub_or: Option<String>,
+    cosigner_share_jsons: Vec<String>,
+    import_input: String,
+    loadable_shares: Loadable<Vec<ThresholdShare>>,
+    selected_signing_npub_or: Option<String>,
+    unsigned_event: text_editor::Content,
+    is_signing: bool,
+    signed_event_json_or: Option<String>,
+}
+
+impl Page {
+    pub fn new(connected_state: &ConnectedState) -> Self {
+        // TODO: Log a warning if the keys fail to load.
+        let keypair_npubs = connected_state
+            .db
+            .list_public_keys("", i64::MAX, 0)
+            .unwrap_or_default();
+
+        let mut page = Self {
+            db: connected_state.db.clone(),
+            nostr_module: connected_state.nostr_module.clone(),
+            selected_keypair_npub_or: keypair_npubs.first().cloned(),
+            keypair_npubs,
+            threshold_input: DEFAULT_THRESHOLD.to_string(),
+            cosigner_count_input: DEFAULT_COSIGNER_COUNT.to_string(),
+            split_npub_or: None,
+            cosigner_share_jsons: Vec::new(),
+            import_input: String::new(),
+            loadable_shares: Loadable::Loading,
+            selected_signing_npub_or: None,
+            unsigned_event: text_editor::Content::new(),
+            is_signing: false,
+            signed_event_json_or: None,
+        };
+
+        page.load_shares();
+
+        page
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::KeypairSelected(npub) => {
+                self.selected_keypair_npub_or = Some(npub);
+
+                Task::none()
+            }
+            Message::ThresholdInputChanged(input) => {
+                self.threshold_input = input;
+
+                Task::none()
+            }
+            Message::CosignerCountInputChanged(input) => {
+                self.cosigner_count_input = input;
+
+                Task::none()
+            }
+            Message::SplitKeypair => match self.split_keypair() {
+                Ok(()) => {
+                    self.load_shares();
+
+                    Task::done(app::Message::AddToast(Toast {
+                        title: "Split key".to_string(),
+                        body: "Hand each cosigner share to a different device, then delete the original key.".to_string(),
+                        status: ToastStatus::Good,
+                    }))
+                }
+                Err(err) => Task::done(app::Message::AddToast(Toast {
+                    title: "Failed to split key".to_string(),
+                    body: err.to_string(),
+                    status: ToastStatus::Bad,
+                })),
+            },
+            Message::ImportInputChanged(input) => {
+                self.import_input = input;
+
+                Task::none()
+            }
+            Message::ImportShare => {
+                let result = ThresholdShare::from_json(&self.import_input)
+                    .and_then(|share| self.db.save_threshold_share(&share));
+
+                match result {
+                    Ok(()) => {
+                        self.import_input.clear();
+                        self.load_shares();
+
+                        Task::batch([
+                            Task::done(app::Message::ThresholdSharesChanged),
+                            Task::done(app::Message::AddToast(Toast {
+                                title: "Imported share".to_string(),
+                                body: "Keystache will ask you to approve each event before helping to sign it.".to_string(),
+                                status: ToastStatus::Good,
+                            })),
+                        ])
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to import share".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::RemoveShare(public_key) => match self.db.remove_threshold_share(&public_key) {
+                Ok(()) => {
+                    self.load_shares();
+
+                    Task::batch([
+                        Task::done(app::Message::ThresholdSharesChanged),
+                        Task::done(app::Message::AddToast(Toast {
+                            title: "Removed share".to_string(),
+                            body: "Signing for the key now needs the other shares.".to_string(),
+                            status: ToastStatus::Good,
+                        })),
+                    ])
+                }
+                Err(err) => Task::done(app::Message::AddToast(Toast {
+                    title: "Failed to remove share".to_string(),
+                    body: err.to_string(),
+                    status: ToastStatus::Bad,
+                })),
+            },
+            Message::SigningShareSelected(npub) => {
+                self.selected_signing_npub_or = Some(npub);
+
+                Task::none()
+            }
+            Message::UnsignedEventEdited(action) => {
+                self.unsigned_event.perform(action);
+
+                Task::none()
+            }
+            Message::SignAndPublish => {
+                let share_and_event = self
+                    .selected_signing_share()
+                    .ok_or_else(|| anyhow::anyhow!("Select the key to sign as"))
+                    .and_then(|share| {
+                        Ok((share, UnsignedEvent::from_json(self.unsigned_event.text())?))
+                    });
+
+                let (share, unsigned_event) = match share_and_event {
+                    Ok(share_and_event) => share_and_event,
+                    Err(err) => {
+                        return Task::done(app::Message::AddToast(Toast {
+                            title: "Failed to sign event".to_string(),
+                            body: err.to_string(),
+                            status: ToastStatus::Bad,
+                        }));
+                    }
+                };
+
+                self.is_signing = true;
+                self.signed_event_json_or = None;
+
+                let nostr_module = self.nostr_module.clone();
+
+                Task::perform(
+                    async move {
+                        let event =
+                            threshold_key::sign_event(&nostr_module, &share, unsigned_event)
+                                .await?;
+
+                        nostr_module.publish(event.clone()).await?;
+
+                        Ok(event.as_json())
+                    },
+                    |result: anyhow::Result<String>| {
+                        threshold_keys_message(Message::SignedAndPublished(
+                            result.map_err(|err| err.to_string()),
+                        ))
+                    },
+                )
+            }
+            Message::SignedAndPublished(result) => {
+                self.is_signing = false;
+
+                match result {
+                    Ok(event_json) => {
+                        self.signed_event_json_or = Some(event_json);
+                        self.unsigned_event = text_editor::Content::new();
+
+                        Task::done(app::Message::AddToast(Toast {
+                            title: "Signed and published event".to_string(),
+                            body: "Enough cosigners approved the event.".to_string(),
+                            status: ToastStatus::Good,
+                        }))
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to sign event".to_string(),
+                        body: err,
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+        }
+    }
+
+    fn split_keypair(&mut self) -> anyhow::Result<()> {
+        let npub = self
+            .selected_keypair_npub_or
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Select the key to split"))?;
+
+        let threshold = self
+            .threshold_input
+            .trim()
+            .parse()
+            .map_err(|_| anyhow::anyhow!("The threshold must be a whole number"))?;
+
+        let cosigner_count = self
+            .cosigner_count_input
+            .trim()
+            .parse()
+            .map_err(|_| anyhow::anyhow!("The number of cosigners must be a whole number"))?;
+
+        let keypair = self.db.get_keypair(&npub)?;
+
+        let (coordinator_share, cosigner_shares) = threshold_key::split_secret_key(
+            &keypair.secret_key().into(),
+            threshold,
+            cosigner_count,
+        )?;
+
+        self.cosigner_share_jsons = cosigner_shares
+            .iter()
+            .map(ThresholdShare::to_json)
+            .collect::<anyhow::Result<_>>()?;
+
+        self.db.save_threshold_share(&coordinator_share)?;
+
+        self.split_npub_or = Some(npub);
+
+        Ok(())
+    }
+
+    fn load_shares(&mut self) {
+        self.loadable_shares = match self.db.list_threshold_shares() {
+            Ok(shares) => {
+                let coordinator_npubs = coordinator_npubs(&shares);
+
+                if !self
+                    .selected_signing_npub_or
+                    .as_ref()
+                    .is_some_and(|npub| coordinator_npubs.contains(npub))
+                {
+                    self.selected_signing_npub_or = coordinator_npubs.first().cloned();
+                }
+
+                Loadable::Loaded(shares)
+            }
+            Err(_err) => Loadable::Failed,
+        };
+    }
+
+    fn selected_signing_share(&self) -> Option<ThresholdShare> {
+        let npub = self.selected_signing_npub_or.as_ref()?;
+
+        self.loadable_shares
+            .as_ref_option()?
+            .iter()
+            .find(|share| {
+                share.is_coordinator
+                    && share
+                        .public_key
+                        .to_bech32()
+                        .is_ok_and(|share_npub| &share_npub == npub)
+            })
+            .cloned()
+    }
+
+    pub fn view<'a>(&'a self, connected_state: &ConnectedState) -> Column<'a, app::Message> {
+        let mut container = container("Threshold Keys")
+            .push(Text::new(
+                "Experimental. A threshold key's secret is split into shares held by different devices, and signing for it takes several of them, so losing one device doesn't give the key away. This device coordinates signing, and each cosigner approves every event before helping to sign it. Cosigners have to have Keystache open to answer.",
+            ))
+            .push(Text::new(
+                "Apps can't ask for signatures from threshold keys over NIP-46 yet. Sign events for them here instead.",
+            ))
+            .push(Text::new("Split a Key").size(25))
+            .push(pick_list(
+                self.keypair_npubs.as_slice(),
+                self.selected_keypair_npub_or.clone(),
+                |npub| threshold_keys_message(Message::KeypairSelected(npub)),
+            ))
+            .push(
+                row![
+                    text_input("Threshold", &self.threshold_input)
+                        .on_input(|input| threshold_keys_message(
+                            Message::ThresholdInputChanged(input)
+                        ))
+                        .padding(10)
+                        .size(20)
+                        .width(100.0),
+                    Text::new("shares needed to sign, out of this device and"),
+                    text_input("Cosigners", &self.cosigner_count_input)
+                        .on_input(|input| threshold_keys_message(
+                            Message::CosignerCountInputChanged(input)
+                        ))
+                        .padding(10)
+                        .size(20)
+                        .width(100.0),
+                    Text::new("cosigners"),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+            )
+            .push(
+                icon_button("Split Key", SvgIcon::Key, PaletteColor::Primary).on_press_maybe(
+                    self.selected_keypair_npub_or
+                        .is_some()
+                        .then(|| threshold_keys_message(Message::SplitKeypair)),
+                ),
+            );
+
+        if let Some(split_npub) = &self.split_npub_or {
+            container = container.push(Text::new(
+                "Copy each cosigner share into Keystache on a different device, under Keys > Threshold Keys. Anyone with a share can help sign, so send them privately. The shares aren't shown again once you leave this page.",
+            ));
+
+            for (index, share_json) in self.cosigner_share_jsons.iter().enumerate() {
+                container = container.push(
+                    row![
+                        Text::new(format!("Cosigner share {}", index + 1)),
+                        icon_button("Copy", SvgIcon::ContentCopy, PaletteColor::Background)
+                            .on_press(app::Message::CopyStringToClipboard(share_json.clone())),
+                    ]
+                    .spacing(10)
+                    .align_y(Alignment::Center),
+                );
+            }
+
+            container = container
+                .push(Text::new(
+                    "The original key can still sign on its own, so the split only protects it once the original is deleted.",
+                ))
+                .push(
+                    icon_button("Delete Original Key", SvgIcon::Delete, PaletteColor::Danger)
+                        .on_press_maybe(self.keypair_npubs.contains(split_npub).then(|| {
+                            app::Message::Routes(routes::Message::NostrKeypairsPage(
+                                super::Message::DeleteKeypair {
+                                    public_key: split_npub.clone(),
+                                },
+                            ))
+                        })),
+                );
+        }
+
+        container = container
+            .push(Text::new("Import a Cosigner Share").size(25))
+            .push(
+                text_input("Cosigner share", &self.import_input)
+                    .on_input(|input| threshold_keys_message(Message::ImportInputChanged(input)))
+                    .secure(true)
+                    .padding(10)
+                    .size(20),
+            )
+            .push(
+                icon_button("Import Share", SvgIcon::Save, PaletteColor::Primary).on_press_maybe(
+                    (!self.import_input.trim().is_empty())
+                        .then(|| threshold_keys_message(Message::ImportShare)),
+                ),
+            )
+            .push(Text::new("Shares on This Device").size(25));
+
+        match &self.loadable_shares {
+            Loadable::Loading => {
+                container = container.push(Text::new("Loading..."));
+            }
+            Loadable::Loaded(shares) if shares.is_empty() => {
+                container = container.push(Text::new("No shares"));
+            }
+            Loadable::Loaded(shares) => {
+                for share in shares {
+                    container = container.push(share_view(share));
+                }
+            }
+            Loadable::Failed => {
+                container = container.push(Text::new("Failed to load shares"));
+            }
+        }
+
+        container = container.push(Text::new("Cosigning Requests").size(25));
+
+        if connected_state.cosigning_requests.is_empty() {
+            container = container.push(Text::new("No requests waiting for approval"));
+        }
+
+        for request in &connected_state.cosigning_requests {
+            container = container.push(
+                Column::new()
+                    .push(Text::new(format!(
+                        "Sign a kind {} event as {}",
+                        request.event.kind,
+                        to_display_npub(&request.public_key)
+                    )))
+                    .push(Text::new(truncate_text(&request.event.content, 200, false)).size(14))
+                    .push(
+                        row![
+                            icon_button("Approve", SvgIcon::ThumbUp, PaletteColor::Primary)
+                                .on_press(app::Message::AnswerCosigningRequest {
+                                    session_id: request.session_id.clone(),
+                                    approve: true,
+                                }),
+                            icon_button("Refuse", SvgIcon::ThumbDown, PaletteColor::Danger)
+                                .on_press(app::Message::AnswerCosigningRequest {
+                                    session_id: request.session_id.clone(),
+                                    approve: false,
+                                }),
+                        ]
+                        .spacing(10),
+                    )
+                    .spacing(5),
+            );
+        }
+
+        let coordinator_npubs = self
+            .loadable_shares
+            .as_ref_option()
+            .map(|shares| coordinator_npubs(shares))
+            .unwrap_or_default();
+
+        container = container
+            .push(Text::new("Sign an Event").size(25))
+            .push(pick_list(
+                coordinator_npubs,
+                self.selected_signing_npub_or.clone(),
+                |npub| threshold_keys_message(Message::SigningShareSelected(npub)),
+            ))
+            .push(
+                text_editor(&self.unsigned_event)
+                    .placeholder("Unsigned event JSON")
+                    .on_action(|action| {
+                        threshold_keys_message(Message::UnsignedEventEdited(action))
+                    })
+                    .height(150)
+                    .padding(10),
+            )
+            .push(
+                icon_button(
+                    if self.is_signing {
+                        "Waiting for Cosigners..."
+                    } else {
+                        "Sign and Publish"
+                    },
+                    SvgIcon::Send,
+                    PaletteColor::Primary,
+                )
+                .on_press_maybe(
+                    (!self.is_signing && self.selected_signing_npub_or.is_some())
+                        .then(|| threshold_keys_message(Message::SignAndPublish)),
+                ),
+            )
+            .push_maybe(self.signed_event_json_or.as_ref().map(|event_json| {
+                icon_button(
+                    "Copy Signed Event",
+                    SvgIcon::ContentCopy,
+                    PaletteColor::Background,
+                )
+                .on_press(app::Message::CopyStringToClipboard(event_json.clone()))
+            }));
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(routes::Message::Navigate(RouteName::NostrKeypairs(
+                    SubrouteName::List,
+                ))),
+            ),
+        )
+    }
+}
+
+fn share_view<'a>(share: &ThresholdShare) -> Column<'a, app::Message> {
+    let role = if share.is_coordinator {
+        "Coordinator"
+    } else {
+        "Cosigner"
+    };
+
+    Column::new()
+        .push(Text::new(to_display_npub(&share.public_key)))
+        .push(
+            Text::new(format!(
+                "{role}, {} of {} shares needed to sign",
+                share.threshold(),
+                share.share_count()
+            ))
+            .size(14),
+        )
+        .push(
+            icon_button("Remove", SvgIcon::Delete, PaletteColor::Danger).on_press(
+                threshold_keys_message(Message::RemoveShare(share.public_key)),
+            ),
+        )
+        .spacing(5)
+}
+
+fn coordinator_npubs(shares: &[ThresholdShare]) -> Vec<String> {
+    shares
+        .iter()
+        .filter(|share| share.is_coordinator)
+        .filter_map(|share| share.public_key.to_bech32().ok())
+        .collect()
+}
+
+fn to_display_npub(public_key: &PublicKey) -> String {
+    public_key.to_bech32().map_or_else(
+        |_| public_key.to_string(),
+        |npub| truncate_text(&npub, 23, true),
+    )
+}
+
+fn threshold_keys_message(message: Message) -> app::Message {
+    app::Message::Routes(routes::Message::NostrKeypairsPage(
+        super::Message::ThresholdKeys(message),
+    ))
+}
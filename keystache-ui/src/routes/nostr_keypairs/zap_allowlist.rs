@@ -0,0 +1,317 @@
+use std::{collections::BTreeSet, sync::Arc};
+
+use chrono::Utc;
+use iced::{
+    widget::{checkbox, pick_list, row, Column, Text},
+    Alignment, Task,
+};
+use nostr_sdk::{FromBech32, PublicKey, ToBech32};
+
+use crate::{
+    app,
+    config::ClockFormat,
+    db::Database,
+    follows::{fetch_follow_list, FollowedKey, ZapAllowlistEntry},
+    nostr::NostrModule,
+    routes::{self, container, Loadable, RouteName},
+    ui_components::{icon_button, PaletteColor, SvgIcon, Toast, ToastStatus},
+    util::{format_time, truncate_text},
+};
+
+use super::{ConnectedState, SubrouteName};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    IdentitySelected(String),
+    FetchFollowList,
+    FollowListFetched(Result<Vec<FollowedKey>, String>),
+    AllowlistToggled(FollowedKey, bool),
+    AddAllFollowsToAllowlist,
+}
+
+pub struct Page {
+    db: Arc<Database>,
+    nostr_module: NostrModule,
+    clock_format: ClockFormat,
+    npubs: Vec<String>,
+    selected_npub_or: Option<String>,
+    // `None` until a follow list is fetched for the selected identity.
+    loadable_follows_or: Option<Loadable<Vec<FollowedKey>>>,
+    loadable_allowlist: Loadable<Vec<ZapAllowlistEntry>>,
+}
+
+impl Page {
+    pub fn new(connected_state: &ConnectedState) -> Self {
+        // TODO: Log a warning if the keys fail to load.
+        let npubs = connected_state
+            .db
+            .list_public_keys("", i64::MAX, 0)
+            .unwrap_or_default();
+
+        let mut page = Self {
+            db: connected_state.db.clone(),
+            nostr_module: connected_state.nostr_module.clone(),
+            clock_format: connected_state.settings.get().clock_format,
+            selected_npub_or: npubs.first().cloned(),
+            npubs,
+            loadable_follows_or: None,
+            loadable_allowlist: Loadable::Loading,
+        };
+
+        page.load_allowlist();
+
+        page
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::IdentitySelected(npub) => {
+                self.selected_npub_or = Some(npub);
+                self.loadable_follows_or = None;
+
+                Task::none()
+            }
+            Message::FetchFollowList => {
+                let Some(public_key) = self
+                    .selected_npub_or
+                    .as_ref()
+                    .and_then(|npub| PublicKey::from_bech32(npub).ok())
+                else {
+                    return Task::none();
+                };
+
+                self.loadable_follows_or = Some(Loadable::Loading);
+
+                let nostr_module = self.nostr_module.clone();
+
+                Task::perform(
+                    async move {
+                        fetch_follow_list(&nostr_module, public_key)
+                            .await
+                            .map_err(|err| err.to_string())
+                    },
+                    |result| zap_allowlist_message(Message::FollowListFetched(result)),
+                )
+            }
+            Message::FollowListFetched(result) => match result {
+                Ok(followed_keys) => {
+                    self.loadable_follows_or = Some(Loadable::Loaded(followed_keys));
+
+                    Task::none()
+                }
+                Err(err) => {
+                    self.loadable_follows_or = Some(Loadable::Failed);
+
+                    Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to fetch follow list".to_string(),
+                        body: err,
+                        status: ToastStatus::Bad,
+                    }))
+                }
+            },
+            Message::AllowlistToggled(followed_key, is_allowed) => {
+                let result = if is_allowed {
+                    self.db
+                        .add_to_zap_allowlist(std::slice::from_ref(&followed_key))
+                } else {
+                    self.db.remove_from_zap_allowlist(&followed_key.public_key)
+                };
+
+                self.load_allowlist();
+
+                match result {
+                    Ok(()) => Task::none(),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to update zap allowlist".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::AddAllFollowsToAllowlist => {
+                let Some(Loadable::Loaded(followed_keys)) = &self.loadable_follows_or else {
+                    return Task::none();
+                };
+
+                let result = self.db.add_to_zap_allowlist(followed_keys);
+                let followed_key_count = followed_keys.len();
+
+                self.load_allowlist();
+
+                match result {
+                    Ok(()) => Task::done(app::Message::AddToast(Toast {
+                        title: "Updated zap allowlist".to_string(),
+                        body: format!(
+                            "All {followed_key_count} followed keys are on the allowlist."
+                        ),
+                        status: ToastStatus::Good,
+                    })),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to update zap allowlist".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+        }
+    }
+
+    fn load_allowlist(&mut self) {
+        self.loadable_allowlist = match self.db.list_zap_allowlist() {
+            Ok(entries) => Loadable::Loaded(entries),
+            Err(_err) => Loadable::Failed,
+        };
+    }
+
+    pub fn view(&self) -> Column<app::Message> {
+        let allowed_public_keys: BTreeSet<PublicKey> = self
+            .loadable_allowlist
+            .as_ref_option()
+            .map(|entries| entries.iter().map(|entry| entry.public_key).collect())
+            .unwrap_or_default();
+
+        let is_fetching = matches!(self.loadable_follows_or, Some(Loadable::Loading));
+
+        let mut container = container("Zap Allowlist")
+            .push(Text::new(
+                "Apps you've approved before can sign zap requests to keys on the allowlist without asking. The zap's invoice is still paid from your wallet as usual.",
+            ))
+            .push(Text::new("Import From Follow List").size(25))
+            .push(
+                row![
+                    pick_list(
+                        self.npubs.as_slice(),
+                        self.selected_npub_or.clone(),
+                        |npub| zap_allowlist_message(Message::IdentitySelected(npub)),
+                    ),
+                    icon_button("Fetch Follow List", SvgIcon::Groups, PaletteColor::Primary)
+                        .on_press_maybe(
+                            (self.selected_npub_or.is_some() && !is_fetching)
+                                .then(|| zap_allowlist_message(Message::FetchFollowList)),
+                        ),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+            );
+
+        match &self.loadable_follows_or {
+            None => {}
+            Some(Loadable::Loading) => {
+                container = container.push(Text::new("Fetching follow list..."));
+            }
+            Some(Loadable::Loaded(followed_keys)) if followed_keys.is_empty() => {
+                container = container.push(Text::new("The follow list is empty"));
+            }
+            Some(Loadable::Loaded(followed_keys)) => {
+                for followed_key in followed_keys {
+                    let followed_key_clone = followed_key.clone();
+
+                    container = container.push(
+                        checkbox(
+                            display_name(
+                                &followed_key.public_key,
+                                followed_key.petname_or.as_deref(),
+                            ),
+                            allowed_public_keys.contains(&followed_key.public_key),
+                        )
+                        .on_toggle(move |is_allowed| {
+                            zap_allowlist_message(Message::AllowlistToggled(
+                                followed_key_clone.clone(),
+                                is_allowed,
+                            ))
+                        }),
+                    );
+                }
+
+                container = container.push(
+                    icon_button("Allow All", SvgIcon::ThumbUp, PaletteColor::Background)
+                        .on_press_maybe(
+                            followed_keys
+                                .iter()
+                                .any(|followed_key| {
+                                    !allowed_public_keys.contains(&followed_key.public_key)
+                                })
+                                .then(|| zap_allowlist_message(Message::AddAllFollowsToAllowlist)),
+                        ),
+                );
+            }
+            Some(Loadable::Failed) => {
+                container = container.push(Text::new("Failed to fetch follow list"));
+            }
+        }
+
+        container = container.push(Text::new("Allowlist").size(25));
+
+        match &self.loadable_allowlist {
+            Loadable::Loading => {
+                container = container.push(Text::new("Loading..."));
+            }
+            Loadable::Loaded(entries) if entries.is_empty() => {
+                container = container.push(Text::new("No keys on the allowlist"));
+            }
+            Loadable::Loaded(entries) => {
+                let now = Utc::now().naive_utc();
+
+                for entry in entries {
+                    container = container.push(
+                        row![
+                            Column::new()
+                                .push(Text::new(display_name(
+                                    &entry.public_key,
+                                    entry.petname_or.as_deref()
+                                )))
+                                .push(
+                                    Text::new(format!(
+                                        "Added {}",
+                                        format_time(entry.create_time, now, self.clock_format)
+                                    ))
+                                    .size(14)
+                                ),
+                            icon_button("Remove", SvgIcon::Delete, PaletteColor::Danger).on_press(
+                                zap_allowlist_message(Message::AllowlistToggled(
+                                    FollowedKey {
+                                        public_key: entry.public_key,
+                                        petname_or: entry.petname_or.clone(),
+                                    },
+                                    false,
+                                ))
+                            ),
+                        ]
+                        .spacing(10)
+                        .align_y(Alignment::Center),
+                    );
+                }
+            }
+            Loadable::Failed => {
+                container = container.push(Text::new("Failed to load zap allowlist"));
+            }
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(routes::Message::Navigate(RouteName::NostrKeypairs(
+                    SubrouteName::List,
+                ))),
+            ),
+        )
+    }
+}
+
+/// The key's petname followed by its truncated npub, or just the npub if it has no petname.
+fn display_name(public_key: &PublicKey, petname_or: Option<&str>) -> String {
+    let npub = public_key.to_bech32().map_or_else(
+        |_| public_key.to_string(),
+        |npub| truncate_text(&npub, 23, true),
+    );
+
+    match petname_or {
+        Some(petname) => format!("{petname} ({npub})"),
+        None => npub,
+    }
+}
+
+fn zap_allowlist_message(message: Message) -> app::Message {
+    app::Message::Routes(routes::Message::NostrKeypairsPage(
+        super::Message::ZapAllowlist(message),
+    ))
+}
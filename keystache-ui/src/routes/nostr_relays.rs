@@ -0,0 +1,1174 @@
+use std::{str::FromStr, time::Instant};
+
+use chrono::Utc;
+use iced::{
+    widget::{checkbox, pick_list, row, Column, Text},
+    Alignment, Color, Element, Task,
+};
+use nostr_relay_pool::RelayStatus;
+use nostr_sdk::{FromBech32, Keys, PublicKey, ToBech32, UnsignedEvent, Url};
+
+use crate::{
+    app,
+    nostr::{
+        is_same_relay, parse_relay_url, NostrModuleMessage, PublishOutcome, RelayLatency,
+        RelayReconnection,
+    },
+    policy::describe_event_kind,
+    relay_list::{
+        build_relay_list, fetch_relay_list, match_saved_relays, parse_relay_list, RelayListEntry,
+    },
+    ui_components::{
+        avatar, icon_button, load_more_controls, selectable_list, text_input, PaletteColor,
+        SelectableListMessage, SelectableListState, SvgIcon, Toast, ToastStatus, PAGE_SIZE,
+    },
+    util::{debounce_search_input, format_time, rank_by_fuzzy_match, truncate_text},
+};
+
+use super::{container, ConnectedState, Loadable, RouteName};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SaveRelay {
+        websocket_url: String,
+    },
+    ReplaceRelay {
+        old_websocket_url: String,
+        new_websocket_url: String,
+    },
+    SaveRelayWebsocketUrlInputChanged(String),
+    DeleteRelay {
+        websocket_url: String,
+    },
+    RelaySelection(SelectableListMessage<String>),
+    LoadMoreRelays,
+    SearchInputChanged(String),
+    SearchDebounced(String),
+    DeleteRelays {
+        websocket_urls: Vec<String>,
+    },
+    AutoReconnectToggled {
+        websocket_url: String,
+        auto_reconnect: bool,
+    },
+    ImportIdentitySelected(String),
+    FetchRelayList,
+    RelayListFetched(Result<Vec<RelayListEntry>, String>),
+    ImportRelays(Vec<RelayListEntry>),
+    RelayUsageToggled {
+        websocket_url: String,
+        read: bool,
+        write: bool,
+    },
+    PublishIdentitySelected(String),
+    ReviewRelayList,
+    ApproveRelayList,
+    RejectRelayList,
+    RelayListPublished(Result<PublishOutcome, String>),
+}
+
+pub struct Page {
+    pub connected_state: ConnectedState,
+    pub subroute: Subroute,
+}
+
+impl Page {
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::SaveRelay { websocket_url } => {
+                let task = match self.connected_state.db.save_relay(websocket_url.clone()) {
+                    Ok(()) => Task::done(app::Message::AddToast(Toast {
+                        title: "Saved relay".to_string(),
+                        body: "The relay was successfully saved.".to_string(),
+                        status: ToastStatus::Good,
+                    })),
+                    Err(_err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to save relay".to_string(),
+                        body: "The relay was not saved.".to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                };
+
+                if let Subroute::Add(add) = &mut self.subroute {
+                    add.existing_websocket_urls = list_websocket_urls(&self.connected_state);
+                }
+
+                Task::batch([
+                    task,
+                    Task::done(app::Message::NostrModule(
+                        NostrModuleMessage::ConnectToRelay(websocket_url),
+                    )),
+                ])
+            }
+            Message::ReplaceRelay {
+                old_websocket_url,
+                new_websocket_url,
+            } => {
+                let result = self
+                    .connected_state
+                    .db
+                    .remove_relay(&old_websocket_url)
+                    .and_then(|()| {
+                        self.connected_state
+                            .db
+                            .save_relay(new_websocket_url.clone())
+                    });
+
+                let task = match result {
+                    Ok(()) => Task::done(app::Message::AddToast(Toast {
+                        title: "Replaced relay".to_string(),
+                        body: format!("{old_websocket_url} was replaced with {new_websocket_url}."),
+                        status: ToastStatus::Good,
+                    })),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to replace relay".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                };
+
+                if let Subroute::Add(add) = &mut self.subroute {
+                    add.existing_websocket_urls = list_websocket_urls(&self.connected_state);
+                }
+
+                Task::batch([
+                    task,
+                    Task::done(app::Message::NostrModule(
+                        NostrModuleMessage::DisconnectFromRelay(old_websocket_url),
+                    )),
+                    Task::done(app::Message::NostrModule(
+                        NostrModuleMessage::ConnectToRelay(new_websocket_url),
+                    )),
+                ])
+            }
+            Message::SaveRelayWebsocketUrlInputChanged(new_websocket_url) => {
+                if let Subroute::Add(Add { websocket_url, .. }) = &mut self.subroute {
+                    *websocket_url = new_websocket_url;
+                }
+
+                Task::none()
+            }
+            Message::DeleteRelay { websocket_url } => {
+                let task = match self.connected_state.db.remove_relay(&websocket_url) {
+                    Ok(()) => Task::done(app::Message::AddToast(Toast {
+                        title: "Deleted relay".to_string(),
+                        body: "The relay was successfully deleted.".to_string(),
+                        status: ToastStatus::Good,
+                    })),
+                    Err(_err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to delete relay".to_string(),
+                        body: "The relay was not deleted.".to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                };
+
+                Task::batch([
+                    task,
+                    Task::done(app::Message::NostrModule(
+                        NostrModuleMessage::DisconnectFromRelay(websocket_url),
+                    )),
+                ])
+            }
+            Message::RelaySelection(selection_message) => {
+                if let Subroute::List(List { selection, .. }) = &mut self.subroute {
+                    selection.update(selection_message);
+                }
+
+                Task::none()
+            }
+            Message::LoadMoreRelays => {
+                if let Subroute::List(list) = &mut self.subroute {
+                    // Scrolling near the end asks for more rows each time, even once all are shown.
+                    // TODO: Log a warning if the relays fail to count.
+                    let count = self
+                        .connected_state
+                        .db
+                        .count_relays(&list.search_query)
+                        .unwrap_or_default();
+                    if list.row_limit < count {
+                        list.row_limit += PAGE_SIZE;
+                    }
+                }
+
+                Task::none()
+            }
+            Message::SearchInputChanged(input) => {
+                if let Subroute::List(List { search_input, .. }) = &mut self.subroute {
+                    search_input.clone_from(&input);
+                }
+
+                Task::perform(debounce_search_input(input), |input| {
+                    app::Message::Routes(super::Message::NostrRelaysPage(Message::SearchDebounced(
+                        input,
+                    )))
+                })
+            }
+            Message::SearchDebounced(input) => {
+                if let Subroute::List(list) = &mut self.subroute {
+                    // Only search once the user has stopped typing.
+                    if list.search_input == input {
+                        list.search_query = input;
+                        list.row_limit = PAGE_SIZE;
+                    }
+                }
+
+                Task::none()
+            }
+            Message::DeleteRelays { websocket_urls } => {
+                if let Subroute::List(List { selection, .. }) = &mut self.subroute {
+                    selection.take_selected_keys();
+                }
+
+                let task = match self.connected_state.db.remove_relays(&websocket_urls) {
+                    Ok(()) => Task::done(app::Message::AddToast(Toast {
+                        title: "Deleted relays".to_string(),
+                        body: format!("{} relays were successfully deleted.", websocket_urls.len()),
+                        status: ToastStatus::Good,
+                    })),
+                    Err(_err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to delete relays".to_string(),
+                        body: "The relays were not deleted.".to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                };
+
+                Task::batch(std::iter::once(task).chain(websocket_urls.into_iter().map(
+                    |websocket_url| {
+                        Task::done(app::Message::NostrModule(
+                            NostrModuleMessage::DisconnectFromRelay(websocket_url),
+                        ))
+                    },
+                )))
+            }
+            Message::AutoReconnectToggled {
+                websocket_url,
+                auto_reconnect,
+            } => match self
+                .connected_state
+                .db
+                .save_relay_auto_reconnect(&websocket_url, auto_reconnect)
+            {
+                Ok(()) => Task::none(),
+                Err(err) => Task::done(app::Message::AddToast(Toast {
+                    title: "Failed to save auto-reconnect".to_string(),
+                    body: err.to_string(),
+                    status: ToastStatus::Bad,
+                })),
+            },
+            Message::ImportIdentitySelected(npub) => {
+                if let Subroute::Import(import) = &mut self.subroute {
+                    import.selected_npub_or = Some(npub);
+                    import.loadable_relay_list_or = None;
+                }
+
+                Task::none()
+            }
+            Message::FetchRelayList => {
+                let Subroute::Import(import) = &mut self.subroute else {
+                    return Task::none();
+                };
+
+                let Some(public_key) = import
+                    .selected_npub_or
+                    .as_ref()
+                    .and_then(|npub| PublicKey::from_bech32(npub).ok())
+                else {
+                    return Task::none();
+                };
+
+                import.loadable_relay_list_or = Some(Loadable::Loading);
+
+                let nostr_module = self.connected_state.nostr_module.clone();
+
+                Task::perform(
+                    async move {
+                        fetch_relay_list(&nostr_module, public_key)
+                            .await
+                            .map_err(|err| err.to_string())
+                    },
+                    |result| {
+                        app::Message::Routes(super::Message::NostrRelaysPage(
+                            Message::RelayListFetched(result),
+                        ))
+                    },
+                )
+            }
+            Message::RelayListFetched(result) => {
+                let Subroute::Import(import) = &mut self.subroute else {
+                    return Task::none();
+                };
+
+                match result {
+                    Ok(entries) => {
+                        import.loadable_relay_list_or = Some(Loadable::Loaded(match_saved_relays(
+                            entries,
+                            &list_websocket_urls(&self.connected_state),
+                        )));
+
+                        Task::none()
+                    }
+                    Err(err) => {
+                        import.loadable_relay_list_or = Some(Loadable::Failed);
+
+                        Task::done(app::Message::AddToast(Toast {
+                            title: "Failed to fetch relay list".to_string(),
+                            body: err,
+                            status: ToastStatus::Bad,
+                        }))
+                    }
+                }
+            }
+            Message::RelayUsageToggled {
+                websocket_url,
+                read,
+                write,
+            } => match self
+                .connected_state
+                .db
+                .save_relay_usage(&websocket_url, read, write)
+            {
+                Ok(()) => Task::none(),
+                Err(err) => Task::done(app::Message::AddToast(Toast {
+                    title: "Failed to save relay".to_string(),
+                    body: err.to_string(),
+                    status: ToastStatus::Bad,
+                })),
+            },
+            Message::PublishIdentitySelected(npub) => {
+                if let Subroute::Publish(publish) = &mut self.subroute {
+                    publish.selected_npub_or = Some(npub);
+                    publish.pending_relay_list_or = None;
+                }
+
+                Task::none()
+            }
+            Message::ReviewRelayList => {
+                let Subroute::Publish(publish) = &mut self.subroute else {
+                    return Task::none();
+                };
+
+                let Some(public_key) = publish
+                    .selected_npub_or
+                    .as_ref()
+                    .and_then(|npub| PublicKey::from_bech32(npub).ok())
+                else {
+                    return Task::none();
+                };
+
+                match build_relay_list(public_key, &list_relay_list_entries(&self.connected_state))
+                {
+                    Ok(unsigned_event) => {
+                        publish.pending_relay_list_or = Some(unsigned_event);
+
+                        Task::none()
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to build relay list".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::ApproveRelayList => {
+                let Subroute::Publish(publish) = &mut self.subroute else {
+                    return Task::none();
+                };
+
+                let Some(unsigned_event) = publish.pending_relay_list_or.take() else {
+                    return Task::none();
+                };
+
+                let keys = match unsigned_event
+                    .pubkey
+                    .to_bech32()
+                    .map_err(anyhow::Error::from)
+                    .and_then(|npub| self.connected_state.db.get_keypair(&npub))
+                {
+                    Ok(keypair) => Keys::new(keypair.secret_key().into()),
+                    Err(err) => {
+                        return Task::done(app::Message::AddToast(Toast {
+                            title: "Failed to publish relay list".to_string(),
+                            body: err.to_string(),
+                            status: ToastStatus::Bad,
+                        }));
+                    }
+                };
+
+                publish.is_publishing = true;
+
+                let nostr_module = self.connected_state.nostr_module.clone();
+
+                Task::perform(
+                    async move {
+                        nostr_module
+                            .sign_and_publish(unsigned_event, &keys)
+                            .await
+                            .map_err(|err| err.to_string())
+                    },
+                    |result| {
+                        app::Message::Routes(super::Message::NostrRelaysPage(
+                            Message::RelayListPublished(result),
+                        ))
+                    },
+                )
+            }
+            Message::RejectRelayList => {
+                if let Subroute::Publish(publish) = &mut self.subroute {
+                    publish.pending_relay_list_or = None;
+                }
+
+                Task::none()
+            }
+            Message::RelayListPublished(result) => {
+                if let Subroute::Publish(publish) = &mut self.subroute {
+                    publish.is_publishing = false;
+                }
+
+                Task::done(app::Message::AddToast(match result {
+                    Ok(PublishOutcome::Sent) => Toast {
+                        title: "Published relay list".to_string(),
+                        body: "Your relay list was sent to your relays.".to_string(),
+                        status: ToastStatus::Good,
+                    },
+                    Ok(PublishOutcome::Queued) => Toast {
+                        title: "Relay list queued".to_string(),
+                        body: "No relay is connected, so your relay list will be sent once one is."
+                            .to_string(),
+                        status: ToastStatus::Neutral,
+                    },
+                    Err(err) => Toast {
+                        title: "Failed to publish relay list".to_string(),
+                        body: err,
+                        status: ToastStatus::Bad,
+                    },
+                }))
+            }
+            Message::ImportRelays(entries) => {
+                let task = match self.connected_state.db.import_relays(&entries) {
+                    Ok(()) => Task::done(app::Message::AddToast(Toast {
+                        title: "Imported relays".to_string(),
+                        body: format!(
+                            "{} relays were imported from your relay list.",
+                            entries.len()
+                        ),
+                        status: ToastStatus::Good,
+                    })),
+                    Err(err) => {
+                        return Task::done(app::Message::AddToast(Toast {
+                            title: "Failed to import relays".to_string(),
+                            body: err.to_string(),
+                            status: ToastStatus::Bad,
+                        }))
+                    }
+                };
+
+                // Relays that are already connected are left as they are.
+                Task::batch(
+                    [
+                        task,
+                        Task::done(app::Message::Routes(super::Message::Navigate(
+                            RouteName::NostrRelays(SubrouteName::List),
+                        ))),
+                    ]
+                    .into_iter()
+                    .chain(entries.into_iter().map(|entry| {
+                        Task::done(app::Message::NostrModule(
+                            NostrModuleMessage::ConnectToRelay(entry.websocket_url),
+                        ))
+                    })),
+                )
+            }
+        }
+    }
+
+    pub fn view<'a>(&self) -> Column<'a, app::Message> {
+        match &self.subroute {
+            Subroute::List(list) => list.view(&self.connected_state),
+            Subroute::Add(add) => add.view(),
+            Subroute::Subscriptions(subscriptions) => subscriptions.view(&self.connected_state),
+            Subroute::Import(import) => import.view(),
+            Subroute::Publish(publish) => publish.view(&self.connected_state),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubrouteName {
+    List,
+    Add,
+    Subscriptions,
+    Import,
+    Publish,
+}
+
+impl SubrouteName {
+    pub fn to_default_subroute(&self, connected_state: &ConnectedState) -> Subroute {
+        match self {
+            Self::List => Subroute::List(List {
+                selection: SelectableListState::default(),
+                row_limit: PAGE_SIZE,
+                search_input: String::new(),
+                search_query: String::new(),
+            }),
+            Self::Add => Subroute::Add(Add {
+                websocket_url: String::new(),
+                existing_websocket_urls: list_websocket_urls(connected_state),
+            }),
+            Self::Subscriptions => Subroute::Subscriptions(Subscriptions {}),
+            Self::Import => {
+                // TODO: Log a warning if the keys fail to load.
+                let npubs = connected_state
+                    .db
+                    .list_public_keys("", i64::MAX, 0)
+                    .unwrap_or_default();
+
+                Subroute::Import(Import {
+                    selected_npub_or: npubs.first().cloned(),
+                    npubs,
+                    loadable_relay_list_or: None,
+                })
+            }
+            Self::Publish => {
+                // TODO: Log a warning if the keys fail to load.
+                let npubs = connected_state
+                    .db
+                    .list_public_keys("", i64::MAX, 0)
+                    .unwrap_or_default();
+
+                Subroute::Publish(Publish {
+                    selected_npub_or: npubs.first().cloned(),
+                    npubs,
+                    pending_relay_list_or: None,
+                    is_publishing: false,
+                })
+            }
+        }
+    }
+}
+
+pub enum Subroute {
+    List(List),
+    Add(Add),
+    Subscriptions(Subscriptions),
+    Import(Import),
+    Publish(Publish),
+}
+
+impl Subroute {
+    pub fn to_name(&self) -> SubrouteName {
+        match self {
+            Self::List(_) => SubrouteName::List,
+            Self::Add(_) => SubrouteName::Add,
+            Self::Subscriptions(_) => SubrouteName::Subscriptions,
+            Self::Import(_) => SubrouteName::Import,
+            Self::Publish(_) => SubrouteName::Publish,
+        }
+    }
+}
+
+pub struct List {
+    selection: SelectableListState<String>,
+    // How many relays are shown. More are loaded as the user scrolls down.
+    row_limit: i64,
+    search_input: String,
+    // The search input, applied once the user stops typing.
+    search_query: String,
+}
+
+impl List {
+    fn view<'a>(&self, connected_state: &ConnectedState) -> Column<'a, app::Message> {
+        let Ok(count) = connected_state.db.count_relays(&self.search_query) else {
+            return container("Relays").push("Failed to load relays");
+        };
+
+        let Ok(relays) = connected_state
+            .db
+            .search_relays(&self.search_query, self.row_limit, 0)
+        else {
+            return container("Relays").push("Failed to load relays");
+        };
+
+        let shown_count = relays.len();
+
+        let relays = rank_by_fuzzy_match(&self.search_query, relays, |relay| {
+            relay.websocket_url.clone()
+        });
+
+        let now = Utc::now().naive_utc();
+        let clock_format = connected_state.settings.get().clock_format;
+
+        let mut rows = Vec::new();
+
+        for relay in relays {
+            let url_or = Url::from_str(&relay.websocket_url).ok();
+
+            let relay_state_or = url_or
+                .as_ref()
+                .and_then(|url| connected_state.nostr_state.relay_connections.get(url));
+
+            let relay_latency_or = url_or
+                .as_ref()
+                .and_then(|url| connected_state.nostr_state.relay_latencies.get(url));
+
+            let relay_reconnection_or = url_or
+                .as_ref()
+                .and_then(|url| connected_state.nostr_state.relay_reconnections.get(url));
+
+            let is_disconnected = relay_state_or.is_some_and(|relay_status| {
+                matches!(
+                    relay_status,
+                    RelayStatus::Disconnected | RelayStatus::Terminated
+                )
+            });
+
+            let relay_connection_color = relay_state_or.map_or_else(
+                || Color::from_rgb(0.3, 0.3, 0.3),
+                |relay_status| match relay_status {
+                    RelayStatus::Initialized => Color::from_rgb(0.3, 0.3, 0.3),
+                    RelayStatus::Pending | RelayStatus::Connecting => {
+                        Color::from_rgb(0.8, 0.8, 0.0)
+                    }
+                    RelayStatus::Connected => Color::from_rgb(0.0, 0.8, 0.0),
+                    RelayStatus::Disconnected | RelayStatus::Terminated => {
+                        Color::from_rgb(0.8, 0.0, 0.0)
+                    }
+                },
+            );
+
+            let row: Element<'a, app::Message> = row![
+                Text::new(truncate_text(&relay.websocket_url, 12, true))
+                    .size(20)
+                    .align_x(iced::alignment::Horizontal::Center),
+                icon_button("Delete", SvgIcon::Delete, PaletteColor::Danger).on_press(
+                    app::Message::Routes(super::Message::NostrRelaysPage(Message::DeleteRelay {
+                        websocket_url: relay.websocket_url.clone()
+                    }))
+                ),
+                SvgIcon::Circle.view(24.0, 24.0, relay_connection_color),
+                relay_latency_view(relay_latency_or),
+                Text::new(format!(
+                    "Added {}",
+                    format_time(relay.create_time, now, clock_format)
+                ))
+                .size(14),
+                checkbox("Read", relay.read).on_toggle({
+                    let websocket_url = relay.websocket_url.clone();
+                    let write = relay.write;
+                    move |read| {
+                        app::Message::Routes(super::Message::NostrRelaysPage(
+                            Message::RelayUsageToggled {
+                                websocket_url: websocket_url.clone(),
+                                read,
+                                write,
+                            },
+                        ))
+                    }
+                }),
+                checkbox("Write", relay.write).on_toggle({
+                    let websocket_url = relay.websocket_url.clone();
+                    let read = relay.read;
+                    move |write| {
+                        app::Message::Routes(super::Message::NostrRelaysPage(
+                            Message::RelayUsageToggled {
+                                websocket_url: websocket_url.clone(),
+                                read,
+                                write,
+                            },
+                        ))
+                    }
+                }),
+                checkbox("Auto-reconnect", relay.auto_reconnect).on_toggle({
+                    let websocket_url = relay.websocket_url.clone();
+                    move |auto_reconnect| {
+                        app::Message::Routes(super::Message::NostrRelaysPage(
+                            Message::AutoReconnectToggled {
+                                websocket_url: websocket_url.clone(),
+                                auto_reconnect,
+                            },
+                        ))
+                    }
+                }),
+            ]
+            .push_maybe(relay_reconnection_or.map(relay_reconnection_view))
+            // Relays without auto-reconnect stay disconnected until they're reconnected by hand.
+            .push_maybe((is_disconnected && !relay.auto_reconnect).then(|| {
+                icon_button("Reconnect", SvgIcon::Refresh, PaletteColor::Background).on_press(
+                    app::Message::NostrModule(NostrModuleMessage::ConnectToRelay(
+                        relay.websocket_url.clone(),
+                    )),
+                )
+            }))
+            .into();
+
+            rows.push((relay.websocket_url, row));
+        }
+
+        let mut container = container("Relays")
+            .push(
+                text_input("Search by URL", &self.search_input)
+                    .on_input(|input| {
+                        app::Message::Routes(super::Message::NostrRelaysPage(
+                            Message::SearchInputChanged(input),
+                        ))
+                    })
+                    .padding(10)
+                    .size(20),
+            )
+            .push(selectable_list(
+                rows,
+                &self.selection,
+                |selection_message| {
+                    app::Message::Routes(super::Message::NostrRelaysPage(Message::RelaySelection(
+                        selection_message,
+                    )))
+                },
+                "Delete",
+                |websocket_urls| {
+                    app::Message::Routes(super::Message::NostrRelaysPage(Message::DeleteRelays {
+                        websocket_urls,
+                    }))
+                },
+            ));
+
+        container = container.push(load_more_controls(
+            shown_count,
+            count,
+            app::Message::Routes(super::Message::NostrRelaysPage(Message::LoadMoreRelays)),
+        ));
+
+        container = container
+            .push(
+                icon_button("Add Relay", SvgIcon::Add, PaletteColor::Primary).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::NostrRelays(
+                        SubrouteName::Add,
+                    ))),
+                ),
+            )
+            .push(
+                icon_button(
+                    "Import from Nostr",
+                    SvgIcon::ArrowDownward,
+                    PaletteColor::Background,
+                )
+                .on_press(app::Message::Routes(super::Message::Navigate(
+                    RouteName::NostrRelays(SubrouteName::Import),
+                ))),
+            )
+            .push(
+                icon_button(
+                    "Publish to Nostr",
+                    SvgIcon::ArrowUpward,
+                    PaletteColor::Background,
+                )
+                .on_press(app::Message::Routes(super::Message::Navigate(
+                    RouteName::NostrRelays(SubrouteName::Publish),
+                ))),
+            )
+            .push(
+                icon_button("Subscriptions", SvgIcon::Hub, PaletteColor::Background).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::NostrRelays(
+                        SubrouteName::Subscriptions,
+                    ))),
+                ),
+            );
+
+        container
+    }
+}
+
+fn list_websocket_urls(connected_state: &ConnectedState) -> Vec<String> {
+    // TODO: Log a warning if the relays fail to load.
+    connected_state
+        .db
+        .iter_relays()
+        .collect::<anyhow::Result<Vec<_>>>()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|relay| relay.websocket_url)
+        .collect()
+}
+
+pub struct Add {
+    websocket_url: String,
+    // Used to catch relays that have already been added under a slightly different URL.
+    existing_websocket_urls: Vec<String>,
+}
+
+impl Add {
+    /// The saved relay that `self.websocket_url` points at, if any.
+    fn find_duplicate(&self) -> Option<&String> {
+        let url = parse_relay_url(&self.websocket_url).ok()?;
+
+        self.existing_websocket_urls.iter().find(|existing| {
+            parse_relay_url(existing).is_ok_and(|existing| is_same_relay(&existing, &url))
+        })
+    }
+
+    fn view<'a>(&self) -> Column<'a, app::Message> {
+        let websocket_url = self.websocket_url.trim().to_string();
+        let duplicate_or = self.find_duplicate();
+
+        let mut container = container("Add Relay")
+            .push(
+                text_input("Websocket URL", &self.websocket_url)
+                    .on_input(|input| {
+                        app::Message::Routes(super::Message::NostrRelaysPage(
+                            Message::SaveRelayWebsocketUrlInputChanged(input),
+                        ))
+                    })
+                    .padding(10)
+                    .size(30),
+            )
+            .push(
+                icon_button("Save", SvgIcon::Save, PaletteColor::Primary).on_press_maybe(
+                    duplicate_or.is_none().then(|| {
+                        app::Message::Routes(super::Message::NostrRelaysPage(Message::SaveRelay {
+                            websocket_url: websocket_url.clone(),
+                        }))
+                    }),
+                ),
+            );
+
+        if let Some(duplicate) = duplicate_or {
+            if *duplicate == websocket_url {
+                container = container.push(Text::new("This relay has already been added."));
+            } else {
+                container = container
+                    .push(Text::new(format!(
+                        "This looks like the same relay as {duplicate}, which has already been added. Keep it as it is, or replace it with the URL above."
+                    )))
+                    .push(
+                        icon_button("Keep Existing", SvgIcon::Close, PaletteColor::Background)
+                            .on_press(app::Message::Routes(
+                                super::Message::NostrRelaysPage(
+                                    Message::SaveRelayWebsocketUrlInputChanged(String::new()),
+                                ),
+                            )),
+                    )
+                    .push(
+                        icon_button("Replace Existing", SvgIcon::Save, PaletteColor::Primary)
+                            .on_press(app::Message::Routes(
+                                super::Message::NostrRelaysPage(Message::ReplaceRelay {
+                                    old_websocket_url: duplicate.clone(),
+                                    new_websocket_url: websocket_url,
+                                }),
+                            )),
+                    );
+            }
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(super::Message::Navigate(RouteName::NostrRelays(
+                    SubrouteName::List,
+                ))),
+            ),
+        )
+    }
+}
+
+pub struct Import {
+    npubs: Vec<String>,
+    selected_npub_or: Option<String>,
+    // `None` until a relay list is fetched for the selected keypair.
+    loadable_relay_list_or: Option<Loadable<Vec<RelayListEntry>>>,
+}
+
+impl Import {
+    fn view<'a>(&self) -> Column<'a, app::Message> {
+        let is_fetching = matches!(self.loadable_relay_list_or, Some(Loadable::Loading));
+
+        let mut container = container("Import Relays")
+            .push(Text::new(
+                "Fetch the relay list (NIP-65) that one of your keypairs has published, and add its relays here. Relays that are already saved are marked for reading and writing as the list says.",
+            ))
+            .push(
+                row![
+                    pick_list(self.npubs.clone(), self.selected_npub_or.clone(), |npub| {
+                        app::Message::Routes(super::Message::NostrRelaysPage(
+                            Message::ImportIdentitySelected(npub),
+                        ))
+                    }),
+                    icon_button("Fetch Relay List", SvgIcon::ArrowDownward, PaletteColor::Primary)
+                        .on_press_maybe((self.selected_npub_or.is_some() && !is_fetching).then(
+                            || {
+                                app::Message::Routes(super::Message::NostrRelaysPage(
+                                    Message::FetchRelayList,
+                                ))
+                            }
+                        )),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+            );
+
+        match &self.loadable_relay_list_or {
+            None => {}
+            Some(Loadable::Loading) => {
+                container = container.push(Text::new("Fetching relay list..."));
+            }
+            Some(Loadable::Loaded(entries)) if entries.is_empty() => {
+                container = container.push(Text::new("The relay list is empty"));
+            }
+            Some(Loadable::Loaded(entries)) => {
+                for entry in entries {
+                    container = container.push(Text::new(format!(
+                        "{} ({})",
+                        entry.websocket_url,
+                        relay_usage_label(entry.read, entry.write)
+                    )));
+                }
+
+                container = container.push(
+                    icon_button("Import Relays", SvgIcon::Save, PaletteColor::Primary).on_press(
+                        app::Message::Routes(super::Message::NostrRelaysPage(
+                            Message::ImportRelays(entries.clone()),
+                        )),
+                    ),
+                );
+            }
+            Some(Loadable::Failed) => {
+                container = container.push(Text::new("Failed to fetch relay list"));
+            }
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(super::Message::Navigate(RouteName::NostrRelays(
+                    SubrouteName::List,
+                ))),
+            ),
+        )
+    }
+}
+
+pub struct Publish {
+    npubs: Vec<String>,
+    selected_npub_or: Option<String>,
+    // The relay list waiting to be approved, before it's signed.
+    pending_relay_list_or: Option<UnsignedEvent>,
+    is_publishing: bool,
+}
+
+impl Publish {
+    fn view<'a>(&self, connected_state: &ConnectedState) -> Column<'a, app::Message> {
+        let mut container = container("Publish Relays").push(Text::new(
+            "Sign a relay list (NIP-65) with one of your keypairs and send it to your relays, so that apps know where to find your events. It replaces any relay list the keypair has published before.",
+        ));
+
+        // Like a NIP-46 request, the relay list is shown exactly as it will be signed.
+        if let Some(unsigned_event) = &self.pending_relay_list_or {
+            let entries = parse_relay_list(&unsigned_event.tags);
+
+            container = container
+                .push(Text::new("Sign this relay list?").size(25))
+                .push(
+                    row![
+                        avatar(&unsigned_event.pubkey, &connected_state.avatars, 48.0),
+                        Text::new(unsigned_event.pubkey.to_bech32().map_or_else(
+                            |_| unsigned_event.pubkey.to_string(),
+                            |npub| truncate_text(&npub, 24, true)
+                        )),
+                    ]
+                    .spacing(10)
+                    .align_y(Alignment::Center),
+                )
+                .push(Text::new(format!(
+                    "{}, listing {} relays",
+                    describe_event_kind(unsigned_event.kind),
+                    entries.len()
+                )));
+
+            for entry in entries {
+                container = container.push(Text::new(format!(
+                    "{} ({})",
+                    entry.websocket_url,
+                    relay_usage_label(entry.read, entry.write)
+                )));
+            }
+
+            return container.push(
+                row![
+                    icon_button("Approve", SvgIcon::ThumbUp, PaletteColor::Primary).on_press(
+                        app::Message::Routes(super::Message::NostrRelaysPage(
+                            Message::ApproveRelayList
+                        ))
+                    ),
+                    icon_button("Reject", SvgIcon::ThumbDown, PaletteColor::Primary).on_press(
+                        app::Message::Routes(super::Message::NostrRelaysPage(
+                            Message::RejectRelayList
+                        ))
+                    ),
+                ]
+                .spacing(20),
+            );
+        }
+
+        let entries = list_relay_list_entries(connected_state);
+
+        container = container.push(
+            row![
+                pick_list(self.npubs.clone(), self.selected_npub_or.clone(), |npub| {
+                    app::Message::Routes(super::Message::NostrRelaysPage(
+                        Message::PublishIdentitySelected(npub),
+                    ))
+                }),
+                icon_button("Review and Sign", SvgIcon::Key, PaletteColor::Primary).on_press_maybe(
+                    (self.selected_npub_or.is_some() && !self.is_publishing).then(|| {
+                        app::Message::Routes(super::Message::NostrRelaysPage(
+                            Message::ReviewRelayList,
+                        ))
+                    })
+                ),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+        );
+
+        if entries.iter().all(|entry| !entry.read && !entry.write) {
+            container = container.push(
+                Text::new(
+                    "No relay is used for reading or writing, so the relay list will be empty.",
+                )
+                .style(iced::widget::text::danger),
+            );
+        }
+
+        container
+            .push_maybe(
+                self.is_publishing
+                    .then(|| Text::new("Talking to your relays...")),
+            )
+            .push(
+                icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::NostrRelays(
+                        SubrouteName::List,
+                    ))),
+                ),
+            )
+    }
+}
+
+fn list_relay_list_entries(connected_state: &ConnectedState) -> Vec<RelayListEntry> {
+    // TODO: Log a warning if the relays fail to load.
+    connected_state
+        .db
+        .iter_relays()
+        .collect::<anyhow::Result<Vec<_>>>()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|relay| RelayListEntry {
+            websocket_url: relay.websocket_url,
+            read: relay.read,
+            write: relay.write,
+        })
+        .collect()
+}
+
+/// Describes what the user's relay list uses a relay for.
+const fn relay_usage_label(read: bool, write: bool) -> &'static str {
+    match (read, write) {
+        (true, true) => "Read and write",
+        (true, false) => "Read only",
+        (false, true) => "Write only",
+        (false, false) => "Unused",
+    }
+}
+
+pub struct Subscriptions {}
+
+impl Subscriptions {
+    // TODO: Remove this clippy allow.
+    #[allow(clippy::unused_self)]
+    fn view<'a>(&self, connected_state: &ConnectedState) -> Column<'a, app::Message> {
+        let mut container = container("Subscriptions").push(Text::new(
+            "Relay subscriptions that Keystache keeps open while it's unlocked. They're sent again whenever a relay reconnects.",
+        ));
+
+        let subscriptions = connected_state.nostr_module.list_subscriptions();
+
+        if subscriptions.is_empty() {
+            container = container.push(Text::new("No open subscriptions"));
+        }
+
+        let now = Instant::now();
+
+        for subscription in subscriptions {
+            let last_event = subscription.last_event_time_or.map_or_else(
+                || "No events yet".to_string(),
+                |last_event_time| {
+                    format!(
+                        "Last event {} seconds ago",
+                        now.saturating_duration_since(last_event_time).as_secs()
+                    )
+                },
+            );
+
+            container = container
+                .push(Text::new(subscription.purpose.to_string()).size(20))
+                .push(Text::new(format!(
+                    "Open for {} minutes, {} events ({:.1} per minute)",
+                    now.saturating_duration_since(subscription.start_time)
+                        .as_secs()
+                        / 60,
+                    subscription.event_count,
+                    subscription.events_per_minute(now)
+                )))
+                .push(Text::new(last_event))
+                .push_maybe((subscription.unverified_event_count > 0).then(|| {
+                    Text::new(format!(
+                        "{} events were dropped because their signature didn't match",
+                        subscription.unverified_event_count
+                    ))
+                    .style(iced::widget::text::danger)
+                }))
+                .push(Text::new(format!(
+                    "Resubscribed {} times after relays reconnected",
+                    subscription.resubscribe_count
+                )));
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(super::Message::Navigate(RouteName::NostrRelays(
+                    SubrouteName::List,
+                ))),
+            ),
+        )
+    }
+}
+
+/// Shows how quickly a relay has answered recent reads, so that slow relays can be pruned.
+fn relay_latency_view<'a>(relay_latency_or: Option<&RelayLatency>) -> Text<'a> {
+    let Some((relay_latency, average)) = relay_latency_or.and_then(|relay_latency| {
+        relay_latency
+            .average_or()
+            .map(|average| (relay_latency, average))
+    }) else {
+        return Text::new("No reads yet");
+    };
+
+    let timeout_count = relay_latency.timeout_count();
+
+    if timeout_count == 0 {
+        return Text::new(format!("{} ms", average.as_millis()));
+    }
+
+    Text::new(format!(
+        "{} ms, {timeout_count} of the last {} reads timed out",
+        average.as_millis(),
+        relay_latency.sample_count()
+    ))
+    .style(iced::widget::text::danger)
+}
+
+/// Shows how many times a relay that lost its connection has been reconnected to.
+fn relay_reconnection_view<'a>(relay_reconnection: &RelayReconnection) -> Text<'a> {
+    let text = match relay_reconnection.attempt_count {
+        0 => "Lost connection, reconnecting soon".to_string(),
+        1 => "Reconnecting, 1 attempt so far".to_string(),
+        attempt_count => format!("Reconnecting, {attempt_count} attempts so far"),
+    };
+
+    Text::new(text).size(14).style(iced::widget::text::danger)
+}
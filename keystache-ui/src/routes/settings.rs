@@ -0,0 +1,2591 @@
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use iced::{
+    widget::{checkbox, pick_list, row, Column, Text},
+    Task,
+};
+use nostr_sdk::{FromBech32, Keys, Kind, PublicKey, ToBech32};
+
+use crate::{
+    app,
+    app_backup::{publish_app_backup, restore_app_backup},
+    backup::{BackupSettings, BackupStatus},
+    config::{ClockFormat, SettingKind, Settings, SHOW_RELEASE_NOTES_KEY},
+    db::{Database, DEFAULT_BUSY_TIMEOUT},
+    fedimint::PaymentSimulation,
+    keychain,
+    legacy::{self, LegacyDatabase},
+    maintenance::{format_size, DATABASE_MAINTENANCE_INTERVAL},
+    metrics::{AppSigningStats, SLOW_NIP46_REQUEST_THRESHOLD},
+    nostr::DataUsage,
+    policy::{describe_event_kind, KindPolicyDecision, Nip46App, Nip46KindPolicy, Nip46Rejection},
+    privacy::InvoicePrivacy,
+    release_notes::{release_notes_up_to, ReleaseNotes},
+    ui_components::{
+        icon_button, mini_icon_button, text_input, PaletteColor, SvgIcon, Toast, ToastStatus,
+    },
+    util::{format_time, truncate_text},
+};
+
+use super::{container, ConnectedState, Loadable, RouteName};
+
+// Waiting longer than this would leave the whole app unresponsive for too long.
+const MAX_BUSY_TIMEOUT_SECS: u64 = 120;
+
+// How many of the most recent rejected NIP-46 requests are shown on the Connected Apps page.
+const MAX_SHOWN_NIP46_REJECTIONS: i64 = 50;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    ChangePasswordCurrentPasswordInputChanged(String),
+    ChangePasswordNewPasswordInputChanged(String),
+    ChangePasswordNewPasswordConfirmationInputChanged(String),
+    ChangePasswordSubmit {
+        current_password: String,
+        new_password: String,
+    },
+
+    SettingInputChanged {
+        key: &'static str,
+        input: String,
+    },
+    SaveSetting {
+        key: &'static str,
+        value: String,
+    },
+
+    KeychainUnlockToggled(bool),
+    ForgetKeychainPassword,
+    DeviceBindingPasswordInputChanged(String),
+    BindDatabaseToDevice(String),
+    UnbindDatabaseFromDevice(String),
+
+    WalletSeedPasswordInputChanged(String),
+    RevealWalletSeed(String),
+    HideWalletSeed,
+
+    AutoApprovePublicKeyReadsToggled(bool),
+    AppAutoApprovePublicKeyReadsToggled(PublicKey, bool),
+    ForgetApp(PublicKey),
+    AppBackupIdentitySelected(String),
+    BackUpApps(String),
+    AppsBackedUp(Result<(), String>),
+    RestoreApps(String),
+    AppsRestored(Result<usize, String>),
+
+    KindPolicyAppSelected(String),
+    KindPolicyKindInputChanged(String),
+    KindPolicyDecisionSelected(KindPolicyDecision),
+    SaveKindPolicy(PublicKey, Kind, KindPolicyDecision),
+    RevokeKindPolicy(PublicKey, Kind),
+    RevokeApprovalGrant(PublicKey),
+    AppRememberConversationsToggled(PublicKey, bool),
+
+    InvoicePrivacySelected(InvoicePrivacy),
+    PaymentSimulationSelected(PaymentSimulation),
+    BusyTimeoutInputChanged(String),
+    SaveBusyTimeout(Duration),
+
+    BackupDirectoryInputChanged(String),
+    BackupRotationCountInputChanged(String),
+    SaveBackupSettings(BackupSettings),
+    BackupStatusChanged,
+    ExportPathInputChanged(String),
+    ExportPasswordInputChanged(String),
+    ExportPasswordConfirmationInputChanged(String),
+    ExportArchive {
+        path: PathBuf,
+        password: String,
+    },
+    ArchiveExported(Result<(), String>),
+
+    VacuumDatabase,
+
+    LegacyEncryptionKeyInputChanged(String),
+    ImportLegacyDatabase {
+        path: PathBuf,
+        encryption_key: String,
+    },
+}
+
+pub struct Page {
+    pub connected_state: ConnectedState,
+    pub subroute: Subroute,
+}
+
+impl Page {
+    // TODO: Remove this clippy allow.
+    #[allow(clippy::too_many_lines)]
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::ChangePasswordCurrentPasswordInputChanged(input) => {
+                if let Subroute::ChangePassword(change_password) = &mut self.subroute {
+                    change_password.current_password_input = input;
+                }
+
+                Task::none()
+            }
+            Message::ChangePasswordNewPasswordInputChanged(input) => {
+                if let Subroute::ChangePassword(change_password) = &mut self.subroute {
+                    change_password.new_password_input = input;
+                }
+
+                Task::none()
+            }
+            Message::ChangePasswordNewPasswordConfirmationInputChanged(input) => {
+                if let Subroute::ChangePassword(change_password) = &mut self.subroute {
+                    change_password.new_password_confirmation_input = input;
+                }
+
+                Task::none()
+            }
+            Message::ChangePasswordSubmit {
+                current_password,
+                new_password,
+            } => {
+                match self
+                    .connected_state
+                    .db
+                    .change_password(&current_password, &new_password)
+                {
+                    Ok(()) => {
+                        // Keep the saved password in sync, so that keychain unlock keeps working.
+                        let body = if keychain::has_password()
+                            && keychain::save_password(&new_password).is_err()
+                        {
+                            let _ = keychain::delete_password();
+
+                            "Your password has been changed. It couldn't be updated in the system keychain, so it has been removed from there."
+                        } else {
+                            "Your password has been changed."
+                        };
+
+                        Task::done(app::Message::Routes(super::Message::Navigate(
+                            RouteName::Settings(SubrouteName::Main),
+                        )))
+                        .chain(Task::done(app::Message::AddToast(
+                            Toast {
+                                title: "Password changed".to_string(),
+                                body: body.to_string(),
+                                status: ToastStatus::Good,
+                            },
+                        )))
+                    }
+                    Err(_err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to change password".to_string(),
+                        body: "Check that you entered your current password correctly.".to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::SettingInputChanged { key, input } => {
+                if let Subroute::General(general) = &mut self.subroute {
+                    general.inputs.insert(key, input);
+                }
+
+                Task::none()
+            }
+            Message::SaveSetting { key, value } => {
+                let result = self
+                    .connected_state
+                    .settings
+                    .get()
+                    .with_field(key, &value)
+                    .and_then(|settings| {
+                        self.connected_state.db.save_settings(&settings)?;
+                        Ok(settings)
+                    });
+
+                match result {
+                    Ok(settings) => {
+                        // Subsystems pick up the new settings from here.
+                        self.connected_state.settings.set(settings);
+
+                        match &mut self.subroute {
+                            Subroute::General(general) => {
+                                *general = General::new(&self.connected_state);
+                            }
+                            Subroute::WhatsNew(whats_new) => {
+                                *whats_new = WhatsNew::new(&self.connected_state);
+                            }
+                            _ => {}
+                        }
+
+                        Task::none()
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to save setting".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::KeychainUnlockToggled(is_enabled) => {
+                let result = self
+                    .connected_state
+                    .db
+                    .save_keychain_unlock_enabled(is_enabled)
+                    .and_then(|()| {
+                        if is_enabled {
+                            Ok(())
+                        } else {
+                            keychain::delete_password()
+                        }
+                    });
+
+                if let Subroute::Security(security) = &mut self.subroute {
+                    *security = Security::new(&self.connected_state);
+                }
+
+                match result {
+                    Ok(()) => Task::none(),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to save security setting".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::DeviceBindingPasswordInputChanged(input) => {
+                if let Subroute::Security(security) = &mut self.subroute {
+                    security.device_binding_password_input = input;
+                }
+
+                Task::none()
+            }
+            Message::BindDatabaseToDevice(password) => {
+                let result = self.connected_state.db.bind_to_device(&password);
+
+                if let Subroute::Security(security) = &mut self.subroute {
+                    *security = Security::new(&self.connected_state);
+                }
+
+                Task::done(app::Message::AddToast(match result {
+                    Ok(()) => Toast {
+                        title: "Bound to this device".to_string(),
+                        body: "Your database can now only be unlocked on this device.".to_string(),
+                        status: ToastStatus::Good,
+                    },
+                    Err(err) => Toast {
+                        title: "Failed to bind to this device".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    },
+                }))
+            }
+            Message::UnbindDatabaseFromDevice(password) => {
+                let result = self.connected_state.db.unbind_from_device(&password);
+
+                if let Subroute::Security(security) = &mut self.subroute {
+                    *security = Security::new(&self.connected_state);
+                }
+
+                Task::done(app::Message::AddToast(match result {
+                    Ok(()) => Toast {
+                        title: "Unbound from this device".to_string(),
+                        body: "Your database can now be unlocked with your password alone."
+                            .to_string(),
+                        status: ToastStatus::Good,
+                    },
+                    Err(err) => Toast {
+                        title: "Failed to unbind from this device".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    },
+                }))
+            }
+            Message::WalletSeedPasswordInputChanged(input) => {
+                if let Subroute::WalletSeed(wallet_seed) = &mut self.subroute {
+                    wallet_seed.password_input = input;
+                }
+
+                Task::none()
+            }
+            Message::RevealWalletSeed(password) => {
+                match self.connected_state.db.reveal_wallet_mnemonic(&password) {
+                    Ok(Some(mnemonic)) => {
+                        if let Subroute::WalletSeed(wallet_seed) = &mut self.subroute {
+                            wallet_seed.password_input.clear();
+                            wallet_seed.revealed_words_or =
+                                Some(mnemonic.word_iter().map(str::to_string).collect());
+                        }
+
+                        Task::none()
+                    }
+                    Ok(None) => Task::done(app::Message::AddToast(Toast {
+                        title: "No wallet seed".to_string(),
+                        body: "This wallet was created before Keystache generated seeds."
+                            .to_string(),
+                        status: ToastStatus::Neutral,
+                    })),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to show wallet seed".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::HideWalletSeed => {
+                if let Subroute::WalletSeed(wallet_seed) = &mut self.subroute {
+                    *wallet_seed = WalletSeed::new(&self.connected_state);
+                }
+
+                Task::none()
+            }
+            Message::ForgetKeychainPassword => {
+                let result = keychain::delete_password();
+
+                if let Subroute::Security(security) = &mut self.subroute {
+                    *security = Security::new(&self.connected_state);
+                }
+
+                match result {
+                    Ok(()) => Task::done(app::Message::AddToast(Toast {
+                        title: "Removed saved password".to_string(),
+                        body: "Your password has been removed from the system keychain."
+                            .to_string(),
+                        status: ToastStatus::Good,
+                    })),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to remove saved password".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::AutoApprovePublicKeyReadsToggled(is_enabled) => {
+                let result = self
+                    .connected_state
+                    .db
+                    .save_auto_approve_public_key_reads(is_enabled);
+
+                if let Subroute::ConnectedApps(connected_apps) = &mut self.subroute {
+                    *connected_apps = ConnectedApps::new(&self.connected_state);
+                }
+
+                match result {
+                    Ok(()) => Task::none(),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to save public key request setting".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::AppAutoApprovePublicKeyReadsToggled(public_key, is_enabled) => {
+                let result = self
+                    .connected_state
+                    .db
+                    .save_nip46_app_auto_approve_public_key_reads(&public_key, is_enabled);
+
+                if let Subroute::ConnectedApps(connected_apps) = &mut self.subroute {
+                    *connected_apps = ConnectedApps::new(&self.connected_state);
+                }
+
+                match result {
+                    Ok(()) => Task::none(),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to save public key request setting".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::ForgetApp(public_key) => {
+                let result = self.connected_state.db.remove_nip46_app(&public_key);
+
+                self.connected_state
+                    .remembered_conversations
+                    .forget_app(&public_key);
+
+                if let Subroute::ConnectedApps(connected_apps) = &mut self.subroute {
+                    *connected_apps = ConnectedApps::new(&self.connected_state);
+                }
+
+                match result {
+                    Ok(()) => Task::done(app::Message::AddToast(Toast {
+                        title: "Forgot app".to_string(),
+                        body: "Its next request will need your approval.".to_string(),
+                        status: ToastStatus::Good,
+                    })),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to forget app".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::AppBackupIdentitySelected(npub) => {
+                if let Subroute::ConnectedApps(connected_apps) = &mut self.subroute {
+                    connected_apps.selected_backup_npub_or = Some(npub);
+                }
+
+                Task::none()
+            }
+            Message::BackUpApps(npub) => {
+                let keys = match self.connected_state.db.get_keypair(&npub) {
+                    Ok(keypair) => Keys::new(keypair.secret_key().into()),
+                    Err(err) => {
+                        return Task::done(app::Message::AddToast(Toast {
+                            title: "Failed to back up apps".to_string(),
+                            body: err.to_string(),
+                            status: ToastStatus::Bad,
+                        }));
+                    }
+                };
+
+                if let Subroute::ConnectedApps(connected_apps) = &mut self.subroute {
+                    connected_apps.is_app_backup_in_progress = true;
+                }
+
+                let nostr_module = self.connected_state.nostr_module.clone();
+                let db = self.connected_state.db.clone();
+
+                Task::perform(
+                    async move {
+                        publish_app_backup(&nostr_module, &db, &keys)
+                            .await
+                            .map_err(|err| err.to_string())
+                    },
+                    |result| {
+                        app::Message::Routes(super::Message::SettingsPage(Message::AppsBackedUp(
+                            result,
+                        )))
+                    },
+                )
+            }
+            Message::AppsBackedUp(result) => {
+                if let Subroute::ConnectedApps(connected_apps) = &mut self.subroute {
+                    connected_apps.is_app_backup_in_progress = false;
+                }
+
+                Task::done(app::Message::AddToast(match result {
+                    Ok(()) => Toast {
+                        title: "Backed up apps".to_string(),
+                        body: "Your connected apps were saved to your relays.".to_string(),
+                        status: ToastStatus::Good,
+                    },
+                    Err(err) => Toast {
+                        title: "Failed to back up apps".to_string(),
+                        body: err,
+                        status: ToastStatus::Bad,
+                    },
+                }))
+            }
+            Message::RestoreApps(npub) => {
+                let keys = match self.connected_state.db.get_keypair(&npub) {
+                    Ok(keypair) => Keys::new(keypair.secret_key().into()),
+                    Err(err) => {
+                        return Task::done(app::Message::AddToast(Toast {
+                            title: "Failed to restore apps".to_string(),
+                            body: err.to_string(),
+                            status: ToastStatus::Bad,
+                        }));
+                    }
+                };
+
+                if let Subroute::ConnectedApps(connected_apps) = &mut self.subroute {
+                    connected_apps.is_app_backup_in_progress = true;
+                }
+
+                let nostr_module = self.connected_state.nostr_module.clone();
+                let db = self.connected_state.db.clone();
+
+                Task::perform(
+                    async move {
+                        restore_app_backup(&nostr_module, &db, &keys)
+                            .await
+                            .map_err(|err| err.to_string())
+                    },
+                    |result| {
+                        app::Message::Routes(super::Message::SettingsPage(Message::AppsRestored(
+                            result,
+                        )))
+                    },
+                )
+            }
+            Message::AppsRestored(result) => {
+                if let Subroute::ConnectedApps(connected_apps) = &mut self.subroute {
+                    let selected_backup_npub_or = connected_apps.selected_backup_npub_or.take();
+
+                    *connected_apps = ConnectedApps::new(&self.connected_state);
+                    connected_apps.selected_backup_npub_or = selected_backup_npub_or;
+                }
+
+                Task::done(app::Message::AddToast(match result {
+                    Ok(app_count) => Toast {
+                        title: "Restored apps".to_string(),
+                        body: format!(
+                            "Restored {app_count} apps. They won't need your approval again."
+                        ),
+                        status: ToastStatus::Good,
+                    },
+                    Err(err) => Toast {
+                        title: "Failed to restore apps".to_string(),
+                        body: err,
+                        status: ToastStatus::Bad,
+                    },
+                }))
+            }
+            Message::KindPolicyAppSelected(npub) => {
+                if let Subroute::AppPermissions(app_permissions) = &mut self.subroute {
+                    app_permissions.selected_app_npub_or = Some(npub);
+                }
+
+                Task::none()
+            }
+            Message::KindPolicyKindInputChanged(input) => {
+                if let Subroute::AppPermissions(app_permissions) = &mut self.subroute {
+                    app_permissions.kind_input = input;
+                }
+
+                Task::none()
+            }
+            Message::KindPolicyDecisionSelected(decision) => {
+                if let Subroute::AppPermissions(app_permissions) = &mut self.subroute {
+                    app_permissions.decision_or = Some(decision);
+                }
+
+                Task::none()
+            }
+            Message::SaveKindPolicy(public_key, kind, decision) => {
+                let result =
+                    self.connected_state
+                        .db
+                        .save_nip46_kind_policy(&public_key, kind, decision);
+
+                if let Subroute::AppPermissions(app_permissions) = &mut self.subroute {
+                    *app_permissions = AppPermissions::new(&self.connected_state);
+                }
+
+                match result {
+                    Ok(()) => Task::none(),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to save rule".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::RevokeKindPolicy(public_key, kind) => {
+                let result = self
+                    .connected_state
+                    .db
+                    .remove_nip46_kind_policy(&public_key, kind);
+
+                if let Subroute::AppPermissions(app_permissions) = &mut self.subroute {
+                    *app_permissions = AppPermissions::new(&self.connected_state);
+                }
+
+                match result {
+                    Ok(()) => Task::none(),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to revoke rule".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::RevokeApprovalGrant(public_key) => {
+                self.connected_state.approval_grants.revoke(&public_key);
+
+                if let Subroute::AppPermissions(app_permissions) = &mut self.subroute {
+                    *app_permissions = AppPermissions::new(&self.connected_state);
+                }
+
+                Task::none()
+            }
+            Message::AppRememberConversationsToggled(public_key, is_enabled) => {
+                let result = self
+                    .connected_state
+                    .db
+                    .save_nip46_app_remember_conversations(&public_key, is_enabled);
+
+                // Conversations approved before are forgotten, so turning this
+                // back on doesn't bring back approvals that were meant to end.
+                if !is_enabled {
+                    self.connected_state
+                        .remembered_conversations
+                        .forget_app(&public_key);
+                }
+
+                if let Subroute::AppPermissions(app_permissions) = &mut self.subroute {
+                    *app_permissions = AppPermissions::new(&self.connected_state);
+                }
+
+                match result {
+                    Ok(()) => Task::none(),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to save conversation setting".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::InvoicePrivacySelected(invoice_privacy) => {
+                match self
+                    .connected_state
+                    .db
+                    .save_invoice_privacy(invoice_privacy)
+                {
+                    Ok(()) => {
+                        if let Subroute::Privacy(privacy) = &mut self.subroute {
+                            privacy.invoice_privacy_or = Some(invoice_privacy);
+                        }
+
+                        Task::none()
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to save privacy setting".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::PaymentSimulationSelected(payment_simulation) => {
+                match self
+                    .connected_state
+                    .db
+                    .save_payment_simulation(payment_simulation)
+                {
+                    Ok(()) => {
+                        self.connected_state
+                            .wallet
+                            .set_payment_simulation(payment_simulation);
+
+                        if let Subroute::Developer(developer) = &mut self.subroute {
+                            developer.payment_simulation = payment_simulation;
+                        }
+
+                        Task::none()
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to save developer setting".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::BusyTimeoutInputChanged(input) => {
+                if let Subroute::Developer(developer) = &mut self.subroute {
+                    developer.busy_timeout_input = input;
+                }
+
+                Task::none()
+            }
+            Message::SaveBusyTimeout(busy_timeout) => {
+                match self.connected_state.db.save_busy_timeout(busy_timeout) {
+                    Ok(()) => Task::done(app::Message::AddToast(Toast {
+                        title: "Saved database timeout".to_string(),
+                        body: format!(
+                            "The database now waits up to {} seconds when it's busy.",
+                            busy_timeout.as_secs()
+                        ),
+                        status: ToastStatus::Good,
+                    })),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to save developer setting".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::BackupDirectoryInputChanged(input) => {
+                if let Subroute::Backup(backup) = &mut self.subroute {
+                    backup.directory_input = input;
+                }
+
+                Task::none()
+            }
+            Message::BackupRotationCountInputChanged(input) => {
+                if let Subroute::Backup(backup) = &mut self.subroute {
+                    backup.rotation_count_input = input;
+                }
+
+                Task::none()
+            }
+            Message::SaveBackupSettings(backup_settings) => {
+                match self
+                    .connected_state
+                    .db
+                    .save_backup_settings(&backup_settings)
+                {
+                    Ok(()) => {
+                        self.connected_state.backup_settings = backup_settings.clone();
+
+                        if let Subroute::Backup(backup) = &mut self.subroute {
+                            backup.saved_settings = backup_settings;
+                        }
+
+                        Task::done(app::Message::AddToast(Toast {
+                            title: "Saved backup settings".to_string(),
+                            body: "Your backup settings have been saved.".to_string(),
+                            status: ToastStatus::Good,
+                        }))
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to save backup settings".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::BackupStatusChanged => {
+                if let Subroute::Backup(backup) = &mut self.subroute {
+                    // TODO: Log a warning if the backup status fails to load.
+                    backup.status = self
+                        .connected_state
+                        .db
+                        .get_backup_status()
+                        .unwrap_or_default();
+                }
+
+                Task::none()
+            }
+            Message::ExportPathInputChanged(input) => {
+                if let Subroute::Backup(backup) = &mut self.subroute {
+                    backup.export_path_input = input;
+                }
+
+                Task::none()
+            }
+            Message::ExportPasswordInputChanged(input) => {
+                if let Subroute::Backup(backup) = &mut self.subroute {
+                    backup.export_password_input = input;
+                }
+
+                Task::none()
+            }
+            Message::ExportPasswordConfirmationInputChanged(input) => {
+                if let Subroute::Backup(backup) = &mut self.subroute {
+                    backup.export_password_confirmation_input = input;
+                }
+
+                Task::none()
+            }
+            Message::ExportArchive { path, password } => {
+                if let Subroute::Backup(backup) = &mut self.subroute {
+                    backup.is_export_in_progress = true;
+                }
+
+                let db = self.connected_state.db.clone();
+                let wallet = self.connected_state.wallet.clone();
+
+                Task::perform(
+                    async move {
+                        let invite_codes = wallet.list_invite_codes().await;
+
+                        db.export_archive(&path, &password, &invite_codes)
+                            .map_err(|err| err.to_string())
+                    },
+                    |result| {
+                        app::Message::Routes(super::Message::SettingsPage(
+                            Message::ArchiveExported(result),
+                        ))
+                    },
+                )
+            }
+            Message::ArchiveExported(result) => {
+                if let Subroute::Backup(backup) = &mut self.subroute {
+                    backup.is_export_in_progress = false;
+
+                    if result.is_ok() {
+                        backup.export_password_input.clear();
+                        backup.export_password_confirmation_input.clear();
+                    }
+                }
+
+                Task::done(app::Message::AddToast(match result {
+                    Ok(()) => Toast {
+                        title: "Exported backup".to_string(),
+                        body: "It can be restored when setting up Keystache on a new device."
+                            .to_string(),
+                        status: ToastStatus::Good,
+                    },
+                    Err(err) => Toast {
+                        title: "Failed to export backup".to_string(),
+                        body: err,
+                        status: ToastStatus::Bad,
+                    },
+                }))
+            }
+            Message::VacuumDatabase => {
+                let size_before_or = self.connected_state.db.get_size_on_disk().ok();
+
+                match self.connected_state.db.vacuum() {
+                    Ok(()) => {
+                        let size_after_or = self.connected_state.db.get_size_on_disk().ok();
+
+                        let body = match (size_before_or, size_after_or) {
+                            (Some(size_before), Some(size_after)) => format!(
+                                "Reclaimed {} of disk space.",
+                                format_size(size_before.saturating_sub(size_after))
+                            ),
+                            _ => "The database has been compacted.".to_string(),
+                        };
+
+                        if let Subroute::Advanced(advanced) = &mut self.subroute {
+                            *advanced = Advanced::new(&self.connected_state);
+                        }
+
+                        Task::done(app::Message::AddToast(Toast {
+                            title: "Compacted database".to_string(),
+                            body,
+                            status: ToastStatus::Good,
+                        }))
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to compact database".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::LegacyEncryptionKeyInputChanged(input) => {
+                if let Subroute::ImportLegacy(import_legacy) = &mut self.subroute {
+                    import_legacy.encryption_key_input = input;
+                }
+
+                Task::none()
+            }
+            Message::ImportLegacyDatabase {
+                path,
+                encryption_key,
+            } => {
+                // Unencrypted databases are opened without a key.
+                let encryption_key_or = Some(encryption_key.as_str()).filter(|key| !key.is_empty());
+
+                let result =
+                    LegacyDatabase::open(&path, encryption_key_or).and_then(|mut legacy_db| {
+                        let summary = legacy_db
+                            .read_contents()?
+                            .import_into(&self.connected_state.db)?;
+
+                        // The file is only archived once everything in it has been imported.
+                        let archived_path = legacy_db.archive()?;
+
+                        Ok((summary, archived_path))
+                    });
+
+                self.subroute =
+                    SubrouteName::ImportLegacy.to_default_subroute(&self.connected_state);
+
+                match result {
+                    Ok((summary, archived_path)) => Task::done(app::Message::AddToast(Toast {
+                        title: "Imported old data".to_string(),
+                        body: format!(
+                            "Imported {} keys and {} connected apps, and skipped {} that were already here. The old database was moved to {}.",
+                            summary.imported_keypair_count,
+                            summary.imported_app_count,
+                            summary.existing_count,
+                            archived_path.display()
+                        ),
+                        status: ToastStatus::Good,
+                    })),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to import old data".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+        }
+    }
+
+    pub fn view<'a>(&self) -> Column<'a, app::Message> {
+        match &self.subroute {
+            Subroute::Main(main) => main.view(),
+            Subroute::ChangePassword(change_password) => change_password.view(),
+            Subroute::General(general) => general.view(),
+            Subroute::Security(security) => security.view(),
+            Subroute::WalletSeed(wallet_seed) => wallet_seed.view(),
+            Subroute::Privacy(privacy) => privacy.view(),
+            Subroute::Developer(developer) => developer.view(),
+            Subroute::Backup(backup) => backup.view(),
+            Subroute::ConnectedApps(connected_apps) => connected_apps.view(),
+            Subroute::AppPermissions(app_permissions) => app_permissions.view(),
+            Subroute::Advanced(advanced) => advanced.view(&self.connected_state),
+            Subroute::ImportLegacy(import_legacy) => import_legacy.view(),
+            Subroute::About(about) => about.view(),
+            Subroute::WhatsNew(whats_new) => whats_new.view(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubrouteName {
+    Main,
+    ChangePassword,
+    General,
+    Security,
+    WalletSeed,
+    Privacy,
+    Developer,
+    Backup,
+    ConnectedApps,
+    AppPermissions,
+    Advanced,
+    ImportLegacy,
+    About,
+    WhatsNew,
+}
+
+impl SubrouteName {
+    pub fn to_default_subroute(&self, connected_state: &ConnectedState) -> Subroute {
+        match self {
+            Self::Main => Subroute::Main(Main {}),
+            Self::ChangePassword => Subroute::ChangePassword(ChangePassword {
+                current_password_input: String::new(),
+                new_password_input: String::new(),
+                new_password_confirmation_input: String::new(),
+            }),
+            Self::General => Subroute::General(General::new(connected_state)),
+            Self::Security => Subroute::Security(Security::new(connected_state)),
+            Self::WalletSeed => Subroute::WalletSeed(WalletSeed::new(connected_state)),
+            Self::Privacy => Subroute::Privacy(Privacy {
+                // TODO: Log a warning if the setting fails to load.
+                invoice_privacy_or: connected_state.db.get_invoice_privacy().ok(),
+            }),
+            Self::Developer => Subroute::Developer(Developer {
+                payment_simulation: connected_state.wallet.get_payment_simulation(),
+                // TODO: Log a warning if the busy timeout fails to load.
+                busy_timeout_input: connected_state
+                    .db
+                    .get_busy_timeout()
+                    .unwrap_or(DEFAULT_BUSY_TIMEOUT)
+                    .as_secs()
+                    .to_string(),
+            }),
+            Self::Backup => {
+                // TODO: Log a warning if the backup settings or status fail to load.
+                let saved_settings = connected_state.db.get_backup_settings().unwrap_or_default();
+
+                Subroute::Backup(Backup {
+                    directory_input: saved_settings
+                        .directory_or
+                        .as_ref()
+                        .map(|directory| directory.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    rotation_count_input: saved_settings.rotation_count.to_string(),
+                    saved_settings,
+                    status: connected_state.db.get_backup_status().unwrap_or_default(),
+                    clock_format: connected_state.settings.get().clock_format,
+                    export_path_input: String::new(),
+                    export_password_input: String::new(),
+                    export_password_confirmation_input: String::new(),
+                    is_export_in_progress: false,
+                })
+            }
+            Self::ConnectedApps => Subroute::ConnectedApps(ConnectedApps::new(connected_state)),
+            Self::AppPermissions => Subroute::AppPermissions(AppPermissions::new(connected_state)),
+            Self::Advanced => Subroute::Advanced(Advanced::new(connected_state)),
+            Self::ImportLegacy => Subroute::ImportLegacy(ImportLegacy {
+                legacy_database_path_or: legacy::find_legacy_database(),
+                encryption_key_input: String::new(),
+            }),
+            Self::About => Subroute::About(About {}),
+            Self::WhatsNew => Subroute::WhatsNew(WhatsNew::new(connected_state)),
+        }
+    }
+}
+
+pub enum Subroute {
+    Main(Main),
+    ChangePassword(ChangePassword),
+    General(General),
+    Security(Security),
+    WalletSeed(WalletSeed),
+    Privacy(Privacy),
+    Developer(Developer),
+    Backup(Backup),
+    ConnectedApps(ConnectedApps),
+    AppPermissions(AppPermissions),
+    Advanced(Advanced),
+    ImportLegacy(ImportLegacy),
+    About(About),
+    WhatsNew(WhatsNew),
+}
+
+impl Subroute {
+    pub fn to_name(&self) -> SubrouteName {
+        match self {
+            Self::Main(_) => SubrouteName::Main,
+            Self::ChangePassword(_) => SubrouteName::ChangePassword,
+            Self::General(_) => SubrouteName::General,
+            Self::Security(_) => SubrouteName::Security,
+            Self::WalletSeed(_) => SubrouteName::WalletSeed,
+            Self::Privacy(_) => SubrouteName::Privacy,
+            Self::Developer(_) => SubrouteName::Developer,
+            Self::Backup(_) => SubrouteName::Backup,
+            Self::ConnectedApps(_) => SubrouteName::ConnectedApps,
+            Self::AppPermissions(_) => SubrouteName::AppPermissions,
+            Self::Advanced(_) => SubrouteName::Advanced,
+            Self::ImportLegacy(_) => SubrouteName::ImportLegacy,
+            Self::About(_) => SubrouteName::About,
+            Self::WhatsNew(_) => SubrouteName::WhatsNew,
+        }
+    }
+}
+
+pub struct Main {}
+
+impl Main {
+    // TODO: Remove this clippy allow.
+    #[allow(clippy::unused_self)]
+    fn view<'a>(&self) -> Column<'a, app::Message> {
+        container("Settings")
+            .push(
+                icon_button("Change Password", SvgIcon::Lock, PaletteColor::Primary).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                        SubrouteName::ChangePassword,
+                    ))),
+                ),
+            )
+            .push(
+                icon_button("General", SvgIcon::Settings, PaletteColor::Primary).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                        SubrouteName::General,
+                    ))),
+                ),
+            )
+            .push(
+                icon_button("Security", SvgIcon::Key, PaletteColor::Primary).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                        SubrouteName::Security,
+                    ))),
+                ),
+            )
+            .push(
+                icon_button("Wallet Seed", SvgIcon::Key, PaletteColor::Primary).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                        SubrouteName::WalletSeed,
+                    ))),
+                ),
+            )
+            .push(
+                icon_button("Privacy", SvgIcon::Lock, PaletteColor::Primary).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                        SubrouteName::Privacy,
+                    ))),
+                ),
+            )
+            .push(
+                icon_button("Developer", SvgIcon::Settings, PaletteColor::Primary).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                        SubrouteName::Developer,
+                    ))),
+                ),
+            )
+            .push(
+                icon_button("Backup", SvgIcon::FileCopy, PaletteColor::Primary).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                        SubrouteName::Backup,
+                    ))),
+                ),
+            )
+            .push(
+                icon_button("Connected Apps", SvgIcon::Groups, PaletteColor::Primary).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                        SubrouteName::ConnectedApps,
+                    ))),
+                ),
+            )
+            .push(
+                icon_button("App Permissions", SvgIcon::Key, PaletteColor::Primary).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                        SubrouteName::AppPermissions,
+                    ))),
+                ),
+            )
+            .push(
+                icon_button("Advanced", SvgIcon::Settings, PaletteColor::Primary).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                        SubrouteName::Advanced,
+                    ))),
+                ),
+            )
+            .push(
+                icon_button(
+                    "Import Old Data",
+                    SvgIcon::ArrowDownward,
+                    PaletteColor::Primary,
+                )
+                .on_press(app::Message::Routes(super::Message::Navigate(
+                    RouteName::Settings(SubrouteName::ImportLegacy),
+                ))),
+            )
+            .push(
+                icon_button("About", SvgIcon::Info, PaletteColor::Primary).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                        SubrouteName::About,
+                    ))),
+                ),
+            )
+    }
+}
+
+// TODO: Remove this clippy allow.
+#[allow(clippy::struct_field_names)]
+pub struct ChangePassword {
+    current_password_input: String,
+    new_password_input: String,
+    new_password_confirmation_input: String,
+}
+
+impl ChangePassword {
+    // TODO: Remove this clippy allow.
+    #[allow(clippy::unused_self)]
+    fn view<'a>(&self) -> Column<'a, app::Message> {
+        container("Change Password")
+            .push(
+                text_input("Current Password", &self.current_password_input)
+                    .on_input(|input| {
+                        app::Message::Routes(super::Message::SettingsPage(
+                            Message::ChangePasswordCurrentPasswordInputChanged(input),
+                        ))
+                    })
+                    .secure(true)
+                    .padding(10)
+                    .size(30),
+            )
+            .push(
+                text_input("New Password", &self.new_password_input)
+                    .on_input(|input| {
+                        app::Message::Routes(super::Message::SettingsPage(
+                            Message::ChangePasswordNewPasswordInputChanged(input),
+                        ))
+                    })
+                    .secure(true)
+                    .padding(10)
+                    .size(30),
+            )
+            .push(
+                text_input(
+                    "Confirm New Password",
+                    &self.new_password_confirmation_input,
+                )
+                .on_input(|input| {
+                    app::Message::Routes(super::Message::SettingsPage(
+                        Message::ChangePasswordNewPasswordConfirmationInputChanged(input),
+                    ))
+                })
+                .secure(true)
+                .padding(10)
+                .size(30),
+            )
+            .push(
+                icon_button("Change Password", SvgIcon::Lock, PaletteColor::Primary)
+                    .on_press_maybe(
+                        (!self.current_password_input.is_empty()
+                            && !self.new_password_input.is_empty()
+                            && self.new_password_input == self.new_password_confirmation_input)
+                            .then(|| {
+                                app::Message::Routes(super::Message::SettingsPage(
+                                    Message::ChangePasswordSubmit {
+                                        current_password: self.current_password_input.clone(),
+                                        new_password: self.new_password_input.clone(),
+                                    },
+                                ))
+                            }),
+                    ),
+            )
+            .push(
+                icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                        SubrouteName::Main,
+                    ))),
+                ),
+            )
+    }
+}
+
+/// Every field of [`Settings`], laid out from their descriptions
+/// so that new settings show up here without any UI changes.
+pub struct General {
+    settings: Settings,
+    // Unsaved input for each text and number field, by key.
+    inputs: BTreeMap<&'static str, String>,
+}
+
+impl General {
+    fn new(connected_state: &ConnectedState) -> Self {
+        let settings = connected_state.settings.get();
+
+        Self {
+            inputs: settings
+                .fields()
+                .into_iter()
+                .map(|field| (field.key, field.value))
+                .collect(),
+            settings,
+        }
+    }
+
+    fn view<'a>(&self) -> Column<'a, app::Message> {
+        let mut container = container("General");
+
+        for field in self.settings.fields() {
+            let key = field.key;
+
+            container = container
+                .push(Text::new(field.label).size(25))
+                .push(Text::new(field.description));
+
+            container = match field.kind {
+                SettingKind::Choice(choices) => {
+                    let selected_or = choices
+                        .iter()
+                        .find(|choice| choice.value == field.value)
+                        .cloned();
+
+                    container.push(pick_list(choices, selected_or, move |choice| {
+                        app::Message::Routes(super::Message::SettingsPage(Message::SaveSetting {
+                            key,
+                            value: choice.value.to_string(),
+                        }))
+                    }))
+                }
+                SettingKind::Toggle => {
+                    let is_enabled = field.value == true.to_string();
+
+                    container.push(
+                        checkbox("Enabled", is_enabled).on_toggle(move |is_enabled| {
+                            app::Message::Routes(super::Message::SettingsPage(
+                                Message::SaveSetting {
+                                    key,
+                                    value: is_enabled.to_string(),
+                                },
+                            ))
+                        }),
+                    )
+                }
+                SettingKind::Text | SettingKind::Number { .. } => {
+                    let placeholder = match field.kind {
+                        SettingKind::Number { min, max } => format!("{min} to {max}"),
+                        _ => field.label.to_string(),
+                    };
+
+                    let input = self.inputs.get(key).cloned().unwrap_or_default();
+
+                    let can_save =
+                        input != field.value && self.settings.with_field(key, &input).is_ok();
+
+                    container
+                        .push(
+                            text_input(&placeholder, &input)
+                                .on_input(move |input| {
+                                    app::Message::Routes(super::Message::SettingsPage(
+                                        Message::SettingInputChanged { key, input },
+                                    ))
+                                })
+                                .padding(10)
+                                .size(20),
+                        )
+                        .push(
+                            icon_button("Save", SvgIcon::Save, PaletteColor::Primary)
+                                .on_press_maybe(can_save.then(|| {
+                                    app::Message::Routes(super::Message::SettingsPage(
+                                        Message::SaveSetting { key, value: input },
+                                    ))
+                                })),
+                        )
+                }
+            };
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                    SubrouteName::Main,
+                ))),
+            ),
+        )
+    }
+}
+
+pub struct Security {
+    keychain_unlock_enabled_or: Option<bool>,
+    has_keychain_password: bool,
+    is_bound_to_device: bool,
+    device_binding_password_input: String,
+}
+
+impl Security {
+    fn new(connected_state: &ConnectedState) -> Self {
+        Self {
+            // TODO: Log a warning if the setting fails to load.
+            keychain_unlock_enabled_or: connected_state.db.get_keychain_unlock_enabled().ok(),
+            has_keychain_password: keychain::has_password(),
+            is_bound_to_device: Database::is_bound_to_device(),
+            device_binding_password_input: String::new(),
+        }
+    }
+
+    fn view<'a>(&self) -> Column<'a, app::Message> {
+        let mut container = container("Security")
+            .push(Text::new("System Keychain").size(25))
+            .push(Text::new(
+                "Keystache can save your password to your system's keychain, so that you can unlock it without typing your password. Anyone who can unlock your computer's keychain can then unlock Keystache, so turn this off if that's a risk for you.",
+            ));
+
+        if let Some(keychain_unlock_enabled) = self.keychain_unlock_enabled_or {
+            container = container.push(
+                checkbox(
+                    "Allow saving password to system keychain",
+                    keychain_unlock_enabled,
+                )
+                .on_toggle(|is_enabled| {
+                    app::Message::Routes(super::Message::SettingsPage(
+                        Message::KeychainUnlockToggled(is_enabled),
+                    ))
+                }),
+            );
+        } else {
+            container = container.push(Text::new("Failed to load keychain setting"));
+        }
+
+        container
+            .push(Text::new(if self.has_keychain_password {
+                "Your password is saved in the system keychain."
+            } else {
+                "Your password isn't saved in the system keychain. To save it, check \"Remember password in system keychain\" the next time you unlock Keystache."
+            }))
+            .push_maybe(self.has_keychain_password.then(|| {
+                icon_button("Forget Saved Password", SvgIcon::Delete, PaletteColor::Danger)
+                    .on_press(app::Message::Routes(super::Message::SettingsPage(
+                        Message::ForgetKeychainPassword,
+                    )))
+            }))
+            .push(self.device_binding_view())
+            .push(
+                icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                        SubrouteName::Main,
+                    ))),
+                ),
+            )
+    }
+}
+
+impl Security {
+    fn device_binding_view<'a>(&self) -> Column<'a, app::Message> {
+        let password = self.device_binding_password_input.clone();
+        let password_or = (!password.is_empty()).then_some(password);
+
+        Column::new()
+            .push(Text::new("Device Binding").size(25))
+            .push(Text::new(
+                "Keystache can bind your database to this device by keeping part of its key in the system keychain. A copy of the database file then can't be opened without this device's keychain, even with your password. The key is stored like any other keychain entry, so it's only as safe as your system keychain.",
+            ))
+            .push(
+                Text::new(
+                    "If the system keychain is reset or this device is lost, the database is lost with it. Export a backup first. Backups have their own password, so they can still be restored on another device.",
+                )
+                .style(iced::widget::text::danger),
+            )
+            .push(Text::new(if self.is_bound_to_device {
+                "Your database is bound to this device."
+            } else {
+                "Your database isn't bound to this device."
+            }))
+            .push(
+                text_input("Password", &self.device_binding_password_input)
+                    .on_input(|input| {
+                        app::Message::Routes(super::Message::SettingsPage(
+                            Message::DeviceBindingPasswordInputChanged(input),
+                        ))
+                    })
+                    .secure(true)
+                    .padding(10)
+                    .size(30),
+            )
+            .push(if self.is_bound_to_device {
+                icon_button("Unbind From This Device", SvgIcon::LockOpen, PaletteColor::Primary)
+                    .on_press_maybe(password_or.map(|password| {
+                        app::Message::Routes(super::Message::SettingsPage(
+                            Message::UnbindDatabaseFromDevice(password),
+                        ))
+                    }))
+            } else {
+                icon_button("Bind to This Device", SvgIcon::Lock, PaletteColor::Danger)
+                    .on_press_maybe(password_or.map(|password| {
+                        app::Message::Routes(super::Message::SettingsPage(
+                            Message::BindDatabaseToDevice(password),
+                        ))
+                    }))
+            })
+            .spacing(10)
+    }
+}
+
+pub struct WalletSeed {
+    has_seed: bool,
+    password_input: String,
+    // Only set once the password has been entered, and
+    // forgotten again when the seed is hidden or the page is left.
+    revealed_words_or: Option<Vec<String>>,
+}
+
+impl WalletSeed {
+    fn new(connected_state: &ConnectedState) -> Self {
+        Self {
+            // TODO: Log a warning if the seed fails to load.
+            has_seed: matches!(connected_state.db.get_wallet_mnemonic(), Ok(Some(_))),
+            password_input: String::new(),
+            revealed_words_or: None,
+        }
+    }
+
+    fn view<'a>(&self) -> Column<'a, app::Message> {
+        let mut container = container("Wallet Seed").push(Text::new(
+            "Your Bitcoin wallet's keys are derived from a seed of 12 words. Write them down and keep them somewhere safe and offline, since they're needed to recover your e-cash if this device is lost.",
+        ));
+
+        if !self.has_seed {
+            container = container.push(Text::new(
+                "This wallet was created before Keystache generated seeds, so it doesn't have one. Its federations were joined with a key that isn't unique to you, so move your funds elsewhere and leave every federation. A seed is generated the next time Keystache is unlocked without any federations.",
+            ).style(iced::widget::text::danger));
+        } else if let Some(words) = &self.revealed_words_or {
+            container = container.push(
+                Text::new("Anyone who sees these words can take your funds. Never share them or type them into a website.")
+                    .style(iced::widget::text::danger),
+            );
+
+            for (row_index, row_words) in words.chunks(3).enumerate() {
+                container = container.push(
+                    row(row_words.iter().enumerate().map(|(i, word)| {
+                        Text::new(format!("{}. {word}", row_index * 3 + i + 1))
+                            .width(150)
+                            .into()
+                    }))
+                    .spacing(10),
+                );
+            }
+
+            container = container
+                .push(
+                    icon_button("Copy Seed", SvgIcon::ContentCopy, PaletteColor::Primary)
+                        .on_press(app::Message::CopyStringToClipboard(words.join(" "))),
+                )
+                .push(
+                    icon_button("Hide Seed", SvgIcon::Lock, PaletteColor::Primary).on_press(
+                        app::Message::Routes(super::Message::SettingsPage(Message::HideWalletSeed)),
+                    ),
+                );
+        } else {
+            let password = self.password_input.clone();
+
+            container = container
+                .push(
+                    text_input("Password", &self.password_input)
+                        .on_input(|input| {
+                            app::Message::Routes(super::Message::SettingsPage(
+                                Message::WalletSeedPasswordInputChanged(input),
+                            ))
+                        })
+                        .secure(true)
+                        .padding(10)
+                        .size(30),
+                )
+                .push(
+                    icon_button("Show Seed", SvgIcon::LockOpen, PaletteColor::Primary)
+                        .on_press_maybe((!password.is_empty()).then(|| {
+                            app::Message::Routes(super::Message::SettingsPage(
+                                Message::RevealWalletSeed(password),
+                            ))
+                        })),
+                );
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                    SubrouteName::Main,
+                ))),
+            ),
+        )
+    }
+}
+
+pub struct Privacy {
+    invoice_privacy_or: Option<InvoicePrivacy>,
+}
+
+impl Privacy {
+    fn view<'a>(&self) -> Column<'a, app::Message> {
+        let mut container = container("Privacy")
+            .push(Text::new("Invoice Metadata in Exports").size(25))
+            .push(Text::new(
+                "Invoice descriptions can contain private notes. Choose how they're handled when exporting your payment history.",
+            ))
+            .push(pick_list(
+                InvoicePrivacy::ALL,
+                self.invoice_privacy_or,
+                |invoice_privacy| {
+                    app::Message::Routes(super::Message::SettingsPage(
+                        Message::InvoicePrivacySelected(invoice_privacy),
+                    ))
+                },
+            ));
+
+        if let Some(redaction_note) = self
+            .invoice_privacy_or
+            .and_then(InvoicePrivacy::redaction_note)
+        {
+            container = container.push(Text::new(redaction_note).size(15));
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                    SubrouteName::Main,
+                ))),
+            ),
+        )
+    }
+}
+
+pub struct Developer {
+    payment_simulation: PaymentSimulation,
+    busy_timeout_input: String,
+}
+
+impl Developer {
+    fn parse_busy_timeout(&self) -> Option<Duration> {
+        self.busy_timeout_input
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .filter(|secs| (1..=MAX_BUSY_TIMEOUT_SECS).contains(secs))
+            .map(Duration::from_secs)
+    }
+
+    fn view<'a>(&self) -> Column<'a, app::Message> {
+        container("Developer")
+            .push(Text::new("Payment Simulation").size(25))
+            .push(Text::new(
+                "Simulated payments don't move any funds. They're recorded in your payment history and marked as simulated.",
+            ))
+            .push(pick_list(
+                PaymentSimulation::ALL,
+                Some(self.payment_simulation),
+                |payment_simulation| {
+                    app::Message::Routes(super::Message::SettingsPage(
+                        Message::PaymentSimulationSelected(payment_simulation),
+                    ))
+                },
+            ))
+            .push(Text::new("Database Busy Timeout").size(25))
+            .push(Text::new(
+                "How many seconds to wait when the database is busy, such as during a burst of signing requests. Changes that still can't be saved show a \"Database busy\" alert, and changes made in the background, such as recording payments, are retried a few more times first.",
+            ))
+            .push(
+                text_input("Seconds", &self.busy_timeout_input)
+                    .on_input(|input| {
+                        app::Message::Routes(super::Message::SettingsPage(
+                            Message::BusyTimeoutInputChanged(input),
+                        ))
+                    })
+                    .padding(10)
+                    .size(20),
+            )
+            .push(
+                icon_button("Save Timeout", SvgIcon::Save, PaletteColor::Primary).on_press_maybe(
+                    self.parse_busy_timeout().map(|busy_timeout| {
+                        app::Message::Routes(super::Message::SettingsPage(
+                            Message::SaveBusyTimeout(busy_timeout),
+                        ))
+                    }),
+                ),
+            )
+            .push(
+                icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                        SubrouteName::Main,
+                    ))),
+                ),
+            )
+    }
+}
+
+pub struct Backup {
+    directory_input: String,
+    rotation_count_input: String,
+    saved_settings: BackupSettings,
+    status: BackupStatus,
+    clock_format: ClockFormat,
+    export_path_input: String,
+    export_password_input: String,
+    export_password_confirmation_input: String,
+    is_export_in_progress: bool,
+}
+
+impl Backup {
+    /// Parses the inputs, returning `None` if they're invalid.
+    fn parse_inputs(&self) -> Option<BackupSettings> {
+        let rotation_count = self
+            .rotation_count_input
+            .parse()
+            .ok()
+            .filter(|rotation_count| *rotation_count > 0)?;
+
+        let directory = self.directory_input.trim();
+
+        Some(BackupSettings {
+            directory_or: (!directory.is_empty()).then(|| PathBuf::from(directory)),
+            rotation_count,
+        })
+    }
+
+    fn view<'a>(&self) -> Column<'a, app::Message> {
+        let parsed_settings_or = self
+            .parse_inputs()
+            .filter(|parsed_settings| parsed_settings != &self.saved_settings);
+
+        let last_success_text = self.status.last_success_time_or.map_or_else(
+            || "Never".to_string(),
+            |last_success_time| {
+                format_time(last_success_time, Utc::now().naive_utc(), self.clock_format)
+            },
+        );
+
+        let mut container = container("Backup")
+            .push(Text::new(
+                "Keystache can back up your data to a folder once a day, such as a folder synced to cloud storage. Your keys are encrypted with your password, and your e-cash is encrypted with your wallet's seed.",
+            ))
+            .push(Text::new("Status").size(25))
+            .push(Text::new(if self.saved_settings.directory_or.is_some() {
+                "Scheduled backups are enabled."
+            } else {
+                "Scheduled backups are disabled. Choose a folder to enable them."
+            }))
+            .push(Text::new(format!("Last successful backup: {last_success_text}")));
+
+        if let Some(last_error) = &self.status.last_error_or {
+            container = container.push(Text::new(format!("Last backup failed: {last_error}")));
+        }
+
+        container
+            .push(Text::new("Settings").size(25))
+            .push(
+                text_input("Backup folder", &self.directory_input)
+                    .on_input(|input| {
+                        app::Message::Routes(super::Message::SettingsPage(
+                            Message::BackupDirectoryInputChanged(input),
+                        ))
+                    })
+                    .padding(10)
+                    .size(30),
+            )
+            .push(
+                text_input("Number of backups to keep", &self.rotation_count_input)
+                    .on_input(|input| {
+                        app::Message::Routes(super::Message::SettingsPage(
+                            Message::BackupRotationCountInputChanged(input),
+                        ))
+                    })
+                    .padding(10)
+                    .size(30),
+            )
+            .push(
+                icon_button("Save", SvgIcon::Save, PaletteColor::Primary).on_press_maybe(
+                    parsed_settings_or.map(|parsed_settings| {
+                        app::Message::Routes(super::Message::SettingsPage(
+                            Message::SaveBackupSettings(parsed_settings),
+                        ))
+                    }),
+                ),
+            )
+            .push(
+                icon_button("Back Up Now", SvgIcon::FileCopy, PaletteColor::Primary)
+                    .on_press_maybe(
+                        self.saved_settings
+                            .directory_or
+                            .is_some()
+                            .then_some(app::Message::RunBackup),
+                    ),
+            )
+            .push(self.export_view())
+            .push(
+                icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                        SubrouteName::Main,
+                    ))),
+                ),
+            )
+    }
+
+    fn export_view<'a>(&self) -> Column<'a, app::Message> {
+        let path = self.export_path_input.trim();
+
+        let export_message_or = (!self.is_export_in_progress
+            && !path.is_empty()
+            && !self.export_password_input.is_empty()
+            && self.export_password_input == self.export_password_confirmation_input)
+            .then(|| {
+                app::Message::Routes(super::Message::SettingsPage(Message::ExportArchive {
+                    path: PathBuf::from(path),
+                    password: self.export_password_input.clone(),
+                }))
+            });
+
+        Column::new()
+            .push(Text::new("Export").size(25))
+            .push(Text::new(
+                "Save everything in Keystache to one file, such as your keys, relays, connected apps and the federations you've joined, to set it up again on a new device. The file is encrypted with the password you choose here, so anyone with both can read your keys. E-cash isn't included, so move it to the new device before switching.",
+            ))
+            .push(
+                text_input("File to export to", &self.export_path_input)
+                    .on_input(|input| {
+                        app::Message::Routes(super::Message::SettingsPage(
+                            Message::ExportPathInputChanged(input),
+                        ))
+                    })
+                    .padding(10)
+                    .size(30),
+            )
+            .push(
+                text_input("Backup password", &self.export_password_input)
+                    .on_input(|input| {
+                        app::Message::Routes(super::Message::SettingsPage(
+                            Message::ExportPasswordInputChanged(input),
+                        ))
+                    })
+                    .secure(true)
+                    .padding(10)
+                    .size(30),
+            )
+            .push(
+                text_input(
+                    "Confirm backup password",
+                    &self.export_password_confirmation_input,
+                )
+                .on_input(|input| {
+                    app::Message::Routes(super::Message::SettingsPage(
+                        Message::ExportPasswordConfirmationInputChanged(input),
+                    ))
+                })
+                .secure(true)
+                .padding(10)
+                .size(30),
+            )
+            .push(
+                icon_button(
+                    if self.is_export_in_progress {
+                        "Exporting..."
+                    } else {
+                        "Export"
+                    },
+                    SvgIcon::Save,
+                    PaletteColor::Primary,
+                )
+                .on_press_maybe(export_message_or),
+            )
+            .spacing(10)
+    }
+}
+
+pub struct ConnectedApps {
+    stats: Vec<(PublicKey, AppSigningStats)>,
+    auto_approve_public_key_reads_or: Option<bool>,
+    // Each app is shown along with how many events of each kind it has signed.
+    loadable_apps: Loadable<Vec<(Nip46App, BTreeMap<Kind, u64>)>>,
+    loadable_rejections: Loadable<Vec<Nip46Rejection>>,
+    clock_format: ClockFormat,
+    npubs: Vec<String>,
+    // The identity whose key the apps are backed up with.
+    selected_backup_npub_or: Option<String>,
+    is_app_backup_in_progress: bool,
+}
+
+impl ConnectedApps {
+    fn new(connected_state: &ConnectedState) -> Self {
+        let mut stats: Vec<(PublicKey, AppSigningStats)> = connected_state
+            .signing_metrics
+            .iter()
+            .map(|(public_key, stats)| (*public_key, stats.clone()))
+            .collect();
+
+        // Show the busiest apps first.
+        stats.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.request_count()));
+
+        // TODO: Log a warning if the keys fail to load.
+        let npubs = connected_state
+            .db
+            .list_public_keys("", i64::MAX, 0)
+            .unwrap_or_default();
+
+        Self {
+            stats,
+            // TODO: Log a warning if the setting fails to load.
+            auto_approve_public_key_reads_or: connected_state
+                .db
+                .get_auto_approve_public_key_reads()
+                .ok(),
+            loadable_apps: connected_state
+                .db
+                .list_nip46_apps()
+                .and_then(|apps| {
+                    apps.into_iter()
+                        .map(|nip46_app| {
+                            let sign_counts_by_kind = connected_state
+                                .db
+                                .get_nip46_app_sign_counts_by_kind(&nip46_app.public_key)?;
+
+                            Ok((nip46_app, sign_counts_by_kind))
+                        })
+                        .collect()
+                })
+                .map_or(Loadable::Failed, Loadable::Loaded),
+            loadable_rejections: connected_state
+                .db
+                .list_nip46_rejections(MAX_SHOWN_NIP46_REJECTIONS)
+                .map_or(Loadable::Failed, Loadable::Loaded),
+            clock_format: connected_state.settings.get().clock_format,
+            selected_backup_npub_or: npubs.first().cloned(),
+            npubs,
+            is_app_backup_in_progress: false,
+        }
+    }
+
+    fn view<'a>(&self) -> Column<'a, app::Message> {
+        let now = Utc::now().naive_utc();
+
+        let mut container = container("Connected Apps")
+            .push(Text::new("Public Key Requests").size(25))
+            .push(Text::new(
+                "Apps ask for your public key to sign you in. This can't sign anything, so apps you've approved before can be allowed to read it without a prompt. These requests still show up in the signing history below.",
+            ));
+
+        container = match self.auto_approve_public_key_reads_or {
+            Some(auto_approve_public_key_reads) => container.push(
+                checkbox(
+                    "Automatically approve public key requests",
+                    auto_approve_public_key_reads,
+                )
+                .on_toggle(|is_enabled| {
+                    app::Message::Routes(super::Message::SettingsPage(
+                        Message::AutoApprovePublicKeyReadsToggled(is_enabled),
+                    ))
+                }),
+            ),
+            None => container.push(Text::new("Failed to load public key request setting")),
+        };
+
+        match &self.loadable_apps {
+            Loadable::Loading => {}
+            Loadable::Loaded(apps) if apps.is_empty() => {
+                container = container.push(Text::new(
+                    "No apps yet. Apps are added here when you first approve one of their requests.",
+                ));
+            }
+            Loadable::Loaded(apps) => {
+                let is_globally_enabled = self.auto_approve_public_key_reads_or == Some(true);
+
+                for (nip46_app, sign_counts_by_kind) in apps {
+                    let public_key = nip46_app.public_key;
+
+                    container = container.push(
+                        row![
+                            mini_icon_button(
+                                "Forget app",
+                                SvgIcon::Delete,
+                                PaletteColor::Danger,
+                                Some(app::Message::Routes(super::Message::SettingsPage(
+                                    Message::ForgetApp(public_key),
+                                ))),
+                            ),
+                            checkbox(
+                                public_key.to_bech32().map_or_else(
+                                    |_| public_key.to_string(),
+                                    |npub| truncate_text(&npub, 23, true),
+                                ),
+                                nip46_app.auto_approve_public_key_reads,
+                            )
+                            .on_toggle_maybe(
+                                is_globally_enabled.then_some(move |is_enabled| {
+                                    app::Message::Routes(super::Message::SettingsPage(
+                                        Message::AppAutoApprovePublicKeyReadsToggled(
+                                            public_key, is_enabled,
+                                        ),
+                                    ))
+                                },)
+                            ),
+                            Text::new(format!(
+                                "Added {}",
+                                format_time(nip46_app.create_time, now, self.clock_format)
+                            )),
+                        ]
+                        .spacing(10)
+                        .align_y(iced::Alignment::Center),
+                    );
+
+                    container = container.push(Text::new(if sign_counts_by_kind.is_empty() {
+                        "Hasn't signed any events".to_string()
+                    } else {
+                        format!(
+                            "Signed: {}",
+                            sign_counts_by_kind
+                                .iter()
+                                .map(|(kind, sign_count)| format!(
+                                    "{} x{sign_count}",
+                                    describe_event_kind(*kind)
+                                ))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )
+                    }));
+                }
+            }
+            Loadable::Failed => {
+                container = container.push(Text::new("Failed to load apps"));
+            }
+        }
+
+        container = container.push(self.app_backup_view());
+
+        container = container
+            .push(Text::new("Signing History").size(25))
+            .push(Text::new(
+                "How long signing requests from each app waited for you this session. If an app says the signer isn't responding, look for dropped or slow requests. Apps often give up if a request isn't answered quickly.",
+            ));
+
+        if self.stats.is_empty() {
+            container = container.push(Text::new("No signing requests this session"));
+        }
+
+        for (public_key, stats) in &self.stats {
+            let average_wait = stats.average_wait_or().map_or_else(
+                || "No prompted requests".to_string(),
+                |average_wait| format!("{:.1} seconds", average_wait.as_secs_f64()),
+            );
+
+            container = container
+                .push(
+                    Text::new(public_key.to_bech32().map_or_else(
+                        |_| public_key.to_string(),
+                        |npub| truncate_text(&npub, 23, true),
+                    ))
+                    .size(20),
+                )
+                .push(Text::new(format!(
+                    "{} requests: {} approved, {} approved automatically, {} rejected, {} rejected automatically",
+                    stats.request_count(),
+                    stats.approved_count,
+                    stats.auto_approved_count,
+                    stats.rejected_count,
+                    stats.auto_rejected_count
+                )))
+                .push(Text::new(format!("Average wait: {average_wait}")))
+                .push_maybe((stats.slow_count > 0).then(|| {
+                    Text::new(format!(
+                        "{} requests waited {} seconds or more",
+                        stats.slow_count,
+                        SLOW_NIP46_REQUEST_THRESHOLD.as_secs()
+                    ))
+                }))
+                .push_maybe((stats.dropped_count > 0).then(|| {
+                    Text::new(format!(
+                        "{} requests were dropped before they were answered. The app may have timed out or disconnected.",
+                        stats.dropped_count
+                    ))
+                    .style(iced::widget::text::danger)
+                }))
+                .push_maybe((stats.expired_count > 0).then(|| {
+                    Text::new(format!(
+                        "{} requests expired before they were answered and were rejected automatically",
+                        stats.expired_count
+                    ))
+                }));
+        }
+
+        container = container
+            .push(Text::new("Rejected Requests").size(25))
+            .push(Text::new(
+                "Requests that weren't signed, and why. If an action failed in an app, it may be listed here.",
+            ));
+
+        match &self.loadable_rejections {
+            Loadable::Loading => {}
+            Loadable::Loaded(rejections) if rejections.is_empty() => {
+                container = container.push(Text::new("No rejected requests"));
+            }
+            Loadable::Loaded(rejections) => {
+                for rejection in rejections {
+                    container = container
+                        .push(
+                            Text::new(format!(
+                                "{}: {}",
+                                rejection.public_key.to_bech32().map_or_else(
+                                    |_| rejection.public_key.to_string(),
+                                    |npub| truncate_text(&npub, 23, true),
+                                ),
+                                rejection.reason
+                            ))
+                            .style(iced::widget::text::danger),
+                        )
+                        .push(Text::new(format!(
+                            "{} - {}",
+                            format_time(rejection.create_time, now, self.clock_format),
+                            rejection.requests_description
+                        )));
+                }
+            }
+            Loadable::Failed => {
+                container = container.push(Text::new("Failed to load rejected requests"));
+            }
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                    SubrouteName::Main,
+                ))),
+            ),
+        )
+    }
+
+    fn app_backup_view<'a>(&self) -> Column<'a, app::Message> {
+        let selected_npub_or = self
+            .selected_backup_npub_or
+            .clone()
+            .filter(|_| !self.is_app_backup_in_progress);
+
+        Column::new()
+            .push(Text::new("Backup").size(25))
+            .push(Text::new(
+                "Save your connected apps and their settings to your relays, encrypted so that only the chosen identity can read them. Restoring them on another device means you won't have to approve each app again. Wallet connections aren't included.",
+            ))
+            .push(
+                row![
+                    pick_list(
+                        self.npubs.as_slice(),
+                        self.selected_backup_npub_or.clone(),
+                        |npub| app::Message::Routes(super::Message::SettingsPage(
+                            Message::AppBackupIdentitySelected(npub)
+                        )),
+                    ),
+                    icon_button("Back Up to Nostr", SvgIcon::ArrowUpward, PaletteColor::Primary)
+                        .on_press_maybe(selected_npub_or.clone().map(|npub| {
+                            app::Message::Routes(super::Message::SettingsPage(
+                                Message::BackUpApps(npub),
+                            ))
+                        })),
+                    icon_button(
+                        "Restore from Nostr",
+                        SvgIcon::ArrowDownward,
+                        PaletteColor::Primary
+                    )
+                    .on_press_maybe(selected_npub_or.map(|npub| {
+                        app::Message::Routes(super::Message::SettingsPage(Message::RestoreApps(
+                            npub,
+                        )))
+                    })),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            )
+            .push_maybe(
+                self.is_app_backup_in_progress
+                    .then(|| Text::new("Talking to your relays...")),
+            )
+            .spacing(20)
+    }
+}
+
+pub struct AppPermissions {
+    loadable_kind_policies: Loadable<Vec<Nip46KindPolicy>>,
+    // Grants made from the approval prompt. They're kept in memory, so they end when Keystache closes.
+    approval_grants: Vec<(PublicKey, Option<Instant>)>,
+    apps: Vec<Nip46App>,
+    app_npubs: Vec<String>,
+    selected_app_npub_or: Option<String>,
+    kind_input: String,
+    decision_or: Option<KindPolicyDecision>,
+    clock_format: ClockFormat,
+}
+
+impl AppPermissions {
+    fn new(connected_state: &ConnectedState) -> Self {
+        // TODO: Log a warning if the apps fail to load.
+        let apps = connected_state.db.list_nip46_apps().unwrap_or_default();
+
+        let app_npubs: Vec<String> = apps
+            .iter()
+            .filter_map(|nip46_app| nip46_app.public_key.to_bech32().ok())
+            .collect();
+
+        Self {
+            loadable_kind_policies: connected_state
+                .db
+                .list_nip46_kind_policies()
+                .map_or(Loadable::Failed, Loadable::Loaded),
+            approval_grants: connected_state.approval_grants.list(Instant::now()),
+            apps,
+            selected_app_npub_or: app_npubs.first().cloned(),
+            app_npubs,
+            kind_input: String::new(),
+            decision_or: None,
+            clock_format: connected_state.settings.get().clock_format,
+        }
+    }
+
+    fn view<'a>(&self) -> Column<'a, app::Message> {
+        let now = Utc::now().naive_utc();
+
+        let mut container = container("App Permissions")
+            .push(Text::new("Event Kind Rules").size(25))
+            .push(Text::new(
+                "Rules answer an app's requests to sign events of one kind without a prompt. A request is rejected if it includes any kind the app is always rejected for, and only approved if every event in it is of a kind the app is always approved for. Requests to sign as a keypair other than the app's are never approved automatically.",
+            ));
+
+        match &self.loadable_kind_policies {
+            Loadable::Loading => {}
+            Loadable::Loaded(kind_policies) if kind_policies.is_empty() => {
+                container = container.push(Text::new("No rules"));
+            }
+            Loadable::Loaded(kind_policies) => {
+                for kind_policy in kind_policies {
+                    let decision_text = Text::new(kind_policy.decision.to_string());
+
+                    let decision_text = match kind_policy.decision {
+                        KindPolicyDecision::AlwaysApprove => decision_text,
+                        KindPolicyDecision::AlwaysReject => {
+                            decision_text.style(iced::widget::text::danger)
+                        }
+                    };
+
+                    container = container.push(
+                        row![
+                            mini_icon_button(
+                                "Revoke rule",
+                                SvgIcon::Delete,
+                                PaletteColor::Danger,
+                                Some(app::Message::Routes(super::Message::SettingsPage(
+                                    Message::RevokeKindPolicy(
+                                        kind_policy.public_key,
+                                        kind_policy.kind
+                                    ),
+                                ))),
+                            ),
+                            Text::new(kind_policy.public_key.to_bech32().map_or_else(
+                                |_| kind_policy.public_key.to_string(),
+                                |npub| truncate_text(&npub, 23, true),
+                            )),
+                            Text::new(describe_event_kind(kind_policy.kind)),
+                            decision_text,
+                            Text::new(format!(
+                                "Added {}",
+                                format_time(kind_policy.create_time, now, self.clock_format)
+                            )),
+                        ]
+                        .spacing(10)
+                        .align_y(iced::Alignment::Center),
+                    );
+                }
+            }
+            Loadable::Failed => {
+                container = container.push(Text::new("Failed to load rules"));
+            }
+        }
+
+        container = container.push(self.add_kind_policy_view());
+
+        container = container
+            .push(Text::new("Session Approvals").size(25))
+            .push(Text::new(
+                "Apps you've approved all requests from for a while, from the approval prompt.",
+            ));
+
+        if self.approval_grants.is_empty() {
+            container = container.push(Text::new("No session approvals"));
+        }
+
+        let now = Instant::now();
+
+        for (public_key, expiry_time_or) in &self.approval_grants {
+            let public_key = *public_key;
+
+            let expiry_text = expiry_time_or.map_or_else(
+                || "Until Keystache closes".to_string(),
+                |expiry_time| {
+                    format!(
+                        "For {} more minutes",
+                        expiry_time
+                            .saturating_duration_since(now)
+                            .as_secs()
+                            .div_ceil(60)
+                    )
+                },
+            );
+
+            container = container.push(
+                row![
+                    mini_icon_button(
+                        "Revoke approval",
+                        SvgIcon::Delete,
+                        PaletteColor::Danger,
+                        Some(app::Message::Routes(super::Message::SettingsPage(
+                            Message::RevokeApprovalGrant(public_key),
+                        ))),
+                    ),
+                    Text::new(public_key.to_bech32().map_or_else(
+                        |_| public_key.to_string(),
+                        |npub| truncate_text(&npub, 23, true),
+                    )),
+                    Text::new(expiry_text),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            );
+        }
+
+        container = container
+            .push(Text::new("Encrypted Conversations").size(25))
+            .push(Text::new(
+                "Apps ask to encrypt and decrypt direct messages with your key. Apps that remember conversations only prompt the first time they ask about each person until Keystache closes. Encryption requests don't say which keypair they're for, so this only works once the app has a keypair chosen for it.",
+            ));
+
+        if self.apps.is_empty() {
+            container = container.push(Text::new(
+                "Apps are added here when you first approve one of their requests.",
+            ));
+        }
+
+        for nip46_app in &self.apps {
+            let public_key = nip46_app.public_key;
+
+            container = container.push(
+                checkbox(
+                    format!(
+                        "Remember conversations for {}",
+                        public_key.to_bech32().map_or_else(
+                            |_| public_key.to_string(),
+                            |npub| truncate_text(&npub, 23, true),
+                        )
+                    ),
+                    nip46_app.remember_conversations,
+                )
+                .on_toggle_maybe(nip46_app.identity_or.is_some().then_some(
+                    move |is_enabled| {
+                        app::Message::Routes(super::Message::SettingsPage(
+                            Message::AppRememberConversationsToggled(public_key, is_enabled),
+                        ))
+                    },
+                )),
+            );
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                    SubrouteName::Main,
+                ))),
+            ),
+        )
+    }
+
+    fn add_kind_policy_view<'a>(&self) -> Column<'a, app::Message> {
+        if self.app_npubs.is_empty() {
+            return Column::new().push(Text::new(
+                "Apps are added here when you first approve one of their requests.",
+            ));
+        }
+
+        let public_key_or = self
+            .selected_app_npub_or
+            .as_ref()
+            .and_then(|npub| PublicKey::from_bech32(npub).ok());
+
+        let kind_or = self.kind_input.trim().parse::<u16>().ok().map(Kind::from);
+
+        let save_message_or = public_key_or.zip(kind_or).zip(self.decision_or).map(
+            |((public_key, kind), decision)| {
+                app::Message::Routes(super::Message::SettingsPage(Message::SaveKindPolicy(
+                    public_key, kind, decision,
+                )))
+            },
+        );
+
+        Column::new()
+            .push(
+                pick_list(
+                    self.app_npubs.clone(),
+                    self.selected_app_npub_or.clone(),
+                    |npub| {
+                        app::Message::Routes(super::Message::SettingsPage(
+                            Message::KindPolicyAppSelected(npub),
+                        ))
+                    },
+                )
+                .placeholder("App"),
+            )
+            .push(
+                text_input("Event kind number, like 1 for notes", &self.kind_input)
+                    .on_input(|input| {
+                        app::Message::Routes(super::Message::SettingsPage(
+                            Message::KindPolicyKindInputChanged(input),
+                        ))
+                    })
+                    .padding(10)
+                    .size(20),
+            )
+            .push(
+                pick_list(&KindPolicyDecision::ALL[..], self.decision_or, |decision| {
+                    app::Message::Routes(super::Message::SettingsPage(
+                        Message::KindPolicyDecisionSelected(decision),
+                    ))
+                })
+                .placeholder("Decision"),
+            )
+            .push(
+                icon_button("Add Rule", SvgIcon::Save, PaletteColor::Primary)
+                    .on_press_maybe(save_message_or),
+            )
+            .spacing(10)
+    }
+}
+
+pub struct Advanced {
+    database_size_or: Option<u64>,
+    // Federations are listed by name, or by ID if they don't have one.
+    federation_data_sizes_or: Option<Vec<(String, u64)>>,
+}
+
+impl Advanced {
+    fn new(connected_state: &ConnectedState) -> Self {
+        let federation_data_sizes_or =
+            connected_state
+                .wallet
+                .get_federation_data_sizes()
+                .ok()
+                .map(|sizes| {
+                    sizes
+                        .into_iter()
+                        .map(|(federation_id, size)| {
+                            let name_or = match &connected_state.loadable_wallet_view {
+                                Loadable::Loaded(wallet_view) => wallet_view
+                                    .federations
+                                    .get(&federation_id)
+                                    .and_then(|federation| federation.metadata.name_or.clone()),
+                                _ => None,
+                            };
+
+                            (name_or.unwrap_or_else(|| federation_id.to_string()), size)
+                        })
+                        .collect()
+                });
+
+        // TODO: Log a warning if the sizes fail to load.
+        Self {
+            database_size_or: connected_state.db.get_size_on_disk().ok(),
+            federation_data_sizes_or,
+        }
+    }
+
+    fn view<'a>(&self, connected_state: &ConnectedState) -> Column<'a, app::Message> {
+        let mut container = container("Advanced")
+            .push(Text::new("Storage").size(25))
+            .push(Text::new(format!(
+                "Database: {}",
+                self.database_size_or
+                    .map_or_else(|| "Unknown".to_string(), format_size)
+            )));
+
+        match &self.federation_data_sizes_or {
+            Some(federation_data_sizes) if federation_data_sizes.is_empty() => {
+                container = container.push(Text::new("No federation data"));
+            }
+            Some(federation_data_sizes) => {
+                for (federation_name, size) in federation_data_sizes {
+                    container = container.push(Text::new(format!(
+                        "{}: {}",
+                        truncate_text(federation_name, 40, true),
+                        format_size(*size)
+                    )));
+                }
+            }
+            None => {
+                container = container.push(Text::new("Failed to load federation data sizes"));
+            }
+        }
+
+        container = container
+            .push(Text::new("Data Usage").size(25))
+            .push(Text::new(
+                "Data sent to and received from each relay since Keystache was unlocked. Turn on Low Data Mode in the general settings to use less.",
+            ));
+
+        let nostr_state = &connected_state.nostr_state;
+
+        if nostr_state.relay_data_usage.is_empty() {
+            container = container.push(Text::new("No relays"));
+        } else {
+            for (url, data_usage) in &nostr_state.relay_data_usage {
+                container = container.push(Text::new(format!(
+                    "{}: {}",
+                    truncate_text(url.as_str(), 40, true),
+                    format_data_usage(*data_usage)
+                )));
+            }
+
+            container = container.push(Text::new(format!(
+                "Total: {}",
+                format_data_usage(nostr_state.total_relay_data_usage())
+            )));
+        }
+
+        container
+            .push(Text::new("Maintenance").size(25))
+            .push(Text::new(format!(
+                "Keystache tidies up the database every {} hours while it's unlocked. Compacting the database reclaims the space left behind by deleted data, but it can take a while and Keystache won't respond until it's done.",
+                DATABASE_MAINTENANCE_INTERVAL.as_secs() / (60 * 60)
+            )))
+            .push(
+                icon_button("Compact Database", SvgIcon::Save, PaletteColor::Primary)
+                    .on_press(app::Message::Routes(super::Message::SettingsPage(
+                        Message::VacuumDatabase,
+                    ))),
+            )
+            .push(
+                icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                        SubrouteName::Main,
+                    ))),
+                ),
+            )
+    }
+}
+
+fn format_data_usage(data_usage: DataUsage) -> String {
+    format!(
+        "{} sent, {} received",
+        format_size(data_usage.bytes_sent),
+        format_size(data_usage.bytes_received)
+    )
+}
+
+pub struct ImportLegacy {
+    legacy_database_path_or: Option<PathBuf>,
+    encryption_key_input: String,
+}
+
+impl ImportLegacy {
+    fn view<'a>(&self) -> Column<'a, app::Message> {
+        let mut container = container("Import Old Data");
+
+        if let Some(legacy_database_path) = &self.legacy_database_path_or {
+            container = container
+                .push(Text::new(format!(
+                    "Found a database from an older version of Keystache at {}. Importing it copies its keys and connected apps into this one. Once everything has been copied, the old database is renamed so that it isn't imported again.",
+                    legacy_database_path.display()
+                )))
+                .push(
+                    text_input(
+                        "Old Database Key (leave empty if it wasn't encrypted)",
+                        &self.encryption_key_input,
+                    )
+                    .on_input(|input| {
+                        app::Message::Routes(super::Message::SettingsPage(
+                            Message::LegacyEncryptionKeyInputChanged(input),
+                        ))
+                    })
+                    .secure(true)
+                    .padding(10)
+                    .size(30),
+                )
+                .push(
+                    icon_button("Import", SvgIcon::ArrowDownward, PaletteColor::Primary).on_press(
+                        app::Message::Routes(super::Message::SettingsPage(
+                            Message::ImportLegacyDatabase {
+                                path: legacy_database_path.clone(),
+                                encryption_key: self.encryption_key_input.clone(),
+                            },
+                        )),
+                    ),
+                );
+        } else {
+            container = container.push(Text::new(
+                "No database from an older version of Keystache was found.",
+            ));
+        }
+
+        container.push(
+            icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(super::Message::Navigate(RouteName::Settings(
+                    SubrouteName::Main,
+                ))),
+            ),
+        )
+    }
+}
+
+pub struct About {}
+
+impl About {
+    // TODO: Remove this clippy allow.
+    #[allow(clippy::unused_self)]
+    fn view<'a>(&self) -> Column<'a, app::Message> {
+        container("About")
+            .push(Text::new("Description").size(25))
+            .push(Text::new("Keystache is a Nostr single-sign-on key management and Fedimint Bitcoin wallet created by Tommy Volk and generously funded by OpenSats").size(15))
+            .push(Text::new("Source Code").size(25))
+            .push(Text::new("https://github.com/nodetec/keystache").size(15))
+            .push(Text::new("Version").size(25))
+            .push(Text::new(env!("CARGO_PKG_VERSION")).size(15))
+            .push(icon_button("What's New", SvgIcon::Info, PaletteColor::Primary).on_press(
+                app::Message::Routes(super::Message::Navigate(RouteName::Settings(SubrouteName::WhatsNew)))
+            ))
+            .push(icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
+                app::Message::Routes(super::Message::Navigate(RouteName::Settings(SubrouteName::Main)))
+            ))
+    }
+}
+
+pub struct WhatsNew {
+    release_notes: &'static [ReleaseNotes],
+    show_release_notes: bool,
+}
+
+impl WhatsNew {
+    fn new(connected_state: &ConnectedState) -> Self {
+        Self {
+            release_notes: release_notes_up_to(env!("CARGO_PKG_VERSION")),
+            show_release_notes: connected_state.settings.get().show_release_notes,
+        }
+    }
+
+    fn view<'a>(&self) -> Column<'a, app::Message> {
+        let mut container = container("What's New");
+
+        for release_notes in self.release_notes {
+            container = container.push(Text::new(release_notes.version).size(25));
+
+            for highlight in release_notes.highlights {
+                container = container.push(Text::new(format!("• {highlight}")));
+            }
+        }
+
+        container
+            .push(
+                checkbox("Show this after updates", self.show_release_notes).on_toggle(
+                    |is_enabled| {
+                        app::Message::Routes(super::Message::SettingsPage(Message::SaveSetting {
+                            key: SHOW_RELEASE_NOTES_KEY,
+                            value: is_enabled.to_string(),
+                        }))
+                    },
+                ),
+            )
+            .push(
+                icon_button("Done", SvgIcon::Home, PaletteColor::Background).on_press(
+                    app::Message::Routes(super::Message::Navigate(RouteName::Home)),
+                ),
+            )
+    }
+}
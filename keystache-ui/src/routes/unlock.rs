@@ -0,0 +1,748 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    path::Path,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::Utc;
+use directories::ProjectDirs;
+use fedimint_core::invite_code::InviteCode;
+use iced::{
+    widget::{checkbox, row, Column, Space, Text},
+    Pixels, Task,
+};
+use nostr_sdk::{bip39::Mnemonic, bitcoin::bip32::Xpriv};
+
+use crate::{
+    app,
+    config::SettingsHandle,
+    db::{Database, DeviceKeyUnavailableError, IncorrectPasswordError},
+    encryption::RememberedConversations,
+    fedimint::{
+        generate_wallet_mnemonic, get_wallet_xpriv, has_joined_federations, Wallet, WALLET_NETWORK,
+    },
+    in_flight::InFlightOperations,
+    keychain, legacy,
+    metrics::SigningMetrics,
+    nostr::{NostrModule, NostrModuleMessage, NostrState},
+    policy::ApprovalGrants,
+    profile::Profiles,
+    release_notes::record_app_version,
+    signing_worker::SigningWorker,
+    ui_components::{icon_button, text_input, Avatars, PaletteColor, SvgIcon, Toast, ToastStatus},
+    unlock_attempts::FailedUnlockAttempts,
+};
+
+use super::{container, ConnectedState, Drafts, Loadable, RouteName};
+
+// The seed that every wallet's key was made from before seeds were generated.
+// See [`load_or_create_wallet_xpriv`].
+const LEGACY_WALLET_SEED: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    PasswordInputChanged(String),
+    ToggleSecureInput,
+    ToggleRememberPassword,
+    PasswordSubmitted,
+    UnlockWithKeychain,
+    CooldownTick,
+    RestorePathInputChanged(String),
+    RestoreSubmitted,
+    RestoreSeedInputChanged(String),
+    RestoreInviteCodesInputChanged(String),
+    RestoreSeedSubmitted {
+        mnemonic: Mnemonic,
+        invite_codes: Vec<InviteCode>,
+    },
+}
+
+pub struct Page {
+    pub password: String,
+    pub is_secure: bool,
+    pub db_already_exists: bool,
+    /// Whether to save the password to the system keychain once it's unlocked.
+    pub remember_password: bool,
+    pub has_keychain_password: bool,
+    /// Password attempts that failed since the database was last unlocked.
+    pub failed_unlock_attempts: FailedUnlockAttempts,
+    /// How much longer the user has to wait before trying another password.
+    pub remaining_cooldown_or: Option<Duration>,
+    /// Where the backup to restore a new database from is. See [`Database::export_archive`].
+    pub restore_path_input: String,
+    /// The seed of a wallet to restore, which a new database is created for.
+    /// See [`Database::restore_seed_in_app_data_dir`].
+    pub restore_seed_input: String,
+    /// The invite codes of the federations to recover the restored wallet's e-cash from.
+    pub restore_invite_codes_input: String,
+}
+
+impl Page {
+    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
+        match msg {
+            Message::PasswordInputChanged(new_password) => {
+                self.password = new_password;
+
+                Task::none()
+            }
+            Message::ToggleSecureInput => {
+                self.is_secure = !self.is_secure;
+
+                Task::none()
+            }
+            Message::ToggleRememberPassword => {
+                self.remember_password = !self.remember_password;
+
+                Task::none()
+            }
+            Message::PasswordSubmitted => {
+                if self.remaining_cooldown_or.is_some() {
+                    return Task::none();
+                }
+
+                match Database::open_or_create_in_app_data_dir(&self.password) {
+                    Ok(db) => Self::unlock(db, &self.password, self.remember_password),
+                    // Only a wrong password counts towards the cooldown. Other failures, such
+                    // as a busy database or a disk error, would fail with any password.
+                    Err(err) if err.is::<IncorrectPasswordError>() => {
+                        let now = Utc::now().naive_utc();
+
+                        // TODO: Log a warning if the failed attempt fails to be saved.
+                        if let Ok(app_data_dir) = Database::app_data_dir() {
+                            let _ = self
+                                .failed_unlock_attempts
+                                .record_failure(&app_data_dir, now);
+                        }
+
+                        self.remaining_cooldown_or =
+                            self.failed_unlock_attempts.remaining_cooldown_or(now);
+
+                        Task::done(app::Message::AddToast(Toast {
+                            title: "Failed to unlock".to_string(),
+                            body: match self.remaining_cooldown_or {
+                                Some(remaining_cooldown) => format!(
+                                    "The password may be incorrect. Wait {} before trying again.",
+                                    format_cooldown(remaining_cooldown)
+                                ),
+                                None => "The password may be incorrect.".to_string(),
+                            },
+                            status: ToastStatus::Bad,
+                        }))
+                    }
+                    Err(err) if err.is::<DeviceKeyUnavailableError>() => {
+                        Task::done(app::Message::AddToast(device_key_unavailable_toast(&err)))
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: if self.db_already_exists {
+                            "Failed to unlock".to_string()
+                        } else {
+                            "Failed to create database".to_string()
+                        },
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::UnlockWithKeychain => match keychain::get_password() {
+                Ok(Some(password)) => match Database::open_or_create_in_app_data_dir(&password) {
+                    Ok(db) => Self::unlock(db, &password, false),
+                    Err(err) if err.is::<IncorrectPasswordError>() => {
+                        // The password was probably changed elsewhere, so the saved one is useless.
+                        // TODO: Log a warning if the password fails to be removed.
+                        let _ = keychain::delete_password();
+                        self.has_keychain_password = false;
+
+                        Task::done(app::Message::AddToast(Toast {
+                            title: "Saved password didn't work".to_string(),
+                            body: "It has been removed from the system keychain. Enter your password to unlock Keystache.".to_string(),
+                            status: ToastStatus::Bad,
+                        }))
+                    }
+                    // The saved password is kept, since it may still be right.
+                    Err(err) if err.is::<DeviceKeyUnavailableError>() => {
+                        Task::done(app::Message::AddToast(device_key_unavailable_toast(&err)))
+                    }
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to unlock".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                },
+                Ok(None) => {
+                    self.has_keychain_password = false;
+
+                    Task::none()
+                }
+                Err(err) => Task::done(app::Message::AddToast(Toast {
+                    title: "Failed to read system keychain".to_string(),
+                    body: err.to_string(),
+                    status: ToastStatus::Bad,
+                })),
+            },
+            Message::CooldownTick => {
+                self.remaining_cooldown_or = self
+                    .failed_unlock_attempts
+                    .remaining_cooldown_or(Utc::now().naive_utc());
+
+                Task::none()
+            }
+            Message::RestorePathInputChanged(input) => {
+                self.restore_path_input = input;
+
+                Task::none()
+            }
+            Message::RestoreSubmitted => {
+                match Database::restore_archive_in_app_data_dir(
+                    Path::new(self.restore_path_input.trim()),
+                    &self.password,
+                ) {
+                    Ok(db) => Self::unlock(db, &self.password, self.remember_password),
+                    Err(err) => Task::done(app::Message::AddToast(Toast {
+                        title: "Failed to restore backup".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    })),
+                }
+            }
+            Message::RestoreSeedInputChanged(input) => {
+                self.restore_seed_input = input;
+
+                Task::none()
+            }
+            Message::RestoreInviteCodesInputChanged(input) => {
+                self.restore_invite_codes_input = input;
+
+                Task::none()
+            }
+            Message::RestoreSeedSubmitted {
+                mnemonic,
+                invite_codes,
+            } => match Database::restore_seed_in_app_data_dir(
+                &self.password,
+                &mnemonic,
+                &invite_codes,
+            ) {
+                Ok(db) => Self::unlock(db, &self.password, self.remember_password),
+                Err(err) => Task::done(app::Message::AddToast(Toast {
+                    title: "Failed to restore wallet".to_string(),
+                    body: err.to_string(),
+                    status: ToastStatus::Bad,
+                })),
+            },
+        }
+    }
+
+    /// Navigates home with `db`, which was opened with `password`.
+    /// Saves `password` to the system keychain if `remember_password` is set,
+    /// unless keychain unlock has been disabled in the settings.
+    // TODO: Remove this clippy allow.
+    #[allow(clippy::too_many_lines)]
+    fn unlock(db: Database, password: &str, remember_password: bool) -> Task<app::Message> {
+        let db = Arc::new(db);
+
+        // TODO: Log a warning if the failed attempts fail to be cleared.
+        if let Ok(app_data_dir) = Database::app_data_dir() {
+            let _ = FailedUnlockAttempts::clear(&app_data_dir);
+        }
+
+        let keychain_toast_or = match db.get_keychain_unlock_enabled() {
+            Ok(true) if remember_password => {
+                keychain::save_password(password).err().map(|err| Toast {
+                    title: "Failed to save password to system keychain".to_string(),
+                    body: err.to_string(),
+                    status: ToastStatus::Bad,
+                })
+            }
+            Ok(true) => None,
+            // Make sure that no password is left behind in the keychain,
+            // such as one saved before keychain unlock was disabled.
+            Ok(false) => {
+                let _ = keychain::delete_password();
+
+                remember_password.then(|| Toast {
+                    title: "Password not saved".to_string(),
+                    body: "Saving your password to the system keychain is disabled in the security settings.".to_string(),
+                    status: ToastStatus::Neutral,
+                })
+            }
+            Err(err) => Some(Toast {
+                title: "Failed to load security settings".to_string(),
+                body: err.to_string(),
+                status: ToastStatus::Bad,
+            }),
+        };
+
+        // TODO: We should initialize project directories elsewhere and pass them in.
+        let Some(project_dirs) = ProjectDirs::from("co", "nodetec", "keystache") else {
+            return Task::done(app::Message::AddToast(Toast {
+                title: "Failed to unlock Keystache".to_string(),
+                body: "Could not determine Keystache project directories.".to_string(),
+                status: ToastStatus::Bad,
+            }));
+        };
+
+        // TODO: Log a warning if the settings fail to load.
+        let settings = SettingsHandle::new(db.get_settings().unwrap_or_default());
+
+        // The version is recorded even if release notes are turned off, so that
+        // turning them back on doesn't show the notes of an update from long ago.
+        // TODO: Log a warning if the app version fails to be recorded.
+        let is_updated = record_app_version(&db, env!("CARGO_PKG_VERSION")).unwrap_or(false);
+        let should_show_release_notes = is_updated && settings.get().show_release_notes;
+
+        // TODO: Retrieve network from elsewhere rather than hardcoding.
+        let xprivkey = match load_or_create_wallet_xpriv(&db, &project_dirs) {
+            Ok(xprivkey) => xprivkey,
+            Err(err) => {
+                return Task::done(app::Message::AddToast(Toast {
+                    title: "Failed to unlock Keystache".to_string(),
+                    body: err.to_string(),
+                    status: ToastStatus::Bad,
+                }));
+            }
+        };
+
+        let wallet = Arc::new(Wallet::new(
+            xprivkey,
+            WALLET_NETWORK,
+            &project_dirs,
+            settings.subscribe(),
+        ));
+
+        // TODO: Log a warning if the setting fails to load.
+        wallet.set_payment_simulation(db.get_payment_simulation().unwrap_or_default());
+
+        // TODO: Log a warning if the pinned gateways fail to load.
+        wallet.set_pinned_gateways(db.list_pinned_gateways().unwrap_or_default());
+
+        // TODO: Log a warning if the API overrides fail to load.
+        wallet.set_api_overrides(db.list_federation_api_overrides().unwrap_or_default());
+
+        // TODO: Log a warning if the gateway fee history fails to load.
+        wallet.set_gateway_fee_stats(db.get_gateway_fee_stats().unwrap_or_default());
+
+        // TODO: Log a warning if the app payment cap fails to load.
+        if let Ok(app_payment_cap) = db.get_app_payment_cap() {
+            wallet.set_app_payment_cap(app_payment_cap);
+        }
+
+        // TODO: Log a warning if the spend approval policy fails to load.
+        wallet.set_spend_approval_policy(db.get_spend_approval_policy().unwrap_or_default());
+
+        // Federations joined when a restored backup was exported are rejoined until they all
+        // succeed. Joining a federation that's already joined does nothing.
+        // TODO: Log a warning if the archived federations fail to load.
+        let archived_invite_codes = db
+            .get_archived_federation_invite_codes()
+            .unwrap_or_default();
+
+        let wallet_clone = wallet.clone();
+        let db_clone = db.clone();
+        let connect_to_federations_task = Task::future(async move {
+            // TODO: Log a warning if the regtest federation can't be joined.
+            #[cfg(feature = "regtest")]
+            let _ = wallet_clone.join_regtest_federation().await;
+
+            if let Err(err) = wallet_clone.connect_to_joined_federations().await {
+                return Some(err);
+            }
+
+            if archived_invite_codes.is_empty() {
+                return None;
+            }
+
+            for invite_code in archived_invite_codes {
+                if let Err(err) = wallet_clone.join_federation(invite_code, |_| {}).await {
+                    return Some(err);
+                }
+            }
+
+            db_clone.remove_archived_federation_invite_codes().err()
+        })
+        .and_then(|err| {
+            Task::done(app::Message::AddToast(Toast {
+                title: "Failed to connect to federations".to_string(),
+                body: err.to_string(),
+                status: ToastStatus::Bad,
+            }))
+        });
+
+        // E-cash is recovered separately from connecting, since it can take a long time.
+        // Federations are recovered again on every unlock until they all succeed, and
+        // interrupted recoveries pick up where they left off.
+        // TODO: Log a warning if the recovery federations fail to load.
+        let recovery_invite_codes = db
+            .get_recovery_federation_invite_codes()
+            .unwrap_or_default();
+
+        let recovery_toast_or = (!recovery_invite_codes.is_empty()).then(|| Toast {
+            title: "Recovering e-cash".to_string(),
+            body: format!(
+                "Your wallet's e-cash is being recovered from {} federations. This can take a while, and balances appear once it's done.",
+                recovery_invite_codes.len()
+            ),
+            status: ToastStatus::Neutral,
+        });
+
+        let wallet_clone = wallet.clone();
+        let db_clone = db.clone();
+        let recover_federations_task = Task::future(async move {
+            if recovery_invite_codes.is_empty() {
+                return None;
+            }
+
+            for invite_code in recovery_invite_codes {
+                if let Err(err) = wallet_clone.recover_federation(invite_code).await {
+                    return Some(Toast {
+                        title: "Failed to recover e-cash".to_string(),
+                        body: err.to_string(),
+                        status: ToastStatus::Bad,
+                    });
+                }
+            }
+
+            Some(match db_clone.remove_recovery_federation_invite_codes() {
+                Ok(()) => Toast {
+                    title: "Recovered e-cash".to_string(),
+                    body: "Your wallet's e-cash has been recovered.".to_string(),
+                    status: ToastStatus::Good,
+                },
+                Err(err) => Toast {
+                    title: "Failed to finish recovering e-cash".to_string(),
+                    body: err.to_string(),
+                    status: ToastStatus::Bad,
+                },
+            })
+        })
+        .and_then(|toast| Task::done(app::Message::AddToast(toast)));
+
+        // TODO: Log a warning if the cached federation metadata fails to load.
+        let federation_metadata = db.list_federation_metadata().unwrap_or_default();
+
+        let nostr_module = NostrModule::new(settings.subscribe(), db.clone());
+
+        let zap_recipient_public_keys = super::list_zap_recipient_public_keys(&db);
+
+        // TODO: Log a warning if the backup settings fail to load.
+        let backup_settings = db.get_backup_settings().unwrap_or_default();
+
+        let nwc_connections = super::list_nwc_connections(&db);
+
+        let cosigner_shares = super::list_cosigner_shares(&db);
+
+        // TODO: Log a warning if the thresholds fail to load.
+        let balance_thresholds = db.list_federation_balance_thresholds().unwrap_or_default();
+
+        wallet.set_nostr_module(nostr_module.clone());
+
+        let signing_worker = SigningWorker::new(db.clone());
+
+        let (relays, relays_toast_or) = match db.iter_relays().collect::<anyhow::Result<Vec<_>>>() {
+            Ok(relays) => (relays, None),
+            Err(err) => (
+                Vec::new(),
+                Some(Toast {
+                    title: "Failed to load relays".to_string(),
+                    body: err.to_string(),
+                    status: ToastStatus::Bad,
+                }),
+            ),
+        };
+
+        let mut task = Task::done(app::Message::Routes(
+            super::Message::NavigateHomeAndSetConnectedState(ConnectedState {
+                db,
+                wallet,
+                settings,
+                in_flight_nip46_requests: VecDeque::new(),
+                nip46_identities: Vec::new(),
+                nip46_approval_hold_or: None,
+                approval_grants: ApprovalGrants::default(),
+                remembered_conversations: RememberedConversations::default(),
+                signing_metrics: SigningMetrics::default(),
+                signing_worker,
+                avatars: Avatars::default(),
+                profiles: Profiles::default(),
+                drafts: Drafts::default(),
+                in_flight_operations: InFlightOperations::default(),
+                federation_operations: BTreeMap::new(),
+                loadable_wallet_view: Loadable::Loading,
+                federation_metadata,
+                nostr_module,
+                nostr_state: NostrState::default(),
+                is_nip55_socket_listening: false,
+                cosigning_requests: Vec::new(),
+                exchange_rate_or: None,
+                zap_recipient_public_keys,
+                backup_settings,
+                nwc_connections,
+                cosigner_shares,
+                balance_thresholds,
+            }),
+        ));
+
+        for relay in relays {
+            task = task.chain(Task::done(app::Message::NostrModule(
+                NostrModuleMessage::ConnectToRelay(relay.websocket_url),
+            )));
+        }
+
+        if should_show_release_notes {
+            task = task.chain(Task::done(app::Message::Routes(super::Message::Navigate(
+                RouteName::Settings(super::settings::SubrouteName::WhatsNew),
+            ))));
+        }
+
+        for toast in [relays_toast_or, keychain_toast_or, recovery_toast_or]
+            .into_iter()
+            .flatten()
+        {
+            task = task.chain(Task::done(app::Message::AddToast(toast)));
+        }
+
+        if legacy::find_legacy_database().is_some() {
+            task = task.chain(Task::done(app::Message::AddToast(Toast {
+                title: "Found data from an older Keystache".to_string(),
+                body: "Its keys and connected apps can be imported in Settings > Import Old Data."
+                    .to_string(),
+                status: ToastStatus::Neutral,
+            })));
+        }
+
+        Task::batch([task, connect_to_federations_task, recover_federations_task])
+    }
+
+    pub fn view<'a>(&self) -> Column<'a, app::Message> {
+        let Self {
+            password,
+            is_secure,
+            db_already_exists,
+            remember_password,
+            has_keychain_password,
+            failed_unlock_attempts: _,
+            remaining_cooldown_or,
+            restore_path_input,
+            restore_seed_input,
+            restore_invite_codes_input,
+        } = self;
+
+        let text_input = text_input("Password", password)
+            .on_input(|input| {
+                app::Message::Routes(super::Message::UnlockPage(Message::PasswordInputChanged(
+                    input,
+                )))
+            })
+            .padding(10)
+            .size(30);
+
+        let container_name = if *db_already_exists {
+            "Enter Password"
+        } else {
+            "Choose a Password"
+        };
+
+        let description = if *db_already_exists {
+            "Your Keystache database is password-encrypted. Enter your password to unlock it."
+        } else {
+            "Keystache will encrypt all of your data at rest. If you forget your password, your keys will be unrecoverable from Keystache. So make sure to backup your keys and keep your password somewhere safe."
+        };
+
+        let next_button_text = if *db_already_exists {
+            "Unlock"
+        } else {
+            "Set Password"
+        };
+
+        let mut container = container(container_name)
+            .push(description)
+            .push(row![
+                text_input.secure(*is_secure),
+                Space::with_width(Pixels(20.0)),
+                checkbox("Show password", !is_secure).on_toggle(|_| app::Message::Routes(
+                    super::Message::UnlockPage(Message::ToggleSecureInput)
+                ))
+            ])
+            .push(
+                checkbox("Remember password in system keychain", *remember_password).on_toggle(
+                    |_| {
+                        app::Message::Routes(super::Message::UnlockPage(
+                            Message::ToggleRememberPassword,
+                        ))
+                    },
+                ),
+            )
+            .push_maybe(remaining_cooldown_or.map(|remaining_cooldown| {
+                Text::new(format!(
+                    "Too many failed attempts. Try again in {}.",
+                    format_cooldown(remaining_cooldown)
+                ))
+                .style(iced::widget::text::danger)
+            }))
+            .push(
+                icon_button(next_button_text, SvgIcon::LockOpen, PaletteColor::Primary)
+                    .on_press_maybe(
+                        (!password.is_empty() && remaining_cooldown_or.is_none()).then_some(
+                            app::Message::Routes(super::Message::UnlockPage(
+                                Message::PasswordSubmitted,
+                            )),
+                        ),
+                    ),
+            );
+
+        if *db_already_exists && *has_keychain_password {
+            container = container.push(
+                icon_button(
+                    "Unlock With System Keychain",
+                    SvgIcon::Key,
+                    PaletteColor::Primary,
+                )
+                .on_press(app::Message::Routes(super::Message::UnlockPage(
+                    Message::UnlockWithKeychain,
+                ))),
+            );
+        }
+
+        if *db_already_exists {
+            container = container.push(
+                icon_button("Delete All Data", SvgIcon::Delete, PaletteColor::Danger)
+                    .on_press(app::Message::DbDeleteAllData),
+            );
+        } else {
+            container = container
+                .push(Text::new("Restore From Backup").size(25))
+                .push(Text::new(
+                    "Or start from a backup exported in Keystache's backup settings. Enter the backup's password above, which becomes your password.",
+                ))
+                .push(
+                    text_input("Backup file", restore_path_input)
+                        .on_input(|input| {
+                            app::Message::Routes(super::Message::UnlockPage(
+                                Message::RestorePathInputChanged(input),
+                            ))
+                        })
+                        .padding(10)
+                        .size(30),
+                )
+                .push(
+                    icon_button("Restore", SvgIcon::ArrowDownward, PaletteColor::Primary)
+                        .on_press_maybe(
+                            (!password.is_empty() && !restore_path_input.trim().is_empty())
+                                .then_some(app::Message::Routes(super::Message::UnlockPage(
+                                    Message::RestoreSubmitted,
+                                ))),
+                        ),
+                );
+
+            let mnemonic_or = Mnemonic::from_str(restore_seed_input.trim())
+                .ok()
+                .filter(|mnemonic| matches!(mnemonic.word_count(), 12 | 24));
+
+            let invite_codes_or = parse_invite_codes(restore_invite_codes_input);
+
+            container = container
+                .push(Text::new("Restore Wallet From Seed").size(25))
+                .push(Text::new(
+                    "Or restore a Bitcoin wallet from its 12 or 24 word seed, found in Keystache's wallet seed settings. Enter the invite codes of the federations it held e-cash in, separated by spaces, and its e-cash is recovered from them once Keystache is unlocked. Your keys and other data aren't part of the seed.",
+                ))
+                .push(
+                    text_input("Seed", restore_seed_input)
+                        .on_input(|input| {
+                            app::Message::Routes(super::Message::UnlockPage(
+                                Message::RestoreSeedInputChanged(input),
+                            ))
+                        })
+                        .secure(*is_secure)
+                        .padding(10)
+                        .size(30),
+                )
+                .push_maybe(
+                    (!restore_seed_input.trim().is_empty() && mnemonic_or.is_none()).then(|| {
+                        Text::new("This isn't a valid 12 or 24 word seed")
+                            .style(iced::widget::text::danger)
+                    }),
+                )
+                .push(
+                    text_input("Federation invite codes", restore_invite_codes_input)
+                        .on_input(|input| {
+                            app::Message::Routes(super::Message::UnlockPage(
+                                Message::RestoreInviteCodesInputChanged(input),
+                            ))
+                        })
+                        .padding(10)
+                        .size(30),
+                )
+                .push_maybe(invite_codes_or.is_none().then(|| {
+                    Text::new("One of these isn't a valid invite code")
+                        .style(iced::widget::text::danger)
+                }))
+                .push(
+                    icon_button("Restore Wallet", SvgIcon::Key, PaletteColor::Primary)
+                        .on_press_maybe(
+                            mnemonic_or
+                                .zip(invite_codes_or)
+                                .filter(|_| !password.is_empty())
+                                .map(|(mnemonic, invite_codes)| {
+                                    app::Message::Routes(super::Message::UnlockPage(
+                                        Message::RestoreSeedSubmitted {
+                                            mnemonic,
+                                            invite_codes,
+                                        },
+                                    ))
+                                }),
+                        ),
+                );
+        }
+
+        container
+    }
+}
+
+/// Gets the wallet's master key from the seed saved in `db`,
+/// generating and saving a seed first if there isn't one yet.
+fn load_or_create_wallet_xpriv(db: &Database, project_dirs: &ProjectDirs) -> anyhow::Result<Xpriv> {
+    if let Some(mnemonic) = db.get_wallet_mnemonic()? {
+        return get_wallet_xpriv(&mnemonic, WALLET_NETWORK);
+    }
+
+    // Federation clients can only be opened with the key they were created with, so
+    // wallets that joined federations before seeds were generated keep the old key.
+    // TODO: Let these wallets move their funds to a seeded wallet.
+    if has_joined_federations(WALLET_NETWORK, project_dirs) {
+        return Ok(Xpriv::new_master(WALLET_NETWORK, &LEGACY_WALLET_SEED)?);
+    }
+
+    let mnemonic = generate_wallet_mnemonic();
+    db.save_wallet_mnemonic(&mnemonic)?;
+
+    get_wallet_xpriv(&mnemonic, WALLET_NETWORK)
+}
+
+/// Reads invite codes separated by whitespace or commas.
+/// Returns `None` if any of them isn't a valid invite code.
+fn parse_invite_codes(input: &str) -> Option<Vec<InviteCode>> {
+    input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|invite_code| !invite_code.is_empty())
+        .map(|invite_code| InviteCode::from_str(invite_code).ok())
+        .collect()
+}
+
+/// Formats a cooldown in whole seconds, rounding up so that it never reads as zero.
+fn format_cooldown(cooldown: Duration) -> String {
+    match cooldown.as_millis().div_ceil(1000) {
+        1 => "1 second".to_string(),
+        seconds => format!("{seconds} seconds"),
+    }
+}
+
+/// Shown when the database is bound to this device but the system keychain
+/// can't be read, so that it isn't mistaken for a wrong password.
+fn device_key_unavailable_toast(err: &anyhow::Error) -> Toast {
+    Toast {
+        title: "Device key unavailable".to_string(),
+        body: format!("{err}. Unlock the system keychain and try again."),
+        status: ToastStatus::Bad,
+    }
+}
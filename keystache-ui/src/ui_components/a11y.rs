@@ -0,0 +1,39 @@
+use iced::{
+    widget::{
+        container,
+        text_input::{self, Status},
+        tooltip, TextInput,
+    },
+    Element, Theme,
+};
+
+use crate::app;
+
+/// Gives a control that doesn't show any text, such as an icon-only button, a name that says
+/// what it does. iced doesn't expose widgets to screen readers yet, so for now the name is
+/// shown as a tooltip when the control is hovered.
+pub fn labeled<'a>(
+    label: &'a str,
+    content: impl Into<Element<'a, app::Message>>,
+) -> Element<'a, app::Message> {
+    tooltip(content, label, tooltip::Position::Bottom)
+        .padding(5)
+        .style(container::rounded_box)
+        .into()
+}
+
+/// A text input whose focus outline is thick enough to stand out against the dark theme.
+pub fn text_input<'a>(placeholder: &str, value: &str) -> TextInput<'a, app::Message> {
+    iced::widget::text_input(placeholder, value).style(focus_visible_text_input_style)
+}
+
+fn focus_visible_text_input_style(theme: &Theme, status: Status) -> text_input::Style {
+    let mut style = text_input::default(theme, status);
+
+    if status == Status::Focused {
+        style.border.width = 2.0;
+        style.border.color = theme.extended_palette().primary.base.color;
+    }
+
+    style
+}
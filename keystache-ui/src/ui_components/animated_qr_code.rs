@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use iced::widget::{qr_code::Data, Column, QRCode, Text};
+
+use crate::{app, bbqr};
+
+/// How long each part of an animated QR code is shown for. Slow enough
+/// for scanners to pick up every part within a few cycles.
+pub const ANIMATED_QR_CODE_FRAME_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Text up to this long is shown as a single QR code, which any scanner can read.
+const MAX_SINGLE_PART_LEN: usize = 600;
+
+/// How much data each part of an animated QR code holds. Small enough
+/// that each part stays easy to scan from a screen.
+const PART_DATA_LEN: usize = 400;
+
+/// A QR code for text that may be too long to scan comfortably in one go, such as a large
+/// invoice. Long text is split into a `BBQr` code, whose parts are shown one after another.
+pub struct AnimatedQrCode {
+    parts: Vec<Data>,
+    current_part_index: usize,
+}
+
+impl AnimatedQrCode {
+    pub fn new(text: &str) -> anyhow::Result<Self> {
+        let parts = if text.len() <= MAX_SINGLE_PART_LEN {
+            vec![Data::new(text)?]
+        } else {
+            bbqr::split(text, PART_DATA_LEN)?
+                .into_iter()
+                .map(Data::new)
+                .collect::<Result<_, _>>()?
+        };
+
+        Ok(Self {
+            parts,
+            current_part_index: 0,
+        })
+    }
+
+    /// Whether the text was split into parts, which need [`Self::show_next_part`] called regularly.
+    pub fn is_animated(&self) -> bool {
+        self.parts.len() > 1
+    }
+
+    /// Moves on to the next part, or back to the first part after the last.
+    pub fn show_next_part(&mut self) {
+        self.current_part_index = (self.current_part_index + 1) % self.parts.len();
+    }
+
+    pub fn view(&self) -> Column<app::Message> {
+        Column::new()
+            .push(QRCode::new(&self.parts[self.current_part_index]))
+            .push_maybe(self.is_animated().then(|| {
+                Text::new(format!(
+                    "Part {} of {}. Scan with a wallet that supports animated QR codes.",
+                    self.current_part_index + 1,
+                    self.parts.len()
+                ))
+                .size(14)
+            }))
+            .spacing(10)
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use iced::{
+    widget::{image, Image},
+    Task,
+};
+use nostr_sdk::{
+    bitcoin::hashes::{sha256, Hash},
+    Filter, JsonUtil, Kind, Metadata, PublicKey,
+};
+use palette::{rgb::Rgb, FromColor, Hsl};
+
+use crate::{app, nostr::NostrModule};
+
+// Relays that take longer than this are left out of the profile, and are recorded as slow.
+const PROFILE_FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+const PICTURE_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(20);
+
+// Profile pictures are shown as small thumbnails, so anything larger
+// than this is either a mistake or an attempt to waste bandwidth.
+const MAX_PICTURE_SIZE_BYTES: u64 = 2 * 1024 * 1024;
+
+// Identicons are a mirrored 5x5 grid of cells, each drawn as a square of pixels.
+const IDENTICON_GRID_SIZE: usize = 5;
+const IDENTICON_CELL_SIZE_PIXELS: usize = 8;
+const IDENTICON_BACKGROUND_RGBA: [u8; 4] = [240, 240, 240, 255];
+
+/// Profile pictures for public keys, loaded from their kind-0 metadata.
+/// Public keys without a loaded picture are shown with an identicon.
+#[derive(Debug, Clone, Default)]
+pub struct Avatars {
+    picture_by_public_key: HashMap<PublicKey, image::Handle>,
+    identicon_by_public_key: HashMap<PublicKey, image::Handle>,
+    // Public keys whose picture has already been requested, whether or not it loaded.
+    requested_public_keys: HashSet<PublicKey>,
+    // Public keys whose profile was found on relays, but wasn't signed by the key itself.
+    unverified_public_keys: HashSet<PublicKey>,
+}
+
+impl Avatars {
+    /// Starts loading the profile pictures of any public keys that haven't been requested yet.
+    /// Each picture that loads is passed back through [`app::Message::AvatarLoaded`].
+    /// Nothing is loaded in low data mode, so the pictures are requested again once it's off.
+    pub fn request(
+        &mut self,
+        public_keys: impl IntoIterator<Item = PublicKey>,
+        nostr_module: &NostrModule,
+    ) -> Task<app::Message> {
+        if nostr_module.is_low_data_mode() {
+            return Task::none();
+        }
+
+        let mut tasks = Vec::new();
+
+        for public_key in public_keys {
+            if !self.requested_public_keys.insert(public_key) {
+                continue;
+            }
+
+            self.identicon_by_public_key
+                .insert(public_key, identicon(&public_key));
+
+            let nostr_module = nostr_module.clone();
+
+            tasks.push(
+                Task::future(async move {
+                    match fetch_picture(&nostr_module, public_key).await {
+                        Ok(picture_bytes) => {
+                            Some(app::Message::AvatarLoaded(public_key, picture_bytes))
+                        }
+                        Err(err) if err.is::<UnverifiedProfileError>() => {
+                            Some(app::Message::AvatarUnverified(public_key))
+                        }
+                        // TODO: Log a warning if the picture fails to load.
+                        Err(_) => None,
+                    }
+                })
+                .and_then(Task::done),
+            );
+        }
+
+        Task::batch(tasks)
+    }
+
+    pub fn insert(&mut self, public_key: PublicKey, picture_bytes: Vec<u8>) {
+        self.picture_by_public_key
+            .insert(public_key, image::Handle::from_bytes(picture_bytes));
+    }
+
+    pub fn mark_unverified(&mut self, public_key: PublicKey) {
+        self.unverified_public_keys.insert(public_key);
+    }
+
+    /// Whether the profile of `public_key` was found on relays but failed verification.
+    /// Anything shown for such a key may have been made up by a relay.
+    pub fn is_unverified(&self, public_key: &PublicKey) -> bool {
+        self.unverified_public_keys.contains(public_key)
+    }
+}
+
+/// Returned when profile metadata was found on relays,
+/// but none of it was signed by the key it claims to be from.
+#[derive(Debug)]
+struct UnverifiedProfileError;
+
+impl std::fmt::Display for UnverifiedProfileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Profile metadata failed verification")
+    }
+}
+
+impl std::error::Error for UnverifiedProfileError {}
+
+/// A square avatar for `public_key`. Shows the profile picture if it has
+/// been loaded into `avatars`, and an identicon otherwise.
+pub fn avatar(public_key: &PublicKey, avatars: &Avatars, size: f32) -> Image<image::Handle> {
+    let handle = avatars
+        .picture_by_public_key
+        .get(public_key)
+        .or_else(|| avatars.identicon_by_public_key.get(public_key))
+        .cloned()
+        .unwrap_or_else(|| identicon(public_key));
+
+    Image::new(handle).width(size).height(size)
+}
+
+/// Fetches the profile picture from the newest kind-0 metadata of `public_key`.
+/// Downloaded pictures are cached on disk by URL.
+async fn fetch_picture(
+    nostr_module: &NostrModule,
+    public_key: PublicKey,
+) -> anyhow::Result<Vec<u8>> {
+    let events = nostr_module
+        .fetch_events(
+            vec![Filter::new()
+                .author(public_key)
+                .kind(Kind::Metadata)
+                .limit(1)],
+            PROFILE_FETCH_TIMEOUT,
+        )
+        .await?;
+
+    if events.is_empty() {
+        bail!("No profile metadata found");
+    }
+
+    // Relays could make up metadata for any key, so only signed metadata is used.
+    let event = events
+        .into_iter()
+        .filter(|event| event.verify().is_ok())
+        .max_by_key(|event| event.created_at)
+        .ok_or(UnverifiedProfileError)?;
+
+    let picture_url = Metadata::from_json(&event.content)?
+        .picture
+        .context("Profile has no picture")?;
+
+    let cache_path =
+        get_picture_cache_dir()?.join(sha256::Hash::hash(picture_url.as_bytes()).to_string());
+
+    if let Ok(picture_bytes) = std::fs::read(&cache_path) {
+        return Ok(picture_bytes);
+    }
+
+    let response = reqwest::Client::builder()
+        .timeout(PICTURE_DOWNLOAD_TIMEOUT)
+        .build()?
+        .get(&picture_url)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    if response
+        .content_length()
+        .is_some_and(|content_length| content_length > MAX_PICTURE_SIZE_BYTES)
+    {
+        bail!("Profile picture is too large");
+    }
+
+    let picture_bytes = response.bytes().await?.to_vec();
+
+    if u64::try_from(picture_bytes.len())? > MAX_PICTURE_SIZE_BYTES {
+        bail!("Profile picture is too large");
+    }
+
+    if let Some(cache_dir) = cache_path.parent() {
+        std::fs::create_dir_all(cache_dir)?;
+    }
+    std::fs::write(&cache_path, &picture_bytes)?;
+
+    Ok(picture_bytes)
+}
+
+fn get_picture_cache_dir() -> anyhow::Result<PathBuf> {
+    let project_dirs = directories::ProjectDirs::from("co", "nodetec", "keystache")
+        .context("Failed to get project directories")?;
+
+    Ok(project_dirs.cache_dir().join("avatars"))
+}
+
+/// Generates a symmetric pattern of colored cells that is unique to `public_key`,
+/// so that keys without a profile picture can still be told apart at a glance.
+fn identicon(public_key: &PublicKey) -> image::Handle {
+    let size_pixels = IDENTICON_GRID_SIZE * IDENTICON_CELL_SIZE_PIXELS;
+
+    let pixels = identicon_pixels(&public_key.to_bytes());
+
+    image::Handle::from_rgba(
+        u32::try_from(size_pixels).unwrap_or_default(),
+        u32::try_from(size_pixels).unwrap_or_default(),
+        pixels,
+    )
+}
+
+/// Renders an identicon as RGBA pixels. The first byte picks the color,
+/// and the following bytes pick which cells in the left half are filled.
+/// The right half mirrors the left half.
+fn identicon_pixels(seed: &[u8; 32]) -> Vec<u8> {
+    let hue = f32::from(seed[0]) / 255.0 * 360.0;
+    let color: Rgb = Rgb::from_color(Hsl::new_srgb(hue, 0.5, 0.55));
+    let color = color.into_format::<u8>();
+    let color_rgba = [color.red, color.green, color.blue, 255];
+
+    let half_grid_size = IDENTICON_GRID_SIZE.div_ceil(2);
+    let size_pixels = IDENTICON_GRID_SIZE * IDENTICON_CELL_SIZE_PIXELS;
+
+    let mut pixels = Vec::with_capacity(size_pixels * size_pixels * 4);
+
+    for y in 0..size_pixels {
+        for x in 0..size_pixels {
+            let row = y / IDENTICON_CELL_SIZE_PIXELS;
+            let column = x / IDENTICON_CELL_SIZE_PIXELS;
+            let mirrored_column = column.min(IDENTICON_GRID_SIZE - 1 - column);
+
+            let is_filled = seed[1 + row * half_grid_size + mirrored_column] & 1 == 1;
+
+            pixels.extend_from_slice(if is_filled {
+                &color_rgba
+            } else {
+                &IDENTICON_BACKGROUND_RGBA
+            });
+        }
+    }
+
+    pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identicon_pixels_are_mirrored() {
+        let seed: [u8; 32] = std::array::from_fn(|i| u8::try_from(i * 7).unwrap());
+
+        let pixels = identicon_pixels(&seed);
+        let size_pixels = IDENTICON_GRID_SIZE * IDENTICON_CELL_SIZE_PIXELS;
+        assert_eq!(pixels.len(), size_pixels * size_pixels * 4);
+
+        let get_pixel = |x: usize, y: usize| {
+            let index = (y * size_pixels + x) * 4;
+            &pixels[index..index + 4]
+        };
+
+        for y in 0..size_pixels {
+            for x in 0..size_pixels {
+                assert_eq!(get_pixel(x, y), get_pixel(size_pixels - 1 - x, y));
+            }
+        }
+
+        // Different seeds produce different identicons.
+        assert_ne!(pixels, identicon_pixels(&[0; 32]));
+    }
+}
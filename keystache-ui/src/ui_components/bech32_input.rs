@@ -0,0 +1,216 @@
+use std::{fmt::Display, str::FromStr};
+
+use fedimint_core::invite_code::InviteCode;
+use iced::widget::{text, Column, Text};
+use lightning_invoice::Bolt11Invoice;
+use nostr_sdk::{nips::nip49::EncryptedSecretKey, FromBech32, PublicKey, SecretKey};
+
+use crate::{app, lnurl};
+
+use super::{text_input, Toast, ToastStatus};
+
+/// URI schemes that are stripped from pasted entities, such as `nostr:npub1...`.
+const URI_SCHEMES: [&str; 3] = ["nostr:", "lightning:", "fedimint:"];
+
+/// A kind of bech32-encoded entity that can be pasted into Keystache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bech32Kind {
+    Npub,
+    Nsec,
+    Ncryptsec,
+    FederationInviteCode,
+    LightningInvoice,
+    Lnurl,
+    /// Not bech32, but pasted wherever LNURLs are. See [`lnurl::parse_pay_url`].
+    LightningAddress,
+}
+
+impl Bech32Kind {
+    /// Detects the kind of entity from its human-readable part, ignoring case.
+    /// The rest of the entity isn't checked. See [`Self::is_valid`].
+    pub fn detect(input: &str) -> Option<Self> {
+        let input = input.to_lowercase();
+
+        if input.starts_with("npub1") {
+            Some(Self::Npub)
+        } else if input.starts_with("nsec1") {
+            Some(Self::Nsec)
+        } else if input.starts_with("ncryptsec1") {
+            Some(Self::Ncryptsec)
+        } else if input.starts_with("fed1") {
+            Some(Self::FederationInviteCode)
+        } else if input.starts_with("lnurl1") {
+            Some(Self::Lnurl)
+        } else if input.starts_with("ln") && !input.starts_with("lnurl") {
+            Some(Self::LightningInvoice)
+        } else if input.contains('@') {
+            Some(Self::LightningAddress)
+        } else {
+            None
+        }
+    }
+
+    /// Whether `input` is a well-formed entity of this kind, including its checksum.
+    pub fn is_valid(self, input: &str) -> bool {
+        match self {
+            Self::Npub => PublicKey::from_bech32(input).is_ok(),
+            Self::Nsec => SecretKey::from_bech32(input).is_ok(),
+            Self::Ncryptsec => EncryptedSecretKey::from_bech32(input).is_ok(),
+            Self::FederationInviteCode => InviteCode::from_str(input).is_ok(),
+            Self::LightningInvoice => Bolt11Invoice::from_str(input).is_ok(),
+            Self::Lnurl | Self::LightningAddress => lnurl::parse_pay_url(input).is_some(),
+        }
+    }
+}
+
+impl Display for Bech32Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Npub => write!(f, "public key (npub)"),
+            Self::Nsec => write!(f, "secret key (nsec)"),
+            Self::Ncryptsec => write!(f, "encrypted secret key (ncryptsec)"),
+            Self::FederationInviteCode => write!(f, "federation invite code"),
+            Self::LightningInvoice => write!(f, "lightning invoice"),
+            Self::Lnurl => write!(f, "LNURL"),
+            Self::LightningAddress => write!(f, "lightning address"),
+        }
+    }
+}
+
+/// A text input for bech32-encoded entities. Whitespace and URI schemes are stripped from
+/// the input, and the detected kind of entity is shown below it along with whether it's valid.
+/// Entities that aren't one of `accepted_kinds` aren't entered at all, so that a secret key
+/// can't end up in a field meant for something else.
+pub fn bech32_input<'a>(
+    placeholder: &str,
+    value: &str,
+    accepted_kinds: &'a [Bech32Kind],
+    on_input: impl Fn(String) -> app::Message + 'a,
+) -> Column<'a, app::Message> {
+    let input = text_input(placeholder, value)
+        .on_input(move |input| {
+            let input = normalize_bech32_input(&input);
+
+            match Bech32Kind::detect(&input) {
+                Some(kind) if !accepted_kinds.contains(&kind) => app::Message::AddToast(Toast {
+                    title: "Wrong kind of input".to_string(),
+                    body: format!(
+                        "Pasted {kind} wasn't entered. Expected {}.",
+                        describe_kinds(accepted_kinds)
+                    ),
+                    status: ToastStatus::Bad,
+                }),
+                _ => on_input(input),
+            }
+        })
+        .padding(10)
+        .size(30);
+
+    Column::new()
+        .push(input)
+        .push_maybe(bech32_input_status(value, accepted_kinds))
+        .spacing(5)
+}
+
+fn bech32_input_status<'a>(value: &str, accepted_kinds: &[Bech32Kind]) -> Option<Text<'a>> {
+    if value.is_empty() {
+        return None;
+    }
+
+    Some(match Bech32Kind::detect(value) {
+        Some(kind) if kind.is_valid(value) => {
+            Text::new(format!("Valid {kind}")).style(text::success)
+        }
+        Some(kind) => Text::new(format!("Invalid {kind}. Check that it was copied in full."))
+            .style(text::danger),
+        None => {
+            Text::new(format!("Expected {}", describe_kinds(accepted_kinds))).style(text::danger)
+        }
+    })
+}
+
+/// Strips surrounding whitespace and any URI scheme from a pasted entity.
+/// Trims `input` and strips any URI scheme from it, such as `lightning:`.
+pub fn normalize_bech32_input(input: &str) -> String {
+    let input = input.trim();
+
+    URI_SCHEMES
+        .iter()
+        .find_map(|scheme| {
+            input
+                .get(..scheme.len())
+                .filter(|prefix| prefix.eq_ignore_ascii_case(scheme))
+                .map(|_| &input[scheme.len()..])
+        })
+        .unwrap_or(input)
+        .to_string()
+}
+
+fn describe_kinds(kinds: &[Bech32Kind]) -> String {
+    kinds
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" or ")
+}
+
+#[cfg(test)]
+mod tests {
+    use nostr_sdk::{Keys, ToBech32};
+
+    use super::*;
+
+    #[test]
+    fn test_detect_and_validate_bech32_kind() {
+        let keys = Keys::generate();
+        let npub = keys.public_key().to_bech32().unwrap();
+        let nsec = keys.secret_key().to_bech32().unwrap();
+
+        assert_eq!(Bech32Kind::detect(&npub), Some(Bech32Kind::Npub));
+        assert_eq!(Bech32Kind::detect(&nsec), Some(Bech32Kind::Nsec));
+        assert_eq!(
+            Bech32Kind::detect(&npub.to_uppercase()),
+            Some(Bech32Kind::Npub)
+        );
+        assert_eq!(
+            Bech32Kind::detect("ncryptsec1qgg9947rlpvqu76pj5ecreduf9jxhselq2nae2kghhvd5g7dgjtcxfqtd67p9m0w57lspw8gsq6yphnm8623nsl8xn9j4jdzz84zm3frztj3z7s35vpzmqf6ksu8r89qk5z2zxfmu5gv8th8wclt0h4p"),
+            Some(Bech32Kind::Ncryptsec)
+        );
+        assert_eq!(
+            Bech32Kind::detect("fed11qgqrgvnhwden5te0v9k8q6rp9ekh2arfdeukuet595cr2ttpd3jhq6rzve6zuer9wchxvetyd938gcewvdhk6tcqqysptkuvknc7erjgf4em3zfh90kffqf9srujn6q53d6r056e4apze5cw27h75"),
+            Some(Bech32Kind::FederationInviteCode)
+        );
+        assert_eq!(
+            Bech32Kind::detect("lnbc2500u1pvjluez"),
+            Some(Bech32Kind::LightningInvoice)
+        );
+        assert_eq!(
+            Bech32Kind::detect("lnurl1dp68gurn8ghj7"),
+            Some(Bech32Kind::Lnurl)
+        );
+        assert_eq!(
+            Bech32Kind::detect("satoshi@example.com"),
+            Some(Bech32Kind::LightningAddress)
+        );
+        assert_eq!(Bech32Kind::detect("hello"), None);
+
+        assert!(Bech32Kind::Npub.is_valid(&npub));
+        assert!(Bech32Kind::Nsec.is_valid(&nsec));
+
+        // A typo breaks the checksum.
+        let mut typo_npub = npub.clone();
+        let last_char = typo_npub.pop().unwrap();
+        typo_npub.push(if last_char == 'q' { 'p' } else { 'q' });
+        assert!(!Bech32Kind::Npub.is_valid(&typo_npub));
+    }
+
+    #[test]
+    fn test_normalize_bech32_input() {
+        assert_eq!(normalize_bech32_input("  npub1abc \n"), "npub1abc");
+        assert_eq!(normalize_bech32_input("nostr:npub1abc"), "npub1abc");
+        assert_eq!(normalize_bech32_input("NOSTR:npub1abc"), "npub1abc");
+        assert_eq!(normalize_bech32_input("lightning:lnbc1abc"), "lnbc1abc");
+        assert_eq!(normalize_bech32_input("npub1abc"), "npub1abc");
+        assert_eq!(normalize_bech32_input(""), "");
+    }
+}
@@ -3,7 +3,7 @@ use iced::{
         button::{self, Status},
         row, text, Button,
     },
-    Border, Color, Length, Shadow, Theme,
+    Border, Color, Element, Length, Shadow, Theme,
 };
 
 use crate::{
@@ -12,16 +12,20 @@ use crate::{
     util::{darken, lighten},
 };
 
-use super::{PaletteColor, SvgIcon};
+use super::{labeled, PaletteColor, SvgIcon};
 
-pub fn mini_icon_button_no_text<'a>(
+/// A small button that only shows an icon. `label` says what the button does,
+/// since the icon alone doesn't. See [`labeled`].
+pub fn mini_icon_button<'a>(
+    label: &'a str,
     icon: SvgIcon,
     palette_color: PaletteColor,
-) -> Button<'a, app::Message, Theme> {
+    on_press_or: Option<app::Message>,
+) -> Element<'a, app::Message> {
     // TODO: Find a way to darken the icon color when the button is disabled.
     let svg = icon.view(16.0, 16.0, Color::WHITE);
 
-    Button::new(svg)
+    let button = Button::new(svg)
         .style(move |theme, status| {
             let border = Border {
                 color: iced::Color::WHITE,
@@ -55,6 +59,9 @@ pub fn mini_icon_button_no_text<'a>(
             }
         })
         .padding(6)
+        .on_press_maybe(on_press_or);
+
+    labeled(label, button)
 }
 
 pub fn icon_button(
@@ -109,8 +116,19 @@ pub fn sidebar_button<'a>(
     self_route_name: &RouteName,
     app: &app::App,
 ) -> Button<'a, app::Message, Theme> {
-    let is_active = self_route_name.is_same_top_level_route_as(&app.page.to_name());
+    sidebar_toggle_button(
+        text_str,
+        icon,
+        self_route_name.is_same_top_level_route_as(&app.page.to_name()),
+    )
+}
 
+/// A sidebar button that isn't tied to a route, such as one that opens a panel.
+pub fn sidebar_toggle_button(
+    text_str: &str,
+    icon: SvgIcon,
+    is_active: bool,
+) -> Button<'_, app::Message, Theme> {
     // TODO: Find a way to darken the icon color when the button is disabled.
     let svg = icon.view(24.0, 24.0, Color::WHITE);
     let content = row![svg, text(text_str).size(24.0)]
@@ -29,6 +29,8 @@ pub enum SvgIcon {
     Key,
     Lock,
     LockOpen,
+    Notifications,
+    Refresh,
     Save,
     Send,
     Settings,
@@ -67,6 +69,8 @@ impl SvgIcon {
             Self::Key => icon_handle!("key.svg"),
             Self::Lock => icon_handle!("lock.svg"),
             Self::LockOpen => icon_handle!("lock_open.svg"),
+            Self::Notifications => icon_handle!("notifications.svg"),
+            Self::Refresh => icon_handle!("refresh.svg"),
             Self::Save => icon_handle!("save.svg"),
             Self::Send => icon_handle!("send.svg"),
             Self::Settings => icon_handle!("settings.svg"),
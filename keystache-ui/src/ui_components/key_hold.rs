@@ -0,0 +1,141 @@
+use std::{
+    f32::consts::{FRAC_PI_2, TAU},
+    time::{Duration, Instant},
+};
+
+use iced::{
+    mouse,
+    widget::{
+        canvas::{self, path::Arc, Path, Stroke},
+        Canvas,
+    },
+    Radians, Rectangle, Renderer, Theme,
+};
+
+use crate::app;
+
+/// A key being held down to confirm something. Confirming takes a deliberate hold
+/// rather than a single press, so that a stray key press can't confirm by accident.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyHold {
+    start_time: Instant,
+    // From 0.0 when the key was pressed to 1.0 once it has been held long enough.
+    progress: f32,
+}
+
+impl KeyHold {
+    pub const fn new(start_time: Instant) -> Self {
+        Self {
+            start_time,
+            progress: 0.0,
+        }
+    }
+
+    /// Updates the progress of the hold to `now`, and returns
+    /// whether the key has now been held for `duration`.
+    pub fn update(&mut self, now: Instant, duration: Duration) -> bool {
+        let held = now.saturating_duration_since(self.start_time);
+
+        self.progress = if duration.is_zero() {
+            1.0
+        } else {
+            (held.as_secs_f32() / duration.as_secs_f32()).min(1.0)
+        };
+
+        self.is_complete()
+    }
+
+    pub const fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.progress >= 1.0
+    }
+
+    /// Completes the hold early, so that it can't confirm anything else until the key is released.
+    pub fn complete(&mut self) {
+        self.progress = 1.0;
+    }
+}
+
+/// A ring that fills clockwise from the top as `progress` goes from 0.0 to 1.0.
+pub fn progress_ring(progress: f32, size: f32) -> Canvas<ProgressRing, app::Message> {
+    Canvas::new(ProgressRing {
+        progress: progress.clamp(0.0, 1.0),
+    })
+    .width(size)
+    .height(size)
+}
+
+pub struct ProgressRing {
+    progress: f32,
+}
+
+impl canvas::Program<app::Message> for ProgressRing {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        const STROKE_WIDTH: f32 = 4.0;
+
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+
+        let center = frame.center();
+        let radius = (frame.width().min(frame.height()) - STROKE_WIDTH) / 2.0;
+
+        frame.stroke(
+            &Path::circle(center, radius),
+            Stroke::default()
+                .with_width(STROKE_WIDTH)
+                .with_color(theme.extended_palette().background.strong.color),
+        );
+
+        if self.progress > 0.0 {
+            let start_angle = -FRAC_PI_2;
+
+            frame.stroke(
+                &Path::new(|builder| {
+                    builder.arc(Arc {
+                        center,
+                        radius,
+                        start_angle: Radians(start_angle),
+                        end_angle: Radians(TAU.mul_add(self.progress, start_angle)),
+                    });
+                }),
+                Stroke::default()
+                    .with_width(STROKE_WIDTH)
+                    .with_color(theme.palette().primary),
+            );
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_hold() {
+        let start_time = Instant::now();
+        let mut key_hold = KeyHold::new(start_time);
+
+        assert!(!key_hold.update(
+            start_time + Duration::from_millis(250),
+            Duration::from_secs(1)
+        ));
+        assert!((key_hold.progress() - 0.25).abs() < f32::EPSILON);
+
+        assert!(key_hold.update(start_time + Duration::from_secs(2), Duration::from_secs(1)));
+        assert!((key_hold.progress() - 1.0).abs() < f32::EPSILON);
+        assert!(key_hold.is_complete());
+    }
+}
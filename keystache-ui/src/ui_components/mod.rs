@@ -1,3 +1,15 @@
+mod a11y;
+pub use a11y::*;
+
+mod animated_qr_code;
+pub use animated_qr_code::*;
+
+mod avatar;
+pub use avatar::*;
+
+mod bech32_input;
+pub use bech32_input::*;
+
 mod button;
 pub use button::*;
 
@@ -5,9 +17,21 @@ mod icon;
 use iced::{Color, Theme};
 pub use icon::*;
 
+mod key_hold;
+pub use key_hold::*;
+
+mod pagination;
+pub use pagination::*;
+
+mod selectable_list;
+pub use selectable_list::*;
+
 mod sidebar;
 pub use sidebar::*;
 
+mod sparkline;
+pub use sparkline::*;
+
 mod toast;
 pub use toast::*;
 
@@ -0,0 +1,29 @@
+use iced::{
+    widget::{row, Row, Text},
+    Alignment,
+};
+
+use crate::app;
+
+use super::{icon_button, PaletteColor, SvgIcon};
+
+/// Number of items shown per page in paginated lists.
+pub const PAGE_SIZE: i64 = 20;
+
+/// How many of the items are shown, with a "Load More" button while some aren't.
+/// For lists that grow by [`PAGE_SIZE`] as the user scrolls, rather than being paged through.
+pub fn load_more_controls<'a>(
+    shown_count: usize,
+    item_count: i64,
+    on_load_more: app::Message,
+) -> Row<'a, app::Message> {
+    let has_more = i64::try_from(shown_count).is_ok_and(|shown_count| shown_count < item_count);
+
+    row![Text::new(format!("Showing {shown_count} of {item_count}"))]
+        .push_maybe(has_more.then(|| {
+            icon_button("Load More", SvgIcon::ChevronRight, PaletteColor::Background)
+                .on_press(on_load_more)
+        }))
+        .spacing(10)
+        .align_y(Alignment::Center)
+}
@@ -0,0 +1,162 @@
+use std::{collections::BTreeSet, rc::Rc};
+
+use iced::{
+    widget::{checkbox, row, Column, Space, Text},
+    Alignment, Element,
+};
+
+use crate::app;
+
+use super::{icon_button, PaletteColor, SvgIcon};
+
+/// Which rows of a [`selectable_list`] are selected, and whether
+/// the user is being asked to confirm a bulk action on them.
+#[derive(Debug, Clone)]
+pub struct SelectableListState<K> {
+    selected_keys: BTreeSet<K>,
+    is_confirming_bulk_action: bool,
+}
+
+impl<K> Default for SelectableListState<K> {
+    fn default() -> Self {
+        Self {
+            selected_keys: BTreeSet::new(),
+            is_confirming_bulk_action: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SelectableListMessage<K> {
+    Toggle(K, bool),
+    SelectAll(BTreeSet<K>),
+    ClearSelection,
+    RequestBulkAction,
+    CancelBulkAction,
+}
+
+impl<K: Ord> SelectableListState<K> {
+    pub fn update(&mut self, msg: SelectableListMessage<K>) {
+        match msg {
+            SelectableListMessage::Toggle(key, is_selected) => {
+                if is_selected {
+                    self.selected_keys.insert(key);
+                } else {
+                    self.selected_keys.remove(&key);
+                }
+            }
+            SelectableListMessage::SelectAll(keys) => {
+                self.selected_keys = keys;
+            }
+            SelectableListMessage::ClearSelection => {
+                self.selected_keys.clear();
+            }
+            SelectableListMessage::RequestBulkAction => {
+                self.is_confirming_bulk_action = !self.selected_keys.is_empty();
+            }
+            SelectableListMessage::CancelBulkAction => {
+                self.is_confirming_bulk_action = false;
+            }
+        }
+
+        if self.selected_keys.is_empty() {
+            self.is_confirming_bulk_action = false;
+        }
+    }
+
+    /// Takes the selected keys, resetting the list to having nothing selected.
+    pub fn take_selected_keys(&mut self) -> BTreeSet<K> {
+        self.is_confirming_bulk_action = false;
+        std::mem::take(&mut self.selected_keys)
+    }
+}
+
+/// A list of rows that can each be selected with a checkbox, along with a
+/// "Select all" toggle and a bulk action that applies to every selected row.
+/// The bulk action only fires after a single confirmation.
+///
+/// * `rows` - Each row's content, keyed by the value used to identify it when selected.
+/// * `bulk_action_name` - A verb describing the bulk action, such as "Delete".
+/// * `on_bulk_action` - Called with the selected keys once the bulk action is confirmed.
+pub fn selectable_list<'a, K: Ord + Clone + 'a>(
+    rows: Vec<(K, Element<'a, app::Message>)>,
+    state: &SelectableListState<K>,
+    to_message: impl Fn(SelectableListMessage<K>) -> app::Message + 'a,
+    bulk_action_name: &'a str,
+    on_bulk_action: impl FnOnce(Vec<K>) -> app::Message,
+) -> Column<'a, app::Message> {
+    let to_message = Rc::new(to_message);
+
+    // Ignore selected keys that no longer have a row, such as rows that were deleted individually.
+    let selected_keys: Vec<K> = rows
+        .iter()
+        .map(|(key, _)| key)
+        .filter(|key| state.selected_keys.contains(key))
+        .cloned()
+        .collect();
+
+    let all_keys: BTreeSet<K> = rows.iter().map(|(key, _)| key.clone()).collect();
+    let is_all_selected = !all_keys.is_empty() && selected_keys.len() == all_keys.len();
+
+    let mut column = Column::new().spacing(10);
+
+    if !rows.is_empty() {
+        let select_all_to_message = to_message.clone();
+
+        column = column.push(checkbox("Select all", is_all_selected).on_toggle(
+            move |is_selected| {
+                select_all_to_message(if is_selected {
+                    SelectableListMessage::SelectAll(all_keys.clone())
+                } else {
+                    SelectableListMessage::ClearSelection
+                })
+            },
+        ));
+    }
+
+    if !selected_keys.is_empty() {
+        let selected_count = selected_keys.len();
+
+        column = if state.is_confirming_bulk_action {
+            column
+                .push(Text::new(format!(
+                    "{bulk_action_name} {selected_count} selected? This can't be undone."
+                )))
+                .push(row![
+                    icon_button("Confirm", SvgIcon::Delete, PaletteColor::Danger)
+                        .on_press(on_bulk_action(selected_keys)),
+                    Space::with_width(10.0),
+                    icon_button("Cancel", SvgIcon::Close, PaletteColor::Background)
+                        .on_press(to_message(SelectableListMessage::CancelBulkAction)),
+                ])
+        } else {
+            column.push(
+                row![
+                    Text::new(format!("{selected_count} selected")),
+                    Space::with_width(10.0),
+                    icon_button(bulk_action_name, SvgIcon::Delete, PaletteColor::Danger)
+                        .on_press(to_message(SelectableListMessage::RequestBulkAction)),
+                ]
+                .align_y(Alignment::Center),
+            )
+        };
+    }
+
+    for (key, content) in rows {
+        let is_selected = state.selected_keys.contains(&key);
+        let row_to_message = to_message.clone();
+
+        column = column.push(
+            row![
+                checkbox("", is_selected).on_toggle(move |is_selected| {
+                    row_to_message(SelectableListMessage::Toggle(key.clone(), is_selected))
+                }),
+                content,
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+        );
+    }
+
+    column
+}
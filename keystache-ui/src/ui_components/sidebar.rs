@@ -0,0 +1,148 @@
+use iced::widget::container::Style;
+use iced::widget::{button, column, container, text, vertical_space, Button, Column};
+use iced::{Alignment, Element, Shadow};
+use iced::{Border, Theme};
+
+use crate::routes::{
+    bitcoin_wallet, nostr_keypairs, nostr_relays, settings, ConnectedState, RouteName,
+};
+use crate::{app, routes};
+
+use super::{sidebar_button, sidebar_toggle_button, SvgIcon};
+use crate::util::lighten;
+
+pub fn sidebar(keystache: &app::App) -> Element<app::Message> {
+    let sidebar = container(
+        column![
+            sidebar_button("Home", SvgIcon::Home, &RouteName::Home, keystache).on_press(
+                app::Message::Routes(routes::Message::Navigate(RouteName::Home))
+            ),
+            sidebar_button(
+                "Keys",
+                SvgIcon::Key,
+                &RouteName::NostrKeypairs(nostr_keypairs::SubrouteName::List),
+                keystache
+            )
+            .on_press(app::Message::Routes(routes::Message::Navigate(
+                RouteName::NostrKeypairs(nostr_keypairs::SubrouteName::List)
+            ))),
+            sidebar_button(
+                "Relays",
+                SvgIcon::Hub,
+                &RouteName::NostrRelays(nostr_relays::SubrouteName::List),
+                keystache
+            )
+            .on_press(app::Message::Routes(routes::Message::Navigate(
+                RouteName::NostrRelays(nostr_relays::SubrouteName::List)
+            ))),
+            sidebar_button(
+                "Wallet",
+                SvgIcon::CurrencyBitcoin,
+                &RouteName::BitcoinWallet(bitcoin_wallet::SubrouteName::List),
+                keystache
+            )
+            .on_press(app::Message::Routes(routes::Message::Navigate(
+                RouteName::BitcoinWallet(bitcoin_wallet::SubrouteName::List)
+            ))),
+            vertical_space(),
+            sidebar_toggle_button(
+                "Alerts",
+                SvgIcon::Notifications,
+                keystache.is_toast_history_open()
+            )
+            .on_press(app::Message::ToggleToastHistory),
+            sidebar_button(
+                "Settings",
+                SvgIcon::Settings,
+                &RouteName::Settings(settings::SubrouteName::Main),
+                keystache
+            )
+            .on_press(app::Message::Routes(routes::Message::Navigate(
+                RouteName::Settings(settings::SubrouteName::Main)
+            ))),
+        ]
+        .push_maybe(
+            keystache
+                .page
+                .get_connected_state()
+                .map(connection_status_view),
+        )
+        .spacing(8)
+        .align_x(Alignment::Start),
+    )
+    .padding(8)
+    .style(|theme| -> Style {
+        Style {
+            text_color: None,
+            background: Some(lighten(theme.palette().background, 0.05).into()),
+            border: Border::default(),
+            shadow: Shadow::default(),
+        }
+    });
+    sidebar.into()
+}
+
+/// A compact summary of what Keystache is connected to, so that problems are
+/// visible from any page. Each line opens the page where it can be looked into.
+fn connection_status_view(connected_state: &ConnectedState) -> Column<app::Message> {
+    let (connected_relay_count, relay_count) = connected_state.nostr_state.connected_relay_counts();
+
+    let federations_status = match connected_state.loadable_wallet_view.as_ref_option() {
+        Some(wallet_view) => {
+            let (connected_federation_count, federation_count) =
+                wallet_view.connected_federation_counts();
+
+            connection_status_button(
+                format!("Federations {connected_federation_count}/{federation_count}"),
+                if connected_federation_count == federation_count {
+                    text::success
+                } else {
+                    text::danger
+                },
+                RouteName::BitcoinWallet(bitcoin_wallet::SubrouteName::List),
+            )
+        }
+        None => connection_status_button(
+            "Federations loading...".to_string(),
+            text::default,
+            RouteName::BitcoinWallet(bitcoin_wallet::SubrouteName::List),
+        ),
+    };
+
+    column![
+        connection_status_button(
+            format!("Relays {connected_relay_count}/{relay_count}"),
+            if relay_count > 0 && connected_relay_count == relay_count {
+                text::success
+            } else {
+                text::danger
+            },
+            RouteName::NostrRelays(nostr_relays::SubrouteName::List),
+        ),
+        federations_status,
+        if connected_state.is_nip55_socket_listening {
+            connection_status_button(
+                "Signer socket listening".to_string(),
+                text::success,
+                RouteName::Settings(settings::SubrouteName::General),
+            )
+        } else {
+            connection_status_button(
+                "Signer socket closed".to_string(),
+                text::danger,
+                RouteName::Settings(settings::SubrouteName::General),
+            )
+        },
+    ]
+}
+
+fn connection_status_button<'a>(
+    label: String,
+    style: fn(&Theme) -> text::Style,
+    route_name: RouteName,
+) -> Button<'a, app::Message> {
+    button(text(label).size(14).style(style))
+        .style(button::text)
+        .padding([2, 8])
+        .on_press(app::Message::Routes(routes::Message::Navigate(route_name)))
+}
@@ -0,0 +1,91 @@
+use iced::{
+    mouse,
+    widget::{
+        canvas::{self, Path, Stroke},
+        Canvas,
+    },
+    Length, Point, Rectangle, Renderer, Theme,
+};
+
+use crate::app;
+
+/// A small line chart of `values` from left to right, scaled so that the largest value
+/// reaches the top and zero sits at the bottom. Meant for showing a trend at a glance,
+/// so it has no axes or labels.
+pub fn sparkline(values: Vec<u64>, height: f32) -> Canvas<Sparkline, app::Message> {
+    Canvas::new(Sparkline { values })
+        .width(Length::Fill)
+        .height(height)
+}
+
+pub struct Sparkline {
+    values: Vec<u64>,
+}
+
+impl canvas::Program<app::Message> for Sparkline {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        const STROKE_WIDTH: f32 = 2.0;
+
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+
+        let max_value = self.values.iter().copied().max().unwrap_or_default();
+
+        // Leaves room for the stroke, so that it isn't cut off at the edges.
+        let width = frame.width() - STROKE_WIDTH;
+        let height = frame.height() - STROKE_WIDTH;
+
+        // Ignoring clippy lint here since points only need to be as precise as pixels.
+        #[allow(clippy::cast_precision_loss)]
+        let point = |index: usize, value: u64| {
+            let x_fraction = if self.values.len() > 1 {
+                index as f32 / (self.values.len() - 1) as f32
+            } else {
+                0.0
+            };
+
+            let y_fraction = if max_value > 0 {
+                value as f32 / max_value as f32
+            } else {
+                0.0
+            };
+
+            Point::new(
+                width.mul_add(x_fraction, STROKE_WIDTH / 2.0),
+                height.mul_add(1.0 - y_fraction, STROKE_WIDTH / 2.0),
+            )
+        };
+
+        let path = Path::new(|builder| {
+            for (index, value) in self.values.iter().enumerate() {
+                if index == 0 {
+                    builder.move_to(point(index, *value));
+                } else {
+                    builder.line_to(point(index, *value));
+                }
+            }
+
+            // A single value is drawn as a flat line across the whole chart.
+            if let [value] = self.values.as_slice() {
+                builder.line_to(Point::new(width + STROKE_WIDTH / 2.0, point(0, *value).y));
+            }
+        });
+
+        frame.stroke(
+            &path,
+            Stroke::default()
+                .with_width(STROKE_WIDTH)
+                .with_color(theme.palette().primary),
+        );
+
+        vec![frame.into_geometry()]
+    }
+}
@@ -0,0 +1,545 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use chrono::NaiveDateTime;
+
+use crate::app;
+use crate::clock::Clock;
+use crate::db::DatabaseBusyError;
+use crate::util::lighten;
+use iced::advanced::layout::{self, Layout, Limits};
+use iced::advanced::renderer;
+use iced::advanced::widget::{self, Tree};
+use iced::advanced::{Clipboard, Shell, Widget};
+use iced::event::{self, Event};
+use iced::widget::{column, container, horizontal_space, row, text, Column};
+use iced::Border;
+use iced::{mouse, Color, Font};
+use iced::{window, Shadow};
+use iced::{Alignment, Element, Length, Rectangle, Renderer, Size, Theme, Vector};
+
+use super::{mini_icon_button, PaletteColor, SvgIcon};
+
+const DEFAULT_TIMEOUT: u64 = 5;
+
+/// Identical toasts added within this window of each other are shown as a single toast.
+const DEDUPLICATION_WINDOW: Duration = Duration::from_secs(10);
+
+/// The most toasts kept in the history. Older toasts are dropped.
+const MAX_TOAST_HISTORY_LEN: usize = 50;
+
+/// The most toasts shown at once. Any more are queued until a shown toast closes.
+const MAX_SHOWN_TOASTS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastStatus {
+    Neutral,
+    Good,
+    Bad,
+}
+
+impl ToastStatus {
+    fn get_style(self, theme: &Theme) -> container::Style {
+        let gray = lighten(theme.palette().background, 0.1);
+
+        let border_color = match self {
+            Self::Neutral => gray,
+            Self::Good => theme.palette().success,
+            Self::Bad => theme.palette().danger,
+        };
+
+        container::Style {
+            background: Some(gray.into()),
+            text_color: Color::WHITE.into(),
+            border: Border {
+                color: border_color,
+                width: 1.,
+                radius: (4.).into(),
+            },
+            shadow: Shadow {
+                color: Color::from_rgba8(0, 0, 0, 0.25),
+                offset: Vector::new(-2., -2.),
+                blur_radius: 4.,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Toast {
+    pub title: String,
+    pub body: String,
+    pub status: ToastStatus,
+}
+
+impl Toast {
+    /// Shown when a change couldn't be saved because the database stayed busy,
+    /// such as during a burst of signing requests.
+    pub fn database_busy() -> Self {
+        Self {
+            title: "Database busy".to_string(),
+            body: DatabaseBusyError.to_string(),
+            status: ToastStatus::Bad,
+        }
+    }
+
+    /// Returns [`Toast::database_busy`] if `result` failed because the database stayed busy.
+    /// Other failures are left to the caller.
+    pub fn database_busy_or<T>(result: &anyhow::Result<T>) -> Option<Self> {
+        match result {
+            Err(err) if err.is::<DatabaseBusyError>() => Some(Self::database_busy()),
+            _ => None,
+        }
+    }
+
+    /// Whether this toast reports an error from a query that failed because the database
+    /// stayed busy. Most errors only reach a toast as text, so the text is checked.
+    fn is_database_busy_error(&self) -> bool {
+        self.status == ToastStatus::Bad && self.body.contains(&DatabaseBusyError.to_string())
+    }
+}
+
+/// A toast waiting to be closed, along with how many identical toasts it represents.
+#[derive(Debug, Clone)]
+pub struct ShownToast {
+    pub toast: Toast,
+    pub count: usize,
+    last_added_time: Instant,
+}
+
+impl ShownToast {
+    /// Adds `toast` to `shown_toasts`, unless an identical toast was added within
+    /// the deduplication window, in which case that toast's count is increased instead.
+    /// Errors from a busy database are all shown as [`Toast::database_busy`], so that a burst
+    /// of them is shown once rather than as a separate toast for every failed query.
+    pub fn push_deduplicated(shown_toasts: &mut Vec<Self>, toast: Toast, now: Instant) {
+        let toast = if toast.is_database_busy_error() {
+            Toast::database_busy()
+        } else {
+            toast
+        };
+
+        if let Some(shown_toast) = shown_toasts.iter_mut().find(|shown_toast| {
+            shown_toast.toast == toast
+                && now.saturating_duration_since(shown_toast.last_added_time) < DEDUPLICATION_WINDOW
+        }) {
+            shown_toast.count += 1;
+            shown_toast.last_added_time = now;
+        } else {
+            shown_toasts.push(Self {
+                toast,
+                count: 1,
+                last_added_time: now,
+            });
+        }
+    }
+}
+
+/// Recently added toasts, newest first, so that they can be re-read after they close.
+#[derive(Debug, Clone, Default)]
+pub struct ToastHistory {
+    entries: VecDeque<(Toast, NaiveDateTime)>,
+}
+
+impl ToastHistory {
+    pub fn push(&mut self, toast: Toast, now: NaiveDateTime) {
+        self.entries.push_front((toast, now));
+        self.entries.truncate(MAX_TOAST_HISTORY_LEN);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn view(&self) -> Column<app::Message> {
+        let mut column = Column::new()
+            .push(text("Alerts").size(25))
+            .spacing(10)
+            .width(300.0);
+
+        if self.entries.is_empty() {
+            return column.push(text("No recent alerts"));
+        }
+
+        for (toast, time) in &self.entries {
+            let status = toast.status;
+
+            column = column.push(
+                container(column![
+                    text(toast.title.as_str()).font(Font {
+                        family: iced::font::Family::default(),
+                        weight: iced::font::Weight::Bold,
+                        stretch: iced::font::Stretch::Normal,
+                        style: iced::font::Style::Normal,
+                    }),
+                    text(toast.body.as_str()),
+                    text(time.format("%Y-%m-%d %H:%M:%S UTC").to_string()).size(12)
+                ])
+                .width(Length::Fill)
+                .padding(8)
+                .style(move |theme| status.get_style(theme)),
+            );
+        }
+
+        column
+    }
+}
+
+pub struct ToastManager<'a> {
+    toasts: Vec<Element<'a, app::Message>>,
+    timeout_secs: u64,
+    clock: Clock,
+    on_close: Box<dyn Fn(usize) -> app::Message + 'a>,
+}
+
+impl<'a> ToastManager<'a> {
+    /// Shows up to [`MAX_SHOWN_TOASTS`] toasts. If there are more, the last
+    /// shown toast notes how many are waiting to be shown.
+    pub fn new(
+        toasts: &'a [ShownToast],
+        clock: Clock,
+        on_close: impl Fn(usize) -> app::Message + 'a,
+    ) -> Self {
+        let shown_count = toasts.len().min(MAX_SHOWN_TOASTS);
+        let queued_count = toasts.len() - shown_count;
+
+        let toasts = toasts[..shown_count]
+            .iter()
+            .enumerate()
+            .map(|(index, ShownToast { toast, count, .. })| {
+                let close_button = mini_icon_button(
+                    "Dismiss notification",
+                    SvgIcon::Close,
+                    PaletteColor::Background,
+                    Some((on_close)(index)),
+                );
+
+                let title = if *count > 1 {
+                    format!("{} ({count}x)", toast.title)
+                } else {
+                    toast.title.clone()
+                };
+
+                let is_last_shown = index + 1 == shown_count;
+
+                container(column![container(
+                    column![
+                        row![
+                            text(title).font(Font {
+                                family: iced::font::Family::default(),
+                                weight: iced::font::Weight::Bold,
+                                stretch: iced::font::Stretch::Normal,
+                                style: iced::font::Style::Normal,
+                            }),
+                            horizontal_space(),
+                            close_button
+                        ]
+                        .align_y(Alignment::Center),
+                        text(toast.body.as_str())
+                    ]
+                    .push_maybe(
+                        (is_last_shown && queued_count > 0).then(|| {
+                            text(format!("+{queued_count} more notifications")).size(12)
+                        })
+                    )
+                )
+                .width(Length::Fill)
+                .padding(16)
+                .style(|theme| toast.status.get_style(theme))])
+                .max_width(256)
+                .into()
+            })
+            .collect();
+
+        Self {
+            toasts,
+            timeout_secs: DEFAULT_TIMEOUT,
+            clock,
+            on_close: Box::new(on_close),
+        }
+    }
+
+    /// How long a toast that was shown at `shown_time` has left before it closes.
+    fn remaining_time(&self, shown_time: Instant) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+            .saturating_sub(self.clock.instant().saturating_duration_since(shown_time))
+    }
+}
+
+impl<'a> Widget<app::Message, Theme, Renderer> for ToastManager<'a> {
+    fn size(&self) -> Size<Length> {
+        Size::new(Length::Fill, Length::Fill)
+    }
+
+    fn layout(&self, tree: &mut Tree, renderer: &Renderer, limits: &Limits) -> layout::Node {
+        layout::flex::resolve(
+            layout::flex::Axis::Vertical,
+            renderer,
+            limits,
+            Length::Fill,
+            Length::Fill,
+            10.into(),
+            10.0,
+            Alignment::End,
+            &self.toasts,
+            &mut tree.children,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        for ((child, state), layout) in self
+            .toasts
+            .iter()
+            .zip(tree.children.iter())
+            .zip(layout.children())
+        {
+            child
+                .as_widget()
+                .draw(state, renderer, theme, style, layout, cursor, viewport);
+        }
+    }
+
+    fn tag(&self) -> widget::tree::Tag {
+        struct Marker;
+        widget::tree::Tag::of::<Marker>()
+    }
+
+    fn state(&self) -> widget::tree::State {
+        widget::tree::State::new(Vec::<Option<Instant>>::new())
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        self.toasts.iter().map(Tree::new).collect()
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        let instants = tree.state.downcast_mut::<Vec<Option<Instant>>>();
+
+        // Invalidating removed instants to None allows us to remove
+        // them here so that diffing for removed / new toast instants
+        // is accurate
+        instants.retain(Option::is_some);
+
+        match (instants.len(), self.toasts.len()) {
+            (old, new) if old > new => {
+                instants.truncate(new);
+            }
+            (old, new) if old < new => {
+                instants.extend(std::iter::repeat(Some(self.clock.instant())).take(new - old));
+            }
+            _ => {}
+        }
+
+        tree.diff_children(&self.toasts.iter().collect::<Vec<_>>());
+    }
+
+    fn operate(
+        &self,
+        state: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn widget::Operation<()>,
+    ) {
+        operation.container(None, layout.bounds(), &mut |operation| {
+            self.toasts
+                .iter()
+                .zip(state.children.iter_mut())
+                .zip(layout.children())
+                .for_each(|((child, state), layout)| {
+                    child
+                        .as_widget()
+                        .operate(state, layout, renderer, operation);
+                });
+        });
+    }
+
+    fn on_event(
+        &mut self,
+        state: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, app::Message>,
+        _viewport: &Rectangle,
+    ) -> event::Status {
+        let instants = state.state.downcast_mut::<Vec<Option<Instant>>>();
+
+        if let Event::Window(window::Event::RedrawRequested(now)) = &event {
+            let mut next_redraw: Option<window::RedrawRequest> = None;
+
+            instants
+                .iter_mut()
+                .enumerate()
+                .for_each(|(index, maybe_instant)| {
+                    if let Some(instant) = maybe_instant.as_mut() {
+                        let remaining = self.remaining_time(*instant);
+
+                        if remaining == Duration::ZERO {
+                            maybe_instant.take();
+                            shell.publish((self.on_close)(index));
+                            next_redraw = Some(window::RedrawRequest::NextFrame);
+                        } else {
+                            let redraw_at = window::RedrawRequest::At(*now + remaining);
+                            next_redraw = next_redraw
+                                .map(|redraw| redraw.min(redraw_at))
+                                .or(Some(redraw_at));
+                        }
+                    }
+                });
+
+            if let Some(redraw) = next_redraw {
+                shell.request_redraw(redraw);
+            }
+        }
+
+        let viewport = layout.bounds();
+
+        self.toasts
+            .iter_mut()
+            .zip(state.children.iter_mut())
+            .zip(layout.children())
+            .zip(instants.iter_mut())
+            .map(|(((child, state), layout), instant)| {
+                let mut local_messages = vec![];
+                let mut local_shell = Shell::new(&mut local_messages);
+
+                let status = child.as_widget_mut().on_event(
+                    state,
+                    event.clone(),
+                    layout,
+                    cursor,
+                    renderer,
+                    clipboard,
+                    &mut local_shell,
+                    &viewport,
+                );
+
+                if !local_shell.is_empty() {
+                    instant.take();
+                }
+
+                shell.merge(local_shell, std::convert::identity);
+
+                status
+            })
+            .fold(event::Status::Ignored, event::Status::merge)
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.toasts
+            .iter()
+            .zip(state.children.iter())
+            .zip(layout.children())
+            .map(|((child, state), layout)| {
+                child
+                    .as_widget()
+                    .mouse_interaction(state, layout, cursor, viewport, renderer)
+            })
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+impl<'a> From<ToastManager<'a>> for Element<'a, app::Message> {
+    fn from(manager: ToastManager<'a>) -> Self {
+        Element::new(manager)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_deduplicated() {
+        let toast = |body: &str| Toast {
+            title: "Failed to connect".to_string(),
+            body: body.to_string(),
+            status: ToastStatus::Bad,
+        };
+        let now = Instant::now();
+
+        let mut shown_toasts = Vec::new();
+        ShownToast::push_deduplicated(&mut shown_toasts, toast("Relay A"), now);
+        ShownToast::push_deduplicated(&mut shown_toasts, toast("Relay A"), now);
+        ShownToast::push_deduplicated(&mut shown_toasts, toast("Relay B"), now);
+        assert_eq!(shown_toasts.len(), 2);
+        assert_eq!(shown_toasts[0].count, 2);
+
+        // Each duplicate extends the window, so a steady stream of duplicates stays collapsed.
+        let later = now + DEDUPLICATION_WINDOW / 2;
+        ShownToast::push_deduplicated(&mut shown_toasts, toast("Relay A"), later);
+        ShownToast::push_deduplicated(
+            &mut shown_toasts,
+            toast("Relay A"),
+            later + DEDUPLICATION_WINDOW / 2,
+        );
+        assert_eq!(shown_toasts[0].count, 4);
+
+        // Duplicates outside of the window are shown separately.
+        ShownToast::push_deduplicated(
+            &mut shown_toasts,
+            toast("Relay B"),
+            now + DEDUPLICATION_WINDOW,
+        );
+        assert_eq!(shown_toasts.len(), 3);
+    }
+
+    #[test]
+    fn test_push_deduplicated_database_busy() {
+        let toast = |title: &str| Toast {
+            title: title.to_string(),
+            body: DatabaseBusyError.to_string(),
+            status: ToastStatus::Bad,
+        };
+        let now = Instant::now();
+
+        let mut shown_toasts = Vec::new();
+        ShownToast::push_deduplicated(&mut shown_toasts, toast("Failed to save relay"), now);
+        ShownToast::push_deduplicated(&mut shown_toasts, toast("Failed to save note"), now);
+        assert_eq!(shown_toasts.len(), 1);
+        assert_eq!(shown_toasts[0].toast, Toast::database_busy());
+        assert_eq!(shown_toasts[0].count, 2);
+    }
+
+    #[test]
+    fn test_remaining_time() {
+        let clock = Clock::manual(std::time::UNIX_EPOCH, 0);
+        let toast_manager = ToastManager::new(&[], clock.clone(), app::Message::CloseToast);
+        let shown_time = clock.instant();
+
+        assert_eq!(
+            toast_manager.remaining_time(shown_time),
+            Duration::from_secs(DEFAULT_TIMEOUT)
+        );
+
+        clock.advance(Duration::from_secs(DEFAULT_TIMEOUT - 1));
+        assert_eq!(
+            toast_manager.remaining_time(shown_time),
+            Duration::from_secs(1)
+        );
+
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(toast_manager.remaining_time(shown_time), Duration::ZERO);
+    }
+}
@@ -1,7 +1,13 @@
-use fedimint_core::Amount;
+use std::time::Duration;
+
 use iced::Color;
 use palette::{rgb::Rgb, FromColor, Hsl};
 
+pub use keystache_core::util::{format_amount, format_time};
+
+pub mod notification;
+pub mod qr;
+
 pub fn darken(color: Color, amount: f32) -> Color {
     let mut hsl = to_hsl(color);
 
@@ -34,37 +40,6 @@ fn from_hsl(hsl: Hsl) -> Color {
     Rgb::from_color(hsl).into()
 }
 
-pub fn format_amount(amount: Amount) -> String {
-    let amount_sats = amount.msats / 1000;
-    let sub_sat_msats = amount.msats % 1000;
-
-    if amount_sats == 1 && sub_sat_msats == 0 {
-        return "1 sat".to_string();
-    }
-
-    let comma_formatted_sats = amount_sats
-        .to_string()
-        .as_bytes()
-        .rchunks(3)
-        .rev()
-        .map(std::str::from_utf8)
-        .collect::<Result<Vec<&str>, _>>()
-        .unwrap()
-        .join(",");
-
-    let msats_str = if sub_sat_msats == 0 {
-        String::new()
-    } else {
-        let mut sub_sat_msats_str = format!(".{sub_sat_msats:03}");
-        while sub_sat_msats_str.ends_with('0') {
-            sub_sat_msats_str.pop();
-        }
-        sub_sat_msats_str
-    };
-
-    format!("{comma_formatted_sats}{msats_str} sats")
-}
-
 /// Adds ellipses to a string if it exceeds a certain length, ensuring the total length is at most
 /// `max_len` characters. Can either place the ellipses at the end of the string or in the center.
 #[must_use]
@@ -112,41 +87,93 @@ pub fn truncate_text(input: &str, max_len: usize, center: bool) -> String {
     }
 }
 
+/// How long to wait after the user stops typing before applying a search query.
+const SEARCH_DEBOUNCE_DURATION: Duration = Duration::from_millis(300);
+
+/// Resolves to `search_input` after a short delay. Callers should only apply
+/// the search if the input hasn't changed in the meantime.
+pub async fn debounce_search_input(search_input: String) -> String {
+    tokio::time::sleep(SEARCH_DEBOUNCE_DURATION).await;
+    search_input
+}
+
+/// Scores how well `query` fuzzy-matches `candidate`, ignoring case. Returns `None` unless every
+/// character of `query` appears in `candidate` in the same order. Higher scores are better matches,
+/// with consecutive characters and matches at the start of `candidate` scoring highest.
+#[must_use]
+pub fn fuzzy_match_score(query: &str, candidate: &str) -> Option<u32> {
+    let mut query_chars = query.chars().flat_map(char::to_lowercase).peekable();
+
+    let mut score = 0;
+    let mut previous_char_matched = false;
+
+    for (i, candidate_char) in candidate.chars().flat_map(char::to_lowercase).enumerate() {
+        let Some(query_char) = query_chars.peek() else {
+            break;
+        };
+
+        if *query_char == candidate_char {
+            score += 1;
+
+            if previous_char_matched {
+                score += 5;
+            }
+
+            if i == 0 {
+                score += 10;
+            }
+
+            query_chars.next();
+            previous_char_matched = true;
+        } else {
+            previous_char_matched = false;
+        }
+    }
+
+    query_chars.peek().is_none().then_some(score)
+}
+
+/// Filters `items` down to those that fuzzy-match `query`, ordered from best to worst match.
+/// Items with equal scores keep their original order.
+pub fn rank_by_fuzzy_match<T>(
+    query: &str,
+    items: Vec<T>,
+    to_text: impl Fn(&T) -> String,
+) -> Vec<T> {
+    let mut scored_items: Vec<(u32, T)> = items
+        .into_iter()
+        .filter_map(|item| Some((fuzzy_match_score(query, &to_text(&item))?, item)))
+        .collect();
+
+    scored_items.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    scored_items.into_iter().map(|(_, item)| item).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn test_format_amount_sats() {
-        // 0 sats is plural.
-        assert_eq!(format_amount(Amount::from_sats(0)), "0 sats");
-
-        // 1 sat is singular.
-        assert_eq!(format_amount(Amount::from_sats(1)), "1 sat");
-
-        // Digits are ordered correctly.
-        assert_eq!(format_amount(Amount::from_sats(1234)), "1,234 sats");
-
-        // Commas are placed correctly.
-        assert_eq!(format_amount(Amount::from_sats(10)), "10 sats");
-        assert_eq!(format_amount(Amount::from_sats(100)), "100 sats");
-        assert_eq!(format_amount(Amount::from_sats(1_000)), "1,000 sats");
-        assert_eq!(format_amount(Amount::from_sats(10_000)), "10,000 sats");
-        assert_eq!(format_amount(Amount::from_sats(100_000)), "100,000 sats");
-        assert_eq!(
-            format_amount(Amount::from_sats(1_000_000)),
-            "1,000,000 sats"
-        );
+    fn test_fuzzy_match() {
+        // An empty query matches everything.
+        assert_eq!(fuzzy_match_score("", "anything"), Some(0));
+
+        // Characters must appear in order.
+        assert!(fuzzy_match_score("dam", "damus.io").is_some());
+        assert!(fuzzy_match_score("mad", "damus.io").is_none());
 
-        // Millisats are displayed as sub-sats, without extra zeros.
-        assert_eq!(format_amount(Amount::from_msats(1)), "0.001 sats");
-        assert_eq!(format_amount(Amount::from_msats(10)), "0.01 sats");
-        assert_eq!(format_amount(Amount::from_msats(100)), "0.1 sats");
+        // Matching ignores case.
+        assert!(fuzzy_match_score("DAMUS", "relay.damus.io").is_some());
 
-        // Millisats are displayed properly with sats
+        // Consecutive and leading matches rank higher.
         assert_eq!(
-            format_amount(Amount::from_msats(123456789)),
-            "123,456.789 sats"
+            rank_by_fuzzy_match(
+                "nos",
+                vec!["relay.nostr.band", "nos.lol", "n-o-s"],
+                ToString::to_string
+            ),
+            vec!["nos.lol", "relay.nostr.band", "n-o-s"]
         );
     }
 
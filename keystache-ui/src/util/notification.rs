@@ -0,0 +1,17 @@
+use notify_rust::Notification;
+
+/// Shows a notification on the desktop, so that alerts are seen even when Keystache is
+/// in the background. Showing it can block on the system's notification service, so
+/// it's done off the UI thread.
+pub async fn show_desktop_notification(summary: String, body: String) -> anyhow::Result<()> {
+    tokio::task::spawn_blocking(move || {
+        Notification::new()
+            .appname("Keystache")
+            .summary(&summary)
+            .body(&body)
+            .show()?;
+
+        Ok(())
+    })
+    .await?
+}
@@ -0,0 +1,137 @@
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use nokhwa::{
+    pixel_format::LumaFormat,
+    utils::{CameraIndex, RequestedFormat, RequestedFormatType},
+    Camera,
+};
+
+/// How long the webcam is watched for a QR code before giving up.
+pub const WEBCAM_SCAN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Reads the text of the first QR code found in a greyscale image,
+/// whose `pixels` are stored row by row, one byte per pixel.
+pub fn decode_greyscale(width: usize, height: usize, pixels: &[u8]) -> Option<String> {
+    if width == 0 || pixels.len() < width * height {
+        return None;
+    }
+
+    let mut image =
+        rqrr::PreparedImage::prepare_from_greyscale(width, height, |x, y| pixels[y * width + x]);
+
+    image
+        .detect_grids()
+        .into_iter()
+        .find_map(|grid| grid.decode().ok().map(|(_, content)| content))
+}
+
+/// Reads the text of a QR code in an image file, such as a screenshot or photo.
+/// Blocks while the file is read, so call it off the UI thread.
+pub fn decode_file(path: &Path) -> anyhow::Result<String> {
+    let image = image::open(path)?.to_luma8();
+
+    decode_greyscale(
+        image.width() as usize,
+        image.height() as usize,
+        image.as_raw(),
+    )
+    .ok_or_else(|| anyhow::anyhow!("No QR code was found in the image"))
+}
+
+/// Watches the default webcam until a QR code is held up to it, and returns its text.
+/// Blocks for up to [`WEBCAM_SCAN_TIMEOUT`], so call it off the UI thread.
+pub fn scan_webcam() -> anyhow::Result<String> {
+    let mut camera = Camera::new(
+        CameraIndex::Index(0),
+        RequestedFormat::new::<LumaFormat>(RequestedFormatType::AbsoluteHighestFrameRate),
+    )?;
+
+    camera.open_stream()?;
+
+    let start = Instant::now();
+
+    let result = loop {
+        if start.elapsed() >= WEBCAM_SCAN_TIMEOUT {
+            break Err(anyhow::anyhow!("No QR code was seen by the webcam"));
+        }
+
+        let frame = match camera
+            .frame()
+            .and_then(|frame| frame.decode_image::<LumaFormat>())
+        {
+            Ok(frame) => frame,
+            Err(err) => break Err(err.into()),
+        };
+
+        if let Some(content) = decode_greyscale(
+            frame.width() as usize,
+            frame.height() as usize,
+            frame.as_raw(),
+        ) {
+            break Ok(content);
+        }
+    };
+
+    // TODO: Log a warning if the webcam fails to stop.
+    let _ = camera.stop_stream();
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use qrcode::QrCode;
+
+    use super::*;
+
+    // Renders `text` as a QR code, with each module drawn as a square
+    // of pixels and a blank border around it like a printed code.
+    fn render_qr_code(text: &str) -> (usize, Vec<u8>) {
+        const SCALE: usize = 4;
+        const BORDER: usize = 4;
+
+        let qr_code = QrCode::new(text).unwrap();
+        let module_count = qr_code.width();
+        let colors = qr_code.to_colors();
+
+        let size = (module_count + BORDER * 2) * SCALE;
+
+        let pixels = (0..size * size)
+            .map(|i| {
+                let (x, y) = ((i % size) / SCALE, (i / size) / SCALE);
+
+                if x < BORDER
+                    || y < BORDER
+                    || x >= module_count + BORDER
+                    || y >= module_count + BORDER
+                {
+                    return 255;
+                }
+
+                colors[(y - BORDER) * module_count + (x - BORDER)].select(0, 255)
+            })
+            .collect();
+
+        (size, pixels)
+    }
+
+    #[test]
+    fn test_decode_greyscale() {
+        let npub = "npub1qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqsaz5nct";
+
+        let (size, pixels) = render_qr_code(npub);
+        assert_eq!(
+            decode_greyscale(size, size, &pixels),
+            Some(npub.to_string())
+        );
+
+        // A blank image has no QR code in it.
+        assert_eq!(decode_greyscale(size, size, &vec![255; size * size]), None);
+
+        // Images that are smaller than their size says aren't read.
+        assert_eq!(decode_greyscale(size, size, &pixels[1..]), None);
+    }
+}
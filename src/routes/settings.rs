@@ -1,249 +0,0 @@
-use iced::{
-    widget::{text_input, Column, Text},
-    Task,
-};
-
-use crate::{
-    app,
-    ui_components::{icon_button, PaletteColor, SvgIcon, Toast, ToastStatus},
-};
-
-use super::{container, ConnectedState, RouteName};
-
-// TODO: Remove this clippy allow once we have more variants.
-#[allow(clippy::enum_variant_names)]
-#[derive(Debug, Clone)]
-pub enum Message {
-    ChangePasswordCurrentPasswordInputChanged(String),
-    ChangePasswordNewPasswordInputChanged(String),
-    ChangePasswordNewPasswordConfirmationInputChanged(String),
-    ChangePasswordSubmit {
-        current_password: String,
-        new_password: String,
-    },
-}
-
-pub struct Page {
-    pub connected_state: ConnectedState,
-    pub subroute: Subroute,
-}
-
-impl Page {
-    pub fn update(&mut self, msg: Message) -> Task<app::Message> {
-        match msg {
-            Message::ChangePasswordCurrentPasswordInputChanged(input) => {
-                if let Subroute::ChangePassword(change_password) = &mut self.subroute {
-                    change_password.current_password_input = input;
-                }
-
-                Task::none()
-            }
-            Message::ChangePasswordNewPasswordInputChanged(input) => {
-                if let Subroute::ChangePassword(change_password) = &mut self.subroute {
-                    change_password.new_password_input = input;
-                }
-
-                Task::none()
-            }
-            Message::ChangePasswordNewPasswordConfirmationInputChanged(input) => {
-                if let Subroute::ChangePassword(change_password) = &mut self.subroute {
-                    change_password.new_password_confirmation_input = input;
-                }
-
-                Task::none()
-            }
-            Message::ChangePasswordSubmit {
-                current_password,
-                new_password,
-            } => {
-                match self
-                    .connected_state
-                    .db
-                    .change_password(&current_password, &new_password)
-                {
-                    Ok(()) => Task::done(app::Message::Routes(super::Message::Navigate(
-                        RouteName::Settings(SubrouteName::Main),
-                    )))
-                    .chain(Task::done(app::Message::AddToast(Toast {
-                        title: "Password changed".to_string(),
-                        body: "Your password has been changed.".to_string(),
-                        status: ToastStatus::Good,
-                    }))),
-                    Err(_err) => Task::done(app::Message::AddToast(Toast {
-                        title: "Failed to change password".to_string(),
-                        body: "Check that you entered your current password correctly.".to_string(),
-                        status: ToastStatus::Bad,
-                    })),
-                }
-            }
-        }
-    }
-
-    pub fn view<'a>(&self) -> Column<'a, app::Message> {
-        match &self.subroute {
-            Subroute::Main(main) => main.view(),
-            Subroute::ChangePassword(change_password) => change_password.view(),
-            Subroute::About(about) => about.view(),
-        }
-    }
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum SubrouteName {
-    Main,
-    ChangePassword,
-    About,
-}
-
-impl SubrouteName {
-    pub fn to_default_subroute(&self) -> Subroute {
-        match self {
-            Self::Main => Subroute::Main(Main {}),
-            Self::ChangePassword => Subroute::ChangePassword(ChangePassword {
-                current_password_input: String::new(),
-                new_password_input: String::new(),
-                new_password_confirmation_input: String::new(),
-            }),
-            Self::About => Subroute::About(About {}),
-        }
-    }
-}
-
-pub enum Subroute {
-    Main(Main),
-    ChangePassword(ChangePassword),
-    About(About),
-}
-
-impl Subroute {
-    pub fn to_name(&self) -> SubrouteName {
-        match self {
-            Self::Main(_) => SubrouteName::Main,
-            Self::ChangePassword(_) => SubrouteName::ChangePassword,
-            Self::About(_) => SubrouteName::About,
-        }
-    }
-}
-
-pub struct Main {}
-
-impl Main {
-    // TODO: Remove this clippy allow.
-    #[allow(clippy::unused_self)]
-    fn view<'a>(&self) -> Column<'a, app::Message> {
-        container("Settings")
-            .push(
-                icon_button("Change Password", SvgIcon::Lock, PaletteColor::Primary).on_press(
-                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
-                        SubrouteName::ChangePassword,
-                    ))),
-                ),
-            )
-            .push(icon_button(
-                "Backup (Coming Soon)",
-                SvgIcon::FileCopy,
-                PaletteColor::Primary,
-            ))
-            .push(
-                icon_button("About", SvgIcon::Info, PaletteColor::Primary).on_press(
-                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
-                        SubrouteName::About,
-                    ))),
-                ),
-            )
-    }
-}
-
-// TODO: Remove this clippy allow.
-#[allow(clippy::struct_field_names)]
-pub struct ChangePassword {
-    current_password_input: String,
-    new_password_input: String,
-    new_password_confirmation_input: String,
-}
-
-impl ChangePassword {
-    // TODO: Remove this clippy allow.
-    #[allow(clippy::unused_self)]
-    fn view<'a>(&self) -> Column<'a, app::Message> {
-        container("Change Password")
-            .push(
-                text_input("Current Password", &self.current_password_input)
-                    .on_input(|input| {
-                        app::Message::Routes(super::Message::SettingsPage(
-                            Message::ChangePasswordCurrentPasswordInputChanged(input),
-                        ))
-                    })
-                    .secure(true)
-                    .padding(10)
-                    .size(30),
-            )
-            .push(
-                text_input("New Password", &self.new_password_input)
-                    .on_input(|input| {
-                        app::Message::Routes(super::Message::SettingsPage(
-                            Message::ChangePasswordNewPasswordInputChanged(input),
-                        ))
-                    })
-                    .secure(true)
-                    .padding(10)
-                    .size(30),
-            )
-            .push(
-                text_input(
-                    "Confirm New Password",
-                    &self.new_password_confirmation_input,
-                )
-                .on_input(|input| {
-                    app::Message::Routes(super::Message::SettingsPage(
-                        Message::ChangePasswordNewPasswordConfirmationInputChanged(input),
-                    ))
-                })
-                .secure(true)
-                .padding(10)
-                .size(30),
-            )
-            .push(
-                icon_button("Change Password", SvgIcon::Lock, PaletteColor::Primary)
-                    .on_press_maybe(
-                        (!self.current_password_input.is_empty()
-                            && !self.new_password_input.is_empty()
-                            && self.new_password_input == self.new_password_confirmation_input)
-                            .then(|| {
-                                app::Message::Routes(super::Message::SettingsPage(
-                                    Message::ChangePasswordSubmit {
-                                        current_password: self.current_password_input.clone(),
-                                        new_password: self.new_password_input.clone(),
-                                    },
-                                ))
-                            }),
-                    ),
-            )
-            .push(
-                icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
-                    app::Message::Routes(super::Message::Navigate(RouteName::Settings(
-                        SubrouteName::Main,
-                    ))),
-                ),
-            )
-    }
-}
-
-pub struct About {}
-
-impl About {
-    // TODO: Remove this clippy allow.
-    #[allow(clippy::unused_self)]
-    fn view<'a>(&self) -> Column<'a, app::Message> {
-        container("About")
-            .push(Text::new("Description").size(25))
-            .push(Text::new("Keystache is a Nostr single-sign-on key management and Fedimint Bitcoin wallet created by Tommy Volk and generously funded by OpenSats").size(15))
-            .push(Text::new("Source Code").size(25))
-            .push(Text::new("https://github.com/nodetec/keystache").size(15))
-            .push(Text::new("Version").size(25))
-            .push(Text::new(env!("CARGO_PKG_VERSION")).size(15))
-            .push(icon_button("Back", SvgIcon::ArrowBack, PaletteColor::Background).on_press(
-                app::Message::Routes(super::Message::Navigate(RouteName::Settings(SubrouteName::Main)))
-            ))
-    }
-}